@@ -9,7 +9,7 @@ use app_error::AppError;
 use casbin::function_map::OperatorFunction;
 use casbin::rhai::{Dynamic, ImmutableString};
 use casbin::{CoreApi, DefaultModel, Enforcer, MgmtApi};
-use database_entity::dto::{AFAccessLevel, AFRole};
+use database_entity::dto::{AFAccessLevel, AFRole, PolicyDecisionExplanation};
 
 use sqlx::PgPool;
 
@@ -91,6 +91,20 @@ impl AccessControl {
   {
     self.enforcer.enforce_policy(uid, obj, act).await
   }
+
+  /// Same as [Self::enforce], but returns the policies and the reasoning behind the decision.
+  /// Meant for debugging permission problems, not for guarding requests.
+  pub async fn explain<T>(
+    &self,
+    uid: &i64,
+    obj: ObjectType,
+    act: T,
+  ) -> Result<PolicyDecisionExplanation, AppError>
+  where
+    T: Acts,
+  {
+    self.enforcer.explain_policy(uid, obj, act).await
+  }
 }
 
 ///
@@ -1,11 +1,16 @@
-use super::access::{load_group_policies, POLICY_FIELD_INDEX_OBJECT, POLICY_FIELD_INDEX_SUBJECT};
+use super::access::{
+  cmp_role_or_level, load_group_policies, POLICY_FIELD_INDEX_ACTION, POLICY_FIELD_INDEX_OBJECT,
+  POLICY_FIELD_INDEX_SUBJECT,
+};
 use crate::act::Acts;
 use crate::entity::{ObjectType, SubjectType};
 use crate::metrics::MetricsCalState;
 use crate::request::PolicyRequest;
 use anyhow::anyhow;
 use app_error::AppError;
+use casbin::rhai::ImmutableString;
 use casbin::{CoreApi, Enforcer, MgmtApi};
+use database_entity::dto::{AFAccessLevel, AFRole, PolicyDecisionExplanation};
 use std::sync::atomic::Ordering;
 use tokio::sync::RwLock;
 use tracing::{event, instrument, trace};
@@ -105,6 +110,86 @@ impl AFEnforcer {
     Ok(result)
   }
 
+  /// Evaluates the request the same way as [Self::enforce_policy], and additionally reports
+  /// which policies were considered and why the request was denied.
+  ///
+  /// The explanation mirrors the matcher defined in [super::access::MODEL_CONF]: a policy
+  /// matches when its subject and object are equal to the request's, and its role or access
+  /// level either groups the requested action or compares greater or equal to it.
+  pub async fn explain_policy<T>(
+    &self,
+    uid: &i64,
+    obj: ObjectType,
+    act: T,
+  ) -> Result<PolicyDecisionExplanation, AppError>
+  where
+    T: Acts,
+  {
+    let policy_request = PolicyRequest::new(*uid, obj.clone(), act);
+    let request = policy_request.to_policy();
+    let requested_act = request[POLICY_FIELD_INDEX_ACTION].clone();
+
+    let enforcer = self.enforcer.read().await;
+    let allowed = enforcer
+      .enforce(request.clone())
+      .map_err(|e| AppError::Internal(anyhow!("enforce: {e:?}")))?;
+    let subject_policies =
+      policies_for_subject_with_given_object(SubjectType::User(*uid), obj.clone(), &enforcer).await;
+    let matching_policies = subject_policies
+      .iter()
+      .filter(|p| policy_grants_act(&enforcer, &p[POLICY_FIELD_INDEX_ACTION], &requested_act))
+      .cloned()
+      .collect::<Vec<_>>();
+    drop(enforcer);
+
+    let policy_acts = subject_policies
+      .iter()
+      .map(|p| p[POLICY_FIELD_INDEX_ACTION].as_str());
+    let role = policy_acts
+      .clone()
+      .filter(|act| act.starts_with("r:"))
+      .map(AFRole::from_enforce_act)
+      .max();
+    let access_level = policy_acts
+      .filter(|act| act.starts_with("l:"))
+      .map(AFAccessLevel::from_enforce_act)
+      .chain(role.as_ref().map(AFAccessLevel::from))
+      .max();
+
+    let denial_reason = if allowed {
+      None
+    } else if subject_policies.is_empty() {
+      Some(format!(
+        "user {} has no policy on {}",
+        uid,
+        obj.policy_object()
+      ))
+    } else {
+      let held = role
+        .as_ref()
+        .map(|role| format!("role {:?}", role))
+        .or_else(|| access_level.map(|level| format!("access level {:?}", level)))
+        .unwrap_or_else(|| "no known role or access level".to_string());
+      Some(format!(
+        "user {} holds {} on {}, which does not grant {}",
+        uid,
+        held,
+        obj.policy_object(),
+        requested_act
+      ))
+    };
+
+    Ok(PolicyDecisionExplanation {
+      allowed,
+      request,
+      subject_policies,
+      matching_policies,
+      role,
+      access_level,
+      denial_reason,
+    })
+  }
+
   #[inline]
   async fn remove_with_enforcer(
     &self,
@@ -149,6 +234,15 @@ async fn policies_for_subject_with_given_object(
     .collect::<Vec<_>>()
 }
 
+/// Returns true if the policy's role or access level `p_act` allows the requested `r_act`,
+/// following the `g(p.act, r.act) || cmpRoleOrLevel(r.act, p.act)` part of the matcher.
+fn policy_grants_act(enforcer: &Enforcer, p_act: &str, r_act: &str) -> bool {
+  enforcer.has_grouping_policy(vec![p_act.to_string(), r_act.to_string()])
+    || cmp_role_or_level(ImmutableString::from(r_act), ImmutableString::from(p_act))
+      .as_bool()
+      .unwrap_or(false)
+}
+
 #[cfg(test)]
 pub(crate) mod tests {
   use crate::{
@@ -254,4 +348,61 @@ pub(crate) mod tests {
       .expect("enforcing access_level=FullAccess failed");
     assert!(!result, "access_level=FullAccess should not be allowed")
   }
+
+  #[tokio::test]
+  async fn explain_policy_test() {
+    let enforcer = test_enforcer().await;
+    let uid = 1;
+    let workspace_id = "w1";
+    enforcer
+      .update_policy(
+        SubjectType::User(uid),
+        ObjectType::Workspace(workspace_id.to_string()),
+        AFRole::Member,
+      )
+      .await
+      .unwrap();
+
+    let explanation = enforcer
+      .explain_policy(
+        &uid,
+        ObjectType::Workspace(workspace_id.to_string()),
+        Action::Write,
+      )
+      .await
+      .unwrap();
+    assert!(explanation.allowed);
+    assert_eq!(explanation.matching_policies.len(), 1);
+    assert_eq!(explanation.role, Some(AFRole::Member));
+    assert_eq!(explanation.access_level, Some(AFAccessLevel::ReadAndWrite));
+    assert!(explanation.denial_reason.is_none());
+
+    let explanation = enforcer
+      .explain_policy(
+        &uid,
+        ObjectType::Workspace(workspace_id.to_string()),
+        Action::Delete,
+      )
+      .await
+      .unwrap();
+    assert!(!explanation.allowed);
+    assert_eq!(explanation.subject_policies.len(), 1);
+    assert!(explanation.matching_policies.is_empty());
+    assert!(explanation.denial_reason.unwrap().contains("Member"));
+
+    let explanation = enforcer
+      .explain_policy(
+        &2,
+        ObjectType::Workspace(workspace_id.to_string()),
+        Action::Read,
+      )
+      .await
+      .unwrap();
+    assert!(!explanation.allowed);
+    assert!(explanation.subject_policies.is_empty());
+    assert_eq!(
+      explanation.denial_reason.as_deref(),
+      Some("user 2 has no policy on workspace::w1")
+    );
+  }
 }
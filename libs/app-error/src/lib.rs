@@ -153,9 +153,30 @@ pub enum AppError {
   #[error("{0}")]
   TooManyImportTask(String),
 
+  #[error("{0}")]
+  TooManyExportTask(String),
+
+  #[error("{0}")]
+  TooManyPasswordAttempts(String),
+
   #[error("There is existing access request for workspace {workspace_id} and view {view_id}")]
   AccessRequestAlreadyExists { workspace_id: Uuid, view_id: Uuid },
 
+  #[error("The access request {request_id} has expired")]
+  AccessRequestExpired { request_id: Uuid },
+
+  #[error("Workspace {workspace_id} requires signing in with its SSO provider")]
+  SsoSignInRequired { workspace_id: Uuid },
+
+  #[error("Workspace {workspace_id} is end-to-end encrypted, the server can't read its content")]
+  EndToEndEncrypted { workspace_id: Uuid },
+
+  #[error("The file of {file_size} bytes exceeds the remaining capacity of {remaining} bytes")]
+  FileStorageLimitExceeded { file_size: u64, remaining: u64 },
+
+  #[error("The file of {file_size} bytes exceeds the single upload limit of {limit} bytes")]
+  SingleUploadLimitExceeded { file_size: u64, limit: u64 },
+
   #[error("There is existing published view for workspace {workspace_id} with publish_name {publish_name}")]
   PublishNameAlreadyExists {
     workspace_id: Uuid,
@@ -254,7 +275,14 @@ impl AppError {
       AppError::NotInviteeOfWorkspaceInvitation(_) => ErrorCode::NotInviteeOfWorkspaceInvitation,
       AppError::MissingView(_) => ErrorCode::MissingView,
       AppError::AccessRequestAlreadyExists { .. } => ErrorCode::AccessRequestAlreadyExists,
+      AppError::AccessRequestExpired { .. } => ErrorCode::AccessRequestExpired,
+      AppError::SsoSignInRequired { .. } => ErrorCode::SsoSignInRequired,
+      AppError::EndToEndEncrypted { .. } => ErrorCode::EndToEndEncrypted,
+      AppError::FileStorageLimitExceeded { .. } => ErrorCode::FileStorageLimitExceeded,
+      AppError::SingleUploadLimitExceeded { .. } => ErrorCode::SingleUploadLimitExceeded,
       AppError::TooManyImportTask(_) => ErrorCode::TooManyImportTask,
+      AppError::TooManyExportTask(_) => ErrorCode::TooManyExportTask,
+      AppError::TooManyPasswordAttempts(_) => ErrorCode::TooManyPasswordAttempts,
       AppError::PublishNameAlreadyExists { .. } => ErrorCode::PublishNameAlreadyExists,
       AppError::PublishNameInvalidCharacter { .. } => ErrorCode::PublishNameInvalidCharacter,
       AppError::PublishNameTooLong { .. } => ErrorCode::PublishNameTooLong,
@@ -432,6 +460,11 @@ pub enum ErrorCode {
   MailerError = 1059,
   LicenseError = 1060,
   AIMaxRequired = 1061,
+  AccessRequestExpired = 1062,
+  SsoSignInRequired = 1063,
+  EndToEndEncrypted = 1064,
+  TooManyExportTask = 1065,
+  TooManyPasswordAttempts = 1066,
 }
 
 impl ErrorCode {
@@ -1,15 +1,23 @@
 use actix_http::Payload;
 use actix_web::{web::Data, FromRequest, HttpRequest};
 
+use futures_util::future::LocalBoxFuture;
 use gotrue_entity::gotrue_jwt::GoTrueJWTClaims;
 use secrecy::{ExposeSecret, Secret};
 use serde::{Deserialize, Serialize};
 use sqlx::types::{uuid, Uuid};
+use sqlx::PgPool;
 use std::fmt::{Display, Formatter};
 use std::ops::Deref;
 use std::str::FromStr;
 use tracing::instrument;
 
+use crate::personal_access_token::{
+  authorization_from_personal_access_token, is_personal_access_token, PersonalAccessTokenRoute,
+  PersonalAccessTokenScope,
+};
+use crate::sso::check_workspace_sso;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserUuid(uuid::Uuid);
 
@@ -44,17 +52,11 @@ impl Display for UserToken {
 impl FromRequest for UserUuid {
   type Error = actix_web::Error;
 
-  type Future = std::future::Ready<Result<Self, Self::Error>>;
+  type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
 
   fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
     let auth = get_auth_from_request(req);
-    match auth {
-      Ok(auth) => match UserUuid::from_auth(auth) {
-        Ok(uuid) => std::future::ready(Ok(uuid)),
-        Err(e) => std::future::ready(Err(e)),
-      },
-      Err(e) => std::future::ready(Err(e)),
-    }
+    Box::pin(async move { UserUuid::from_auth(auth.await?) })
   }
 }
 
@@ -65,17 +67,17 @@ pub struct OptionalUserUuid(Option<UserUuid>);
 impl FromRequest for OptionalUserUuid {
   type Error = actix_web::Error;
 
-  type Future = std::future::Ready<Result<Self, Self::Error>>;
+  type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
 
   fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
     let auth = get_auth_from_request(req);
-    match auth {
-      Ok(auth) => match UserUuid::from_auth(auth) {
-        Ok(uuid) => std::future::ready(Ok(OptionalUserUuid(Some(uuid)))),
-        Err(_) => std::future::ready(Ok(OptionalUserUuid(None))),
-      },
-      Err(_) => std::future::ready(Ok(OptionalUserUuid(None))),
-    }
+    Box::pin(async move {
+      let uuid = auth
+        .await
+        .ok()
+        .and_then(|auth| UserUuid::from_auth(auth).ok());
+      Ok(OptionalUserUuid(uuid))
+    })
   }
 }
 
@@ -89,6 +91,10 @@ impl OptionalUserUuid {
 pub struct Authorization {
   pub token: String,
   pub claims: GoTrueJWTClaims,
+  /// Set when the request is authenticated with a personal access token instead of a GoTrue
+  /// token. The claims of a personal access token are filled in from its user.
+  #[serde(default)]
+  pub personal_access_token: Option<PersonalAccessTokenScope>,
 }
 
 impl Authorization {
@@ -108,29 +114,79 @@ impl Authorization {
         ))
       })
   }
+
+  /// Returns true if the token was issued to the GoTrue admin user.
+  pub fn is_admin(&self) -> bool {
+    self.claims.role == "supabase_admin"
+  }
+
+  /// Returns the scope of the token if it is a personal access token.
+  pub fn personal_access_token(&self) -> Option<&PersonalAccessTokenScope> {
+    self.personal_access_token.as_ref()
+  }
 }
 
 impl FromRequest for Authorization {
   type Error = actix_web::Error;
 
-  type Future = std::future::Ready<Result<Self, Self::Error>>;
+  type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
 
   fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
-    let auth = get_auth_from_request(req);
-    match auth {
-      Ok(auth) => std::future::ready(Ok(auth)),
-      Err(e) => std::future::ready(Err(e)),
-    }
+    get_auth_from_request(req)
   }
 }
 
-fn get_auth_from_request(req: &HttpRequest) -> Result<Authorization, actix_web::Error> {
-  let jwt_secret_data =
-    req
-      .app_data::<Data<Secret<String>>>()
-      .ok_or(actix_web::error::ErrorInternalServerError(
-        "jwt secret not found",
+/// Personal access tokens are looked up in the database, so that revoked tokens are rejected
+/// before they expire. The token of a request targeting a workspace is checked against the SSO
+/// enforcement of that workspace. Services that don't register a [PgPool] don't accept personal
+/// access tokens.
+fn get_auth_from_request(
+  req: &HttpRequest,
+) -> LocalBoxFuture<'static, Result<Authorization, actix_web::Error>> {
+  let token = match bearer_token_from_request(req) {
+    Ok(token) => token,
+    Err(err) => return Box::pin(std::future::ready(Err(err))),
+  };
+  let pg_pool = req.app_data::<Data<PgPool>>().cloned();
+  let workspace_id = req
+    .match_info()
+    .get("workspace_id")
+    .and_then(|workspace_id| Uuid::parse_str(workspace_id).ok());
+
+  if is_personal_access_token(&token) {
+    let route = PersonalAccessTokenRoute::from_request(req);
+    return Box::pin(async move {
+      let pg_pool = pg_pool.ok_or(actix_web::error::ErrorUnauthorized(
+        "Personal access tokens are not supported by this service",
       ))?;
+      let auth = authorization_from_personal_access_token(&pg_pool, &token).await?;
+      if let Some(scope) = auth.personal_access_token() {
+        route.check_scope(scope)?;
+      }
+      if let Some(workspace_id) = workspace_id {
+        check_workspace_sso(&pg_pool, &auth, &workspace_id).await?;
+      }
+      Ok(auth)
+    });
+  }
+
+  let auth = match req.app_data::<Data<Secret<String>>>() {
+    Some(jwt_secret) => decode_authorization(&token, jwt_secret),
+    None => Err(actix_web::error::ErrorInternalServerError(
+      "jwt secret not found",
+    )),
+  };
+  match (auth, pg_pool, workspace_id) {
+    (Err(err), _, _) => Box::pin(std::future::ready(Err(err))),
+    (Ok(auth), Some(pg_pool), Some(workspace_id)) => Box::pin(async move {
+      check_workspace_sso(&pg_pool, &auth, &workspace_id).await?;
+      Ok(auth)
+    }),
+    (Ok(auth), _, _) => Box::pin(std::future::ready(Ok(auth))),
+  }
+}
+
+fn bearer_token_from_request(req: &HttpRequest) -> Result<String, actix_web::Error> {
   let bearer = req
     .headers()
     .get("Authorization")
@@ -147,19 +203,34 @@ fn get_auth_from_request(req: &HttpRequest) -> Result<Authorization, actix_web::
     .ok_or(actix_web::error::ErrorUnauthorized(
       "Invalid Authorization header, missing Bearer",
     ))?;
-
-  authorization_from_token(token, jwt_secret_data)
+  Ok(token.to_string())
 }
 
+/// Used for realtime connections, which only accept GoTrue tokens: personal access tokens can't
+/// be checked for revocation once the connection is established. The caller is responsible for
+/// excluding the workspaces returned by [crate::sso::sso_restricted_workspaces].
 #[instrument(level = "trace", skip_all, err)]
 pub fn authorization_from_token(
   token: &str,
   jwt_secret: &Data<Secret<String>>,
+) -> Result<Authorization, actix_web::Error> {
+  if is_personal_access_token(token) {
+    return Err(actix_web::error::ErrorUnauthorized(
+      "Personal access tokens can't be used for realtime connections",
+    ));
+  }
+  decode_authorization(token, jwt_secret)
+}
+
+fn decode_authorization(
+  token: &str,
+  jwt_secret: &Data<Secret<String>>,
 ) -> Result<Authorization, actix_web::Error> {
   let claims = gotrue_jwt_claims_from_token(token, jwt_secret)?;
   Ok(Authorization {
     token: token.to_string(),
     claims,
+    personal_access_token: None,
   })
 }
 
@@ -1,4 +1,6 @@
 pub mod error;
 pub mod jwt;
 pub mod password;
+pub mod personal_access_token;
+pub mod sso;
 pub mod user;
@@ -75,7 +75,7 @@ async fn get_stored_credentials(
   Ok(row)
 }
 
-fn verify_password_hash(
+pub fn verify_password_hash(
   expected_password_hash: Secret<String>,
   password_candidate: Secret<String>,
 ) -> Result<(), AuthError> {
@@ -0,0 +1,221 @@
+use actix_web::http::Method;
+use actix_web::HttpRequest;
+use gotrue_entity::gotrue_jwt::GoTrueJWTClaims;
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::types::Uuid;
+use sqlx::PgPool;
+
+use crate::jwt::Authorization;
+
+/// Prefix of personal access tokens, which tells them apart from the GoTrue tokens.
+pub const PERSONAL_ACCESS_TOKEN_PREFIX: &str = "afpat_";
+const PERSONAL_ACCESS_TOKEN_SECRET_LENGTH: usize = 48;
+
+/// Routes that a workspace-restricted token is allowed to use although they don't target a
+/// workspace. Every other route must have a `workspace_id` path parameter matching the token.
+const WORKSPACE_INDEPENDENT_ROUTES: [&str; 1] = ["/api/user/profile"];
+
+/// Restricts what a personal access token can be used for.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PersonalAccessTokenScope {
+  pub token_id: Uuid,
+  /// Only requests that don't modify any data are allowed.
+  pub read_only: bool,
+  /// Only requests targeting this workspace are allowed.
+  pub workspace_id: Option<Uuid>,
+}
+
+/// Personal access tokens are opaque random strings, so they can't be confused with the JWTs
+/// issued by GoTrue. Only their hash is stored. Returns the token and its hash.
+pub fn generate_personal_access_token() -> (String, Vec<u8>) {
+  let secret: String = rand::thread_rng()
+    .sample_iter(&Alphanumeric)
+    .take(PERSONAL_ACCESS_TOKEN_SECRET_LENGTH)
+    .map(char::from)
+    .collect();
+  let token = format!("{}{}", PERSONAL_ACCESS_TOKEN_PREFIX, secret);
+  let token_hash = hash_personal_access_token(&token);
+  (token, token_hash)
+}
+
+pub fn is_personal_access_token(token: &str) -> bool {
+  token.starts_with(PERSONAL_ACCESS_TOKEN_PREFIX)
+}
+
+fn hash_personal_access_token(token: &str) -> Vec<u8> {
+  Sha256::digest(token.as_bytes()).to_vec()
+}
+
+/// Looks up the personal access token, which must be neither revoked nor expired.
+pub async fn authorization_from_personal_access_token(
+  pg_pool: &PgPool,
+  token: &str,
+) -> Result<Authorization, actix_web::Error> {
+  let token_hash = hash_personal_access_token(token);
+  let row = sqlx::query!(
+    r#"
+      SELECT
+        t.token_id,
+        t.read_only,
+        t.workspace_id,
+        EXTRACT(EPOCH FROM t.expires_at)::BIGINT AS "expires_at!",
+        u.uuid,
+        u.email
+      FROM af_personal_access_token t
+      JOIN af_user u ON u.uid = t.uid
+      WHERE t.token_hash = $1
+        AND t.revoked_at IS NULL
+        AND t.expires_at > NOW()
+    "#,
+    token_hash,
+  )
+  .fetch_optional(pg_pool)
+  .await
+  .map_err(actix_web::error::ErrorInternalServerError)?
+  .ok_or(actix_web::error::ErrorUnauthorized(
+    "The personal access token is invalid, has been revoked or has expired",
+  ))?;
+
+  let claims = GoTrueJWTClaims {
+    aud: Some("authenticated".to_string()),
+    exp: Some(row.expires_at),
+    jti: None,
+    iat: None,
+    iss: None,
+    nbf: None,
+    sub: Some(row.uuid.to_string()),
+    email: row.email,
+    phone: String::new(),
+    app_metadata: serde_json::json!({}),
+    user_metadata: serde_json::json!({}),
+    role: "authenticated".to_string(),
+    aal: None,
+    amr: None,
+    session_id: None,
+  };
+  Ok(Authorization {
+    token: token.to_string(),
+    claims,
+    personal_access_token: Some(PersonalAccessTokenScope {
+      token_id: row.token_id,
+      read_only: row.read_only,
+      workspace_id: row.workspace_id,
+    }),
+  })
+}
+
+/// The parts of a request that the scope of a personal access token is checked against. They are
+/// taken from the request before the token is looked up in the database.
+pub(crate) struct PersonalAccessTokenRoute {
+  method: Method,
+  workspace_id: Option<String>,
+  pattern: Option<String>,
+}
+
+impl PersonalAccessTokenRoute {
+  pub(crate) fn from_request(req: &HttpRequest) -> Self {
+    Self {
+      method: req.method().clone(),
+      workspace_id: req.match_info().get("workspace_id").map(str::to_string),
+      pattern: req.match_pattern(),
+    }
+  }
+
+  /// A read-only token can only be used for `GET` and `HEAD` requests. A workspace-restricted
+  /// token can only be used for the routes targeting its workspace, and for the few routes listed
+  /// in [WORKSPACE_INDEPENDENT_ROUTES].
+  pub(crate) fn check_scope(
+    &self,
+    scope: &PersonalAccessTokenScope,
+  ) -> Result<(), actix_web::Error> {
+    if scope.read_only && !matches!(self.method, Method::GET | Method::HEAD) {
+      return Err(actix_web::error::ErrorForbidden(
+        "The personal access token is read-only",
+      ));
+    }
+
+    if let Some(allowed_workspace_id) = scope.workspace_id {
+      let is_allowed = match &self.workspace_id {
+        Some(workspace_id) => Uuid::parse_str(workspace_id)
+          .map(|workspace_id| workspace_id == allowed_workspace_id)
+          .unwrap_or(false),
+        None => self
+          .pattern
+          .as_deref()
+          .map(|pattern| WORKSPACE_INDEPENDENT_ROUTES.contains(&pattern))
+          .unwrap_or(false),
+      };
+      if !is_allowed {
+        return Err(actix_web::error::ErrorForbidden(
+          "The personal access token is not allowed to access this resource",
+        ));
+      }
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn route(method: Method, workspace_id: Option<&str>, pattern: &str) -> PersonalAccessTokenRoute {
+    PersonalAccessTokenRoute {
+      method,
+      workspace_id: workspace_id.map(str::to_string),
+      pattern: Some(pattern.to_string()),
+    }
+  }
+
+  #[test]
+  fn generate_personal_access_token_test() {
+    let (token, token_hash) = generate_personal_access_token();
+    assert!(is_personal_access_token(&token));
+    assert_eq!(token_hash, hash_personal_access_token(&token));
+    assert_ne!(token, generate_personal_access_token().0);
+  }
+
+  #[test]
+  fn workspace_restricted_scope_test() {
+    let workspace_id = Uuid::new_v4();
+    let scope = PersonalAccessTokenScope {
+      token_id: Uuid::new_v4(),
+      read_only: false,
+      workspace_id: Some(workspace_id),
+    };
+    let pattern = "/api/workspace/{workspace_id}/settings";
+    assert!(route(Method::GET, Some(&workspace_id.to_string()), pattern)
+      .check_scope(&scope)
+      .is_ok());
+    assert!(
+      route(Method::GET, Some(&Uuid::new_v4().to_string()), pattern)
+        .check_scope(&scope)
+        .is_err()
+    );
+    assert!(route(Method::GET, None, "/api/user/profile")
+      .check_scope(&scope)
+      .is_ok());
+    // Routes that don't target a workspace are rejected unless they are allowed explicitly
+    assert!(route(Method::GET, None, "/api/workspace")
+      .check_scope(&scope)
+      .is_err());
+  }
+
+  #[test]
+  fn read_only_scope_test() {
+    let scope = PersonalAccessTokenScope {
+      token_id: Uuid::new_v4(),
+      read_only: true,
+      workspace_id: None,
+    };
+    assert!(route(Method::GET, None, "/api/workspace")
+      .check_scope(&scope)
+      .is_ok());
+    assert!(route(Method::POST, None, "/api/workspace")
+      .check_scope(&scope)
+      .is_err());
+  }
+}
@@ -0,0 +1,94 @@
+use std::sync::{Arc, LazyLock};
+use std::time::{Duration, Instant};
+
+use app_error::AppError;
+use dashmap::DashMap;
+use database::workspace::select_sso_enforced_workspaces_for_member;
+use gotrue_entity::gotrue_jwt::GoTrueJWTClaims;
+use sqlx::types::Uuid;
+use sqlx::PgPool;
+
+use crate::jwt::Authorization;
+
+/// The authentication method GoTrue records in the `amr` claim for SAML sign-ins.
+pub const SSO_AMR_METHOD: &str = "sso/saml";
+/// How long the SSO requirements of a user are cached, so that they aren't queried on every
+/// request. A change to the SSO settings or to the members of a workspace takes up to this long
+/// to be enforced.
+const SSO_REQUIREMENTS_CACHE_TTL: Duration = Duration::from_secs(60);
+/// The expired entries are removed once the cache holds this many users.
+const SSO_REQUIREMENTS_CACHE_CLEANUP_SIZE: usize = 10_000;
+
+type SsoRequirements = Arc<Vec<(Uuid, String)>>;
+
+/// The workspaces requiring SSO of each user, see [select_sso_enforced_workspaces_for_member].
+static SSO_REQUIREMENTS_CACHE: LazyLock<DashMap<Uuid, (Instant, SsoRequirements)>> =
+  LazyLock::new(DashMap::new);
+
+/// Returns the id of the SSO provider that was used to obtain the token, if the user signed in
+/// with SSO.
+pub fn sso_provider_id_from_claims(claims: &GoTrueJWTClaims) -> Option<String> {
+  claims
+    .amr
+    .as_ref()?
+    .iter()
+    .find(|amr| amr.method == SSO_AMR_METHOD)
+    .and_then(|amr| amr.provider.clone())
+}
+
+async fn sso_requirements(pg_pool: &PgPool, user_uuid: &Uuid) -> Result<SsoRequirements, AppError> {
+  if let Some(entry) = SSO_REQUIREMENTS_CACHE.get(user_uuid) {
+    let (cached_at, requirements) = entry.value();
+    if cached_at.elapsed() < SSO_REQUIREMENTS_CACHE_TTL {
+      return Ok(requirements.clone());
+    }
+  }
+
+  let requirements = Arc::new(select_sso_enforced_workspaces_for_member(pg_pool, user_uuid).await?);
+  if SSO_REQUIREMENTS_CACHE.len() >= SSO_REQUIREMENTS_CACHE_CLEANUP_SIZE {
+    SSO_REQUIREMENTS_CACHE
+      .retain(|_, (cached_at, _)| cached_at.elapsed() < SSO_REQUIREMENTS_CACHE_TTL);
+  }
+  SSO_REQUIREMENTS_CACHE.insert(*user_uuid, (Instant::now(), requirements.clone()));
+  Ok(requirements)
+}
+
+/// Returns the workspaces the token can't be used for, as they require their members to sign in
+/// with an SSO provider other than the one the token was obtained with. Workspace owners are
+/// exempt, so that a misconfigured provider can't lock the owner out of the workspace settings.
+/// Personal access tokens are not obtained with SSO, so they can't be used for these workspaces.
+pub async fn sso_restricted_workspaces(
+  pg_pool: &PgPool,
+  auth: &Authorization,
+) -> Result<Vec<Uuid>, actix_web::Error> {
+  let user_uuid = auth.uuid()?;
+  let sso_provider_id = sso_provider_id_from_claims(&auth.claims);
+  let workspace_ids = sso_requirements(pg_pool, &user_uuid)
+    .await?
+    .iter()
+    .filter(|(_, provider_id)| sso_provider_id.as_deref() != Some(provider_id.as_str()))
+    .map(|(workspace_id, _)| *workspace_id)
+    .collect();
+  Ok(workspace_ids)
+}
+
+/// Rejects the token for a workspace it is restricted from, see [sso_restricted_workspaces]. The
+/// user keeps access to their other workspaces.
+pub async fn check_workspace_sso(
+  pg_pool: &PgPool,
+  auth: &Authorization,
+  workspace_id: &Uuid,
+) -> Result<(), actix_web::Error> {
+  if sso_restricted_workspaces(pg_pool, auth)
+    .await?
+    .contains(workspace_id)
+  {
+    return Err(
+      AppError::SsoSignInRequired {
+        workspace_id: *workspace_id,
+      }
+      .into(),
+    );
+  }
+  Ok(())
+}
@@ -13,9 +13,9 @@ use tokio::select;
 use tokio_util::sync::CancellationToken;
 use tracing::{error, instrument, trace, warn};
 use yrs::encoding::read::Cursor;
-use yrs::updates::decoder::DecoderV1;
+use yrs::updates::decoder::{Decode, DecoderV1};
 use yrs::updates::encoder::Encode;
-use yrs::ReadTxn;
+use yrs::{ReadTxn, StateVector, Update};
 
 use client_api_entity::{validate_data_for_folder, CollabType};
 use collab_rt_entity::{AckCode, ClientCollabMessage, ServerCollabMessage, ServerInit, UpdateSync};
@@ -24,7 +24,7 @@ use collab_rt_protocol::{
 };
 
 use crate::collab_sync::{
-  start_sync, CollabSink, MissUpdateReason, SyncError, SyncObject, SyncReason,
+  start_sync, CollabEncryptor, CollabSink, MissUpdateReason, SyncError, SyncObject, SyncReason,
 };
 
 pub type CollabRef = Weak<RwLock<dyn BorrowMut<Collab> + Send + Sync + 'static>>;
@@ -69,6 +69,9 @@ where
     let init_sync_cancel_token = ArcSwap::new(Arc::new(CancellationToken::new()));
     let arc_object = Arc::new(object);
 
+    // The server of an end-to-end encrypted workspace responds to a sync step 1 with all the
+    // updates of the collab, so it's only sent by the init sync.
+    let periodic_sync_interval = periodic_sync_interval.filter(|_| object.encryptor.is_none());
     if let Some(interval) = periodic_sync_interval {
       tracing::trace!("setting periodic sync step 1 for {}", object_id);
       tokio::spawn(ObserveCollab::<Sink, Stream>::periodic_sync_step_1(
@@ -326,8 +329,10 @@ where
     let result = tokio::spawn(async move {
       let mut decoder = DecoderV1::new(Cursor::new(&payload));
       let reader = MessageReader::new(&mut decoder);
+      // The state of the server, known from the encrypted updates it sent in this payload.
+      let mut remote_sv = StateVector::default();
       for yrs_message in reader {
-        let msg = yrs_message?;
+        let mut msg = yrs_message?;
 
         // When the client receives a SyncStep1 message, it indicates that the server is requesting
         // the client to send updates that the server is missing. This typically occurs when the client
@@ -343,6 +348,53 @@ where
             .map_err(|err| SyncError::OverrideWithIncorrectData(err.to_string()))?;
         }
 
+        if let Some(encryptor) = &sync_object.encryptor {
+          msg = match msg {
+            Message::Sync(SyncMessage::SyncStep1(_)) => {
+              // The server can't tell which updates it's missing, so they are computed from the
+              // updates it sent before this message.
+              let update = {
+                let lock = collab.read().await;
+                let txn = (*lock).borrow().transact();
+                let update = if txn.state_vector() <= remote_sv {
+                  None
+                } else {
+                  Some(txn.encode_state_as_update_v1(&remote_sv))
+                };
+                update
+              };
+              if let Some(update) = update {
+                let payload =
+                  Message::Sync(SyncMessage::SyncStep2(encryptor.encrypt_update(update)?))
+                    .encode_v1();
+                let object_id = sync_object.object_id.clone();
+                sink.queue_msg(|msg_id| {
+                  ClientCollabMessage::new_server_init_sync(ServerInit::new(
+                    message_origin.clone(),
+                    object_id,
+                    payload,
+                    msg_id,
+                  ))
+                });
+              }
+              continue;
+            },
+            Message::Sync(SyncMessage::SyncStep2(update)) => {
+              match decrypt_remote_update(encryptor, update, &mut remote_sv) {
+                Some(update) => Message::Sync(SyncMessage::SyncStep2(update)),
+                None => continue,
+              }
+            },
+            Message::Sync(SyncMessage::Update(update)) => {
+              match decrypt_remote_update(encryptor, update, &mut remote_sv) {
+                Some(update) => Message::Sync(SyncMessage::Update(update)),
+                None => continue,
+              }
+            },
+            msg => msg,
+          };
+        }
+
         if let Some(return_payload) = ClientSyncProtocol
           .handle_message(&message_origin, &collab, msg)
           .await?
@@ -380,6 +432,33 @@ where
   }
 }
 
+/// Decrypts an update of an end-to-end encrypted collab, and merges its state vector into
+/// `remote_sv`. The updates that can't be decrypted, e.g. the ones encrypted with another key, are
+/// skipped.
+fn decrypt_remote_update(
+  encryptor: &CollabEncryptor,
+  update: Vec<u8>,
+  remote_sv: &mut StateVector,
+) -> Option<Vec<u8>> {
+  let update = match encryptor.decrypt_update(update) {
+    Ok(update) => update,
+    Err(err) => {
+      error!("Failed to decrypt update: {}", err);
+      return None;
+    },
+  };
+  match Update::decode_v1(&update) {
+    Ok(decoded) => {
+      remote_sv.merge(decoded.state_vector());
+      Some(update)
+    },
+    Err(err) => {
+      error!("Failed to decode decrypted update: {}", err);
+      None
+    },
+  }
+}
+
 #[derive(Default)]
 pub struct SeqNumCounter {
   /// The sequence number of the last update broadcast by the server.
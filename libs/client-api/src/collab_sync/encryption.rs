@@ -0,0 +1,48 @@
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+
+use bytes::Bytes;
+use encrypt::{DataEncryptor, EncryptionData};
+
+use crate::collab_sync::SyncError;
+
+/// Encrypts the collab updates of an end-to-end encrypted workspace before they are sent to the
+/// server, and decrypts the ones received from it. The server only relays and stores the encrypted
+/// updates, so the key has to be shared with the other members of the workspace by the application.
+#[derive(Clone)]
+pub struct CollabEncryptor(Arc<dyn DataEncryptor + Send + Sync>);
+
+impl CollabEncryptor {
+  pub fn new<E>(encryptor: E) -> Self
+  where
+    E: DataEncryptor + Send + Sync + 'static,
+  {
+    Self(Arc::new(encryptor))
+  }
+
+  pub fn encrypt_update(&self, update: Vec<u8>) -> Result<Vec<u8>, SyncError> {
+    let data = EncryptionData::from_data(&update, self)?;
+    Ok(Vec::try_from(data)?)
+  }
+
+  pub fn decrypt_update(&self, data: Vec<u8>) -> Result<Vec<u8>, SyncError> {
+    let data = EncryptionData::try_from(Bytes::from(data))?;
+    Ok(data.decrypt(self)?)
+  }
+}
+
+impl DataEncryptor for CollabEncryptor {
+  fn encrypt(&self, data: Bytes) -> Result<Bytes, anyhow::Error> {
+    self.0.encrypt(data)
+  }
+
+  fn decrypt(&self, data: Bytes) -> Result<Bytes, anyhow::Error> {
+    self.0.decrypt(data)
+  }
+}
+
+impl Debug for CollabEncryptor {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.write_str("CollabEncryptor")
+  }
+}
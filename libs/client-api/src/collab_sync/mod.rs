@@ -1,11 +1,13 @@
 mod collab_sink;
 mod collab_stream;
+mod encryption;
 mod error;
 mod plugin;
 mod sync_control;
 
 pub use collab_rt_entity::{MsgId, ServerCollabMessage};
 pub use collab_sink::*;
+pub use encryption::*;
 pub use error::*;
 pub use plugin::*;
 pub use sync_control::*;
@@ -22,7 +22,7 @@ use collab_rt_entity::{ClientCollabMessage, ServerCollabMessage, UpdateSync};
 use collab_rt_protocol::{Message, SyncMessage};
 
 use crate::collab_sync::collab_stream::CollabRef;
-use crate::collab_sync::{CollabSyncState, SinkConfig, SyncControl, SyncReason};
+use crate::collab_sync::{CollabEncryptor, CollabSyncState, SinkConfig, SyncControl, SyncReason};
 use crate::ws::{ConnectState, WSConnectStateReceiver};
 
 pub struct SyncPlugin<Sink, Stream, Channel> {
@@ -165,7 +165,19 @@ where
   }
 
   fn receive_local_update(&self, origin: &CollabOrigin, _object_id: &str, update: &[u8]) {
-    let update = update.to_vec();
+    let update = match &self.object.encryptor {
+      None => update.to_vec(),
+      Some(encryptor) => match encryptor.encrypt_update(update.to_vec()) {
+        Ok(update) => update,
+        Err(err) => {
+          error!(
+            "Failed to encrypt update of {}: {}",
+            self.object.object_id, err
+          );
+          return;
+        },
+      },
+    };
     let payload = Message::Sync(SyncMessage::Update(update)).encode_v1();
     self.sync_queue.queue_msg(|msg_id| {
       let update_sync = UpdateSync::new(
@@ -223,6 +235,8 @@ pub struct SyncObject {
   pub workspace_id: String,
   pub collab_type: CollabType,
   pub device_id: String,
+  /// Set when the collab belongs to an end-to-end encrypted workspace.
+  pub encryptor: Option<CollabEncryptor>,
 }
 
 impl SyncObject {
@@ -237,8 +251,17 @@ impl SyncObject {
       workspace_id: workspace_id.to_string(),
       collab_type,
       device_id: device_id.to_string(),
+      encryptor: None,
     }
   }
+
+  /// Encrypts the updates of the collab with the given encryptor. The updates are then stored by
+  /// the server as they are sent, and the collab can only be synced with the other clients that
+  /// use the same key.
+  pub fn with_encryptor(mut self, encryptor: CollabEncryptor) -> Self {
+    self.encryptor = Some(encryptor);
+    self
+  }
 }
 
 impl From<CollabObject> for SyncObject {
@@ -248,6 +271,7 @@ impl From<CollabObject> for SyncObject {
       workspace_id: collab_object.workspace_id,
       collab_type: collab_object.collab_type,
       device_id: collab_object.device_id,
+      encryptor: None,
     }
   }
 }
@@ -18,8 +18,8 @@ use collab_rt_protocol::{ClientSyncProtocol, CollabSyncProtocol, Message, SyncMe
 
 use crate::collab_sync::collab_stream::{CollabRef, ObserveCollab};
 use crate::collab_sync::{
-  CollabSink, CollabSinkRunner, CollabSyncState, MissUpdateReason, SinkSignal, SyncError,
-  SyncObject,
+  CollabEncryptor, CollabSink, CollabSinkRunner, CollabSyncState, MissUpdateReason, SinkSignal,
+  SyncError, SyncObject,
 };
 
 pub const DEFAULT_SYNC_TIMEOUT: u64 = 10;
@@ -166,11 +166,19 @@ fn gen_sync_state<P: CollabSyncProtocol>(
   Ok(encoder.to_vec())
 }
 
-fn gen_missing_updates(collab: &Collab, sv: StateVector) -> Result<Vec<u8>, SyncError> {
+fn gen_missing_updates(
+  collab: &Collab,
+  sv: StateVector,
+  encryptor: Option<&CollabEncryptor>,
+) -> Result<Vec<u8>, SyncError> {
   let update = {
     let txn = collab.transact();
     txn.encode_state_as_update_v1(&sv)
   };
+  let update = match encryptor {
+    None => update,
+    Some(encryptor) => encryptor.encrypt_update(update)?,
+  };
 
   let mut encoder = EncoderV1::new();
   Message::Sync(SyncMessage::Update(update)).encode(&mut encoder);
@@ -224,7 +232,7 @@ where
     } => match StateVector::decode_v1(&state_vector_v1) {
       Ok(sv) => {
         trace!("🔥{} start sync, reason:{}", &sync_object.object_id, reason);
-        let update = gen_missing_updates(collab, sv)?;
+        let update = gen_missing_updates(collab, sv, sync_object.encryptor.as_ref())?;
         sink.queue_msg(|msg_id| {
           let update_sync = UpdateSync::new(
             origin.clone(),
@@ -59,9 +59,17 @@ pub struct ClientConfiguration {
   /// A larger buffer size means more data is compressed in a single operation, which can lead to better compression ratios
   /// since Brotli has more data to analyze for patterns and repetitions.
   pub(crate) compression_buffer_size: usize,
+  /// When set, requests are authenticated with this personal access token instead of a GoTrue
+  /// session, and the token is never refreshed.
+  pub(crate) personal_access_token: Option<String>,
 }
 
 impl ClientConfiguration {
+  pub fn with_personal_access_token(mut self, personal_access_token: String) -> Self {
+    self.personal_access_token = Some(personal_access_token);
+    self
+  }
+
   pub fn with_compression_buffer_size(mut self, compression_buffer_size: usize) -> Self {
     self.compression_buffer_size = compression_buffer_size;
     self
@@ -83,6 +91,7 @@ impl Default for ClientConfiguration {
     Self {
       compression_quality: 8,
       compression_buffer_size: 10240,
+      personal_access_token: None,
     }
   }
 }
@@ -562,6 +571,9 @@ impl Client {
   /// - `Err(AppResponseError)`: An `AppResponseError` indicating either an inability to read the token or that the user is not logged in.
   ///
   pub fn access_token(&self) -> Result<String, AppResponseError> {
+    if let Some(personal_access_token) = &self.config.personal_access_token {
+      return Ok(personal_access_token.clone());
+    }
     match &self.token.try_read_for(Duration::from_secs(2)) {
       None => Err(AppError::Unhandled("Failed to read token".to_string()).into()),
       Some(token) => {
@@ -1050,6 +1062,9 @@ impl Client {
 
   // Refresh token if given timestamp is close to the token expiration time
   pub async fn refresh_if_expired(&self, ts: i64, reason: &str) -> Result<(), AppResponseError> {
+    if self.config.personal_access_token.is_some() {
+      return Ok(());
+    }
     let expires_at = self.token_expires_at()?;
 
     if ts + 30 > expires_at {
@@ -0,0 +1,26 @@
+use client_api_entity::{ExplainPolicyDecisionQueryParams, PolicyDecisionExplanation};
+use reqwest::Method;
+use shared_entity::response::{AppResponse, AppResponseError};
+
+use crate::Client;
+
+// Access Control API
+impl Client {
+  /// Explains why the access control grants or denies the given request.
+  /// Only available to the admin user.
+  pub async fn explain_policy_decision(
+    &self,
+    params: &ExplainPolicyDecisionQueryParams,
+  ) -> Result<PolicyDecisionExplanation, AppResponseError> {
+    let url = format!("{}/api/access-control/explain", self.base_url);
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .query(params)
+      .send()
+      .await?;
+    AppResponse::<PolicyDecisionExplanation>::from_response(resp)
+      .await?
+      .into_data()
+  }
+}
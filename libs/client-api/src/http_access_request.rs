@@ -1,6 +1,6 @@
 use client_api_entity::{
-  access_request_dto::AccessRequest, AccessRequestMinimal, ApproveAccessRequestParams,
-  CreateAccessRequestParams,
+  access_request_dto::AccessRequest, AccessRequestAutoApprovalRule, AccessRequestMinimal,
+  ApproveAccessRequestParams, CreateAccessRequestParams,
 };
 use reqwest::Method;
 use shared_entity::response::{AppResponse, AppResponseError};
@@ -73,4 +73,57 @@ impl Client {
       .await?;
     AppResponse::<()>::from_response(resp).await?.into_error()
   }
+
+  pub async fn list_access_request_auto_approval_rules(
+    &self,
+    workspace_id: Uuid,
+  ) -> Result<Vec<AccessRequestAutoApprovalRule>, AppResponseError> {
+    let url = format!(
+      "{}/api/access-request/workspace/{}/auto-approval-rule",
+      self.base_url, workspace_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .send()
+      .await?;
+    AppResponse::<Vec<AccessRequestAutoApprovalRule>>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  pub async fn upsert_access_request_auto_approval_rule(
+    &self,
+    workspace_id: Uuid,
+    rule: &AccessRequestAutoApprovalRule,
+  ) -> Result<(), AppResponseError> {
+    let url = format!(
+      "{}/api/access-request/workspace/{}/auto-approval-rule",
+      self.base_url, workspace_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::PUT, &url)
+      .await?
+      .json(rule)
+      .send()
+      .await?;
+    AppResponse::<()>::from_response(resp).await?.into_error()
+  }
+
+  pub async fn delete_access_request_auto_approval_rule(
+    &self,
+    workspace_id: Uuid,
+    email_domain: &str,
+  ) -> Result<(), AppResponseError> {
+    let url = format!(
+      "{}/api/access-request/workspace/{}/auto-approval-rule/{}",
+      self.base_url, workspace_id, email_domain
+    );
+    let resp = self
+      .http_client_with_auth(Method::DELETE, &url)
+      .await?
+      .send()
+      .await?;
+    AppResponse::<()>::from_response(resp).await?.into_error()
+  }
 }
@@ -0,0 +1,50 @@
+use client_api_entity::{AuditLogs, ListAuditLogsQueryParams};
+use reqwest::Method;
+use shared_entity::response::{AppResponse, AppResponseError};
+use uuid::Uuid;
+
+use crate::Client;
+
+fn audit_log_url(base_url: &str, workspace_id: Uuid) -> String {
+  format!("{base_url}/api/workspace/{workspace_id}/audit-log")
+}
+
+// Audit Log API
+impl Client {
+  pub async fn list_audit_logs(
+    &self,
+    workspace_id: Uuid,
+    params: &ListAuditLogsQueryParams,
+  ) -> Result<AuditLogs, AppResponseError> {
+    let url = audit_log_url(&self.base_url, workspace_id);
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .query(params)
+      .send()
+      .await?;
+    AppResponse::<AuditLogs>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  /// Returns the audit log entries matching `params` as CSV, including a header row.
+  pub async fn export_audit_logs_csv(
+    &self,
+    workspace_id: Uuid,
+    params: &ListAuditLogsQueryParams,
+  ) -> Result<String, AppResponseError> {
+    let url = format!("{}/csv", audit_log_url(&self.base_url, workspace_id));
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .query(params)
+      .send()
+      .await?;
+    if resp.status().is_success() {
+      Ok(resp.text().await?)
+    } else {
+      AppResponse::from_response(resp).await?.into_data()
+    }
+  }
+}
@@ -7,7 +7,10 @@ use futures_util::TryStreamExt;
 use mime::Mime;
 use percent_encoding::{percent_decode_str, utf8_percent_encode, NON_ALPHANUMERIC};
 use reqwest::{header, Method, StatusCode};
-use shared_entity::dto::workspace_dto::{BlobMetadata, RepeatedBlobMetaData};
+use shared_entity::dto::workspace_dto::{
+  BlobMetadata, OrphanedBlobsReport, RepeatedBlobMetaData, UpdateWorkspaceStorageQuotaParams,
+  WorkspaceStorageQuota,
+};
 use shared_entity::response::{AppResponse, AppResponseError};
 
 use shared_entity::dto::file_dto::PutFileResponse;
@@ -144,6 +147,19 @@ impl Client {
     self.get_blob(&url).await
   }
 
+  /// Returns the resized variant of the image for the given width, or the original file if it
+  /// isn't a resizable image or isn't wider than the requested width.
+  pub async fn get_blob_v1_resized(
+    &self,
+    workspace_id: &str,
+    parent_dir: &str,
+    file_id: &str,
+    width: u32,
+  ) -> Result<(Mime, Vec<u8>), AppResponseError> {
+    let url = self.get_blob_url_v1(workspace_id, parent_dir, file_id);
+    self.get_blob(&format!("{}?w={}", url, width)).await
+  }
+
   #[instrument(level = "info", skip_all)]
   pub async fn delete_blob_v1(
     &self,
@@ -275,4 +291,60 @@ impl Client {
       .await?
       .into_data()
   }
+
+  /// Lists the blobs that the blob garbage collector will delete. Only the workspace owner can
+  /// get the report.
+  pub async fn get_orphaned_blobs(
+    &self,
+    workspace_id: &str,
+  ) -> Result<OrphanedBlobsReport, AppResponseError> {
+    let url = format!(
+      "{}/api/file_storage/{}/orphaned_blobs",
+      self.base_url, workspace_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<OrphanedBlobsReport>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  /// Returns the storage quotas in effect for the workspace and its consumed capacity.
+  pub async fn get_workspace_storage_quota(
+    &self,
+    workspace_id: &str,
+  ) -> Result<WorkspaceStorageQuota, AppResponseError> {
+    let url = format!("{}/api/file_storage/{}/quota", self.base_url, workspace_id);
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<WorkspaceStorageQuota>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  /// Overrides the default storage quotas of the workspace. Only the admin of the server can
+  /// update them.
+  pub async fn update_workspace_storage_quota(
+    &self,
+    workspace_id: &str,
+    params: &UpdateWorkspaceStorageQuotaParams,
+  ) -> Result<(), AppResponseError> {
+    let url = format!("{}/api/file_storage/{}/quota", self.base_url, workspace_id);
+    let resp = self
+      .http_client_with_auth(Method::PUT, &url)
+      .await?
+      .json(params)
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<()>::from_response(resp).await?.into_error()
+  }
 }
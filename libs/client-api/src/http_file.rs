@@ -7,9 +7,13 @@ use async_trait::async_trait;
 use std::fs::metadata;
 
 use client_api_entity::{
-  CompleteUploadRequest, CreateUploadRequest, CreateUploadResponse, UploadPartResponse,
+  CompleteUploadRequest, CreateUploadRequest, CreateUploadResponse, ListUploadPartsResponse,
+  UploadPartResponse,
+};
+use client_api_entity::{
+  CreateDatabaseImportTask, CreateImportTask, CreateImportTaskResponse, CreateWorkspaceImportTask,
+  ImportSource, SpreadsheetFormat,
 };
-use client_api_entity::{CreateImportTask, CreateImportTaskResponse};
 
 use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
 use reqwest::{multipart, Body, Method};
@@ -18,6 +22,7 @@ use std::path::Path;
 
 use base64::engine::general_purpose::STANDARD;
 use base64::Engine;
+use shared_entity::dto::export_dto::{CreateExportTaskResponse, ExportTaskDetail, UserExportTask};
 use shared_entity::dto::import_dto::UserImportTask;
 use tokio::fs::File;
 use tokio::io::{AsyncBufReadExt, BufReader};
@@ -103,6 +108,53 @@ impl Client {
     AppResponse::<()>::from_response(resp).await?.into_error()
   }
 
+  /// Returns the parts of an upload that were received by the server, so that an interrupted
+  /// upload can be resumed by only uploading the missing parts.
+  pub async fn list_upload_parts(
+    &self,
+    workspace_id: &str,
+    parent_dir: &str,
+    file_id: &str,
+    upload_id: &str,
+  ) -> Result<ListUploadPartsResponse, AppResponseError> {
+    let parent_dir = utf8_percent_encode(parent_dir, NON_ALPHANUMERIC).to_string();
+    let url = format!(
+      "{}/api/file_storage/{workspace_id}/upload_parts/{parent_dir}/{file_id}/{upload_id}",
+      self.base_url
+    );
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<ListUploadPartsResponse>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  /// Aborts an upload, deleting the parts that were already uploaded.
+  pub async fn abort_upload(
+    &self,
+    workspace_id: &str,
+    parent_dir: &str,
+    file_id: &str,
+    upload_id: &str,
+  ) -> Result<(), AppResponseError> {
+    let parent_dir = utf8_percent_encode(parent_dir, NON_ALPHANUMERIC).to_string();
+    let url = format!(
+      "{}/api/file_storage/{workspace_id}/upload/{parent_dir}/{file_id}/{upload_id}",
+      self.base_url
+    );
+    let resp = self
+      .http_client_with_auth(Method::DELETE, &url)
+      .await?
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<()>::from_response(resp).await?.into_error()
+  }
+
   /// Sends a POST request to import a file to the server.
   ///
   /// This function streams the contents of a file located at the provided `file_path`
@@ -177,6 +229,18 @@ impl Client {
   pub async fn create_import(
     &self,
     file_path: &Path,
+  ) -> Result<CreateImportTaskResponse, AppResponseError> {
+    self
+      .create_import_with_source(file_path, ImportSource::Notion)
+      .await
+  }
+
+  /// Same as [Self::create_import], but for a zip file exported by the given [ImportSource],
+  /// e.g. a zip of Markdown files or HTML pages.
+  pub async fn create_import_with_source(
+    &self,
+    file_path: &Path,
+    source: ImportSource,
   ) -> Result<CreateImportTaskResponse, AppResponseError> {
     let url = format!("{}/api/import/create", self.base_url);
     let file_name = file_path
@@ -188,6 +252,89 @@ impl Client {
     let params = CreateImportTask {
       workspace_name: file_name.clone(),
       content_length,
+      source,
+    };
+    let resp = self
+      .http_client_with_auth(Method::POST, &url)
+      .await?
+      .header("X-Host", self.base_url.clone())
+      .json(&params)
+      .send()
+      .await?;
+
+    log_request_id(&resp);
+    AppResponse::<CreateImportTaskResponse>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  /// Creates a task that imports a zip file exported by the given [ImportSource] into an existing
+  /// workspace, under `parent_view_id`, instead of a new workspace. The imported pages are nested
+  /// under a new page named after the file.
+  ///
+  /// As with [Self::create_import], use [Self::upload_import_file] to upload the file to the
+  /// presigned URL of the returned [CreateImportTaskResponse].
+  pub async fn create_workspace_import(
+    &self,
+    workspace_id: &str,
+    parent_view_id: &str,
+    file_path: &Path,
+    source: ImportSource,
+  ) -> Result<CreateImportTaskResponse, AppResponseError> {
+    let url = format!("{}/api/import/{}/pages", self.base_url, workspace_id);
+    let name = file_path
+      .file_stem()
+      .map(|s| s.to_string_lossy().to_string())
+      .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+    let content_length = tokio::fs::metadata(file_path).await?.len();
+    let params = CreateWorkspaceImportTask {
+      parent_view_id: parent_view_id.to_string(),
+      name,
+      content_length,
+      source,
+    };
+    let resp = self
+      .http_client_with_auth(Method::POST, &url)
+      .await?
+      .header("X-Host", self.base_url.clone())
+      .json(&params)
+      .send()
+      .await?;
+
+    log_request_id(&resp);
+    AppResponse::<CreateImportTaskResponse>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  /// Creates a task that imports a CSV or Excel file into a new grid under `parent_view_id`.
+  /// The grid is named after the file and its format is derived from the file extension.
+  ///
+  /// As with [Self::create_import], use [Self::upload_import_file] to upload the file to the
+  /// presigned URL of the returned [CreateImportTaskResponse].
+  pub async fn create_database_import(
+    &self,
+    workspace_id: &str,
+    parent_view_id: &str,
+    file_path: &Path,
+  ) -> Result<CreateImportTaskResponse, AppResponseError> {
+    let url = format!("{}/api/import/{}/database", self.base_url, workspace_id);
+    let format = match file_path.extension().and_then(|ext| ext.to_str()) {
+      Some(ext) if ext.eq_ignore_ascii_case("xlsx") => SpreadsheetFormat::Xlsx,
+      _ => SpreadsheetFormat::Csv,
+    };
+    let name = file_path
+      .file_stem()
+      .map(|s| s.to_string_lossy().to_string())
+      .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+    let content_length = tokio::fs::metadata(file_path).await?.len();
+    let params = CreateDatabaseImportTask {
+      parent_view_id: parent_view_id.to_string(),
+      name,
+      content_length,
+      format,
     };
     let resp = self
       .http_client_with_auth(Method::POST, &url)
@@ -252,6 +399,62 @@ impl Client {
       .await?
       .into_data()
   }
+
+  /// Cancels a pending import task. The workspace it was importing into is deleted.
+  pub async fn cancel_import(&self, task_id: &str) -> Result<(), AppResponseError> {
+    let url = format!("{}/api/import/{}/cancel", self.base_url, task_id);
+    let resp = self
+      .http_client_with_auth(Method::POST, &url)
+      .await?
+      .send()
+      .await?;
+    AppResponse::<()>::from_response(resp).await?.into_error()
+  }
+
+  /// Creates a task that exports the workspace into a zip archive of Markdown and CSV files.
+  /// Use [Self::get_export_detail] to get the link to the archive once it's completed.
+  pub async fn create_export(
+    &self,
+    workspace_id: &str,
+  ) -> Result<CreateExportTaskResponse, AppResponseError> {
+    let url = format!("{}/api/export/{}", self.base_url, workspace_id);
+    let resp = self
+      .http_client_with_auth(Method::POST, &url)
+      .await?
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<CreateExportTaskResponse>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  pub async fn get_export_list(&self) -> Result<UserExportTask, AppResponseError> {
+    let url = format!("{}/api/export", self.base_url);
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .send()
+      .await?;
+    AppResponse::<UserExportTask>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  pub async fn get_export_detail(
+    &self,
+    task_id: &str,
+  ) -> Result<ExportTaskDetail, AppResponseError> {
+    let url = format!("{}/api/export/task/{}", self.base_url, task_id);
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .send()
+      .await?;
+    AppResponse::<ExportTaskDetail>::from_response(resp)
+      .await?
+      .into_data()
+  }
 }
 
 #[async_trait]
@@ -0,0 +1,59 @@
+use client_api_entity::{
+  CreatePersonalAccessTokenParams, CreatedPersonalAccessToken, PersonalAccessToken,
+};
+use reqwest::Method;
+use shared_entity::response::{AppResponse, AppResponseError};
+use uuid::Uuid;
+
+use crate::Client;
+
+// Personal Access Token API
+impl Client {
+  /// Creates a personal access token. The returned token can be passed to
+  /// [crate::ClientConfiguration::with_personal_access_token], and can't be retrieved again.
+  pub async fn create_personal_access_token(
+    &self,
+    params: &CreatePersonalAccessTokenParams,
+  ) -> Result<CreatedPersonalAccessToken, AppResponseError> {
+    let url = format!("{}/api/user/personal-access-token", self.base_url);
+    let resp = self
+      .http_client_with_auth(Method::POST, &url)
+      .await?
+      .json(params)
+      .send()
+      .await?;
+    AppResponse::<CreatedPersonalAccessToken>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  pub async fn list_personal_access_tokens(
+    &self,
+  ) -> Result<Vec<PersonalAccessToken>, AppResponseError> {
+    let url = format!("{}/api/user/personal-access-token", self.base_url);
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .send()
+      .await?;
+    AppResponse::<Vec<PersonalAccessToken>>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  pub async fn revoke_personal_access_token(
+    &self,
+    token_id: &Uuid,
+  ) -> Result<(), AppResponseError> {
+    let url = format!(
+      "{}/api/user/personal-access-token/{}",
+      self.base_url, token_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::DELETE, &url)
+      .await?
+      .send()
+      .await?;
+    AppResponse::<()>::from_response(resp).await?.into_error()
+  }
+}
@@ -1,18 +1,25 @@
 use bytes::Bytes;
 use client_api_entity::publish_dto::DuplicatePublishedPageResponse;
-use client_api_entity::workspace_dto::{PublishInfoView, PublishedView};
+use client_api_entity::workspace_dto::{
+  CreatePublishSchedule, PublishHistoryDetail, PublishHistoryInfo, PublishInfoView,
+  PublishSchedule, PublishedView,
+};
 use client_api_entity::{workspace_dto::PublishedDuplicate, PublishInfo, UpdatePublishNamespace};
 use client_api_entity::{
   CreateGlobalCommentParams, CreateReactionParams, DeleteGlobalCommentParams, DeleteReactionParams,
-  GetReactionQueryParams, GlobalComments, PatchPublishedCollab, PublishInfoMeta, Reactions,
-  UpdateDefaultPublishView,
+  GetReactionQueryParams, GlobalComments, PatchPublishedCollab, PublishAnalytics,
+  PublishAnalyticsQueryParams, PublishCustomDomain, PublishInfoMeta, Reactions,
+  SetPublishCustomDomain, UpdateDefaultPublishView,
 };
-use reqwest::Method;
+use reqwest::{Method, RequestBuilder};
 use shared_entity::response::{AppResponse, AppResponseError};
 use tracing::instrument;
 
 use crate::{log_request_id, Client};
 
+/// Header carrying the password of a password protected published view.
+pub const X_PUBLISH_PASSWORD: &str = "X-Publish-Password";
+
 // Publisher API
 impl Client {
   #[instrument(level = "debug", skip_all)]
@@ -82,6 +89,109 @@ impl Client {
       .into_data()
   }
 
+  pub async fn set_workspace_publish_custom_domain(
+    &self,
+    workspace_id: &str,
+    domain: &str,
+  ) -> Result<PublishCustomDomain, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/publish-custom-domain",
+      self.base_url, workspace_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::PUT, &url)
+      .await?
+      .json(&SetPublishCustomDomain {
+        domain: domain.to_string(),
+      })
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<PublishCustomDomain>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  pub async fn get_workspace_publish_custom_domain(
+    &self,
+    workspace_id: &str,
+  ) -> Result<PublishCustomDomain, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/publish-custom-domain",
+      self.base_url, workspace_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<PublishCustomDomain>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  /// Verifies the custom domain by its TXT record. The domain serves the published pages of the
+  /// workspace once it is verified.
+  pub async fn verify_workspace_publish_custom_domain(
+    &self,
+    workspace_id: &str,
+  ) -> Result<PublishCustomDomain, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/publish-custom-domain/verify",
+      self.base_url, workspace_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::POST, &url)
+      .await?
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<PublishCustomDomain>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  pub async fn delete_workspace_publish_custom_domain(
+    &self,
+    workspace_id: &str,
+  ) -> Result<(), AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/publish-custom-domain",
+      self.base_url, workspace_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::DELETE, &url)
+      .await?
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<()>::from_response(resp).await?.into_error()
+  }
+
+  /// Views, unique visitors, duplicates and top referrers of the published views of the
+  /// workspace. Only the owner of the workspace can get them.
+  pub async fn get_workspace_publish_analytics(
+    &self,
+    workspace_id: &str,
+    params: &PublishAnalyticsQueryParams,
+  ) -> Result<PublishAnalytics, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/publish-analytics",
+      self.base_url, workspace_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .query(params)
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<PublishAnalytics>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
   pub async fn patch_published_collabs(
     &self,
     workspace_id: &str,
@@ -258,6 +368,151 @@ impl Client {
       .await?
       .into_data()
   }
+
+  pub async fn list_publish_history(
+    &self,
+    workspace_id: &str,
+    view_id: &str,
+  ) -> Result<Vec<PublishHistoryInfo>, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/page-view/{}/publish-history",
+      self.base_url, workspace_id, view_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<Vec<PublishHistoryInfo>>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  pub async fn get_publish_history_detail(
+    &self,
+    workspace_id: &str,
+    view_id: &str,
+    history_id: &uuid::Uuid,
+  ) -> Result<PublishHistoryDetail, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/page-view/{}/publish-history/{}",
+      self.base_url, workspace_id, view_id, history_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<PublishHistoryDetail>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  pub async fn get_publish_history_blob(
+    &self,
+    workspace_id: &str,
+    view_id: &str,
+    history_id: &uuid::Uuid,
+  ) -> Result<Bytes, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/page-view/{}/publish-history/{}/blob",
+      self.base_url, workspace_id, view_id, history_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .send()
+      .await?;
+    log_request_id(&resp);
+    let bytes = resp.error_for_status()?.bytes().await?;
+
+    if let Ok(app_err) = serde_json::from_slice::<AppResponseError>(&bytes) {
+      return Err(app_err);
+    }
+
+    Ok(bytes)
+  }
+
+  pub async fn rollback_to_publish_history(
+    &self,
+    workspace_id: &str,
+    view_id: &str,
+    history_id: &uuid::Uuid,
+  ) -> Result<(), AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/page-view/{}/publish-history/{}/rollback",
+      self.base_url, workspace_id, view_id, history_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::POST, &url)
+      .await?
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<()>::from_response(resp).await?.into_error()
+  }
+
+  pub async fn create_publish_schedule(
+    &self,
+    workspace_id: &str,
+    view_id: &str,
+    params: &CreatePublishSchedule,
+  ) -> Result<PublishSchedule, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/page-view/{}/publish-schedule",
+      self.base_url, workspace_id, view_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::POST, &url)
+      .await?
+      .json(params)
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<PublishSchedule>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  pub async fn list_publish_schedules(
+    &self,
+    workspace_id: &str,
+    view_id: &str,
+  ) -> Result<Vec<PublishSchedule>, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/page-view/{}/publish-schedule",
+      self.base_url, workspace_id, view_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<Vec<PublishSchedule>>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  pub async fn cancel_publish_schedule(
+    &self,
+    workspace_id: &str,
+    schedule_id: &uuid::Uuid,
+  ) -> Result<(), AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/publish-schedule/{}",
+      self.base_url, workspace_id, schedule_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::DELETE, &url)
+      .await?
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<()>::from_response(resp).await?.into_error()
+  }
 }
 
 // Optional login
@@ -365,14 +620,31 @@ impl Client {
       publish_namespace,
       publish_name
     );
+    self
+      .get_protected_published_collab(publish_namespace, publish_name, None)
+      .await
+  }
+
+  /// Get the metadata of a published view restricted to the members of its workspace, or
+  /// protected by a password. Members don't need the password when the client is signed in.
+  #[instrument(level = "debug", skip_all)]
+  pub async fn get_protected_published_collab<T>(
+    &self,
+    publish_namespace: &str,
+    publish_name: &str,
+    password: Option<&str>,
+  ) -> Result<T, AppResponseError>
+  where
+    T: serde::de::DeserializeOwned + 'static,
+  {
     let url = format!(
       "{}/api/workspace/v1/published/{}/{}",
       self.base_url, publish_namespace, publish_name
     );
 
     let resp = self
-      .cloud_client
-      .get(&url)
+      .published_view_request(&url, password)
+      .await?
       .send()
       .await?
       .error_for_status()?;
@@ -392,11 +664,29 @@ impl Client {
       publish_namespace,
       publish_name
     );
+    self
+      .get_protected_published_collab_blob(publish_namespace, publish_name, None)
+      .await
+  }
+
+  /// Get the content of a published view restricted to the members of its workspace, or
+  /// protected by a password.
+  #[instrument(level = "debug", skip_all)]
+  pub async fn get_protected_published_collab_blob(
+    &self,
+    publish_namespace: &str,
+    publish_name: &str,
+    password: Option<&str>,
+  ) -> Result<Bytes, AppResponseError> {
     let url = format!(
       "{}/api/workspace/published/{}/{}/blob",
       self.base_url, publish_namespace, publish_name
     );
-    let resp = self.cloud_client.get(&url).send().await?;
+    let resp = self
+      .published_view_request(&url, password)
+      .await?
+      .send()
+      .await?;
     log_request_id(&resp);
     let bytes = resp.error_for_status()?.bytes().await?;
 
@@ -411,17 +701,31 @@ impl Client {
     &self,
     workspace_id: &str,
     publish_duplicate: &PublishedDuplicate,
+  ) -> Result<DuplicatePublishedPageResponse, AppResponseError> {
+    self
+      .duplicate_protected_published_to_workspace(workspace_id, publish_duplicate, None)
+      .await
+  }
+
+  /// Duplicate a published view protected by a password into the workspace.
+  pub async fn duplicate_protected_published_to_workspace(
+    &self,
+    workspace_id: &str,
+    publish_duplicate: &PublishedDuplicate,
+    password: Option<&str>,
   ) -> Result<DuplicatePublishedPageResponse, AppResponseError> {
     let url = format!(
       "{}/api/workspace/{}/published-duplicate",
       self.base_url, workspace_id
     );
-    let resp = self
+    let mut builder = self
       .http_client_with_auth(Method::POST, &url)
       .await?
-      .json(publish_duplicate)
-      .send()
-      .await?;
+      .json(publish_duplicate);
+    if let Some(password) = password {
+      builder = builder.header(X_PUBLISH_PASSWORD, password);
+    }
+    let resp = builder.send().await?;
     log_request_id(&resp);
     AppResponse::<DuplicatePublishedPageResponse>::from_response(resp)
       .await?
@@ -450,4 +754,22 @@ impl Client {
       .await?
       .into_data()
   }
+
+  /// Published views are fetched with the access token of the signed in user, if any, so that
+  /// the members of the workspace can view the published views restricted to them.
+  async fn published_view_request(
+    &self,
+    url: &str,
+    password: Option<&str>,
+  ) -> Result<RequestBuilder, AppResponseError> {
+    let mut builder = if self.access_token().is_ok() {
+      self.http_client_with_auth(Method::GET, url).await?
+    } else {
+      self.cloud_client.get(url)
+    };
+    if let Some(password) = password {
+      builder = builder.header(X_PUBLISH_PASSWORD, password);
+    }
+    Ok(builder)
+  }
 }
@@ -2,6 +2,7 @@ use reqwest::Method;
 use tracing::{instrument, trace};
 
 use client_api_entity::AFWorkspaceSettings;
+use shared_entity::dto::workspace_dto::WorkspaceDataKeyRotated;
 use shared_entity::response::{AppResponse, AppResponseError};
 
 use crate::entity::AFWorkspaceSettingsChange;
@@ -51,4 +52,26 @@ impl Client {
     let resp = AppResponse::<AFWorkspaceSettings>::from_response(resp).await?;
     resp.into_data()
   }
+
+  /// Only available to the admin, when the collabs are encrypted at rest.
+  #[instrument(level = "info", skip_all, err)]
+  pub async fn rotate_workspace_data_key<T: AsRef<str>>(
+    &self,
+    workspace_id: T,
+  ) -> Result<WorkspaceDataKeyRotated, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/data-key/rotate",
+      self.base_url,
+      workspace_id.as_ref()
+    );
+    let resp = self
+      .http_client_with_auth(Method::POST, &url)
+      .await?
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<WorkspaceDataKeyRotated>::from_response(resp)
+      .await?
+      .into_data()
+  }
 }
@@ -1,6 +1,6 @@
 use client_api_entity::workspace_dto::{
-  CreatePageParams, CreateSpaceParams, MovePageParams, Page, PageCollab, PublishPageParams, Space,
-  UpdatePageParams, UpdateSpaceParams,
+  CreatePageParams, CreateSpaceParams, ExportPageQuery, MovePageParams, Page, PageCollab,
+  PageExportFormat, PublishPageParams, Space, UpdatePageParams, UpdateSpaceParams,
 };
 use reqwest::Method;
 use serde_json::json;
@@ -169,6 +169,31 @@ impl Client {
       .into_data()
   }
 
+  /// Returns the page rendered in the given format, Markdown for documents and CSV for
+  /// databases if not set.
+  pub async fn export_workspace_page_view(
+    &self,
+    workspace_id: Uuid,
+    view_id: &str,
+    format: Option<PageExportFormat>,
+  ) -> Result<String, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/page-view/{}/export",
+      self.base_url, workspace_id, view_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .query(&ExportPageQuery { format })
+      .send()
+      .await?;
+    if resp.status().is_success() {
+      Ok(resp.text().await?)
+    } else {
+      AppResponse::from_response(resp).await?.into_data()
+    }
+  }
+
   pub async fn publish_page(
     &self,
     workspace_id: Uuid,
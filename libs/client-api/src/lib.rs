@@ -2,10 +2,13 @@ mod http;
 mod http_ai;
 mod http_billing;
 
+mod http_access_control;
 mod http_access_request;
+mod http_audit_log;
 mod http_blob;
 mod http_collab;
 mod http_member;
+mod http_personal_access_token;
 mod http_publish;
 mod http_quick_note;
 mod http_search;
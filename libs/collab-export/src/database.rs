@@ -0,0 +1,96 @@
+use anyhow::Context;
+use collab_database::entity::FieldType;
+use collab_database::fields::{type_option_cell_reader, Field, TypeOptionCellReader};
+use collab_database::rows::{Cell, Row};
+use collab_database::template::timestamp_parse::TimestampCellData;
+use std::collections::HashMap;
+
+/// Converts the rows of a database view into CSV. The fields and the rows are expected in the
+/// order of the view, and each cell is written as the text that the clients display.
+pub fn database_to_csv(fields: &[Field], rows: &[Row]) -> Result<String, anyhow::Error> {
+  let readers = fields
+    .iter()
+    .map(|field| {
+      let field_type = FieldType::from(field.field_type);
+      let type_option_data = field
+        .get_any_type_option(field_type.type_id())
+        .unwrap_or_default();
+      type_option_cell_reader(type_option_data, &field_type)
+    })
+    .collect::<Vec<Box<dyn TypeOptionCellReader>>>();
+
+  let mut writer = csv::Writer::from_writer(vec![]);
+  writer.write_record(fields.iter().map(|field| field.name.as_str()))?;
+  for row in rows {
+    let record = fields
+      .iter()
+      .zip(readers.iter())
+      .map(|(field, reader)| match row_cell(row, field) {
+        Some(cell) => reader.stringify_cell(&cell),
+        None => String::new(),
+      })
+      .collect::<Vec<_>>();
+    writer.write_record(&record)?;
+  }
+  let bytes = writer.into_inner().context("Failed to flush csv")?;
+  Ok(String::from_utf8(bytes)?)
+}
+
+/// Returns the cell of the row for the field. The created and last edited time are not stored in
+/// the cells, so they are read from the row itself.
+fn row_cell(row: &Row, field: &Field) -> Option<Cell> {
+  let field_type = FieldType::from(field.field_type);
+  match field_type {
+    FieldType::CreatedTime => {
+      Some(TimestampCellData::new(Some(row.created_at)).to_cell(field_type))
+    },
+    FieldType::LastEditedTime => {
+      Some(TimestampCellData::new(Some(row.modified_at)).to_cell(field_type))
+    },
+    _ => row.cells.get(&field.id).cloned(),
+  }
+}
+
+/// Returns the fields of the view in their order, followed by the fields that the view doesn't
+/// order.
+pub fn fields_in_view_order(fields: Vec<Field>, field_order_ids: &[String]) -> Vec<Field> {
+  let position = field_order_ids
+    .iter()
+    .enumerate()
+    .map(|(i, id)| (id.as_str(), i))
+    .collect::<HashMap<_, _>>();
+  let mut fields = fields;
+  fields.sort_by_key(|field| {
+    position
+      .get(field.id.as_str())
+      .copied()
+      .unwrap_or(usize::MAX)
+  });
+  fields
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use collab_database::rows::new_cell_builder;
+  use collab_database::template::entity::CELL_DATA;
+
+  #[test]
+  fn database_to_csv_test() {
+    let name = Field::from_field_type("Name", FieldType::RichText, true);
+    let notes = Field::from_field_type("Notes", FieldType::RichText, false);
+    let mut row = Row::new("row", "database");
+    let mut cell = new_cell_builder(FieldType::RichText);
+    cell.insert(CELL_DATA.into(), "Pen, blue".into());
+    row.cells.insert(name.id.clone(), cell);
+
+    let fields = fields_in_view_order(
+      vec![notes.clone(), name.clone()],
+      &[name.id.clone(), notes.id.clone()],
+    );
+    assert_eq!(
+      database_to_csv(&fields, &[row]).unwrap(),
+      "Name,Notes\n\"Pen, blue\",\n"
+    );
+  }
+}
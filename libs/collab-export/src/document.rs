@@ -0,0 +1,966 @@
+use collab_document::blocks::{Block, DocumentData};
+use serde_json::{json, Map, Value};
+
+/// A link to another page of the workspace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageLink {
+  pub name: String,
+  pub href: String,
+}
+
+/// Resolves the links of an exported document, which depend on where the pages and the files
+/// end up, e.g. relative paths in an archive or URLs of the server.
+pub trait ExportLinks {
+  /// Returns the link to a page that is mentioned or embedded in the document, or `None` if the
+  /// page isn't accessible, in which case only its name is kept if it's known.
+  fn page_link(&self, view_id: &str) -> Option<PageLink>;
+
+  /// Returns the link to a file embedded in the document, e.g. an image.
+  fn file_link(&self, url: &str) -> String {
+    url.to_string()
+  }
+}
+
+/// Keeps the links of the files as is and drops the links to other pages.
+pub struct NoPageLinks;
+
+impl ExportLinks for NoPageLinks {
+  fn page_link(&self, _view_id: &str) -> Option<PageLink> {
+    None
+  }
+}
+
+pub fn document_to_markdown(data: &DocumentData, links: &dyn ExportLinks) -> String {
+  let reader = DocumentReader { data, links };
+  let mut markdown = reader.markdown_blocks(&reader.children(&data.page_id));
+  if !markdown.is_empty() {
+    markdown.push('\n');
+  }
+  markdown
+}
+
+/// Returns the body of the HTML page of the document, see [html_page].
+pub fn document_to_html(data: &DocumentData, links: &dyn ExportLinks) -> String {
+  let reader = DocumentReader { data, links };
+  reader.html_blocks(&reader.children(&data.page_id))
+}
+
+/// Wraps the HTML of a document into a standalone page.
+pub fn html_page(title: &str, body: &str) -> String {
+  format!(
+    "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n</head>\n<body>\n<h1>{title}</h1>\n{body}\n</body>\n</html>\n",
+    title = escape_html(title),
+    body = body
+  )
+}
+
+/// Converts the document into a tree of blocks, each with its type, its data and its children,
+/// the layout the workspace templates are written in. The text of a block is the `delta` of its
+/// data, and the blocks that link to a page get an `href` if the page is accessible.
+pub fn document_to_json(data: &DocumentData, links: &dyn ExportLinks) -> Value {
+  let reader = DocumentReader { data, links };
+  match data.blocks.get(&data.page_id) {
+    Some(page) => reader.json_block(page),
+    None => json!({ "type": "page", "data": {}, "children": [] }),
+  }
+}
+
+/// Returns the URLs of the images and files embedded in the document.
+pub fn document_file_urls(data: &DocumentData) -> Vec<String> {
+  let mut urls = data
+    .blocks
+    .values()
+    .filter(|block| block.ty == "image" || block.ty == "file")
+    .filter_map(|block| block.data.get("url").and_then(|url| url.as_str()))
+    .filter(|url| !url.is_empty())
+    .map(|url| url.to_string())
+    .collect::<Vec<_>>();
+  urls.sort();
+  urls.dedup();
+  urls
+}
+
+struct DocumentReader<'a> {
+  data: &'a DocumentData,
+  links: &'a dyn ExportLinks,
+}
+
+impl<'a> DocumentReader<'a> {
+  fn children(&self, block_id: &str) -> Vec<&'a Block> {
+    let Some(block) = self.data.blocks.get(block_id) else {
+      return vec![];
+    };
+    self
+      .data
+      .meta
+      .children_map
+      .get(&block.children)
+      .map(|ids| {
+        ids
+          .iter()
+          .filter_map(|id| self.data.blocks.get(id))
+          .collect()
+      })
+      .unwrap_or_default()
+  }
+
+  /// Returns the delta of the text of the block, i.e. its list of inserts.
+  fn delta(&self, block: &Block) -> Vec<Value> {
+    let text_map = match self.data.meta.text_map.as_ref() {
+      Some(text_map) => text_map,
+      None => return vec![],
+    };
+    block
+      .external_id
+      .as_ref()
+      .and_then(|external_id| text_map.get(external_id))
+      .or_else(|| text_map.get(&block.id))
+      .and_then(|delta| serde_json::from_str::<Vec<Value>>(delta).ok())
+      .unwrap_or_default()
+  }
+
+  fn plain_text(&self, block: &Block) -> String {
+    self
+      .delta(block)
+      .iter()
+      .filter_map(|op| op.get("insert").and_then(|insert| insert.as_str()))
+      .collect()
+  }
+
+  fn data_str(block: &'a Block, key: &str) -> &'a str {
+    block
+      .data
+      .get(key)
+      .and_then(|value| value.as_str())
+      .unwrap_or_default()
+  }
+
+  /// Returns the text of an inline mention, or `None` if the insert isn't a mention.
+  fn mention(&self, attributes: &Map<String, Value>, html: bool) -> Option<String> {
+    let mention = attributes.get("mention")?;
+    let text = match mention.get("type").and_then(|ty| ty.as_str()) {
+      Some("page") | Some("childPage") => {
+        let page_id = mention.get("page_id").and_then(|id| id.as_str())?;
+        match self.links.page_link(page_id) {
+          Some(link) if html => html_link(&link.href, &escape_html(&link.name)),
+          Some(link) => format!("[{}]({})", link.name, markdown_href(&link.href)),
+          None => String::new(),
+        }
+      },
+      Some("date") | Some("reminder") => mention
+        .get("date")
+        .and_then(|date| date.as_str())
+        .unwrap_or_default()
+        .to_string(),
+      _ => String::new(),
+    };
+    Some(text)
+  }
+
+  fn markdown_text(&self, block: &Block) -> String {
+    let mut text = String::new();
+    for op in self.delta(block) {
+      let insert = op
+        .get("insert")
+        .and_then(|insert| insert.as_str())
+        .unwrap_or_default();
+      let Some(attributes) = op.get("attributes").and_then(|attrs| attrs.as_object()) else {
+        text.push_str(insert);
+        continue;
+      };
+      if let Some(mention) = self.mention(attributes, false) {
+        text.push_str(&mention);
+        continue;
+      }
+      if let Some(formula) = attributes.get("formula").and_then(|f| f.as_str()) {
+        text.push_str(&format!("${}$", formula));
+        continue;
+      }
+
+      let mut span = insert.to_string();
+      if is_set(attributes, "code") {
+        span = format!("`{}`", span);
+      }
+      if is_set(attributes, "bold") {
+        span = format!("**{}**", span);
+      }
+      if is_set(attributes, "italic") {
+        span = format!("_{}_", span);
+      }
+      if is_set(attributes, "strikethrough") {
+        span = format!("~~{}~~", span);
+      }
+      if let Some(href) = attributes.get("href").and_then(|href| href.as_str()) {
+        span = format!("[{}]({})", span, markdown_href(href));
+      }
+      text.push_str(&span);
+    }
+    text
+  }
+
+  fn json_block(&self, block: &Block) -> Value {
+    let mut data = block
+      .data
+      .iter()
+      .map(|(key, value)| (key.clone(), value.clone()))
+      .collect::<Map<String, Value>>();
+    let delta = self.delta(block);
+    if !delta.is_empty() {
+      data.insert("delta".to_string(), Value::Array(delta));
+    }
+    match block.ty.as_str() {
+      "image" | "file" => {
+        let url = Self::data_str(block, "url");
+        if !url.is_empty() {
+          data.insert("url".to_string(), Value::String(self.links.file_link(url)));
+        }
+      },
+      "grid" | "board" | "calendar" | "sub_page" => {
+        if let Some(link) = self.links.page_link(Self::data_str(block, "view_id")) {
+          data.insert("href".to_string(), Value::String(link.href));
+        }
+      },
+      _ => {},
+    }
+    let children = self
+      .children(&block.id)
+      .into_iter()
+      .map(|child| self.json_block(child))
+      .collect::<Vec<_>>();
+    json!({ "type": block.ty, "data": data, "children": children })
+  }
+
+  fn markdown_blocks(&self, blocks: &[&Block]) -> String {
+    let mut markdown = String::new();
+    let mut previous_is_list = false;
+    let mut number = 0;
+    for block in blocks {
+      let is_list = is_list_item(block);
+      number = if block.ty == "numbered_list" {
+        number + 1
+      } else {
+        0
+      };
+      let rendered = self.markdown_block(block, number);
+      if rendered.is_empty() {
+        continue;
+      }
+      if !markdown.is_empty() {
+        markdown.push_str(if previous_is_list && is_list {
+          "\n"
+        } else {
+          "\n\n"
+        });
+      }
+      markdown.push_str(&rendered);
+      previous_is_list = is_list;
+    }
+    markdown
+  }
+
+  fn markdown_block(&self, block: &Block, number: usize) -> String {
+    let children = self.markdown_blocks(&self.children(&block.id));
+    match block.ty.as_str() {
+      "heading" => {
+        let level = block
+          .data
+          .get("level")
+          .and_then(|level| level.as_u64())
+          .unwrap_or(1)
+          .clamp(1, 6) as usize;
+        let heading = format!("{} {}", "#".repeat(level), self.markdown_text(block));
+        join_paragraphs(heading, children)
+      },
+      "bulleted_list" | "toggle_list" => list_item("- ", &self.markdown_text(block), &children),
+      "numbered_list" => list_item(
+        &format!("{}. ", number),
+        &self.markdown_text(block),
+        &children,
+      ),
+      "todo_list" => {
+        let checked = block
+          .data
+          .get("checked")
+          .and_then(|checked| checked.as_bool())
+          .unwrap_or(false);
+        let marker = if checked { "- [x] " } else { "- [ ] " };
+        list_item(marker, &self.markdown_text(block), &children)
+      },
+      "quote" => prefix_lines("> ", &join_paragraphs(self.markdown_text(block), children)),
+      "callout" => {
+        let icon = Self::data_str(block, "icon");
+        let text = if icon.is_empty() {
+          self.markdown_text(block)
+        } else {
+          format!("{} {}", icon, self.markdown_text(block))
+        };
+        prefix_lines("> ", &join_paragraphs(text, children))
+      },
+      "code" => format!(
+        "```{}\n{}\n```",
+        Self::data_str(block, "language"),
+        self.plain_text(block)
+      ),
+      "divider" => "---".to_string(),
+      "image" => {
+        let url = Self::data_str(block, "url");
+        if url.is_empty() {
+          String::new()
+        } else {
+          format!("![]({})", markdown_href(&self.links.file_link(url)))
+        }
+      },
+      "file" => {
+        let url = Self::data_str(block, "url");
+        let name = Self::data_str(block, "name");
+        if url.is_empty() {
+          String::new()
+        } else {
+          let name = if name.is_empty() { url } else { name };
+          format!("[{}]({})", name, markdown_href(&self.links.file_link(url)))
+        }
+      },
+      "link_preview" => {
+        let url = Self::data_str(block, "url");
+        if url.is_empty() {
+          String::new()
+        } else {
+          format!("[{}]({})", url, markdown_href(url))
+        }
+      },
+      "math_equation" => format!("$$\n{}\n$$", Self::data_str(block, "formula")),
+      "grid" | "board" | "calendar" | "sub_page" => self
+        .links
+        .page_link(Self::data_str(block, "view_id"))
+        .map(|link| format!("[{}]({})", link.name, markdown_href(&link.href)))
+        .unwrap_or_default(),
+      "table" => self.markdown_table(block),
+      _ => join_paragraphs(self.markdown_text(block), children),
+    }
+  }
+
+  /// Returns the text of each cell of a table, by row.
+  fn table_cells(&self, block: &Block, cell_text: impl Fn(&Block) -> String) -> Vec<Vec<String>> {
+    let len = |key: &str| {
+      block
+        .data
+        .get(key)
+        .and_then(|len| len.as_u64())
+        .unwrap_or(0) as usize
+    };
+    let (rows_len, cols_len) = (len("rowsLen"), len("colsLen"));
+    let mut rows = vec![vec![String::new(); cols_len]; rows_len];
+    for cell in self.children(&block.id) {
+      let position = |key: &str| {
+        cell
+          .data
+          .get(key)
+          .and_then(|position| position.as_u64())
+          .map(|position| position as usize)
+      };
+      if let (Some(row), Some(col)) = (position("rowPosition"), position("colPosition")) {
+        if row < rows_len && col < cols_len {
+          rows[row][col] = cell_text(cell);
+        }
+      }
+    }
+    rows
+  }
+
+  fn markdown_table(&self, block: &Block) -> String {
+    let rows = self.table_cells(block, |cell| {
+      self
+        .children(&cell.id)
+        .iter()
+        .map(|child| self.markdown_text(child))
+        .collect::<Vec<_>>()
+        .join("<br>")
+        .replace('|', "\\|")
+        .replace('\n', "<br>")
+    });
+    let Some((header, body)) = rows.split_first() else {
+      return String::new();
+    };
+    let mut lines = vec![
+      format!("| {} |", header.join(" | ")),
+      format!("|{}", " --- |".repeat(header.len())),
+    ];
+    lines.extend(body.iter().map(|row| format!("| {} |", row.join(" | "))));
+    lines.join("\n")
+  }
+
+  fn html_text(&self, block: &Block) -> String {
+    let mut html = String::new();
+    for op in self.delta(block) {
+      let insert = op
+        .get("insert")
+        .and_then(|insert| insert.as_str())
+        .unwrap_or_default();
+      let Some(attributes) = op.get("attributes").and_then(|attrs| attrs.as_object()) else {
+        html.push_str(&escape_html(insert));
+        continue;
+      };
+      if let Some(mention) = self.mention(attributes, true) {
+        html.push_str(&mention);
+        continue;
+      }
+      if let Some(formula) = attributes.get("formula").and_then(|f| f.as_str()) {
+        html.push_str(&format!(
+          "<span class=\"formula\">{}</span>",
+          escape_html(formula)
+        ));
+        continue;
+      }
+
+      let mut span = escape_html(insert);
+      for (key, tag) in [
+        ("code", "code"),
+        ("bold", "strong"),
+        ("italic", "em"),
+        ("underline", "u"),
+        ("strikethrough", "s"),
+      ] {
+        if is_set(attributes, key) {
+          span = format!("<{tag}>{span}</{tag}>");
+        }
+      }
+      if let Some(href) = attributes.get("href").and_then(|href| href.as_str()) {
+        span = html_link(href, &span);
+      }
+      html.push_str(&span);
+    }
+    html
+  }
+
+  fn html_blocks(&self, blocks: &[&Block]) -> String {
+    let mut html = vec![];
+    // The tag of the list that is open, if any
+    let mut open_list: Option<&str> = None;
+    for block in blocks {
+      let list = match block.ty.as_str() {
+        "bulleted_list" | "todo_list" => Some("ul"),
+        "numbered_list" => Some("ol"),
+        _ => None,
+      };
+      if open_list != list {
+        if let Some(tag) = open_list {
+          html.push(format!("</{}>", tag));
+        }
+        if let Some(tag) = list {
+          html.push(format!("<{}>", tag));
+        }
+        open_list = list;
+      }
+      let rendered = self.html_block(block);
+      if !rendered.is_empty() {
+        html.push(rendered);
+      }
+    }
+    if let Some(tag) = open_list {
+      html.push(format!("</{}>", tag));
+    }
+    html.join("\n")
+  }
+
+  fn html_block(&self, block: &Block) -> String {
+    let children = self.html_blocks(&self.children(&block.id));
+    let text = || self.html_text(block);
+    let with_children = |html: String| {
+      if children.is_empty() {
+        html
+      } else {
+        format!("{}\n{}", html, children)
+      }
+    };
+    match block.ty.as_str() {
+      "heading" => {
+        let level = block
+          .data
+          .get("level")
+          .and_then(|level| level.as_u64())
+          .unwrap_or(1)
+          .clamp(1, 6);
+        with_children(format!("<h{level}>{}</h{level}>", text()))
+      },
+      "bulleted_list" | "numbered_list" => format!("<li>{}</li>", with_children(text())),
+      "todo_list" => {
+        let checked = block
+          .data
+          .get("checked")
+          .and_then(|checked| checked.as_bool())
+          .unwrap_or(false);
+        let checkbox = if checked {
+          "<input type=\"checkbox\" disabled checked>"
+        } else {
+          "<input type=\"checkbox\" disabled>"
+        };
+        format!("<li>{} {}</li>", checkbox, with_children(text()))
+      },
+      "toggle_list" => format!(
+        "<details>\n<summary>{}</summary>\n{}\n</details>",
+        text(),
+        children
+      ),
+      "quote" => format!(
+        "<blockquote>\n{}\n</blockquote>",
+        with_children(format!("<p>{}</p>", text()))
+      ),
+      "callout" => {
+        let icon = escape_html(Self::data_str(block, "icon"));
+        format!(
+          "<aside class=\"callout\">\n{}\n</aside>",
+          with_children(format!("<p>{} {}</p>", icon, text()))
+        )
+      },
+      "code" => format!(
+        "<pre><code class=\"language-{}\">{}</code></pre>",
+        escape_html(Self::data_str(block, "language")),
+        escape_html(&self.plain_text(block))
+      ),
+      "divider" => "<hr>".to_string(),
+      "image" => {
+        let url = Self::data_str(block, "url");
+        let url = self.links.file_link(url);
+        if url.is_empty() || !is_safe_url(&url) {
+          String::new()
+        } else {
+          format!("<img src=\"{}\">", escape_html(&url))
+        }
+      },
+      "file" => {
+        let url = Self::data_str(block, "url");
+        let name = Self::data_str(block, "name");
+        if url.is_empty() {
+          String::new()
+        } else {
+          let name = if name.is_empty() { url } else { name };
+          format!(
+            "<p>{}</p>",
+            html_link(&self.links.file_link(url), &escape_html(name))
+          )
+        }
+      },
+      "link_preview" => {
+        let url = Self::data_str(block, "url");
+        if url.is_empty() {
+          String::new()
+        } else {
+          format!("<p>{}</p>", html_link(url, &escape_html(url)))
+        }
+      },
+      "math_equation" => format!(
+        "<p class=\"math-equation\">{}</p>",
+        escape_html(Self::data_str(block, "formula"))
+      ),
+      "grid" | "board" | "calendar" | "sub_page" => self
+        .links
+        .page_link(Self::data_str(block, "view_id"))
+        .map(|link| format!("<p>{}</p>", html_link(&link.href, &escape_html(&link.name))))
+        .unwrap_or_default(),
+      "table" => self.html_table(block),
+      _ => {
+        let text = text();
+        if text.is_empty() && children.is_empty() {
+          String::new()
+        } else {
+          with_children(format!("<p>{}</p>", text))
+        }
+      },
+    }
+  }
+
+  fn html_table(&self, block: &Block) -> String {
+    let rows = self.table_cells(block, |cell| self.html_blocks(&self.children(&cell.id)));
+    let rows = rows
+      .iter()
+      .map(|row| {
+        let cells = row
+          .iter()
+          .map(|cell| format!("<td>{}</td>", cell))
+          .collect::<String>();
+        format!("<tr>{}</tr>", cells)
+      })
+      .collect::<Vec<_>>();
+    if rows.is_empty() {
+      return String::new();
+    }
+    format!("<table>\n{}\n</table>", rows.join("\n"))
+  }
+}
+
+fn is_set(attributes: &Map<String, Value>, key: &str) -> bool {
+  attributes
+    .get(key)
+    .and_then(|value| value.as_bool())
+    .unwrap_or(false)
+}
+
+fn is_list_item(block: &Block) -> bool {
+  matches!(
+    block.ty.as_str(),
+    "bulleted_list" | "numbered_list" | "todo_list" | "toggle_list"
+  )
+}
+
+fn join_paragraphs(text: String, children: String) -> String {
+  match (text.is_empty(), children.is_empty()) {
+    (_, true) => text,
+    (true, false) => children,
+    (false, false) => format!("{}\n\n{}", text, children),
+  }
+}
+
+/// Renders a list item whose children are indented under its marker.
+fn list_item(marker: &str, text: &str, children: &str) -> String {
+  let mut item = format!("{}{}", marker, text);
+  if !children.is_empty() {
+    let indent = " ".repeat(marker.trim_end().len() + 1);
+    item.push('\n');
+    item.push_str(&prefix_lines(&indent, children));
+  }
+  item
+}
+
+fn prefix_lines(prefix: &str, text: &str) -> String {
+  text
+    .lines()
+    .map(|line| {
+      if line.is_empty() {
+        prefix.trim_end().to_string()
+      } else {
+        format!("{}{}", prefix, line)
+      }
+    })
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+/// Encodes the characters that would end a Markdown link destination.
+fn markdown_href(href: &str) -> String {
+  href
+    .replace(' ', "%20")
+    .replace('(', "%28")
+    .replace(')', "%29")
+}
+
+/// Returns a link to the URL around the HTML, or only the HTML if the URL could run a script
+/// when followed.
+fn html_link(url: &str, html: &str) -> String {
+  if is_safe_url(url) {
+    format!("<a href=\"{}\">{}</a>", escape_html(url), html)
+  } else {
+    html.to_string()
+  }
+}
+
+/// Only relative URLs and the http, https and mailto schemes are kept in HTML, as a link to e.g.
+/// `javascript:` would run in the page. Browsers ignore whitespace and control characters in the
+/// scheme, so they are ignored here too.
+fn is_safe_url(url: &str) -> bool {
+  let url = url
+    .chars()
+    .filter(|c| !c.is_whitespace() && !c.is_control())
+    .collect::<String>();
+  match url.find([':', '/', '?', '#']) {
+    Some(i) if url[i..].starts_with(':') => matches!(
+      url[..i].to_ascii_lowercase().as_str(),
+      "http" | "https" | "mailto"
+    ),
+    _ => true,
+  }
+}
+
+pub fn escape_html(text: &str) -> String {
+  let mut escaped = String::with_capacity(text.len());
+  for c in text.chars() {
+    match c {
+      '&' => escaped.push_str("&amp;"),
+      '<' => escaped.push_str("&lt;"),
+      '>' => escaped.push_str("&gt;"),
+      '"' => escaped.push_str("&quot;"),
+      '\'' => escaped.push_str("&#39;"),
+      _ => escaped.push(c),
+    }
+  }
+  escaped
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use collab_document::blocks::DocumentMeta;
+  use serde_json::json;
+  use std::collections::HashMap;
+
+  struct TestLinks;
+
+  impl ExportLinks for TestLinks {
+    fn page_link(&self, view_id: &str) -> Option<PageLink> {
+      Some(PageLink {
+        name: format!("Page {}", view_id),
+        href: format!("{}.md", view_id),
+      })
+    }
+
+    fn file_link(&self, url: &str) -> String {
+      format!("assets/{}", url)
+    }
+  }
+
+  /// Builds a document from `(id, parent id, type, data, delta)` tuples, in the order of the
+  /// children.
+  fn document(blocks: Vec<(&str, &str, &str, Value, Option<Value>)>) -> DocumentData {
+    let mut data = DocumentData {
+      page_id: "page".to_string(),
+      blocks: HashMap::new(),
+      meta: DocumentMeta {
+        children_map: HashMap::new(),
+        text_map: Some(HashMap::new()),
+      },
+    };
+    let mut all = vec![("page", "", "page", json!({}), None)];
+    all.extend(blocks);
+    for (id, parent, ty, block_data, delta) in all {
+      let block = Block {
+        id: id.to_string(),
+        ty: ty.to_string(),
+        parent: parent.to_string(),
+        children: format!("{}_children", id),
+        external_id: delta.as_ref().map(|_| format!("{}_text", id)),
+        external_type: delta.as_ref().map(|_| "text".to_string()),
+        data: serde_json::from_value(block_data).unwrap(),
+      };
+      if let Some(delta) = delta {
+        data
+          .meta
+          .text_map
+          .as_mut()
+          .unwrap()
+          .insert(format!("{}_text", id), delta.to_string());
+      }
+      if !parent.is_empty() {
+        data
+          .meta
+          .children_map
+          .entry(format!("{}_children", parent))
+          .or_default()
+          .push(id.to_string());
+      }
+      data.blocks.insert(id.to_string(), block);
+    }
+    data
+  }
+
+  fn text(insert: &str) -> Option<Value> {
+    Some(json!([{ "insert": insert }]))
+  }
+
+  #[test]
+  fn markdown_test() {
+    let data = document(vec![
+      ("h", "page", "heading", json!({ "level": 2 }), text("Title")),
+      (
+        "p",
+        "page",
+        "paragraph",
+        json!({}),
+        Some(json!([
+          { "insert": "Hello " },
+          { "insert": "world", "attributes": { "bold": true } },
+          { "insert": " see " },
+          { "insert": "$", "attributes": { "mention": { "type": "page", "page_id": "sub" } } },
+          { "insert": " and " },
+          { "insert": "docs", "attributes": { "href": "https://appflowy.io" } },
+        ])),
+      ),
+      ("b1", "page", "bulleted_list", json!({}), text("one")),
+      ("b2", "b1", "bulleted_list", json!({}), text("nested")),
+      ("n1", "page", "numbered_list", json!({}), text("first")),
+      ("n2", "page", "numbered_list", json!({}), text("second")),
+      (
+        "t",
+        "page",
+        "todo_list",
+        json!({ "checked": true }),
+        text("done"),
+      ),
+      (
+        "c",
+        "page",
+        "code",
+        json!({ "language": "rust" }),
+        text("let a = 1;"),
+      ),
+      ("i", "page", "image", json!({ "url": "a.png" }), None),
+      ("g", "page", "grid", json!({ "view_id": "db" }), None),
+      ("s", "page", "sub_page", json!({ "view_id": "child" }), None),
+    ]);
+    assert_eq!(
+      document_to_markdown(&data, &TestLinks),
+      "## Title\n\nHello **world** see [Page sub](sub.md) and [docs](https://appflowy.io)\n\n\
+       - one\n  - nested\n1. first\n2. second\n- [x] done\n\n```rust\nlet a = 1;\n```\n\n\
+       ![](assets/a.png)\n\n[Page db](db.md)\n\n[Page child](child.md)\n"
+    );
+  }
+
+  #[test]
+  fn markdown_table_test() {
+    let data = document(vec![
+      (
+        "table",
+        "page",
+        "table",
+        json!({ "rowsLen": 2, "colsLen": 2 }),
+        None,
+      ),
+      (
+        "c00",
+        "table",
+        "table/cell",
+        json!({ "rowPosition": 0, "colPosition": 0 }),
+        None,
+      ),
+      ("p00", "c00", "paragraph", json!({}), text("Name")),
+      (
+        "c01",
+        "table",
+        "table/cell",
+        json!({ "rowPosition": 0, "colPosition": 1 }),
+        None,
+      ),
+      ("p01", "c01", "paragraph", json!({}), text("Price")),
+      (
+        "c10",
+        "table",
+        "table/cell",
+        json!({ "rowPosition": 1, "colPosition": 0 }),
+        None,
+      ),
+      ("p10", "c10", "paragraph", json!({}), text("a|b")),
+    ]);
+    assert_eq!(
+      document_to_markdown(&data, &TestLinks),
+      "| Name | Price |\n| --- | --- |\n| a\\|b |  |\n"
+    );
+  }
+
+  #[test]
+  fn html_test() {
+    let data = document(vec![
+      (
+        "h",
+        "page",
+        "heading",
+        json!({ "level": 1 }),
+        text("<Title>"),
+      ),
+      ("b1", "page", "bulleted_list", json!({}), text("one")),
+      ("b2", "page", "bulleted_list", json!({}), text("two")),
+      (
+        "p",
+        "page",
+        "paragraph",
+        json!({}),
+        Some(json!([{ "insert": "code", "attributes": { "code": true } }])),
+      ),
+    ]);
+    assert_eq!(
+      document_to_html(&data, &NoPageLinks),
+      "<h1>&lt;Title&gt;</h1>\n<ul>\n<li>one</li>\n<li>two</li>\n</ul>\n<p><code>code</code></p>"
+    );
+  }
+
+  #[test]
+  fn html_unsafe_links_test() {
+    let data = document(vec![
+      (
+        "p",
+        "page",
+        "paragraph",
+        json!({}),
+        Some(json!([
+          { "insert": "safe", "attributes": { "href": "https://appflowy.io" } },
+          { "insert": " " },
+          { "insert": "unsafe", "attributes": { "href": " JavaScript:alert(1)" } },
+          { "insert": " " },
+          { "insert": "tab", "attributes": { "href": "java\tscript:alert(1)" } }
+        ])),
+      ),
+      (
+        "i",
+        "page",
+        "image",
+        json!({ "url": "data:text/html,x" }),
+        None,
+      ),
+      (
+        "l",
+        "page",
+        "link_preview",
+        json!({ "url": "vbscript:x" }),
+        None,
+      ),
+      (
+        "f",
+        "page",
+        "file",
+        json!({ "url": "a.pdf", "name": "a" }),
+        None,
+      ),
+    ]);
+    assert_eq!(
+      document_to_html(&data, &NoPageLinks),
+      "<p><a href=\"https://appflowy.io\">safe</a> unsafe tab</p>\n<p>vbscript:x</p>\n<p><a href=\"a.pdf\">a</a></p>"
+    );
+  }
+
+  #[test]
+  fn json_test() {
+    let data = document(vec![
+      ("h", "page", "heading", json!({ "level": 1 }), text("Title")),
+      ("i", "page", "image", json!({ "url": "a.png" }), None),
+      ("s", "page", "sub_page", json!({ "view_id": "child" }), None),
+    ]);
+    assert_eq!(
+      document_to_json(&data, &TestLinks),
+      json!({
+        "type": "page",
+        "data": {},
+        "children": [
+          {
+            "type": "heading",
+            "data": { "level": 1, "delta": [{ "insert": "Title" }] },
+            "children": [],
+          },
+          {
+            "type": "image",
+            "data": { "url": "assets/a.png" },
+            "children": [],
+          },
+          {
+            "type": "sub_page",
+            "data": { "view_id": "child", "href": "child.md" },
+            "children": [],
+          },
+        ],
+      })
+    );
+  }
+
+  #[test]
+  fn document_file_urls_test() {
+    let data = document(vec![
+      ("i1", "page", "image", json!({ "url": "b.png" }), None),
+      ("i2", "page", "image", json!({ "url": "a.png" }), None),
+      (
+        "f",
+        "page",
+        "file",
+        json!({ "url": "b.png", "name": "b" }),
+        None,
+      ),
+      ("p", "page", "paragraph", json!({}), text("text")),
+    ]);
+    assert_eq!(document_file_urls(&data), vec!["a.png", "b.png"]);
+  }
+}
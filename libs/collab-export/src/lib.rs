@@ -0,0 +1,5 @@
+//! Converts the collabs of a workspace into plain formats that can be read without AppFlowy:
+//! documents into Markdown or HTML, and databases into CSV.
+
+pub mod database;
+pub mod document;
@@ -1,4 +1,4 @@
-use database_entity::dto::AFWorkspaceMember;
+use database_entity::dto::{AFWorkspaceMember, AccessRequestStatus};
 use serde::{Deserialize, Serialize};
 use std::fmt::{Debug, Display, Formatter};
 use std::hash::Hash;
@@ -7,6 +7,7 @@ use std::hash::Hash;
 pub enum UserMessage {
   ProfileChange(AFUserChange),
   WorkspaceMemberChange(AFWorkspaceMemberChange),
+  AccessRequestChange(AFAccessRequestChange),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Hash, Eq, PartialEq)]
@@ -24,6 +25,17 @@ pub struct AFWorkspaceMemberChange {
   removed: Vec<AFWorkspaceMember>,
 }
 
+/// Sent to the workspace owner when an access request to one of the workspace's views is created
+/// or its status changes.
+#[derive(Debug, Clone, Serialize, Deserialize, Hash, Eq, PartialEq)]
+pub struct AFAccessRequestChange {
+  pub request_id: String,
+  pub workspace_id: String,
+  pub view_id: String,
+  pub requester_uid: i64,
+  pub status: AccessRequestStatus,
+}
+
 #[derive(Clone, Hash, PartialEq, Eq, Debug)]
 pub struct UserDevice {
   device_id: String,
@@ -2,7 +2,7 @@ use crate::error::EntityError;
 use crate::error::EntityError::{DeserializationError, InvalidData};
 
 use bytes::Bytes;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use collab_entity::proto;
 use collab_entity::CollabType;
 use infra::validate::{validate_not_empty_payload, validate_not_empty_str};
@@ -380,6 +380,56 @@ pub struct UpdatePublishNamespace {
   pub new_namespace: String,
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct SetPublishCustomDomain {
+  pub domain: String,
+}
+
+/// A custom domain serving the published pages of the workspace. The domain is verified once the
+/// TXT record `verification_record_name` contains `verification_record_value`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PublishCustomDomain {
+  pub domain: String,
+  pub verification_record_name: String,
+  pub verification_record_value: String,
+  pub verified_at: Option<DateTime<Utc>>,
+  pub created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct PublishAnalyticsQueryParams {
+  /// Only include the analytics of this published view
+  pub view_id: Option<Uuid>,
+  /// First day included, 29 days before `until` by default
+  pub since: Option<NaiveDate>,
+  /// Last day included, today by default
+  pub until: Option<NaiveDate>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PublishViewDailyStats {
+  pub view_id: Uuid,
+  /// Current publish name of the view, if it is still published
+  pub publish_name: Option<String>,
+  pub date: NaiveDate,
+  pub view_count: i64,
+  pub unique_visitor_count: i64,
+  pub duplicate_count: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PublishReferrerStats {
+  /// Host of the referrer
+  pub referrer: String,
+  pub view_count: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PublishAnalytics {
+  pub daily_stats: Vec<PublishViewDailyStats>,
+  pub top_referrers: Vec<PublishReferrerStats>,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct UpdateDefaultPublishView {
   pub view_id: Uuid,
@@ -441,6 +491,12 @@ pub struct PublishInfo {
   pub comments_enabled: bool,
   #[serde(default = "default_duplicate_enabled")]
   pub duplicate_enabled: bool,
+  /// A password is required to view the published view
+  #[serde(default)]
+  pub password_protected: bool,
+  /// Only the members of the workspace can view the published view
+  #[serde(default)]
+  pub members_only: bool,
 }
 
 fn default_comments_enabled() -> bool {
@@ -663,6 +719,15 @@ pub struct AFWorkspaceSettings {
 
   #[serde(default)]
   pub ai_model: String,
+
+  #[serde(default)]
+  pub sso: Option<AFWorkspaceSsoSettings>,
+
+  /// Set when the workspace is created, and can't be changed afterwards. The clients encrypt the
+  /// updates of the collabs before sending them, so the features of the server that need their
+  /// content, like search indexing, publishing and AI, are disabled.
+  #[serde(default)]
+  pub end_to_end_encryption: bool,
 }
 
 impl Default for AFWorkspaceSettings {
@@ -670,16 +735,38 @@ impl Default for AFWorkspaceSettings {
     Self {
       disable_search_indexing: false,
       ai_model: "".to_string(),
+      sso: None,
+      end_to_end_encryption: false,
     }
   }
 }
 
+/// Single sign-on settings of a workspace. `provider_id` is the id of an SSO provider registered
+/// in GoTrue.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AFWorkspaceSsoSettings {
+  pub provider_id: String,
+  /// When enabled, members other than the owner can only sign in through the SSO provider.
+  #[serde(default)]
+  pub enforce_sso: bool,
+  /// Users with an email in one of these domains who sign in through the SSO provider join the
+  /// workspace automatically. Each domain must be one of the domains registered for the provider.
+  #[serde(default)]
+  pub auto_join_domains: Vec<String>,
+  /// The role given to users who join through `auto_join_domains`.
+  pub default_role: AFRole,
+}
+
 #[derive(Default, Serialize, Deserialize, Debug)]
 pub struct AFWorkspaceSettingsChange {
   #[serde(skip_serializing_if = "Option::is_none")]
   pub disable_search_indexing: Option<bool>,
   #[serde(skip_serializing_if = "Option::is_none")]
   pub ai_model: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub sso: Option<AFWorkspaceSsoSettings>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub remove_sso: Option<bool>,
 }
 
 impl AFWorkspaceSettingsChange {
@@ -687,6 +774,8 @@ impl AFWorkspaceSettingsChange {
     Self {
       disable_search_indexing: None,
       ai_model: None,
+      sso: None,
+      remove_sso: None,
     }
   }
   pub fn disable_search_indexing(mut self, disable_search_indexing: bool) -> Self {
@@ -697,6 +786,17 @@ impl AFWorkspaceSettingsChange {
     self.ai_model = Some(ai_model);
     self
   }
+  pub fn sso(mut self, sso: AFWorkspaceSsoSettings) -> Self {
+    self.sso = Some(sso);
+    self
+  }
+  pub fn remove_sso(mut self) -> Self {
+    self.remove_sso = Some(true);
+    self
+  }
+  pub fn changes_sso(&self) -> bool {
+    self.sso.is_some() || self.remove_sso.unwrap_or(false)
+  }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -823,6 +923,12 @@ pub struct PatchPublishedCollab {
   pub publish_name: Option<String>,
   pub comments_enabled: Option<bool>,
   pub duplicate_enabled: Option<bool>,
+  /// Password required to view the published view. An empty password removes the password
+  /// protection.
+  #[serde(default)]
+  pub password: Option<String>,
+  #[serde(default)]
+  pub members_only: Option<bool>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -1116,12 +1222,24 @@ pub struct AvatarImageSource {
   pub file_id: String,
 }
 
-#[derive(Serialize_repr, Deserialize_repr, PartialEq, Debug, Copy, Clone)]
+#[derive(Serialize_repr, Deserialize_repr, PartialEq, Eq, Hash, Debug, Copy, Clone)]
 #[repr(i32)]
 pub enum AccessRequestStatus {
   Pending = 0,
   Approved = 1,
   Rejected = 2,
+  Expired = 3,
+}
+
+impl From<i32> for AccessRequestStatus {
+  fn from(value: i32) -> Self {
+    match value {
+      1 => AccessRequestStatus::Approved,
+      2 => AccessRequestStatus::Rejected,
+      3 => AccessRequestStatus::Expired,
+      _ => AccessRequestStatus::Pending,
+    }
+  }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -1162,11 +1280,121 @@ pub struct ApproveAccessRequestParams {
   pub is_approved: bool,
 }
 
+/// Access requests coming from a user whose email belongs to `email_domain` are approved
+/// automatically, and the requester joins the workspace with the given `role`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AccessRequestAutoApprovalRule {
+  pub email_domain: String,
+  pub role: AFRole,
+}
+
 #[derive(Debug, Clone, Validate, Serialize, Deserialize)]
 pub struct CreateImportTask {
   #[validate(custom(function = "validate_not_empty_str"))]
   pub workspace_name: String,
   pub content_length: u64,
+  #[serde(default)]
+  pub source: ImportSource,
+}
+
+/// The app that exported the zip file of an import task.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportSource {
+  #[default]
+  Notion,
+  /// A zip of Markdown files, e.g. an Obsidian vault
+  Markdown,
+  /// A zip of HTML pages, e.g. a Confluence space export
+  Html,
+}
+
+impl ImportSource {
+  /// The name of the import task variant that processes the zip file in the worker.
+  pub fn task_name(&self) -> &'static str {
+    match self {
+      ImportSource::Notion => "notion",
+      ImportSource::Markdown => "markdown",
+      ImportSource::Html => "html",
+    }
+  }
+}
+
+/// Create a task that imports a zip file into an existing workspace. The imported pages are
+/// nested under a new page named `name`, itself created under `parent_view_id`.
+#[derive(Debug, Clone, Validate, Serialize, Deserialize)]
+pub struct CreateWorkspaceImportTask {
+  /// The space, or any other view, that the imported pages are created under
+  #[validate(custom(function = "validate_not_empty_str"))]
+  pub parent_view_id: String,
+  #[validate(custom(function = "validate_not_empty_str"))]
+  pub name: String,
+  pub content_length: u64,
+  #[serde(default)]
+  pub source: ImportSource,
+}
+
+/// Create a task that imports a CSV or Excel file into a new grid database of an existing
+/// workspace.
+#[derive(Debug, Clone, Validate, Serialize, Deserialize)]
+pub struct CreateDatabaseImportTask {
+  /// The space, or any other view, that the grid is created under
+  #[validate(custom(function = "validate_not_empty_str"))]
+  pub parent_view_id: String,
+  #[validate(custom(function = "validate_not_empty_str"))]
+  pub name: String,
+  pub content_length: u64,
+  pub format: SpreadsheetFormat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpreadsheetFormat {
+  Csv,
+  /// Only the first worksheet of the workbook is imported
+  Xlsx,
+}
+
+impl SpreadsheetFormat {
+  /// The name of the import task variant that processes the file in the worker.
+  pub fn task_name(&self) -> &'static str {
+    match self {
+      SpreadsheetFormat::Csv => "csv",
+      SpreadsheetFormat::Xlsx => "xlsx",
+    }
+  }
+}
+
+/// A row of an imported spreadsheet that couldn't be imported as is.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportRowError {
+  /// The 1-based number of the row in the file, the header being the first row
+  pub row: u64,
+  pub message: String,
+}
+
+/// The step of an import task that the worker is running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportPhase {
+  Download = 0,
+  Unzip = 1,
+  /// Creating the documents and databases, the progress counts the pages
+  CreateCollab = 2,
+  /// Uploading the files embedded in the pages, the progress counts the files
+  Upload = 3,
+}
+
+impl ImportPhase {
+  pub fn from_i16(value: i16) -> Option<Self> {
+    match value {
+      0 => Some(ImportPhase::Download),
+      1 => Some(ImportPhase::Unzip),
+      2 => Some(ImportPhase::CreateCollab),
+      3 => Some(ImportPhase::Upload),
+      _ => None,
+    }
+  }
 }
 
 /// Create a import task
@@ -1215,6 +1443,166 @@ pub struct ListQuickNotesQueryParams {
   pub limit: Option<i32>,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+  InviteMember,
+  UpdateMemberRole,
+  RemoveMember,
+  PublishView,
+  UnpublishView,
+  DeleteViewFromTrash,
+  DeleteAllViewsFromTrash,
+  ApproveAccessRequest,
+  RejectAccessRequest,
+  DownloadBlob,
+}
+
+impl AuditAction {
+  pub fn as_str(&self) -> &str {
+    match self {
+      AuditAction::InviteMember => "invite_member",
+      AuditAction::UpdateMemberRole => "update_member_role",
+      AuditAction::RemoveMember => "remove_member",
+      AuditAction::PublishView => "publish_view",
+      AuditAction::UnpublishView => "unpublish_view",
+      AuditAction::DeleteViewFromTrash => "delete_view_from_trash",
+      AuditAction::DeleteAllViewsFromTrash => "delete_all_views_from_trash",
+      AuditAction::ApproveAccessRequest => "approve_access_request",
+      AuditAction::RejectAccessRequest => "reject_access_request",
+      AuditAction::DownloadBlob => "download_blob",
+    }
+  }
+}
+
+impl Display for AuditAction {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.write_str(self.as_str())
+  }
+}
+
+impl FromStr for AuditAction {
+  type Err = EntityError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "invite_member" => Ok(AuditAction::InviteMember),
+      "update_member_role" => Ok(AuditAction::UpdateMemberRole),
+      "remove_member" => Ok(AuditAction::RemoveMember),
+      "publish_view" => Ok(AuditAction::PublishView),
+      "unpublish_view" => Ok(AuditAction::UnpublishView),
+      "delete_view_from_trash" => Ok(AuditAction::DeleteViewFromTrash),
+      "delete_all_views_from_trash" => Ok(AuditAction::DeleteAllViewsFromTrash),
+      "approve_access_request" => Ok(AuditAction::ApproveAccessRequest),
+      "reject_access_request" => Ok(AuditAction::RejectAccessRequest),
+      "download_blob" => Ok(AuditAction::DownloadBlob),
+      _ => Err(InvalidData(format!("Invalid audit action: {}", s))),
+    }
+  }
+}
+
+/// A single audit log entry. `actor_uid` is `None` when the action was performed by the server
+/// itself, for example when an access request is approved by an auto approval rule.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AuditLog {
+  pub id: i64,
+  pub workspace_id: Uuid,
+  pub actor_uid: Option<i64>,
+  pub actor_email: Option<String>,
+  pub action: AuditAction,
+  pub object_id: Option<String>,
+  pub metadata: serde_json::Value,
+  pub created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AuditLogs {
+  pub audit_logs: Vec<AuditLog>,
+  pub has_more: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct ListAuditLogsQueryParams {
+  pub action: Option<AuditAction>,
+  pub actor_uid: Option<i64>,
+  pub object_id: Option<String>,
+  pub since: Option<DateTime<Utc>>,
+  pub until: Option<DateTime<Utc>>,
+  pub offset: Option<i32>,
+  pub limit: Option<i32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyObjectType {
+  Workspace,
+  Collab,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ExplainPolicyDecisionQueryParams {
+  pub uid: i64,
+  pub object_type: PolicyObjectType,
+  pub object_id: String,
+  /// The requested action, in the same form as it is passed to the enforcer:
+  /// `read`, `write`, `delete`, a role (`r:1`, `r:2`, `r:3`) or an access level
+  /// (`l:10`, `l:20`, `l:30`, `l:50`).
+  pub action: String,
+}
+
+/// Describes how the access control enforcer reached its decision for a single request.
+/// Policies are represented as `[subject, object, role/level]`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PolicyDecisionExplanation {
+  pub allowed: bool,
+  /// The request as it was evaluated: `[subject, object, action]`.
+  pub request: Vec<String>,
+  /// All the policies stored for the subject on the requested object.
+  pub subject_policies: Vec<Vec<String>>,
+  /// The subset of `subject_policies` that grants the requested action.
+  pub matching_policies: Vec<Vec<String>>,
+  /// The highest role the subject holds on the object, if any.
+  pub role: Option<AFRole>,
+  /// The highest access level the subject holds on the object, either granted directly or
+  /// derived from its role.
+  pub access_level: Option<AFAccessLevel>,
+  /// Present only when the request was denied.
+  pub denial_reason: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CreatePersonalAccessTokenParams {
+  pub name: String,
+  /// Restrict the token to requests that don't modify any data.
+  #[serde(default)]
+  pub read_only: bool,
+  /// Restrict the token to a single workspace.
+  #[serde(default)]
+  pub workspace_id: Option<Uuid>,
+  /// Number of days before the token expires. Defaults to 90 days, and can't be more than 365 days.
+  #[serde(default)]
+  pub expires_in_days: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PersonalAccessToken {
+  pub token_id: Uuid,
+  pub name: String,
+  pub read_only: bool,
+  pub workspace_id: Option<Uuid>,
+  pub created_at: DateTime<Utc>,
+  pub expires_at: DateTime<Utc>,
+  pub revoked_at: Option<DateTime<Utc>>,
+}
+
+/// Returned once when the token is created. The token itself is not stored, so it can't be
+/// retrieved later.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CreatedPersonalAccessToken {
+  pub personal_access_token: PersonalAccessToken,
+  pub token: String,
+}
+
 #[cfg(test)]
 mod test {
   use crate::dto::{CollabParams, CollabParamsV0};
@@ -85,3 +85,22 @@ pub struct CompleteUploadResponse {
   pub upload_id: String,
   pub parts: Vec<CompletedPartRequest>,
 }
+
+/// A part of a multipart upload that was received by the server.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UploadedPart {
+  pub part_number: i32,
+  pub e_tag: String,
+  pub size: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ListUploadPartsResponse {
+  pub file_id: String,
+  pub upload_id: String,
+  /// The parts received so far, ordered by part number.
+  pub parts: Vec<UploadedPart>,
+  /// The upload is aborted if no part is received before this time, as a Unix timestamp in
+  /// seconds.
+  pub expires_at: i64,
+}
@@ -1,9 +1,10 @@
 use crate::pg_row::{
-  AFAccessRequestStatusColumn, AFAccessRequestWithViewIdColumn, AFAccessRequesterColumn,
-  AFWorkspaceWithMemberCountRow,
+  AFAccessRequestAutoApprovalRuleRow, AFAccessRequestStatusColumn, AFAccessRequestWithViewIdColumn,
+  AFAccessRequesterColumn, AFWorkspaceWithMemberCountRow,
 };
 use app_error::AppError;
-use database_entity::dto::AccessRequestWithViewId;
+use chrono::{DateTime, Utc};
+use database_entity::dto::{AFRole, AccessRequestAutoApprovalRule, AccessRequestWithViewId};
 use sqlx::{Executor, Postgres};
 use uuid::Uuid;
 
@@ -138,3 +139,120 @@ pub async fn delete_access_request<'a, E: Executor<'a, Database = Postgres>>(
   .await?;
   Ok(())
 }
+
+/// Remove the pending or expired access request of the user for the given view if it was
+/// created before `expire_before`, so that the user is able to request access again.
+pub async fn delete_stale_access_request<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: Uuid,
+  view_id: Uuid,
+  uid: i64,
+  expire_before: DateTime<Utc>,
+) -> Result<(), AppError> {
+  sqlx::query!(
+    r#"
+      DELETE FROM af_access_request
+      WHERE workspace_id = $1
+        AND view_id = $2
+        AND uid = $3
+        AND (status = $4 OR (status = $5 AND created_at < $6))
+    "#,
+    workspace_id,
+    view_id,
+    uid,
+    AFAccessRequestStatusColumn::Expired as _,
+    AFAccessRequestStatusColumn::Pending as _,
+    expire_before,
+  )
+  .execute(executor)
+  .await?;
+  Ok(())
+}
+
+pub async fn select_access_request_auto_approval_rules<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: Uuid,
+) -> Result<Vec<AccessRequestAutoApprovalRule>, AppError> {
+  let rows = sqlx::query_as!(
+    AFAccessRequestAutoApprovalRuleRow,
+    r#"
+      SELECT email_domain, role_id
+      FROM af_access_request_auto_approval_rule
+      WHERE workspace_id = $1
+      ORDER BY created_at
+    "#,
+    workspace_id,
+  )
+  .fetch_all(executor)
+  .await?;
+  Ok(rows.into_iter().map(Into::into).collect())
+}
+
+/// Find the auto approval rule that applies to the given email, if any.
+pub async fn select_access_request_auto_approval_rule_for_email<
+  'a,
+  E: Executor<'a, Database = Postgres>,
+>(
+  executor: E,
+  workspace_id: Uuid,
+  email: &str,
+) -> Result<Option<AccessRequestAutoApprovalRule>, AppError> {
+  let email_domain = match email.rsplit_once('@') {
+    Some((_, domain)) if !domain.is_empty() => domain.to_lowercase(),
+    _ => return Ok(None),
+  };
+  let row = sqlx::query_as!(
+    AFAccessRequestAutoApprovalRuleRow,
+    r#"
+      SELECT email_domain, role_id
+      FROM af_access_request_auto_approval_rule
+      WHERE workspace_id = $1 AND email_domain = $2
+    "#,
+    workspace_id,
+    email_domain,
+  )
+  .fetch_optional(executor)
+  .await?;
+  Ok(row.map(Into::into))
+}
+
+pub async fn upsert_access_request_auto_approval_rule<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: Uuid,
+  email_domain: &str,
+  role: &AFRole,
+) -> Result<(), AppError> {
+  let role_id: i32 = role.into();
+  sqlx::query!(
+    r#"
+      INSERT INTO af_access_request_auto_approval_rule (workspace_id, email_domain, role_id)
+      VALUES ($1, $2, $3)
+      ON CONFLICT (workspace_id, email_domain)
+      DO UPDATE SET role_id = EXCLUDED.role_id
+    "#,
+    workspace_id,
+    email_domain.to_lowercase(),
+    role_id,
+  )
+  .execute(executor)
+  .await?;
+  Ok(())
+}
+
+pub async fn delete_access_request_auto_approval_rule<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: Uuid,
+  email_domain: &str,
+) -> Result<(), AppError> {
+  sqlx::query!(
+    r#"
+      DELETE FROM af_access_request_auto_approval_rule
+      WHERE workspace_id = $1 AND email_domain = $2
+    "#,
+    workspace_id,
+    email_domain.to_lowercase(),
+  )
+  .execute(executor)
+  .await?;
+  Ok(())
+}
@@ -0,0 +1,102 @@
+use app_error::AppError;
+use chrono::{DateTime, Utc};
+use database_entity::dto::{AuditAction, AuditLog};
+use sqlx::{Executor, Postgres, QueryBuilder};
+use uuid::Uuid;
+
+use crate::pg_row::AFAuditLogRow;
+
+pub async fn insert_audit_log<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  actor_uid: Option<i64>,
+  action: &AuditAction,
+  object_id: Option<&str>,
+  metadata: &serde_json::Value,
+) -> Result<(), AppError> {
+  sqlx::query!(
+    r#"
+      INSERT INTO af_audit_log (workspace_id, actor_uid, action, object_id, metadata)
+      VALUES ($1, $2, $3, $4, $5)
+    "#,
+    workspace_id,
+    actor_uid,
+    action.as_str(),
+    object_id,
+    metadata,
+  )
+  .execute(executor)
+  .await?;
+  Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn select_audit_logs_with_one_more_than_limit<
+  'a,
+  E: Executor<'a, Database = Postgres>,
+>(
+  executor: E,
+  workspace_id: &Uuid,
+  action: Option<&AuditAction>,
+  actor_uid: Option<i64>,
+  object_id: Option<&str>,
+  since: Option<DateTime<Utc>>,
+  until: Option<DateTime<Utc>>,
+  offset: Option<i32>,
+  limit: Option<i32>,
+) -> Result<Vec<AuditLog>, AppError> {
+  let mut query_builder: QueryBuilder<Postgres> = QueryBuilder::new(
+    r#"
+    SELECT
+      al.id,
+      al.workspace_id,
+      al.actor_uid,
+      au.email AS actor_email,
+      al.action,
+      al.object_id,
+      al.metadata,
+      al.created_at
+    FROM af_audit_log al
+    LEFT JOIN af_user au ON au.uid = al.actor_uid
+    WHERE al.workspace_id =
+    "#,
+  );
+  query_builder.push_bind(workspace_id);
+  if let Some(action) = action {
+    query_builder.push(" AND al.action = ");
+    query_builder.push_bind(action.as_str());
+  }
+  if let Some(actor_uid) = actor_uid {
+    query_builder.push(" AND al.actor_uid = ");
+    query_builder.push_bind(actor_uid);
+  }
+  if let Some(object_id) = object_id.filter(|id| !id.is_empty()) {
+    query_builder.push(" AND al.object_id = ");
+    query_builder.push_bind(object_id);
+  }
+  if let Some(since) = since {
+    query_builder.push(" AND al.created_at >= ");
+    query_builder.push_bind(since);
+  }
+  if let Some(until) = until {
+    query_builder.push(" AND al.created_at < ");
+    query_builder.push_bind(until);
+  }
+  query_builder.push(" ORDER BY al.created_at DESC, al.id DESC");
+  if let Some(limit) = limit {
+    query_builder.push(" LIMIT ");
+    query_builder.push_bind(limit);
+    query_builder.push(" + 1 ");
+  }
+  if let Some(offset) = offset {
+    query_builder.push(" OFFSET ");
+    query_builder.push_bind(offset);
+  }
+  let query = query_builder.build_query_as::<AFAuditLogRow>();
+  query
+    .fetch_all(executor)
+    .await?
+    .into_iter()
+    .map(AuditLog::try_from)
+    .collect()
+}
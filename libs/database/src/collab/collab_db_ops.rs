@@ -7,10 +7,13 @@ use database_entity::dto::{
 use shared_entity::dto::workspace_dto::{DatabaseRowUpdatedItem, EmbeddedCollabQuery};
 
 use crate::collab::{partition_key_from_collab_type, SNAPSHOT_PER_HOUR};
+use crate::encryption::is_encrypted_collab_blob;
 use crate::pg_row::AFCollabRowMeta;
 use crate::pg_row::AFSnapshotRow;
 use app_error::AppError;
 use chrono::{DateTime, Duration, Utc};
+use futures_util::stream::BoxStream;
+use futures_util::{StreamExt, TryStreamExt};
 
 use sqlx::postgres::PgRow;
 use sqlx::{Error, Executor, PgPool, Postgres, Row, Transaction};
@@ -50,7 +53,7 @@ pub async fn insert_into_af_collab(
   workspace_id: &str,
   params: &CollabParams,
 ) -> Result<(), AppError> {
-  let encrypt = 0;
+  let encrypt = is_encrypted_collab_blob(&params.encoded_collab_v1) as i32;
   let partition_key = crate::collab::partition_key_from_collab_type(&params.collab_type);
   let workspace_id = Uuid::from_str(workspace_id)?;
   tracing::trace!(
@@ -150,7 +153,6 @@ pub async fn insert_into_af_collab_bulk_for_user(
     return Ok(());
   }
 
-  let encrypt = 0;
   let workspace_uuid = Uuid::from_str(workspace_id)?;
 
   // Insert values into `af_collab` tables in bulk
@@ -159,6 +161,7 @@ pub async fn insert_into_af_collab_bulk_for_user(
   let mut blobs: Vec<Vec<u8>> = Vec::with_capacity(len);
   let mut lengths: Vec<i32> = Vec::with_capacity(len);
   let mut partition_keys: Vec<i32> = Vec::with_capacity(len);
+  let mut encrypts: Vec<i32> = Vec::with_capacity(len);
   let mut visited = HashSet::with_capacity(collab_params_list.len());
   for params in collab_params_list {
     let oid = Uuid::from_str(&params.object_id)?;
@@ -168,6 +171,7 @@ pub async fn insert_into_af_collab_bulk_for_user(
       blobs.push(params.encoded_collab_v1.to_vec());
       lengths.push(params.encoded_collab_v1.len() as i32);
       partition_keys.push(partition_key);
+      encrypts.push(is_encrypted_collab_blob(&params.encoded_collab_v1) as i32);
     }
   }
 
@@ -185,7 +189,7 @@ pub async fn insert_into_af_collab_bulk_for_user(
       &blobs,
       &lengths,
       &partition_keys,
-      &vec![encrypt; object_ids.len()],
+      &encrypts,
       &uids,
       &workspace_ids
     )
@@ -816,3 +820,68 @@ where
   }
   Ok(RepeatedAFCollabEmbedInfo(items))
 }
+
+/// Returns the object ids of the collabs of the given type in the workspace.
+pub async fn select_collab_oids_of_workspace(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  collab_type: &CollabType,
+) -> Result<Vec<String>, sqlx::Error> {
+  let partition_key = partition_key_from_collab_type(collab_type);
+  sqlx::query_scalar!(
+    r#"
+      SELECT oid FROM af_collab
+      WHERE workspace_id = $1 AND partition_key = $2 AND deleted_at IS NULL
+    "#,
+    workspace_id,
+    partition_key,
+  )
+  .fetch_all(pg_pool)
+  .await
+}
+
+/// Marks the given collabs of the workspace as deleted, e.g. the collabs created by an import that
+/// is rolled back.
+pub async fn soft_delete_collabs_of_workspace(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  object_ids: &[String],
+) -> Result<u64, sqlx::Error> {
+  let result = sqlx::query!(
+    r#"
+      UPDATE af_collab
+      SET deleted_at = NOW()
+      WHERE workspace_id = $1 AND oid = ANY($2) AND deleted_at IS NULL
+    "#,
+    workspace_id,
+    object_ids,
+  )
+  .execute(pg_pool)
+  .await?;
+  Ok(result.rows_affected())
+}
+
+/// Streams the `(object_id, blob)` of the collabs in the workspace. Documents are only returned if
+/// their object id is in `document_ids`. The blob is empty for the collabs that are stored in the
+/// bucket.
+pub fn stream_workspace_collab_blobs<'a>(
+  pg_pool: &'a PgPool,
+  workspace_id: &Uuid,
+  document_ids: Vec<String>,
+) -> BoxStream<'a, sqlx::Result<(String, Vec<u8>)>> {
+  let document_partition_key = partition_key_from_collab_type(&CollabType::Document);
+  sqlx::query!(
+    r#"
+      SELECT oid, blob FROM af_collab
+      WHERE workspace_id = $1
+        AND deleted_at IS NULL
+        AND (partition_key <> $2 OR oid = ANY($3))
+    "#,
+    workspace_id,
+    document_partition_key,
+    &document_ids,
+  )
+  .fetch(pg_pool)
+  .map_ok(|row| (row.oid, row.blob))
+  .boxed()
+}
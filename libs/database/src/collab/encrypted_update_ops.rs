@@ -0,0 +1,95 @@
+use crate::pg_row::AFCollabEncryptedUpdateRow;
+use app_error::AppError;
+use sqlx::{Executor, Postgres, Transaction};
+use std::ops::DerefMut;
+use tracing::instrument;
+use uuid::Uuid;
+
+/// Appends an update of a collab of an end-to-end encrypted workspace, as sent by the client.
+/// Returns the sequence number of the update.
+#[instrument(level = "trace", skip(executor, data), err)]
+pub async fn insert_encrypted_collab_update<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  oid: &str,
+  data: &[u8],
+) -> Result<i64, AppError> {
+  let seq = sqlx::query_scalar!(
+    r#"
+      INSERT INTO af_collab_encrypted_update (workspace_id, oid, data)
+      VALUES ($1, $2, $3)
+      RETURNING seq
+    "#,
+    workspace_id,
+    oid,
+    data,
+  )
+  .fetch_one(executor)
+  .await?;
+  Ok(seq)
+}
+
+/// Returns the updates of a collab of an end-to-end encrypted workspace that haven't been replaced
+/// by a compaction, in the order they were received.
+#[instrument(level = "trace", skip(executor), err)]
+pub async fn select_encrypted_collab_updates<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  oid: &str,
+) -> Result<Vec<AFCollabEncryptedUpdateRow>, AppError> {
+  let updates = sqlx::query_as!(
+    AFCollabEncryptedUpdateRow,
+    r#"
+      SELECT seq, data
+      FROM af_collab_encrypted_update
+      WHERE workspace_id = $1 AND oid = $2 AND replaced_by_seq IS NULL
+      ORDER BY seq
+    "#,
+    workspace_id,
+    oid,
+  )
+  .fetch_all(executor)
+  .await?;
+  Ok(updates)
+}
+
+/// Replaces the updates of a collab of an end-to-end encrypted workspace, up to and including
+/// `up_to_seq`, with a single update that contains all of them, as merged by a client.
+///
+/// The replaced updates are kept until the next compaction, which deletes them: the server can't
+/// verify the merged update, so the previous updates remain available if it turns out to be
+/// broken.
+#[instrument(level = "trace", skip(txn, data), err)]
+pub async fn compact_encrypted_collab_updates(
+  txn: &mut Transaction<'_, Postgres>,
+  workspace_id: &Uuid,
+  oid: &str,
+  up_to_seq: i64,
+  data: &[u8],
+) -> Result<(), AppError> {
+  sqlx::query!(
+    r#"
+      DELETE FROM af_collab_encrypted_update
+      WHERE workspace_id = $1 AND oid = $2 AND replaced_by_seq IS NOT NULL
+    "#,
+    workspace_id,
+    oid,
+  )
+  .execute(txn.deref_mut())
+  .await?;
+  let seq = insert_encrypted_collab_update(txn.deref_mut(), workspace_id, oid, data).await?;
+  sqlx::query!(
+    r#"
+      UPDATE af_collab_encrypted_update
+      SET replaced_by_seq = $4
+      WHERE workspace_id = $1 AND oid = $2 AND seq <= $3 AND replaced_by_seq IS NULL
+    "#,
+    workspace_id,
+    oid,
+    up_to_seq,
+    seq,
+  )
+  .execute(txn.deref_mut())
+  .await?;
+  Ok(())
+}
@@ -1,9 +1,11 @@
 mod collab_db_ops;
 mod collab_storage;
+mod encrypted_update_ops;
 
 pub use collab_db_ops::*;
 use collab_entity::CollabType;
 pub use collab_storage::*;
+pub use encrypted_update_ops::*;
 
 pub(crate) fn partition_key_from_collab_type(collab_type: &CollabType) -> i32 {
   match collab_type {
@@ -0,0 +1,340 @@
+use crate::pg_row::AFWorkspaceDataKeyRow;
+use anyhow::anyhow;
+use app_error::AppError;
+use bytes::Bytes;
+use encrypt::envelope::{
+  envelope_key_version, generate_data_key, EnvelopeEncryptor, MasterKeyring,
+};
+use encrypt::DataEncryptor;
+use sqlx::{Executor, PgPool, Postgres};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::{info, instrument, warn};
+use uuid::Uuid;
+
+/// Encrypts the collabs of the workspaces at rest, using envelope encryption: each workspace has
+/// its own data keys, which are stored in `af_workspace_data_key` wrapped by the current master
+/// key of the server.
+///
+/// Both kinds of keys can be rotated without re-encrypting the existing data:
+/// * [CollabEncryption::rotate_data_key] adds a new data key version to a workspace. New data is
+///   encrypted with it, while the data encrypted with the previous versions can still be decrypted.
+/// * The master key is rotated by prepending the new key to the configured master keys, and then
+///   calling [CollabEncryption::rewrap_data_keys] to wrap the data keys with it. The previous master
+///   key can be removed from the configuration once all the data keys are wrapped again.
+#[derive(Clone)]
+pub struct CollabEncryption {
+  pg_pool: PgPool,
+  keyring: Arc<MasterKeyring>,
+  data_keys: Arc<RwLock<HashMap<Uuid, WorkspaceDataKeys>>>,
+}
+
+#[derive(Clone, Default)]
+struct WorkspaceDataKeys {
+  current: Option<Arc<EnvelopeEncryptor>>,
+  by_version: HashMap<i32, Arc<EnvelopeEncryptor>>,
+}
+
+impl CollabEncryption {
+  /// Returns `None` if no master key is configured, in which case the collabs are stored in
+  /// plaintext. See [MasterKeyring::parse] for the format of `master_keys`.
+  pub fn new(pg_pool: PgPool, master_keys: &str) -> Result<Option<Self>, AppError> {
+    let keyring = MasterKeyring::parse(master_keys).map_err(AppError::Internal)?;
+    Ok(keyring.map(|keyring| Self {
+      pg_pool,
+      keyring: Arc::new(keyring),
+      data_keys: Default::default(),
+    }))
+  }
+
+  /// Encrypts the data with the current data key of the workspace, which is generated on first use.
+  pub async fn encrypt(&self, workspace_id: &str, data: Bytes) -> Result<Bytes, AppError> {
+    let workspace_id = Uuid::from_str(workspace_id)?;
+    let encryptor = self.current_data_key(&workspace_id).await?;
+    encryptor.encrypt(data).map_err(AppError::Internal)
+  }
+
+  /// Decrypts data encrypted by [CollabEncryption::encrypt]. Data that isn't encrypted, e.g. data
+  /// written before the encryption was enabled, is returned as is.
+  pub async fn decrypt(&self, workspace_id: &str, data: Bytes) -> Result<Bytes, AppError> {
+    let key_version = match envelope_key_version(&data) {
+      Some(key_version) => key_version,
+      None => return Ok(data),
+    };
+    let workspace_id = Uuid::from_str(workspace_id)?;
+    let encryptor = self.data_key(&workspace_id, key_version).await?;
+    encryptor.decrypt(data).map_err(AppError::Internal)
+  }
+
+  /// Adds a new data key to the workspace, and returns its version. The data written from now on
+  /// is encrypted with the new key. Other servers keep using their cached key until they reload the
+  /// keys of the workspace, which is harmless as the previous versions are never deleted.
+  #[instrument(level = "info", skip(self), err)]
+  pub async fn rotate_data_key(&self, workspace_id: &Uuid) -> Result<i32, AppError> {
+    let master_key = self.keyring.current();
+    let wrapped_key = master_key
+      .wrap_data_key(&generate_data_key())
+      .map_err(AppError::Internal)?;
+    let key_version =
+      insert_next_workspace_data_key(&self.pg_pool, workspace_id, &master_key.id, &wrapped_key)
+        .await?;
+    self.data_keys.write().await.remove(workspace_id);
+    Ok(key_version)
+  }
+
+  /// Wraps the data keys that are wrapped by a previous master key with the current master key.
+  /// Returns the number of data keys that were wrapped again.
+  pub async fn rewrap_data_keys(&self) -> Result<usize, AppError> {
+    let current = self.keyring.current();
+    let rows = select_data_keys_not_wrapped_by(&self.pg_pool, &current.id).await?;
+    let mut count = 0;
+    for row in rows {
+      let master_key = match self.keyring.get(&row.master_key_id) {
+        Some(master_key) => master_key,
+        None => {
+          warn!(
+            "Can't wrap data key {} of workspace {} again: master key {} is not configured",
+            row.key_version, row.workspace_id, row.master_key_id
+          );
+          continue;
+        },
+      };
+      let data_key = master_key
+        .unwrap_data_key(&row.wrapped_key)
+        .map_err(AppError::Internal)?;
+      let wrapped_key = current
+        .wrap_data_key(&data_key)
+        .map_err(AppError::Internal)?;
+      if update_wrapped_data_key(&self.pg_pool, &row, &current.id, &wrapped_key).await? {
+        count += 1;
+      }
+    }
+    if count > 0 {
+      info!(
+        "Wrapped {} data keys with the master key {}",
+        count, current.id
+      );
+    }
+    Ok(count)
+  }
+
+  async fn current_data_key(
+    &self,
+    workspace_id: &Uuid,
+  ) -> Result<Arc<EnvelopeEncryptor>, AppError> {
+    if let Some(current) = self
+      .data_keys
+      .read()
+      .await
+      .get(workspace_id)
+      .and_then(|keys| keys.current.clone())
+    {
+      return Ok(current);
+    }
+
+    if let Some(current) = self.load_data_keys(workspace_id).await?.current {
+      return Ok(current);
+    }
+
+    // The workspace doesn't have a data key yet. If another server creates it concurrently, the
+    // key inserted first is used by both.
+    let master_key = self.keyring.current();
+    let wrapped_key = master_key
+      .wrap_data_key(&generate_data_key())
+      .map_err(AppError::Internal)?;
+    insert_first_workspace_data_key(&self.pg_pool, workspace_id, &master_key.id, &wrapped_key)
+      .await?;
+    self
+      .load_data_keys(workspace_id)
+      .await?
+      .current
+      .ok_or_else(|| {
+        AppError::Internal(anyhow!(
+          "Failed to create the data key of workspace {}",
+          workspace_id
+        ))
+      })
+  }
+
+  async fn data_key(
+    &self,
+    workspace_id: &Uuid,
+    key_version: i32,
+  ) -> Result<Arc<EnvelopeEncryptor>, AppError> {
+    if let Some(encryptor) = self
+      .data_keys
+      .read()
+      .await
+      .get(workspace_id)
+      .and_then(|keys| keys.by_version.get(&key_version).cloned())
+    {
+      return Ok(encryptor);
+    }
+
+    // The key might have been added by another server since the keys were cached
+    self
+      .load_data_keys(workspace_id)
+      .await?
+      .by_version
+      .get(&key_version)
+      .cloned()
+      .ok_or_else(|| {
+        AppError::Internal(anyhow!(
+          "Data key {} of workspace {} not found",
+          key_version,
+          workspace_id
+        ))
+      })
+  }
+
+  async fn load_data_keys(&self, workspace_id: &Uuid) -> Result<WorkspaceDataKeys, AppError> {
+    let rows = select_workspace_data_keys(&self.pg_pool, workspace_id).await?;
+    let mut keys = WorkspaceDataKeys::default();
+    for row in rows {
+      let master_key = self.keyring.get(&row.master_key_id).ok_or_else(|| {
+        AppError::Internal(anyhow!(
+          "Master key {} of data key {} of workspace {} is not configured",
+          row.master_key_id,
+          row.key_version,
+          workspace_id
+        ))
+      })?;
+      let data_key = master_key
+        .unwrap_data_key(&row.wrapped_key)
+        .map_err(AppError::Internal)?;
+      let encryptor = Arc::new(EnvelopeEncryptor::new(row.key_version, data_key));
+      // The rows are ordered by version, so the last key is the current one
+      keys.current = Some(encryptor.clone());
+      keys.by_version.insert(row.key_version, encryptor);
+    }
+    if keys.current.is_some() {
+      self
+        .data_keys
+        .write()
+        .await
+        .insert(*workspace_id, keys.clone());
+    }
+    Ok(keys)
+  }
+}
+
+/// Returns whether the blob of a collab is encrypted by [CollabEncryption].
+#[inline]
+pub fn is_encrypted_collab_blob(blob: &[u8]) -> bool {
+  envelope_key_version(blob).is_some()
+}
+
+#[instrument(level = "trace", skip_all, err)]
+pub async fn select_workspace_data_keys<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+) -> Result<Vec<AFWorkspaceDataKeyRow>, AppError> {
+  let rows = sqlx::query_as!(
+    AFWorkspaceDataKeyRow,
+    r#"
+      SELECT workspace_id, key_version, master_key_id, wrapped_key
+      FROM af_workspace_data_key
+      WHERE workspace_id = $1
+      ORDER BY key_version
+    "#,
+    workspace_id,
+  )
+  .fetch_all(executor)
+  .await?;
+  Ok(rows)
+}
+
+/// Inserts the first data key of the workspace, unless the workspace already has one.
+#[instrument(level = "trace", skip_all, err)]
+pub async fn insert_first_workspace_data_key<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  master_key_id: &str,
+  wrapped_key: &[u8],
+) -> Result<(), AppError> {
+  sqlx::query!(
+    r#"
+      INSERT INTO af_workspace_data_key (workspace_id, key_version, master_key_id, wrapped_key)
+      VALUES ($1, 1, $2, $3)
+      ON CONFLICT (workspace_id, key_version) DO NOTHING
+    "#,
+    workspace_id,
+    master_key_id,
+    wrapped_key,
+  )
+  .execute(executor)
+  .await?;
+  Ok(())
+}
+
+/// Inserts a data key with the next version of the workspace, and returns the version.
+#[instrument(level = "trace", skip_all, err)]
+pub async fn insert_next_workspace_data_key<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  master_key_id: &str,
+  wrapped_key: &[u8],
+) -> Result<i32, AppError> {
+  let key_version = sqlx::query_scalar!(
+    r#"
+      INSERT INTO af_workspace_data_key (workspace_id, key_version, master_key_id, wrapped_key)
+      SELECT $1, COALESCE(MAX(key_version), 0) + 1, $2, $3
+      FROM af_workspace_data_key
+      WHERE workspace_id = $1
+      RETURNING key_version
+    "#,
+    workspace_id,
+    master_key_id,
+    wrapped_key,
+  )
+  .fetch_one(executor)
+  .await?;
+  Ok(key_version)
+}
+
+#[instrument(level = "trace", skip_all, err)]
+pub async fn select_data_keys_not_wrapped_by<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  master_key_id: &str,
+) -> Result<Vec<AFWorkspaceDataKeyRow>, AppError> {
+  let rows = sqlx::query_as!(
+    AFWorkspaceDataKeyRow,
+    r#"
+      SELECT workspace_id, key_version, master_key_id, wrapped_key
+      FROM af_workspace_data_key
+      WHERE master_key_id <> $1
+    "#,
+    master_key_id,
+  )
+  .fetch_all(executor)
+  .await?;
+  Ok(rows)
+}
+
+/// Replaces the wrapped data key, unless it was already wrapped again by another server. Returns
+/// whether the key was replaced.
+#[instrument(level = "trace", skip_all, err)]
+pub async fn update_wrapped_data_key<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  row: &AFWorkspaceDataKeyRow,
+  master_key_id: &str,
+  wrapped_key: &[u8],
+) -> Result<bool, AppError> {
+  let result = sqlx::query!(
+    r#"
+      UPDATE af_workspace_data_key
+      SET master_key_id = $4, wrapped_key = $5
+      WHERE workspace_id = $1 AND key_version = $2 AND master_key_id = $3
+    "#,
+    row.workspace_id,
+    row.key_version,
+    row.master_key_id,
+    master_key_id,
+    wrapped_key,
+  )
+  .execute(executor)
+  .await?;
+  Ok(result.rows_affected() > 0)
+}
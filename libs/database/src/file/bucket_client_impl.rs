@@ -0,0 +1,238 @@
+use crate::file::fs_client_impl::{LocalFsBucketClientImpl, LocalFsResponseData};
+use crate::file::s3_client_impl::{AwsS3BucketClientImpl, S3ResponseData};
+use crate::file::{BlobRange, BlobStream, BucketClient, BucketStorage, ResponseBlob};
+use app_error::AppError;
+use async_trait::async_trait;
+use aws_sdk_s3::primitives::ByteStream;
+use database_entity::file_dto::{
+  CompleteUploadRequest, CreateUploadRequest, CreateUploadResponse, UploadPartData,
+  UploadPartResponse, UploadedPart,
+};
+
+pub type BucketStorageImpl = BucketStorage<BucketClientImpl>;
+
+/// The [BucketClient] selected in the configuration of the services.
+#[derive(Clone)]
+pub enum BucketClientImpl {
+  S3(AwsS3BucketClientImpl),
+  LocalFs(LocalFsBucketClientImpl),
+}
+
+impl From<AwsS3BucketClientImpl> for BucketClientImpl {
+  fn from(client: AwsS3BucketClientImpl) -> Self {
+    Self::S3(client)
+  }
+}
+
+impl From<LocalFsBucketClientImpl> for BucketClientImpl {
+  fn from(client: LocalFsBucketClientImpl) -> Self {
+    Self::LocalFs(client)
+  }
+}
+
+impl BucketClientImpl {
+  /// Presigned urls let clients upload directly to S3, so they are not available when blobs are
+  /// stored on the local file system.
+  pub async fn gen_presigned_url(
+    &self,
+    s3_key: &str,
+    content_length: u64,
+    expires_in_secs: u64,
+  ) -> Result<String, AppError> {
+    match self {
+      Self::S3(client) => {
+        client
+          .gen_presigned_url(s3_key, content_length, expires_in_secs)
+          .await
+      },
+      Self::LocalFs(_) => Err(AppError::InvalidRequest(
+        "Presigned urls are only available when blobs are stored in S3".to_string(),
+      )),
+    }
+  }
+
+  pub async fn gen_presigned_download_url(
+    &self,
+    s3_key: &str,
+    expires_in_secs: u64,
+  ) -> Result<String, AppError> {
+    match self {
+      Self::S3(client) => {
+        client
+          .gen_presigned_download_url(s3_key, expires_in_secs)
+          .await
+      },
+      Self::LocalFs(_) => Err(AppError::InvalidRequest(
+        "Presigned urls are only available when blobs are stored in S3".to_string(),
+      )),
+    }
+  }
+}
+
+#[async_trait]
+impl BucketClient for BucketClientImpl {
+  type ResponseData = BucketResponseData;
+
+  async fn put_blob(
+    &self,
+    object_key: &str,
+    content: ByteStream,
+    content_type: Option<&str>,
+  ) -> Result<(), AppError> {
+    match self {
+      Self::S3(client) => client.put_blob(object_key, content, content_type).await,
+      Self::LocalFs(client) => client.put_blob(object_key, content, content_type).await,
+    }
+  }
+
+  async fn put_blob_with_content_type(
+    &self,
+    object_key: &str,
+    stream: ByteStream,
+    content_type: &str,
+  ) -> Result<(), AppError> {
+    match self {
+      Self::S3(client) => {
+        client
+          .put_blob_with_content_type(object_key, stream, content_type)
+          .await
+      },
+      Self::LocalFs(client) => {
+        client
+          .put_blob_with_content_type(object_key, stream, content_type)
+          .await
+      },
+    }
+  }
+
+  async fn delete_blob(&self, object_key: &str) -> Result<Self::ResponseData, AppError> {
+    match self {
+      Self::S3(client) => client.delete_blob(object_key).await.map(Into::into),
+      Self::LocalFs(client) => client.delete_blob(object_key).await.map(Into::into),
+    }
+  }
+
+  async fn delete_blobs(&self, object_keys: Vec<String>) -> Result<(), AppError> {
+    match self {
+      Self::S3(client) => client.delete_blobs(object_keys).await,
+      Self::LocalFs(client) => client.delete_blobs(object_keys).await,
+    }
+  }
+
+  async fn get_blob(&self, object_key: &str) -> Result<Self::ResponseData, AppError> {
+    match self {
+      Self::S3(client) => client.get_blob(object_key).await.map(Into::into),
+      Self::LocalFs(client) => client.get_blob(object_key).await.map(Into::into),
+    }
+  }
+
+  async fn get_blob_stream(
+    &self,
+    object_key: &str,
+    range: Option<BlobRange>,
+  ) -> Result<BlobStream, AppError> {
+    match self {
+      Self::S3(client) => client.get_blob_stream(object_key, range).await,
+      Self::LocalFs(client) => client.get_blob_stream(object_key, range).await,
+    }
+  }
+
+  async fn create_upload(
+    &self,
+    object_key: &str,
+    req: CreateUploadRequest,
+  ) -> Result<CreateUploadResponse, AppError> {
+    match self {
+      Self::S3(client) => client.create_upload(object_key, req).await,
+      Self::LocalFs(client) => client.create_upload(object_key, req).await,
+    }
+  }
+
+  async fn upload_part(
+    &self,
+    object_key: &str,
+    req: UploadPartData,
+  ) -> Result<UploadPartResponse, AppError> {
+    match self {
+      Self::S3(client) => client.upload_part(object_key, req).await,
+      Self::LocalFs(client) => client.upload_part(object_key, req).await,
+    }
+  }
+
+  async fn complete_upload(
+    &self,
+    object_key: &str,
+    req: CompleteUploadRequest,
+  ) -> Result<(usize, String), AppError> {
+    match self {
+      Self::S3(client) => client.complete_upload(object_key, req).await,
+      Self::LocalFs(client) => client.complete_upload(object_key, req).await,
+    }
+  }
+
+  async fn list_upload_parts(
+    &self,
+    object_key: &str,
+    upload_id: &str,
+  ) -> Result<Vec<UploadedPart>, AppError> {
+    match self {
+      Self::S3(client) => client.list_upload_parts(object_key, upload_id).await,
+      Self::LocalFs(client) => client.list_upload_parts(object_key, upload_id).await,
+    }
+  }
+
+  async fn abort_upload(&self, object_key: &str, upload_id: &str) -> Result<(), AppError> {
+    match self {
+      Self::S3(client) => client.abort_upload(object_key, upload_id).await,
+      Self::LocalFs(client) => client.abort_upload(object_key, upload_id).await,
+    }
+  }
+
+  async fn remove_dir(&self, dir: &str) -> Result<(), AppError> {
+    match self {
+      Self::S3(client) => client.remove_dir(dir).await,
+      Self::LocalFs(client) => client.remove_dir(dir).await,
+    }
+  }
+
+  async fn list_dir(&self, dir: &str, limit: usize) -> Result<Vec<String>, AppError> {
+    match self {
+      Self::S3(client) => client.list_dir(dir, limit).await,
+      Self::LocalFs(client) => client.list_dir(dir, limit).await,
+    }
+  }
+}
+
+#[derive(Debug)]
+pub enum BucketResponseData {
+  S3(S3ResponseData),
+  LocalFs(LocalFsResponseData),
+}
+
+impl From<S3ResponseData> for BucketResponseData {
+  fn from(data: S3ResponseData) -> Self {
+    Self::S3(data)
+  }
+}
+
+impl From<LocalFsResponseData> for BucketResponseData {
+  fn from(data: LocalFsResponseData) -> Self {
+    Self::LocalFs(data)
+  }
+}
+
+impl ResponseBlob for BucketResponseData {
+  fn to_blob(self) -> Vec<u8> {
+    match self {
+      Self::S3(data) => data.to_blob(),
+      Self::LocalFs(data) => data.to_blob(),
+    }
+  }
+
+  fn content_type(&self) -> Option<String> {
+    match self {
+      Self::S3(data) => data.content_type(),
+      Self::LocalFs(data) => data.content_type(),
+    }
+  }
+}
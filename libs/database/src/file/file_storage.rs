@@ -1,19 +1,74 @@
 use crate::pg_row::AFBlobMetadataRow;
 use crate::resource_usage::{
-  delete_blob_metadata, get_blob_metadata, insert_blob_metadata, is_blob_metadata_exists,
+  count_blob_content_references, delete_blob_metadata, get_blob_metadata, insert_blob_metadata,
+  insert_blob_metadata_with_content_hash, is_blob_metadata_exists, lock_blob_content,
+  select_blob_content_hash,
 };
+use anyhow::anyhow;
 use app_error::AppError;
 use async_trait::async_trait;
 use aws_sdk_s3::primitives::ByteStream;
 use database_entity::file_dto::{
   CompleteUploadRequest, CreateUploadRequest, CreateUploadResponse, UploadPartData,
-  UploadPartResponse,
+  UploadPartResponse, UploadedPart,
 };
+use sha2::{Digest, Sha256};
 use sqlx::PgPool;
+use std::pin::Pin;
+use tokio::io::AsyncRead;
 
-use tracing::{info, instrument, warn};
+use tracing::{info, instrument, trace, warn};
 use uuid::Uuid;
 
+/// Directory of a workspace holding the content-addressed objects, which are shared by all blobs of
+/// the workspace with the same content. It must not be used as the parent dir of a blob.
+pub const BLOB_CONTENT_DIR: &str = "_content";
+
+pub fn blob_content_object_key(workspace_id: &Uuid, content_hash: &str) -> String {
+  format!("{}/{}/{}", workspace_id, BLOB_CONTENT_DIR, content_hash)
+}
+
+/// Widths, in pixels, of the resized variants that can be generated for an image blob. Requested
+/// widths are rounded up to one of them, so that each image has a bounded number of variants.
+pub const BLOB_IMAGE_VARIANT_WIDTHS: [u32; 5] = [64, 128, 256, 512, 1024];
+
+/// Returns the key of the object holding the resized variant of the stored object. Variants are
+/// stored next to the object they were generated from.
+pub fn blob_image_variant_object_key(object_key: &str, width: u32) -> String {
+  format!("{}.variants/w{}", object_key, width)
+}
+
+/// Returns the keys of all the variants that may have been generated for the stored object.
+pub fn blob_image_variant_object_keys(object_key: &str) -> Vec<String> {
+  BLOB_IMAGE_VARIANT_WIDTHS
+    .iter()
+    .map(|width| blob_image_variant_object_key(object_key, *width))
+    .collect()
+}
+
+/// A byte range of a blob, both ends inclusive like in the `Range` header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlobRange {
+  pub start: u64,
+  pub end: u64,
+}
+
+impl BlobRange {
+  pub fn len(&self) -> u64 {
+    self.end - self.start + 1
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.end < self.start
+  }
+}
+
+/// The content of a blob, read while it is sent instead of being loaded in memory at once.
+pub struct BlobStream {
+  pub reader: Pin<Box<dyn AsyncRead + Send>>,
+  pub content_length: u64,
+}
+
 pub trait ResponseBlob {
   fn to_blob(self) -> Vec<u8>;
   fn content_type(&self) -> Option<String>;
@@ -43,6 +98,13 @@ pub trait BucketClient {
 
   async fn get_blob(&self, object_key: &str) -> Result<Self::ResponseData, AppError>;
 
+  /// Returns a stream of the object, or of the given range of it.
+  async fn get_blob_stream(
+    &self,
+    object_key: &str,
+    range: Option<BlobRange>,
+  ) -> Result<BlobStream, AppError>;
+
   async fn create_upload(
     &self,
     object_key: &str,
@@ -58,6 +120,15 @@ pub trait BucketClient {
     object_key: &str,
     req: CompleteUploadRequest,
   ) -> Result<(usize, String), AppError>;
+  /// Returns the parts received so far by the multipart upload, ordered by part number.
+  async fn list_upload_parts(
+    &self,
+    object_key: &str,
+    upload_id: &str,
+  ) -> Result<Vec<UploadedPart>, AppError>;
+  /// Aborts the multipart upload and deletes the parts received so far. Aborting an upload that
+  /// doesn't exist anymore is not an error.
+  async fn abort_upload(&self, object_key: &str, upload_id: &str) -> Result<(), AppError>;
 
   async fn remove_dir(&self, dir: &str) -> Result<(), AppError>;
 
@@ -90,6 +161,8 @@ where
     Ok(())
   }
 
+  /// Stores the blob in the content-addressed object of the workspace, so that identical files are
+  /// only stored once. The object is uploaded only if no other blob references the same content.
   #[instrument(skip_all, err)]
   #[inline]
   pub async fn put_blob_with_content_type<K: BlobKey>(
@@ -108,26 +181,67 @@ where
       return Ok(());
     }
 
-    self
-      .client
-      .put_blob(&key.object_key(), file_stream, Some(&file_type))
-      .await?;
-    insert_blob_metadata(
-      &self.pg_pool,
+    let content = file_stream
+      .collect()
+      .await
+      .map_err(|err| AppError::Internal(anyhow!("Failed to read blob content: {}", err)))?
+      .into_bytes();
+    let content_hash = format!("{:x}", Sha256::digest(&content));
+
+    let mut tx = self.pg_pool.begin().await?;
+    lock_blob_content(&mut tx, key.workspace_id(), &content_hash).await?;
+    if count_blob_content_references(&mut tx, key.workspace_id(), &content_hash).await? == 0 {
+      self
+        .client
+        .put_blob(
+          &blob_content_object_key(key.workspace_id(), &content_hash),
+          ByteStream::from(content),
+          Some(&file_type),
+        )
+        .await?;
+    } else {
+      trace!(
+        "reuse stored content {} for blob_metadata_key: {}",
+        content_hash,
+        key.blob_metadata_key()
+      );
+    }
+    insert_blob_metadata_with_content_hash(
+      &mut tx,
       &key.blob_metadata_key(),
       key.workspace_id(),
       &file_type,
       file_size,
+      &content_hash,
     )
     .await?;
+    tx.commit().await?;
     Ok(())
   }
 
+  /// Deletes the blob and its variants. A content-addressed object is only deleted once no other
+  /// blob references it.
   pub async fn delete_blob(&self, key: impl BlobKey) -> Result<(), AppError> {
-    self.client.delete_blob(&key.object_key()).await?;
-
     let mut tx = self.pg_pool.begin().await?;
-    delete_blob_metadata(&mut tx, key.workspace_id(), &key.blob_metadata_key()).await?;
+    let object_key =
+      match delete_blob_metadata(&mut tx, key.workspace_id(), &key.blob_metadata_key()).await? {
+        Some(content_hash) => {
+          lock_blob_content(&mut tx, key.workspace_id(), &content_hash).await?;
+          if count_blob_content_references(&mut tx, key.workspace_id(), &content_hash).await? == 0 {
+            Some(blob_content_object_key(key.workspace_id(), &content_hash))
+          } else {
+            None
+          }
+        },
+        None => Some(key.object_key()),
+      };
+    if let Some(object_key) = object_key {
+      self.client.delete_blob(&object_key).await?;
+      self
+        .client
+        .delete_blobs(blob_image_variant_object_keys(&object_key))
+        .await?;
+    }
     tx.commit().await?;
     Ok(())
   }
@@ -142,10 +256,67 @@ where
   }
 
   pub async fn get_blob(&self, key: &impl BlobKey) -> Result<Vec<u8>, AppError> {
-    let blob = self.client.get_blob(&key.object_key()).await?.to_blob();
+    let object_key = self.stored_object_key(key).await?;
+    let blob = self.client.get_blob(&object_key).await?.to_blob();
     Ok(blob)
   }
 
+  pub async fn get_blob_stream(
+    &self,
+    key: &impl BlobKey,
+    range: Option<BlobRange>,
+  ) -> Result<BlobStream, AppError> {
+    let object_key = self.stored_object_key(key).await?;
+    self.client.get_blob_stream(&object_key, range).await
+  }
+
+  /// Returns the resized variant of the image blob, or `None` if it hasn't been generated yet.
+  pub async fn get_blob_image_variant(
+    &self,
+    key: &impl BlobKey,
+    width: u32,
+  ) -> Result<Option<Vec<u8>>, AppError> {
+    let object_key = self.stored_object_key(key).await?;
+    match self
+      .client
+      .get_blob(&blob_image_variant_object_key(&object_key, width))
+      .await
+    {
+      Ok(blob) => Ok(Some(blob.to_blob())),
+      Err(err) if err.is_record_not_found() => Ok(None),
+      Err(err) => Err(err),
+    }
+  }
+
+  pub async fn put_blob_image_variant(
+    &self,
+    key: &impl BlobKey,
+    width: u32,
+    content: Vec<u8>,
+    content_type: &str,
+  ) -> Result<(), AppError> {
+    let object_key = self.stored_object_key(key).await?;
+    self
+      .client
+      .put_blob(
+        &blob_image_variant_object_key(&object_key, width),
+        ByteStream::from(content),
+        Some(content_type),
+      )
+      .await
+  }
+
+  /// Returns the key of the object holding the content of the blob. Blobs uploaded in multiple
+  /// parts, or before content deduplication was introduced, are stored under their own key.
+  async fn stored_object_key(&self, key: &impl BlobKey) -> Result<String, AppError> {
+    let content_hash =
+      select_blob_content_hash(&self.pg_pool, key.workspace_id(), &key.blob_metadata_key()).await?;
+    Ok(match content_hash {
+      Some(content_hash) => blob_content_object_key(key.workspace_id(), &content_hash),
+      None => key.object_key(),
+    })
+  }
+
   pub async fn create_upload(
     &self,
     key: impl BlobKey,
@@ -162,10 +333,28 @@ where
     self.client.upload_part(&key.object_key(), req).await
   }
 
+  pub async fn list_upload_parts(
+    &self,
+    key: impl BlobKey,
+    upload_id: &str,
+  ) -> Result<Vec<UploadedPart>, AppError> {
+    self
+      .client
+      .list_upload_parts(&key.object_key(), upload_id)
+      .await
+  }
+
+  pub async fn abort_upload(&self, key: impl BlobKey, upload_id: &str) -> Result<(), AppError> {
+    self.client.abort_upload(&key.object_key(), upload_id).await
+  }
+
+  /// Completes the multipart upload. `check_file_size` is called with the size of the uploaded
+  /// file before its metadata is saved, and the uploaded object is deleted if the check fails.
   pub async fn complete_upload(
     &self,
     key: impl BlobKey,
     req: CompleteUploadRequest,
+    check_file_size: impl FnOnce(u64) -> Result<(), AppError> + Send,
   ) -> Result<(), AppError> {
     if is_blob_metadata_exists(&self.pg_pool, key.workspace_id(), &key.object_key()).await? {
       warn!(
@@ -178,6 +367,10 @@ where
 
     let (content_length, content_type) =
       self.client.complete_upload(&key.object_key(), req).await?;
+    if let Err(err) = check_file_size(content_length as u64) {
+      self.client.delete_blob(&key.object_key()).await?;
+      return Err(err);
+    }
     insert_blob_metadata(
       &self.pg_pool,
       &key.blob_metadata_key(),
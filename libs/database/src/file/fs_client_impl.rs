@@ -0,0 +1,556 @@
+use crate::file::{BlobRange, BlobStream, BucketClient, BucketStorage, ResponseBlob};
+use anyhow::anyhow;
+use app_error::AppError;
+use async_trait::async_trait;
+use aws_sdk_s3::primitives::ByteStream;
+use database_entity::file_dto::{
+  CompleteUploadRequest, CompletedPartRequest, CreateUploadRequest, CreateUploadResponse,
+  UploadPartData, UploadPartResponse, UploadedPart,
+};
+use sha2::{Digest, Sha256};
+use std::io::{ErrorKind, SeekFrom};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tracing::trace;
+use uuid::Uuid;
+
+const OBJECTS_DIR: &str = "objects";
+const METADATA_DIR: &str = "metadata";
+const UPLOADS_DIR: &str = "uploads";
+const TMP_DIR: &str = "tmp";
+const UPLOAD_CONTENT_TYPE_FILE: &str = "content_type";
+const UPLOAD_OBJECT_KEY_FILE: &str = "object_key";
+const COPY_BUFFER_SIZE: usize = 64 * 1024;
+const DEFAULT_CONTENT_TYPE: &str = "application/octet-stream";
+
+pub type LocalFsBucketStorage = BucketStorage<LocalFsBucketClientImpl>;
+
+/// A [BucketClient] that stores blobs in a local directory, for self-hosted installs that don't
+/// want to run S3 or MinIO. The directory is laid out as follows:
+/// - `objects/<object_key>`: the content of the blobs.
+/// - `metadata/<object_key>`: the content type of the blobs.
+/// - `uploads/<upload_id>/<part_number>`: the parts of multipart uploads in progress, next to the
+///   key of the object and the content type the upload was created for.
+/// - `tmp`: files being written, which are moved into `objects` once complete.
+///
+/// Several services can share the same directory, e.g. when it is mounted as a volume.
+#[derive(Clone, Debug)]
+pub struct LocalFsBucketClientImpl {
+  root: PathBuf,
+}
+
+impl LocalFsBucketClientImpl {
+  pub async fn new(root: impl Into<PathBuf>) -> Result<Self, AppError> {
+    let root = root.into();
+    for dir in [OBJECTS_DIR, METADATA_DIR, UPLOADS_DIR, TMP_DIR] {
+      fs::create_dir_all(root.join(dir)).await.map_err(|err| {
+        AppError::Internal(anyhow!(
+          "Failed to create blob storage directory {:?}: {}",
+          root.join(dir),
+          err
+        ))
+      })?;
+    }
+    Ok(Self { root })
+  }
+
+  /// Returns the path of the file holding the content of the blob, which allows streaming the blob
+  /// instead of loading it in memory.
+  pub fn object_path(&self, object_key: &str) -> Result<PathBuf, AppError> {
+    Ok(
+      self
+        .root
+        .join(OBJECTS_DIR)
+        .join(key_to_relative_path(object_key)?),
+    )
+  }
+
+  fn metadata_path(&self, object_key: &str) -> Result<PathBuf, AppError> {
+    Ok(
+      self
+        .root
+        .join(METADATA_DIR)
+        .join(key_to_relative_path(object_key)?),
+    )
+  }
+
+  pub async fn get_content_type(&self, object_key: &str) -> Result<Option<String>, AppError> {
+    match fs::read_to_string(self.metadata_path(object_key)?).await {
+      Ok(content_type) => Ok(Some(content_type)),
+      Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+      Err(err) => Err(io_error(err)),
+    }
+  }
+
+  fn upload_dir(&self, upload_id: &str) -> Result<PathBuf, AppError> {
+    let upload_id = Uuid::parse_str(upload_id)
+      .map_err(|_| AppError::InvalidRequest(format!("Invalid upload id: {}", upload_id)))?;
+    Ok(self.root.join(UPLOADS_DIR).join(upload_id.to_string()))
+  }
+
+  /// Returns the directory of an upload in progress, which must have been created for the given
+  /// object key, so that the parts of an upload can't be completed into another object.
+  async fn existing_upload_dir(
+    &self,
+    object_key: &str,
+    upload_id: &str,
+  ) -> Result<PathBuf, AppError> {
+    let upload_dir = self.upload_dir(upload_id)?;
+    match fs::read_to_string(upload_dir.join(UPLOAD_OBJECT_KEY_FILE)).await {
+      Ok(upload_object_key) if upload_object_key == object_key => Ok(upload_dir),
+      Ok(_) => Err(upload_not_found(upload_id)),
+      Err(err) if err.kind() == ErrorKind::NotFound => Err(upload_not_found(upload_id)),
+      Err(err) => Err(io_error(err)),
+    }
+  }
+
+  /// Writes the content to a temporary file first, so that readers never observe a partially
+  /// written blob.
+  async fn write_object(
+    &self,
+    object_key: &str,
+    content: &[u8],
+    content_type: &str,
+  ) -> Result<(), AppError> {
+    let tmp_path = self.tmp_path();
+    fs::write(&tmp_path, content).await.map_err(io_error)?;
+    self
+      .move_to_object(&tmp_path, object_key, content_type)
+      .await
+  }
+
+  fn tmp_path(&self) -> PathBuf {
+    self.root.join(TMP_DIR).join(Uuid::new_v4().to_string())
+  }
+
+  /// Moves a fully written temporary file into `objects`.
+  async fn move_to_object(
+    &self,
+    tmp_path: &Path,
+    object_key: &str,
+    content_type: &str,
+  ) -> Result<(), AppError> {
+    let object_path = self.object_path(object_key)?;
+    let metadata_path = self.metadata_path(object_key)?;
+    create_parent_dir(&object_path).await?;
+    create_parent_dir(&metadata_path).await?;
+    fs::write(&metadata_path, content_type)
+      .await
+      .map_err(io_error)?;
+    fs::rename(tmp_path, &object_path).await.map_err(io_error)?;
+    Ok(())
+  }
+
+  async fn remove_object(&self, object_key: &str) -> Result<(), AppError> {
+    remove_file_if_exists(&self.object_path(object_key)?).await?;
+    remove_file_if_exists(&self.metadata_path(object_key)?).await?;
+    Ok(())
+  }
+
+  /// Returns the keys of the objects starting with `prefix`, in lexicographic order like S3.
+  async fn list_keys(&self, prefix: &str, limit: Option<usize>) -> Result<Vec<String>, AppError> {
+    let objects_dir = self.root.join(OBJECTS_DIR);
+    // Only the directory containing the prefix has to be walked
+    let start_dir = match prefix.rsplit_once('/') {
+      Some((dir, _)) if !dir.is_empty() => objects_dir.join(key_to_relative_path(dir)?),
+      _ => objects_dir.clone(),
+    };
+
+    let mut keys = vec![];
+    let mut dirs = vec![start_dir];
+    while let Some(dir) = dirs.pop() {
+      let mut entries = match fs::read_dir(&dir).await {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == ErrorKind::NotFound => continue,
+        Err(err) => return Err(io_error(err)),
+      };
+      while let Some(entry) = entries.next_entry().await.map_err(io_error)? {
+        let path = entry.path();
+        if entry.file_type().await.map_err(io_error)?.is_dir() {
+          dirs.push(path);
+          continue;
+        }
+        let key = relative_path_to_key(&objects_dir, &path)?;
+        if key.starts_with(prefix) {
+          keys.push(key);
+        }
+      }
+    }
+    keys.sort();
+    if let Some(limit) = limit {
+      keys.truncate(limit);
+    }
+    Ok(keys)
+  }
+}
+
+#[async_trait]
+impl BucketClient for LocalFsBucketClientImpl {
+  type ResponseData = LocalFsResponseData;
+
+  async fn put_blob(
+    &self,
+    object_key: &str,
+    content: ByteStream,
+    content_type: Option<&str>,
+  ) -> Result<(), AppError> {
+    let content = content
+      .collect()
+      .await
+      .map_err(|err| AppError::Internal(anyhow!("Failed to read blob content: {}", err)))?
+      .into_bytes();
+    self
+      .write_object(
+        object_key,
+        &content,
+        content_type.unwrap_or(DEFAULT_CONTENT_TYPE),
+      )
+      .await?;
+    trace!("put object to local file system: {}", object_key);
+    Ok(())
+  }
+
+  async fn put_blob_with_content_type(
+    &self,
+    object_key: &str,
+    stream: ByteStream,
+    content_type: &str,
+  ) -> Result<(), AppError> {
+    self.put_blob(object_key, stream, Some(content_type)).await
+  }
+
+  async fn delete_blob(&self, object_key: &str) -> Result<Self::ResponseData, AppError> {
+    self.remove_object(object_key).await?;
+    trace!("deleted object from local file system: {}", object_key);
+    Ok(LocalFsResponseData::default())
+  }
+
+  async fn delete_blobs(&self, object_keys: Vec<String>) -> Result<(), AppError> {
+    for object_key in object_keys.iter() {
+      self.remove_object(object_key).await?;
+    }
+    trace!(
+      "deleted {} objects from local file system",
+      object_keys.len()
+    );
+    Ok(())
+  }
+
+  async fn get_blob(&self, object_key: &str) -> Result<Self::ResponseData, AppError> {
+    let data = match fs::read(self.object_path(object_key)?).await {
+      Ok(data) => data,
+      Err(err) if err.kind() == ErrorKind::NotFound => {
+        return Err(AppError::RecordNotFound(format!(
+          "blob not found for key:{object_key}"
+        )));
+      },
+      Err(err) => return Err(io_error(err)),
+    };
+    let content_type = self.get_content_type(object_key).await?;
+    trace!(
+      "get object from local file system: {} ({} bytes)",
+      object_key,
+      data.len()
+    );
+    Ok(LocalFsResponseData { data, content_type })
+  }
+  async fn get_blob_stream(
+    &self,
+    object_key: &str,
+    range: Option<BlobRange>,
+  ) -> Result<BlobStream, AppError> {
+    let mut file = match fs::File::open(self.object_path(object_key)?).await {
+      Ok(file) => file,
+      Err(err) if err.kind() == ErrorKind::NotFound => {
+        return Err(AppError::RecordNotFound(format!(
+          "blob not found for key:{object_key}"
+        )));
+      },
+      Err(err) => return Err(io_error(err)),
+    };
+    let file_size = file.metadata().await.map_err(io_error)?.len();
+    let (start, content_length) = match range {
+      Some(range) if range.start < file_size => {
+        (range.start, range.len().min(file_size - range.start))
+      },
+      Some(_) => (file_size, 0),
+      None => (0, file_size),
+    };
+    file.seek(SeekFrom::Start(start)).await.map_err(io_error)?;
+    trace!(
+      "stream object from local file system: {} ({} bytes)",
+      object_key,
+      content_length
+    );
+    Ok(BlobStream {
+      reader: Box::pin(file.take(content_length)),
+      content_length,
+    })
+  }
+
+  async fn create_upload(
+    &self,
+    object_key: &str,
+    req: CreateUploadRequest,
+  ) -> Result<CreateUploadResponse, AppError> {
+    trace!(
+      "creating multi-part upload to local file system: {} - {}",
+      object_key,
+      req
+    );
+    let upload_id = Uuid::new_v4().to_string();
+    let upload_dir = self.upload_dir(&upload_id)?;
+    fs::create_dir_all(&upload_dir).await.map_err(io_error)?;
+    fs::write(upload_dir.join(UPLOAD_CONTENT_TYPE_FILE), &req.content_type)
+      .await
+      .map_err(io_error)?;
+    fs::write(upload_dir.join(UPLOAD_OBJECT_KEY_FILE), object_key)
+      .await
+      .map_err(io_error)?;
+    Ok(CreateUploadResponse {
+      file_id: req.file_id,
+      upload_id,
+    })
+  }
+
+  async fn upload_part(
+    &self,
+    object_key: &str,
+    req: UploadPartData,
+  ) -> Result<UploadPartResponse, AppError> {
+    if req.body.is_empty() {
+      return Err(AppError::InvalidRequest("body is empty".to_string()));
+    }
+    trace!(
+      "multi-part upload to local file system: {} - {}",
+      object_key,
+      req
+    );
+    let upload_dir = self.existing_upload_dir(object_key, &req.upload_id).await?;
+    let e_tag = format!("{:x}", Sha256::digest(&req.body));
+    fs::write(upload_dir.join(req.part_number.to_string()), &req.body)
+      .await
+      .map_err(io_error)?;
+    Ok(UploadPartResponse {
+      part_num: req.part_number,
+      e_tag,
+    })
+  }
+
+  /// Return the content length and content type of the uploaded object
+  async fn complete_upload(
+    &self,
+    object_key: &str,
+    req: CompleteUploadRequest,
+  ) -> Result<(usize, String), AppError> {
+    if req.parts.is_empty() {
+      return Err(AppError::InvalidRequest("parts is empty".to_string()));
+    }
+    let upload_dir = self.existing_upload_dir(object_key, &req.upload_id).await?;
+    let content_type = fs::read_to_string(upload_dir.join(UPLOAD_CONTENT_TYPE_FILE))
+      .await
+      .map_err(|_| upload_not_found(&req.upload_id))?;
+
+    let mut parts = req.parts;
+    parts.sort_by_key(|part| part.part_number);
+    let tmp_path = self.tmp_path();
+    let content_length = match write_upload_parts(&upload_dir, &parts, &tmp_path).await {
+      Ok(content_length) => content_length,
+      Err(err) => {
+        remove_file_if_exists(&tmp_path).await?;
+        return Err(err);
+      },
+    };
+
+    self
+      .move_to_object(&tmp_path, object_key, &content_type)
+      .await?;
+    fs::remove_dir_all(&upload_dir).await.map_err(io_error)?;
+    trace!(
+      "completed upload to local file system: {} ({} bytes)",
+      object_key,
+      content_length
+    );
+    Ok((content_length as usize, content_type))
+  }
+
+  async fn list_upload_parts(
+    &self,
+    object_key: &str,
+    upload_id: &str,
+  ) -> Result<Vec<UploadedPart>, AppError> {
+    let upload_dir = self.existing_upload_dir(object_key, upload_id).await?;
+    let mut entries = fs::read_dir(&upload_dir).await.map_err(io_error)?;
+
+    let mut parts = vec![];
+    while let Some(entry) = entries.next_entry().await.map_err(io_error)? {
+      // Skip the content type and object key files, the other files are named after their part
+      // number
+      let part_number = match entry
+        .file_name()
+        .to_str()
+        .and_then(|s| s.parse::<i32>().ok())
+      {
+        Some(part_number) => part_number,
+        None => continue,
+      };
+      let data = fs::read(entry.path()).await.map_err(io_error)?;
+      parts.push(UploadedPart {
+        part_number,
+        e_tag: format!("{:x}", Sha256::digest(&data)),
+        size: data.len() as u64,
+      });
+    }
+    parts.sort_by_key(|part| part.part_number);
+    Ok(parts)
+  }
+
+  async fn abort_upload(&self, object_key: &str, upload_id: &str) -> Result<(), AppError> {
+    trace!(
+      "aborting multi-part upload to local file system: {} - {}",
+      object_key,
+      upload_id
+    );
+    // Aborting an upload that no longer exists succeeds
+    if !fs::try_exists(self.upload_dir(upload_id)?)
+      .await
+      .map_err(io_error)?
+    {
+      return Ok(());
+    }
+    let upload_dir = self.existing_upload_dir(object_key, upload_id).await?;
+    match fs::remove_dir_all(upload_dir).await {
+      Ok(_) => Ok(()),
+      Err(err) if err.kind() == ErrorKind::NotFound => Ok(()),
+      Err(err) => Err(io_error(err)),
+    }
+  }
+
+  async fn remove_dir(&self, parent_dir: &str) -> Result<(), AppError> {
+    let object_keys = self.list_keys(parent_dir, None).await?;
+    trace!(
+      "deleting {} objects at directory: {}",
+      object_keys.len(),
+      parent_dir
+    );
+    self.delete_blobs(object_keys).await
+  }
+
+  async fn list_dir(&self, dir: &str, limit: usize) -> Result<Vec<String>, AppError> {
+    self.list_keys(dir, Some(limit)).await
+  }
+}
+
+#[derive(Debug, Default)]
+pub struct LocalFsResponseData {
+  data: Vec<u8>,
+  content_type: Option<String>,
+}
+
+impl Deref for LocalFsResponseData {
+  type Target = Vec<u8>;
+
+  fn deref(&self) -> &Self::Target {
+    &self.data
+  }
+}
+
+impl ResponseBlob for LocalFsResponseData {
+  fn to_blob(self) -> Vec<u8> {
+    self.data
+  }
+
+  fn content_type(&self) -> Option<String> {
+    self.content_type.clone()
+  }
+}
+
+/// Object keys are `/` separated. Keys that could escape the storage directory are rejected.
+fn key_to_relative_path(object_key: &str) -> Result<PathBuf, AppError> {
+  let mut path = PathBuf::new();
+  for component in object_key.split('/') {
+    if component.is_empty() || component == "." || component == ".." || component.contains('\\') {
+      return Err(AppError::InvalidRequest(format!(
+        "Invalid object key: {}",
+        object_key
+      )));
+    }
+    path.push(component);
+  }
+  Ok(path)
+}
+
+fn relative_path_to_key(root: &Path, path: &Path) -> Result<String, AppError> {
+  let relative_path = path
+    .strip_prefix(root)
+    .map_err(|err| AppError::Internal(anyhow!("Invalid object path {:?}: {}", path, err)))?;
+  Ok(
+    relative_path
+      .components()
+      .map(|component| component.as_os_str().to_string_lossy())
+      .collect::<Vec<_>>()
+      .join("/"),
+  )
+}
+
+async fn create_parent_dir(path: &Path) -> Result<(), AppError> {
+  if let Some(parent) = path.parent() {
+    fs::create_dir_all(parent).await.map_err(io_error)?;
+  }
+  Ok(())
+}
+
+async fn remove_file_if_exists(path: &Path) -> Result<(), AppError> {
+  match fs::remove_file(path).await {
+    Ok(_) => Ok(()),
+    Err(err) if err.kind() == ErrorKind::NotFound => Ok(()),
+    Err(err) => Err(io_error(err)),
+  }
+}
+
+/// Appends the parts to the file in order, checking the e_tag of each part. Returns the size of
+/// the written content.
+async fn write_upload_parts(
+  upload_dir: &Path,
+  parts: &[CompletedPartRequest],
+  path: &Path,
+) -> Result<u64, AppError> {
+  let mut file = fs::File::create(path).await.map_err(io_error)?;
+  let mut buf = vec![0; COPY_BUFFER_SIZE];
+  let mut content_length = 0;
+  for part in parts {
+    let mut part_file = fs::File::open(upload_dir.join(part.part_number.to_string()))
+      .await
+      .map_err(|_| {
+        AppError::InvalidRequest(format!("part {} was not uploaded", part.part_number))
+      })?;
+    let mut hasher = Sha256::new();
+    loop {
+      let n = part_file.read(&mut buf).await.map_err(io_error)?;
+      if n == 0 {
+        break;
+      }
+      hasher.update(&buf[..n]);
+      file.write_all(&buf[..n]).await.map_err(io_error)?;
+      content_length += n as u64;
+    }
+    if format!("{:x}", hasher.finalize()) != part.e_tag {
+      return Err(AppError::InvalidRequest(format!(
+        "e_tag of part {} doesn't match",
+        part.part_number
+      )));
+    }
+  }
+  file.flush().await.map_err(io_error)?;
+  Ok(content_length)
+}
+
+fn upload_not_found(upload_id: &str) -> AppError {
+  AppError::RecordNotFound(format!("upload not found for id:{}", upload_id))
+}
+
+fn io_error(err: std::io::Error) -> AppError {
+  AppError::Internal(anyhow!("Local file system blob storage error: {}", err))
+}
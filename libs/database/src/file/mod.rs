@@ -1,4 +1,6 @@
+pub mod bucket_client_impl;
 mod file_storage;
+pub mod fs_client_impl;
 pub mod s3_client_impl;
 mod utils;
 
@@ -1,4 +1,4 @@
-use crate::file::{BucketClient, BucketStorage, ResponseBlob};
+use crate::file::{BlobRange, BlobStream, BucketClient, BucketStorage, ResponseBlob};
 use anyhow::anyhow;
 use app_error::AppError;
 use async_trait::async_trait;
@@ -7,7 +7,8 @@ use aws_sdk_s3::operation::delete_object::DeleteObjectOutput;
 use std::ops::Deref;
 use std::time::{Duration, SystemTime};
 
-use aws_sdk_s3::error::SdkError;
+use aws_sdk_s3::error::{ProvideErrorMetadata, SdkError};
+use aws_sdk_s3::operation::abort_multipart_upload::AbortMultipartUploadError;
 use aws_sdk_s3::operation::delete_objects::DeleteObjectsOutput;
 use aws_sdk_s3::operation::get_object::GetObjectError;
 
@@ -17,7 +18,7 @@ use aws_sdk_s3::types::{CompletedMultipartUpload, CompletedPart, Delete, ObjectI
 use aws_sdk_s3::Client;
 use database_entity::file_dto::{
   CompleteUploadRequest, CreateUploadRequest, CreateUploadResponse, UploadPartData,
-  UploadPartResponse,
+  UploadPartResponse, UploadedPart,
 };
 
 use tracing::{error, trace};
@@ -100,6 +101,36 @@ impl AwsS3BucketClientImpl {
     Ok(public_url)
   }
 
+  /// Generates a url that lets anyone holding it download the object until it expires.
+  pub async fn gen_presigned_download_url(
+    &self,
+    s3_key: &str,
+    expires_in_secs: u64,
+  ) -> Result<String, AppError> {
+    let config = PresigningConfig::builder()
+      .start_time(SystemTime::now())
+      .expires_in(Duration::from_secs(expires_in_secs))
+      .build()
+      .map_err(|e| AppError::S3ResponseError(e.to_string()))?;
+    let get_object_req = self
+      .client
+      .get_object()
+      .bucket(&self.bucket)
+      .key(s3_key)
+      .presigned(config)
+      .await
+      .map_err(|err| AppError::Internal(anyhow!("Generate presigned url failed: {:?}", err)))?;
+    let url = get_object_req.uri().to_string();
+    Ok(
+      self
+        .presigned_url_endpoint
+        .as_ref()
+        .map_or(url.clone(), |presigned| {
+          url.replace(&self.endpoint, presigned)
+        }),
+    )
+  }
+
   async fn complete_upload_and_get_metadata(
     &self,
     object_key: &str,
@@ -296,6 +327,47 @@ impl BucketClient for AwsS3BucketClientImpl {
       ))),
     }
   }
+  async fn get_blob_stream(
+    &self,
+    object_key: &str,
+    range: Option<BlobRange>,
+  ) -> Result<BlobStream, AppError> {
+    match self
+      .client
+      .get_object()
+      .bucket(&self.bucket)
+      .key(object_key)
+      .set_range(range.map(|range| format!("bytes={}-{}", range.start, range.end)))
+      .send()
+      .await
+    {
+      Ok(output) => {
+        let content_length = output.content_length.unwrap_or_default().max(0) as u64;
+        trace!(
+          "stream object from S3: {} ({} bytes)",
+          object_key,
+          content_length
+        );
+        Ok(BlobStream {
+          reader: Box::pin(output.body.into_async_read()),
+          content_length,
+        })
+      },
+      Err(SdkError::ServiceError(service_err)) => match service_err.err() {
+        GetObjectError::NoSuchKey(_) => Err(AppError::RecordNotFound(format!(
+          "blob not found for key:{object_key}"
+        ))),
+        _ => Err(AppError::from(anyhow!(
+          "Failed to get object from S3: {:?}",
+          service_err
+        ))),
+      },
+      Err(err) => Err(AppError::from(anyhow!(
+        "Failed to get object from S3: {}",
+        err
+      ))),
+    }
+  }
 
   /// Create a new upload session
   /// https://docs.aws.amazon.com/AmazonS3/latest/userguide/mpuoverview.html
@@ -381,6 +453,85 @@ impl BucketClient for AwsS3BucketClientImpl {
       .await
   }
 
+  async fn list_upload_parts(
+    &self,
+    object_key: &str,
+    upload_id: &str,
+  ) -> Result<Vec<UploadedPart>, AppError> {
+    let mut parts = vec![];
+    let mut part_number_marker = None;
+    loop {
+      let list_parts = self
+        .client
+        .list_parts()
+        .bucket(&self.bucket)
+        .key(object_key)
+        .upload_id(upload_id)
+        .set_part_number_marker(part_number_marker)
+        .send()
+        .await
+        .map_err(|err| match err {
+          SdkError::ServiceError(service_err)
+            if service_err.err().code() == Some("NoSuchUpload") =>
+          {
+            AppError::RecordNotFound(format!("upload not found for id:{}", upload_id))
+          },
+          _ => AppError::Internal(anyhow!("Failed to list upload parts: {}", err)),
+        })?;
+
+      parts.extend(
+        list_parts
+          .parts
+          .unwrap_or_default()
+          .into_iter()
+          .filter_map(|part| {
+            Some(UploadedPart {
+              part_number: part.part_number?,
+              e_tag: part.e_tag?,
+              size: part.size.unwrap_or(0) as u64,
+            })
+          }),
+      );
+
+      if !list_parts.is_truncated.unwrap_or(false) {
+        break;
+      }
+      part_number_marker = list_parts.next_part_number_marker;
+    }
+    Ok(parts)
+  }
+
+  async fn abort_upload(&self, object_key: &str, upload_id: &str) -> Result<(), AppError> {
+    trace!(
+      "aborting multi-part upload to S3: {} - {}",
+      object_key,
+      upload_id
+    );
+    match self
+      .client
+      .abort_multipart_upload()
+      .bucket(&self.bucket)
+      .key(object_key)
+      .upload_id(upload_id)
+      .send()
+      .await
+    {
+      Ok(_) => Ok(()),
+      Err(SdkError::ServiceError(service_err))
+        if matches!(
+          service_err.err(),
+          AbortMultipartUploadError::NoSuchUpload(_)
+        ) =>
+      {
+        Ok(())
+      },
+      Err(err) => Err(AppError::Internal(anyhow!(
+        "Failed to abort upload: {}",
+        err
+      ))),
+    }
+  }
+
   async fn remove_dir(&self, parent_dir: &str) -> Result<(), AppError> {
     let mut continuation_token = None;
     loop {
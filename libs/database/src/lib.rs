@@ -1,10 +1,13 @@
 pub mod access_request;
+pub mod audit_log;
 pub mod chat;
 pub mod collab;
+pub mod encryption;
 pub mod file;
 pub mod history;
 pub mod index;
 pub mod listener;
+pub mod personal_access_token;
 pub mod pg_row;
 pub mod publish;
 pub mod quick_note;
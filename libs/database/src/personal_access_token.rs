@@ -0,0 +1,80 @@
+use app_error::AppError;
+use chrono::{DateTime, Utc};
+use database_entity::dto::PersonalAccessToken;
+use sqlx::{Executor, Postgres};
+use uuid::Uuid;
+
+use crate::pg_row::AFPersonalAccessTokenRow;
+
+/// Only the hash of the token is stored, the token itself is returned once to the user.
+#[allow(clippy::too_many_arguments)]
+pub async fn insert_personal_access_token<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  token_id: &Uuid,
+  token_hash: &[u8],
+  uid: i64,
+  name: &str,
+  read_only: bool,
+  workspace_id: Option<&Uuid>,
+  expires_at: DateTime<Utc>,
+) -> Result<PersonalAccessToken, AppError> {
+  let row = sqlx::query_as!(
+    AFPersonalAccessTokenRow,
+    r#"
+      INSERT INTO af_personal_access_token (
+        token_id, token_hash, uid, name, read_only, workspace_id, expires_at
+      )
+      VALUES ($1, $2, $3, $4, $5, $6, $7)
+      RETURNING token_id, name, read_only, workspace_id, created_at, expires_at, revoked_at
+    "#,
+    token_id,
+    token_hash,
+    uid,
+    name,
+    read_only,
+    workspace_id,
+    expires_at,
+  )
+  .fetch_one(executor)
+  .await?;
+  Ok(row.into())
+}
+
+pub async fn select_personal_access_tokens<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  uid: i64,
+) -> Result<Vec<PersonalAccessToken>, AppError> {
+  let rows = sqlx::query_as!(
+    AFPersonalAccessTokenRow,
+    r#"
+      SELECT token_id, name, read_only, workspace_id, created_at, expires_at, revoked_at
+      FROM af_personal_access_token
+      WHERE uid = $1
+      ORDER BY created_at DESC
+    "#,
+    uid,
+  )
+  .fetch_all(executor)
+  .await?;
+  Ok(rows.into_iter().map(PersonalAccessToken::from).collect())
+}
+
+/// Returns false if the user has no active token with the given id.
+pub async fn revoke_personal_access_token<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  uid: i64,
+  token_id: &Uuid,
+) -> Result<bool, AppError> {
+  let result = sqlx::query!(
+    r#"
+      UPDATE af_personal_access_token
+      SET revoked_at = NOW()
+      WHERE token_id = $1 AND uid = $2 AND revoked_at IS NULL
+    "#,
+    token_id,
+    uid,
+  )
+  .execute(executor)
+  .await?;
+  Ok(result.rows_affected() > 0)
+}
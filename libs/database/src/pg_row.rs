@@ -1,13 +1,14 @@
 use anyhow::anyhow;
 use app_error::AppError;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 
 use database_entity::dto::{
   AFAccessLevel, AFRole, AFUserProfile, AFWebUser, AFWorkspace, AFWorkspaceInvitationStatus,
-  AccessRequestMinimal, AccessRequestStatus, AccessRequestWithViewId, AccessRequesterInfo,
-  AccountLink, GlobalComment, QuickNote, Reaction, Template, TemplateCategory,
-  TemplateCategoryMinimal, TemplateCategoryType, TemplateCreator, TemplateCreatorMinimal,
-  TemplateGroup, TemplateMinimal,
+  AccessRequestAutoApprovalRule, AccessRequestMinimal, AccessRequestStatus,
+  AccessRequestWithViewId, AccessRequesterInfo, AccountLink, AuditAction, AuditLog, GlobalComment,
+  PersonalAccessToken, PublishInfo, PublishReferrerStats, PublishViewDailyStats, QuickNote,
+  Reaction, Template, TemplateCategory, TemplateCategoryMinimal, TemplateCategoryType,
+  TemplateCreator, TemplateCreatorMinimal, TemplateGroup, TemplateMinimal,
 };
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
@@ -203,6 +204,12 @@ pub struct AFCollabMemberRow {
 pub enum AFBlobStatus {
   Ok = 0,
   DallEContentPolicyViolation = 1,
+  /// The blob is waiting to be scanned for malware.
+  ScanPending = 2,
+  /// The malware scanner found the blob to be infected. It can't be downloaded anymore.
+  Infected = 3,
+  /// The malware scanner failed to scan the blob.
+  ScanFailed = 4,
 }
 
 impl From<i16> for AFBlobStatus {
@@ -210,6 +217,9 @@ impl From<i16> for AFBlobStatus {
     match value {
       0 => AFBlobStatus::Ok,
       1 => AFBlobStatus::DallEContentPolicyViolation,
+      2 => AFBlobStatus::ScanPending,
+      3 => AFBlobStatus::Infected,
+      4 => AFBlobStatus::ScanFailed,
       _ => AFBlobStatus::Ok,
     }
   }
@@ -224,6 +234,41 @@ pub struct AFBlobMetadataRow {
   pub modified_at: DateTime<Utc>,
   #[serde(default)]
   pub status: i16,
+  /// Sha256 of the content, when the blob is stored in the content-addressed object shared by
+  /// every blob of the workspace with the same content.
+  #[serde(default)]
+  pub content_hash: Option<String>,
+  /// When the blob garbage collector found that no collab references the blob anymore.
+  #[serde(default)]
+  pub orphaned_at: Option<DateTime<Utc>>,
+}
+
+/// Storage quotas overriding the defaults for a workspace. `None` falls back to the default.
+#[derive(Debug, FromRow)]
+pub struct AFWorkspaceStorageQuotaRow {
+  pub storage_limit: Option<i64>,
+  pub single_upload_limit: Option<i64>,
+}
+
+/// A multipart upload in progress, see [crate::file::BucketStorage::create_upload].
+#[derive(Debug, FromRow)]
+pub struct AFUploadSessionRow {
+  pub upload_id: String,
+  pub workspace_id: Uuid,
+  pub parent_dir: String,
+  pub file_id: String,
+  pub created_by: i64,
+  pub created_at: DateTime<Utc>,
+  pub expires_at: DateTime<Utc>,
+}
+
+/// A data key of a workspace, wrapped by the master key `master_key_id`.
+#[derive(Debug, FromRow)]
+pub struct AFWorkspaceDataKeyRow {
+  pub workspace_id: Uuid,
+  pub key_version: i32,
+  pub master_key_id: String,
+  pub wrapped_key: Vec<u8>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -584,13 +629,179 @@ pub struct AFImportTask {
   pub created_at: DateTime<Utc>,
   #[serde(default)]
   pub file_url: Option<String>,
+  /// See [database_entity::dto::ImportPhase], `None` until the worker starts the task
+  #[serde(default)]
+  pub phase: Option<i16>,
+  #[serde(default)]
+  pub processed_count: i32,
+  #[serde(default)]
+  pub total_count: i32,
+}
+
+/// An export of a workspace, see [crate::workspace::ExportTaskState] for its status.
+#[derive(Debug, FromRow, Serialize, Deserialize)]
+pub struct AFExportTask {
+  pub task_id: Uuid,
+  pub workspace_id: Uuid,
+  pub created_by: i64,
+  pub status: i16,
+  pub s3_key: Option<String>,
+  pub file_size: Option<i64>,
+  pub metadata: serde_json::Value,
+  pub created_at: DateTime<Utc>,
+  pub updated_at: DateTime<Utc>,
+}
+
+/// A version of a published view, with the published content.
+#[derive(Debug, FromRow)]
+pub struct AFPublishHistoryVersion {
+  pub history_id: Uuid,
+  pub view_id: Uuid,
+  pub publish_name: String,
+  pub publisher_email: String,
+  pub published_at: DateTime<Utc>,
+  pub comments_enabled: bool,
+  pub duplicate_enabled: bool,
+  pub metadata: serde_json::Value,
+  pub blob: Vec<u8>,
+}
+
+/// A version of a published view, without its content.
+#[derive(Debug, FromRow)]
+pub struct AFPublishHistory {
+  pub history_id: Uuid,
+  pub view_id: Uuid,
+  pub publish_name: String,
+  pub publisher_email: String,
+  pub published_at: DateTime<Utc>,
+  pub comments_enabled: bool,
+  pub duplicate_enabled: bool,
+}
+
+/// A publish or unpublish of a view at a future time. The `params` are the options of a publish.
+#[derive(Debug, FromRow)]
+pub struct AFPublishSchedule {
+  pub schedule_id: Uuid,
+  pub workspace_id: Uuid,
+  pub view_id: Uuid,
+  pub action: i16,
+  pub scheduled_at: DateTime<Utc>,
+  pub created_by: i64,
+  pub params: serde_json::Value,
+  pub status: i16,
+  pub error: Option<String>,
+  pub created_at: DateTime<Utc>,
+  pub executed_at: Option<DateTime<Utc>>,
+}
+
+/// A published view that is republished when its content changes.
+#[derive(Debug, FromRow)]
+pub struct AFAutoRepublishCollab {
+  pub workspace_id: Uuid,
+  pub view_id: Uuid,
+  pub publish_name: String,
+  pub published_by: i64,
+  pub publisher_uuid: Uuid,
+  pub metadata: serde_json::Value,
+  pub blob: Vec<u8>,
+  pub comments_enabled: bool,
+  pub duplicate_enabled: bool,
+}
+
+#[derive(Debug, FromRow)]
+pub struct AFWorkspaceCustomDomain {
+  pub domain: String,
+  pub workspace_id: Uuid,
+  pub verification_token: String,
+  pub verified_at: Option<DateTime<Utc>>,
+  pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, FromRow)]
+pub struct AFPublishInfoRow {
+  pub namespace: String,
+  pub publish_name: String,
+  pub view_id: Uuid,
+  pub publisher_email: String,
+  pub publish_timestamp: DateTime<Utc>,
+  pub unpublished_timestamp: Option<DateTime<Utc>>,
+  pub comments_enabled: bool,
+  pub duplicate_enabled: bool,
+  pub password_protected: bool,
+  pub members_only: bool,
 }
+
+impl From<AFPublishInfoRow> for PublishInfo {
+  fn from(value: AFPublishInfoRow) -> Self {
+    Self {
+      namespace: value.namespace,
+      publish_name: value.publish_name,
+      view_id: value.view_id,
+      publisher_email: value.publisher_email,
+      publish_timestamp: value.publish_timestamp,
+      unpublished_timestamp: value.unpublished_timestamp,
+      comments_enabled: value.comments_enabled,
+      duplicate_enabled: value.duplicate_enabled,
+      password_protected: value.password_protected,
+      members_only: value.members_only,
+    }
+  }
+}
+
+/// Access restrictions of a published view
+#[derive(Debug, FromRow)]
+pub struct AFPublishedCollabAccess {
+  pub workspace_id: Uuid,
+  pub view_id: Uuid,
+  pub password_hash: Option<String>,
+  pub members_only: bool,
+}
+
+#[derive(Debug, FromRow)]
+pub struct AFPublishedViewDailyStats {
+  pub view_id: Uuid,
+  pub publish_name: Option<String>,
+  pub stat_date: NaiveDate,
+  pub view_count: i64,
+  pub unique_visitor_count: i64,
+  pub duplicate_count: i64,
+}
+
+impl From<AFPublishedViewDailyStats> for PublishViewDailyStats {
+  fn from(value: AFPublishedViewDailyStats) -> Self {
+    Self {
+      view_id: value.view_id,
+      publish_name: value.publish_name,
+      date: value.stat_date,
+      view_count: value.view_count,
+      unique_visitor_count: value.unique_visitor_count,
+      duplicate_count: value.duplicate_count,
+    }
+  }
+}
+
+#[derive(Debug, FromRow)]
+pub struct AFPublishedViewReferrer {
+  pub referrer: String,
+  pub view_count: i64,
+}
+
+impl From<AFPublishedViewReferrer> for PublishReferrerStats {
+  fn from(value: AFPublishedViewReferrer) -> Self {
+    Self {
+      referrer: value.referrer,
+      view_count: value.view_count,
+    }
+  }
+}
+
 #[derive(sqlx::Type, Serialize, Deserialize, Debug)]
 #[repr(i32)]
 pub enum AFAccessRequestStatusColumn {
   Pending = 0,
   Approved = 1,
   Rejected = 2,
+  Expired = 3,
 }
 
 impl From<AFAccessRequestStatusColumn> for AccessRequestStatus {
@@ -599,6 +810,34 @@ impl From<AFAccessRequestStatusColumn> for AccessRequestStatus {
       AFAccessRequestStatusColumn::Pending => AccessRequestStatus::Pending,
       AFAccessRequestStatusColumn::Approved => AccessRequestStatus::Approved,
       AFAccessRequestStatusColumn::Rejected => AccessRequestStatus::Rejected,
+      AFAccessRequestStatusColumn::Expired => AccessRequestStatus::Expired,
+    }
+  }
+}
+
+/// Payload sent on the `af_access_request_channel` whenever an access request is created or its
+/// status changes.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct AFAccessRequestNotification {
+  pub request_id: Uuid,
+  pub workspace_id: Uuid,
+  pub view_id: Uuid,
+  pub requester_uid: i64,
+  pub owner_uid: Option<i64>,
+  pub status: i32,
+}
+
+#[derive(FromRow, Debug, Clone)]
+pub struct AFAccessRequestAutoApprovalRuleRow {
+  pub email_domain: String,
+  pub role_id: i32,
+}
+
+impl From<AFAccessRequestAutoApprovalRuleRow> for AccessRequestAutoApprovalRule {
+  fn from(value: AFAccessRequestAutoApprovalRuleRow) -> Self {
+    Self {
+      email_domain: value.email_domain,
+      role: AFRole::from(value.role_id),
     }
   }
 }
@@ -687,6 +926,64 @@ impl From<AFQuickNoteRow> for QuickNote {
   }
 }
 
+#[derive(FromRow, Clone, Debug)]
+pub struct AFAuditLogRow {
+  pub id: i64,
+  pub workspace_id: Uuid,
+  pub actor_uid: Option<i64>,
+  pub actor_email: Option<String>,
+  pub action: String,
+  pub object_id: Option<String>,
+  pub metadata: serde_json::Value,
+  pub created_at: DateTime<Utc>,
+}
+
+impl TryFrom<AFAuditLogRow> for AuditLog {
+  type Error = AppError;
+
+  fn try_from(value: AFAuditLogRow) -> Result<Self, Self::Error> {
+    let action = value
+      .action
+      .parse::<AuditAction>()
+      .map_err(|err| AppError::Internal(anyhow!(err)))?;
+    Ok(Self {
+      id: value.id,
+      workspace_id: value.workspace_id,
+      actor_uid: value.actor_uid,
+      actor_email: value.actor_email,
+      action,
+      object_id: value.object_id,
+      metadata: value.metadata,
+      created_at: value.created_at,
+    })
+  }
+}
+
+#[derive(FromRow, Clone, Debug)]
+pub struct AFPersonalAccessTokenRow {
+  pub token_id: Uuid,
+  pub name: String,
+  pub read_only: bool,
+  pub workspace_id: Option<Uuid>,
+  pub created_at: DateTime<Utc>,
+  pub expires_at: DateTime<Utc>,
+  pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl From<AFPersonalAccessTokenRow> for PersonalAccessToken {
+  fn from(value: AFPersonalAccessTokenRow) -> Self {
+    Self {
+      token_id: value.token_id,
+      name: value.name,
+      read_only: value.read_only,
+      workspace_id: value.workspace_id,
+      created_at: value.created_at,
+      expires_at: value.expires_at,
+      revoked_at: value.revoked_at,
+    }
+  }
+}
+
 pub struct AFPublishViewWithPublishInfo {
   pub view_id: Uuid,
   pub publish_name: String,
@@ -694,4 +991,12 @@ pub struct AFPublishViewWithPublishInfo {
   pub publish_timestamp: DateTime<Utc>,
   pub comments_enabled: bool,
   pub duplicate_enabled: bool,
+  pub password_protected: bool,
+  pub members_only: bool,
+}
+
+#[derive(Debug, FromRow)]
+pub struct AFCollabEncryptedUpdateRow {
+  pub seq: i64,
+  pub data: Vec<u8>,
 }
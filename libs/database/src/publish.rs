@@ -1,11 +1,23 @@
 use app_error::AppError;
+use chrono::{DateTime, NaiveDate, Utc};
 use database_entity::dto::{
   PatchPublishedCollab, PublishCollabItem, PublishCollabKey, PublishInfo, WorkspaceNamespace,
 };
 use sqlx::{Executor, PgPool, Postgres, QueryBuilder};
 use uuid::Uuid;
 
-use crate::pg_row::AFPublishViewWithPublishInfo;
+use crate::pg_row::{
+  AFAutoRepublishCollab, AFPublishHistory, AFPublishHistoryVersion, AFPublishInfoRow,
+  AFPublishSchedule, AFPublishViewWithPublishInfo, AFPublishedCollabAccess,
+  AFPublishedViewDailyStats, AFPublishedViewReferrer, AFWorkspaceCustomDomain,
+};
+
+/// Number of versions kept in the publish history of a view.
+pub const MAX_PUBLISH_HISTORY_PER_VIEW: i64 = 50;
+
+/// Number of days after which a custom domain that was never verified can be claimed by another
+/// workspace.
+pub const UNVERIFIED_CUSTOM_DOMAIN_EXPIRATION_DAYS: i64 = 7;
 
 pub async fn select_user_is_collab_publisher_for_all_views(
   pg_pool: &PgPool,
@@ -265,6 +277,7 @@ pub async fn insert_or_replace_publish_collabs(
   });
 
   let mut txn = pg_pool.begin().await?;
+  let kept_options = select_kept_publish_options(&mut txn, workspace_id, &view_ids).await?;
   delete_published_collabs(&mut txn, workspace_id, &publish_names).await?;
 
   let res = sqlx::query!(
@@ -306,10 +319,485 @@ pub async fn insert_or_replace_publish_collabs(
     );
   }
 
+  sqlx::query!(
+    r#"
+      UPDATE af_published_collab
+      SET unpublished_at = NULL
+      WHERE workspace_id = $1
+        AND view_id = ANY($2)
+    "#,
+    workspace_id,
+    &view_ids,
+  )
+  .execute(txn.as_mut())
+  .await?;
+  restore_kept_publish_options(&mut txn, workspace_id, kept_options).await?;
+  insert_publish_history(&mut txn, workspace_id, &view_ids).await?;
+
   txn.commit().await?;
   Ok(())
 }
 
+/// Options of a published view that are not part of the publish items. A view republished under
+/// the same publish name is deleted and inserted again, so they are restored after the insert.
+#[derive(sqlx::FromRow)]
+struct KeptPublishOptions {
+  view_id: Uuid,
+  auto_republish: bool,
+  password_hash: Option<String>,
+  members_only: bool,
+}
+
+async fn select_kept_publish_options(
+  txn: &mut sqlx::Transaction<'_, Postgres>,
+  workspace_id: &Uuid,
+  view_ids: &[Uuid],
+) -> Result<Vec<KeptPublishOptions>, AppError> {
+  let options = sqlx::query_as!(
+    KeptPublishOptions,
+    r#"
+      SELECT view_id, auto_republish, password_hash, members_only
+      FROM af_published_collab
+      WHERE workspace_id = $1
+        AND view_id = ANY($2)
+    "#,
+    workspace_id,
+    view_ids,
+  )
+  .fetch_all(txn.as_mut())
+  .await?;
+  Ok(options)
+}
+
+async fn restore_kept_publish_options(
+  txn: &mut sqlx::Transaction<'_, Postgres>,
+  workspace_id: &Uuid,
+  options: Vec<KeptPublishOptions>,
+) -> Result<(), AppError> {
+  if options.is_empty() {
+    return Ok(());
+  }
+  let item_count = options.len();
+  let mut view_ids: Vec<Uuid> = Vec::with_capacity(item_count);
+  let mut auto_republish_list: Vec<bool> = Vec::with_capacity(item_count);
+  let mut password_hashes: Vec<Option<String>> = Vec::with_capacity(item_count);
+  let mut members_only_list: Vec<bool> = Vec::with_capacity(item_count);
+  options.into_iter().for_each(|option| {
+    view_ids.push(option.view_id);
+    auto_republish_list.push(option.auto_republish);
+    password_hashes.push(option.password_hash);
+    members_only_list.push(option.members_only);
+  });
+  sqlx::query!(
+    r#"
+      UPDATE af_published_collab p
+      SET auto_republish = kept.auto_republish,
+          password_hash = kept.password_hash,
+          members_only = kept.members_only
+      FROM UNNEST($2::uuid[], $3::boolean[], $4::text[], $5::boolean[])
+        AS kept(view_id, auto_republish, password_hash, members_only)
+      WHERE p.workspace_id = $1
+        AND p.view_id = kept.view_id
+    "#,
+    workspace_id,
+    &view_ids,
+    &auto_republish_list,
+    &password_hashes as &[Option<String>],
+    &members_only_list,
+  )
+  .execute(txn.as_mut())
+  .await?;
+  Ok(())
+}
+
+/// Record the current published content of the views as a new version in their publish history,
+/// and drop the oldest versions beyond [MAX_PUBLISH_HISTORY_PER_VIEW].
+async fn insert_publish_history(
+  txn: &mut sqlx::Transaction<'_, Postgres>,
+  workspace_id: &Uuid,
+  view_ids: &[Uuid],
+) -> Result<(), AppError> {
+  sqlx::query!(
+    r#"
+      INSERT INTO af_published_collab_history
+        (workspace_id, view_id, publish_name, published_by, metadata, blob, comments_enabled, duplicate_enabled)
+      SELECT workspace_id, view_id, publish_name, published_by, metadata, blob, comments_enabled, duplicate_enabled
+      FROM af_published_collab
+      WHERE workspace_id = $1
+        AND view_id = ANY($2)
+    "#,
+    workspace_id,
+    view_ids,
+  )
+  .execute(txn.as_mut())
+  .await?;
+
+  sqlx::query!(
+    r#"
+      DELETE FROM af_published_collab_history
+      WHERE history_id IN (
+        SELECT history_id FROM (
+          SELECT
+            history_id,
+            ROW_NUMBER() OVER (PARTITION BY view_id ORDER BY published_at DESC) AS version
+          FROM af_published_collab_history
+          WHERE workspace_id = $1
+            AND view_id = ANY($2)
+        ) AS versions
+        WHERE version > $3
+      )
+    "#,
+    workspace_id,
+    view_ids,
+    MAX_PUBLISH_HISTORY_PER_VIEW,
+  )
+  .execute(txn.as_mut())
+  .await?;
+  Ok(())
+}
+
+pub async fn select_publish_history(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  view_id: &Uuid,
+) -> Result<Vec<AFPublishHistory>, AppError> {
+  let history = sqlx::query_as!(
+    AFPublishHistory,
+    r#"
+      SELECT
+        h.history_id,
+        h.view_id,
+        h.publish_name,
+        u.email AS publisher_email,
+        h.published_at,
+        h.comments_enabled,
+        h.duplicate_enabled
+      FROM af_published_collab_history h
+      JOIN af_user u ON h.published_by = u.uid
+      WHERE h.workspace_id = $1
+        AND h.view_id = $2
+      ORDER BY h.published_at DESC
+    "#,
+    workspace_id,
+    view_id,
+  )
+  .fetch_all(pg_pool)
+  .await?;
+  Ok(history)
+}
+
+pub async fn select_publish_history_version(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  view_id: &Uuid,
+  history_id: &Uuid,
+) -> Result<AFPublishHistoryVersion, AppError> {
+  let version = sqlx::query_as!(
+    AFPublishHistoryVersion,
+    r#"
+      SELECT
+        h.history_id,
+        h.view_id,
+        h.publish_name,
+        u.email AS publisher_email,
+        h.published_at,
+        h.comments_enabled,
+        h.duplicate_enabled,
+        h.metadata,
+        h.blob
+      FROM af_published_collab_history h
+      JOIN af_user u ON h.published_by = u.uid
+      WHERE h.workspace_id = $1
+        AND h.view_id = $2
+        AND h.history_id = $3
+    "#,
+    workspace_id,
+    view_id,
+    history_id,
+  )
+  .fetch_optional(pg_pool)
+  .await?
+  .ok_or_else(|| {
+    AppError::RecordNotFound(format!(
+      "publish history {} not found for view {}",
+      history_id, view_id
+    ))
+  })?;
+  Ok(version)
+}
+
+pub async fn update_published_collab_auto_republish<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  view_id: &Uuid,
+  auto_republish: bool,
+) -> Result<(), AppError> {
+  sqlx::query!(
+    r#"
+      UPDATE af_published_collab
+      SET auto_republish = $3,
+          auto_republish_checked_at = NOW()
+      WHERE workspace_id = $1
+        AND view_id = $2
+    "#,
+    workspace_id,
+    view_id,
+    auto_republish,
+  )
+  .execute(executor)
+  .await?;
+  Ok(())
+}
+
+/// Update the access restrictions of a published view. The password protection is left unchanged
+/// if `password_hash` is not set, and removed if it is set to `None`.
+pub async fn update_published_collab_access<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  view_id: &Uuid,
+  password_hash: Option<Option<&str>>,
+  members_only: Option<bool>,
+) -> Result<(), AppError> {
+  sqlx::query!(
+    r#"
+      UPDATE af_published_collab
+      SET password_hash = CASE WHEN $3 THEN $4 ELSE password_hash END,
+          members_only = COALESCE($5, members_only)
+      WHERE workspace_id = $1
+        AND view_id = $2
+    "#,
+    workspace_id,
+    view_id,
+    password_hash.is_some(),
+    password_hash.flatten(),
+    members_only,
+  )
+  .execute(executor)
+  .await?;
+  Ok(())
+}
+
+pub async fn select_published_collab_access<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  publish_namespace: &str,
+  publish_name: &str,
+) -> Result<Option<AFPublishedCollabAccess>, AppError> {
+  let access = sqlx::query_as!(
+    AFPublishedCollabAccess,
+    r#"
+      SELECT workspace_id, view_id, password_hash, members_only
+      FROM af_published_collab
+      WHERE workspace_id = (SELECT workspace_id FROM af_workspace_namespace WHERE namespace = $1)
+        AND unpublished_at IS NULL
+        AND publish_name = $2
+    "#,
+    publish_namespace,
+    publish_name,
+  )
+  .fetch_optional(executor)
+  .await?;
+  Ok(access)
+}
+
+pub async fn select_published_collab_access_for_view_id<
+  'a,
+  E: Executor<'a, Database = Postgres>,
+>(
+  executor: E,
+  view_id: &Uuid,
+) -> Result<Option<AFPublishedCollabAccess>, AppError> {
+  let access = sqlx::query_as!(
+    AFPublishedCollabAccess,
+    r#"
+      SELECT workspace_id, view_id, password_hash, members_only
+      FROM af_published_collab
+      WHERE view_id = $1
+        AND unpublished_at IS NULL
+    "#,
+    view_id,
+  )
+  .fetch_optional(executor)
+  .await?;
+  Ok(access)
+}
+
+/// Claim the published views with auto republish enabled that have not been checked since
+/// `checked_before`. Claimed views are marked as checked so that other workers skip them.
+pub async fn claim_auto_republish_collabs(
+  pg_pool: &PgPool,
+  checked_before: DateTime<Utc>,
+  limit: i64,
+) -> Result<Vec<AFAutoRepublishCollab>, AppError> {
+  let collabs = sqlx::query_as!(
+    AFAutoRepublishCollab,
+    r#"
+      WITH claimed AS (
+        UPDATE af_published_collab
+        SET auto_republish_checked_at = NOW()
+        WHERE (workspace_id, view_id) IN (
+          SELECT workspace_id, view_id
+          FROM af_published_collab
+          WHERE auto_republish
+            AND unpublished_at IS NULL
+            AND (
+              auto_republish_checked_at IS NULL
+              OR auto_republish_checked_at < $1
+            )
+          ORDER BY auto_republish_checked_at NULLS FIRST
+          LIMIT $2
+          FOR UPDATE SKIP LOCKED
+        )
+        RETURNING workspace_id, view_id, publish_name, published_by, metadata, blob, comments_enabled, duplicate_enabled
+      )
+      SELECT
+        c.workspace_id,
+        c.view_id,
+        c.publish_name,
+        c.published_by,
+        u.uuid AS publisher_uuid,
+        c.metadata,
+        c.blob,
+        c.comments_enabled,
+        c.duplicate_enabled
+      FROM claimed c
+      JOIN af_user u ON c.published_by = u.uid
+    "#,
+    checked_before,
+    limit,
+  )
+  .fetch_all(pg_pool)
+  .await?;
+  Ok(collabs)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn insert_publish_schedule(
+  pg_pool: &PgPool,
+  schedule_id: &Uuid,
+  workspace_id: &Uuid,
+  view_id: &Uuid,
+  action: i16,
+  scheduled_at: DateTime<Utc>,
+  created_by: i64,
+  params: serde_json::Value,
+) -> Result<AFPublishSchedule, AppError> {
+  let schedule = sqlx::query_as!(
+    AFPublishSchedule,
+    r#"
+      INSERT INTO af_publish_schedule
+        (schedule_id, workspace_id, view_id, action, scheduled_at, created_by, params)
+      VALUES ($1, $2, $3, $4, $5, $6, $7)
+      RETURNING schedule_id, workspace_id, view_id, action, scheduled_at, created_by, params, status, error, created_at, executed_at
+    "#,
+    schedule_id,
+    workspace_id,
+    view_id,
+    action,
+    scheduled_at,
+    created_by,
+    params,
+  )
+  .fetch_one(pg_pool)
+  .await?;
+  Ok(schedule)
+}
+
+pub async fn select_publish_schedules_for_view(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  view_id: &Uuid,
+) -> Result<Vec<AFPublishSchedule>, AppError> {
+  let schedules = sqlx::query_as!(
+    AFPublishSchedule,
+    r#"
+      SELECT schedule_id, workspace_id, view_id, action, scheduled_at, created_by, params, status, error, created_at, executed_at
+      FROM af_publish_schedule
+      WHERE workspace_id = $1
+        AND view_id = $2
+      ORDER BY scheduled_at DESC
+    "#,
+    workspace_id,
+    view_id,
+  )
+  .fetch_all(pg_pool)
+  .await?;
+  Ok(schedules)
+}
+
+/// Cancel a pending schedule. Returns false if the schedule does not exist or is no longer pending.
+pub async fn cancel_publish_schedule(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  schedule_id: &Uuid,
+) -> Result<bool, AppError> {
+  let res = sqlx::query!(
+    r#"
+      UPDATE af_publish_schedule
+      SET status = 3
+      WHERE workspace_id = $1
+        AND schedule_id = $2
+        AND status = 0
+    "#,
+    workspace_id,
+    schedule_id,
+  )
+  .execute(pg_pool)
+  .await?;
+  Ok(res.rows_affected() > 0)
+}
+
+/// Claim the next pending schedule that is due, or a schedule left processing since
+/// `stale_before` by a worker that stopped.
+pub async fn claim_due_publish_schedule(
+  pg_pool: &PgPool,
+  stale_before: DateTime<Utc>,
+) -> Result<Option<AFPublishSchedule>, AppError> {
+  let schedule = sqlx::query_as!(
+    AFPublishSchedule,
+    r#"
+      UPDATE af_publish_schedule
+      SET status = 4,
+          executed_at = NOW()
+      WHERE schedule_id = (
+        SELECT schedule_id
+        FROM af_publish_schedule
+        WHERE (status = 0 AND scheduled_at <= NOW())
+          OR (status = 4 AND executed_at < $1)
+        ORDER BY scheduled_at
+        LIMIT 1
+        FOR UPDATE SKIP LOCKED
+      )
+      RETURNING schedule_id, workspace_id, view_id, action, scheduled_at, created_by, params, status, error, created_at, executed_at
+    "#,
+    stale_before,
+  )
+  .fetch_optional(pg_pool)
+  .await?;
+  Ok(schedule)
+}
+
+/// Mark a claimed schedule as completed, or as failed with the given error.
+pub async fn update_publish_schedule_result(
+  pg_pool: &PgPool,
+  schedule_id: &Uuid,
+  error: Option<&str>,
+) -> Result<(), AppError> {
+  sqlx::query!(
+    r#"
+      UPDATE af_publish_schedule
+      SET status = CASE WHEN $2::text IS NULL THEN 1 ELSE 2 END,
+          error = $2,
+          executed_at = NOW()
+      WHERE schedule_id = $1
+    "#,
+    schedule_id,
+    error,
+  )
+  .execute(pg_pool)
+  .await?;
+  Ok(())
+}
+
 #[inline]
 pub async fn select_publish_collab_meta<'a, E: Executor<'a, Database = Postgres>>(
   executor: E,
@@ -381,6 +869,13 @@ pub async fn update_published_collabs(
     delete_published_collabs(txn, workspace_id, &publish_names).await?;
   }
   for patch in patches {
+    if patch.comments_enabled.is_none()
+      && patch.duplicate_enabled.is_none()
+      && patch.publish_name.is_none()
+    {
+      // Only the access restrictions are patched, see [update_published_collab_access]
+      continue;
+    }
     let mut query_builder: QueryBuilder<Postgres> = QueryBuilder::new(
       r#"
         UPDATE af_published_collab SET
@@ -572,8 +1067,8 @@ pub async fn select_publish_info_for_view_ids(
   pg_pool: &PgPool,
   view_ids: &[Uuid],
 ) -> Result<Vec<PublishInfo>, AppError> {
-  let mut res = sqlx::query_as!(
-    PublishInfo,
+  let mut res: Vec<PublishInfo> = sqlx::query_as!(
+    AFPublishInfoRow,
     r#"
       SELECT
         awn.namespace,
@@ -583,7 +1078,9 @@ pub async fn select_publish_info_for_view_ids(
         apc.created_at AS publish_timestamp,
         apc.unpublished_at AS unpublished_timestamp,
         apc.comments_enabled,
-        apc.duplicate_enabled
+        apc.duplicate_enabled,
+        apc.password_hash IS NOT NULL AS "password_protected!",
+        apc.members_only
       FROM af_published_collab apc
       JOIN af_user au ON apc.published_by = au.uid
       JOIN af_workspace aw ON apc.workspace_id = aw.workspace_id
@@ -593,7 +1090,10 @@ pub async fn select_publish_info_for_view_ids(
     view_ids,
   )
   .fetch_all(pg_pool)
-  .await?;
+  .await?
+  .into_iter()
+  .map(PublishInfo::from)
+  .collect();
 
   if res.is_empty() {
     return Ok(res);
@@ -623,8 +1123,8 @@ pub async fn select_all_published_collab_info(
   pg_pool: &PgPool,
   workspace_id: &Uuid,
 ) -> Result<Vec<PublishInfo>, AppError> {
-  let mut res = sqlx::query_as!(
-    PublishInfo,
+  let mut res: Vec<PublishInfo> = sqlx::query_as!(
+    AFPublishInfoRow,
     r#"
       SELECT
         awn.namespace,
@@ -634,7 +1134,9 @@ pub async fn select_all_published_collab_info(
         apc.created_at AS publish_timestamp,
         apc.unpublished_at AS unpublished_timestamp,
         apc.comments_enabled,
-        apc.duplicate_enabled
+        apc.duplicate_enabled,
+        apc.password_hash IS NOT NULL AS "password_protected!",
+        apc.members_only
       FROM af_published_collab apc
       JOIN af_user au ON apc.published_by = au.uid
       JOIN af_workspace aw ON apc.workspace_id = aw.workspace_id
@@ -644,7 +1146,10 @@ pub async fn select_all_published_collab_info(
     workspace_id,
   )
   .fetch_all(pg_pool)
-  .await?;
+  .await?
+  .into_iter()
+  .map(PublishInfo::from)
+  .collect();
 
   use_non_orginal_namespace_if_possible(pg_pool, &mut res).await?;
   Ok(res)
@@ -721,7 +1226,9 @@ pub async fn select_published_view_ids_with_publish_info_for_workspace<
         au.email AS publisher_email,
         apc.created_at AS publish_timestamp,
         apc.comments_enabled,
-        apc.duplicate_enabled
+        apc.duplicate_enabled,
+        apc.password_hash IS NOT NULL AS "password_protected!",
+        apc.members_only
       FROM af_published_collab apc
       JOIN af_user au ON apc.published_by = au.uid
       WHERE workspace_id = $1
@@ -734,3 +1241,349 @@ pub async fn select_published_view_ids_with_publish_info_for_workspace<
 
   Ok(res)
 }
+
+/// Set the custom domain of the workspace, replacing its previous custom domain. Setting the
+/// current domain again keeps its verification token and status. A domain claimed by another
+/// workspace is only available once that claim is unverified for more than
+/// [UNVERIFIED_CUSTOM_DOMAIN_EXPIRATION_DAYS], so that a workspace can't squat a domain it doesn't
+/// own.
+pub async fn upsert_workspace_custom_domain(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  domain: &str,
+  verification_token: &str,
+) -> Result<AFWorkspaceCustomDomain, AppError> {
+  let mut txn = pg_pool.begin().await?;
+  let existing = sqlx::query_as!(
+    AFWorkspaceCustomDomain,
+    r#"
+      SELECT domain, workspace_id, verification_token, verified_at, created_at
+      FROM af_workspace_custom_domain
+      WHERE domain = $1
+      FOR UPDATE
+    "#,
+    domain,
+  )
+  .fetch_optional(txn.as_mut())
+  .await?;
+  if let Some(existing) = existing {
+    if existing.workspace_id == *workspace_id {
+      txn.commit().await?;
+      return Ok(existing);
+    }
+    let expired_at =
+      existing.created_at + chrono::Duration::days(UNVERIFIED_CUSTOM_DOMAIN_EXPIRATION_DAYS);
+    if existing.verified_at.is_some() || expired_at > Utc::now() {
+      return Err(AppError::RecordAlreadyExists(format!(
+        "domain {} is used by another workspace",
+        domain
+      )));
+    }
+    sqlx::query!(
+      "DELETE FROM af_workspace_custom_domain WHERE domain = $1",
+      domain
+    )
+    .execute(txn.as_mut())
+    .await?;
+  }
+
+  sqlx::query!(
+    "DELETE FROM af_workspace_custom_domain WHERE workspace_id = $1",
+    workspace_id
+  )
+  .execute(txn.as_mut())
+  .await?;
+  let custom_domain = sqlx::query_as!(
+    AFWorkspaceCustomDomain,
+    r#"
+      INSERT INTO af_workspace_custom_domain (domain, workspace_id, verification_token)
+      VALUES ($1, $2, $3)
+      RETURNING domain, workspace_id, verification_token, verified_at, created_at
+    "#,
+    domain,
+    workspace_id,
+    verification_token,
+  )
+  .fetch_one(txn.as_mut())
+  .await?;
+  txn.commit().await?;
+  Ok(custom_domain)
+}
+
+pub async fn select_workspace_custom_domain(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+) -> Result<Option<AFWorkspaceCustomDomain>, AppError> {
+  let custom_domain = sqlx::query_as!(
+    AFWorkspaceCustomDomain,
+    r#"
+      SELECT domain, workspace_id, verification_token, verified_at, created_at
+      FROM af_workspace_custom_domain
+      WHERE workspace_id = $1
+    "#,
+    workspace_id,
+  )
+  .fetch_optional(pg_pool)
+  .await?;
+  Ok(custom_domain)
+}
+
+pub async fn update_workspace_custom_domain_verified(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  domain: &str,
+) -> Result<AFWorkspaceCustomDomain, AppError> {
+  let custom_domain = sqlx::query_as!(
+    AFWorkspaceCustomDomain,
+    r#"
+      UPDATE af_workspace_custom_domain
+      SET verified_at = COALESCE(verified_at, NOW())
+      WHERE workspace_id = $1
+        AND domain = $2
+      RETURNING domain, workspace_id, verification_token, verified_at, created_at
+    "#,
+    workspace_id,
+    domain,
+  )
+  .fetch_optional(pg_pool)
+  .await?
+  .ok_or_else(|| {
+    AppError::RecordNotFound(format!(
+      "custom domain {} not found for workspace {}",
+      domain, workspace_id
+    ))
+  })?;
+  Ok(custom_domain)
+}
+
+/// Returns false if the workspace has no custom domain.
+pub async fn delete_workspace_custom_domain(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+) -> Result<bool, AppError> {
+  let res = sqlx::query!(
+    "DELETE FROM af_workspace_custom_domain WHERE workspace_id = $1",
+    workspace_id
+  )
+  .execute(pg_pool)
+  .await?;
+  Ok(res.rows_affected() > 0)
+}
+
+/// The publish namespace served by a verified custom domain. A workspace that set its own
+/// namespace is served under it rather than under its original namespace.
+pub async fn select_publish_namespace_for_custom_domain(
+  pg_pool: &PgPool,
+  domain: &str,
+) -> Result<Option<String>, AppError> {
+  let namespace = sqlx::query_scalar!(
+    r#"
+      SELECT n.namespace
+      FROM af_workspace_custom_domain d
+      JOIN af_workspace_namespace n ON n.workspace_id = d.workspace_id
+      WHERE d.domain = $1
+        AND d.verified_at IS NOT NULL
+      ORDER BY n.is_original ASC, n.updated_at DESC
+      LIMIT 1
+    "#,
+    domain,
+  )
+  .fetch_optional(pg_pool)
+  .await?;
+  Ok(namespace)
+}
+
+/// Get the salt of the visitor hashes of the day, inserting `salt` if the day has no salt yet.
+/// The salts and visitor hashes of the previous days are deleted when the salt of a new day is
+/// inserted.
+pub async fn select_or_insert_publish_analytics_salt(
+  pg_pool: &PgPool,
+  salt_date: NaiveDate,
+  salt: &[u8],
+) -> Result<Vec<u8>, AppError> {
+  let inserted = sqlx::query!(
+    r#"
+      INSERT INTO af_publish_analytics_salt (salt_date, salt)
+      VALUES ($1, $2)
+      ON CONFLICT (salt_date) DO NOTHING
+    "#,
+    salt_date,
+    salt,
+  )
+  .execute(pg_pool)
+  .await?
+  .rows_affected()
+    > 0;
+  if inserted {
+    sqlx::query!(
+      "DELETE FROM af_publish_analytics_salt WHERE salt_date < $1",
+      salt_date
+    )
+    .execute(pg_pool)
+    .await?;
+    sqlx::query!(
+      "DELETE FROM af_published_view_visitor WHERE visit_date < $1",
+      salt_date
+    )
+    .execute(pg_pool)
+    .await?;
+  }
+
+  let salt = sqlx::query_scalar!(
+    r#"
+      SELECT salt
+      FROM af_publish_analytics_salt
+      WHERE salt_date = $1
+    "#,
+    salt_date,
+  )
+  .fetch_one(pg_pool)
+  .await?;
+  Ok(salt)
+}
+
+/// Count a view of a published view. The visitor is counted as a unique visitor of the day if
+/// `visitor_hash` has not been seen on that day.
+pub async fn insert_published_view_visit(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  view_id: &Uuid,
+  visit_date: NaiveDate,
+  visitor_hash: &[u8],
+  referrer: Option<&str>,
+) -> Result<(), AppError> {
+  let mut txn = pg_pool.begin().await?;
+  sqlx::query!(
+    r#"
+      WITH new_visitor AS (
+        INSERT INTO af_published_view_visitor (workspace_id, view_id, visit_date, visitor_hash)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT DO NOTHING
+        RETURNING 1
+      )
+      INSERT INTO af_published_view_daily_stats AS stats
+        (workspace_id, view_id, stat_date, view_count, unique_visitor_count)
+      VALUES ($1, $2, $3, 1, (SELECT COUNT(*) FROM new_visitor))
+      ON CONFLICT (workspace_id, view_id, stat_date) DO UPDATE
+      SET view_count = stats.view_count + 1,
+          unique_visitor_count = stats.unique_visitor_count + EXCLUDED.unique_visitor_count
+    "#,
+    workspace_id,
+    view_id,
+    visit_date,
+    visitor_hash,
+  )
+  .execute(txn.as_mut())
+  .await?;
+
+  if let Some(referrer) = referrer {
+    sqlx::query!(
+      r#"
+        INSERT INTO af_published_view_daily_referrer AS referrers
+          (workspace_id, view_id, stat_date, referrer, view_count)
+        VALUES ($1, $2, $3, $4, 1)
+        ON CONFLICT (workspace_id, view_id, stat_date, referrer) DO UPDATE
+        SET view_count = referrers.view_count + 1
+      "#,
+      workspace_id,
+      view_id,
+      visit_date,
+      referrer,
+    )
+    .execute(txn.as_mut())
+    .await?;
+  }
+  txn.commit().await?;
+  Ok(())
+}
+
+pub async fn increment_published_view_duplicate_count(
+  pg_pool: &PgPool,
+  view_id: &Uuid,
+  stat_date: NaiveDate,
+) -> Result<(), AppError> {
+  sqlx::query!(
+    r#"
+      INSERT INTO af_published_view_daily_stats AS stats
+        (workspace_id, view_id, stat_date, duplicate_count)
+      SELECT workspace_id, view_id, $2, 1
+      FROM af_published_collab
+      WHERE view_id = $1
+      ON CONFLICT (workspace_id, view_id, stat_date) DO UPDATE
+      SET duplicate_count = stats.duplicate_count + 1
+    "#,
+    view_id,
+    stat_date,
+  )
+  .execute(pg_pool)
+  .await?;
+  Ok(())
+}
+
+pub async fn select_published_view_daily_stats(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  view_id: Option<&Uuid>,
+  since: NaiveDate,
+  until: NaiveDate,
+) -> Result<Vec<AFPublishedViewDailyStats>, AppError> {
+  let stats = sqlx::query_as!(
+    AFPublishedViewDailyStats,
+    r#"
+      SELECT
+        stats.view_id,
+        apc.publish_name AS "publish_name?",
+        stats.stat_date,
+        stats.view_count,
+        stats.unique_visitor_count,
+        stats.duplicate_count
+      FROM af_published_view_daily_stats stats
+      LEFT JOIN af_published_collab apc
+        ON apc.workspace_id = stats.workspace_id
+        AND apc.view_id = stats.view_id
+        AND apc.unpublished_at IS NULL
+      WHERE stats.workspace_id = $1
+        AND ($2::uuid IS NULL OR stats.view_id = $2)
+        AND stats.stat_date BETWEEN $3 AND $4
+      ORDER BY stats.stat_date, stats.view_id
+    "#,
+    workspace_id,
+    view_id,
+    since,
+    until,
+  )
+  .fetch_all(pg_pool)
+  .await?;
+  Ok(stats)
+}
+
+pub async fn select_published_view_top_referrers(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  view_id: Option<&Uuid>,
+  since: NaiveDate,
+  until: NaiveDate,
+  limit: i64,
+) -> Result<Vec<AFPublishedViewReferrer>, AppError> {
+  let referrers = sqlx::query_as!(
+    AFPublishedViewReferrer,
+    r#"
+      SELECT referrer, SUM(view_count)::BIGINT AS "view_count!"
+      FROM af_published_view_daily_referrer
+      WHERE workspace_id = $1
+        AND ($2::uuid IS NULL OR view_id = $2)
+        AND stat_date BETWEEN $3 AND $4
+      GROUP BY referrer
+      ORDER BY SUM(view_count) DESC, referrer
+      LIMIT $5
+    "#,
+    workspace_id,
+    view_id,
+    since,
+    until,
+    limit,
+  )
+  .fetch_all(pg_pool)
+  .await?;
+  Ok(referrers)
+}
@@ -1,5 +1,9 @@
-use crate::pg_row::AFBlobMetadataRow;
+use crate::pg_row::{
+  AFBlobMetadataRow, AFBlobStatus, AFUploadSessionRow, AFWorkspaceStorageQuotaRow,
+};
 use app_error::AppError;
+use chrono::{DateTime, Utc};
+use futures_util::stream::BoxStream;
 use rust_decimal::prelude::ToPrimitive;
 use sqlx::types::Decimal;
 use sqlx::{Executor, PgPool, Postgres, Transaction};
@@ -109,28 +113,119 @@ pub async fn insert_blob_metadata_bulk<'a, E: Executor<'a, Database = Postgres>>
 
   Ok(result.rows_affected())
 }
+/// Deletes the blob metadata and returns its content hash, if the blob was stored in a
+/// content-addressed object.
 #[instrument(level = "trace", skip_all, err)]
 #[inline]
 pub async fn delete_blob_metadata(
   tx: &mut Transaction<'_, sqlx::Postgres>,
   workspace_id: &Uuid,
   file_id: &str,
-) -> Result<(), AppError> {
-  let result = sqlx::query!(
+) -> Result<Option<String>, AppError> {
+  let deleted = sqlx::query_scalar!(
     r#"
         DELETE FROM af_blob_metadata
         WHERE workspace_id = $1 AND file_id = $2
+        RETURNING content_hash
         "#,
     workspace_id,
     file_id,
   )
+  .fetch_all(tx.deref_mut())
+  .await?;
+  tracing::info!("delete_blob_metadata: rows_affected: {}", deleted.len());
+  Ok(deleted.into_iter().flatten().next())
+}
+
+#[instrument(level = "trace", skip_all, err)]
+pub async fn insert_blob_metadata_with_content_hash(
+  tx: &mut Transaction<'_, sqlx::Postgres>,
+  file_id: &str,
+  workspace_id: &Uuid,
+  file_type: &str,
+  file_size: usize,
+  content_hash: &str,
+) -> Result<(), AppError> {
+  sqlx::query!(
+    r#"
+        INSERT INTO af_blob_metadata
+        (workspace_id, file_id, file_type, file_size, content_hash)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (workspace_id, file_id) DO UPDATE SET
+            file_type = $3,
+            file_size = $4,
+            content_hash = $5
+        "#,
+    workspace_id,
+    file_id,
+    file_type,
+    file_size as i64,
+    content_hash,
+  )
   .execute(tx.deref_mut())
   .await?;
-  let n = result.rows_affected();
-  tracing::info!("delete_blob_metadata: rows_affected: {}", n);
   Ok(())
 }
 
+/// Serializes the uploads and deletions of the blobs sharing the same content until the end of the
+/// transaction, so that the shared object is never deleted while a new reference is being added.
+#[instrument(level = "trace", skip_all, err)]
+pub async fn lock_blob_content(
+  tx: &mut Transaction<'_, sqlx::Postgres>,
+  workspace_id: &Uuid,
+  content_hash: &str,
+) -> Result<(), AppError> {
+  sqlx::query!(
+    "SELECT pg_advisory_xact_lock(hashtextextended($1, 0))",
+    format!("{}/{}", workspace_id, content_hash),
+  )
+  .execute(tx.deref_mut())
+  .await?;
+  Ok(())
+}
+
+/// Returns the number of blobs in the workspace that are stored in the object with the given
+/// content hash.
+#[instrument(level = "trace", skip_all, err)]
+pub async fn count_blob_content_references(
+  tx: &mut Transaction<'_, sqlx::Postgres>,
+  workspace_id: &Uuid,
+  content_hash: &str,
+) -> Result<i64, AppError> {
+  let count = sqlx::query_scalar!(
+    r#"
+        SELECT COUNT(*) AS "count!" FROM af_blob_metadata
+        WHERE workspace_id = $1 AND content_hash = $2
+        "#,
+    workspace_id,
+    content_hash,
+  )
+  .fetch_one(tx.deref_mut())
+  .await?;
+  Ok(count)
+}
+
+/// Returns the content hash of the blob, or `None` if the blob doesn't exist or is stored under its
+/// own object key.
+#[instrument(level = "trace", skip_all, err)]
+pub async fn select_blob_content_hash(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  file_id: &str,
+) -> Result<Option<String>, AppError> {
+  let content_hash = sqlx::query_scalar!(
+    r#"
+        SELECT content_hash FROM af_blob_metadata
+        WHERE workspace_id = $1 AND file_id = $2
+        "#,
+    workspace_id,
+    file_id,
+  )
+  .fetch_optional(pg_pool)
+  .await?;
+  Ok(content_hash.flatten())
+}
+
 #[instrument(level = "trace", skip_all, err)]
 pub async fn get_blob_metadata(
   pg_pool: &PgPool,
@@ -199,17 +294,313 @@ pub async fn get_all_workspace_blob_ids(
   Ok(file_ids)
 }
 
-/// Return the total size of a workspace in bytes
+/// Return the total size of a workspace in bytes. Blobs sharing the same content are only counted
+/// once.
 #[instrument(level = "trace", skip_all, err)]
 #[inline]
 pub async fn get_workspace_usage_size(pool: &PgPool, workspace_id: &Uuid) -> Result<u64, AppError> {
-  let row: (Option<Decimal>,) =
-    sqlx::query_as(r#"SELECT SUM(file_size) FROM af_blob_metadata WHERE workspace_id = $1;"#)
-      .bind(workspace_id)
-      .fetch_one(pool)
-      .await?;
-  match row.0 {
+  let size: Option<Decimal> = sqlx::query_scalar!(
+    r#"
+      SELECT SUM(file_size) FROM (
+        SELECT MAX(file_size) AS file_size
+        FROM af_blob_metadata
+        WHERE workspace_id = $1
+        GROUP BY content_hash, CASE WHEN content_hash IS NULL THEN file_id END
+      ) AS unique_blobs
+    "#,
+    workspace_id,
+  )
+  .fetch_one(pool)
+  .await?;
+  match size {
     Some(decimal) => Ok(decimal.to_u64().unwrap_or(0)),
     None => Ok(0),
   }
 }
+
+#[instrument(level = "trace", skip_all, err)]
+pub async fn update_blob_status(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  file_id: &str,
+  status: AFBlobStatus,
+) -> Result<(), AppError> {
+  sqlx::query!(
+    r#"
+      UPDATE af_blob_metadata
+      SET status = $3
+      WHERE workspace_id = $1 AND file_id = $2
+    "#,
+    workspace_id,
+    file_id,
+    status as i16,
+  )
+  .execute(pg_pool)
+  .await?;
+  Ok(())
+}
+
+#[instrument(level = "trace", skip_all, err)]
+pub async fn select_workspace_storage_quota(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+) -> Result<Option<AFWorkspaceStorageQuotaRow>, AppError> {
+  let quota = sqlx::query_as!(
+    AFWorkspaceStorageQuotaRow,
+    r#"
+      SELECT storage_limit, single_upload_limit
+      FROM af_workspace_storage_quota
+      WHERE workspace_id = $1
+    "#,
+    workspace_id,
+  )
+  .fetch_optional(pg_pool)
+  .await?;
+  Ok(quota)
+}
+
+#[instrument(level = "trace", skip_all, err)]
+pub async fn upsert_workspace_storage_quota(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  storage_limit: Option<i64>,
+  single_upload_limit: Option<i64>,
+) -> Result<(), AppError> {
+  sqlx::query!(
+    r#"
+      INSERT INTO af_workspace_storage_quota (workspace_id, storage_limit, single_upload_limit)
+      VALUES ($1, $2, $3)
+      ON CONFLICT (workspace_id) DO UPDATE
+      SET storage_limit = EXCLUDED.storage_limit,
+          single_upload_limit = EXCLUDED.single_upload_limit,
+          updated_at = NOW()
+    "#,
+    workspace_id,
+    storage_limit,
+    single_upload_limit,
+  )
+  .execute(pg_pool)
+  .await?;
+  Ok(())
+}
+
+/// Return the ids of the workspaces that have at least one blob
+#[instrument(level = "trace", skip_all, err)]
+pub async fn select_workspace_ids_with_blobs(pg_pool: &PgPool) -> Result<Vec<Uuid>, AppError> {
+  let workspace_ids = sqlx::query_scalar!(r#"SELECT DISTINCT workspace_id FROM af_blob_metadata"#)
+    .fetch_all(pg_pool)
+    .await?;
+  Ok(workspace_ids)
+}
+
+/// Streams the content outside of the collabs of the workspace that may reference its blobs: the
+/// published pages and their history, the chats and their messages, the workspace icon and the
+/// metadata of its members, which holds their avatar.
+pub fn stream_workspace_blob_referencing_content<'a>(
+  pg_pool: &'a PgPool,
+  workspace_id: &Uuid,
+) -> BoxStream<'a, sqlx::Result<Vec<u8>>> {
+  sqlx::query_scalar!(
+    r#"
+      SELECT blob AS "content!" FROM af_published_collab WHERE workspace_id = $1
+      UNION ALL
+      SELECT blob FROM af_published_collab_history WHERE workspace_id = $1
+      UNION ALL
+      SELECT convert_to(m.content || ' ' || m.meta_data::TEXT, 'UTF8')
+      FROM af_chat_messages m
+      JOIN af_chat c ON c.chat_id = m.chat_id
+      WHERE c.workspace_id = $1 AND m.deleted_at IS NULL
+      UNION ALL
+      SELECT convert_to(meta_data::TEXT, 'UTF8') FROM af_chat WHERE workspace_id = $1
+      UNION ALL
+      SELECT convert_to(icon, 'UTF8') FROM af_workspace WHERE workspace_id = $1
+      UNION ALL
+      SELECT convert_to(COALESCE(u.metadata::TEXT, ''), 'UTF8')
+      FROM af_user u
+      JOIN af_workspace_member wm ON wm.uid = u.uid
+      WHERE wm.workspace_id = $1
+    "#,
+    workspace_id,
+  )
+  .fetch(pg_pool)
+}
+
+/// Return the blob metadata of a workspace that was last modified before the given time
+#[instrument(level = "trace", skip_all, err)]
+pub async fn select_blob_metadata_modified_before(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  modified_before: DateTime<Utc>,
+) -> Result<Vec<AFBlobMetadataRow>, AppError> {
+  let metadata = sqlx::query_as!(
+    AFBlobMetadataRow,
+    r#"
+        SELECT * FROM af_blob_metadata
+        WHERE workspace_id = $1 AND modified_at < $2
+        "#,
+    workspace_id,
+    modified_before,
+  )
+  .fetch_all(pg_pool)
+  .await?;
+  Ok(metadata)
+}
+
+/// Return the blobs of a workspace that are marked as orphaned by the blob garbage collector
+#[instrument(level = "trace", skip_all, err)]
+pub async fn select_orphaned_blob_metadata(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+) -> Result<Vec<AFBlobMetadataRow>, AppError> {
+  let metadata = sqlx::query_as!(
+    AFBlobMetadataRow,
+    r#"
+        SELECT * FROM af_blob_metadata
+        WHERE workspace_id = $1 AND orphaned_at IS NOT NULL
+        ORDER BY orphaned_at
+        "#,
+    workspace_id,
+  )
+  .fetch_all(pg_pool)
+  .await?;
+  Ok(metadata)
+}
+
+/// Marks the given blobs of the workspace as orphaned, keeping the time they were first found
+/// orphaned, and unmarks the other blobs, which are referenced again.
+#[instrument(level = "trace", skip_all, err)]
+pub async fn update_orphaned_blobs(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  orphaned_file_ids: &[String],
+) -> Result<(), AppError> {
+  let mut tx = pg_pool.begin().await?;
+  sqlx::query!(
+    r#"
+        UPDATE af_blob_metadata SET orphaned_at = NOW()
+        WHERE workspace_id = $1 AND file_id = ANY($2) AND orphaned_at IS NULL
+        "#,
+    workspace_id,
+    orphaned_file_ids,
+  )
+  .execute(tx.deref_mut())
+  .await?;
+  sqlx::query!(
+    r#"
+        UPDATE af_blob_metadata SET orphaned_at = NULL
+        WHERE workspace_id = $1 AND orphaned_at IS NOT NULL AND NOT (file_id = ANY($2))
+        "#,
+    workspace_id,
+    orphaned_file_ids,
+  )
+  .execute(tx.deref_mut())
+  .await?;
+  tx.commit().await?;
+  Ok(())
+}
+
+#[instrument(level = "trace", skip_all, err)]
+pub async fn insert_upload_session<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  upload_id: &str,
+  workspace_id: &Uuid,
+  parent_dir: &str,
+  file_id: &str,
+  created_by: i64,
+  expires_at: DateTime<Utc>,
+) -> Result<(), AppError> {
+  sqlx::query!(
+    r#"
+        INSERT INTO af_upload_session
+        (upload_id, workspace_id, parent_dir, file_id, created_by, expires_at)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        "#,
+    upload_id,
+    workspace_id,
+    parent_dir,
+    file_id,
+    created_by,
+    expires_at,
+  )
+  .execute(executor)
+  .await?;
+  Ok(())
+}
+
+#[instrument(level = "trace", skip_all, err)]
+pub async fn select_upload_session<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  upload_id: &str,
+) -> Result<Option<AFUploadSessionRow>, AppError> {
+  let session = sqlx::query_as!(
+    AFUploadSessionRow,
+    r#"
+        SELECT upload_id, workspace_id, parent_dir, file_id, created_by, created_at, expires_at
+        FROM af_upload_session
+        WHERE workspace_id = $1 AND upload_id = $2
+        "#,
+    workspace_id,
+    upload_id,
+  )
+  .fetch_optional(executor)
+  .await?;
+  Ok(session)
+}
+
+/// Postpones the expiration of the upload session, which is called whenever a part is received.
+#[instrument(level = "trace", skip_all, err)]
+pub async fn update_upload_session_expires_at<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  upload_id: &str,
+  expires_at: DateTime<Utc>,
+) -> Result<(), AppError> {
+  sqlx::query!(
+    r#"UPDATE af_upload_session SET expires_at = $3 WHERE workspace_id = $1 AND upload_id = $2"#,
+    workspace_id,
+    upload_id,
+    expires_at,
+  )
+  .execute(executor)
+  .await?;
+  Ok(())
+}
+
+#[instrument(level = "trace", skip_all, err)]
+pub async fn delete_upload_session<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  upload_id: &str,
+) -> Result<(), AppError> {
+  sqlx::query!(
+    r#"DELETE FROM af_upload_session WHERE upload_id = $1"#,
+    upload_id
+  )
+  .execute(executor)
+  .await?;
+  Ok(())
+}
+
+/// Return the upload sessions that expired before the given time, oldest first
+#[instrument(level = "trace", skip_all, err)]
+pub async fn select_expired_upload_sessions(
+  pg_pool: &PgPool,
+  expired_before: DateTime<Utc>,
+  limit: i64,
+) -> Result<Vec<AFUploadSessionRow>, AppError> {
+  let sessions = sqlx::query_as!(
+    AFUploadSessionRow,
+    r#"
+        SELECT upload_id, workspace_id, parent_dir, file_id, created_by, created_at, expires_at
+        FROM af_upload_session
+        WHERE expires_at < $1
+        ORDER BY expires_at
+        LIMIT $2
+        "#,
+    expired_before,
+    limit,
+  )
+  .fetch_all(pg_pool)
+  .await?;
+  Ok(sessions)
+}
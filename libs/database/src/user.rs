@@ -162,6 +162,16 @@ pub async fn select_uid_from_uuid<'a, E: Executor<'a, Database = Postgres>>(
   Ok(uid)
 }
 
+pub async fn select_uuid_from_uid<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  uid: i64,
+) -> Result<Uuid, AppError> {
+  let uuid = sqlx::query_scalar!("SELECT uuid FROM af_user WHERE uid = $1", uid)
+    .fetch_one(executor)
+    .await?;
+  Ok(uuid)
+}
+
 pub fn select_all_uid_uuid<'a, E: Executor<'a, Database = Postgres> + 'a>(
   executor: E,
 ) -> BoxStream<'a, sqlx::Result<AFUserIdRow>> {
@@ -1,7 +1,7 @@
 use chrono::{DateTime, Utc};
 use database_entity::dto::{
   AFRole, AFWorkspaceInvitation, AFWorkspaceInvitationStatus, AFWorkspaceSettings, GlobalComment,
-  Reaction,
+  ImportPhase, Reaction,
 };
 use futures_util::stream::BoxStream;
 use sqlx::{types::uuid, Executor, PgPool, Postgres, Transaction};
@@ -10,7 +10,7 @@ use tracing::{event, instrument};
 use uuid::Uuid;
 
 use crate::pg_row::{
-  AFGlobalCommentRow, AFImportTask, AFPermissionRow, AFReactionRow, AFUserProfileRow,
+  AFExportTask, AFGlobalCommentRow, AFImportTask, AFPermissionRow, AFReactionRow, AFUserProfileRow,
   AFWebUserColumn, AFWorkspaceInvitationMinimal, AFWorkspaceMemberPermRow, AFWorkspaceMemberRow,
   AFWorkspaceRow,
 };
@@ -41,8 +41,8 @@ pub async fn delete_from_workspace(pg_pool: &PgPool, workspace_id: &Uuid) -> Res
 }
 
 #[inline]
-pub async fn insert_user_workspace(
-  pg_pool: &PgPool,
+pub async fn insert_user_workspace<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
   user_uuid: &Uuid,
   workspace_name: &str,
   is_initialized: bool,
@@ -73,7 +73,7 @@ pub async fn insert_user_workspace(
     workspace_name,
     is_initialized,
   )
-  .fetch_one(pg_pool)
+  .fetch_one(executor)
   .await?;
 
   Ok(workspace)
@@ -985,6 +985,26 @@ pub async fn select_workspace_settings<'a, E: Executor<'a, Database = Postgres>>
     },
   }
 }
+/// Returns whether the workspace is end-to-end encrypted, see
+/// [AFWorkspaceSettings::end_to_end_encryption].
+pub async fn select_workspace_end_to_end_encryption<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+) -> Result<bool, AppError> {
+  let end_to_end_encryption = sqlx::query_scalar!(
+    r#"
+      SELECT (settings->>'end_to_end_encryption')::boolean
+      FROM af_workspace
+      WHERE workspace_id = $1
+    "#,
+    workspace_id,
+  )
+  .fetch_optional(executor)
+  .await?
+  .flatten();
+  Ok(end_to_end_encryption.unwrap_or(false))
+}
+
 pub async fn upsert_workspace_settings(
   tx: &mut Transaction<'_, Postgres>,
   workspace_id: &Uuid,
@@ -1325,6 +1345,7 @@ impl From<i16> for ImportTaskState {
       0 => ImportTaskState::Pending,
       1 => ImportTaskState::Completed,
       2 => ImportTaskState::Failed,
+      3 => ImportTaskState::Expire,
       4 => ImportTaskState::Cancel,
       _ => ImportTaskState::Pending,
     }
@@ -1336,6 +1357,7 @@ impl From<i16> for ImportTaskState {
 ///   1 => Completed,
 ///   2 => Failed,
 ///   3 => Expire,
+///   4 => Cancel,
 pub async fn update_import_task_status<'a, E: Executor<'a, Database = Postgres>>(
   task_id: &Uuid,
   new_status: ImportTaskState,
@@ -1422,6 +1444,278 @@ pub async fn update_import_task_metadata(
   Ok(())
 }
 
+/// Persists the progress of the import task, returning the status of the task so that the worker
+/// stops once it's cancelled.
+pub async fn update_import_task_progress(
+  pg_pool: &PgPool,
+  task_id: &Uuid,
+  phase: ImportPhase,
+  processed_count: i32,
+  total_count: i32,
+) -> Result<ImportTaskState, AppError> {
+  let status = sqlx::query_scalar!(
+    r#"
+      UPDATE af_import_task
+      SET phase = $2, processed_count = $3, total_count = $4
+      WHERE task_id = $1
+      RETURNING status
+    "#,
+    task_id,
+    phase as i16,
+    processed_count,
+    total_count,
+  )
+  .fetch_one(pg_pool)
+  .await?;
+  Ok(ImportTaskState::from(status))
+}
+
+/// Marks the import task as completed unless it was cancelled, in which case `false` is returned
+/// and the imported data must be discarded.
+pub async fn complete_import_task<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  task_id: &Uuid,
+) -> Result<bool, AppError> {
+  let res = sqlx::query!(
+    "UPDATE af_import_task SET status = $2 WHERE task_id = $1 AND status = $3",
+    task_id,
+    ImportTaskState::Completed as i16,
+    ImportTaskState::Pending as i16,
+  )
+  .execute(executor)
+  .await?;
+  Ok(res.rows_affected() > 0)
+}
+
+/// Cancels the pending import task of the user, returning `false` if there is no such task.
+pub async fn cancel_import_task(
+  pg_pool: &PgPool,
+  task_id: &Uuid,
+  created_by: i64,
+) -> Result<bool, AppError> {
+  let res = sqlx::query!(
+    r#"
+      UPDATE af_import_task
+      SET status = $3
+      WHERE task_id = $1 AND created_by = $2 AND status = $4
+    "#,
+    task_id,
+    created_by,
+    ImportTaskState::Cancel as i16,
+    ImportTaskState::Pending as i16,
+  )
+  .execute(pg_pool)
+  .await?;
+  Ok(res.rows_affected() > 0)
+}
+
+#[derive(Clone, Debug)]
+pub enum ExportTaskState {
+  Pending = 0,
+  Completed = 1,
+  Failed = 2,
+  /// The archive was deleted from the bucket
+  Expire = 3,
+  Processing = 4,
+}
+
+impl From<i16> for ExportTaskState {
+  fn from(val: i16) -> Self {
+    match val {
+      1 => ExportTaskState::Completed,
+      2 => ExportTaskState::Failed,
+      3 => ExportTaskState::Expire,
+      4 => ExportTaskState::Processing,
+      _ => ExportTaskState::Pending,
+    }
+  }
+}
+
+pub async fn insert_export_task(
+  pg_pool: &PgPool,
+  task_id: &Uuid,
+  workspace_id: &Uuid,
+  created_by: i64,
+  metadata: serde_json::Value,
+) -> Result<(), AppError> {
+  sqlx::query!(
+    r#"
+      INSERT INTO af_export_task (task_id, workspace_id, created_by, status, metadata)
+      VALUES ($1, $2, $3, $4, $5)
+    "#,
+    task_id,
+    workspace_id,
+    created_by,
+    ExportTaskState::Pending as i16,
+    metadata,
+  )
+  .execute(pg_pool)
+  .await
+  .map_err(|err| {
+    AppError::Internal(anyhow::anyhow!(
+      "Failed to create a new export task: {:?}",
+      err
+    ))
+  })?;
+  Ok(())
+}
+
+pub async fn select_export_task(
+  pg_pool: &PgPool,
+  task_id: &Uuid,
+) -> Result<AFExportTask, AppError> {
+  let task = sqlx::query_as!(
+    AFExportTask,
+    r#"
+      SELECT task_id, workspace_id, created_by, status, s3_key, file_size, metadata, created_at, updated_at
+      FROM af_export_task
+      WHERE task_id = $1
+    "#,
+    task_id,
+  )
+  .fetch_one(pg_pool)
+  .await?;
+  Ok(task)
+}
+
+/// Returns the export tasks created by the user, the most recent first.
+pub async fn select_export_tasks_for_user(
+  pg_pool: &PgPool,
+  uid: i64,
+) -> Result<Vec<AFExportTask>, AppError> {
+  let tasks = sqlx::query_as!(
+    AFExportTask,
+    r#"
+      SELECT task_id, workspace_id, created_by, status, s3_key, file_size, metadata, created_at, updated_at
+      FROM af_export_task
+      WHERE created_by = $1
+      ORDER BY created_at DESC
+    "#,
+    uid,
+  )
+  .fetch_all(pg_pool)
+  .await?;
+  Ok(tasks)
+}
+
+/// Returns the number of export tasks of the user that are not processed yet.
+pub async fn select_unfinished_export_task_count(
+  pg_pool: &PgPool,
+  uid: i64,
+) -> Result<i64, AppError> {
+  let count = sqlx::query_scalar!(
+    r#"
+      SELECT COUNT(*) AS "count!"
+      FROM af_export_task
+      WHERE created_by = $1 AND status IN ($2, $3)
+    "#,
+    uid,
+    ExportTaskState::Pending as i16,
+    ExportTaskState::Processing as i16,
+  )
+  .fetch_one(pg_pool)
+  .await?;
+  Ok(count)
+}
+
+/// Marks the oldest pending export task as processing and returns it. A task that has been
+/// processing since before `stale_before` is considered abandoned, e.g. because the worker
+/// restarted, and is claimed again.
+pub async fn claim_next_export_task(
+  pg_pool: &PgPool,
+  stale_before: DateTime<Utc>,
+) -> Result<Option<AFExportTask>, AppError> {
+  let task = sqlx::query_as!(
+    AFExportTask,
+    r#"
+      UPDATE af_export_task
+      SET status = $1, updated_at = NOW()
+      WHERE task_id = (
+        SELECT task_id FROM af_export_task
+        WHERE status = $2 OR (status = $1 AND updated_at < $3)
+        ORDER BY created_at
+        LIMIT 1
+        FOR UPDATE SKIP LOCKED
+      )
+      RETURNING task_id, workspace_id, created_by, status, s3_key, file_size, metadata, created_at, updated_at
+    "#,
+    ExportTaskState::Processing as i16,
+    ExportTaskState::Pending as i16,
+    stale_before,
+  )
+  .fetch_optional(pg_pool)
+  .await?;
+  Ok(task)
+}
+
+pub async fn update_export_task_completed(
+  pg_pool: &PgPool,
+  task_id: &Uuid,
+  s3_key: &str,
+  file_size: i64,
+) -> Result<(), AppError> {
+  sqlx::query!(
+    r#"
+      UPDATE af_export_task
+      SET status = $1, s3_key = $2, file_size = $3, updated_at = NOW()
+      WHERE task_id = $4
+    "#,
+    ExportTaskState::Completed as i16,
+    s3_key,
+    file_size,
+    task_id,
+  )
+  .execute(pg_pool)
+  .await?;
+  Ok(())
+}
+
+/// Updates the status of the export task, merging `metadata` into its metadata.
+pub async fn update_export_task_status(
+  pg_pool: &PgPool,
+  task_id: &Uuid,
+  status: ExportTaskState,
+  metadata: serde_json::Value,
+) -> Result<(), AppError> {
+  sqlx::query!(
+    r#"
+      UPDATE af_export_task
+      SET status = $1, metadata = metadata || $2, updated_at = NOW()
+      WHERE task_id = $3
+    "#,
+    status as i16,
+    metadata,
+    task_id,
+  )
+  .execute(pg_pool)
+  .await?;
+  Ok(())
+}
+
+/// Returns the completed export tasks whose archive was uploaded before `before`.
+pub async fn select_expired_export_tasks(
+  pg_pool: &PgPool,
+  before: DateTime<Utc>,
+  limit: i64,
+) -> Result<Vec<AFExportTask>, AppError> {
+  let tasks = sqlx::query_as!(
+    AFExportTask,
+    r#"
+      SELECT task_id, workspace_id, created_by, status, s3_key, file_size, metadata, created_at, updated_at
+      FROM af_export_task
+      WHERE status = $1 AND updated_at < $2
+      ORDER BY updated_at
+      LIMIT $3
+    "#,
+    ExportTaskState::Completed as i16,
+    before,
+    limit,
+  )
+  .fetch_all(pg_pool)
+  .await?;
+  Ok(tasks)
+}
+
 #[inline]
 pub async fn select_publish_name_exists(
   pg_pool: &PgPool,
@@ -1469,3 +1763,84 @@ pub async fn select_view_id_from_publish_name(
 
   Ok(res)
 }
+
+/// Returns the workspaces, other than those owned by the user, that require their members to
+/// sign in with SSO, together with the id of the SSO provider they require.
+pub async fn select_sso_enforced_workspaces_for_member<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  user_uuid: &Uuid,
+) -> Result<Vec<(Uuid, String)>, AppError> {
+  let owner_role_id: i32 = AFRole::Owner.into();
+  let workspaces = sqlx::query!(
+    r#"
+      SELECT w.workspace_id, w.settings->'sso'->>'provider_id' AS "provider_id!"
+      FROM af_workspace w
+      JOIN af_workspace_member wm ON wm.workspace_id = w.workspace_id
+      JOIN af_user u ON u.uid = wm.uid
+      WHERE u.uuid = $1
+        AND wm.role_id != $2
+        AND (w.settings->'sso'->>'enforce_sso')::BOOLEAN IS TRUE
+        AND w.settings->'sso'->>'provider_id' IS NOT NULL
+    "#,
+    user_uuid,
+    owner_role_id,
+  )
+  .fetch_all(executor)
+  .await?
+  .into_iter()
+  .map(|row| (row.workspace_id, row.provider_id))
+  .collect();
+  Ok(workspaces)
+}
+
+/// Returns the ids of the workspaces that automatically add users from the given email domain.
+pub async fn select_workspace_ids_with_auto_join_domain<
+  'a,
+  E: Executor<'a, Database = Postgres>,
+>(
+  executor: E,
+  email_domain: &str,
+) -> Result<Vec<Uuid>, AppError> {
+  let workspace_ids = sqlx::query_scalar!(
+    r#"
+      SELECT workspace_id
+      FROM af_workspace
+      WHERE settings->'sso'->'auto_join_domains' ? $1
+    "#,
+    email_domain,
+  )
+  .fetch_all(executor)
+  .await?;
+  Ok(workspace_ids)
+}
+
+/// Returns the workspaces that automatically add users from the given email domain, and that
+/// the user hasn't joined yet.
+pub async fn select_auto_join_workspaces_for_user<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  email_domain: &str,
+  uid: i64,
+) -> Result<Vec<(Uuid, AFWorkspaceSettings)>, AppError> {
+  let rows = sqlx::query!(
+    r#"
+      SELECT w.workspace_id, w.settings AS "settings!"
+      FROM af_workspace w
+      WHERE w.settings->'sso'->'auto_join_domains' ? $1
+        AND NOT EXISTS (
+          SELECT 1 FROM af_workspace_member wm
+          WHERE wm.workspace_id = w.workspace_id AND wm.uid = $2
+        )
+    "#,
+    email_domain,
+    uid,
+  )
+  .fetch_all(executor)
+  .await?;
+
+  let mut workspaces = Vec::with_capacity(rows.len());
+  for row in rows {
+    let settings: AFWorkspaceSettings = serde_json::from_value(row.settings)?;
+    workspaces.push((row.workspace_id, settings));
+  }
+  Ok(workspaces)
+}
@@ -0,0 +1,207 @@
+use crate::aes_encrypt::{decrypt_data, encrypt_data};
+use crate::encryptor::DataEncryptor;
+use anyhow::{anyhow, Result};
+use bytes::Bytes;
+use rand::Rng;
+use std::fmt::{Debug, Formatter};
+
+/// Prefix of the data encrypted by [EnvelopeEncryptor], followed by the version of the data key.
+const ENVELOPE_MAGIC: &[u8; 4] = b"AFE\x01";
+
+/// The length of the data keys in bytes.
+pub const DATA_KEY_LENGTH: usize = 32;
+
+/// Minimum length of the master keys in bytes.
+const MIN_MASTER_KEY_LENGTH: usize = 32;
+
+/// A server master key, only used to wrap the data keys.
+pub struct MasterKey {
+  pub id: String,
+  key: Vec<u8>,
+}
+
+impl MasterKey {
+  pub fn new(id: String, key: Vec<u8>) -> Result<Self> {
+    if id.is_empty() {
+      return Err(anyhow!("Master key id is empty"));
+    }
+    if key.len() < MIN_MASTER_KEY_LENGTH {
+      return Err(anyhow!(
+        "Master key {} must be at least {} bytes long",
+        id,
+        MIN_MASTER_KEY_LENGTH
+      ));
+    }
+    Ok(Self { id, key })
+  }
+
+  pub fn wrap_data_key(&self, data_key: &[u8]) -> Result<Vec<u8>> {
+    encrypt_data(data_key, &self.key)
+  }
+
+  pub fn unwrap_data_key(&self, wrapped_key: &[u8]) -> Result<Vec<u8>> {
+    let data_key = decrypt_data(wrapped_key, &self.key)?;
+    if data_key.len() != DATA_KEY_LENGTH {
+      return Err(anyhow!("Invalid data key length: {}", data_key.len()));
+    }
+    Ok(data_key)
+  }
+}
+
+impl Debug for MasterKey {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("MasterKey").field("id", &self.id).finish()
+  }
+}
+
+/// The master keys of the server. The first key wraps the new data keys, the other ones are only
+/// kept to unwrap the data keys that haven't been wrapped again with the first key yet.
+#[derive(Debug)]
+pub struct MasterKeyring {
+  keys: Vec<MasterKey>,
+}
+
+impl MasterKeyring {
+  /// Parses a comma separated list of `<key id>:<hex encoded key>`, the first key being the
+  /// current one. Returns `None` if the list is empty.
+  pub fn parse(value: &str) -> Result<Option<Self>> {
+    let mut keys: Vec<MasterKey> = vec![];
+    for entry in value.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+      let (id, key) = entry
+        .split_once(':')
+        .ok_or_else(|| anyhow!("Master key must be formatted as <key id>:<hex encoded key>"))?;
+      let key =
+        hex::decode(key.trim()).map_err(|err| anyhow!("Invalid master key {}: {}", id, err))?;
+      let key = MasterKey::new(id.trim().to_string(), key)?;
+      if keys.iter().any(|k| k.id == key.id) {
+        return Err(anyhow!("Duplicate master key id: {}", key.id));
+      }
+      keys.push(key);
+    }
+    if keys.is_empty() {
+      Ok(None)
+    } else {
+      Ok(Some(Self { keys }))
+    }
+  }
+
+  pub fn current(&self) -> &MasterKey {
+    &self.keys[0]
+  }
+
+  pub fn get(&self, id: &str) -> Option<&MasterKey> {
+    self.keys.iter().find(|key| key.id == id)
+  }
+}
+
+pub fn generate_data_key() -> Vec<u8> {
+  let key: [u8; DATA_KEY_LENGTH] = rand::thread_rng().gen();
+  key.to_vec()
+}
+
+/// Encrypts data with a data key. The version of the data key is stored in the header of the
+/// encrypted data, so that the data encrypted before a key rotation can still be decrypted.
+pub struct EnvelopeEncryptor {
+  key_version: i32,
+  data_key: Vec<u8>,
+}
+
+impl EnvelopeEncryptor {
+  pub fn new(key_version: i32, data_key: Vec<u8>) -> Self {
+    Self {
+      key_version,
+      data_key,
+    }
+  }
+
+  pub fn key_version(&self) -> i32 {
+    self.key_version
+  }
+}
+
+impl DataEncryptor for EnvelopeEncryptor {
+  fn encrypt(&self, data: Bytes) -> Result<Bytes> {
+    let encrypted = encrypt_data(&data, &self.data_key)?;
+    let mut buf = Vec::with_capacity(ENVELOPE_MAGIC.len() + 4 + encrypted.len());
+    buf.extend_from_slice(ENVELOPE_MAGIC);
+    buf.extend_from_slice(&self.key_version.to_be_bytes());
+    buf.extend_from_slice(&encrypted);
+    Ok(Bytes::from(buf))
+  }
+
+  fn decrypt(&self, data: Bytes) -> Result<Bytes> {
+    match envelope_key_version(&data) {
+      Some(version) if version == self.key_version => {
+        let decrypted = decrypt_data(&data[ENVELOPE_MAGIC.len() + 4..], &self.data_key)?;
+        Ok(Bytes::from(decrypted))
+      },
+      Some(version) => Err(anyhow!(
+        "Data is encrypted with key version {}, not {}",
+        version,
+        self.key_version
+      )),
+      None => Err(anyhow!("Data is not encrypted")),
+    }
+  }
+}
+
+/// Returns the version of the data key used to encrypt the data, or `None` if the data wasn't
+/// encrypted by an [EnvelopeEncryptor].
+pub fn envelope_key_version(data: &[u8]) -> Option<i32> {
+  let header = data.strip_prefix(ENVELOPE_MAGIC.as_slice())?;
+  let version = header.get(..4)?;
+  Some(i32::from_be_bytes(version.try_into().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const MASTER_KEYS: &str = "k2:cc66c018bfe0a7af8ce0f98847d2ead96a9927df16111068bf98a79f40b39e00,\
+     k1:1f0e2d3c4b5a69788796a5b4c3d2e1f00112233445566778899aabbccddeeff0";
+
+  #[test]
+  fn parse_master_keyring_test() {
+    let keyring = MasterKeyring::parse(MASTER_KEYS).unwrap().unwrap();
+    assert_eq!(keyring.current().id, "k2");
+    assert!(keyring.get("k1").is_some());
+    assert!(keyring.get("k0").is_none());
+
+    assert!(MasterKeyring::parse(" ").unwrap().is_none());
+    assert!(MasterKeyring::parse("k1:abcd").is_err());
+    assert!(MasterKeyring::parse("cc66c018bfe0a7af8ce0f98847d2ead9").is_err());
+  }
+
+  #[test]
+  fn rewrap_data_key_test() {
+    let keyring = MasterKeyring::parse(MASTER_KEYS).unwrap().unwrap();
+    let data_key = generate_data_key();
+    let wrapped = keyring.get("k1").unwrap().wrap_data_key(&data_key).unwrap();
+    assert!(keyring.current().unwrap_data_key(&wrapped).is_err());
+
+    let unwrapped = keyring
+      .get("k1")
+      .unwrap()
+      .unwrap_data_key(&wrapped)
+      .unwrap();
+    let rewrapped = keyring.current().wrap_data_key(&unwrapped).unwrap();
+    assert_eq!(
+      keyring.current().unwrap_data_key(&rewrapped).unwrap(),
+      data_key
+    );
+  }
+
+  #[test]
+  fn envelope_encrypt_decrypt_test() {
+    let encryptor = EnvelopeEncryptor::new(3, generate_data_key());
+    let data = Bytes::from_static(b"hello world");
+    let encrypted = encryptor.encrypt(data.clone()).unwrap();
+    assert_eq!(envelope_key_version(&encrypted), Some(3));
+    assert_eq!(envelope_key_version(&data), None);
+    assert_eq!(encryptor.decrypt(encrypted.clone()).unwrap(), data);
+
+    let rotated = EnvelopeEncryptor::new(4, generate_data_key());
+    assert!(rotated.decrypt(encrypted).is_err());
+    assert!(rotated.decrypt(data).is_err());
+  }
+}
@@ -1,5 +1,8 @@
 pub mod aes_encrypt;
 mod data;
 mod encryptor;
+pub mod envelope;
 
+pub use data::EncryptionData;
+pub use encryptor::{DataEncryptor, NoopEncryptor};
 pub use x25519_dalek;
@@ -289,7 +289,7 @@ impl IndexerScheduler {
     let settings = select_workspace_settings(&self.pg_pool, &uuid).await?;
     match settings {
       None => Ok(true),
-      Some(settings) => Ok(!settings.disable_search_indexing),
+      Some(settings) => Ok(!settings.disable_search_indexing && !settings.end_to_end_encryption),
     }
   }
 }
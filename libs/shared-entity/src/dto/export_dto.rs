@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateExportTaskResponse {
+  pub task_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UserExportTask {
+  pub tasks: Vec<ExportTaskDetail>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportTaskDetail {
+  pub task_id: String,
+  pub workspace_id: String,
+  /// 0 for pending, 1 for completed, 2 for failed, 3 for expired, 4 for processing
+  pub status: i16,
+  pub created_at: i64,
+  /// Size of the archive in bytes, once completed
+  #[serde(default)]
+  pub file_size: Option<u64>,
+  /// Short-lived link to download the archive, once completed
+  #[serde(default)]
+  pub download_url: Option<String>,
+  #[serde(default)]
+  pub error: Option<String>,
+}
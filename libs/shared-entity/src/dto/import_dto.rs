@@ -1,3 +1,4 @@
+use database_entity::dto::{ImportPhase, ImportRowError};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -12,4 +13,19 @@ pub struct ImportTaskDetail {
   pub file_size: u64,
   pub created_at: i64,
   pub status: i16,
+  /// The rows that couldn't be imported as is, only reported by spreadsheet imports
+  #[serde(default)]
+  pub row_errors: Vec<ImportRowError>,
+  /// `None` until the worker starts processing the task
+  #[serde(default)]
+  pub progress: Option<ImportTaskProgress>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportTaskProgress {
+  pub phase: ImportPhase,
+  /// The number of pages, or files, of the phase that are processed
+  pub processed_count: u64,
+  /// `0` if the phase doesn't count what it processes
+  pub total_count: u64,
 }
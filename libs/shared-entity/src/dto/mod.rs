@@ -3,6 +3,7 @@ pub mod ai_dto;
 pub mod auth_dto;
 pub mod billing_dto;
 pub mod chat_dto;
+pub mod export_dto;
 pub mod file_dto;
 pub mod history_dto;
 pub mod import_dto;
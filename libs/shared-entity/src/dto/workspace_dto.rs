@@ -96,6 +96,31 @@ pub struct WorkspaceSpaceUsage {
   pub consumed_capacity: u64,
 }
 
+/// Storage quotas in effect for a workspace, in bytes. A `None` limit means unlimited.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct WorkspaceStorageQuota {
+  pub consumed_capacity: u64,
+  pub storage_limit: Option<u64>,
+  pub remaining_capacity: Option<u64>,
+  pub single_upload_limit: Option<u64>,
+}
+
+/// Overrides the default storage quotas of a workspace, in bytes. A limit of 0 means unlimited,
+/// and `None` restores the default of the server.
+#[derive(Deserialize, Serialize, Debug, Default)]
+pub struct UpdateWorkspaceStorageQuotaParams {
+  #[serde(default)]
+  pub storage_limit: Option<u64>,
+  #[serde(default)]
+  pub single_upload_limit: Option<u64>,
+}
+
+/// Version of the data key that encrypts the collabs of the workspace written from now on.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct WorkspaceDataKeyRotated {
+  pub key_version: i32,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct RepeatedBlobMetaData(pub Vec<BlobMetadata>);
 
@@ -106,11 +131,47 @@ pub struct BlobMetadata {
   pub file_type: String,
   pub file_size: i64,
   pub modified_at: DateTime<Utc>,
+  #[serde(default)]
+  pub status: BlobStatus,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Eq, PartialEq, Debug, Clone, Default, Serialize_repr, Deserialize_repr)]
+#[repr(u8)]
+pub enum BlobStatus {
+  #[default]
+  Ok = 0,
+  ContentPolicyViolation = 1,
+  /// The blob is waiting to be scanned for malware.
+  ScanPending = 2,
+  /// The blob is infected, and can't be downloaded.
+  Infected = 3,
+  /// The blob couldn't be scanned for malware.
+  ScanFailed = 4,
+}
+
+/// Blobs that are no longer referenced by any page or database of the workspace. The blob garbage
+/// collector deletes them once they have stayed unreferenced for its grace period.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct OrphanedBlobsReport {
+  pub blobs: Vec<OrphanedBlob>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct OrphanedBlob {
+  pub file_id: String,
+  pub file_type: String,
+  pub file_size: i64,
+  pub modified_at: DateTime<Utc>,
+  /// When the blob garbage collector found that the blob is not referenced anymore.
+  pub orphaned_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
 pub struct CreateWorkspaceParam {
   pub workspace_name: Option<String>,
+  /// Creates an end-to-end encrypted workspace, whose collab updates are encrypted by the clients.
+  #[serde(default)]
+  pub end_to_end_encryption: bool,
 }
 
 #[derive(Serialize, Deserialize, Default)]
@@ -208,6 +269,21 @@ pub struct PageCollab {
   pub last_editor: Option<AFWebUser>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PageExportFormat {
+  Markdown,
+  Html,
+  Json,
+  Csv,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExportPageQuery {
+  /// Markdown for documents and CSV for databases if not set
+  pub format: Option<PageExportFormat>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PublishedDuplicate {
   pub published_view_id: String,
@@ -281,12 +357,105 @@ pub struct PublishInfoView {
   pub info: PublishInfo,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct PublishPageParams {
   pub publish_name: Option<String>,
   pub visible_database_view_ids: Option<Vec<String>>,
   pub comments_enabled: Option<bool>,
   pub duplicate_enabled: Option<bool>,
+  /// Republish the page when its content changes. Left unchanged if not set.
+  #[serde(default)]
+  pub auto_republish: Option<bool>,
+  /// Password required to view the published page. An empty password removes the password
+  /// protection. Left unchanged if not set.
+  #[serde(default)]
+  pub password: Option<String>,
+  /// Only the members of the workspace can view the published page. Left unchanged if not set.
+  #[serde(default)]
+  pub members_only: Option<bool>,
+}
+
+/// A version of a published view, recorded each time the view is published.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PublishHistoryInfo {
+  pub history_id: Uuid,
+  pub view_id: Uuid,
+  pub publish_name: String,
+  pub publisher_email: String,
+  pub published_at: DateTime<Utc>,
+  pub comments_enabled: bool,
+  pub duplicate_enabled: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PublishHistoryDetail {
+  pub info: PublishHistoryInfo,
+  /// The metadata of the view as it was published
+  pub metadata: Value,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PublishScheduleAction {
+  Publish = 0,
+  Unpublish = 1,
+}
+
+impl PublishScheduleAction {
+  pub fn from_i16(value: i16) -> Option<Self> {
+    match value {
+      0 => Some(PublishScheduleAction::Publish),
+      1 => Some(PublishScheduleAction::Unpublish),
+      _ => None,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PublishScheduleStatus {
+  Pending = 0,
+  Completed = 1,
+  Failed = 2,
+  Cancelled = 3,
+  /// The worker is publishing or unpublishing the page
+  Processing = 4,
+}
+
+impl PublishScheduleStatus {
+  pub fn from_i16(value: i16) -> Option<Self> {
+    match value {
+      0 => Some(PublishScheduleStatus::Pending),
+      1 => Some(PublishScheduleStatus::Completed),
+      2 => Some(PublishScheduleStatus::Failed),
+      3 => Some(PublishScheduleStatus::Cancelled),
+      4 => Some(PublishScheduleStatus::Processing),
+      _ => None,
+    }
+  }
+}
+
+/// Publishes or unpublishes a page at `scheduled_at`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreatePublishSchedule {
+  pub action: PublishScheduleAction,
+  pub scheduled_at: DateTime<Utc>,
+  /// The options of a scheduled publish, ignored when unpublishing
+  #[serde(default)]
+  pub params: PublishPageParams,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PublishSchedule {
+  pub schedule_id: Uuid,
+  pub view_id: Uuid,
+  pub action: PublishScheduleAction,
+  pub scheduled_at: DateTime<Utc>,
+  pub status: PublishScheduleStatus,
+  /// Why the scheduled action failed
+  pub error: Option<String>,
+  pub created_at: DateTime<Utc>,
+  pub executed_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Eq, PartialEq, Debug, Hash, Clone, Serialize_repr, Deserialize_repr)]
@@ -0,0 +1,34 @@
+-- Rules that allow access requests to be approved without waiting for the workspace owner.
+CREATE TABLE IF NOT EXISTS af_access_request_auto_approval_rule (
+  workspace_id UUID NOT NULL REFERENCES af_workspace (workspace_id) ON DELETE CASCADE,
+  email_domain TEXT NOT NULL,
+  role_id INT NOT NULL REFERENCES af_roles (id),
+  created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  PRIMARY KEY (workspace_id, email_domain)
+);
+
+-- Notify the workspace owner whenever an access request is created or its status changes.
+CREATE OR REPLACE FUNCTION notify_af_access_request_change() RETURNS TRIGGER AS $$
+DECLARE
+    payload TEXT;
+BEGIN
+    payload := json_build_object(
+            'request_id', NEW.request_id,
+            'workspace_id', NEW.workspace_id,
+            'view_id', NEW.view_id,
+            'requester_uid', NEW.uid,
+            'owner_uid', (SELECT owner_uid FROM af_workspace WHERE workspace_id = NEW.workspace_id),
+            'status', NEW.status
+            )::text;
+
+    PERFORM pg_notify('af_access_request_channel', payload);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS af_access_request_change_trigger ON af_access_request;
+
+CREATE TRIGGER af_access_request_change_trigger
+    AFTER INSERT OR UPDATE OF status ON af_access_request
+    FOR EACH ROW
+EXECUTE FUNCTION notify_af_access_request_change();
@@ -0,0 +1,15 @@
+-- Record of security-relevant events that happened within a workspace.
+CREATE TABLE IF NOT EXISTS af_audit_log (
+  id BIGSERIAL PRIMARY KEY,
+  workspace_id UUID NOT NULL REFERENCES af_workspace (workspace_id) ON DELETE CASCADE,
+  -- Not a foreign key, so that the entries outlive the user who performed the action.
+  -- NULL when the action was performed by the system, such as an auto approval rule.
+  actor_uid BIGINT,
+  action TEXT NOT NULL,
+  object_id TEXT,
+  metadata JSONB NOT NULL DEFAULT '{}'::jsonb,
+  created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_af_audit_log_workspace_id_created_at
+  ON af_audit_log (workspace_id, created_at DESC);
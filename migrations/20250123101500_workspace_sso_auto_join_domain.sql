@@ -0,0 +1,3 @@
+-- Speeds up looking up the workspaces that automatically add users from a given email domain.
+CREATE INDEX IF NOT EXISTS idx_af_workspace_sso_auto_join_domains
+  ON af_workspace USING GIN ((settings->'sso'->'auto_join_domains'));
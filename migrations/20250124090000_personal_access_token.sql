@@ -0,0 +1,14 @@
+-- Personal access tokens are signed JWTs issued by AppFlowy Cloud. Only their metadata is stored,
+-- so that tokens can be listed and revoked before they expire.
+CREATE TABLE IF NOT EXISTS af_personal_access_token (
+  token_id UUID PRIMARY KEY,
+  uid BIGINT NOT NULL REFERENCES af_user (uid) ON DELETE CASCADE,
+  name TEXT NOT NULL,
+  read_only BOOLEAN NOT NULL DEFAULT FALSE,
+  workspace_id UUID REFERENCES af_workspace (workspace_id) ON DELETE CASCADE,
+  created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+  revoked_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX IF NOT EXISTS idx_af_personal_access_token_uid ON af_personal_access_token (uid);
@@ -0,0 +1,8 @@
+-- Blobs with the same content in a workspace share a single object in the bucket. The number of
+-- af_blob_metadata rows with the same content_hash is the reference count of that object.
+ALTER TABLE af_blob_metadata
+ADD COLUMN IF NOT EXISTS content_hash TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_af_blob_metadata_workspace_id_content_hash
+    ON af_blob_metadata (workspace_id, content_hash)
+    WHERE content_hash IS NOT NULL;
@@ -0,0 +1,4 @@
+-- Set by the blob garbage collector when no collab references the blob anymore. Orphaned blobs are
+-- deleted once they have stayed unreferenced for the grace period.
+ALTER TABLE af_blob_metadata
+ADD COLUMN IF NOT EXISTS orphaned_at TIMESTAMP WITH TIME ZONE;
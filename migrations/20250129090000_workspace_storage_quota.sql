@@ -0,0 +1,8 @@
+-- Storage quotas of a workspace overriding the defaults of the server configuration.
+-- A NULL limit falls back to the default, and 0 means unlimited.
+CREATE TABLE IF NOT EXISTS af_workspace_storage_quota (
+  workspace_id UUID PRIMARY KEY REFERENCES af_workspace (workspace_id) ON DELETE CASCADE,
+  storage_limit BIGINT CHECK (storage_limit >= 0),
+  single_upload_limit BIGINT CHECK (single_upload_limit >= 0),
+  updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,13 @@
+-- Data keys used to encrypt the collabs of a workspace at rest. The data keys are stored wrapped
+-- (encrypted) by a master key of the server configuration, identified by master_key_id.
+-- New data is encrypted with the latest key_version, the previous versions are kept to decrypt the
+-- data written before a key rotation.
+CREATE TABLE IF NOT EXISTS af_workspace_data_key (
+  workspace_id UUID NOT NULL REFERENCES af_workspace (workspace_id) ON DELETE CASCADE,
+  key_version INT NOT NULL,
+  master_key_id TEXT NOT NULL,
+  wrapped_key BYTEA NOT NULL,
+  created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  PRIMARY KEY (workspace_id, key_version)
+);
+CREATE INDEX IF NOT EXISTS idx_af_workspace_data_key_master_key_id ON af_workspace_data_key (master_key_id);
@@ -0,0 +1,10 @@
+-- Updates of the collabs of end-to-end encrypted workspaces. The server can't decrypt them, so they
+-- are stored as sent by the clients, and sent back to the clients when they sync the collab.
+CREATE TABLE IF NOT EXISTS af_collab_encrypted_update (
+  seq BIGSERIAL PRIMARY KEY,
+  workspace_id UUID NOT NULL REFERENCES af_workspace (workspace_id) ON DELETE CASCADE,
+  oid TEXT NOT NULL,
+  data BYTEA NOT NULL,
+  created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+CREATE INDEX IF NOT EXISTS idx_af_collab_encrypted_update_oid ON af_collab_encrypted_update (oid, seq);
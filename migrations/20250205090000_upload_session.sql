@@ -0,0 +1,12 @@
+-- Multipart uploads in progress. An upload that doesn't receive any part before expires_at is
+-- considered abandoned, and aborted by the worker so that its parts don't linger in the bucket.
+CREATE TABLE IF NOT EXISTS af_upload_session (
+  upload_id TEXT PRIMARY KEY,
+  workspace_id UUID NOT NULL REFERENCES af_workspace (workspace_id) ON DELETE CASCADE,
+  parent_dir TEXT NOT NULL,
+  file_id TEXT NOT NULL,
+  created_by BIGINT NOT NULL,
+  created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  expires_at TIMESTAMP WITH TIME ZONE NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_af_upload_session_expires_at ON af_upload_session (expires_at);
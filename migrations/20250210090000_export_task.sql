@@ -0,0 +1,16 @@
+-- Exports of a workspace into a zip archive, which the worker builds and uploads to the bucket.
+CREATE TABLE IF NOT EXISTS af_export_task (
+  task_id UUID PRIMARY KEY,
+  workspace_id UUID NOT NULL REFERENCES af_workspace (workspace_id) ON DELETE CASCADE,
+  created_by BIGINT NOT NULL,
+  -- 0 for pending, 1 for completed, 2 for failed, 3 for expired, 4 for processing
+  status SMALLINT NOT NULL,
+  -- Key of the archive in the bucket, once completed
+  s3_key TEXT,
+  file_size BIGINT,
+  metadata JSONB NOT NULL DEFAULT '{}',
+  created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+CREATE INDEX IF NOT EXISTS idx_af_export_task_status_created_at ON af_export_task (status, created_at);
+CREATE INDEX IF NOT EXISTS idx_af_export_task_created_by ON af_export_task (created_by, created_at);
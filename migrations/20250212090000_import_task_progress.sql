@@ -0,0 +1,5 @@
+-- Progress of the import, reported by the worker while it processes the task
+ALTER TABLE af_import_task
+ADD COLUMN phase SMALLINT,
+ADD COLUMN processed_count INTEGER NOT NULL DEFAULT 0,
+ADD COLUMN total_count INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,39 @@
+-- Every version of a published view, recorded each time the view is published.
+CREATE TABLE IF NOT EXISTS af_published_collab_history (
+  history_id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  workspace_id UUID NOT NULL REFERENCES af_workspace (workspace_id) ON DELETE CASCADE,
+  view_id UUID NOT NULL,
+  publish_name TEXT NOT NULL,
+  published_by BIGINT NOT NULL REFERENCES af_user (uid) ON DELETE CASCADE,
+  metadata JSONB NOT NULL,
+  blob BYTEA NOT NULL,
+  comments_enabled BOOLEAN NOT NULL,
+  duplicate_enabled BOOLEAN NOT NULL,
+  published_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+CREATE INDEX IF NOT EXISTS idx_af_published_collab_history_view ON af_published_collab_history (workspace_id, view_id, published_at);
+
+-- Publishes and unpublishes of a view at a future time, executed by the worker.
+CREATE TABLE IF NOT EXISTS af_publish_schedule (
+  schedule_id UUID PRIMARY KEY,
+  workspace_id UUID NOT NULL REFERENCES af_workspace (workspace_id) ON DELETE CASCADE,
+  view_id UUID NOT NULL,
+  -- 0 for publish, 1 for unpublish
+  action SMALLINT NOT NULL,
+  scheduled_at TIMESTAMP WITH TIME ZONE NOT NULL,
+  created_by BIGINT NOT NULL REFERENCES af_user (uid) ON DELETE CASCADE,
+  -- Options of the publish, e.g. the publish name
+  params JSONB NOT NULL DEFAULT '{}',
+  -- 0 for pending, 1 for completed, 2 for failed, 3 for cancelled, 4 for processing
+  status SMALLINT NOT NULL DEFAULT 0,
+  error TEXT,
+  created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  executed_at TIMESTAMP WITH TIME ZONE
+);
+CREATE INDEX IF NOT EXISTS idx_af_publish_schedule_pending ON af_publish_schedule (scheduled_at) WHERE status = 0;
+CREATE INDEX IF NOT EXISTS idx_af_publish_schedule_view ON af_publish_schedule (workspace_id, view_id);
+
+-- Published views that the worker republishes when their content changes
+ALTER TABLE af_published_collab
+ADD COLUMN auto_republish BOOLEAN NOT NULL DEFAULT FALSE,
+ADD COLUMN auto_republish_checked_at TIMESTAMP WITH TIME ZONE;
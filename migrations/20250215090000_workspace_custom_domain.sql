@@ -0,0 +1,11 @@
+-- Custom domains serving the published pages of a workspace, under the publish namespace of the workspace.
+-- A domain only routes to the workspace once it is verified by the TXT record containing the verification token.
+CREATE TABLE IF NOT EXISTS af_workspace_custom_domain (
+  domain TEXT PRIMARY KEY,
+  workspace_id UUID NOT NULL REFERENCES af_workspace (workspace_id) ON DELETE CASCADE,
+  verification_token TEXT NOT NULL,
+  verified_at TIMESTAMP WITH TIME ZONE,
+  created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+-- A workspace has at most one custom domain
+CREATE UNIQUE INDEX IF NOT EXISTS idx_af_workspace_custom_domain_workspace_id ON af_workspace_custom_domain (workspace_id);
@@ -0,0 +1,5 @@
+-- Access restrictions of published views, next to the publish options.
+-- The password is stored as an argon2 hash, and is not required if NULL.
+ALTER TABLE af_published_collab
+ADD COLUMN password_hash TEXT,
+ADD COLUMN members_only BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,39 @@
+-- Daily views, unique visitors and duplicates of the published views.
+CREATE TABLE IF NOT EXISTS af_published_view_daily_stats (
+  workspace_id UUID NOT NULL REFERENCES af_workspace (workspace_id) ON DELETE CASCADE,
+  view_id UUID NOT NULL,
+  stat_date DATE NOT NULL,
+  view_count BIGINT NOT NULL DEFAULT 0,
+  unique_visitor_count BIGINT NOT NULL DEFAULT 0,
+  duplicate_count BIGINT NOT NULL DEFAULT 0,
+  PRIMARY KEY (workspace_id, view_id, stat_date)
+);
+CREATE INDEX IF NOT EXISTS idx_af_published_view_daily_stats_date ON af_published_view_daily_stats (workspace_id, stat_date);
+
+-- Daily views of the published views by the host of their referrer.
+CREATE TABLE IF NOT EXISTS af_published_view_daily_referrer (
+  workspace_id UUID NOT NULL REFERENCES af_workspace (workspace_id) ON DELETE CASCADE,
+  view_id UUID NOT NULL,
+  stat_date DATE NOT NULL,
+  referrer TEXT NOT NULL,
+  view_count BIGINT NOT NULL DEFAULT 0,
+  PRIMARY KEY (workspace_id, view_id, stat_date, referrer)
+);
+CREATE INDEX IF NOT EXISTS idx_af_published_view_daily_referrer_date ON af_published_view_daily_referrer (workspace_id, stat_date);
+
+-- Salted hashes of the visitors of the current day, used to count the unique visitors without
+-- storing their IP address or user agent.
+CREATE TABLE IF NOT EXISTS af_published_view_visitor (
+  workspace_id UUID NOT NULL REFERENCES af_workspace (workspace_id) ON DELETE CASCADE,
+  view_id UUID NOT NULL,
+  visit_date DATE NOT NULL,
+  visitor_hash BYTEA NOT NULL,
+  PRIMARY KEY (workspace_id, view_id, visit_date, visitor_hash)
+);
+
+-- Random salt of the visitor hashes of each day. The salts and hashes of the previous days are
+-- deleted, so that the hashes can't be linked to the visitors afterwards.
+CREATE TABLE IF NOT EXISTS af_publish_analytics_salt (
+  salt_date DATE PRIMARY KEY,
+  salt BYTEA NOT NULL
+);
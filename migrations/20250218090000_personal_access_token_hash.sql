@@ -0,0 +1,6 @@
+-- Personal access tokens are opaque random strings looked up by their SHA-256 hash, instead of
+-- JWTs signed with the GoTrue secret. The tokens issued before can't be looked up, so they are
+-- revoked.
+ALTER TABLE af_personal_access_token ADD COLUMN IF NOT EXISTS token_hash BYTEA;
+UPDATE af_personal_access_token SET revoked_at = NOW() WHERE token_hash IS NULL AND revoked_at IS NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_af_personal_access_token_hash ON af_personal_access_token (token_hash);
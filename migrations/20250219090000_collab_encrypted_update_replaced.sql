@@ -0,0 +1,4 @@
+-- The updates replaced by a compaction are kept, and no longer sent to the clients, until the next
+-- compaction of the collab succeeds, so that the collab can be restored if the compacted update a
+-- client sent turns out to be broken.
+ALTER TABLE af_collab_encrypted_update ADD COLUMN IF NOT EXISTS replaced_by_seq BIGINT;
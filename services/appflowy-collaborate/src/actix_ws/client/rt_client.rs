@@ -17,6 +17,7 @@ use governor::middleware::NoOpMiddleware;
 use governor::state::{InMemoryState, NotKeyed};
 use governor::{Quota, RateLimiter};
 use semver::Version;
+use std::collections::HashSet;
 use std::num::NonZeroU32;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
@@ -48,6 +49,9 @@ pub struct RealtimeClient<S: RealtimeServer> {
   /// mechanism. This limits the number of messages a client can send per second, ensuring the server's
   /// mailbox does not get full from receiving too many messages at the same time.
   binary_rate_limiter: Arc<BinaryRateLimiter>,
+  /// The workspaces the user can't collaborate on with this connection, as they require signing
+  /// in with another SSO provider.
+  sso_restricted_workspaces: HashSet<String>,
 }
 
 impl<S> RealtimeClient<S>
@@ -73,9 +77,15 @@ where
       external_source: Some(external_source),
       client_version,
       binary_rate_limiter: Arc::new(rate_limiter),
+      sso_restricted_workspaces: HashSet::new(),
     }
   }
 
+  pub fn with_sso_restricted_workspaces(mut self, workspace_ids: HashSet<String>) -> Self {
+    self.sso_restricted_workspaces = workspace_ids;
+    self
+  }
+
   fn hb(&self, ctx: &mut ws::WebsocketContext<Self>) {
     ctx.run_interval(self.heartbeat_interval, move |act, ctx| {
       if Instant::now().duration_since(act.hb) > act.client_timeout {
@@ -226,6 +236,7 @@ where
         .send(Connect {
           socket: ctx.address().recipient(),
           user: self.user.clone(),
+          sso_restricted_workspaces: self.sso_restricted_workspaces.clone(),
         })
         // Converts the future into an actor future, allowing it to be handled within the actor context.
         .into_actor(self)
@@ -7,12 +7,14 @@ use collab_entity::CollabType;
 use collab_rt_entity::user::RealtimeUser;
 pub use collab_rt_entity::RealtimeMessage;
 use serde_repr::{Deserialize_repr, Serialize_repr};
+use std::collections::HashSet;
 use std::fmt::Debug;
 #[derive(Debug, Message, Clone)]
 #[rtype(result = "Result<(), RealtimeError>")]
 pub struct Connect {
   pub socket: Recipient<RealtimeMessage>,
   pub user: RealtimeUser,
+  pub sso_restricted_workspaces: HashSet<String>,
 }
 
 #[derive(Debug, Message, Clone)]
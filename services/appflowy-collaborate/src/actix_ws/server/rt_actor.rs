@@ -71,7 +71,7 @@ where
 
   fn handle(&mut self, new_conn: Connect, _ctx: &mut Context<Self>) -> Self::Result {
     let conn_sink = RealtimeClientWebsocketSinkImpl(new_conn.socket);
-    self.handle_new_connection(new_conn.user, conn_sink)
+    self.handle_new_connection(new_conn.user, conn_sink, new_conn.sso_restricted_workspaces)
   }
 }
 
@@ -19,6 +19,7 @@ use tracing::{debug, error, event, instrument, trace};
 
 use app_error::AppError;
 use authentication::jwt::{authorization_from_token, UserUuid};
+use authentication::sso::sso_restricted_workspaces;
 use collab_rt_entity::user::{AFUserChange, RealtimeUser, UserMessage};
 use collab_rt_entity::{HttpRealtimeMessage, RealtimeMessage};
 use shared_entity::response::{AppResponse, AppResponseError};
@@ -248,6 +249,11 @@ async fn start_connect(
   connect_at: i64,
 ) -> Result<HttpResponse> {
   let auth = authorization_from_token(access_token.as_str(), jwt_secret)?;
+  let sso_restricted_workspaces = sso_restricted_workspaces(&state.pg_pool, &auth)
+    .await?
+    .iter()
+    .map(|workspace_id| workspace_id.to_string())
+    .collect();
   let user_uuid = UserUuid::from_auth(auth)?;
   let result = state.user_cache.get_user_uid(&user_uuid).await;
 
@@ -275,7 +281,8 @@ async fn start_connect(
         client_app_version,
         external_source,
         10,
-      );
+      )
+      .with_sso_restricted_workspaces(sso_restricted_workspaces);
 
       // Receive user change notifications and send them to the client.
       listen_on_user_change(state, uid, tx);
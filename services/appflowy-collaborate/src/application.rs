@@ -26,12 +26,15 @@ use crate::collab::access_control::CollabStorageAccessControlImpl;
 use access_control::casbin::access::AccessControl;
 use collab_stream::metrics::CollabStreamMetrics;
 use collab_stream::stream_router::{StreamRouter, StreamRouterOptions};
+use database::encryption::CollabEncryption;
+use database::file::bucket_client_impl::BucketClientImpl;
+use database::file::fs_client_impl::LocalFsBucketClientImpl;
 use database::file::s3_client_impl::AwsS3BucketClientImpl;
 
 use crate::collab::cache::CollabCache;
 use crate::collab::storage::CollabStorageImpl;
 use crate::command::{CLCommandReceiver, CLCommandSender};
-use crate::config::{get_env_var, Config, DatabaseSetting, S3Setting};
+use crate::config::{get_env_var, BlobStorageBackend, Config, DatabaseSetting, S3Setting};
 use crate::pg_listener::PgListeners;
 use crate::snapshot::SnapshotControl;
 use crate::state::{AppMetrics, AppState, UserCache};
@@ -86,6 +89,7 @@ pub async fn run_actix_server(
     Duration::from_secs(config.collab.group_persistence_interval_secs),
     Duration::from_secs(config.collab.group_prune_grace_period_secs),
     state.indexer_scheduler.clone(),
+    state.pg_pool.clone(),
   )
   .await
   .unwrap();
@@ -94,6 +98,7 @@ pub async fn run_actix_server(
     App::new()
       .app_data(Data::new(state.clone()))
       .app_data(Data::new(state.config.gotrue.jwt_secret.clone()))
+      .app_data(Data::new(state.pg_pool.clone()))
       .app_data(Data::new(realtime_server_actor.clone()))
       .service(ws_scope())
       .service(collab_scope())
@@ -124,22 +129,24 @@ pub async fn init_state(config: &Config, rt_cmd_tx: CLCommandSender) -> Result<A
   let access_control =
     AccessControl::new(pg_pool.clone(), metrics.access_control_metrics.clone()).await?;
 
-  info!("Setting up S3 bucket...");
-  let s3_client = AwsS3BucketClientImpl::new(
-    get_aws_s3_client(&config.s3).await?,
-    config.s3.bucket.clone(),
-    config.s3.minio_url.clone(),
-    config.s3.presigned_url_endpoint.clone(),
-  );
+  let bucket_client = get_bucket_client(config).await?;
 
   let collab_access_control = CollabAccessControlImpl::new(access_control.clone());
   let workspace_access_control = WorkspaceAccessControlImpl::new(access_control.clone());
+  let collab_encryption = CollabEncryption::new(
+    pg_pool.clone(),
+    config.collab.encryption_master_keys.expose_secret(),
+  )?;
+  if collab_encryption.is_some() {
+    info!("Collabs are encrypted at rest");
+  }
   let collab_cache = CollabCache::new(
     redis_conn_manager.clone(),
     pg_pool.clone(),
-    s3_client.clone(),
+    bucket_client.clone(),
     metrics.collab_metrics.clone(),
     config.collab.s3_collab_threshold as usize,
+    collab_encryption.clone(),
   );
 
   let collab_storage_access_control = CollabStorageAccessControlImpl {
@@ -149,8 +156,9 @@ pub async fn init_state(config: &Config, rt_cmd_tx: CLCommandSender) -> Result<A
   };
   let snapshot_control = SnapshotControl::new(
     pg_pool.clone(),
-    s3_client.clone(),
+    bucket_client.clone(),
     metrics.collab_metrics.clone(),
+    collab_encryption,
   )
   .await;
   let collab_storage = Arc::new(CollabStorageImpl::new(
@@ -181,6 +189,7 @@ pub async fn init_state(config: &Config, rt_cmd_tx: CLCommandSender) -> Result<A
 
   let app_state = AppState {
     config: Arc::new(config.clone()),
+    pg_pool,
     pg_listeners,
     user_cache,
     redis_stream_router,
@@ -231,6 +240,29 @@ async fn get_connection_pool(setting: &DatabaseSetting) -> Result<PgPool, Error>
     .map_err(|e| anyhow::anyhow!("Failed to connect to postgres database: {}", e))
 }
 
+pub async fn get_bucket_client(config: &Config) -> Result<BucketClientImpl, Error> {
+  match config.blob_storage.backend {
+    BlobStorageBackend::S3 => {
+      info!("Setting up S3 bucket...");
+      let s3_client = AwsS3BucketClientImpl::new(
+        get_aws_s3_client(&config.s3).await?,
+        config.s3.bucket.clone(),
+        config.s3.minio_url.clone(),
+        config.s3.presigned_url_endpoint.clone(),
+      );
+      Ok(s3_client.into())
+    },
+    BlobStorageBackend::LocalFs => {
+      info!(
+        "Using local file system as the blob storage backend: {}",
+        config.blob_storage.local_path
+      );
+      let fs_client = LocalFsBucketClientImpl::new(&config.blob_storage.local_path).await?;
+      Ok(fs_client.into())
+    },
+  }
+}
+
 pub async fn get_aws_s3_client(s3_setting: &S3Setting) -> Result<aws_sdk_s3::Client, Error> {
   let credentials = Credentials::new(
     s3_setting.access_key.clone(),
@@ -1,3 +1,4 @@
+use std::collections::HashSet;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -32,6 +33,9 @@ pub struct ClientMessageRouter {
   /// The message flow:
   /// ClientSession(websocket) -> [CollabRealtimeServer] -> [ClientMessageRouter] -> [CollabBroadcast] 1->* websocket(client)
   pub(crate) stream_tx: tokio::sync::broadcast::Sender<MessageByObjectId>,
+  /// The workspaces whose collabs the client can't subscribe to, as they require signing in with
+  /// another SSO provider.
+  sso_restricted_workspaces: HashSet<String>,
 }
 
 impl ClientMessageRouter {
@@ -41,9 +45,19 @@ impl ClientMessageRouter {
     Self {
       sink: Arc::new(sink),
       stream_tx,
+      sso_restricted_workspaces: HashSet::new(),
     }
   }
 
+  pub fn with_sso_restricted_workspaces(mut self, workspace_ids: HashSet<String>) -> Self {
+    self.sso_restricted_workspaces = workspace_ids;
+    self
+  }
+
+  pub fn is_sso_restricted(&self, workspace_id: &str) -> bool {
+    self.sso_restricted_workspaces.contains(workspace_id)
+  }
+
   /// Initializes a communication channel for a client and a specific collaboration object.
   ///
   /// sets up a two-way communication channel between the client and the collaboration server,
@@ -12,7 +12,8 @@ use super::disk_cache::CollabDiskCache;
 use super::mem_cache::{cache_exp_secs_from_collab_type, CollabMemCache};
 use crate::CollabMetrics;
 use app_error::AppError;
-use database::file::s3_client_impl::AwsS3BucketClientImpl;
+use database::encryption::CollabEncryption;
+use database::file::bucket_client_impl::BucketClientImpl;
 use database_entity::dto::{CollabParams, PendingCollabWrite, QueryCollab, QueryCollabResult};
 
 #[derive(Clone)]
@@ -27,13 +28,19 @@ impl CollabCache {
   pub fn new(
     redis_conn_manager: redis::aio::ConnectionManager,
     pg_pool: PgPool,
-    s3: AwsS3BucketClientImpl,
+    s3: BucketClientImpl,
     metrics: Arc<CollabMetrics>,
     s3_collab_threshold: usize,
+    encryption: Option<CollabEncryption>,
   ) -> Self {
     let mem_cache = CollabMemCache::new(redis_conn_manager.clone(), metrics.clone());
-    let disk_cache =
-      CollabDiskCache::new(pg_pool.clone(), s3, s3_collab_threshold, metrics.clone());
+    let disk_cache = CollabDiskCache::new(
+      pg_pool.clone(),
+      s3,
+      s3_collab_threshold,
+      metrics.clone(),
+      encryption,
+    );
     Self {
       disk_cache,
       mem_cache,
@@ -183,6 +190,7 @@ impl CollabCache {
       s3,
       self.s3_collab_threshold,
       &self.metrics,
+      self.disk_cache.encryption(),
     )
     .await?;
 
@@ -17,7 +17,8 @@ use database::collab::{
   batch_select_collab_blob, insert_into_af_collab, insert_into_af_collab_bulk_for_user,
   is_collab_exists, select_blob_from_af_collab, AppResult,
 };
-use database::file::s3_client_impl::AwsS3BucketClientImpl;
+use database::encryption::CollabEncryption;
+use database::file::bucket_client_impl::BucketClientImpl;
 use database::file::{BucketClient, ResponseBlob};
 use database_entity::dto::{
   CollabParams, PendingCollabWrite, QueryCollab, QueryCollabResult, ZSTD_COMPRESSION_LEVEL,
@@ -26,23 +27,27 @@ use database_entity::dto::{
 #[derive(Clone)]
 pub struct CollabDiskCache {
   pg_pool: PgPool,
-  s3: AwsS3BucketClientImpl,
+  s3: BucketClientImpl,
   s3_collab_threshold: usize,
   metrics: Arc<CollabMetrics>,
+  /// Encrypts the collabs stored in Postgres and S3, when a master key is configured.
+  encryption: Option<CollabEncryption>,
 }
 
 impl CollabDiskCache {
   pub fn new(
     pg_pool: PgPool,
-    s3: AwsS3BucketClientImpl,
+    s3: BucketClientImpl,
     s3_collab_threshold: usize,
     metrics: Arc<CollabMetrics>,
+    encryption: Option<CollabEncryption>,
   ) -> Self {
     Self {
       pg_pool,
       s3,
       s3_collab_threshold,
       metrics,
+      encryption,
     }
   }
 
@@ -80,6 +85,7 @@ impl CollabDiskCache {
       self.s3.clone(),
       self.s3_collab_threshold,
       &self.metrics,
+      self.encryption.as_ref(),
     )
     .await?;
 
@@ -95,18 +101,24 @@ impl CollabDiskCache {
     Ok(())
   }
 
-  pub fn s3_client(&self) -> AwsS3BucketClientImpl {
+  pub fn s3_client(&self) -> BucketClientImpl {
     self.s3.clone()
   }
 
+  pub fn encryption(&self) -> Option<&CollabEncryption> {
+    self.encryption.as_ref()
+  }
+
+  #[allow(clippy::too_many_arguments)]
   pub async fn upsert_collab_with_transaction(
     workspace_id: &str,
     uid: &i64,
     mut params: CollabParams,
     transaction: &mut Transaction<'_, sqlx::Postgres>,
-    s3: AwsS3BucketClientImpl,
+    s3: BucketClientImpl,
     s3_collab_threshold: usize,
     metrics: &CollabMetrics,
+    encryption: Option<&CollabEncryption>,
   ) -> AppResult<()> {
     let mut delete_from_s3 = Vec::new();
     let key = collab_key(workspace_id, &params.object_id);
@@ -115,6 +127,8 @@ impl CollabDiskCache {
       let encoded_collab = std::mem::take(&mut params.encoded_collab_v1);
       tokio::spawn(Self::insert_blob_with_retries(
         s3.clone(),
+        encryption.cloned(),
+        workspace_id.to_string(),
         key,
         encoded_collab,
         3,
@@ -122,6 +136,11 @@ impl CollabDiskCache {
       metrics.s3_write_collab_count.inc();
     } else {
       // put collab into Postgres (and remove outdated version from S3)
+      if let Some(encryption) = encryption {
+        params.encoded_collab_v1 = encryption
+          .encrypt(workspace_id, params.encoded_collab_v1)
+          .await?;
+      }
       metrics.pg_write_collab_count.inc();
       delete_from_s3.push(key);
     }
@@ -141,7 +160,7 @@ impl CollabDiskCache {
     match self.s3.get_blob(&key).await {
       Ok(resp) => {
         self.metrics.s3_read_collab_count.inc();
-        let blob = resp.to_blob();
+        let blob = self.decrypt(workspace_id, resp.to_blob().into()).await?;
         let now = Instant::now();
         let decompressed = zstd::decode_all(&*blob)?;
         tracing::trace!(
@@ -178,7 +197,8 @@ impl CollabDiskCache {
       match result {
         Ok(data) => {
           self.metrics.pg_read_collab_count.inc();
-          return encode_collab_from_bytes(data).await;
+          let data = self.decrypt(workspace_id, data.into()).await?;
+          return encode_collab_from_bytes(data.to_vec()).await;
         },
         Err(e) => {
           match e {
@@ -222,6 +242,11 @@ impl CollabDiskCache {
         blobs.insert(key, blob);
       } else {
         // put collab into Postgres (and remove outdated version from S3)
+        if let Some(encryption) = &self.encryption {
+          param.encoded_collab_v1 = encryption
+            .encrypt(workspace_id, std::mem::take(&mut param.encoded_collab_v1))
+            .await?;
+        }
         delete_from_s3.push(key);
       }
     }
@@ -234,7 +259,7 @@ impl CollabDiskCache {
     transaction.commit().await?;
     self.metrics.observe_pg_tx(start.elapsed());
 
-    batch_put_collab_to_s3(&self.s3, blobs).await?;
+    batch_put_collab_to_s3(&self.s3, self.encryption.as_ref(), workspace_id, blobs).await?;
     if !delete_from_s3.is_empty() {
       let s3 = self.s3.clone();
       tokio::spawn(async move {
@@ -285,6 +310,7 @@ impl CollabDiskCache {
         s3.clone(),
         self.s3_collab_threshold,
         &self.metrics,
+        self.encryption.as_ref(),
       )
       .await
       {
@@ -319,12 +345,36 @@ impl CollabDiskCache {
     queries: Vec<QueryCollab>,
   ) -> HashMap<String, QueryCollabResult> {
     let mut results = HashMap::new();
-    let not_found = batch_get_collab_from_s3(&self.s3, workspace_id, queries, &mut results).await;
+    let not_found = batch_get_collab_from_s3(
+      &self.s3,
+      self.encryption.as_ref(),
+      workspace_id,
+      queries,
+      &mut results,
+    )
+    .await;
     let s3_fetch = results.len() as u64;
     batch_select_collab_blob(&self.pg_pool, not_found, &mut results).await;
     let pg_fetch = results.len() as u64 - s3_fetch;
     self.metrics.s3_read_collab_count.inc_by(s3_fetch);
     self.metrics.pg_read_collab_count.inc_by(pg_fetch);
+
+    if let Some(encryption) = &self.encryption {
+      // Only the collabs read from Postgres are still encrypted, the other ones are returned as is
+      for result in results.values_mut() {
+        if let QueryCollabResult::Success { encode_collab_v1 } = result {
+          let data = std::mem::take(encode_collab_v1);
+          *result = match encryption.decrypt(workspace_id, data.into()).await {
+            Ok(data) => QueryCollabResult::Success {
+              encode_collab_v1: data.to_vec(),
+            },
+            Err(err) => QueryCollabResult::Failed {
+              error: err.to_string(),
+            },
+          };
+        }
+      }
+    }
     results
   }
 
@@ -347,13 +397,25 @@ impl CollabDiskCache {
     }
   }
 
+  async fn decrypt(&self, workspace_id: &str, data: Bytes) -> Result<Bytes, AppError> {
+    match &self.encryption {
+      Some(encryption) => encryption.decrypt(workspace_id, data).await,
+      None => Ok(data),
+    }
+  }
+
   async fn insert_blob_with_retries(
-    s3: AwsS3BucketClientImpl,
+    s3: BucketClientImpl,
+    encryption: Option<CollabEncryption>,
+    workspace_id: String,
     key: String,
     blob: Bytes,
     mut retries: usize,
   ) -> Result<(), AppError> {
-    let doc_state = Self::compress_encoded_collab(blob)?;
+    let mut doc_state = Self::compress_encoded_collab(blob)?;
+    if let Some(encryption) = encryption {
+      doc_state = encryption.encrypt(&workspace_id, doc_state).await?;
+    }
     while let Err(err) = s3.put_blob(&key, doc_state.clone().into(), None).await {
       match err {
         AppError::ServiceTemporaryUnavailable(err) if retries > 0 => {
@@ -391,15 +453,22 @@ impl CollabDiskCache {
 }
 
 async fn batch_put_collab_to_s3(
-  s3: &AwsS3BucketClientImpl,
+  s3: &BucketClientImpl,
+  encryption: Option<&CollabEncryption>,
+  workspace_id: &str,
   collabs: HashMap<String, Bytes>,
 ) -> Result<(), AppError> {
   let mut join_set = JoinSet::<Result<(), AppError>>::new();
   let mut i = 0;
   for (key, blob) in collabs {
     let s3 = s3.clone();
+    let encryption = encryption.cloned();
+    let workspace_id = workspace_id.to_string();
     join_set.spawn(async move {
-      let compressed = CollabDiskCache::compress_encoded_collab(blob)?;
+      let mut compressed = CollabDiskCache::compress_encoded_collab(blob)?;
+      if let Some(encryption) = encryption {
+        compressed = encryption.encrypt(&workspace_id, compressed).await?;
+      }
       s3.put_blob(&key, compressed.into(), None).await?;
       Ok(())
     });
@@ -419,7 +488,8 @@ async fn batch_put_collab_to_s3(
 }
 
 async fn batch_get_collab_from_s3(
-  s3: &AwsS3BucketClientImpl,
+  s3: &BucketClientImpl,
+  encryption: Option<&CollabEncryption>,
   workspace_id: &str,
   params: Vec<QueryCollab>,
   results: &mut HashMap<String, QueryCollabResult>,
@@ -482,9 +552,20 @@ async fn batch_get_collab_from_s3(
   for query in params {
     let key = collab_key(workspace_id, &query.object_id);
     let s3 = s3.clone();
+    let encryption = encryption.cloned();
+    let workspace_id = workspace_id.to_string();
     join_set.spawn(async move {
       match s3.get_blob(&key).await {
-        Ok(resp) => GetResult::Found(query.object_id, resp.to_blob()),
+        Ok(resp) => match encryption {
+          Some(encryption) => match encryption
+            .decrypt(&workspace_id, resp.to_blob().into())
+            .await
+          {
+            Ok(blob) => GetResult::Found(query.object_id, blob.to_vec()),
+            Err(err) => GetResult::Error(query.object_id, err.to_string()),
+          },
+          None => GetResult::Found(query.object_id, resp.to_blob()),
+        },
         Err(AppError::RecordNotFound(_)) => GetResult::NotFound(query),
         Err(err) => GetResult::Error(query.object_id, err.to_string()),
       }
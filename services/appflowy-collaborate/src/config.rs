@@ -19,6 +19,7 @@ pub struct Config {
   pub redis_worker_count: usize,
   pub ai: AISettings,
   pub s3: S3Setting,
+  pub blob_storage: BlobStorageSetting,
 }
 
 #[derive(serde::Deserialize, Clone, Debug)]
@@ -33,6 +34,32 @@ pub struct S3Setting {
   pub presigned_url_endpoint: Option<String>,
 }
 
+#[derive(Clone, Debug)]
+pub enum BlobStorageBackend {
+  S3,
+  LocalFs,
+}
+
+impl TryFrom<&str> for BlobStorageBackend {
+  type Error = anyhow::Error;
+
+  fn try_from(value: &str) -> Result<Self, Self::Error> {
+    match value {
+      "s3" => Ok(BlobStorageBackend::S3),
+      "local_fs" => Ok(BlobStorageBackend::LocalFs),
+      _ => Err(anyhow::anyhow!("Invalid BlobStorageBackend")),
+    }
+  }
+}
+
+#[derive(Clone, Debug)]
+pub struct BlobStorageSetting {
+  pub backend: BlobStorageBackend,
+  /// Directory where blobs are stored when using [BlobStorageBackend::LocalFs]. It must be shared
+  /// with the API server and the worker.
+  pub local_path: String,
+}
+
 #[derive(Clone, Debug)]
 pub struct ApplicationSetting {
   pub port: u16,
@@ -133,6 +160,9 @@ pub struct CollabSetting {
   pub edit_state_max_count: u32,
   pub edit_state_max_secs: i64,
   pub s3_collab_threshold: u64,
+  /// Master keys used to encrypt the collabs at rest, formatted as `<key id>:<hex encoded key>`
+  /// and separated by commas, the first key being the current one. Empty disables the encryption.
+  pub encryption_master_keys: Secret<String>,
 }
 
 pub fn get_env_var(key: &str, default: &str) -> String {
@@ -193,6 +223,12 @@ pub fn get_configuration() -> Result<Config, anyhow::Error> {
       region: get_env_var("APPFLOWY_S3_REGION", ""),
       presigned_url_endpoint: None,
     },
+    blob_storage: BlobStorageSetting {
+      backend: get_env_var("APPFLOWY_BLOB_STORAGE_BACKEND", "s3")
+        .as_str()
+        .try_into()?,
+      local_path: get_env_var("APPFLOWY_BLOB_STORAGE_LOCAL_PATH", "./data/blob"),
+    },
     gotrue: GoTrueSetting {
       jwt_secret: get_env_var("APPFLOWY_GOTRUE_JWT_SECRET", "hello456").into(),
     },
@@ -207,6 +243,7 @@ pub fn get_configuration() -> Result<Config, anyhow::Error> {
       edit_state_max_count: get_env_var("APPFLOWY_COLLAB_EDIT_STATE_MAX_COUNT", "100").parse()?,
       edit_state_max_secs: get_env_var("APPFLOWY_COLLAB_EDIT_STATE_MAX_SECS", "60").parse()?,
       s3_collab_threshold: get_env_var("APPFLOWY_COLLAB_S3_THRESHOLD", "8000").parse()?,
+      encryption_master_keys: get_env_var("APPFLOWY_COLLAB_ENCRYPTION_MASTER_KEYS", "").into(),
     },
     redis_uri: get_env_var("APPFLOWY_REDIS_URI", "redis://localhost:6379").into(),
     redis_worker_count: get_env_var("APPFLOWY_REDIS_WORKERS", "60").parse()?,
@@ -37,6 +37,9 @@ pub enum RealtimeError {
   #[error("Client:{0} does not have enough permission to read")]
   NotEnoughPermissionToRead(i64),
 
+  #[error("Workspace:{0} requires signing in with its SSO provider")]
+  SsoSignInRequired(String),
+
   #[error("{0}")]
   UserNotFound(String),
 
@@ -81,6 +84,9 @@ pub enum RealtimeError {
 
   #[error("failed to send ws message: {0}")]
   SendWSMessageFailed(String),
+
+  #[error("collab {0} is end-to-end encrypted")]
+  EndToEndEncrypted(String),
 }
 
 #[derive(Debug)]
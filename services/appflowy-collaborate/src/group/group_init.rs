@@ -1,4 +1,5 @@
 use crate::error::RealtimeError;
+use access_control::collab::RealtimeAccessControl;
 use anyhow::anyhow;
 use app_error::AppError;
 use arc_swap::ArcSwap;
@@ -23,11 +24,16 @@ use collab_document::document::DocumentBody;
 use collab_stream::error::StreamError;
 use collab_stream::model::{AwarenessStreamUpdate, CollabStreamUpdate, MessageId, UpdateFlags};
 use dashmap::DashMap;
-use database::collab::{CollabStorage, GetCollabOrigin};
+use database::collab::{
+  compact_encrypted_collab_updates, insert_encrypted_collab_update,
+  select_encrypted_collab_updates, CollabStorage, GetCollabOrigin,
+};
+use database::pg_row::AFCollabEncryptedUpdateRow;
 use database_entity::dto::{CollabParams, QueryCollabParams};
 use futures::{pin_mut, Sink, Stream};
 use futures_util::{SinkExt, StreamExt};
 use indexer::scheduler::{IndexerScheduler, UnindexedCollabTask, UnindexedData};
+use sqlx::PgPool;
 use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant, SystemTime};
@@ -62,6 +68,12 @@ struct CollabGroupState {
   seq_no: AtomicU32,
   /// The most recent state vector from a redis update.
   state_vector: RwLock<StateVector>,
+  /// For the collabs of an end-to-end encrypted workspace: the sequence number of the last stored
+  /// update sent to a client in response to its sync step 1, by client origin.
+  encrypted_sync_seq: DashMap<String, i64>,
+  /// Used to check that a client can write the encrypted updates it sends, as the server can't
+  /// tell them apart from a compaction that replaces the updates of every other client.
+  access_control: Arc<dyn RealtimeAccessControl>,
 }
 
 impl Drop for CollabGroup {
@@ -85,6 +97,9 @@ impl CollabGroup {
     prune_grace_period: Duration,
     state_vector: StateVector,
     indexer_scheduler: Arc<IndexerScheduler>,
+    pg_pool: PgPool,
+    end_to_end_encryption: bool,
+    access_control: Arc<dyn RealtimeAccessControl>,
   ) -> Result<Self, StreamError>
   where
     S: CollabStorage,
@@ -100,6 +115,8 @@ impl CollabGroup {
       indexer_scheduler,
       metrics.clone(),
       prune_grace_period,
+      pg_pool,
+      end_to_end_encryption,
     );
 
     let state = Arc::new(CollabGroupState {
@@ -113,6 +130,8 @@ impl CollabGroup {
       last_activity: ArcSwap::new(Instant::now().into()),
       seq_no: AtomicU32::new(0),
       state_vector: state_vector.into(),
+      encrypted_sync_seq: DashMap::new(),
+      access_control,
     });
 
     /*
@@ -209,21 +228,24 @@ impl CollabGroup {
   }
 
   async fn handle_inbound_update(state: &CollabGroupState, update: CollabStreamUpdate) {
-    // update state vector based on incoming message
-    match Update::decode_v1(&update.data) {
-      Ok(update) => state
-        .state_vector
-        .write()
-        .await
-        .merge(update.state_vector()),
-      Err(err) => {
-        tracing::error!(
-          "received malformed update for collab `{}`: {}",
-          state.object_id,
-          err
-        );
-        return;
-      },
+    // update state vector based on incoming message. The updates of an end-to-end encrypted collab
+    // can't be decoded, so they are broadcast as is.
+    if !state.persister.end_to_end_encryption {
+      match Update::decode_v1(&update.data) {
+        Ok(update) => state
+          .state_vector
+          .write()
+          .await
+          .merge(update.state_vector()),
+        Err(err) => {
+          tracing::error!(
+            "received malformed update for collab `{}`: {}",
+            state.object_id,
+            err
+          );
+          return;
+        },
+      }
     }
 
     let seq_num = state.seq_no.fetch_add(1, Ordering::SeqCst) + 1;
@@ -353,6 +375,11 @@ impl CollabGroup {
   /// Generate embedding for the current Collab immediately
   ///
   pub async fn generate_embeddings(&self) -> Result<(), AppError> {
+    if self.state.persister.end_to_end_encryption {
+      return Err(AppError::EndToEndEncrypted {
+        workspace_id: Uuid::parse_str(&self.state.workspace_id)?,
+      });
+    }
     let collab = self
       .encode_collab()
       .await
@@ -380,6 +407,11 @@ impl CollabGroup {
     &self,
     state_vector: StateVector,
   ) -> Result<Vec<u8>, RealtimeError> {
+    if self.state.persister.end_to_end_encryption {
+      return Err(RealtimeError::EndToEndEncrypted(
+        self.state.object_id.clone(),
+      ));
+    }
     {
       // first check if we need to send any updates
       let collab_sv = self.state.state_vector.read().await;
@@ -671,6 +703,9 @@ impl CollabGroup {
     origin: &CollabOrigin,
     msg: Message,
   ) -> Result<Option<Vec<u8>>, RTProtocolError> {
+    if state.persister.end_to_end_encryption {
+      return Self::handle_encrypted_protocol_message(state, origin, msg).await;
+    }
     match msg {
       Message::Sync(msg) => match msg {
         SyncMessage::SyncStep1(sv) => Self::handle_sync_step1(state, &sv).await,
@@ -684,6 +719,116 @@ impl CollabGroup {
     }
   }
 
+  /// Handles the messages of a collab of an end-to-end encrypted workspace. The updates are
+  /// encrypted by the clients, so they are stored and broadcast as is, and the response to a sync
+  /// step 1 contains all the stored updates, since the server can't tell which ones are missing.
+  ///
+  /// The response ends with a sync step 1 with an empty state vector, so the client answers with
+  /// a sync step 2 holding its whole document state, which includes the updates it just received.
+  /// That sync step 2 then replaces those updates, which keeps the number of stored updates, and
+  /// the size of the next responses, bounded. The replaced updates are only deleted by the next
+  /// compaction, see [compact_encrypted_collab_updates].
+  async fn handle_encrypted_protocol_message(
+    state: &CollabGroupState,
+    origin: &CollabOrigin,
+    msg: Message,
+  ) -> Result<Option<Vec<u8>>, RTProtocolError> {
+    match msg {
+      Message::Sync(SyncMessage::SyncStep1(_)) => {
+        let updates = state
+          .persister
+          .load_encrypted_updates()
+          .await
+          .map_err(|err| RTProtocolError::Internal(err.into()))?;
+        tracing::trace!(
+          "sending {} encrypted updates of {} to client",
+          updates.len(),
+          state.object_id
+        );
+        match updates.last() {
+          Some(last) => {
+            state
+              .encrypted_sync_seq
+              .insert(origin.to_string(), last.seq);
+          },
+          None => {
+            state.encrypted_sync_seq.remove(&origin.to_string());
+          },
+        }
+        let mut encoder = EncoderV1::new();
+        for update in updates {
+          Message::Sync(SyncMessage::SyncStep2(update.data)).encode(&mut encoder);
+        }
+        // The client computes what the server is missing from the updates above, as the server
+        // can't read their state vectors.
+        Message::Sync(SyncMessage::SyncStep1(StateVector::default())).encode(&mut encoder);
+        Ok(Some(encoder.to_vec()))
+      },
+      Message::Sync(SyncMessage::SyncStep2(update)) => {
+        state.metrics.collab_size.observe(update.len() as f64);
+        Self::check_encrypted_write_permission(state, origin).await?;
+        match state.encrypted_sync_seq.remove(&origin.to_string()) {
+          Some((_, up_to_seq)) => {
+            tracing::trace!(
+              "compacting encrypted updates of {} up to {}",
+              state.object_id,
+              up_to_seq
+            );
+            state
+              .persister
+              .compact_encrypted_updates(origin.clone(), up_to_seq, update)
+              .await
+          },
+          None => {
+            state
+              .persister
+              .save_encrypted_update(origin.clone(), update)
+              .await
+          },
+        }
+        .map_err(|err| RTProtocolError::Internal(err.into()))?;
+        Ok(None)
+      },
+      Message::Sync(SyncMessage::Update(update)) => {
+        state.metrics.collab_size.observe(update.len() as f64);
+        Self::check_encrypted_write_permission(state, origin).await?;
+        state
+          .persister
+          .save_encrypted_update(origin.clone(), update)
+          .await
+          .map_err(|err| RTProtocolError::Internal(err.into()))?;
+        Ok(None)
+      },
+      Message::Awareness(update) => Self::handle_awareness_update(state, origin, update).await,
+      Message::Auth(_reason) => Ok(None),
+      Message::Custom(_msg) => Ok(None),
+    }
+  }
+
+  /// Storing an update of an end-to-end encrypted collab, and compacting its updates in
+  /// particular, requires the permission to write the collab.
+  async fn check_encrypted_write_permission(
+    state: &CollabGroupState,
+    origin: &CollabOrigin,
+  ) -> Result<(), RTProtocolError> {
+    let uid = origin
+      .client_user_id()
+      .ok_or_else(|| RTProtocolError::PermissionDenied {
+        reason: format!("{} is not a client", origin),
+      })?;
+    let can_write = state
+      .access_control
+      .can_write_collab(&state.workspace_id, &uid, &state.object_id)
+      .await
+      .map_err(|err| RTProtocolError::Internal(err.into()))?;
+    if !can_write {
+      return Err(RTProtocolError::PermissionDenied {
+        reason: format!("user {} can't write {}", uid, state.object_id),
+      });
+    }
+    Ok(())
+  }
+
   async fn handle_sync_step1(
     state: &CollabGroupState,
     remote_sv: &StateVector,
@@ -884,6 +1029,10 @@ struct CollabPersister {
   /// A grace period for prunning Redis collab updates. Instead of deleting all messages we
   /// read right away, we give 1min for other potential client to catch up.
   prune_grace_period: Duration,
+  pg_pool: PgPool,
+  /// Whether the collab belongs to an end-to-end encrypted workspace. Its updates are then stored
+  /// in `af_collab_encrypted_update`, and the Redis stream is only used to broadcast them.
+  end_to_end_encryption: bool,
 }
 
 impl CollabPersister {
@@ -898,6 +1047,8 @@ impl CollabPersister {
     indexer_scheduler: Arc<IndexerScheduler>,
     metrics: Arc<CollabRealtimeMetrics>,
     prune_grace_period: Duration,
+    pg_pool: PgPool,
+    end_to_end_encryption: bool,
   ) -> Self {
     let update_sink = collab_redis_stream.collab_update_sink(&workspace_id, &object_id);
     let awareness_sink = collab_redis_stream.awareness_update_sink(&workspace_id, &object_id);
@@ -913,9 +1064,62 @@ impl CollabPersister {
       update_sink,
       awareness_sink,
       prune_grace_period,
+      pg_pool,
+      end_to_end_encryption,
     }
   }
 
+  /// Stores an update of an end-to-end encrypted collab, and sends it to the Redis stream to
+  /// broadcast it to the other subscribers.
+  async fn save_encrypted_update(
+    &self,
+    sender: CollabOrigin,
+    update: Vec<u8>,
+  ) -> Result<MessageId, RealtimeError> {
+    let workspace_id =
+      Uuid::parse_str(&self.workspace_id).map_err(|err| RealtimeError::Internal(err.into()))?;
+    insert_encrypted_collab_update(&self.pg_pool, &workspace_id, &self.object_id, &update)
+      .await
+      .map_err(|err| RealtimeError::Internal(err.into()))?;
+    let msg_id = self.send_update(sender, update).await?;
+    Ok(msg_id)
+  }
+
+  /// Replaces the stored updates of an end-to-end encrypted collab, up to and including
+  /// `up_to_seq`, with the given update, and sends it to the Redis stream to broadcast it to the
+  /// other subscribers.
+  async fn compact_encrypted_updates(
+    &self,
+    sender: CollabOrigin,
+    up_to_seq: i64,
+    update: Vec<u8>,
+  ) -> Result<MessageId, RealtimeError> {
+    let workspace_id =
+      Uuid::parse_str(&self.workspace_id).map_err(|err| RealtimeError::Internal(err.into()))?;
+    let mut txn = self
+      .pg_pool
+      .begin()
+      .await
+      .map_err(|err| RealtimeError::Internal(err.into()))?;
+    compact_encrypted_collab_updates(&mut txn, &workspace_id, &self.object_id, up_to_seq, &update)
+      .await
+      .map_err(|err| RealtimeError::Internal(err.into()))?;
+    txn
+      .commit()
+      .await
+      .map_err(|err| RealtimeError::Internal(err.into()))?;
+    let msg_id = self.send_update(sender, update).await?;
+    Ok(msg_id)
+  }
+
+  async fn load_encrypted_updates(&self) -> Result<Vec<AFCollabEncryptedUpdateRow>, RealtimeError> {
+    let workspace_id =
+      Uuid::parse_str(&self.workspace_id).map_err(|err| RealtimeError::Internal(err.into()))?;
+    select_encrypted_collab_updates(&self.pg_pool, &workspace_id, &self.object_id)
+      .await
+      .map_err(|err| RealtimeError::Internal(err.into()))
+  }
+
   async fn send_update(
     &self,
     sender: CollabOrigin,
@@ -967,6 +1171,15 @@ impl CollabPersister {
     };
     self.metrics.load_collab_count.inc();
 
+    // The updates of an end-to-end encrypted collab can't be applied, so only the state stored
+    // when the collab was created is returned.
+    if self.end_to_end_encryption {
+      return Ok(CollabSnapshot {
+        collab,
+        last_message_id: None,
+      });
+    }
+
     // 2. consume all Redis updates on top of it (keep redis msg id)
     let mut last_message_id = None;
     let mut tx = collab.transact_mut();
@@ -1073,6 +1286,11 @@ impl CollabPersister {
   }
 
   async fn save(&self) -> Result<(), RealtimeError> {
+    if self.end_to_end_encryption {
+      // the updates are already stored in the database as they are received
+      return self.prune_update_stream().await;
+    }
+
     // load collab but only if there were pending updates in Redis
     if let Some(mut snapshot) = self.load_if_changed().await? {
       tracing::debug!("requesting save for collab {}", self.object_id);
@@ -1132,16 +1350,7 @@ impl CollabPersister {
       );
 
       // 3. finally we can drop Redis messages
-      let now = SystemTime::UNIX_EPOCH.elapsed().unwrap().as_millis();
-      let msg_id = MessageId {
-        timestamp_ms: (now - self.prune_grace_period.as_millis()) as u64,
-        sequence_number: 0,
-      };
-      let stream_key = CollabStreamUpdate::stream_key(&self.workspace_id, &self.object_id);
-      self
-        .collab_redis_stream
-        .prune_update_stream(&stream_key, msg_id)
-        .await?;
+      self.prune_update_stream().await?;
 
       let _ = lease.release().await;
     }
@@ -1149,6 +1358,21 @@ impl CollabPersister {
     Ok(())
   }
 
+  /// Drops the Redis messages older than the prune grace period.
+  async fn prune_update_stream(&self) -> Result<(), RealtimeError> {
+    let now = SystemTime::UNIX_EPOCH.elapsed().unwrap().as_millis();
+    let msg_id = MessageId {
+      timestamp_ms: (now - self.prune_grace_period.as_millis()) as u64,
+      sequence_number: 0,
+    };
+    let stream_key = CollabStreamUpdate::stream_key(&self.workspace_id, &self.object_id);
+    self
+      .collab_redis_stream
+      .prune_update_stream(&stream_key, msg_id)
+      .await?;
+    Ok(())
+  }
+
   async fn trim_awareness(&self) -> Result<(), RealtimeError> {
     let stream_key = AwarenessStreamUpdate::stream_key(&self.workspace_id, &self.object_id);
     self
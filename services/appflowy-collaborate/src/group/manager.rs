@@ -12,8 +12,11 @@ use collab_rt_entity::user::RealtimeUser;
 use collab_rt_entity::CollabMessage;
 use collab_stream::client::CollabRedisStream;
 use database::collab::{CollabStorage, GetCollabOrigin};
+use database::workspace::select_workspace_end_to_end_encryption;
 use database_entity::dto::QueryCollabParams;
+use sqlx::PgPool;
 use tracing::{instrument, trace};
+use uuid::Uuid;
 use yrs::{ReadTxn, StateVector};
 
 use crate::client::client_msg_router::ClientMessageRouter;
@@ -32,6 +35,7 @@ pub struct GroupManager<S> {
   persistence_interval: Duration,
   prune_grace_period: Duration,
   indexer_scheduler: Arc<IndexerScheduler>,
+  pg_pool: PgPool,
 }
 
 impl<S> GroupManager<S>
@@ -47,6 +51,7 @@ where
     persistence_interval: Duration,
     prune_grace_period: Duration,
     indexer_scheduler: Arc<IndexerScheduler>,
+    pg_pool: PgPool,
   ) -> Result<Self, RealtimeError> {
     let collab_stream = Arc::new(collab_stream);
     Ok(Self {
@@ -58,6 +63,7 @@ where
       persistence_interval,
       prune_grace_period,
       indexer_scheduler,
+      pg_pool,
     })
   }
 
@@ -91,6 +97,11 @@ where
     // Lock the group and subscribe the user to the group.
     if let Some(mut e) = self.state.get_mut_group(object_id).await {
       let group = e.value_mut();
+      if client_msg_router.is_sso_restricted(group.workspace_id()) {
+        return Err(RealtimeError::SsoSignInRequired(
+          group.workspace_id().to_string(),
+        ));
+      }
       trace!("[realtime]: {} subscribe group:{}", user, object_id,);
       let (sink, stream) = client_msg_router.init_client_communication::<CollabMessage>(
         group.workspace_id(),
@@ -136,6 +147,12 @@ where
       Err(err) if err.is_record_not_found() => StateVector::default(),
       Err(err) => return Err(RealtimeError::CannotCreateGroup(err.to_string())),
     };
+    let workspace_uuid = Uuid::parse_str(workspace_id)
+      .map_err(|err| RealtimeError::CannotCreateGroup(err.to_string()))?;
+    let end_to_end_encryption =
+      select_workspace_end_to_end_encryption(&self.pg_pool, &workspace_uuid)
+        .await
+        .map_err(|err| RealtimeError::CannotCreateGroup(err.to_string()))?;
 
     trace!(
       "[realtime]: create group: uid:{},workspace_id:{},object_id:{}:{}",
@@ -157,6 +174,9 @@ where
       self.prune_grace_period,
       state_vector,
       self.indexer_scheduler.clone(),
+      self.pg_pool.clone(),
+      end_to_end_encryption,
+      self.access_control.clone(),
     )?;
     self.state.insert_group(object_id, group);
     Ok(())
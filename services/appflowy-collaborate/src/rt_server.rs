@@ -1,3 +1,4 @@
+use std::collections::HashSet;
 use std::sync::{Arc, Weak};
 use std::time::Duration;
 
@@ -11,6 +12,7 @@ use collab_stream::stream_router::StreamRouter;
 use dashmap::mapref::entry::Entry;
 use dashmap::DashMap;
 use redis::aio::ConnectionManager;
+use sqlx::PgPool;
 use tokio::sync::mpsc::Sender;
 use tokio::task::yield_now;
 use tokio::time::interval;
@@ -58,6 +60,7 @@ where
     group_persistence_interval: Duration,
     prune_grace_period: Duration,
     indexer_scheduler: Arc<IndexerScheduler>,
+    pg_pool: PgPool,
   ) -> Result<Self, RealtimeError> {
     let enable_custom_runtime = get_env_var("APPFLOWY_COLLABORATE_MULTI_THREAD", "false")
       .parse::<bool>()
@@ -81,6 +84,7 @@ where
         group_persistence_interval,
         prune_grace_period,
         indexer_scheduler.clone(),
+        pg_pool,
       )
       .await?,
     );
@@ -115,8 +119,10 @@ where
     &self,
     connected_user: RealtimeUser,
     conn_sink: impl RealtimeClientWebsocketSink,
+    sso_restricted_workspaces: HashSet<String>,
   ) -> Result<(), RealtimeError> {
-    let new_client_router = ClientMessageRouter::new(conn_sink);
+    let new_client_router =
+      ClientMessageRouter::new(conn_sink).with_sso_restricted_workspaces(sso_restricted_workspaces);
     if let Some(old_user) = self
       .connect_state
       .handle_user_connect(connected_user, new_client_router)
@@ -1,6 +1,7 @@
 use std::sync::Arc;
 use std::time::Duration;
 
+use bytes::Bytes;
 use chrono::{DateTime, Utc};
 use collab::entity::{EncodedCollab, EncoderVersion};
 use collab_entity::CollabType;
@@ -13,7 +14,8 @@ use database::collab::{
   get_all_collab_snapshot_meta, latest_snapshot_time, select_snapshot, AppResult,
   COLLAB_SNAPSHOT_LIMIT, SNAPSHOT_PER_HOUR,
 };
-use database::file::s3_client_impl::AwsS3BucketClientImpl;
+use database::encryption::CollabEncryption;
+use database::file::bucket_client_impl::BucketClientImpl;
 use database::file::{BucketClient, ResponseBlob};
 use database::history::ops::get_latest_snapshot;
 use database_entity::dto::{
@@ -64,20 +66,25 @@ fn get_meta(objct_key: String) -> Option<AFSnapshotMeta> {
 #[derive(Clone)]
 pub struct SnapshotControl {
   pg_pool: PgPool,
-  s3: AwsS3BucketClientImpl,
+  s3: BucketClientImpl,
   collab_metrics: Arc<CollabMetrics>,
+  /// Snapshots are encrypted at rest like the collabs stored in S3, see
+  /// [crate::collab::cache::disk_cache::CollabDiskCache].
+  encryption: Option<CollabEncryption>,
 }
 
 impl SnapshotControl {
   pub async fn new(
     pg_pool: PgPool,
-    s3: AwsS3BucketClientImpl,
+    s3: BucketClientImpl,
     collab_metrics: Arc<CollabMetrics>,
+    encryption: Option<CollabEncryption>,
   ) -> Self {
     Self {
       pg_pool,
       s3,
       collab_metrics,
+      encryption,
     }
   }
 
@@ -120,7 +127,11 @@ impl SnapshotControl {
     let timestamp = Utc::now();
     let snapshot_id = timestamp.timestamp_millis();
     let key = collab_snapshot_key(&params.workspace_id, &params.object_id, snapshot_id);
-    let compressed = zstd::encode_all(params.doc_state.as_ref(), ZSTD_COMPRESSION_LEVEL)?;
+    let mut compressed: Bytes =
+      zstd::encode_all(params.doc_state.as_ref(), ZSTD_COMPRESSION_LEVEL)?.into();
+    if let Some(encryption) = &self.encryption {
+      compressed = encryption.encrypt(&params.workspace_id, compressed).await?;
+    }
     if let Err(err) = self.s3.put_blob(&key, compressed.into(), None).await {
       self.collab_metrics.write_snapshot_failures.inc();
       return Err(err);
@@ -166,7 +177,7 @@ impl SnapshotControl {
     match self.s3.get_blob(&key).await {
       Ok(resp) => {
         self.collab_metrics.read_snapshot.inc();
-        let decompressed = zstd::decode_all(&*resp.to_blob())?;
+        let decompressed = self.decode_snapshot(workspace_id, resp.to_blob()).await?;
         let encoded_collab = EncodedCollab {
           state_vector: Default::default(),
           doc_state: decompressed.into(),
@@ -252,7 +263,7 @@ impl SnapshotControl {
     let mut resp = self.s3.list_dir(&snapshot_prefix, 1).await?;
     if let Some(key) = resp.pop() {
       let resp = self.s3.get_blob(&key).await?;
-      let decompressed = zstd::decode_all(&*resp.to_blob())?;
+      let decompressed = self.decode_snapshot(workspace_id, resp.to_blob()).await?;
       let encoded_collab = EncodedCollab {
         state_vector: Default::default(),
         doc_state: decompressed.into(),
@@ -277,6 +288,19 @@ impl SnapshotControl {
     }
   }
 
+  /// Decrypts and decompresses the snapshot stored in S3. Snapshots stored before the encryption
+  /// was enabled are only decompressed.
+  async fn decode_snapshot(&self, workspace_id: &str, blob: Vec<u8>) -> AppResult<Vec<u8>> {
+    let blob = match &self.encryption {
+      Some(encryption) => encryption
+        .decrypt(workspace_id, blob.into())
+        .await?
+        .to_vec(),
+      None => blob,
+    };
+    Ok(zstd::decode_all(&*blob)?)
+  }
+
   async fn latest_snapshot_time(
     &self,
     workspace_id: &str,
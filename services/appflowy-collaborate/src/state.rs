@@ -24,6 +24,7 @@ pub type RedisConnectionManager = redis::aio::ConnectionManager;
 #[derive(Clone)]
 pub struct AppState {
   pub config: Arc<Config>,
+  pub pg_pool: PgPool,
   pub pg_listeners: Arc<PgListeners>,
   pub user_cache: UserCache,
   pub redis_stream_router: Arc<StreamRouter>,
@@ -1,14 +1,20 @@
-use crate::config::{Config, DatabaseSetting, Environment, S3Setting};
+use crate::config::{BlobStorageBackend, Config, DatabaseSetting, Environment, S3Setting};
 use anyhow::Error;
 use redis::aio::ConnectionManager;
 use sqlx::postgres::PgPoolOptions;
 use sqlx::PgPool;
 
+use crate::blob_gc_worker::{run_blob_gc_worker, BlobGcConfig};
+use crate::export_worker::{run_export_worker, ExportWorkerConfig};
 use crate::import_worker::worker::run_import_worker;
+use crate::publish_worker::{run_publish_worker, PublishWorkerConfig};
 use aws_sdk_s3::config::{Credentials, Region, SharedCredentialsProvider};
 
 use crate::import_worker::email_notifier::EmailNotifier;
-use crate::s3_client::S3ClientImpl;
+use crate::s3_client::{S3Client, S3ClientImpl};
+use crate::upload_session_worker::{run_upload_session_cleanup_worker, UploadSessionCleanupConfig};
+use database::encryption::CollabEncryption;
+use database::file::fs_client_impl::LocalFsBucketClientImpl;
 
 use axum::Router;
 
@@ -93,8 +99,12 @@ pub async fn create_app(listener: TcpListener, config: Config) -> Result<(), Err
     .expect("failed to get redis connection manager");
 
   let mailer = get_worker_mailer(&config).await?;
-  let s3_client = get_aws_s3_client(&config.s3_setting).await?;
+  let s3_client = get_blob_client(&config).await?;
   let metrics = AppMetrics::new();
+  let collab_encryption = CollabEncryption::new(
+    pg_pool.clone(),
+    config.collab_encryption_master_keys.expose_secret(),
+  )?;
 
   let state = AppState {
     redis_client,
@@ -120,7 +130,8 @@ pub async fn create_app(listener: TcpListener, config: Config) -> Result<(), Err
     state.pg_pool.clone(),
     state.redis_client.clone(),
     Some(state.metrics.import_metrics.clone()),
-    Arc::new(state.s3_client.clone()),
+    state.s3_client.clone(),
+    collab_encryption.clone(),
     Arc::new(email_notifier),
     "import_task_stream",
     tick_interval,
@@ -152,6 +163,84 @@ pub async fn create_app(listener: TcpListener, config: Config) -> Result<(), Err
     },
   ));
 
+  tokio::spawn(run_export_worker(
+    state.pg_pool.clone(),
+    state.redis_client.clone(),
+    state.s3_client.clone(),
+    collab_encryption.clone(),
+    state.mailer.clone(),
+    ExportWorkerConfig {
+      enable: get_env_var("APPFLOWY_WORKER_EXPORT_ENABLED", "true")
+        .parse::<bool>()
+        .unwrap_or(true),
+      tick_interval_secs: get_env_var("APPFLOWY_WORKER_EXPORT_TICK_INTERVAL", "10")
+        .parse::<u64>()
+        .unwrap_or(10),
+      archive_expiration_hours: get_env_var("APPFLOWY_WORKER_EXPORT_EXPIRATION_HOURS", "72")
+        .parse::<u64>()
+        .unwrap_or(72),
+    },
+  ));
+
+  tokio::spawn(run_publish_worker(
+    state.pg_pool.clone(),
+    state.redis_client.clone(),
+    state.s3_client.clone(),
+    collab_encryption.clone(),
+    PublishWorkerConfig {
+      enable: get_env_var("APPFLOWY_WORKER_PUBLISH_ENABLED", "true")
+        .parse::<bool>()
+        .unwrap_or(true),
+      tick_interval_secs: get_env_var("APPFLOWY_WORKER_PUBLISH_TICK_INTERVAL", "30")
+        .parse::<u64>()
+        .unwrap_or(30),
+      auto_republish_interval_secs: get_env_var(
+        "APPFLOWY_WORKER_PUBLISH_AUTO_REPUBLISH_INTERVAL",
+        "300",
+      )
+      .parse::<u64>()
+      .unwrap_or(300),
+    },
+  ));
+
+  tokio::spawn(run_blob_gc_worker(
+    state.pg_pool.clone(),
+    state.s3_client.clone(),
+    collab_encryption,
+    BlobGcConfig {
+      enable: get_env_var("APPFLOWY_WORKER_BLOB_GC_ENABLED", "true")
+        .parse::<bool>()
+        .unwrap_or(true),
+      tick_interval_secs: get_env_var("APPFLOWY_WORKER_BLOB_GC_TICK_INTERVAL", "21600")
+        .parse::<u64>()
+        .unwrap_or(21600),
+      grace_period: sqlx::types::chrono::Duration::days(
+        get_env_var("APPFLOWY_WORKER_BLOB_GC_GRACE_PERIOD_DAYS", "30")
+          .parse::<i64>()
+          .unwrap_or(30),
+      ),
+      dry_run: get_env_var("APPFLOWY_WORKER_BLOB_GC_DRY_RUN", "true")
+        .parse::<bool>()
+        .unwrap_or(true),
+    },
+  ));
+
+  tokio::spawn(run_upload_session_cleanup_worker(
+    state.pg_pool.clone(),
+    state.s3_client.clone(),
+    UploadSessionCleanupConfig {
+      enable: get_env_var("APPFLOWY_WORKER_UPLOAD_SESSION_CLEANUP_ENABLED", "true")
+        .parse::<bool>()
+        .unwrap_or(true),
+      tick_interval_secs: get_env_var(
+        "APPFLOWY_WORKER_UPLOAD_SESSION_CLEANUP_TICK_INTERVAL",
+        "3600",
+      )
+      .parse::<u64>()
+      .unwrap_or(3600),
+    },
+  ));
+
   let app = Router::new()
     .route("/metrics", get(metrics_handler))
     .with_state(Arc::new(state));
@@ -172,8 +261,7 @@ pub async fn create_app(listener: TcpListener, config: Config) -> Result<(), Err
 pub struct AppState {
   pub redis_client: ConnectionManager,
   pub pg_pool: PgPool,
-  pub s3_client: S3ClientImpl,
-  #[allow(dead_code)]
+  pub s3_client: Arc<dyn S3Client>,
   pub mailer: AFWorkerMailer,
   pub metrics: AppMetrics,
 }
@@ -207,6 +295,20 @@ async fn get_connection_pool(setting: &DatabaseSetting) -> Result<PgPool, Error>
     .map_err(|e| anyhow::anyhow!("Failed to connect to postgres database: {}", e))
 }
 
+async fn get_blob_client(config: &Config) -> Result<Arc<dyn S3Client>, Error> {
+  match config.blob_storage.backend {
+    BlobStorageBackend::S3 => Ok(Arc::new(get_aws_s3_client(&config.s3_setting).await?)),
+    BlobStorageBackend::LocalFs => {
+      info!(
+        "Storing blobs in local directory: {}",
+        config.blob_storage.local_path
+      );
+      let client = LocalFsBucketClientImpl::new(&config.blob_storage.local_path).await?;
+      Ok(Arc::new(client))
+    },
+  }
+}
+
 pub async fn get_aws_s3_client(s3_setting: &S3Setting) -> Result<S3ClientImpl, Error> {
   let credentials = Credentials::new(
     s3_setting.access_key.clone(),
@@ -232,6 +334,8 @@ pub async fn get_aws_s3_client(s3_setting: &S3Setting) -> Result<S3ClientImpl, E
   Ok(S3ClientImpl {
     inner: client,
     bucket: s3_setting.bucket.clone(),
+    endpoint: s3_setting.minio_url.clone(),
+    presigned_url_endpoint: s3_setting.presigned_url_endpoint.clone(),
   })
 }
 
@@ -0,0 +1,2 @@
+mod worker;
+pub use worker::*;
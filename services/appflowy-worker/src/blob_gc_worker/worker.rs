@@ -0,0 +1,498 @@
+use crate::error::WorkerError;
+use crate::import_worker::worker::collab_key;
+use crate::s3_client::{app_error_to_worker_error, S3Client};
+use anyhow::anyhow;
+use collab::core::origin::CollabOrigin;
+use collab::entity::{EncodedCollab, EncoderVersion};
+use collab_database::rows::{meta_id_from_row_id, RowMetaKey};
+use collab_entity::CollabType;
+use collab_folder::Folder;
+use database::collab::{
+  select_blob_from_af_collab, select_collab_oids_of_workspace, stream_workspace_collab_blobs,
+};
+use database::encryption::CollabEncryption;
+use database::file::{blob_content_object_key, blob_image_variant_object_keys, BLOB_CONTENT_DIR};
+use database::pg_row::AFBlobMetadataRow;
+use database::resource_usage::{
+  count_blob_content_references, delete_blob_metadata, lock_blob_content,
+  select_blob_metadata_modified_before, select_workspace_ids_with_blobs,
+  stream_workspace_blob_referencing_content, update_orphaned_blobs,
+};
+use database::workspace::select_workspace_end_to_end_encryption;
+use futures::{AsyncReadExt, TryStreamExt};
+use percent_encoding::percent_decode_str;
+use sqlx::types::chrono::{Duration as ChronoDuration, Utc};
+use sqlx::PgPool;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::{interval, MissedTickBehavior};
+use tracing::{error, info, trace};
+use uuid::Uuid;
+
+pub struct BlobGcConfig {
+  pub enable: bool,
+  pub tick_interval_secs: u64,
+  /// Blobs are only deleted after they have stayed unreferenced for this long. Blobs uploaded
+  /// within the grace period are never considered orphaned, as the collab referencing them might
+  /// not have been saved yet.
+  pub grace_period: ChronoDuration,
+  /// Only mark the orphaned blobs, which are listed in the report of the workspace owners, without
+  /// deleting them.
+  pub dry_run: bool,
+}
+
+/// Periodically looks for the blobs that are not referenced by any document or database of their
+/// workspace anymore, e.g. because the pages referencing them were deleted from the trash, and
+/// deletes them once the grace period has elapsed.
+pub async fn run_blob_gc_worker(
+  pg_pool: PgPool,
+  s3_client: Arc<dyn S3Client>,
+  collab_encryption: Option<CollabEncryption>,
+  config: BlobGcConfig,
+) {
+  if !config.enable {
+    info!("Blob garbage collector is disabled");
+    return;
+  }
+
+  info!(
+    "Starting blob garbage collector, grace period: {} days, dry run: {}",
+    config.grace_period.num_days(),
+    config.dry_run
+  );
+  let mut interval = interval(Duration::from_secs(config.tick_interval_secs));
+  interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+  loop {
+    interval.tick().await;
+    let workspace_ids = match select_workspace_ids_with_blobs(&pg_pool).await {
+      Ok(workspace_ids) => workspace_ids,
+      Err(err) => {
+        error!("[Blob GC] failed to select workspaces: {:?}", err);
+        continue;
+      },
+    };
+
+    for workspace_id in workspace_ids {
+      if let Err(err) = collect_workspace_blobs(
+        &pg_pool,
+        &s3_client,
+        collab_encryption.as_ref(),
+        &workspace_id,
+        &config,
+      )
+      .await
+      {
+        error!(
+          "[Blob GC] failed to collect blobs of workspace {}: {:?}",
+          workspace_id, err
+        );
+      }
+    }
+  }
+}
+
+async fn collect_workspace_blobs(
+  pg_pool: &PgPool,
+  s3_client: &Arc<dyn S3Client>,
+  collab_encryption: Option<&CollabEncryption>,
+  workspace_id: &Uuid,
+  config: &BlobGcConfig,
+) -> Result<(), WorkerError> {
+  let deadline = Utc::now() - config.grace_period;
+  let candidates = select_blob_metadata_modified_before(pg_pool, workspace_id, deadline)
+    .await
+    .map_err(app_error_to_worker_error)?;
+  if candidates.is_empty() {
+    return Ok(());
+  }
+
+  let orphaned_blobs = find_unreferenced_blobs(
+    pg_pool,
+    s3_client,
+    collab_encryption,
+    workspace_id,
+    candidates,
+  )
+  .await?;
+  let orphaned_file_ids = orphaned_blobs
+    .iter()
+    .map(|blob| blob.file_id.clone())
+    .collect::<Vec<_>>();
+  update_orphaned_blobs(pg_pool, workspace_id, &orphaned_file_ids)
+    .await
+    .map_err(app_error_to_worker_error)?;
+  if config.dry_run {
+    return Ok(());
+  }
+
+  for blob in orphaned_blobs {
+    if blob
+      .orphaned_at
+      .map(|orphaned_at| orphaned_at < deadline)
+      .unwrap_or(false)
+    {
+      info!(
+        "[Blob GC] delete blob {} of workspace {}, orphaned since {:?}",
+        blob.file_id, workspace_id, blob.orphaned_at
+      );
+      delete_orphaned_blob(pg_pool, s3_client, workspace_id, &blob.file_id).await?;
+    }
+  }
+  Ok(())
+}
+
+/// Returns the candidates that are not referenced by any collab of the workspace, nor by its
+/// published pages, chats, icon or member avatars. A blob is referenced by the urls pointing to
+/// it, see [collect_referenced_blob_keys].
+///
+/// The collabs of an end-to-end encrypted workspace can't be read by the server, so none of its
+/// blobs is ever considered unreferenced.
+async fn find_unreferenced_blobs(
+  pg_pool: &PgPool,
+  s3_client: &Arc<dyn S3Client>,
+  collab_encryption: Option<&CollabEncryption>,
+  workspace_id: &Uuid,
+  mut candidates: Vec<AFBlobMetadataRow>,
+) -> Result<Vec<AFBlobMetadataRow>, WorkerError> {
+  if select_workspace_end_to_end_encryption(pg_pool, workspace_id)
+    .await
+    .map_err(app_error_to_worker_error)?
+  {
+    trace!(
+      "[Blob GC] skip end-to-end encrypted workspace {}",
+      workspace_id
+    );
+    return Ok(vec![]);
+  }
+
+  let document_ids =
+    match select_live_document_ids(pg_pool, s3_client, collab_encryption, workspace_id).await? {
+      Some(document_ids) => document_ids,
+      None => {
+        trace!("[Blob GC] skip workspace {} without folder", workspace_id);
+        return Ok(vec![]);
+      },
+    };
+
+  let mut referenced_keys = HashSet::new();
+  let mut stream = stream_workspace_collab_blobs(pg_pool, workspace_id, document_ids);
+  while let Some((object_id, blob)) = stream
+    .try_next()
+    .await
+    .map_err(|err| WorkerError::Internal(err.into()))?
+  {
+    let doc_state =
+      read_collab_doc_state(s3_client, collab_encryption, workspace_id, &object_id, blob).await?;
+    collect_referenced_blob_keys(
+      &String::from_utf8_lossy(&doc_state),
+      workspace_id,
+      &mut referenced_keys,
+    );
+  }
+
+  let mut stream = stream_workspace_blob_referencing_content(pg_pool, workspace_id);
+  while let Some(content) = stream
+    .try_next()
+    .await
+    .map_err(|err| WorkerError::Internal(err.into()))?
+  {
+    collect_referenced_blob_keys(
+      &readable_content(&content),
+      workspace_id,
+      &mut referenced_keys,
+    );
+  }
+
+  candidates.retain(|candidate| !referenced_keys.contains(&candidate.file_id));
+  Ok(candidates)
+}
+
+/// Returns the ids of the documents that can still be opened: the documents of the views in the
+/// folder, including the trash, and the documents of the database rows. Returns `None` if the
+/// folder of the workspace can't be found.
+async fn select_live_document_ids(
+  pg_pool: &PgPool,
+  s3_client: &Arc<dyn S3Client>,
+  collab_encryption: Option<&CollabEncryption>,
+  workspace_id: &Uuid,
+) -> Result<Option<Vec<String>>, WorkerError> {
+  let workspace_id_str = workspace_id.to_string();
+  let folder_blob =
+    match select_blob_from_af_collab(pg_pool, &CollabType::Folder, &workspace_id_str).await {
+      Ok(blob) => blob,
+      Err(sqlx::Error::RowNotFound) => return Ok(None),
+      Err(err) => return Err(WorkerError::Internal(err.into())),
+    };
+  let doc_state = read_collab_doc_state(
+    s3_client,
+    collab_encryption,
+    workspace_id,
+    &workspace_id_str,
+    folder_blob,
+  )
+  .await?;
+  let encoded_collab = EncodedCollab {
+    state_vector: Default::default(),
+    doc_state: doc_state.into(),
+    version: EncoderVersion::V1,
+  };
+  let folder = Folder::from_collab_doc_state(
+    0,
+    CollabOrigin::Server,
+    encoded_collab.into(),
+    &workspace_id_str,
+    vec![],
+  )
+  .map_err(|err| {
+    anyhow!(
+      "Unable to open folder of workspace {}: {}",
+      workspace_id,
+      err
+    )
+  })?;
+  let mut document_ids = {
+    let txn = folder.collab.transact();
+    folder
+      .body
+      .views
+      .get_all_views(&txn)
+      .into_iter()
+      .map(|view| view.id.clone())
+      .collect::<Vec<_>>()
+  };
+
+  let row_ids = select_collab_oids_of_workspace(pg_pool, workspace_id, &CollabType::DatabaseRow)
+    .await
+    .map_err(|err| WorkerError::Internal(err.into()))?;
+  document_ids.extend(
+    row_ids
+      .iter()
+      .filter_map(|row_id| Uuid::parse_str(row_id).ok())
+      .map(|row_id| meta_id_from_row_id(&row_id, RowMetaKey::DocumentId)),
+  );
+  Ok(Some(document_ids))
+}
+
+/// Returns the doc state of the collab. Large collabs are stored in the bucket, compressed with
+/// zstd, in which case their blob in Postgres is empty.
+async fn read_collab_doc_state(
+  s3_client: &Arc<dyn S3Client>,
+  collab_encryption: Option<&CollabEncryption>,
+  workspace_id: &Uuid,
+  object_id: &str,
+  blob: Vec<u8>,
+) -> Result<Vec<u8>, WorkerError> {
+  let workspace_id = workspace_id.to_string();
+  if blob.is_empty() {
+    let key = collab_key(&workspace_id, object_id);
+    let mut resp = s3_client.get_blob_stream(&key).await?;
+    let mut compressed = Vec::new();
+    resp.stream.read_to_end(&mut compressed).await?;
+    if let Some(collab_encryption) = collab_encryption {
+      compressed = collab_encryption
+        .decrypt(&workspace_id, compressed.into())
+        .await
+        .map_err(app_error_to_worker_error)?
+        .to_vec();
+    }
+    let doc_state = zstd::decode_all(&*compressed)?;
+    return Ok(doc_state);
+  }
+
+  let blob = match collab_encryption {
+    Some(collab_encryption) => collab_encryption
+      .decrypt(&workspace_id, blob.into())
+      .await
+      .map_err(app_error_to_worker_error)?
+      .to_vec(),
+    None => blob,
+  };
+
+  match EncodedCollab::decode_from_bytes(&blob) {
+    Ok(encoded_collab) => Ok(encoded_collab.doc_state.to_vec()),
+    // Look up the file ids in the raw blob rather than skipping the collab
+    Err(_) => Ok(blob),
+  }
+}
+
+/// Adds the blob metadata keys of the blobs of the workspace whose url appears in the content.
+/// The key of a blob is the file id of its url, or its parent dir and file id joined by `_`, see
+/// the `BlobKey` implementations of the file storage api.
+fn collect_referenced_blob_keys(content: &str, workspace_id: &Uuid, keys: &mut HashSet<String>) {
+  let prefix = format!("/api/file_storage/{}/", workspace_id);
+  for (index, _) in content.match_indices(&prefix) {
+    let path = &content[index + prefix.len()..];
+    let end = path
+      .find(|c: char| !is_blob_url_path_char(c))
+      .unwrap_or(path.len());
+    let segments = path[..end]
+      .split('/')
+      .map(|segment| percent_decode_str(segment).decode_utf8_lossy())
+      .collect::<Vec<_>>();
+    match segments.as_slice() {
+      [version, blob, parent_dir, file_id] if version == "v1" && blob == "blob" => {
+        keys.insert(format!("{}_{}", parent_dir, file_id));
+      },
+      [blob, file_id] if blob == "blob" => {
+        keys.insert(file_id.to_string());
+      },
+      _ => {},
+    }
+  }
+}
+
+/// The query string, the fragment and the quotes or brackets surrounding the url are not part of
+/// the blob path.
+fn is_blob_url_path_char(c: char) -> bool {
+  c.is_ascii_alphanumeric() || "-._~%/+=@:,!$;".contains(c)
+}
+
+/// Published pages are stored as json, in which the collab states are arrays of bytes. Returns
+/// the strings of the json along with the bytes of its arrays, so that the urls they contain can
+/// be found.
+fn readable_content(content: &[u8]) -> String {
+  fn collect_strings(value: &serde_json::Value, output: &mut String) {
+    match value {
+      serde_json::Value::String(s) => {
+        output.push_str(s);
+        output.push('\n');
+      },
+      serde_json::Value::Array(values) => {
+        let bytes = values
+          .iter()
+          .map(|value| value.as_u64().and_then(|n| u8::try_from(n).ok()))
+          .collect::<Option<Vec<u8>>>();
+        match bytes {
+          Some(bytes) if !bytes.is_empty() => {
+            output.push_str(&String::from_utf8_lossy(&bytes));
+            output.push('\n');
+          },
+          _ => values
+            .iter()
+            .for_each(|value| collect_strings(value, output)),
+        }
+      },
+      serde_json::Value::Object(map) => map
+        .values()
+        .for_each(|value| collect_strings(value, output)),
+      _ => {},
+    }
+  }
+
+  match serde_json::from_slice::<serde_json::Value>(content) {
+    Ok(value) => {
+      let mut output = String::new();
+      collect_strings(&value, &mut output);
+      output
+    },
+    Err(_) => String::from_utf8_lossy(content).into_owned(),
+  }
+}
+
+async fn delete_orphaned_blob(
+  pg_pool: &PgPool,
+  s3_client: &Arc<dyn S3Client>,
+  workspace_id: &Uuid,
+  file_id: &str,
+) -> Result<(), WorkerError> {
+  let mut tx = pg_pool
+    .begin()
+    .await
+    .map_err(|err| WorkerError::Internal(err.into()))?;
+  let content_hash = delete_blob_metadata(&mut tx, workspace_id, file_id)
+    .await
+    .map_err(app_error_to_worker_error)?;
+  let object_keys = match content_hash {
+    Some(content_hash) => {
+      lock_blob_content(&mut tx, workspace_id, &content_hash)
+        .await
+        .map_err(app_error_to_worker_error)?;
+      let references = count_blob_content_references(&mut tx, workspace_id, &content_hash)
+        .await
+        .map_err(app_error_to_worker_error)?;
+      if references == 0 {
+        vec![blob_content_object_key(workspace_id, &content_hash)]
+      } else {
+        vec![]
+      }
+    },
+    None => own_object_keys(workspace_id, file_id),
+  };
+
+  for object_key in object_keys {
+    s3_client.delete_blob(&object_key).await?;
+    for variant_key in blob_image_variant_object_keys(&object_key) {
+      s3_client.delete_blob(&variant_key).await?;
+    }
+  }
+  tx.commit()
+    .await
+    .map_err(|err| WorkerError::Internal(err.into()))?;
+  Ok(())
+}
+
+/// Returns the keys of the objects that may hold a blob stored under its own key, i.e. a blob
+/// uploaded in multiple parts or before content deduplication. As the parent dir may itself
+/// contain `_`, every split of the file id at a `_` is tried.
+fn own_object_keys(workspace_id: &Uuid, file_id: &str) -> Vec<String> {
+  let mut object_keys = vec![format!("{}/{}", workspace_id, file_id)];
+  object_keys.extend(
+    file_id
+      .match_indices('_')
+      .map(|(index, _)| (&file_id[..index], &file_id[index + 1..]))
+      .filter(|(parent_dir, _)| !parent_dir.is_empty() && *parent_dir != BLOB_CONTENT_DIR)
+      .map(|(parent_dir, file_id)| format!("{}/{}/{}", workspace_id, parent_dir, file_id)),
+  );
+  object_keys
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn referenced_blob_keys_test() {
+    let workspace_id = Uuid::new_v4();
+    let content = format!(
+      "{{\"url\":\"https://appflowy.io/api/file_storage/{0}/v1/blob/view_1/abc_def.png?width=800\"}} \
+       ![](https://appflowy.io/api/file_storage/{0}/blob/old%20file.png) \
+       https://appflowy.io/api/file_storage/{1}/blob/other.png",
+      workspace_id,
+      Uuid::new_v4()
+    );
+    let mut keys = HashSet::new();
+    collect_referenced_blob_keys(&content, &workspace_id, &mut keys);
+    assert_eq!(
+      keys,
+      HashSet::from(["view_1_abc_def.png".to_string(), "old file.png".to_string()])
+    );
+    // A file id that only appears as a part of another url is not referenced
+    assert!(!keys.contains("abc_def.png"));
+  }
+
+  #[test]
+  fn readable_published_content_test() {
+    let url = "https://appflowy.io/api/file_storage/w/v1/blob/view/image.png";
+    let content = serde_json::json!({
+      "meta": { "name": "page" },
+      "data": url.as_bytes(),
+    });
+    let readable = readable_content(&serde_json::to_vec(&content).unwrap());
+    assert!(readable.contains(url));
+    assert!(readable.contains("page"));
+  }
+
+  #[test]
+  fn own_object_keys_test() {
+    let workspace_id = Uuid::new_v4();
+    let keys = own_object_keys(&workspace_id, &format!("{}_a_b", BLOB_CONTENT_DIR));
+    assert_eq!(
+      keys,
+      vec![
+        format!("{}/_content_a_b", workspace_id),
+        format!("{}/_content_a/b", workspace_id),
+      ]
+    );
+  }
+}
@@ -1,5 +1,5 @@
 use anyhow::{Context, Error};
-use infra::env_util::get_env_var;
+use infra::env_util::{get_env_var, get_env_var_opt};
 use mailer::config::MailerSetting;
 use secrecy::Secret;
 use serde::Deserialize;
@@ -12,7 +12,11 @@ pub struct Config {
   pub redis_url: String,
   pub db_settings: DatabaseSetting,
   pub s3_setting: S3Setting,
+  pub blob_storage: BlobStorageSetting,
   pub mailer: MailerSetting,
+  /// Master keys used to decrypt the collabs encrypted at rest, see the `APPFLOWY_COLLAB_ENCRYPTION_MASTER_KEYS`
+  /// setting of the API server.
+  pub collab_encryption_master_keys: Secret<String>,
 }
 
 impl Config {
@@ -44,6 +48,13 @@ impl Config {
         secret_key: get_env_var("APPFLOWY_S3_SECRET_KEY", "minioadmin").into(),
         bucket: get_env_var("APPFLOWY_S3_BUCKET", "appflowy"),
         region: get_env_var("APPFLOWY_S3_REGION", ""),
+        presigned_url_endpoint: get_env_var_opt("APPFLOWY_S3_PRESIGNED_URL_ENDPOINT"),
+      },
+      blob_storage: BlobStorageSetting {
+        backend: get_env_var("APPFLOWY_BLOB_STORAGE_BACKEND", "s3")
+          .as_str()
+          .try_into()?,
+        local_path: get_env_var("APPFLOWY_BLOB_STORAGE_LOCAL_PATH", "./data/blob"),
       },
       mailer: MailerSetting {
         smtp_host: get_env_var("APPFLOWY_MAILER_SMTP_HOST", "smtp.gmail.com"),
@@ -58,6 +69,8 @@ impl Config {
         smtp_password: get_env_var("APPFLOWY_MAILER_SMTP_PASSWORD", "password").into(),
         smtp_tls_kind: get_env_var("APPFLOWY_MAILER_SMTP_TLS_KIND", "wrapper"),
       },
+      collab_encryption_master_keys: get_env_var("APPFLOWY_COLLAB_ENCRYPTION_MASTER_KEYS", "")
+        .into(),
     })
   }
 }
@@ -133,4 +146,33 @@ pub struct S3Setting {
   pub secret_key: Secret<String>,
   pub bucket: String,
   pub region: String,
+  /// Public endpoint that replaces the endpoint of the bucket in the presigned urls sent to the
+  /// users, see the same setting of the API server.
+  pub presigned_url_endpoint: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+pub enum BlobStorageBackend {
+  S3,
+  LocalFs,
+}
+
+impl TryFrom<&str> for BlobStorageBackend {
+  type Error = anyhow::Error;
+
+  fn try_from(value: &str) -> Result<Self, Self::Error> {
+    match value {
+      "s3" => Ok(BlobStorageBackend::S3),
+      "local_fs" => Ok(BlobStorageBackend::LocalFs),
+      _ => Err(anyhow::anyhow!("Invalid BlobStorageBackend")),
+    }
+  }
+}
+
+#[derive(Clone, Debug)]
+pub struct BlobStorageSetting {
+  pub backend: BlobStorageBackend,
+  /// Directory where blobs are stored when using [BlobStorageBackend::LocalFs]. It must be shared
+  /// with the API server, which writes the files to import.
+  pub local_path: String,
 }
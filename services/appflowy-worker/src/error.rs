@@ -49,6 +49,15 @@ pub enum ImportError {
     max_size_in_mb: f64,
   },
 
+  #[error("Parent view not found: {0}")]
+  ParentViewNotFound(String),
+
+  #[error("Invalid spreadsheet: {0}")]
+  InvalidSpreadsheet(String),
+
+  #[error("Import task was cancelled")]
+  Cancelled,
+
   #[error(transparent)]
   Internal(#[from] anyhow::Error),
 }
@@ -214,6 +223,30 @@ impl ImportError {
           format!("Task ID: {} - Upload file too large: {} MB", task_id, file_size_in_mb),
         )
       }
+      ImportError::ParentViewNotFound(view_id) => {
+        (
+          format!(
+            "Task ID: {} - The page to import into no longer exists. Please choose another page and try again.",
+            task_id
+          ),
+          format!("Task ID: {} - Parent view not found: {}", task_id, view_id),
+        )
+      }
+      ImportError::InvalidSpreadsheet(err) => {
+        (
+          format!(
+            "Task ID: {} - The spreadsheet could not be read. Please ensure it is a valid CSV or Excel file.",
+            task_id
+          ),
+          format!("Task ID: {} - Invalid spreadsheet: {}", task_id, err),
+        )
+      }
+      ImportError::Cancelled => {
+        (
+          format!("Task ID: {} - The import was cancelled.", task_id),
+          format!("Task ID: {} - Cancelled", task_id),
+        )
+      }
     }
   }
 }
@@ -0,0 +1,253 @@
+use collab_export::document::{ExportLinks, PageLink};
+use std::collections::{HashMap, HashSet};
+
+/// Directory of the archive holding the files embedded in the documents.
+pub const ASSETS_DIR: &str = "assets";
+
+/// Maximum number of characters of a file name derived from the name of a view.
+const MAX_FILE_NAME_LEN: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeKind {
+  /// Only holds its children, it doesn't have content of its own
+  Space,
+  Document,
+  Database,
+}
+
+/// A view of the folder to export, with the children that are exported too.
+#[derive(Debug, Clone)]
+pub struct FolderNode {
+  pub view_id: String,
+  pub name: String,
+  pub kind: NodeKind,
+  pub children: Vec<FolderNode>,
+}
+
+/// The file of a view in the archive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveEntry {
+  pub view_id: String,
+  pub name: String,
+  pub kind: NodeKind,
+  pub path: String,
+}
+
+/// Lays out the views in the archive, keeping the hierarchy of the folder: each view is written
+/// to a file named after it, and its children to a directory with the same name next to it.
+pub fn layout_archive(roots: &[FolderNode]) -> Vec<ArchiveEntry> {
+  let mut entries = vec![];
+  let mut used_names = HashSet::from([ASSETS_DIR.to_string()]);
+  layout_dir(roots, "", &mut used_names, &mut entries);
+  entries
+}
+
+fn layout_dir(
+  nodes: &[FolderNode],
+  dir: &str,
+  used_names: &mut HashSet<String>,
+  entries: &mut Vec<ArchiveEntry>,
+) {
+  for node in nodes {
+    let name = unique_name(&sanitize_file_name(&node.name), used_names);
+    let path = if dir.is_empty() {
+      name
+    } else {
+      format!("{}/{}", dir, name)
+    };
+    let extension = match node.kind {
+      NodeKind::Space => None,
+      NodeKind::Document => Some("md"),
+      NodeKind::Database => Some("csv"),
+    };
+    if let Some(extension) = extension {
+      entries.push(ArchiveEntry {
+        view_id: node.view_id.clone(),
+        name: node.name.clone(),
+        kind: node.kind,
+        path: format!("{}.{}", path, extension),
+      });
+    }
+    if !node.children.is_empty() {
+      layout_dir(&node.children, &path, &mut HashSet::new(), entries);
+    }
+  }
+}
+
+/// Replaces the characters that aren't allowed in file names on common file systems.
+pub fn sanitize_file_name(name: &str) -> String {
+  let sanitized = name
+    .chars()
+    .map(|c| match c {
+      '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+      c if c.is_control() => '_',
+      c => c,
+    })
+    .take(MAX_FILE_NAME_LEN)
+    .collect::<String>();
+  let sanitized = sanitized.trim_matches(|c: char| c.is_whitespace() || c == '.');
+  if sanitized.is_empty() {
+    "Untitled".to_string()
+  } else {
+    sanitized.to_string()
+  }
+}
+
+/// Appends a number to the name if a file of the same directory already has it. Names are
+/// compared case-insensitively, as some file systems are.
+fn unique_name(name: &str, used_names: &mut HashSet<String>) -> String {
+  let mut unique = name.to_string();
+  let mut n = 1;
+  while !used_names.insert(unique.to_lowercase()) {
+    n += 1;
+    unique = format!("{} ({})", name, n);
+  }
+  unique
+}
+
+/// Returns the path of `to` relative to the directory of the file `from`, both being paths in the
+/// archive.
+pub fn relative_path(from: &str, to: &str) -> String {
+  let from_dirs = from.split('/').collect::<Vec<_>>();
+  let from_dirs = &from_dirs[..from_dirs.len() - 1];
+  let to_parts = to.split('/').collect::<Vec<_>>();
+  let common = from_dirs
+    .iter()
+    .zip(to_parts[..to_parts.len() - 1].iter())
+    .take_while(|(a, b)| a == b)
+    .count();
+  let mut parts = vec![".."; from_dirs.len() - common];
+  parts.extend(&to_parts[common..]);
+  parts.join("/")
+}
+
+/// Returns the parent dir and the file id of a blob of the workspace, given its url, i.e.
+/// `{host}/api/file_storage/{workspace_id}/v1/blob/{parent_dir}/{file_id}`.
+pub fn parse_blob_url<'a>(url: &'a str, workspace_id: &str) -> Option<(&'a str, &'a str)> {
+  let (_, path) = url.split_once("/api/file_storage/")?;
+  let path = path.split(['?', '#']).next()?;
+  let parts = path.split('/').collect::<Vec<_>>();
+  match parts.as_slice() {
+    [ws, "v1", "blob", parent_dir, file_id]
+      if *ws == workspace_id && !parent_dir.is_empty() && !file_id.is_empty() =>
+    {
+      Some((*parent_dir, *file_id))
+    },
+    _ => None,
+  }
+}
+
+/// Returns the path of the blob in the archive.
+pub fn asset_path(parent_dir: &str, file_id: &str) -> String {
+  format!(
+    "{}/{}/{}",
+    ASSETS_DIR,
+    sanitize_file_name(parent_dir),
+    sanitize_file_name(file_id)
+  )
+}
+
+/// Links of a document of the archive, relative to its file.
+pub struct ArchiveLinks<'a> {
+  pub path: &'a str,
+  /// Name and path of the views in the archive, by view id
+  pub views: &'a HashMap<String, (String, String)>,
+  /// Path of the downloaded blobs in the archive, by url
+  pub assets: &'a HashMap<String, String>,
+}
+
+impl ExportLinks for ArchiveLinks<'_> {
+  fn page_link(&self, view_id: &str) -> Option<PageLink> {
+    self.views.get(view_id).map(|(name, path)| PageLink {
+      name: name.clone(),
+      href: relative_path(self.path, path),
+    })
+  }
+
+  fn file_link(&self, url: &str) -> String {
+    match self.assets.get(url) {
+      Some(path) => relative_path(self.path, path),
+      None => url.to_string(),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn node(view_id: &str, name: &str, kind: NodeKind, children: Vec<FolderNode>) -> FolderNode {
+    FolderNode {
+      view_id: view_id.to_string(),
+      name: name.to_string(),
+      kind,
+      children,
+    }
+  }
+
+  #[test]
+  fn layout_archive_test() {
+    let roots = vec![
+      node(
+        "space",
+        "General",
+        NodeKind::Space,
+        vec![
+          node(
+            "doc",
+            "Notes",
+            NodeKind::Document,
+            vec![node("grid", "Tasks", NodeKind::Database, vec![])],
+          ),
+          node("doc2", "notes", NodeKind::Document, vec![]),
+          node("doc3", "a/b: c?", NodeKind::Document, vec![]),
+          node("doc4", "", NodeKind::Document, vec![]),
+        ],
+      ),
+      node("space2", "Assets", NodeKind::Space, vec![]),
+    ];
+    let paths = layout_archive(&roots)
+      .into_iter()
+      .map(|entry| (entry.view_id, entry.path))
+      .collect::<Vec<_>>();
+    assert_eq!(
+      paths,
+      vec![
+        ("doc".to_string(), "General/Notes.md".to_string()),
+        ("grid".to_string(), "General/Notes/Tasks.csv".to_string()),
+        ("doc2".to_string(), "General/notes (2).md".to_string()),
+        ("doc3".to_string(), "General/a_b_ c_.md".to_string()),
+        ("doc4".to_string(), "General/Untitled.md".to_string()),
+      ]
+    );
+  }
+
+  #[test]
+  fn relative_path_test() {
+    assert_eq!(relative_path("a/b.md", "a/c.md"), "c.md");
+    assert_eq!(relative_path("a/b.md", "a/b/c.csv"), "b/c.csv");
+    assert_eq!(relative_path("a/b/c.md", "d/e.md"), "../../d/e.md");
+    assert_eq!(
+      relative_path("a/b.md", "assets/x/y.png"),
+      "../assets/x/y.png"
+    );
+  }
+
+  #[test]
+  fn parse_blob_url_test() {
+    let workspace_id = "0a2c4bfe-50b2-4d6b-a4b3-0cb2aaf8d0d9";
+    let url = format!(
+      "https://beta.appflowy.cloud/api/file_storage/{}/v1/blob/view_id/file.png",
+      workspace_id
+    );
+    assert_eq!(
+      parse_blob_url(&url, workspace_id),
+      Some(("view_id", "file.png"))
+    );
+    assert_eq!(parse_blob_url(&url, "other_workspace"), None);
+    assert_eq!(
+      parse_blob_url("https://example.com/file.png", workspace_id),
+      None
+    );
+  }
+}
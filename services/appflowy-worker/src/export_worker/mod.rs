@@ -0,0 +1,3 @@
+mod archive;
+mod worker;
+pub use worker::*;
@@ -0,0 +1,662 @@
+use crate::error::WorkerError;
+use crate::export_worker::archive::{
+  asset_path, layout_archive, parse_blob_url, ArchiveEntry, ArchiveLinks, FolderNode, NodeKind,
+};
+use crate::import_worker::live_collab::apply_pending_updates;
+use crate::import_worker::worker::get_encode_collab_from_bytes;
+use crate::mailer::{
+  AFWorkerMailer, ExportMailerParam, EXPORT_FAIL_TEMPLATE, EXPORT_SUCCESS_TEMPLATE,
+};
+use crate::s3_client::{app_error_to_worker_error, S3Client};
+use anyhow::anyhow;
+use async_zip::base::write::ZipFileWriter;
+use async_zip::{Compression, ZipEntryBuilder};
+use aws_sdk_s3::primitives::ByteStream;
+use collab::core::origin::CollabOrigin;
+use collab::preclude::Collab;
+use collab_database::database::DatabaseBody;
+use collab_database::rows::{Row, RowDetail};
+use collab_database::workspace_database::{NoPersistenceDatabaseCollabService, WorkspaceDatabase};
+use collab_document::document::Document;
+use collab_entity::CollabType;
+use collab_export::database::{database_to_csv, fields_in_view_order};
+use collab_export::document::{document_file_urls, document_to_markdown};
+use collab_folder::{Folder, View, ViewLayout};
+use database::encryption::CollabEncryption;
+use database::file::blob_content_object_key;
+use database::pg_row::AFExportTask;
+use database::resource_usage::select_blob_content_hash;
+use database::workspace::{
+  claim_next_export_task, select_expired_export_tasks, select_workspace_database_storage_id,
+  update_export_task_completed, update_export_task_status, ExportTaskState,
+};
+use redis::aio::ConnectionManager;
+use serde::Deserialize;
+use serde_json::json;
+use sqlx::types::chrono::{Duration as ChronoDuration, Utc};
+use sqlx::PgPool;
+use std::collections::{HashMap, HashSet};
+use std::env::temp_dir;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::fs;
+use tokio::time::{interval, MissedTickBehavior};
+use tokio_util::compat::TokioAsyncWriteCompatExt;
+use tracing::{error, info, trace, warn};
+use uuid::Uuid;
+
+/// A task that has been processing for longer is considered abandoned, e.g. because the worker
+/// restarted, and is processed again.
+const PROCESSING_TIMEOUT_MINUTES: i64 = 60;
+
+/// Maximum number of expired archives deleted per tick.
+const EXPIRED_ARCHIVE_BATCH_SIZE: i64 = 100;
+
+pub struct ExportWorkerConfig {
+  pub enable: bool,
+  pub tick_interval_secs: u64,
+  /// How long the archives are kept in the bucket, which is also how long the emailed links stay
+  /// valid.
+  pub archive_expiration_hours: u64,
+}
+
+/// Set by the API server when the task is created.
+#[derive(Debug, Default, Deserialize)]
+struct ExportTaskMetadata {
+  #[serde(default)]
+  user_name: String,
+  #[serde(default)]
+  user_email: String,
+  #[serde(default)]
+  workspace_name: String,
+}
+
+struct ExportContext {
+  pg_pool: PgPool,
+  redis_client: ConnectionManager,
+  s3_client: Arc<dyn S3Client>,
+  collab_encryption: Option<CollabEncryption>,
+  mailer: AFWorkerMailer,
+  archive_expiration_hours: u64,
+}
+
+/// Processes the export tasks created by the API server: each workspace is exported into a zip
+/// archive of Markdown and CSV files, uploaded to the bucket, and the user is emailed a link to
+/// download it. The archives are deleted once they expire.
+pub async fn run_export_worker(
+  pg_pool: PgPool,
+  redis_client: ConnectionManager,
+  s3_client: Arc<dyn S3Client>,
+  collab_encryption: Option<CollabEncryption>,
+  mailer: AFWorkerMailer,
+  config: ExportWorkerConfig,
+) {
+  if !config.enable {
+    info!("Export worker is disabled");
+    return;
+  }
+
+  info!("Starting export worker");
+  let mut context = ExportContext {
+    pg_pool,
+    redis_client,
+    s3_client,
+    collab_encryption,
+    mailer,
+    archive_expiration_hours: config.archive_expiration_hours,
+  };
+  let mut interval = interval(Duration::from_secs(config.tick_interval_secs));
+  interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+  loop {
+    interval.tick().await;
+    loop {
+      let stale_before = Utc::now() - ChronoDuration::minutes(PROCESSING_TIMEOUT_MINUTES);
+      match claim_next_export_task(&context.pg_pool, stale_before).await {
+        Ok(Some(task)) => process_export_task(&mut context, task).await,
+        Ok(None) => break,
+        Err(err) => {
+          error!("[Export] failed to claim export task: {:?}", err);
+          break;
+        },
+      }
+    }
+    delete_expired_archives(&context).await;
+  }
+}
+
+async fn process_export_task(context: &mut ExportContext, task: AFExportTask) {
+  info!(
+    "[Export] start exporting workspace {}, task: {}",
+    task.workspace_id, task.task_id
+  );
+  let metadata =
+    serde_json::from_value::<ExportTaskMetadata>(task.metadata.clone()).unwrap_or_default();
+  let s3_key = archive_key(&task.workspace_id, &task.task_id);
+  let result = match export_workspace(context, &task, &s3_key).await {
+    Ok(file_size) => {
+      update_export_task_completed(&context.pg_pool, &task.task_id, &s3_key, file_size as i64)
+        .await
+        .map_err(app_error_to_worker_error)
+    },
+    Err(err) => Err(err),
+  };
+
+  let param = match result {
+    Ok(_) => {
+      info!("[Export] successfully exported task: {}", task.task_id);
+      let expires_in_secs = context.archive_expiration_hours * 3600;
+      let download_url = match context
+        .s3_client
+        .gen_presigned_download_url(&s3_key, expires_in_secs)
+        .await
+      {
+        Ok(url) => Some(url),
+        Err(err) => {
+          warn!(
+            "[Export] failed to generate download url of task {}: {}",
+            task.task_id, err
+          );
+          None
+        },
+      };
+      ExportMailerParam {
+        export_task_id: task.task_id.to_string(),
+        user_name: metadata.user_name.clone(),
+        workspace_id: task.workspace_id.to_string(),
+        workspace_name: metadata.workspace_name.clone(),
+        download_url,
+        expires_in_hours: context.archive_expiration_hours,
+        error: None,
+      }
+    },
+    Err(err) => {
+      error!("[Export] failed to export task {}: {:?}", task.task_id, err);
+      let error = "Failed to export the workspace".to_string();
+      if let Err(err) = update_export_task_status(
+        &context.pg_pool,
+        &task.task_id,
+        ExportTaskState::Failed,
+        json!({ "error": error }),
+      )
+      .await
+      {
+        error!(
+          "[Export] failed to update status of task {}: {:?}",
+          task.task_id, err
+        );
+      }
+      ExportMailerParam {
+        export_task_id: task.task_id.to_string(),
+        user_name: metadata.user_name.clone(),
+        workspace_id: task.workspace_id.to_string(),
+        workspace_name: metadata.workspace_name.clone(),
+        download_url: None,
+        expires_in_hours: context.archive_expiration_hours,
+        error: Some(error),
+      }
+    },
+  };
+
+  if metadata.user_email.is_empty() {
+    return;
+  }
+  let template = if param.error.is_none() {
+    EXPORT_SUCCESS_TEMPLATE
+  } else {
+    EXPORT_FAIL_TEMPLATE
+  };
+  if let Err(err) = context
+    .mailer
+    .send_email_template(
+      Some(metadata.user_name),
+      &metadata.user_email,
+      template,
+      param,
+      "Notification: Export Report",
+    )
+    .await
+  {
+    error!("[Export] failed to send export report email: {}", err);
+  }
+}
+
+fn archive_key(workspace_id: &Uuid, task_id: &Uuid) -> String {
+  format!("exports/{}/{}.zip", workspace_id, task_id)
+}
+
+/// Builds the archive of the workspace and uploads it to the bucket. Returns the size of the
+/// archive.
+async fn export_workspace(
+  context: &mut ExportContext,
+  task: &AFExportTask,
+  s3_key: &str,
+) -> Result<u64, WorkerError> {
+  let archive_path = temp_dir().join(format!("export_{}.zip", task.task_id));
+  let result = write_and_upload_archive(context, task, &archive_path, s3_key).await;
+  if let Err(err) = fs::remove_file(&archive_path).await {
+    warn!(
+      "[Export] failed to remove archive {:?}: {}",
+      archive_path, err
+    );
+  }
+  result
+}
+
+async fn write_and_upload_archive(
+  context: &mut ExportContext,
+  task: &AFExportTask,
+  archive_path: &Path,
+  s3_key: &str,
+) -> Result<u64, WorkerError> {
+  let workspace_id = task.workspace_id.to_string();
+
+  // 1. lay out the views that the user can see, on top of the updates that aren't persisted yet
+  let mut folder = open_folder(context, &workspace_id, task.created_by).await?;
+  apply_pending_updates(
+    &mut context.redis_client,
+    &workspace_id,
+    &workspace_id,
+    &mut folder.collab,
+  )
+  .await?;
+  let excluded_view_ids = hidden_view_ids(&folder);
+  let roots = folder_nodes(&folder, &workspace_id, &excluded_view_ids);
+  let entries = layout_archive(&roots);
+  let views = entries
+    .iter()
+    .map(|entry| {
+      (
+        entry.view_id.clone(),
+        (entry.name.clone(), entry.path.clone()),
+      )
+    })
+    .collect::<HashMap<_, _>>();
+  trace!(
+    "[Export] {} exporting {} views",
+    workspace_id,
+    entries.len()
+  );
+
+  // 2. write the documents, the databases and the files they embed
+  let file = fs::File::create(archive_path).await?;
+  let mut writer = ZipFileWriter::new(file.compat_write());
+  let mut workspace_database = None;
+  let mut assets = HashMap::new();
+  for entry in entries.iter() {
+    let content = match entry.kind {
+      NodeKind::Document => {
+        export_document(context, &workspace_id, entry, &views, &mut assets).await
+      },
+      NodeKind::Database => {
+        if workspace_database.is_none() {
+          workspace_database = Some(open_workspace_database(context, &workspace_id).await?);
+        }
+        let workspace_database = workspace_database.as_ref().unwrap();
+        export_database(context, &workspace_id, workspace_database, &entry.view_id).await
+      },
+      NodeKind::Space => continue,
+    };
+    // A view that can't be exported, e.g. because its collab is missing, doesn't fail the export
+    let content = match content {
+      Ok(content) => content,
+      Err(err) => {
+        warn!(
+          "[Export] skip view {} of workspace {}: {:?}",
+          entry.view_id, workspace_id, err
+        );
+        continue;
+      },
+    };
+    writer
+      .write_entry_whole(
+        ZipEntryBuilder::new(entry.path.clone().into(), Compression::Deflate),
+        content.as_bytes(),
+      )
+      .await?;
+  }
+
+  let mut written_assets = HashSet::new();
+  for (url, path) in assets {
+    if !written_assets.insert(path.clone()) {
+      continue;
+    }
+    if let Err(err) = write_asset(context, &task.workspace_id, &url, &path, &mut writer).await {
+      warn!(
+        "[Export] skip file {} of workspace {}: {:?}",
+        url, workspace_id, err
+      );
+    }
+  }
+  writer.close().await?;
+
+  // 3. upload the archive
+  let file_size = fs::metadata(archive_path).await?.len();
+  let content = ByteStream::from_path(archive_path)
+    .await
+    .map_err(|err| anyhow!("Failed to read archive: {}", err))?;
+  context
+    .s3_client
+    .put_blob(s3_key, content, Some("application/zip"))
+    .await?;
+  Ok(file_size)
+}
+
+async fn open_folder(
+  context: &ExportContext,
+  workspace_id: &str,
+  uid: i64,
+) -> Result<Folder, WorkerError> {
+  let encoded_collab = get_encode_collab_from_bytes(
+    workspace_id,
+    workspace_id,
+    &CollabType::Folder,
+    &context.pg_pool,
+    &context.s3_client,
+    context.collab_encryption.as_ref(),
+  )
+  .await?;
+  Folder::from_collab_doc_state(
+    uid,
+    CollabOrigin::Server,
+    encoded_collab.into(),
+    workspace_id,
+    vec![],
+  )
+  .map_err(|err| WorkerError::Internal(anyhow!("Failed to open folder: {}", err)))
+}
+
+async fn open_collab(
+  context: &mut ExportContext,
+  workspace_id: &str,
+  object_id: &str,
+  collab_type: CollabType,
+) -> Result<Collab, WorkerError> {
+  let encoded_collab = get_encode_collab_from_bytes(
+    workspace_id,
+    object_id,
+    &collab_type,
+    &context.pg_pool,
+    &context.s3_client,
+    context.collab_encryption.as_ref(),
+  )
+  .await?;
+  let mut collab = Collab::new_with_source(
+    CollabOrigin::Server,
+    object_id,
+    encoded_collab.into(),
+    vec![],
+    false,
+  )
+  .map_err(|err| WorkerError::Internal(anyhow!("Failed to open collab {}: {}", object_id, err)))?;
+  apply_pending_updates(
+    &mut context.redis_client,
+    workspace_id,
+    object_id,
+    &mut collab,
+  )
+  .await?;
+  Ok(collab)
+}
+
+async fn open_workspace_database(
+  context: &mut ExportContext,
+  workspace_id: &str,
+) -> Result<WorkspaceDatabase, WorkerError> {
+  let w_database_id = select_workspace_database_storage_id(&context.pg_pool, workspace_id)
+    .await
+    .map_err(|err| anyhow!("Failed to select workspace database storage id: {:?}", err))?
+    .to_string();
+  let collab = open_collab(
+    context,
+    workspace_id,
+    &w_database_id,
+    CollabType::WorkspaceDatabase,
+  )
+  .await?;
+  WorkspaceDatabase::open(collab)
+    .map_err(|err| WorkerError::Internal(anyhow!("Failed to open workspace database: {}", err)))
+}
+
+/// Returns the views that the owner of the task can't see in the folder: the views in the trash,
+/// and the private spaces of the other members.
+fn hidden_view_ids(folder: &Folder) -> HashSet<String> {
+  let my_private_section_ids = folder
+    .get_my_private_sections()
+    .into_iter()
+    .map(|section| section.id)
+    .collect::<HashSet<_>>();
+  folder
+    .get_all_private_sections()
+    .into_iter()
+    .map(|section| section.id)
+    .filter(|id| !my_private_section_ids.contains(id))
+    .chain(
+      folder
+        .get_all_trash_sections()
+        .into_iter()
+        .map(|section| section.id),
+    )
+    .collect()
+}
+
+fn folder_nodes(
+  folder: &Folder,
+  parent_id: &str,
+  hidden_view_ids: &HashSet<String>,
+) -> Vec<FolderNode> {
+  folder
+    .get_views_belong_to(parent_id)
+    .into_iter()
+    .filter(|view| !hidden_view_ids.contains(&view.id))
+    .filter_map(|view| {
+      let kind = if is_space(&view) {
+        NodeKind::Space
+      } else {
+        match view.layout {
+          ViewLayout::Document => NodeKind::Document,
+          ViewLayout::Grid | ViewLayout::Board | ViewLayout::Calendar => NodeKind::Database,
+          // Chats don't have content that can be exported
+          _ => return None,
+        }
+      };
+      Some(FolderNode {
+        view_id: view.id.clone(),
+        name: view.name.clone(),
+        kind,
+        children: folder_nodes(folder, &view.id, hidden_view_ids),
+      })
+    })
+    .collect()
+}
+
+fn is_space(view: &View) -> bool {
+  view
+    .extra
+    .as_deref()
+    .and_then(|extra| serde_json::from_str::<serde_json::Value>(extra).ok())
+    .and_then(|extra| {
+      extra
+        .get("is_space")
+        .and_then(|is_space| is_space.as_bool())
+    })
+    .unwrap_or(false)
+}
+
+/// Converts the document into Markdown, registering the blobs of the workspace it embeds in
+/// `assets` so that they are written to the archive.
+async fn export_document(
+  context: &mut ExportContext,
+  workspace_id: &str,
+  entry: &ArchiveEntry,
+  views: &HashMap<String, (String, String)>,
+  assets: &mut HashMap<String, String>,
+) -> Result<String, WorkerError> {
+  let collab = open_collab(context, workspace_id, &entry.view_id, CollabType::Document).await?;
+  let document = Document::open(collab)
+    .map_err(|err| WorkerError::Internal(anyhow!("Failed to open document: {}", err)))?;
+  let data = document
+    .get_document_data()
+    .map_err(|err| WorkerError::Internal(anyhow!("Failed to read document: {}", err)))?;
+  for url in document_file_urls(&data) {
+    if let Some((parent_dir, file_id)) = parse_blob_url(&url, workspace_id) {
+      let path = asset_path(parent_dir, file_id);
+      assets.insert(url, path);
+    }
+  }
+  let links = ArchiveLinks {
+    path: &entry.path,
+    views,
+    assets,
+  };
+  Ok(document_to_markdown(&data, &links))
+}
+
+/// Converts the rows of the database view into CSV, in the order of the view.
+async fn export_database(
+  context: &mut ExportContext,
+  workspace_id: &str,
+  workspace_database: &WorkspaceDatabase,
+  view_id: &str,
+) -> Result<String, WorkerError> {
+  let database_id = workspace_database
+    .get_database_meta_with_view_id(view_id)
+    .ok_or_else(|| anyhow!("Database of view {} not found", view_id))?
+    .database_id;
+  let collab = open_collab(context, workspace_id, &database_id, CollabType::Database).await?;
+  let body = DatabaseBody::from_collab(&collab, Arc::new(NoPersistenceDatabaseCollabService), None)
+    .ok_or_else(|| anyhow!("Failed to open database {}", database_id))?;
+  let (fields, row_ids) = {
+    let txn = collab.transact();
+    let view = body
+      .views
+      .get_view(&txn, view_id)
+      .ok_or_else(|| anyhow!("Database view {} not found", view_id))?;
+    let field_order_ids = view
+      .field_orders
+      .iter()
+      .map(|field_order| field_order.id.clone())
+      .collect::<Vec<_>>();
+    let fields = fields_in_view_order(body.fields.get_all_fields(&txn), &field_order_ids);
+    let row_ids = view
+      .row_orders
+      .iter()
+      .map(|row_order| row_order.id.to_string())
+      .collect::<Vec<_>>();
+    (fields, row_ids)
+  };
+
+  let mut rows: Vec<Row> = Vec::with_capacity(row_ids.len());
+  for row_id in row_ids {
+    let encoded_collab = match get_encode_collab_from_bytes(
+      workspace_id,
+      &row_id,
+      &CollabType::DatabaseRow,
+      &context.pg_pool,
+      &context.s3_client,
+      context.collab_encryption.as_ref(),
+    )
+    .await
+    {
+      Ok(encoded_collab) => encoded_collab,
+      Err(err) => {
+        warn!(
+          "[Export] skip row {} of database {}: {:?}",
+          row_id, database_id, err
+        );
+        continue;
+      },
+    };
+    let row = Collab::new_with_source(
+      CollabOrigin::Server,
+      &row_id,
+      encoded_collab.into(),
+      vec![],
+      false,
+    )
+    .ok()
+    .and_then(|collab| RowDetail::from_collab(&collab))
+    .map(|row_detail| row_detail.row);
+    match row {
+      Some(row) => rows.push(row),
+      None => warn!(
+        "[Export] skip invalid row {} of database {}",
+        row_id, database_id
+      ),
+    }
+  }
+  Ok(database_to_csv(&fields, &rows)?)
+}
+
+/// Copies the blob into the archive. Blobs deduplicated by content are read from their content
+/// object.
+async fn write_asset<W>(
+  context: &ExportContext,
+  workspace_id: &Uuid,
+  url: &str,
+  path: &str,
+  writer: &mut ZipFileWriter<W>,
+) -> Result<(), WorkerError>
+where
+  W: futures::AsyncWrite + Unpin,
+{
+  let (parent_dir, file_id) = parse_blob_url(url, &workspace_id.to_string())
+    .ok_or_else(|| anyhow!("Invalid blob url: {}", url))?;
+  let content_hash = select_blob_content_hash(
+    &context.pg_pool,
+    workspace_id,
+    &format!("{}_{}", parent_dir, file_id),
+  )
+  .await
+  .map_err(app_error_to_worker_error)?;
+  let object_key = match content_hash {
+    Some(content_hash) => blob_content_object_key(workspace_id, &content_hash),
+    None => format!("{}/{}/{}", workspace_id, parent_dir, file_id),
+  };
+  let mut blob = context.s3_client.get_blob_stream(&object_key).await?;
+  let mut entry_writer = writer
+    .write_entry_stream(ZipEntryBuilder::new(
+      path.to_string().into(),
+      Compression::Deflate,
+    ))
+    .await?;
+  futures::io::copy(&mut blob.stream, &mut entry_writer).await?;
+  entry_writer.close().await?;
+  Ok(())
+}
+
+/// Deletes the archives that expired, whose links sent to the users aren't valid anymore.
+async fn delete_expired_archives(context: &ExportContext) {
+  let expired_before = Utc::now() - ChronoDuration::hours(context.archive_expiration_hours as i64);
+  let tasks =
+    match select_expired_export_tasks(&context.pg_pool, expired_before, EXPIRED_ARCHIVE_BATCH_SIZE)
+      .await
+    {
+      Ok(tasks) => tasks,
+      Err(err) => {
+        error!("[Export] failed to select expired export tasks: {:?}", err);
+        return;
+      },
+    };
+  for task in tasks {
+    if let Some(s3_key) = task.s3_key.as_ref() {
+      if let Err(err) = context.s3_client.delete_blob(s3_key).await {
+        error!("[Export] failed to delete archive {}: {:?}", s3_key, err);
+        continue;
+      }
+    }
+    if let Err(err) = update_export_task_status(
+      &context.pg_pool,
+      &task.task_id,
+      ExportTaskState::Expire,
+      json!({}),
+    )
+    .await
+    {
+      error!(
+        "[Export] failed to expire export task {}: {:?}",
+        task.task_id, err
+      );
+    }
+  }
+}
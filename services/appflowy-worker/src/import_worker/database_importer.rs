@@ -0,0 +1,835 @@
+use crate::error::ImportError;
+use anyhow::anyhow;
+use async_zip::base::read::stream::ZipFileReader;
+use collab_database::database::{gen_field_id, gen_row_id, Database, DatabaseContext};
+use collab_database::entity::{CreateDatabaseParams, CreateViewParams, EncodedDatabase, FieldType};
+use collab_database::fields::select_type_option::{
+  MultiSelectTypeOption, SelectOption, SelectOptionColor, SelectOptionIds, SingleSelectTypeOption,
+};
+use collab_database::fields::{default_field_settings_for_fields, Field};
+use collab_database::rows::{new_cell_builder, Cell, CreateRowParams};
+use collab_database::template::entity::CELL_DATA;
+use collab_database::views::DatabaseLayout;
+use collab_database::workspace_database::NoPersistenceDatabaseCollabService;
+use database_entity::dto::{ImportRowError, SpreadsheetFormat};
+use sqlx::types::chrono::{DateTime, NaiveDate, NaiveDateTime};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::Arc;
+use tokio_util::compat::TokioAsyncReadCompatExt;
+
+/// A column whose values only use a few distinct options is imported as a select field.
+const MAX_SELECT_OPTIONS: usize = 20;
+const MAX_SELECT_OPTION_LEN: usize = 40;
+const DATE_FORMATS: [&str; 4] = ["%Y-%m-%d", "%Y/%m/%d", "%m/%d/%Y", "%d.%m.%Y"];
+const DATE_TIME_FORMATS: [&str; 5] = [
+  "%Y-%m-%d %H:%M:%S",
+  "%Y-%m-%dT%H:%M:%S",
+  "%Y-%m-%d %H:%M",
+  "%Y/%m/%d %H:%M:%S",
+  "%m/%d/%Y %H:%M",
+];
+
+/// The content of an imported CSV file or Excel worksheet.
+#[derive(Debug, Default)]
+pub struct Spreadsheet {
+  pub headers: Vec<String>,
+  /// The rows after the header, with their 1-based number in the file
+  pub rows: Vec<(u64, Vec<String>)>,
+  /// The rows that couldn't be read
+  pub errors: Vec<ImportRowError>,
+}
+
+pub async fn read_spreadsheet(
+  path: &Path,
+  format: SpreadsheetFormat,
+) -> Result<Spreadsheet, ImportError> {
+  let rows = match format {
+    SpreadsheetFormat::Csv => {
+      let path = path.to_path_buf();
+      tokio::task::spawn_blocking(move || read_csv(&path))
+        .await
+        .map_err(|err| ImportError::Internal(err.into()))??
+    },
+    SpreadsheetFormat::Xlsx => read_xlsx(path).await?,
+  };
+  spreadsheet_from_rows(rows)
+}
+
+fn spreadsheet_from_rows(
+  rows: Vec<(u64, Result<Vec<String>, String>)>,
+) -> Result<Spreadsheet, ImportError> {
+  let mut spreadsheet = Spreadsheet::default();
+  let mut rows = rows.into_iter().filter(|(_, row)| match row {
+    Ok(cells) => cells.iter().any(|cell| !cell.trim().is_empty()),
+    Err(_) => true,
+  });
+  match rows.next() {
+    Some((_, Ok(headers))) => {
+      spreadsheet.headers = headers
+        .into_iter()
+        .enumerate()
+        .map(|(i, header)| {
+          let header = header.trim_start_matches('\u{feff}').trim();
+          if header.is_empty() {
+            format!("Column {}", i + 1)
+          } else {
+            header.to_string()
+          }
+        })
+        .collect();
+    },
+    Some((_, Err(err))) => {
+      return Err(ImportError::InvalidSpreadsheet(format!(
+        "Failed to read the header: {}",
+        err
+      )))
+    },
+    None => {
+      return Err(ImportError::InvalidSpreadsheet(
+        "The file is empty".to_string(),
+      ))
+    },
+  }
+
+  for (row, cells) in rows {
+    match cells {
+      Ok(mut cells) => {
+        if cells.len() > spreadsheet.headers.len() {
+          let dropped = cells.split_off(spreadsheet.headers.len());
+          if dropped.iter().any(|cell| !cell.trim().is_empty()) {
+            spreadsheet.errors.push(ImportRowError {
+              row,
+              message: format!(
+                "The row has {} cells but the header only has {}, the extra cells were dropped",
+                cells.len() + dropped.len(),
+                spreadsheet.headers.len()
+              ),
+            });
+          }
+        }
+        spreadsheet.rows.push((row, cells));
+      },
+      Err(message) => spreadsheet.errors.push(ImportRowError { row, message }),
+    }
+  }
+  Ok(spreadsheet)
+}
+
+fn read_csv(path: &Path) -> Result<Vec<(u64, Result<Vec<String>, String>)>, ImportError> {
+  let mut reader = csv::ReaderBuilder::new()
+    .has_headers(false)
+    .flexible(true)
+    .from_path(path)
+    .map_err(|err| ImportError::InvalidSpreadsheet(err.to_string()))?;
+  let rows = reader
+    .records()
+    .enumerate()
+    .map(|(i, record)| {
+      let cells = record
+        .map(|record| record.iter().map(|cell| cell.to_string()).collect())
+        .map_err(|err| err.to_string());
+      (i as u64 + 1, cells)
+    })
+    .collect();
+  Ok(rows)
+}
+
+/// Reads the first worksheet of an Excel workbook. Dates are stored as numbers by Excel, so they
+/// are imported as numbers.
+async fn read_xlsx(path: &Path) -> Result<Vec<(u64, Result<Vec<String>, String>)>, ImportError> {
+  let file = tokio::fs::File::open(path)
+    .await
+    .map_err(|err| ImportError::Internal(err.into()))?;
+  let mut zip = ZipFileReader::new(tokio::io::BufReader::new(file).compat());
+  let mut shared_strings = None;
+  let mut sheet = None;
+  while let Some(mut entry) = zip
+    .next_with_entry()
+    .await
+    .map_err(|err| ImportError::InvalidSpreadsheet(err.to_string()))?
+  {
+    let file_name = entry
+      .reader()
+      .entry()
+      .filename()
+      .as_str()
+      .unwrap_or_default()
+      .to_string();
+    if file_name == "xl/sharedStrings.xml" || file_name == "xl/worksheets/sheet1.xml" {
+      let mut content = Vec::new();
+      entry
+        .reader_mut()
+        .read_to_end_checked(&mut content)
+        .await
+        .map_err(|err| ImportError::InvalidSpreadsheet(err.to_string()))?;
+      let content = String::from_utf8_lossy(&content).to_string();
+      if file_name == "xl/sharedStrings.xml" {
+        shared_strings = Some(content);
+      } else {
+        sheet = Some(content);
+      }
+      zip = entry
+        .done()
+        .await
+        .map_err(|err| ImportError::InvalidSpreadsheet(err.to_string()))?;
+    } else {
+      zip = entry
+        .skip()
+        .await
+        .map_err(|err| ImportError::InvalidSpreadsheet(err.to_string()))?;
+    }
+  }
+
+  let sheet = sheet.ok_or_else(|| {
+    ImportError::InvalidSpreadsheet("The workbook doesn't have any worksheet".to_string())
+  })?;
+  let shared_strings = shared_strings
+    .map(|xml| parse_shared_strings(&xml))
+    .unwrap_or_default();
+  Ok(
+    parse_sheet(&sheet, &shared_strings)
+      .into_iter()
+      .map(|(row, cells)| (row, Ok(cells)))
+      .collect(),
+  )
+}
+
+enum XmlToken<'a> {
+  Start {
+    name: &'a str,
+    attrs: &'a str,
+    empty: bool,
+  },
+  End(&'a str),
+  Text(&'a str),
+}
+
+/// A minimal XML tokenizer, enough to read the worksheets and shared strings of a workbook.
+struct XmlTokens<'a> {
+  rest: &'a str,
+}
+
+impl<'a> Iterator for XmlTokens<'a> {
+  type Item = XmlToken<'a>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    loop {
+      if self.rest.is_empty() {
+        return None;
+      }
+      if !self.rest.starts_with('<') {
+        let end = self.rest.find('<').unwrap_or(self.rest.len());
+        let text = &self.rest[..end];
+        self.rest = &self.rest[end..];
+        return Some(XmlToken::Text(text));
+      }
+      if let Some(rest) = self.rest.strip_prefix("<![CDATA[") {
+        let end = rest.find("]]>").unwrap_or(rest.len());
+        self.rest = rest.get(end + 3..).unwrap_or_default();
+        return Some(XmlToken::Text(&rest[..end]));
+      }
+      let end = self.rest.find('>')?;
+      let tag = &self.rest[1..end];
+      self.rest = &self.rest[end + 1..];
+      if tag.starts_with('?') || tag.starts_with('!') {
+        continue;
+      }
+      if let Some(name) = tag.strip_prefix('/') {
+        return Some(XmlToken::End(local_name(name.trim())));
+      }
+      let empty = tag.ends_with('/');
+      let tag = tag.trim_end_matches('/');
+      let (name, attrs) = tag.split_once(char::is_whitespace).unwrap_or((tag, ""));
+      return Some(XmlToken::Start {
+        name: local_name(name),
+        attrs,
+        empty,
+      });
+    }
+  }
+}
+
+fn xml_tokens(xml: &str) -> XmlTokens {
+  XmlTokens { rest: xml }
+}
+
+fn local_name(name: &str) -> &str {
+  name.rsplit(':').next().unwrap_or(name)
+}
+
+fn xml_attr<'a>(attrs: &'a str, key: &str) -> Option<&'a str> {
+  let mut rest = attrs;
+  while let Some(eq) = rest.find('=') {
+    let name = rest[..eq].trim();
+    let value = rest[eq + 1..].trim_start();
+    let quote = value.chars().next()?;
+    let value = &value[1..];
+    let end = value.find(quote)?;
+    if name == key {
+      return Some(&value[..end]);
+    }
+    rest = &value[end + 1..];
+  }
+  None
+}
+
+fn xml_unescape(text: &str) -> String {
+  let mut output = String::with_capacity(text.len());
+  let mut rest = text;
+  while let Some(start) = rest.find('&') {
+    output.push_str(&rest[..start]);
+    rest = &rest[start..];
+    let Some(end) = rest.find(';') else {
+      break;
+    };
+    let entity = &rest[1..end];
+    let c = match entity {
+      "amp" => Some('&'),
+      "lt" => Some('<'),
+      "gt" => Some('>'),
+      "quot" => Some('"'),
+      "apos" => Some('\''),
+      _ => entity
+        .strip_prefix("#x")
+        .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+        .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+        .and_then(char::from_u32),
+    };
+    match c {
+      Some(c) => {
+        output.push(c);
+        rest = &rest[end + 1..];
+      },
+      None => {
+        output.push('&');
+        rest = &rest[1..];
+      },
+    }
+  }
+  output.push_str(rest);
+  output
+}
+
+fn parse_shared_strings(xml: &str) -> Vec<String> {
+  let mut strings = vec![];
+  let mut current = String::new();
+  let mut in_text = false;
+  // The phonetic runs of a string repeat its text
+  let mut in_phonetic = false;
+  for token in xml_tokens(xml) {
+    match token {
+      XmlToken::Start { name: "si", .. } => current.clear(),
+      XmlToken::Start {
+        name: "t",
+        empty: false,
+        ..
+      } => in_text = true,
+      XmlToken::Start {
+        name: "rPh",
+        empty: false,
+        ..
+      } => in_phonetic = true,
+      XmlToken::End("t") => in_text = false,
+      XmlToken::End("rPh") => in_phonetic = false,
+      XmlToken::End("si") => strings.push(std::mem::take(&mut current)),
+      XmlToken::Text(text) if in_text && !in_phonetic => current.push_str(&xml_unescape(text)),
+      _ => {},
+    }
+  }
+  strings
+}
+
+/// Returns the 0-based column of a cell reference, e.g. 27 for `AB12`.
+fn column_index(cell_ref: &str) -> Option<usize> {
+  let letters = cell_ref
+    .chars()
+    .take_while(|c| c.is_ascii_alphabetic())
+    .collect::<String>();
+  if letters.is_empty() {
+    return None;
+  }
+  let index = letters
+    .to_ascii_uppercase()
+    .chars()
+    .fold(0usize, |index, c| {
+      index * 26 + (c as usize - 'A' as usize + 1)
+    });
+  Some(index - 1)
+}
+
+fn parse_sheet(xml: &str, shared_strings: &[String]) -> Vec<(u64, Vec<String>)> {
+  let mut rows = vec![];
+  let mut current_row: Option<(u64, Vec<String>)> = None;
+  let mut cell: Option<(Option<usize>, String, String)> = None;
+  let mut in_value = false;
+  for token in xml_tokens(xml) {
+    match token {
+      XmlToken::Start {
+        name: "row",
+        attrs,
+        empty,
+      } => {
+        let number = xml_attr(attrs, "r")
+          .and_then(|r| r.parse().ok())
+          .unwrap_or_else(|| rows.len() as u64 + 1);
+        if !empty {
+          current_row = Some((number, vec![]));
+        }
+      },
+      XmlToken::Start {
+        name: "c",
+        attrs,
+        empty: false,
+      } => {
+        let column = xml_attr(attrs, "r").and_then(column_index);
+        let cell_type = xml_attr(attrs, "t").unwrap_or_default().to_string();
+        cell = Some((column, cell_type, String::new()));
+      },
+      XmlToken::Start {
+        name: "v" | "t",
+        empty: false,
+        ..
+      } => in_value = true,
+      XmlToken::End("v" | "t") => in_value = false,
+      XmlToken::Text(text) if in_value => {
+        if let Some((_, _, value)) = cell.as_mut() {
+          value.push_str(&xml_unescape(text));
+        }
+      },
+      XmlToken::End("c") => {
+        if let (Some((column, cell_type, value)), Some((_, cells))) =
+          (cell.take(), current_row.as_mut())
+        {
+          let value = match cell_type.as_str() {
+            "s" => value
+              .trim()
+              .parse::<usize>()
+              .ok()
+              .and_then(|index| shared_strings.get(index).cloned())
+              .unwrap_or_default(),
+            "b" => (if value.trim() == "1" { "true" } else { "false" }).to_string(),
+            _ => value,
+          };
+          let column = column.unwrap_or(cells.len());
+          if cells.len() <= column {
+            cells.resize(column + 1, String::new());
+          }
+          cells[column] = value;
+        }
+      },
+      XmlToken::End("row") => {
+        if let Some(row) = current_row.take() {
+          rows.push(row);
+        }
+      },
+      _ => {},
+    }
+  }
+  rows
+}
+
+/// The type of an imported column, inferred from its values.
+#[derive(Debug, PartialEq)]
+enum ColumnType {
+  Text,
+  Number,
+  Checkbox,
+  DateTime,
+  Url,
+  SingleSelect(Vec<String>),
+  MultiSelect(Vec<String>),
+}
+
+fn parse_checkbox(value: &str) -> Option<bool> {
+  match value.to_ascii_lowercase().as_str() {
+    "true" | "yes" => Some(true),
+    "false" | "no" => Some(false),
+    _ => None,
+  }
+}
+
+/// Parses a date into its timestamp in seconds, and whether it includes a time.
+fn parse_date(value: &str) -> Option<(i64, bool)> {
+  if let Ok(date_time) = DateTime::parse_from_rfc3339(value) {
+    return Some((date_time.timestamp(), true));
+  }
+  for format in DATE_TIME_FORMATS {
+    if let Ok(date_time) = NaiveDateTime::parse_from_str(value, format) {
+      return Some((date_time.and_utc().timestamp(), true));
+    }
+  }
+  for format in DATE_FORMATS {
+    if let Ok(date) = NaiveDate::parse_from_str(value, format) {
+      return date
+        .and_hms_opt(0, 0, 0)
+        .map(|date_time| (date_time.and_utc().timestamp(), false));
+    }
+  }
+  None
+}
+
+fn is_url(value: &str) -> bool {
+  (value.starts_with("http://") || value.starts_with("https://"))
+    && !value.contains(char::is_whitespace)
+}
+
+/// Returns the distinct options of the values, in the order they first appear, if there are few
+/// enough of them compared to the number of values.
+fn select_options<'a>(values: impl Iterator<Item = &'a str>) -> Option<Vec<String>> {
+  let mut options = vec![];
+  let mut seen = HashSet::new();
+  let mut count = 0;
+  for value in values {
+    count += 1;
+    if value.chars().count() > MAX_SELECT_OPTION_LEN {
+      return None;
+    }
+    if seen.insert(value) {
+      options.push(value.to_string());
+      if options.len() > MAX_SELECT_OPTIONS {
+        return None;
+      }
+    }
+  }
+  // Each option must be used twice on average, otherwise the column is more likely free text
+  (count >= 2 && options.len() * 2 <= count).then_some(options)
+}
+
+fn option_color(index: usize) -> SelectOptionColor {
+  match index % 9 {
+    0 => SelectOptionColor::Purple,
+    1 => SelectOptionColor::Pink,
+    2 => SelectOptionColor::LightPink,
+    3 => SelectOptionColor::Orange,
+    4 => SelectOptionColor::Yellow,
+    5 => SelectOptionColor::Lime,
+    6 => SelectOptionColor::Green,
+    7 => SelectOptionColor::Aqua,
+    _ => SelectOptionColor::Blue,
+  }
+}
+
+fn split_multi_select(value: &str) -> impl Iterator<Item = &str> {
+  value
+    .split(',')
+    .map(|option| option.trim())
+    .filter(|option| !option.is_empty())
+}
+
+fn infer_column_type(values: &[&str]) -> ColumnType {
+  let values = values
+    .iter()
+    .map(|value| value.trim())
+    .filter(|value| !value.is_empty())
+    .collect::<Vec<_>>();
+  if values.is_empty() {
+    return ColumnType::Text;
+  }
+  if values.iter().all(|value| parse_checkbox(value).is_some()) {
+    return ColumnType::Checkbox;
+  }
+  if values.iter().all(|value| value.parse::<f64>().is_ok()) {
+    return ColumnType::Number;
+  }
+  if values.iter().all(|value| parse_date(value).is_some()) {
+    return ColumnType::DateTime;
+  }
+  if values.iter().all(|value| is_url(value)) {
+    return ColumnType::Url;
+  }
+  if let Some(options) = select_options(values.iter().copied()) {
+    return ColumnType::SingleSelect(options);
+  }
+  if values.iter().any(|value| value.contains(',')) {
+    if let Some(options) = select_options(values.iter().flat_map(|value| split_multi_select(value)))
+    {
+      return ColumnType::MultiSelect(options);
+    }
+  }
+  ColumnType::Text
+}
+
+struct ImportedColumn {
+  field: Field,
+  /// Option name => option id, for the select fields
+  option_ids: HashMap<String, String>,
+}
+
+impl ImportedColumn {
+  fn new(name: &str, column_type: ColumnType, is_primary: bool) -> Self {
+    let mut option_ids = HashMap::new();
+    let mut build_options = |names: Vec<String>| {
+      names
+        .into_iter()
+        .enumerate()
+        .map(|(i, name)| {
+          let option = SelectOption::with_color(&name, option_color(i));
+          option_ids.insert(name, option.id.clone());
+          option
+        })
+        .collect::<Vec<_>>()
+    };
+
+    let field = match column_type {
+      ColumnType::SingleSelect(names) => {
+        let mut type_option = SingleSelectTypeOption::default();
+        type_option.options.extend(build_options(names));
+        let mut field = Field::new(
+          gen_field_id(),
+          name.to_string(),
+          FieldType::SingleSelect.into(),
+          is_primary,
+        );
+        field
+          .type_options
+          .insert(FieldType::SingleSelect.to_string(), type_option.into());
+        field
+      },
+      ColumnType::MultiSelect(names) => {
+        let mut type_option = MultiSelectTypeOption::default();
+        type_option.options.extend(build_options(names));
+        let mut field = Field::new(
+          gen_field_id(),
+          name.to_string(),
+          FieldType::MultiSelect.into(),
+          is_primary,
+        );
+        field
+          .type_options
+          .insert(FieldType::MultiSelect.to_string(), type_option.into());
+        field
+      },
+      column_type => {
+        let field_type = match column_type {
+          ColumnType::Number => FieldType::Number,
+          ColumnType::Checkbox => FieldType::Checkbox,
+          ColumnType::DateTime => FieldType::DateTime,
+          ColumnType::Url => FieldType::URL,
+          _ => FieldType::RichText,
+        };
+        Field::from_field_type(name, field_type, is_primary)
+      },
+    };
+    Self { field, option_ids }
+  }
+
+  fn to_cell(&self, value: &str) -> Option<Cell> {
+    let value = value.trim();
+    if value.is_empty() {
+      return None;
+    }
+    let field_type = FieldType::from(self.field.field_type);
+    let cell = match field_type {
+      FieldType::SingleSelect => SelectOptionIds::from(vec![self.option_ids.get(value)?.clone()])
+        .to_cell(FieldType::SingleSelect),
+      FieldType::MultiSelect => SelectOptionIds::from(
+        split_multi_select(value)
+          .filter_map(|option| self.option_ids.get(option).cloned())
+          .collect::<Vec<_>>(),
+      )
+      .to_cell(FieldType::MultiSelect),
+      FieldType::Checkbox => {
+        let mut cell = new_cell_builder(FieldType::Checkbox);
+        let checked = if parse_checkbox(value)? { "Yes" } else { "No" };
+        cell.insert(CELL_DATA.into(), checked.into());
+        cell
+      },
+      FieldType::DateTime => {
+        let (timestamp, include_time) = parse_date(value)?;
+        let mut cell = new_cell_builder(FieldType::DateTime);
+        cell.insert(CELL_DATA.into(), timestamp.to_string().into());
+        cell.insert("include_time".into(), include_time.into());
+        cell
+      },
+      _ => {
+        let mut cell = new_cell_builder(field_type);
+        cell.insert(CELL_DATA.into(), value.to_string().into());
+        cell
+      },
+    };
+    Some(cell)
+  }
+}
+
+/// Builds a grid database from the spreadsheet. The type of the fields is inferred from the values
+/// of their column, except for the primary field which is always a text field.
+pub async fn build_grid_database(
+  spreadsheet: &Spreadsheet,
+  database_id: &str,
+  view_id: &str,
+  name: &str,
+) -> Result<EncodedDatabase, ImportError> {
+  let columns = spreadsheet
+    .headers
+    .iter()
+    .enumerate()
+    .map(|(i, header)| {
+      let column_type = if i == 0 {
+        ColumnType::Text
+      } else {
+        let values = spreadsheet
+          .rows
+          .iter()
+          .map(|(_, cells)| cells.get(i).map(|cell| cell.as_str()).unwrap_or_default())
+          .collect::<Vec<_>>();
+        infer_column_type(&values)
+      };
+      ImportedColumn::new(header, column_type, i == 0)
+    })
+    .collect::<Vec<_>>();
+
+  let rows = spreadsheet
+    .rows
+    .iter()
+    .map(|(_, cells)| {
+      let mut row = CreateRowParams::new(gen_row_id(), database_id.to_string());
+      for (column, value) in columns.iter().zip(cells) {
+        if let Some(cell) = column.to_cell(value) {
+          row.cells.insert(column.field.id.clone(), cell);
+        }
+      }
+      row
+    })
+    .collect();
+
+  let fields = columns
+    .into_iter()
+    .map(|column| column.field)
+    .collect::<Vec<_>>();
+  let timestamp = collab_database::database::timestamp();
+  let field_settings = default_field_settings_for_fields(&fields, DatabaseLayout::Grid);
+  let params = CreateDatabaseParams {
+    database_id: database_id.to_string(),
+    fields,
+    rows,
+    views: vec![CreateViewParams {
+      database_id: database_id.to_string(),
+      view_id: view_id.to_string(),
+      name: name.to_string(),
+      layout: DatabaseLayout::Grid,
+      field_settings,
+      created_at: timestamp,
+      modified_at: timestamp,
+      ..Default::default()
+    }],
+  };
+  let context = DatabaseContext::new(Arc::new(NoPersistenceDatabaseCollabService));
+  let database = Database::create_with_view(params, context)
+    .await
+    .map_err(|err| ImportError::Internal(anyhow!("Failed to create database: {}", err)))?;
+  database
+    .encode_database_collabs()
+    .await
+    .map_err(|err| ImportError::Internal(anyhow!("Failed to encode database: {}", err)))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn infer_column_type_test() {
+    assert_eq!(
+      infer_column_type(&["1", "2.5", "", "-3"]),
+      ColumnType::Number
+    );
+    assert_eq!(
+      infer_column_type(&["Yes", "no", "TRUE"]),
+      ColumnType::Checkbox
+    );
+    assert_eq!(
+      infer_column_type(&["2024-01-02", "2024-03-04 10:00"]),
+      ColumnType::DateTime
+    );
+    assert_eq!(
+      infer_column_type(&["https://appflowy.io", "http://a.b/c"]),
+      ColumnType::Url
+    );
+    assert_eq!(
+      infer_column_type(&["Todo", "Done", "Todo", "Done"]),
+      ColumnType::SingleSelect(vec!["Todo".to_string(), "Done".to_string()])
+    );
+    assert_eq!(
+      infer_column_type(&["a, b", "b", "a", "c, a"]),
+      ColumnType::MultiSelect(vec!["a".to_string(), "b".to_string(), "c".to_string()])
+    );
+    assert_eq!(
+      infer_column_type(&["first note", "second note"]),
+      ColumnType::Text
+    );
+    assert_eq!(infer_column_type(&["", " "]), ColumnType::Text);
+  }
+
+  #[test]
+  fn parse_date_test() {
+    assert_eq!(parse_date("1970-01-02"), Some((86400, false)));
+    assert_eq!(parse_date("01/02/1970"), Some((86400, false)));
+    assert_eq!(parse_date("1970-01-01T00:01:00Z"), Some((60, true)));
+    assert_eq!(parse_date("yesterday"), None);
+  }
+
+  #[test]
+  fn spreadsheet_row_errors_test() {
+    let rows = vec![
+      (1, Ok(vec!["\u{feff}Name".to_string(), "".to_string()])),
+      (
+        2,
+        Ok(vec!["a".to_string(), "1".to_string(), "extra".to_string()]),
+      ),
+      (3, Ok(vec!["".to_string()])),
+      (4, Err("invalid UTF-8".to_string())),
+      (5, Ok(vec!["b".to_string()])),
+    ];
+    let spreadsheet = spreadsheet_from_rows(rows).unwrap();
+    assert_eq!(spreadsheet.headers, vec!["Name", "Column 2"]);
+    assert_eq!(
+      spreadsheet.rows,
+      vec![
+        (2, vec!["a".to_string(), "1".to_string()]),
+        (5, vec!["b".to_string()])
+      ]
+    );
+    assert_eq!(
+      spreadsheet
+        .errors
+        .iter()
+        .map(|error| error.row)
+        .collect::<Vec<_>>(),
+      vec![2, 4]
+    );
+  }
+
+  #[test]
+  fn parse_sheet_test() {
+    let shared_strings = parse_shared_strings(
+      r#"<?xml version="1.0"?><sst><si><t>Name</t></si><si><r><t>Tom </t></r><r><t xml:space="preserve">&amp; Jerry</t></r><rPh><t>x</t></rPh></si></sst>"#,
+    );
+    assert_eq!(shared_strings, vec!["Name", "Tom & Jerry"]);
+
+    let rows = parse_sheet(
+      r#"<worksheet><sheetData>
+        <row r="1"><c r="A1" t="s"><v>0</v></c><c r="C1" t="inlineStr"><is><t>Done</t></is></c></row>
+        <row r="3"><c r="A3" t="s"><v>1</v></c><c r="B3"><v>42</v></c><c r="C3" t="b"><v>1</v></c></row>
+      </sheetData></worksheet>"#,
+      &shared_strings,
+    );
+    assert_eq!(
+      rows,
+      vec![
+        (
+          1,
+          vec!["Name".to_string(), "".to_string(), "Done".to_string()]
+        ),
+        (
+          3,
+          vec![
+            "Tom & Jerry".to_string(),
+            "42".to_string(),
+            "true".to_string()
+          ]
+        ),
+      ]
+    );
+  }
+}
@@ -0,0 +1,1005 @@
+use crate::error::{CollabImporterError, ImportError};
+use crate::import_worker::worker::NotionImportTask;
+use anyhow::anyhow;
+use collab::entity::EncodedCollab;
+use collab_document::document::Document;
+use collab_document::importer::md_importer::MDImporter;
+use collab_folder::hierarchy_builder::NestedChildViewBuilder;
+use collab_folder::{SpaceInfo, View, ViewLayout};
+use collab_importer::util::FileId;
+use database::resource_usage::BulkInsertMeta;
+use scraper::{ElementRef, Html, Node, Selector};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use tracing::{trace, warn};
+use uuid::Uuid;
+
+/// The format of the pages inside a zip that is imported by the [FolderImporter].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FolderImportFormat {
+  /// Markdown files, e.g. an Obsidian vault. Supports front matter, relative links and images,
+  /// and the `[[wiki link]]` and `![[embed]]` syntax.
+  Markdown,
+  /// HTML pages, e.g. a Confluence space export.
+  Html,
+}
+
+impl FolderImportFormat {
+  fn is_page(&self, path: &Path) -> bool {
+    let ext = path
+      .extension()
+      .and_then(|ext| ext.to_str())
+      .map(|ext| ext.to_lowercase())
+      .unwrap_or_default();
+    match self {
+      FolderImportFormat::Markdown => ext == "md" || ext == "markdown",
+      FolderImportFormat::Html => ext == "html" || ext == "htm",
+    }
+  }
+}
+
+pub struct ImportedFolder {
+  /// The views to insert into the workspace folder. Parents always come before their children,
+  /// starting with the space that holds all the imported pages.
+  pub views: Vec<View>,
+  pub documents: Vec<(String, EncodedCollab)>,
+  pub files: Vec<ImportedFile>,
+}
+
+/// A file referenced by one of the imported documents, which has to be uploaded to the bucket.
+pub struct ImportedFile {
+  pub file_path: PathBuf,
+  pub meta: BulkInsertMeta,
+}
+
+/// Builds the folder hierarchy and documents from an unzipped folder of Markdown or HTML pages.
+///
+/// Every directory and page becomes a document view, nested under a new space named after the
+/// workspace. A page and a directory with the same name, as Notion-like exports produce for
+/// pages with sub pages, are merged into one view. Links to other pages are rewritten to point
+/// to the imported views, and the images and files referenced by the pages are registered as
+/// blobs of the document that embeds them.
+pub struct FolderImporter {
+  uid: i64,
+  workspace_id: String,
+  workspace_name: String,
+  host: String,
+  format: FolderImportFormat,
+  root: PathBuf,
+}
+
+struct PageNode {
+  view_id: String,
+  name: String,
+  page_path: Option<PathBuf>,
+  children: BTreeMap<String, PageNode>,
+}
+
+impl PageNode {
+  fn new(name: String) -> Self {
+    Self {
+      view_id: Uuid::new_v4().to_string(),
+      name,
+      page_path: None,
+      children: BTreeMap::new(),
+    }
+  }
+}
+
+#[derive(Default)]
+struct LinkTargets {
+  /// Relative page path => view id
+  pages: HashMap<PathBuf, String>,
+  /// Lowercased page file stem => view id, used to resolve wiki links
+  page_names: HashMap<String, String>,
+  /// Relative paths of all non-page files
+  files: HashSet<PathBuf>,
+  /// Lowercased file name => relative path, used to resolve embeds
+  file_names: HashMap<String, PathBuf>,
+}
+
+impl FolderImporter {
+  pub fn new(task: &NotionImportTask, root: &Path, format: FolderImportFormat) -> Self {
+    Self {
+      uid: task.uid,
+      workspace_id: task.workspace_id.clone(),
+      workspace_name: task.workspace_name.clone(),
+      host: task.host.clone(),
+      format,
+      root: root.to_path_buf(),
+    }
+  }
+
+  pub fn import(self) -> Result<ImportedFolder, ImportError> {
+    let root = content_root(&self.root);
+    let mut targets = LinkTargets::default();
+    let mut tree = PageNode::new(self.workspace_name.clone());
+    collect_entries(&root, Path::new(""), self.format, &mut tree, &mut targets)?;
+    if tree.children.is_empty() {
+      return Err(ImportError::ImportCollabError(
+        CollabImporterError::CannotImport,
+      ));
+    }
+
+    let space_view = NestedChildViewBuilder::new(self.uid, self.workspace_id.clone())
+      .with_view_id(tree.view_id.clone())
+      .with_name(&self.workspace_name)
+      .with_extra(|builder| builder.with_space_info(SpaceInfo::default()).build())
+      .build()
+      .view;
+
+    let mut imported = ImportedFolder {
+      views: vec![space_view],
+      documents: vec![],
+      files: vec![],
+    };
+    let mut file_ids = HashMap::new();
+    for child in tree.children.values() {
+      self.import_node(
+        &root,
+        child,
+        &tree.view_id,
+        &targets,
+        &mut file_ids,
+        &mut imported,
+      )?;
+    }
+    Ok(imported)
+  }
+
+  fn import_node(
+    &self,
+    root: &Path,
+    node: &PageNode,
+    parent_view_id: &str,
+    targets: &LinkTargets,
+    file_ids: &mut HashMap<PathBuf, String>,
+    imported: &mut ImportedFolder,
+  ) -> Result<(), ImportError> {
+    let mut name = node.name.clone();
+    let mut markdown = String::new();
+    if let Some(page_path) = &node.page_path {
+      let content = fs::read_to_string(root.join(page_path))
+        .map_err(|err| ImportError::Internal(anyhow!("Failed to read {:?}: {}", page_path, err)))?;
+      let (title, body) = match self.format {
+        FolderImportFormat::Markdown => split_front_matter(&content),
+        FolderImportFormat::Html => html_to_markdown(&content),
+      };
+      if let Some(title) = title.filter(|title| !title.is_empty()) {
+        name = title;
+      }
+
+      let base_dir = page_path.parent().unwrap_or(Path::new("")).to_path_buf();
+      let mut files = vec![];
+      markdown = self.rewrite_links(&body, &base_dir, targets, &mut |path| {
+        let file_id = file_id_of(root, path, file_ids)?;
+        files.push(path.to_path_buf());
+        Some(self.blob_url(&node.view_id, &file_id))
+      });
+
+      let mut seen = HashSet::new();
+      for path in files {
+        let file_id = file_ids[&path].clone();
+        if !seen.insert(file_id.clone()) {
+          continue;
+        }
+        let file_path = root.join(&path);
+        let file_size = fs::metadata(&file_path)
+          .map_err(|err| ImportError::Internal(err.into()))?
+          .len() as i64;
+        imported.files.push(ImportedFile {
+          meta: BulkInsertMeta {
+            object_id: node.view_id.clone(),
+            file_id,
+            file_type: mime_guess::from_path(&file_path)
+              .first_or_octet_stream()
+              .to_string(),
+            file_size,
+          },
+          file_path,
+        });
+      }
+    }
+
+    trace!(
+      "[Import]: {} import page:{} as view:{}",
+      self.workspace_id,
+      name,
+      node.view_id
+    );
+    let document_data = MDImporter::new(None)
+      .import(&node.view_id, markdown)
+      .map_err(|err| ImportError::Internal(anyhow!("Failed to import {}: {:?}", name, err)))?;
+    let document = Document::create(&node.view_id, document_data)
+      .map_err(|err| ImportError::Internal(anyhow!("Failed to create {}: {:?}", name, err)))?;
+    let encoded_collab = document
+      .encode_collab()
+      .map_err(|err| ImportError::Internal(anyhow!("Failed to encode {}: {:?}", name, err)))?;
+    imported
+      .documents
+      .push((node.view_id.clone(), encoded_collab));
+
+    let view = NestedChildViewBuilder::new(self.uid, parent_view_id.to_string())
+      .with_view_id(node.view_id.clone())
+      .with_name(&name)
+      .with_layout(ViewLayout::Document)
+      .build()
+      .view;
+    imported.views.push(view);
+
+    for child in node.children.values() {
+      self.import_node(root, child, &node.view_id, targets, file_ids, imported)?;
+    }
+    Ok(())
+  }
+
+  /// Rewrites the relative links, images, wiki links and embeds of a markdown page. Links to
+  /// pages point to the imported views, links to files point to the uploaded blobs. `upload`
+  /// registers the file at the given relative path and returns its blob url.
+  fn rewrite_links(
+    &self,
+    markdown: &str,
+    base_dir: &Path,
+    targets: &LinkTargets,
+    upload: &mut dyn FnMut(&Path) -> Option<String>,
+  ) -> String {
+    let mut output = String::with_capacity(markdown.len());
+    let mut in_code_block = false;
+    for line in markdown.split_inclusive('\n') {
+      if line.trim_start().starts_with("```") {
+        in_code_block = !in_code_block;
+      }
+      if in_code_block || line.trim_start().starts_with("```") {
+        output.push_str(line);
+        continue;
+      }
+      output.push_str(&self.rewrite_line(line, base_dir, targets, upload));
+    }
+    output
+  }
+
+  fn rewrite_line(
+    &self,
+    line: &str,
+    base_dir: &Path,
+    targets: &LinkTargets,
+    upload: &mut dyn FnMut(&Path) -> Option<String>,
+  ) -> String {
+    let mut output = String::with_capacity(line.len());
+    let mut rest = line;
+    while let Some(start) = rest.find('[') {
+      let is_embed = rest[..start].ends_with('!');
+      let prefix_end = if is_embed { start - 1 } else { start };
+      output.push_str(&rest[..prefix_end]);
+      let candidate = &rest[start..];
+
+      // [[wiki link]] or ![[embed]]
+      if let Some(inner) = candidate
+        .strip_prefix("[[")
+        .and_then(|s| s.find("]]").map(|end| &s[..end]))
+      {
+        let consumed = start + inner.len() + 4;
+        output.push_str(&self.rewrite_wiki_link(inner, is_embed, targets, upload));
+        rest = &rest[consumed..];
+        continue;
+      }
+
+      // [text](target) or ![alt](target)
+      if let Some((text, target, len)) = parse_inline_link(candidate) {
+        let url = self.resolve_link(target, base_dir, targets, upload);
+        let url = url.unwrap_or_else(|| target.to_string());
+        if is_embed {
+          output.push('!');
+        }
+        output.push_str(&format!("[{}]({})", text, url));
+        rest = &rest[start + len..];
+        continue;
+      }
+
+      output.push_str(&rest[prefix_end..=start]);
+      rest = &rest[start + 1..];
+    }
+    output.push_str(rest);
+    output
+  }
+
+  fn rewrite_wiki_link(
+    &self,
+    inner: &str,
+    is_embed: bool,
+    targets: &LinkTargets,
+    upload: &mut dyn FnMut(&Path) -> Option<String>,
+  ) -> String {
+    let (target, alias) = match inner.split_once('|') {
+      Some((target, alias)) => (target.trim(), Some(alias.trim())),
+      None => (inner.trim(), None),
+    };
+    let target = target.split('#').next().unwrap_or_default().trim();
+    let text = alias
+      .filter(|alias| !alias.is_empty() && !alias.chars().all(|c| c.is_ascii_digit() || c == 'x'))
+      .unwrap_or(target);
+
+    let file_name = Path::new(target)
+      .file_name()
+      .and_then(|name| name.to_str())
+      .unwrap_or(target)
+      .to_lowercase();
+    if let Some(path) = targets.file_names.get(&file_name) {
+      if let Some(url) = upload(path) {
+        return if is_embed {
+          format!("![{}]({})", text, url)
+        } else {
+          format!("[{}]({})", text, url)
+        };
+      }
+    }
+
+    let page_name = Path::new(&file_name)
+      .file_stem()
+      .and_then(|stem| stem.to_str())
+      .unwrap_or(&file_name)
+      .to_string();
+    let page_name = if self.format.is_page(Path::new(&file_name)) {
+      page_name
+    } else {
+      file_name
+    };
+    match targets.page_names.get(&page_name) {
+      Some(view_id) => format!("[{}]({})", text, self.view_url(view_id)),
+      None => text.to_string(),
+    }
+  }
+
+  fn resolve_link(
+    &self,
+    target: &str,
+    base_dir: &Path,
+    targets: &LinkTargets,
+    upload: &mut dyn FnMut(&Path) -> Option<String>,
+  ) -> Option<String> {
+    let target = target.trim();
+    if target.is_empty()
+      || target.starts_with('#')
+      || target.contains("://")
+      || target.starts_with("mailto:")
+      || target.starts_with("data:")
+    {
+      return None;
+    }
+    let target = target.split(['#', '?']).next().unwrap_or_default();
+    let path = normalize_path(&base_dir.join(percent_decode(target)))?;
+    if let Some(view_id) = targets.pages.get(&path) {
+      return Some(self.view_url(view_id));
+    }
+    if targets.files.contains(&path) {
+      return upload(&path);
+    }
+    None
+  }
+
+  fn view_url(&self, view_id: &str) -> String {
+    format!("{}/app/{}/{}", self.host, self.workspace_id, view_id)
+  }
+
+  fn blob_url(&self, object_id: &str, file_id: &str) -> String {
+    format!(
+      "{}/api/file_storage/{}/v1/blob/{}/{}",
+      self.host, self.workspace_id, object_id, file_id
+    )
+  }
+}
+
+/// Zips usually wrap the exported pages in a single directory, skip it so that it doesn't become
+/// an empty page.
+fn content_root(root: &Path) -> PathBuf {
+  let mut root = root.to_path_buf();
+  loop {
+    let entries = match fs::read_dir(&root) {
+      Ok(entries) => entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| !is_ignored(path))
+        .collect::<Vec<_>>(),
+      Err(_) => return root,
+    };
+    match entries.as_slice() {
+      [single] if single.is_dir() => root = single.clone(),
+      _ => return root,
+    }
+  }
+}
+
+fn is_ignored(path: &Path) -> bool {
+  path
+    .file_name()
+    .and_then(|name| name.to_str())
+    .map(|name| name.starts_with('.') || name == "__MACOSX")
+    .unwrap_or(true)
+}
+
+/// Walks the directory and adds its pages and sub directories to `node`. Returns whether the
+/// directory contains any page.
+fn collect_entries(
+  root: &Path,
+  relative_dir: &Path,
+  format: FolderImportFormat,
+  node: &mut PageNode,
+  targets: &mut LinkTargets,
+) -> Result<bool, ImportError> {
+  let mut entries = fs::read_dir(root.join(relative_dir))
+    .map_err(|err| ImportError::Internal(err.into()))?
+    .filter_map(|entry| entry.ok())
+    .map(|entry| entry.path())
+    .filter(|path| !is_ignored(path))
+    .collect::<Vec<_>>();
+  entries.sort();
+
+  let mut has_page = false;
+  for path in entries {
+    let file_name = path
+      .file_name()
+      .and_then(|name| name.to_str())
+      .unwrap_or_default()
+      .to_string();
+    let relative_path = relative_dir.join(&file_name);
+    if path.is_dir() {
+      let mut child = node
+        .children
+        .remove(&file_name)
+        .unwrap_or_else(|| PageNode::new(file_name.clone()));
+      if collect_entries(root, &relative_path, format, &mut child, targets)?
+        || child.page_path.is_some()
+      {
+        has_page = true;
+        node.children.insert(file_name, child);
+      }
+    } else if format.is_page(&path) {
+      let stem = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or_default()
+        .to_string();
+      let child = node
+        .children
+        .entry(stem.clone())
+        .or_insert_with(|| PageNode::new(stem.clone()));
+      child.page_path = Some(relative_path.clone());
+      targets.pages.insert(relative_path, child.view_id.clone());
+      targets
+        .page_names
+        .entry(stem.to_lowercase())
+        .or_insert_with(|| child.view_id.clone());
+      has_page = true;
+    } else {
+      targets
+        .file_names
+        .entry(file_name.to_lowercase())
+        .or_insert_with(|| relative_path.clone());
+      targets.files.insert(relative_path);
+    }
+  }
+  Ok(has_page)
+}
+
+fn file_id_of(root: &Path, path: &Path, file_ids: &mut HashMap<PathBuf, String>) -> Option<String> {
+  if let Some(file_id) = file_ids.get(path) {
+    return Some(file_id.clone());
+  }
+  match fs::read(root.join(path)) {
+    Ok(bytes) => {
+      let file_id = FileId::from_bytes(&bytes, "".to_string());
+      file_ids.insert(path.to_path_buf(), file_id.clone());
+      Some(file_id)
+    },
+    Err(err) => {
+      warn!("[Import]: failed to read file {:?}: {}", path, err);
+      None
+    },
+  }
+}
+
+/// Parses `[text](target)` at the start of `s`. Returns the text, the target and the length of
+/// the link.
+fn parse_inline_link(s: &str) -> Option<(&str, &str, usize)> {
+  let mut depth = 0;
+  let mut text_end = None;
+  for (i, c) in s.char_indices() {
+    match c {
+      '[' => depth += 1,
+      ']' => {
+        depth -= 1;
+        if depth == 0 {
+          text_end = Some(i);
+          break;
+        }
+      },
+      _ => {},
+    }
+  }
+  let text_end = text_end?;
+  let after = s[text_end + 1..].strip_prefix('(')?;
+  let (target, close) = match after.strip_prefix('<') {
+    Some(stripped) => {
+      let end = stripped.find('>')?;
+      (&stripped[..end], after[end + 2..].find(')')? + end + 2)
+    },
+    None => {
+      let close = after.find(')')?;
+      let target = after[..close].split_whitespace().next().unwrap_or_default();
+      (target, close)
+    },
+  };
+  Some((&s[1..text_end], target, text_end + 2 + close + 1))
+}
+
+fn normalize_path(path: &Path) -> Option<PathBuf> {
+  let mut normalized = PathBuf::new();
+  for component in path.components() {
+    match component {
+      Component::Normal(part) => normalized.push(part),
+      Component::ParentDir => {
+        if !normalized.pop() {
+          return None;
+        }
+      },
+      Component::CurDir => {},
+      Component::RootDir | Component::Prefix(_) => return None,
+    }
+  }
+  Some(normalized)
+}
+
+fn percent_decode(s: &str) -> String {
+  let bytes = s.as_bytes();
+  let mut decoded = Vec::with_capacity(bytes.len());
+  let mut i = 0;
+  while i < bytes.len() {
+    if bytes[i] == b'%' {
+      if let (Some(high), Some(low)) = (
+        bytes.get(i + 1).and_then(|b| (*b as char).to_digit(16)),
+        bytes.get(i + 2).and_then(|b| (*b as char).to_digit(16)),
+      ) {
+        decoded.push((high * 16 + low) as u8);
+        i += 3;
+        continue;
+      }
+    }
+    decoded.push(bytes[i]);
+    i += 1;
+  }
+  String::from_utf8_lossy(&decoded).to_string()
+}
+
+/// Splits the YAML front matter from the markdown body, returning the `title` of the front matter
+/// if any.
+fn split_front_matter(content: &str) -> (Option<String>, String) {
+  let content = content.trim_start_matches('\u{feff}');
+  let Some(rest) = content
+    .strip_prefix("---\n")
+    .or_else(|| content.strip_prefix("---\r\n"))
+  else {
+    return (None, content.to_string());
+  };
+
+  let mut offset = 0;
+  for line in rest.split_inclusive('\n') {
+    if line.trim_end() == "---" {
+      let front_matter = &rest[..offset];
+      let body = &rest[offset + line.len()..];
+      let title = front_matter.lines().find_map(|line| {
+        line
+          .strip_prefix("title:")
+          .map(|title| title.trim().trim_matches(['"', '\'']).to_string())
+      });
+      return (title, body.to_string());
+    }
+    offset += line.len();
+  }
+  (None, content.to_string())
+}
+
+/// Elements that only hold the navigation of the exported page, e.g. the breadcrumbs and title
+/// of a Confluence page, which are already part of the folder hierarchy.
+const SKIPPED_ELEMENT_IDS: [&str; 3] = ["breadcrumb-section", "title-heading", "footer"];
+
+/// Converts an HTML page to markdown, returning the title of the page if any.
+fn html_to_markdown(html: &str) -> (Option<String>, String) {
+  let document = Html::parse_document(html);
+  let title = Selector::parse("title")
+    .ok()
+    .and_then(|selector| document.select(&selector).next())
+    .map(|title| title.text().collect::<String>())
+    .or_else(|| {
+      Selector::parse("h1")
+        .ok()
+        .and_then(|selector| document.select(&selector).next())
+        .map(|h1| h1.text().collect::<String>())
+    })
+    .map(|title| {
+      // Confluence prefixes the title with the space name, e.g. "Space : Page"
+      let title = title.rsplit(" : ").next().unwrap_or_default();
+      collapse_whitespace(title).trim().to_string()
+    });
+
+  let mut writer = MarkdownWriter::default();
+  writer.write_children(document.root_element());
+  (title, writer.finish())
+}
+
+#[derive(Default)]
+struct MarkdownWriter {
+  output: String,
+  /// The nested lists, with the next number of the ordered ones.
+  lists: Vec<Option<usize>>,
+  in_pre: bool,
+}
+
+impl MarkdownWriter {
+  fn finish(self) -> String {
+    let mut markdown = String::with_capacity(self.output.len());
+    let mut blank_lines = 0;
+    for line in self.output.lines() {
+      if line.trim().is_empty() {
+        blank_lines += 1;
+        if blank_lines > 1 {
+          continue;
+        }
+      } else {
+        blank_lines = 0;
+      }
+      markdown.push_str(line.trim_end());
+      markdown.push('\n');
+    }
+    markdown.trim().to_string()
+  }
+
+  /// Ends the current block, lists only separate their blocks by a line break.
+  fn block(&mut self) {
+    while self.output.ends_with(' ') {
+      self.output.pop();
+    }
+    if self.output.is_empty() {
+      return;
+    }
+    let line_breaks = if self.lists.is_empty() { 2 } else { 1 };
+    let existing = self.output.chars().rev().take_while(|c| *c == '\n').count();
+    for _ in existing..line_breaks {
+      self.output.push('\n');
+    }
+  }
+
+  fn write_text(&mut self, text: &str) {
+    if self.in_pre {
+      self.output.push_str(text);
+      return;
+    }
+    let text = collapse_whitespace(text);
+    if self.output.is_empty() || self.output.ends_with('\n') || self.output.ends_with(' ') {
+      self.output.push_str(text.trim_start());
+    } else {
+      self.output.push_str(&text);
+    }
+  }
+
+  fn write_children(&mut self, element: ElementRef) {
+    for child in element.children() {
+      if let Some(child) = ElementRef::wrap(child) {
+        self.write_element(child);
+      } else if let Node::Text(text) = child.value() {
+        self.write_text(text);
+      }
+    }
+  }
+
+  fn inline(&mut self, element: ElementRef) -> String {
+    let mut writer = MarkdownWriter {
+      in_pre: self.in_pre,
+      ..Default::default()
+    };
+    writer.write_children(element);
+    writer.output.trim().replace('\n', " ")
+  }
+
+  fn wrap(&mut self, element: ElementRef, marker: &str) {
+    let inner = self.inline(element);
+    if !inner.is_empty() {
+      self.write_text(" ");
+      self
+        .output
+        .push_str(&format!("{}{}{}", marker, inner, marker));
+    }
+  }
+
+  fn write_element(&mut self, element: ElementRef) {
+    if element
+      .value()
+      .id()
+      .map(|id| SKIPPED_ELEMENT_IDS.contains(&id))
+      .unwrap_or(false)
+    {
+      return;
+    }
+
+    let name = element.value().name();
+    match name {
+      "head" | "title" | "script" | "style" | "noscript" | "nav" | "template" => {},
+      "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+        let level = name[1..].parse::<usize>().unwrap_or(1);
+        let text = self.inline(element);
+        self.block();
+        self
+          .output
+          .push_str(&format!("{} {}", "#".repeat(level), text));
+        self.block();
+      },
+      "br" => self.output.push('\n'),
+      "hr" => {
+        self.block();
+        self.output.push_str("---");
+        self.block();
+      },
+      "strong" | "b" => self.wrap(element, "**"),
+      "em" | "i" => self.wrap(element, "*"),
+      "s" | "del" | "strike" => self.wrap(element, "~~"),
+      "code" if !self.in_pre => self.wrap(element, "`"),
+      "pre" => {
+        self.block();
+        self.output.push_str("```\n");
+        self.in_pre = true;
+        self
+          .output
+          .push_str(element.text().collect::<String>().trim_end());
+        self.in_pre = false;
+        self.output.push_str("\n```");
+        self.block();
+      },
+      "a" => {
+        let text = self.inline(element);
+        match element.value().attr("href") {
+          Some(href) if !text.is_empty() => {
+            self.write_text(" ");
+            self.output.push_str(&format!("[{}]({})", text, href));
+          },
+          _ => self.write_text(&text),
+        }
+      },
+      "img" => {
+        if let Some(src) = element.value().attr("src") {
+          let alt = element.value().attr("alt").unwrap_or_default();
+          self.write_text(" ");
+          self.output.push_str(&format!("![{}]({})", alt, src));
+        }
+      },
+      "ul" | "ol" => {
+        self.block();
+        self.lists.push((name == "ol").then_some(1));
+        self.write_children(element);
+        self.lists.pop();
+        self.block();
+      },
+      "li" => {
+        if !self.output.is_empty() && !self.output.ends_with('\n') {
+          self.output.push('\n');
+        }
+        let indent = "  ".repeat(self.lists.len().saturating_sub(1));
+        let marker = match self.lists.last_mut() {
+          Some(Some(number)) => {
+            *number += 1;
+            format!("{}. ", *number - 1)
+          },
+          _ => "- ".to_string(),
+        };
+        self.output.push_str(&indent);
+        self.output.push_str(&marker);
+        self.write_children(element);
+      },
+      "blockquote" => {
+        let mut writer = MarkdownWriter::default();
+        writer.write_children(element);
+        self.block();
+        for line in writer.finish().lines() {
+          self.output.push_str(&format!("> {}\n", line));
+        }
+        self.block();
+      },
+      "table" => self.write_table(element),
+      "p" | "div" | "section" | "article" | "main" | "header" | "footer" | "figure" => {
+        self.block();
+        self.write_children(element);
+        self.block();
+      },
+      _ => self.write_children(element),
+    }
+  }
+
+  fn write_table(&mut self, table: ElementRef) {
+    let (Ok(row_selector), Ok(cell_selector)) = (Selector::parse("tr"), Selector::parse("th, td"))
+    else {
+      return;
+    };
+    let rows = table
+      .select(&row_selector)
+      .map(|row| {
+        row
+          .select(&cell_selector)
+          .map(|cell| self.inline(cell).replace('|', "\\|"))
+          .collect::<Vec<_>>()
+      })
+      .filter(|cells| !cells.is_empty())
+      .collect::<Vec<_>>();
+    let columns = rows.iter().map(|cells| cells.len()).max().unwrap_or(0);
+    if columns == 0 {
+      return;
+    }
+
+    self.block();
+    for (i, cells) in rows.iter().enumerate() {
+      let mut cells = cells.clone();
+      cells.resize(columns, String::new());
+      self
+        .output
+        .push_str(&format!("| {} |\n", cells.join(" | ")));
+      if i == 0 {
+        self
+          .output
+          .push_str(&format!("|{}\n", " --- |".repeat(columns)));
+      }
+    }
+    self.block();
+  }
+}
+
+fn collapse_whitespace(text: &str) -> String {
+  let mut collapsed = String::with_capacity(text.len());
+  let mut last_is_whitespace = false;
+  for c in text.chars() {
+    if c.is_whitespace() {
+      if !last_is_whitespace {
+        collapsed.push(' ');
+      }
+      last_is_whitespace = true;
+    } else {
+      collapsed.push(c);
+      last_is_whitespace = false;
+    }
+  }
+  collapsed
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn importer(format: FolderImportFormat) -> FolderImporter {
+    FolderImporter {
+      uid: 1,
+      workspace_id: "w".to_string(),
+      workspace_name: "Vault".to_string(),
+      host: "https://appflowy.io".to_string(),
+      format,
+      root: PathBuf::new(),
+    }
+  }
+
+  fn targets() -> LinkTargets {
+    let mut targets = LinkTargets::default();
+    targets
+      .pages
+      .insert(PathBuf::from("notes/Other Page.md"), "view_2".to_string());
+    targets
+      .page_names
+      .insert("other page".to_string(), "view_2".to_string());
+    targets.files.insert(PathBuf::from("assets/cat.png"));
+    targets
+      .file_names
+      .insert("cat.png".to_string(), PathBuf::from("assets/cat.png"));
+    targets
+  }
+
+  fn rewrite(markdown: &str) -> String {
+    importer(FolderImportFormat::Markdown).rewrite_links(
+      markdown,
+      Path::new("notes"),
+      &targets(),
+      &mut |path| Some(format!("blob:{}", path.display())),
+    )
+  }
+
+  #[test]
+  fn front_matter_test() {
+    let (title, body) = split_front_matter("---\ntitle: \"My page\"\ntags: [a]\n---\n# Hello\n");
+    assert_eq!(title.as_deref(), Some("My page"));
+    assert_eq!(body, "# Hello\n");
+
+    let (title, body) = split_front_matter("# No front matter\n---\n");
+    assert!(title.is_none());
+    assert_eq!(body, "# No front matter\n---\n");
+  }
+
+  #[test]
+  fn rewrite_relative_links_test() {
+    assert_eq!(
+      rewrite("See [other](Other%20Page.md#intro) and ![cat](../assets/cat.png \"Cat\")"),
+      "See [other](https://appflowy.io/app/w/view_2) and ![cat](blob:assets/cat.png)"
+    );
+    assert_eq!(
+      rewrite("[site](https://appflowy.io) [missing](missing.md) [x] y"),
+      "[site](https://appflowy.io) [missing](missing.md) [x] y"
+    );
+    assert_eq!(
+      rewrite("```\n[other](Other%20Page.md)\n```\n"),
+      "```\n[other](Other%20Page.md)\n```\n"
+    );
+  }
+
+  #[test]
+  fn percent_decode_test() {
+    assert_eq!(percent_decode("Other%20Page%2emd"), "Other Page.md");
+    assert_eq!(percent_decode("caf%C3%A9"), "café");
+    // Invalid or truncated escapes are kept as is, including before multibyte characters
+    assert_eq!(percent_decode("%é%+1%4"), "%é%+1%4");
+    assert_eq!(percent_decode("a%"), "a%");
+  }
+
+  #[test]
+  fn rewrite_wiki_links_test() {
+    assert_eq!(
+      rewrite("[[Other Page#Intro|see here]] ![[cat.png|300]] [[Unknown]]"),
+      "[see here](https://appflowy.io/app/w/view_2) ![cat.png](blob:assets/cat.png) Unknown"
+    );
+  }
+
+  #[test]
+  fn html_to_markdown_test() {
+    let html = r#"<html><head><title>Space : Release notes</title><style>p {}</style></head>
+      <body><div id="breadcrumb-section">Space / Home</div>
+      <h2>Summary</h2><p>Some <strong>bold</strong> text with a <a href="Other_1.html">link</a>.</p>
+      <ul><li>one</li><li>two<ol><li>nested</li></ol></li></ul>
+      <img src="attachments/1/2.png" alt="diagram">
+      <table><tr><th>Name</th><th>Value</th></tr><tr><td>a</td><td>1</td></tr></table>
+      </body></html>"#;
+    let (title, markdown) = html_to_markdown(html);
+    assert_eq!(title.as_deref(), Some("Release notes"));
+    assert_eq!(
+      markdown,
+      "## Summary\n\nSome **bold** text with a [link](Other_1.html).\n\n- one\n- two\n  1. nested\n\n![diagram](attachments/1/2.png)\n\n| Name | Value |\n| --- | --- |\n| a | 1 |"
+    );
+  }
+
+  #[test]
+  fn collect_entries_test() {
+    let root = std::env::temp_dir().join(Uuid::new_v4().to_string());
+    fs::create_dir_all(root.join("Projects/attachments")).unwrap();
+    fs::create_dir_all(root.join(".obsidian")).unwrap();
+    fs::write(root.join("Projects.md"), "# Projects").unwrap();
+    fs::write(root.join("Projects/Roadmap.md"), "# Roadmap").unwrap();
+    fs::write(root.join("Projects/attachments/a.png"), [1, 2, 3]).unwrap();
+
+    let mut tree = PageNode::new("Vault".to_string());
+    let mut targets = LinkTargets::default();
+    let has_page = collect_entries(
+      &root,
+      Path::new(""),
+      FolderImportFormat::Markdown,
+      &mut tree,
+      &mut targets,
+    )
+    .unwrap();
+    fs::remove_dir_all(&root).unwrap();
+
+    assert!(has_page);
+    assert_eq!(tree.children.len(), 1);
+    let projects = &tree.children["Projects"];
+    assert_eq!(projects.page_path, Some(PathBuf::from("Projects.md")));
+    assert_eq!(projects.children.len(), 1);
+    assert!(projects.children.contains_key("Roadmap"));
+    assert!(targets
+      .files
+      .contains(&PathBuf::from("Projects/attachments/a.png")));
+    assert_eq!(
+      targets.page_names["roadmap"],
+      projects.children["Roadmap"].view_id
+    );
+  }
+}
@@ -0,0 +1,58 @@
+use crate::error::ImportError;
+use anyhow::anyhow;
+use collab::core::origin::CollabOrigin;
+use collab::preclude::Collab;
+use collab_stream::collab_update_sink::CollabUpdateSink;
+use collab_stream::model::{CollabStreamUpdate, UpdateFlags};
+use redis::aio::ConnectionManager;
+use redis::streams::StreamReadReply;
+use redis::AsyncCommands;
+
+/// Applies the updates of the collab that were sent by the clients but not persisted yet, so that
+/// an update computed from the collab doesn't overwrite them.
+pub async fn apply_pending_updates(
+  redis_client: &mut ConnectionManager,
+  workspace_id: &str,
+  object_id: &str,
+  collab: &mut Collab,
+) -> Result<(), ImportError> {
+  let stream_key = CollabStreamUpdate::stream_key(workspace_id, object_id);
+  let mut reply: StreamReadReply = redis_client
+    .xread(&[&stream_key], &["0"])
+    .await
+    .map_err(|err| ImportError::Internal(err.into()))?;
+  let Some(key) = reply.keys.pop() else {
+    return Ok(());
+  };
+
+  let mut txn = collab.transact_mut();
+  for stream_id in key.ids {
+    let update = CollabStreamUpdate::try_from(stream_id.map)
+      .and_then(|update| update.into_update())
+      .map_err(|err| ImportError::Internal(anyhow!("Invalid pending update: {}", err)))?;
+    txn
+      .apply_update(update)
+      .map_err(|err| ImportError::Internal(anyhow!("Failed to apply pending update: {}", err)))?;
+  }
+  Ok(())
+}
+
+/// Sends an update of a collab that may be opened by the clients. The collab server broadcasts it
+/// to the connected clients and persists it.
+pub async fn send_collab_update(
+  redis_client: &ConnectionManager,
+  workspace_id: &str,
+  object_id: &str,
+  update: Vec<u8>,
+) -> Result<(), ImportError> {
+  let stream_key = CollabStreamUpdate::stream_key(workspace_id, object_id);
+  CollabUpdateSink::new(redis_client.clone(), stream_key)
+    .send(&CollabStreamUpdate::new(
+      update,
+      CollabOrigin::Server,
+      UpdateFlags::default(),
+    ))
+    .await
+    .map_err(|err| ImportError::Internal(anyhow!("Failed to send collab update: {}", err)))?;
+  Ok(())
+}
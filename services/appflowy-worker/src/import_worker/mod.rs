@@ -1,3 +1,6 @@
+pub mod database_importer;
 pub mod email_notifier;
+pub mod folder_importer;
+pub mod live_collab;
 pub mod report;
 pub mod worker;
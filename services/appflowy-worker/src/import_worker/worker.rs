@@ -10,22 +10,32 @@ use crate::s3_client::S3Client;
 use bytes::Bytes;
 use collab::core::origin::CollabOrigin;
 use collab::entity::{EncodedCollab, EncoderVersion};
+use collab::preclude::{ReadTxn, StateVector};
+use collab_database::database::gen_database_id;
 use collab_database::workspace_database::WorkspaceDatabase;
 use collab_entity::CollabType;
+use collab_folder::hierarchy_builder::NestedChildViewBuilder;
 use collab_folder::{Folder, View, ViewLayout};
 use collab_importer::imported_collab::ImportType;
 use collab_importer::notion::page::CollabResource;
 use collab_importer::notion::NotionImporter;
 use collab_importer::util::FileId;
-use database::collab::{insert_into_af_collab_bulk_for_user, select_blob_from_af_collab};
+use database::collab::{
+  insert_into_af_collab_bulk_for_user, select_blob_from_af_collab, soft_delete_collabs_of_workspace,
+};
+use database::encryption::CollabEncryption;
 use database::resource_usage::{insert_blob_metadata_bulk, BulkInsertMeta};
 use database::workspace::{
-  delete_from_workspace, select_import_task, select_workspace_database_storage_id,
+  complete_import_task, delete_from_workspace, select_import_task,
+  select_workspace_database_storage_id, update_import_task_metadata, update_import_task_progress,
   update_import_task_status, update_updated_at_of_workspace_with_uid, update_workspace_status,
   ImportTaskState,
 };
-use database_entity::dto::CollabParams;
+use database_entity::dto::{CollabParams, ImportPhase, SpreadsheetFormat};
 
+use crate::import_worker::database_importer::{build_grid_database, read_spreadsheet};
+use crate::import_worker::folder_importer::{FolderImportFormat, FolderImporter};
+use crate::import_worker::live_collab::{apply_pending_updates, send_collab_update};
 use crate::metric::ImportMetrics;
 use async_zip::base::read::stream::{Ready, ZipFileReader};
 use collab_importer::zip_tool::async_zip::async_unzip;
@@ -43,7 +53,7 @@ use redis::{AsyncCommands, RedisResult, Value};
 
 use database::pg_row::AFImportTask;
 use serde::{Deserialize, Serialize};
-use serde_json::from_str;
+use serde_json::{from_str, json};
 use sqlx::types::chrono::{DateTime, TimeZone, Utc};
 use sqlx::PgPool;
 use std::collections::{HashMap, HashSet};
@@ -68,6 +78,8 @@ use uuid::Uuid;
 const GROUP_NAME: &str = "import_task_group";
 const CONSUMER_NAME: &str = "appflowy_worker";
 const MAXIMUM_CONTENT_LENGTH: &str = "3221225472";
+/// Number of pages created between two reports of the progress of an import
+const PROGRESS_REPORT_INTERVAL: usize = 10;
 
 #[allow(clippy::too_many_arguments)]
 pub async fn run_import_worker(
@@ -75,6 +87,7 @@ pub async fn run_import_worker(
   mut redis_client: ConnectionManager,
   metrics: Option<Arc<ImportMetrics>>,
   s3_client: Arc<dyn S3Client>,
+  collab_encryption: Option<CollabEncryption>,
   notifier: Arc<dyn ImportNotifier>,
   stream_name: &str,
   tick_interval_secs: u64,
@@ -90,6 +103,7 @@ pub async fn run_import_worker(
     &storage_dir,
     &mut redis_client,
     &s3_client,
+    &collab_encryption,
     &pg_pool,
     stream_name,
     GROUP_NAME,
@@ -104,6 +118,7 @@ pub async fn run_import_worker(
     &storage_dir,
     &mut redis_client,
     &s3_client,
+    &collab_encryption,
     pg_pool,
     stream_name,
     GROUP_NAME,
@@ -123,6 +138,7 @@ async fn process_un_acked_tasks(
   storage_dir: &Path,
   redis_client: &mut ConnectionManager,
   s3_client: &Arc<dyn S3Client>,
+  collab_encryption: &Option<CollabEncryption>,
   pg_pool: &PgPool,
   stream_name: &str,
   group_name: &str,
@@ -140,6 +156,7 @@ async fn process_un_acked_tasks(
           storage_dir: storage_dir.to_path_buf(),
           redis_client: redis_client.clone(),
           s3_client: s3_client.clone(),
+          collab_encryption: collab_encryption.clone(),
           pg_pool: pg_pool.clone(),
           notifier: notifier.clone(),
           metrics: metrics.clone(),
@@ -165,6 +182,7 @@ async fn process_upcoming_tasks(
   storage_dir: &Path,
   redis_client: &mut ConnectionManager,
   s3_client: &Arc<dyn S3Client>,
+  collab_encryption: &Option<CollabEncryption>,
   pg_pool: PgPool,
   stream_name: &str,
   group_name: &str,
@@ -219,6 +237,7 @@ async fn process_upcoming_tasks(
               storage_dir: storage_dir.to_path_buf(),
               redis_client: redis_client.clone(),
               s3_client: s3_client.clone(),
+              collab_encryption: collab_encryption.clone(),
               pg_pool: pg_pool.clone(),
               notifier: notifier.clone(),
               metrics: metrics.clone(),
@@ -259,6 +278,7 @@ struct TaskContext {
   storage_dir: PathBuf,
   redis_client: ConnectionManager,
   s3_client: Arc<dyn S3Client>,
+  collab_encryption: Option<CollabEncryption>,
   pg_pool: PgPool,
   notifier: Arc<dyn ImportNotifier>,
   metrics: Option<Arc<ImportMetrics>>,
@@ -273,7 +293,29 @@ async fn consume_task(
   group_name: &str,
   entry_id: String,
 ) -> Result<(), ImportError> {
-  if let ImportTask::Notion(task) = &mut import_task {
+  if let Some(task) = import_task.uploaded_task_mut() {
+    // The task may be cancelled before the worker starts processing it, e.g. while waiting for
+    // the file to be uploaded
+    if let Ok(import_record) = select_import_task(&context.pg_pool, &task.task_id).await {
+      if matches!(
+        ImportTaskState::from(import_record.status),
+        ImportTaskState::Cancel
+      ) {
+        handle_failed_task(
+          &mut context,
+          &import_record,
+          task,
+          stream_name,
+          group_name,
+          &entry_id,
+          ImportError::Cancelled,
+          ImportTaskState::Cancel,
+        )
+        .await?;
+        return Ok(());
+      }
+    }
+
     // If no created_at timestamp, proceed directly to processing
     if task.created_at.is_none() {
       return process_and_ack_task(context, import_task, stream_name, group_name, &entry_id).await;
@@ -356,7 +398,7 @@ async fn consume_task(
       Ok(())
     }
   } else {
-    // If the task is not a notion task, proceed directly to processing
+    // If the task doesn't import an uploaded file, proceed directly to processing
     process_and_ack_task(context, import_task, stream_name, group_name, &entry_id).await
   }
 }
@@ -383,8 +425,11 @@ async fn handle_failed_task(
       error!("Failed to update import task status: {:?}", e);
       ImportError::Internal(e.into())
     })?;
-  remove_workspace(&import_record.workspace_id, &context.pg_pool).await;
-  info!("[Import]: deleted workspace {}", task.workspace_id);
+  // The imports into an existing workspace must keep it
+  if task.parent_view_id.is_none() {
+    remove_workspace(&import_record.workspace_id, &context.pg_pool).await;
+    info!("[Import]: deleted workspace {}", task.workspace_id);
+  }
 
   if let Err(err) = context.s3_client.delete_blob(task.s3_key.as_str()).await {
     error!(
@@ -534,9 +579,40 @@ async fn delete_task(
   Ok(())
 }
 
-async fn process_task(
+async fn process_task(context: TaskContext, import_task: ImportTask) -> Result<(), ImportError> {
+  info!("[Import]: Processing task: {}", import_task);
+
+  match import_task {
+    ImportTask::Notion(task) => process_zip_task(context, task, None).await,
+    ImportTask::Markdown(task) => {
+      process_zip_task(context, task, Some(FolderImportFormat::Markdown)).await
+    },
+    ImportTask::Html(task) => process_zip_task(context, task, Some(FolderImportFormat::Html)).await,
+    ImportTask::Csv(task) => process_database_task(context, task, SpreadsheetFormat::Csv).await,
+    ImportTask::Xlsx(task) => process_database_task(context, task, SpreadsheetFormat::Xlsx).await,
+    ImportTask::Custom(value) => {
+      trace!("Custom task: {:?}", value);
+      let result = ImportResult {
+        user_name: "".to_string(),
+        user_email: "".to_string(),
+        is_success: true,
+        value: Default::default(),
+      };
+      context
+        .notifier
+        .notify_progress(ImportProgress::Finished(result))
+        .await;
+      Ok(())
+    },
+  }
+}
+
+/// Processes the imports that are uploaded as a zip file. `folder_format` is `None` for Notion
+/// exports, which are imported by the [NotionImporter].
+async fn process_zip_task(
   mut context: TaskContext,
-  import_task: ImportTask,
+  task: Box<NotionImportTask>,
+  folder_format: Option<FolderImportFormat>,
 ) -> Result<(), ImportError> {
   let retry_interval: u64 = get_env_var("APPFLOWY_WORKER_IMPORT_TASK_RETRY_INTERVAL", "10")
     .parse()
@@ -546,94 +622,333 @@ async fn process_task(
     .parse()
     .unwrap_or(false);
 
-  info!("[Import]: Processing task: {}", import_task);
+  let progress = ProgressReporter {
+    pg_pool: context.pg_pool.clone(),
+    task_id: task.task_id,
+  };
 
-  match import_task {
-    ImportTask::Notion(task) => {
-      // 1. download zip file
-      let unzip_result = download_and_unzip_file_retry(
-        &context.storage_dir,
+  // 1. download zip file
+  let unzip_result = download_and_unzip_file_retry(
+    &context.storage_dir,
+    &task,
+    &context.s3_client,
+    3,
+    Duration::from_secs(retry_interval),
+    streaming,
+    &context.metrics,
+    &progress,
+  )
+  .await;
+
+  trace!(
+    "[Import]: {} download and unzip file result: {:?}",
+    task.workspace_id,
+    unzip_result
+  );
+  match unzip_result {
+    Ok(unzip_dir_path) => {
+      // 2. process unzip file
+      let result = process_unzip_file(
         &task,
+        &unzip_dir_path,
+        folder_format,
+        &context.pg_pool,
+        &mut context.redis_client,
         &context.s3_client,
-        3,
-        Duration::from_secs(retry_interval),
-        streaming,
-        &context.metrics,
+        context.collab_encryption.as_ref(),
+        &progress,
       )
       .await;
 
-      trace!(
-        "[Import]: {} download and unzip file result: {:?}",
-        task.workspace_id,
-        unzip_result
-      );
-      match unzip_result {
-        Ok(unzip_dir_path) => {
-          // 2. process unzip file
-          let result = process_unzip_file(
-            &task,
-            &unzip_dir_path,
-            &context.pg_pool,
-            &mut context.redis_client,
-            &context.s3_client,
-          )
-          .await;
-
-          // If there is any errors when processing the unzip file, we will remove the workspace and notify the user.
-          if result.is_err() {
-            info!(
-              "[Import]: failed to import notion file, delete workspace:{}",
-              task.workspace_id
-            );
-            remove_workspace(&task.workspace_id, &context.pg_pool).await;
-          }
-
-          clean_up(&context.s3_client, &task).await;
-          notify_user(&task, result, context.notifier, &context.metrics).await?;
-
-          tokio::spawn(async move {
-            match fs::remove_dir_all(&unzip_dir_path).await {
-              Ok(_) => info!(
-                "[Import]: {} deleted unzip file: {:?}",
-                task.workspace_id, unzip_dir_path
-              ),
-              Err(err) => {
-                if err.kind() != ErrorKind::NotFound {
-                  error!("Failed to delete unzip file: {:?}", err);
-                }
-              },
-            }
-          });
-        },
-        Err(err) => {
-          // If there is any errors when download or unzip the file, we will remove the file from S3 and notify the user.
-          if let Err(err) = &context.s3_client.delete_blob(task.s3_key.as_str()).await {
-            error!("Failed to delete zip file from S3: {:?}", err);
-          }
+      // If there is any errors when processing the unzip file, we will remove the workspace and notify the user.
+      // An import into an existing workspace only rolls back the imported views instead.
+      if result.is_err() {
+        if task.parent_view_id.is_none() {
+          info!(
+            "[Import]: failed to import file, delete workspace:{}",
+            task.workspace_id
+          );
           remove_workspace(&task.workspace_id, &context.pg_pool).await;
-          clean_up(&context.s3_client, &task).await;
-          notify_user(&task, Err(err), context.notifier, &context.metrics).await?;
-        },
+        } else if !matches!(result, Err(ImportError::Cancelled)) {
+          mark_task_failed(&task, &context.pg_pool).await;
+        }
       }
 
-      Ok(())
+      clean_up(&context.s3_client, &task).await;
+      notify_user(&task, result, context.notifier, &context.metrics).await?;
+
+      tokio::spawn(async move {
+        match fs::remove_dir_all(&unzip_dir_path).await {
+          Ok(_) => info!(
+            "[Import]: {} deleted unzip file: {:?}",
+            task.workspace_id, unzip_dir_path
+          ),
+          Err(err) => {
+            if err.kind() != ErrorKind::NotFound {
+              error!("Failed to delete unzip file: {:?}", err);
+            }
+          },
+        }
+      });
     },
-    ImportTask::Custom(value) => {
-      trace!("Custom task: {:?}", value);
-      let result = ImportResult {
-        user_name: "".to_string(),
-        user_email: "".to_string(),
-        is_success: true,
-        value: Default::default(),
-      };
-      context
-        .notifier
-        .notify_progress(ImportProgress::Finished(result))
-        .await;
-      Ok(())
+    Err(err) => {
+      // If there is any errors when download or unzip the file, we will remove the file from S3 and notify the user.
+      if let Err(err) = &context.s3_client.delete_blob(task.s3_key.as_str()).await {
+        error!("Failed to delete zip file from S3: {:?}", err);
+      }
+      if task.parent_view_id.is_none() {
+        remove_workspace(&task.workspace_id, &context.pg_pool).await;
+      } else if !matches!(err, ImportError::Cancelled) {
+        mark_task_failed(&task, &context.pg_pool).await;
+      }
+      clean_up(&context.s3_client, &task).await;
+      notify_user(&task, Err(err), context.notifier, &context.metrics).await?;
     },
   }
+
+  Ok(())
+}
+
+/// Processes the imports of a spreadsheet into a new grid of an existing workspace. Unlike the zip
+/// imports, the workspace is kept when the import fails.
+async fn process_database_task(
+  mut context: TaskContext,
+  task: Box<NotionImportTask>,
+  format: SpreadsheetFormat,
+) -> Result<(), ImportError> {
+  let result = import_spreadsheet(&mut context, &task, format).await;
+  // A cancelled task keeps its status
+  if result.is_err() && !matches!(result, Err(ImportError::Cancelled)) {
+    mark_task_failed(&task, &context.pg_pool).await;
+  }
+
+  clean_up(&context.s3_client, &task).await;
+  notify_user(&task, result, context.notifier, &context.metrics).await?;
+  Ok(())
 }
+
+async fn import_spreadsheet(
+  context: &mut TaskContext,
+  import_task: &NotionImportTask,
+  format: SpreadsheetFormat,
+) -> Result<(), ImportError> {
+  let parent_view_id = import_task
+    .parent_view_id
+    .as_deref()
+    .ok_or_else(|| ImportError::Internal(anyhow!("Missing parent view id")))?;
+  let view_name = import_task.view_name.clone().unwrap_or_default();
+  let progress = ProgressReporter {
+    pg_pool: context.pg_pool.clone(),
+    task_id: import_task.task_id,
+  };
+
+  // 1. download and read the file
+  progress.report(ImportPhase::Download, 0, 0).await?;
+  let S3StreamResponse { stream, .. } = context
+    .s3_client
+    .get_blob_stream(import_task.s3_key.as_str())
+    .await?;
+  let file = download_file(
+    &import_task.workspace_id,
+    &context.storage_dir,
+    stream,
+    &import_task.md5_base64,
+  )
+  .await?;
+  let spreadsheet = read_spreadsheet(file.path_buf(), format).await?;
+  trace!(
+    "[Import]: {} read {} rows, {} row errors",
+    import_task.workspace_id,
+    spreadsheet.rows.len(),
+    spreadsheet.errors.len()
+  );
+
+  // 2. build the database
+  progress
+    .report(ImportPhase::CreateCollab, 0, spreadsheet.rows.len())
+    .await?;
+  let database_id = gen_database_id();
+  let view_id = Uuid::new_v4().to_string();
+  let encoded_database =
+    build_grid_database(&spreadsheet, &database_id, &view_id, &view_name).await?;
+
+  // 3. add the grid view to the folder, on top of the updates that aren't persisted yet
+  let mut folder = open_workspace_folder(
+    import_task,
+    &context.pg_pool,
+    &context.s3_client,
+    context.collab_encryption.as_ref(),
+  )
+  .await?;
+  apply_pending_updates(
+    &mut context.redis_client,
+    &import_task.workspace_id,
+    &import_task.workspace_id,
+    &mut folder.collab,
+  )
+  .await?;
+  if folder.get_view(parent_view_id).is_none() {
+    return Err(ImportError::ParentViewNotFound(parent_view_id.to_string()));
+  }
+  let folder_update = {
+    let view = NestedChildViewBuilder::new(import_task.uid, parent_view_id.to_string())
+      .with_view_id(&view_id)
+      .with_name(&view_name)
+      .with_layout(ViewLayout::Grid)
+      .build()
+      .view;
+    let mut txn = folder.collab.transact_mut();
+    folder.body.views.insert(&mut txn, view, None);
+    txn.encode_update_v1()
+  };
+
+  // 4. add the database to the workspace database
+  let w_database_id =
+    select_workspace_database_storage_id(&context.pg_pool, &import_task.workspace_id)
+      .await
+      .map_err(|err| {
+        ImportError::Internal(anyhow!(
+          "Failed to select workspace database storage id: {:?}",
+          err
+        ))
+      })
+      .map(|id| id.to_string())?;
+  let w_db_collab = get_encode_collab_from_bytes(
+    &import_task.workspace_id,
+    &w_database_id,
+    &CollabType::WorkspaceDatabase,
+    &context.pg_pool,
+    &context.s3_client,
+    context.collab_encryption.as_ref(),
+  )
+  .await?;
+  let mut w_database = WorkspaceDatabase::from_collab_doc_state(
+    &w_database_id,
+    CollabOrigin::Server,
+    w_db_collab.into(),
+  )
+  .map_err(|err| ImportError::CannotOpenWorkspace(err.to_string()))?;
+  apply_pending_updates(
+    &mut context.redis_client,
+    &import_task.workspace_id,
+    &w_database_id,
+    &mut w_database.collab,
+  )
+  .await?;
+  let w_database_update = {
+    let mut txn = w_database.collab.transact_mut();
+    w_database
+      .body
+      .add_database(&mut txn, &database_id, vec![view_id.clone()]);
+    txn.encode_update_v1()
+  };
+
+  // 5. insert the database and its rows
+  let mut collab_params_list = vec![CollabParams {
+    object_id: database_id.clone(),
+    collab_type: CollabType::Database,
+    encoded_collab_v1: Bytes::from(
+      encoded_database
+        .encoded_database_collab
+        .encoded_collab
+        .encode_to_bytes()
+        .map_err(|err| ImportError::Internal(err.into()))?,
+    ),
+  }];
+  for row_collab in encoded_database.encoded_row_collabs {
+    collab_params_list.push(CollabParams {
+      object_id: row_collab.object_id,
+      collab_type: CollabType::DatabaseRow,
+      encoded_collab_v1: Bytes::from(
+        row_collab
+          .encoded_collab
+          .encode_to_bytes()
+          .map_err(|err| ImportError::Internal(err.into()))?,
+      ),
+    });
+  }
+  if let Some(collab_encryption) = context.collab_encryption.as_ref() {
+    for params in collab_params_list.iter_mut() {
+      params.encoded_collab_v1 = collab_encryption
+        .encrypt(
+          &import_task.workspace_id,
+          std::mem::take(&mut params.encoded_collab_v1),
+        )
+        .await
+        .map_err(|err| ImportError::Internal(err.into()))?;
+    }
+  }
+
+  update_import_task_metadata(
+    import_task.task_id,
+    json!({ "row_errors": spreadsheet.errors }),
+    &context.pg_pool,
+  )
+  .await
+  .map_err(|err| ImportError::Internal(err.into()))?;
+  progress
+    .report(
+      ImportPhase::CreateCollab,
+      spreadsheet.rows.len(),
+      spreadsheet.rows.len(),
+    )
+    .await?;
+
+  let mut transaction = context.pg_pool.begin().await.map_err(|err| {
+    ImportError::Internal(anyhow!(
+      "Failed to start transaction when importing data: {:?}",
+      err
+    ))
+  })?;
+  insert_into_af_collab_bulk_for_user(
+    &mut transaction,
+    &import_task.uid,
+    &import_task.workspace_id,
+    &collab_params_list,
+  )
+  .await
+  .map_err(|err| {
+    ImportError::Internal(anyhow!(
+      "Failed to insert collabs into database when importing data: {:?}",
+      err
+    ))
+  })?;
+  let completed = complete_import_task(transaction.deref_mut(), &import_task.task_id)
+    .await
+    .map_err(|err| {
+      ImportError::Internal(anyhow!(
+        "Failed to update import task status when importing data: {:?}",
+        err
+      ))
+    })?;
+  if !completed {
+    return Err(ImportError::Cancelled);
+  }
+  transaction.commit().await.map_err(|err| {
+    ImportError::Internal(anyhow!(
+      "Failed to commit transaction when importing data: {:?}",
+      err
+    ))
+  })?;
+
+  // 6. the database must be referenced by the workspace database before the view shows up
+  send_collab_update(
+    &context.redis_client,
+    &import_task.workspace_id,
+    &w_database_id,
+    w_database_update,
+  )
+  .await?;
+  send_collab_update(
+    &context.redis_client,
+    &import_task.workspace_id,
+    &import_task.workspace_id,
+    folder_update,
+  )
+  .await?;
+  Ok(())
+}
+
 /// Retries the download and unzipping of a file from an S3 source.
 ///
 /// This function attempts to download a zip file from an S3 bucket and unzip it to a local directory.
@@ -647,15 +962,28 @@ pub async fn download_and_unzip_file_retry(
   interval: Duration,
   streaming: bool,
   metrics: &Option<Arc<ImportMetrics>>,
+  progress: &ProgressReporter,
 ) -> Result<PathBuf, ImportError> {
   let mut attempt = 0;
   loop {
     attempt += 1;
-    match download_and_unzip_file(storage_dir, import_task, s3_client, streaming, metrics).await {
+    match download_and_unzip_file(
+      storage_dir,
+      import_task,
+      s3_client,
+      streaming,
+      metrics,
+      progress,
+    )
+    .await
+    {
       Ok(result) => return Ok(result),
       Err(err) => {
-        // If the Upload file not found error occurs, we will not retry.
-        if matches!(err, ImportError::UploadFileNotFound) {
+        // If the Upload file not found error occurs, or the task is cancelled, we will not retry.
+        if matches!(
+          err,
+          ImportError::UploadFileNotFound | ImportError::Cancelled
+        ) {
           return Err(err);
         }
 
@@ -687,7 +1015,9 @@ async fn download_and_unzip_file(
   s3_client: &Arc<dyn S3Client>,
   streaming: bool,
   metrics: &Option<Arc<ImportMetrics>>,
+  progress: &ProgressReporter,
 ) -> Result<PathBuf, ImportError> {
+  progress.report(ImportPhase::Download, 0, 0).await?;
   let blob_meta = s3_client.get_blob_meta(import_task.s3_key.as_str()).await?;
   match blob_meta.content_type {
     None => {
@@ -747,6 +1077,8 @@ async fn download_and_unzip_file(
     metrics.record_import_size_bytes(buffer_size);
   }
   if streaming {
+    // The file is unzipped while it's downloaded
+    progress.report(ImportPhase::Unzip, 0, 0).await?;
     let zip_reader = get_zip_reader(buffer_size, StreamOrFile::Stream(stream)).await?;
     let unique_file_name = Uuid::new_v4().to_string();
     let output_file_path = storage_dir.join(unique_file_name);
@@ -778,6 +1110,7 @@ async fn download_and_unzip_file(
       import_task.workspace_id,
       file.path_buf()
     );
+    progress.report(ImportPhase::Unzip, 0, 0).await?;
 
     let file_path = file.path_buf().clone();
     let storage_dir = storage_dir.to_path_buf();
@@ -865,104 +1198,178 @@ fn buffer_size_from_content_length(content_length: Option<i64>) -> usize {
   }
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn process_unzip_file(
   import_task: &NotionImportTask,
   unzip_dir_path: &PathBuf,
+  folder_format: Option<FolderImportFormat>,
   pg_pool: &PgPool,
   redis_client: &mut ConnectionManager,
   s3_client: &Arc<dyn S3Client>,
+  collab_encryption: Option<&CollabEncryption>,
+  progress: &ProgressReporter,
 ) -> Result<(), ImportError> {
   let workspace_id =
     Uuid::parse_str(&import_task.workspace_id).map_err(|err| ImportError::Internal(err.into()))?;
-  let notion_importer = NotionImporter::new(
-    import_task.uid,
-    unzip_dir_path,
-    import_task.workspace_id.clone(),
-    import_task.host.clone(),
-  )
-  .map_err(ImportError::ImportCollabError)?;
-
-  trace!(
-    "[Import]: {} start import notion data",
-    import_task.workspace_id
-  );
-  let imported = notion_importer
-    .import()
-    .await
-    .map_err(ImportError::ImportCollabError)?;
-  let nested_views = imported.build_nested_views().await;
-  trace!(
-    "[Import]: {} imported nested views:{}",
-    import_task.workspace_id,
-    nested_views
-  );
-
-  // 1. Open the workspace folder
-  let folder_collab = get_encode_collab_from_bytes(
-    &imported.workspace_id,
-    &imported.workspace_id,
-    &CollabType::Folder,
-    pg_pool,
-    s3_client,
-  )
-  .await?;
-  let mut folder = Folder::from_collab_doc_state(
-    import_task.uid,
-    CollabOrigin::Server,
-    folder_collab.into(),
-    &imported.workspace_id,
-    vec![],
-  )
-  .map_err(|err| ImportError::CannotOpenWorkspace(err.to_string()))?;
-
-  // 2. Insert collabs' views into the folder
-  trace!(
-    "[Import]: {} insert views:{} to folder",
-    import_task.workspace_id,
-    nested_views.len()
-  );
-  folder.insert_nested_views(nested_views.into_inner());
 
   let mut resources = vec![];
+  let mut upload_resources = vec![];
   let mut collab_params_list = vec![];
   let mut database_view_ids_by_database_id: HashMap<String, Vec<String>> = HashMap::new();
   let mut orphan_view_ids = HashSet::new();
 
-  // 3. Collect all collabs and resources
-  let mut stream = imported.into_collab_stream().await;
-  while let Some(imported_collab_info) = stream.next().await {
-    trace!(
-      "[Import]: {} imported collab: {}",
-      import_task.workspace_id,
-      imported_collab_info
-    );
-    resources.extend(imported_collab_info.resources);
-    collab_params_list.extend(
-      imported_collab_info
-        .imported_collabs
-        .into_iter()
-        .map(|imported_collab| CollabParams {
-          object_id: imported_collab.object_id,
-          collab_type: imported_collab.collab_type,
-          encoded_collab_v1: Bytes::from(imported_collab.encoded_collab.encode_to_bytes().unwrap()),
-        })
-        .collect::<Vec<_>>(),
-    );
+  let (mut folder, merge) = match folder_format {
+    None => {
+      let notion_importer = NotionImporter::new(
+        import_task.uid,
+        unzip_dir_path,
+        import_task.workspace_id.clone(),
+        import_task.host.clone(),
+      )
+      .map_err(ImportError::ImportCollabError)?;
 
-    match imported_collab_info.import_type {
-      ImportType::Database {
-        database_id,
-        view_ids,
-        row_document_ids,
-      } => {
-        database_view_ids_by_database_id.insert(database_id, view_ids);
-        orphan_view_ids.extend(row_document_ids);
-      },
-      ImportType::Document => {
-        // do nothing
-      },
-    }
-  }
+      trace!(
+        "[Import]: {} start import notion data",
+        import_task.workspace_id
+      );
+      progress.report(ImportPhase::CreateCollab, 0, 0).await?;
+      let imported = notion_importer
+        .import()
+        .await
+        .map_err(ImportError::ImportCollabError)?;
+      let nested_views = imported.build_nested_views().await;
+      trace!(
+        "[Import]: {} imported nested views:{}",
+        import_task.workspace_id,
+        nested_views
+      );
+
+      // 1. Open the workspace folder
+      let (mut folder, merge) = open_import_folder(
+        import_task,
+        pg_pool,
+        redis_client,
+        s3_client,
+        collab_encryption,
+      )
+      .await?;
+
+      // 2. Insert collabs' views into the folder
+      trace!(
+        "[Import]: {} insert views:{} to folder",
+        import_task.workspace_id,
+        nested_views.len()
+      );
+      let num_of_existing_views = num_of_views(&folder);
+      folder.insert_nested_views(nested_views.into_inner());
+      let num_of_pages = num_of_views(&folder).saturating_sub(num_of_existing_views);
+      progress
+        .report(ImportPhase::CreateCollab, 0, num_of_pages)
+        .await?;
+
+      // 3. Collect all collabs and resources
+      let mut num_of_processed_pages = 0;
+      let mut stream = imported.into_collab_stream().await;
+      while let Some(imported_collab_info) = stream.next().await {
+        trace!(
+          "[Import]: {} imported collab: {}",
+          import_task.workspace_id,
+          imported_collab_info
+        );
+        num_of_processed_pages += 1;
+        if num_of_processed_pages % PROGRESS_REPORT_INTERVAL == 0 {
+          progress
+            .report(
+              ImportPhase::CreateCollab,
+              num_of_processed_pages.min(num_of_pages),
+              num_of_pages,
+            )
+            .await?;
+        }
+        resources.extend(imported_collab_info.resources);
+        collab_params_list.extend(
+          imported_collab_info
+            .imported_collabs
+            .into_iter()
+            .map(|imported_collab| CollabParams {
+              object_id: imported_collab.object_id,
+              collab_type: imported_collab.collab_type,
+              encoded_collab_v1: Bytes::from(
+                imported_collab.encoded_collab.encode_to_bytes().unwrap(),
+              ),
+            })
+            .collect::<Vec<_>>(),
+        );
+
+        match imported_collab_info.import_type {
+          ImportType::Database {
+            database_id,
+            view_ids,
+            row_document_ids,
+          } => {
+            database_view_ids_by_database_id.insert(database_id, view_ids);
+            orphan_view_ids.extend(row_document_ids);
+          },
+          ImportType::Document => {
+            // do nothing
+          },
+        }
+      }
+      (folder, merge)
+    },
+    Some(format) => {
+      trace!(
+        "[Import]: {} start import {:?} folder",
+        import_task.workspace_id,
+        format
+      );
+      progress.report(ImportPhase::CreateCollab, 0, 0).await?;
+      let importer = FolderImporter::new(import_task, unzip_dir_path, format);
+      let imported = tokio::task::spawn_blocking(move || importer.import())
+        .await
+        .map_err(|err| ImportError::Internal(err.into()))??;
+
+      // 1. Open the workspace folder
+      let (mut folder, merge) = open_import_folder(
+        import_task,
+        pg_pool,
+        redis_client,
+        s3_client,
+        collab_encryption,
+      )
+      .await?;
+
+      // 2. Insert the views of the pages into the folder
+      trace!(
+        "[Import]: {} insert views:{} to folder",
+        import_task.workspace_id,
+        imported.views.len()
+      );
+      let num_of_pages = imported.views.len();
+      folder.insert_views(imported.views);
+      progress
+        .report(ImportPhase::CreateCollab, num_of_pages, num_of_pages)
+        .await?;
+
+      // 3. Collect all documents and the files they embed
+      for (object_id, encoded_collab) in imported.documents {
+        let encoded_collab_v1 = encoded_collab
+          .encode_to_bytes()
+          .map_err(|err| ImportError::Internal(err.into()))?;
+        collab_params_list.push(CollabParams {
+          object_id,
+          collab_type: CollabType::Document,
+          encoded_collab_v1: Bytes::from(encoded_collab_v1),
+        });
+      }
+      upload_resources.extend(imported.files.into_iter().map(|file| UploadCollabResource {
+        object_id: file.meta.object_id.clone(),
+        file_path: file.file_path.to_string_lossy().to_string(),
+        meta: file.meta,
+      }));
+      (folder, merge)
+    },
+  };
 
   let w_database_id = select_workspace_database_storage_id(pg_pool, &import_task.workspace_id)
     .await
@@ -975,6 +1382,7 @@ async fn process_unzip_file(
     .map(|id| id.to_string())?;
 
   // 4. Edit workspace database collab and then encode workspace database collab
+  let mut w_database_update = None;
   if !database_view_ids_by_database_id.is_empty() {
     let w_db_collab = get_encode_collab_from_bytes(
       &import_task.workspace_id,
@@ -982,6 +1390,7 @@ async fn process_unzip_file(
       &CollabType::WorkspaceDatabase,
       pg_pool,
       s3_client,
+      collab_encryption,
     )
     .await?;
     let mut w_database = WorkspaceDatabase::from_collab_doc_state(
@@ -990,47 +1399,67 @@ async fn process_unzip_file(
       w_db_collab.into(),
     )
     .map_err(|err| ImportError::CannotOpenWorkspace(err.to_string()))?;
-    w_database.batch_add_database(database_view_ids_by_database_id);
 
-    let w_database_collab = w_database.encode_collab_v1().map_err(|err| {
-      ImportError::Internal(anyhow!(
-        "Failed to encode workspace database collab: {:?}",
-        err
-      ))
-    })?;
+    if merge.is_some() {
+      // The workspace database of an existing workspace only receives the added databases
+      apply_pending_updates(
+        redis_client,
+        &import_task.workspace_id,
+        &w_database_id,
+        &mut w_database.collab,
+      )
+      .await?;
+      let state_vector = w_database.collab.transact().state_vector();
+      w_database.batch_add_database(database_view_ids_by_database_id);
+      w_database_update = Some(
+        w_database
+          .collab
+          .transact()
+          .encode_state_as_update_v1(&state_vector),
+      );
+    } else {
+      w_database.batch_add_database(database_view_ids_by_database_id);
 
-    match w_database_collab.encode_to_bytes() {
-      Ok(bytes) => {
-        if let Err(err) = redis_client
-          .set_ex::<String, Vec<u8>, Value>(
-            encode_collab_key(&w_database_id),
-            bytes,
-            2592000, // WorkspaceDatabase => 1 month
-          )
-          .await
-        {
-          warn!(
-            "[Import] Failed to insert workspace database to Redis: {}",
-            err
-          );
-        }
-      },
-      Err(err) => warn!(
-        "[Import] Failed to encode workspace database collab payload: {}",
-        err
-      ),
-    }
+      let w_database_collab = w_database.encode_collab_v1().map_err(|err| {
+        ImportError::Internal(anyhow!(
+          "Failed to encode workspace database collab: {:?}",
+          err
+        ))
+      })?;
 
-    trace!(
-      "[Import]: {} did encode workspace database collab",
-      import_task.workspace_id
-    );
-    let w_database_collab_params = CollabParams {
-      object_id: w_database_id.clone(),
-      collab_type: CollabType::WorkspaceDatabase,
-      encoded_collab_v1: Bytes::from(w_database_collab.encode_to_bytes().unwrap()),
-    };
-    collab_params_list.push(w_database_collab_params);
+      match w_database_collab.encode_to_bytes() {
+        Ok(bytes) => {
+          if let Err(err) = redis_client
+            .set_ex::<String, Vec<u8>, Value>(
+              encode_collab_key(&w_database_id),
+              bytes,
+              2592000, // WorkspaceDatabase => 1 month
+            )
+            .await
+          {
+            warn!(
+              "[Import] Failed to insert workspace database to Redis: {}",
+              err
+            );
+          }
+        },
+        Err(err) => warn!(
+          "[Import] Failed to encode workspace database collab payload: {}",
+          err
+        ),
+      }
+
+      trace!(
+        "[Import]: {} did encode workspace database collab",
+        import_task.workspace_id
+      );
+      let w_database_collab_params = CollabParams {
+        object_id: w_database_id.clone(),
+        collab_type: CollabType::WorkspaceDatabase,
+        encoded_collab_v1: Bytes::from(w_database_collab.encode_to_bytes().unwrap()),
+      };
+      collab_params_list.push(w_database_collab_params);
+    }
   }
 
   // 5. Insert orphan view to folder
@@ -1044,39 +1473,65 @@ async fn process_unzip_file(
     folder.insert_views(orphan_views);
   }
 
-  // 6. Encode Folder
-  let folder_collab = folder
-    .encode_collab_v1(|collab| CollabType::Folder.validate_require_data(collab))
-    .map_err(|err| ImportError::Internal(err.into()))?;
+  // 6. Encode Folder, or only the update that merges the imported views into an existing folder
+  let merged = match merge {
+    Some(merge) => {
+      let imported_view_ids = merge.nest_imported_views(&mut folder, &import_task.workspace_id);
+      let folder_update = folder
+        .collab
+        .transact()
+        .encode_state_as_update_v1(&merge.state_vector);
+      trace!(
+        "[Import]: {} merge views:{} into folder",
+        import_task.workspace_id,
+        imported_view_ids.len()
+      );
+      Some(MergedImport {
+        folder_update,
+        w_database_update,
+        imported_view_ids,
+      })
+    },
+    None => {
+      let folder_collab = folder
+        .encode_collab_v1(|collab| CollabType::Folder.validate_require_data(collab))
+        .map_err(|err| ImportError::Internal(err.into()))?;
 
-  match folder_collab.encode_to_bytes() {
-    Ok(bytes) => {
-      if let Err(err) = redis_client
-        .set_ex::<String, Vec<u8>, Value>(
-          encode_collab_key(&import_task.workspace_id),
-          bytes,
-          604800, // Folder => 1 week
-        )
-        .await
-      {
-        warn!("[Import] Failed to insert folder collab to Redis: {}", err);
+      match folder_collab.encode_to_bytes() {
+        Ok(bytes) => {
+          if let Err(err) = redis_client
+            .set_ex::<String, Vec<u8>, Value>(
+              encode_collab_key(&import_task.workspace_id),
+              bytes,
+              604800, // Folder => 1 week
+            )
+            .await
+          {
+            warn!("[Import] Failed to insert folder collab to Redis: {}", err);
+          }
+        },
+        Err(err) => warn!("[Import] Failed to encode folder collab payload: {}", err),
       }
-    },
-    Err(err) => warn!("[Import] Failed to encode folder collab payload: {}", err),
-  }
 
-  let folder_collab_params = CollabParams {
-    object_id: import_task.workspace_id.clone(),
-    collab_type: CollabType::Folder,
-    encoded_collab_v1: Bytes::from(folder_collab.encode_to_bytes().unwrap()),
+      let folder_collab_params = CollabParams {
+        object_id: import_task.workspace_id.clone(),
+        collab_type: CollabType::Folder,
+        encoded_collab_v1: Bytes::from(folder_collab.encode_to_bytes().unwrap()),
+      };
+      trace!(
+        "[Import]: {} did encode folder collab",
+        import_task.workspace_id
+      );
+      collab_params_list.push(folder_collab_params);
+      None
+    },
   };
-  trace!(
-    "[Import]: {} did encode folder collab",
-    import_task.workspace_id
-  );
-  collab_params_list.push(folder_collab_params);
 
-  let upload_resources = process_resources(resources).await;
+  upload_resources.extend(process_resources(resources).await);
+  // Last chance to cancel the import, the task is completed by the transaction below
+  progress
+    .report(ImportPhase::Upload, 0, upload_resources.len())
+    .await?;
 
   // 7. Start a transaction to insert all collabs
   let mut transaction = pg_pool.begin().await.map_err(|err| {
@@ -1092,6 +1547,17 @@ async fn process_unzip_file(
   );
 
   // 8. write all collab to disk
+  if let Some(collab_encryption) = collab_encryption {
+    for params in collab_params_list.iter_mut() {
+      params.encoded_collab_v1 = collab_encryption
+        .encrypt(
+          &import_task.workspace_id,
+          std::mem::take(&mut params.encoded_collab_v1),
+        )
+        .await
+        .map_err(|err| ImportError::Internal(err.into()))?;
+    }
+  }
   insert_into_af_collab_bulk_for_user(
     &mut transaction,
     &import_task.uid,
@@ -1111,49 +1577,57 @@ async fn process_unzip_file(
     import_task.workspace_id,
     import_task.task_id,
   );
-  update_import_task_status(
-    &import_task.task_id,
-    ImportTaskState::Completed,
-    transaction.deref_mut(),
-  )
-  .await
-  .map_err(|err| {
-    ImportError::Internal(anyhow!(
-      "Failed to update import task status when importing data: {:?}",
-      err
-    ))
-  })?;
-
-  trace!(
-    "[Import]: {} set is_initialized to true",
-    import_task.workspace_id,
-  );
-  update_workspace_status(transaction.deref_mut(), &workspace_id, true)
+  let completed = complete_import_task(transaction.deref_mut(), &import_task.task_id)
     .await
     .map_err(|err| {
       ImportError::Internal(anyhow!(
-        "Failed to update workspace status when importing data: {:?}",
+        "Failed to update import task status when importing data: {:?}",
         err
       ))
     })?;
+  if !completed {
+    if merged.is_none() {
+      let _: RedisResult<Value> = redis_client.del(encode_collab_key(&w_database_id)).await;
+      let _: RedisResult<Value> = redis_client
+        .del(encode_collab_key(&import_task.workspace_id))
+        .await;
+    }
+    return Err(ImportError::Cancelled);
+  }
 
-  // Set the workspace's updated_at to the earliest possible timestamp, as it is created by an import task
-  // and not actively updated by a user. This ensures that when sorting workspaces by updated_at to find
-  // the most recent, the imported workspace doesn't appear as the most recently visited workspace.
-  let updated_at = DateTime::from_timestamp(0, 0).unwrap_or_else(Utc::now);
-  update_updated_at_of_workspace_with_uid(
-    transaction.deref_mut(),
-    import_task.uid,
-    &workspace_id,
-    updated_at,
-  )
-  .await
-  .map_err(|err| {
-    ImportError::Internal(anyhow!(
-      "Failed to update workspace updated_at when importing data: {:?}",
-      err
-    ))
-  })?;
+  // An existing workspace is already initialized and keeps its updated_at
+  if merged.is_none() {
+    trace!(
+      "[Import]: {} set is_initialized to true",
+      import_task.workspace_id,
+    );
+    update_workspace_status(transaction.deref_mut(), &workspace_id, true)
+      .await
+      .map_err(|err| {
+        ImportError::Internal(anyhow!(
+          "Failed to update workspace status when importing data: {:?}",
+          err
+        ))
+      })?;
+
+    // Set the workspace's updated_at to the earliest possible timestamp, as it is created by an import task
+    // and not actively updated by a user. This ensures that when sorting workspaces by updated_at to find
+    // the most recent, the imported workspace doesn't appear as the most recently visited workspace.
+    let updated_at = DateTime::from_timestamp(0, 0).unwrap_or_else(Utc::now);
+    update_updated_at_of_workspace_with_uid(
+      transaction.deref_mut(),
+      import_task.uid,
+      &workspace_id,
+      updated_at,
+    )
+    .await
+    .map_err(|err| {
+      ImportError::Internal(anyhow!(
+        "Failed to update workspace updated_at when importing data: {:?}",
+        err
+      ))
+    })?;
+  }
 
   // insert metadata into database
   let metas = upload_resources
@@ -1186,22 +1660,308 @@ async fn process_unzip_file(
   });
 
   if result.is_err() {
-    let _: RedisResult<Value> = redis_client.del(encode_collab_key(&w_database_id)).await;
-    let _: RedisResult<Value> = redis_client
-      .del(encode_collab_key(&import_task.workspace_id))
-      .await;
+    if merged.is_none() {
+      let _: RedisResult<Value> = redis_client.del(encode_collab_key(&w_database_id)).await;
+      let _: RedisResult<Value> = redis_client
+        .del(encode_collab_key(&import_task.workspace_id))
+        .await;
+    }
 
     return result;
   }
 
-  // 9. after inserting all collabs, upload all files to S3
+  // 9. merge the imported views into the collabs of the existing workspace. The database must be
+  // referenced by the workspace database before its view shows up in the folder
+  let imported_object_ids = collab_params_list
+    .iter()
+    .map(|params| params.object_id.clone())
+    .collect::<Vec<_>>();
+  if let Some(merged) = &merged {
+    let mut result = Ok(());
+    if let Some(w_database_update) = merged.w_database_update.clone() {
+      result = send_collab_update(
+        redis_client,
+        &import_task.workspace_id,
+        &w_database_id,
+        w_database_update,
+      )
+      .await;
+    }
+    if result.is_ok() {
+      result = send_collab_update(
+        redis_client,
+        &import_task.workspace_id,
+        &import_task.workspace_id,
+        merged.folder_update.clone(),
+      )
+      .await;
+    }
+    if let Err(err) = result {
+      roll_back_imported_views(
+        import_task,
+        pg_pool,
+        redis_client,
+        None,
+        merged,
+        &imported_object_ids,
+      )
+      .await;
+      return Err(err);
+    }
+  }
+
+  // 10. after inserting all collabs, upload all files to S3
   trace!("[Import]: {} upload files to s3", import_task.workspace_id,);
-  batch_upload_files_to_s3(&import_task.workspace_id, s3_client, upload_resources)
+  let num_of_files = upload_resources.len();
+  if let Err(err) =
+    batch_upload_files_to_s3(&import_task.workspace_id, s3_client, upload_resources).await
+  {
+    if let Some(merged) = &merged {
+      roll_back_imported_views(
+        import_task,
+        pg_pool,
+        redis_client,
+        Some(&mut folder),
+        merged,
+        &imported_object_ids,
+      )
+      .await;
+    }
+    return Err(ImportError::Internal(anyhow!(
+      "Failed to upload files to S3: {:?}",
+      err
+    )));
+  }
+  // The import is completed, failing to report the progress doesn't fail it
+  if let Err(err) = progress
+    .report(ImportPhase::Upload, num_of_files, num_of_files)
     .await
-    .map_err(|err| ImportError::Internal(anyhow!("Failed to upload files to S3: {:?}", err)))?;
+  {
+    warn!(
+      "[Import]: {} failed to report progress: {}",
+      import_task.workspace_id, err
+    );
+  }
   Ok(())
 }
 
+/// The folder of an existing workspace that the imported views are merged into.
+struct FolderMerge {
+  parent_view_id: String,
+  name: String,
+  /// The state of the folder before the import, to encode the update that adds the imported views
+  state_vector: StateVector,
+  existing_view_ids: HashSet<String>,
+}
+
+impl FolderMerge {
+  /// Moves the views that the import created at the root of the workspace under the parent view.
+  /// They become regular pages named after the import instead of spaces. Returns the ids of all
+  /// imported views.
+  fn nest_imported_views(&self, folder: &mut Folder, workspace_id: &str) -> Vec<String> {
+    let mut txn = folder.collab.transact_mut();
+    let imported_views = folder
+      .body
+      .views
+      .get_all_views(&txn)
+      .into_iter()
+      .filter(|view| !self.existing_view_ids.contains(&view.id))
+      .collect::<Vec<_>>();
+    for view in imported_views
+      .iter()
+      .filter(|view| view.parent_view_id == workspace_id)
+    {
+      folder
+        .body
+        .move_nested_view(&mut txn, &view.id, &self.parent_view_id, None);
+      folder.body.views.update_view(&mut txn, &view.id, |update| {
+        update.set_name(&self.name).set_extra("{}").done()
+      });
+    }
+    imported_views
+      .into_iter()
+      .map(|view| view.id.clone())
+      .collect()
+  }
+}
+
+/// The changes of an import into an existing workspace, which are sent to the collabs opened by
+/// the clients once the imported collabs are persisted.
+struct MergedImport {
+  folder_update: Vec<u8>,
+  w_database_update: Option<Vec<u8>>,
+  imported_view_ids: Vec<String>,
+}
+
+/// Opens the folder that the views are imported into. When importing into an existing workspace,
+/// the folder includes the updates that aren't persisted yet and the returned [FolderMerge]
+/// records its state before the import.
+async fn open_import_folder(
+  import_task: &NotionImportTask,
+  pg_pool: &PgPool,
+  redis_client: &mut ConnectionManager,
+  s3_client: &Arc<dyn S3Client>,
+  collab_encryption: Option<&CollabEncryption>,
+) -> Result<(Folder, Option<FolderMerge>), ImportError> {
+  let mut folder =
+    open_workspace_folder(import_task, pg_pool, s3_client, collab_encryption).await?;
+  let Some(parent_view_id) = import_task.parent_view_id.clone() else {
+    return Ok((folder, None));
+  };
+
+  apply_pending_updates(
+    redis_client,
+    &import_task.workspace_id,
+    &import_task.workspace_id,
+    &mut folder.collab,
+  )
+  .await?;
+  if folder.get_view(&parent_view_id).is_none() {
+    return Err(ImportError::ParentViewNotFound(parent_view_id));
+  }
+  let merge = {
+    let txn = folder.collab.transact();
+    FolderMerge {
+      name: import_task
+        .view_name
+        .clone()
+        .unwrap_or_else(|| import_task.workspace_name.clone()),
+      parent_view_id,
+      state_vector: txn.state_vector(),
+      existing_view_ids: folder
+        .body
+        .views
+        .get_all_views(&txn)
+        .into_iter()
+        .map(|view| view.id.clone())
+        .collect(),
+    }
+  };
+  Ok((folder, Some(merge)))
+}
+
+/// Rolls back an import into an existing workspace once its collabs are persisted: the imported
+/// views are removed from the folder if it was updated, and the imported collabs are deleted. The
+/// rest of the workspace is left untouched.
+async fn roll_back_imported_views(
+  import_task: &NotionImportTask,
+  pg_pool: &PgPool,
+  redis_client: &ConnectionManager,
+  updated_folder: Option<&mut Folder>,
+  merged: &MergedImport,
+  imported_object_ids: &[String],
+) {
+  info!(
+    "[Import]: {} roll back {} imported views",
+    import_task.workspace_id,
+    merged.imported_view_ids.len()
+  );
+  if let Some(folder) = updated_folder {
+    let update = {
+      let mut txn = folder.collab.transact_mut();
+      folder
+        .body
+        .views
+        .delete_views(&mut txn, merged.imported_view_ids.clone());
+      txn.encode_update_v1()
+    };
+    if let Err(err) = send_collab_update(
+      redis_client,
+      &import_task.workspace_id,
+      &import_task.workspace_id,
+      update,
+    )
+    .await
+    {
+      error!(
+        "[Import]: {} failed to remove imported views: {}",
+        import_task.workspace_id, err
+      );
+    }
+  }
+
+  match Uuid::parse_str(&import_task.workspace_id) {
+    Ok(workspace_id) => {
+      if let Err(err) =
+        soft_delete_collabs_of_workspace(pg_pool, &workspace_id, imported_object_ids).await
+      {
+        error!(
+          "[Import]: {} failed to delete imported collabs: {}",
+          import_task.workspace_id, err
+        );
+      }
+    },
+    Err(err) => error!("[Import]: invalid workspace id: {}", err),
+  }
+}
+
+fn num_of_views(folder: &Folder) -> usize {
+  let txn = folder.collab.transact();
+  folder.body.views.get_all_views(&txn).len()
+}
+
+/// Persists the progress of an import task. Reports fail with [ImportError::Cancelled] once the
+/// task is cancelled, which stops the import at its next step.
+pub struct ProgressReporter {
+  pg_pool: PgPool,
+  task_id: Uuid,
+}
+
+impl ProgressReporter {
+  async fn report(
+    &self,
+    phase: ImportPhase,
+    processed_count: usize,
+    total_count: usize,
+  ) -> Result<(), ImportError> {
+    let status = update_import_task_progress(
+      &self.pg_pool,
+      &self.task_id,
+      phase,
+      processed_count as i32,
+      total_count as i32,
+    )
+    .await
+    .map_err(|err| ImportError::Internal(err.into()))?;
+    match status {
+      ImportTaskState::Cancel => Err(ImportError::Cancelled),
+      _ => Ok(()),
+    }
+  }
+}
+
+async fn open_workspace_folder(
+  import_task: &NotionImportTask,
+  pg_pool: &PgPool,
+  s3_client: &Arc<dyn S3Client>,
+  collab_encryption: Option<&CollabEncryption>,
+) -> Result<Folder, ImportError> {
+  let folder_collab = get_encode_collab_from_bytes(
+    &import_task.workspace_id,
+    &import_task.workspace_id,
+    &CollabType::Folder,
+    pg_pool,
+    s3_client,
+    collab_encryption,
+  )
+  .await?;
+  Folder::from_collab_doc_state(
+    import_task.uid,
+    CollabOrigin::Server,
+    folder_collab.into(),
+    &import_task.workspace_id,
+    vec![],
+  )
+  .map_err(|err| ImportError::CannotOpenWorkspace(err.to_string()))
+}
+
+async fn mark_task_failed(task: &NotionImportTask, pg_pool: &PgPool) {
+  if let Err(err) = update_import_task_status(&task.task_id, ImportTaskState::Failed, pg_pool).await
+  {
+    error!("Failed to update import task status: {:?}", err);
+  }
+}
+
 async fn clean_up(s3_client: &Arc<dyn S3Client>, task: &NotionImportTask) {
   if let Err(err) = s3_client.delete_blob(task.s3_key.as_str()).await {
     error!("Failed to delete zip file from S3: {:?}", err);
@@ -1225,6 +1985,11 @@ async fn notify_user(
   notifier: Arc<dyn ImportNotifier>,
   metrics: &Option<Arc<ImportMetrics>>,
 ) -> Result<(), ImportError> {
+  // The user cancelled the import, there is nothing to tell them
+  if matches!(result, Err(ImportError::Cancelled)) {
+    info!("[Import]: cancelled import:{}", import_task);
+    return Ok(());
+  }
   let task_id = import_task.task_id.to_string();
   let (error, error_detail) = match result {
     Ok(_) => {
@@ -1252,7 +2017,10 @@ async fn notify_user(
   let value = serde_json::to_value(ImportNotionMailerParam {
     import_task_id: task_id,
     user_name: import_task.user_name.clone(),
-    import_file_name: import_task.workspace_name.clone(),
+    import_file_name: import_task
+      .view_name
+      .clone()
+      .unwrap_or_else(|| import_task.workspace_name.clone()),
     workspace_id: import_task.workspace_id.clone(),
     workspace_name: import_task.workspace_name.clone(),
     open_workspace: false,
@@ -1348,12 +2116,13 @@ async fn upload_file_to_s3(
   ))
 }
 
-async fn get_encode_collab_from_bytes(
+pub(crate) async fn get_encode_collab_from_bytes(
   workspace_id: &str,
   object_id: &str,
   collab_type: &CollabType,
   pg_pool: &PgPool,
   s3: &Arc<dyn S3Client>,
+  collab_encryption: Option<&CollabEncryption>,
 ) -> Result<EncodedCollab, ImportError> {
   let key = collab_key(workspace_id, object_id);
   match s3.get_blob_stream(&key).await {
@@ -1364,6 +2133,13 @@ async fn get_encode_collab_from_bytes(
         .read_to_end(&mut buf)
         .await
         .map_err(|err| ImportError::Internal(err.into()))?;
+      if let Some(collab_encryption) = collab_encryption {
+        buf = collab_encryption
+          .decrypt(workspace_id, buf.into())
+          .await
+          .map_err(|err| ImportError::Internal(err.into()))?
+          .to_vec();
+      }
       let decompressed = zstd::decode_all(&*buf).map_err(|e| ImportError::Internal(e.into()))?;
       Ok(EncodedCollab {
         state_vector: Default::default(),
@@ -1373,9 +2149,16 @@ async fn get_encode_collab_from_bytes(
     },
     Err(WorkerError::RecordNotFound(_)) => {
       // fallback to postgres
-      let bytes = select_blob_from_af_collab(pg_pool, collab_type, object_id)
+      let mut bytes = select_blob_from_af_collab(pg_pool, collab_type, object_id)
         .await
         .map_err(|err| ImportError::Internal(err.into()))?;
+      if let Some(collab_encryption) = collab_encryption {
+        bytes = collab_encryption
+          .decrypt(workspace_id, bytes.into())
+          .await
+          .map_err(|err| ImportError::Internal(err.into()))?
+          .to_vec();
+      }
 
       Ok(
         EncodedCollab::decode_from_bytes(&bytes)
@@ -1460,6 +2243,9 @@ async fn get_un_ack_tasks(
   }
 }
 
+/// The task of an import whose file is uploaded to S3. Shared by the Notion, Markdown and HTML
+/// imports, which only differ in how the content of the zip is imported, and by the spreadsheet
+/// imports.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NotionImportTask {
   pub uid: i64,
@@ -1478,6 +2264,12 @@ pub struct NotionImportTask {
   pub last_process_at: Option<i64>,
   #[serde(default)]
   pub file_size: Option<i64>,
+  /// Set for the imports into an existing workspace, which create their views under this view
+  /// instead of importing into a new workspace
+  #[serde(default)]
+  pub parent_view_id: Option<String>,
+  #[serde(default)]
+  pub view_name: Option<String>,
 }
 
 impl Display for NotionImportTask {
@@ -1496,9 +2288,31 @@ impl Display for NotionImportTask {
 pub enum ImportTask {
   // boxing the large fields to reduce the total size of the enum
   Notion(Box<NotionImportTask>),
+  /// A zip of Markdown files, e.g. an Obsidian vault
+  Markdown(Box<NotionImportTask>),
+  /// A zip of HTML pages, e.g. a Confluence space export
+  Html(Box<NotionImportTask>),
+  /// A CSV file imported into a new grid
+  Csv(Box<NotionImportTask>),
+  /// An Excel workbook imported into a new grid
+  Xlsx(Box<NotionImportTask>),
   Custom(serde_json::Value),
 }
 
+impl ImportTask {
+  /// Returns the task of the imports whose file is uploaded to S3.
+  fn uploaded_task_mut(&mut self) -> Option<&mut NotionImportTask> {
+    match self {
+      ImportTask::Notion(task)
+      | ImportTask::Markdown(task)
+      | ImportTask::Html(task)
+      | ImportTask::Csv(task)
+      | ImportTask::Xlsx(task) => Some(task.as_mut()),
+      ImportTask::Custom(_) => None,
+    }
+  }
+}
+
 impl Display for ImportTask {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     match self {
@@ -1507,6 +2321,26 @@ impl Display for ImportTask {
         "NotionImportTask {{ workspace_id: {}, workspace_name: {} }}",
         task.workspace_id, task.workspace_name
       ),
+      ImportTask::Markdown(task) => write!(
+        f,
+        "MarkdownImportTask {{ workspace_id: {}, workspace_name: {} }}",
+        task.workspace_id, task.workspace_name
+      ),
+      ImportTask::Html(task) => write!(
+        f,
+        "HtmlImportTask {{ workspace_id: {}, workspace_name: {} }}",
+        task.workspace_id, task.workspace_name
+      ),
+      ImportTask::Csv(task) => write!(
+        f,
+        "CsvImportTask {{ workspace_id: {}, parent_view_id: {:?} }}",
+        task.workspace_id, task.parent_view_id
+      ),
+      ImportTask::Xlsx(task) => write!(
+        f,
+        "XlsxImportTask {{ workspace_id: {}, parent_view_id: {:?} }}",
+        task.workspace_id, task.parent_view_id
+      ),
       ImportTask::Custom(value) => write!(f, "CustomTask {{ {} }}", value),
     }
   }
@@ -1605,7 +2439,7 @@ async fn insert_meta_from_path(
   })
 }
 
-fn collab_key(workspace_id: &str, object_id: &str) -> String {
+pub(crate) fn collab_key(workspace_id: &str, object_id: &str) -> String {
   format!(
     "collabs/{}/{}/encoded_collab.v1.zstd",
     workspace_id, object_id
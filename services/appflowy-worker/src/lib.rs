@@ -1,6 +1,10 @@
+pub mod blob_gc_worker;
 pub mod error;
+pub mod export_worker;
 pub mod import_worker;
 pub mod indexer_worker;
 mod mailer;
 pub mod metric;
+pub mod publish_worker;
 pub mod s3_client;
+pub mod upload_session_worker;
@@ -1,8 +1,12 @@
 mod application;
+mod blob_gc_worker;
 mod config;
 pub mod error;
+mod export_worker;
 pub mod import_worker;
+mod publish_worker;
 pub(crate) mod s3_client;
+mod upload_session_worker;
 
 mod metric;
 
@@ -0,0 +1,563 @@
+use crate::error::WorkerError;
+use crate::import_worker::live_collab::apply_pending_updates;
+use crate::import_worker::worker::get_encode_collab_from_bytes;
+use crate::s3_client::{app_error_to_worker_error, S3Client};
+use anyhow::anyhow;
+use aws_sdk_s3::primitives::ByteStream;
+use collab::core::collab::DataSource;
+use collab::core::origin::CollabOrigin;
+use collab::preclude::{Collab, ReadTxn, StateVector};
+use collab_database::database::DatabaseBody;
+use collab_database::rows::RowId;
+use collab_database::workspace_database::{NoPersistenceDatabaseCollabService, WorkspaceDatabase};
+use collab_entity::CollabType;
+use collab_folder::{Folder, ViewLayout};
+use database::encryption::CollabEncryption;
+use database::pg_row::{AFAutoRepublishCollab, AFPublishSchedule};
+use database::publish::{
+  claim_auto_republish_collabs, claim_due_publish_schedule, insert_or_replace_publish_collabs,
+  set_published_collabs_as_unpublished, update_publish_schedule_result,
+  update_published_collab_access, update_published_collab_auto_republish,
+};
+use database::user::select_uuid_from_uid;
+use database::workspace::select_workspace_database_storage_id;
+use database_entity::dto::{PublishCollabItem, PublishCollabMetadata};
+use redis::aio::ConnectionManager;
+use serde_json::Value;
+use shared_entity::dto::publish_dto::{PublishDatabaseData, PublishViewInfo, PublishViewMetaData};
+use shared_entity::dto::workspace_dto::{
+  IconType, PublishPageParams, PublishScheduleAction, ViewIcon, ViewLayout as DtoViewLayout,
+};
+use sqlx::types::chrono::{Duration as ChronoDuration, Utc};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::{interval, MissedTickBehavior};
+use tracing::{error, info, trace};
+use uuid::Uuid;
+
+/// A schedule that has been processing for longer is considered abandoned, e.g. because the
+/// worker restarted, and is processed again.
+const PROCESSING_TIMEOUT_MINUTES: i64 = 10;
+
+/// Maximum number of published views checked for changes per tick.
+const AUTO_REPUBLISH_BATCH_SIZE: i64 = 50;
+
+pub struct PublishWorkerConfig {
+  pub enable: bool,
+  pub tick_interval_secs: u64,
+  /// How often the published views with auto republish enabled are checked for changes.
+  pub auto_republish_interval_secs: u64,
+}
+
+struct PublishContext {
+  pg_pool: PgPool,
+  redis_client: ConnectionManager,
+  s3_client: Arc<dyn S3Client>,
+  collab_encryption: Option<CollabEncryption>,
+}
+
+/// Executes the scheduled publishes and unpublishes once they are due, and republishes the
+/// published views with auto republish enabled when their content changed since they were last
+/// published.
+pub async fn run_publish_worker(
+  pg_pool: PgPool,
+  redis_client: ConnectionManager,
+  s3_client: Arc<dyn S3Client>,
+  collab_encryption: Option<CollabEncryption>,
+  config: PublishWorkerConfig,
+) {
+  if !config.enable {
+    info!("Publish worker is disabled");
+    return;
+  }
+
+  info!("Starting publish worker");
+  let mut context = PublishContext {
+    pg_pool,
+    redis_client,
+    s3_client,
+    collab_encryption,
+  };
+  let mut interval = interval(Duration::from_secs(config.tick_interval_secs));
+  interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+  loop {
+    interval.tick().await;
+    loop {
+      let stale_before = Utc::now() - ChronoDuration::minutes(PROCESSING_TIMEOUT_MINUTES);
+      match claim_due_publish_schedule(&context.pg_pool, stale_before).await {
+        Ok(Some(schedule)) => process_publish_schedule(&mut context, schedule).await,
+        Ok(None) => break,
+        Err(err) => {
+          error!("[Publish] failed to claim publish schedule: {:?}", err);
+          break;
+        },
+      }
+    }
+
+    let checked_before =
+      Utc::now() - ChronoDuration::seconds(config.auto_republish_interval_secs as i64);
+    match claim_auto_republish_collabs(&context.pg_pool, checked_before, AUTO_REPUBLISH_BATCH_SIZE)
+      .await
+    {
+      Ok(collabs) => {
+        for published in collabs {
+          let view_id = published.view_id;
+          if let Err(err) = republish_if_changed(&mut context, published).await {
+            error!("[Publish] failed to republish view {}: {:?}", view_id, err);
+          }
+        }
+      },
+      Err(err) => error!("[Publish] failed to claim auto republish views: {:?}", err),
+    }
+  }
+}
+
+async fn process_publish_schedule(context: &mut PublishContext, schedule: AFPublishSchedule) {
+  info!(
+    "[Publish] executing schedule {} of view {}",
+    schedule.schedule_id, schedule.view_id
+  );
+  let result = match PublishScheduleAction::from_i16(schedule.action) {
+    Some(PublishScheduleAction::Publish) => publish_scheduled_view(context, &schedule).await,
+    Some(PublishScheduleAction::Unpublish) => {
+      unpublish_view(context, &schedule.workspace_id, &schedule.view_id).await
+    },
+    None => Err(WorkerError::Internal(anyhow!(
+      "Invalid publish schedule action: {}",
+      schedule.action
+    ))),
+  };
+  let error = match result {
+    Ok(_) => None,
+    Err(err) => {
+      error!(
+        "[Publish] failed to execute schedule {}: {:?}",
+        schedule.schedule_id, err
+      );
+      Some(err.to_string())
+    },
+  };
+  if let Err(err) =
+    update_publish_schedule_result(&context.pg_pool, &schedule.schedule_id, error.as_deref()).await
+  {
+    error!(
+      "[Publish] failed to update schedule {}: {:?}",
+      schedule.schedule_id, err
+    );
+  }
+}
+
+async fn publish_scheduled_view(
+  context: &mut PublishContext,
+  schedule: &AFPublishSchedule,
+) -> Result<(), WorkerError> {
+  let params = serde_json::from_value::<PublishPageParams>(schedule.params.clone())
+    .map_err(|err| anyhow!("Invalid publish schedule params: {}", err))?;
+  let publisher_uuid = select_uuid_from_uid(&context.pg_pool, schedule.created_by)
+    .await
+    .map_err(app_error_to_worker_error)?;
+  let workspace_id = schedule.workspace_id.to_string();
+  let view_id = schedule.view_id.to_string();
+  let (metadata, data) = generate_publish_data(
+    context,
+    &workspace_id,
+    &view_id,
+    schedule.created_by,
+    params.visible_database_view_ids,
+  )
+  .await?;
+  let publish_name = params.publish_name.ok_or_else(|| {
+    anyhow!(
+      "Publish name of schedule {} is missing",
+      schedule.schedule_id
+    )
+  })?;
+  publish_view(
+    context,
+    &schedule.workspace_id,
+    &publisher_uuid,
+    PublishCollabItem {
+      meta: PublishCollabMetadata {
+        view_id: schedule.view_id,
+        publish_name,
+        metadata,
+      },
+      data,
+      comments_enabled: params.comments_enabled.unwrap_or(true),
+      duplicate_enabled: params.duplicate_enabled.unwrap_or(true),
+    },
+  )
+  .await?;
+  if let Some(auto_republish) = params.auto_republish {
+    update_published_collab_auto_republish(
+      &context.pg_pool,
+      &schedule.workspace_id,
+      &schedule.view_id,
+      auto_republish,
+    )
+    .await
+    .map_err(app_error_to_worker_error)?;
+  }
+  if let Some(members_only) = params.members_only {
+    update_published_collab_access(
+      &context.pg_pool,
+      &schedule.workspace_id,
+      &schedule.view_id,
+      None,
+      Some(members_only),
+    )
+    .await
+    .map_err(app_error_to_worker_error)?;
+  }
+  Ok(())
+}
+
+/// Compares the published content of the view with its current content, and publishes the view
+/// again under the same name and options if they differ.
+async fn republish_if_changed(
+  context: &mut PublishContext,
+  published: AFAutoRepublishCollab,
+) -> Result<(), WorkerError> {
+  let workspace_id = published.workspace_id.to_string();
+  let view_id = published.view_id.to_string();
+  let visible_database_view_ids = serde_json::from_slice::<PublishDatabaseData>(&published.blob)
+    .ok()
+    .map(|data| data.visible_database_view_ids);
+  let (metadata, data) = generate_publish_data(
+    context,
+    &workspace_id,
+    &view_id,
+    published.published_by,
+    visible_database_view_ids,
+  )
+  .await?;
+  if metadata == published.metadata && !publish_data_changed(&view_id, &published.blob, &data)? {
+    trace!("[Publish] published view {} is up to date", view_id);
+    return Ok(());
+  }
+
+  info!("[Publish] republishing changed view {}", view_id);
+  publish_view(
+    context,
+    &published.workspace_id,
+    &published.publisher_uuid,
+    PublishCollabItem {
+      meta: PublishCollabMetadata {
+        view_id: published.view_id,
+        publish_name: published.publish_name,
+        metadata,
+      },
+      data,
+      comments_enabled: published.comments_enabled,
+      duplicate_enabled: published.duplicate_enabled,
+    },
+  )
+  .await
+}
+
+/// Writes the published view to the bucket and to Postgres, so that it is served whether the
+/// server reads published views from the bucket or from Postgres.
+async fn publish_view(
+  context: &PublishContext,
+  workspace_id: &Uuid,
+  publisher_uuid: &Uuid,
+  item: PublishCollabItem<Value, Vec<u8>>,
+) -> Result<(), WorkerError> {
+  let object_key = published_collab_key(workspace_id, &item.meta.view_id);
+  context
+    .s3_client
+    .put_blob(&object_key, ByteStream::from(item.data.clone()), None)
+    .await?;
+  insert_or_replace_publish_collabs(&context.pg_pool, workspace_id, publisher_uuid, vec![item])
+    .await
+    .map_err(app_error_to_worker_error)
+}
+
+async fn unpublish_view(
+  context: &PublishContext,
+  workspace_id: &Uuid,
+  view_id: &Uuid,
+) -> Result<(), WorkerError> {
+  context
+    .s3_client
+    .delete_blob(&published_collab_key(workspace_id, view_id))
+    .await?;
+  set_published_collabs_as_unpublished(&context.pg_pool, workspace_id, &[*view_id])
+    .await
+    .map_err(app_error_to_worker_error)
+}
+
+/// Same key as the one used by the server to store published collabs in the bucket.
+fn published_collab_key(workspace_id: &Uuid, view_id: &Uuid) -> String {
+  format!("published-collab/{}/{}", workspace_id, view_id)
+}
+
+/// Generates the metadata and the data of the view in the same format as the publish API.
+async fn generate_publish_data(
+  context: &mut PublishContext,
+  workspace_id: &str,
+  view_id: &str,
+  uid: i64,
+  visible_database_view_ids: Option<Vec<String>>,
+) -> Result<(Value, Vec<u8>), WorkerError> {
+  let folder = open_folder(context, workspace_id, uid).await?;
+  let view = folder
+    .get_view(view_id)
+    .ok_or_else(|| WorkerError::RecordNotFound(format!("View {} not found", view_id)))?;
+  let metadata = PublishViewMetaData {
+    view: PublishViewInfo {
+      view_id: view_id.to_string(),
+      name: view.name.clone(),
+      icon: view.icon.clone().map(|icon| ViewIcon {
+        ty: match icon.ty {
+          collab_folder::IconType::Emoji => IconType::Emoji,
+          collab_folder::IconType::Url => IconType::Url,
+          collab_folder::IconType::Icon => IconType::Icon,
+        },
+        value: icon.value,
+      }),
+      layout: match view.layout {
+        ViewLayout::Document => DtoViewLayout::Document,
+        ViewLayout::Grid => DtoViewLayout::Grid,
+        ViewLayout::Board => DtoViewLayout::Board,
+        ViewLayout::Calendar => DtoViewLayout::Calendar,
+        ViewLayout::Chat => DtoViewLayout::Chat,
+      },
+      extra: view.extra.clone(),
+      created_by: view.created_by,
+      last_edited_by: view.last_edited_by,
+      last_edited_time: view.last_edited_time,
+      created_at: view.created_at,
+      child_views: None,
+    },
+    child_views: vec![],
+    ancestor_views: vec![],
+  };
+  let metadata = serde_json::to_value(metadata).map_err(|err| anyhow!(err))?;
+
+  let data = match view.layout {
+    ViewLayout::Document => {
+      let collab = open_collab(context, workspace_id, view_id, CollabType::Document).await?;
+      encode_doc_state(&collab)
+    },
+    ViewLayout::Grid | ViewLayout::Board | ViewLayout::Calendar => {
+      generate_publish_data_for_database(context, workspace_id, view_id, visible_database_view_ids)
+        .await?
+    },
+    ViewLayout::Chat => {
+      return Err(WorkerError::Internal(anyhow!(
+        "AI Chat cannot be published"
+      )))
+    },
+  };
+  Ok((metadata, data))
+}
+
+async fn generate_publish_data_for_database(
+  context: &mut PublishContext,
+  workspace_id: &str,
+  view_id: &str,
+  visible_database_view_ids: Option<Vec<String>>,
+) -> Result<Vec<u8>, WorkerError> {
+  let workspace_database = open_workspace_database(context, workspace_id).await?;
+  let database_id = workspace_database
+    .get_database_meta_with_view_id(view_id)
+    .ok_or_else(|| anyhow!("Database of view {} not found", view_id))?
+    .database_id;
+  let db_collab = open_collab(context, workspace_id, &database_id, CollabType::Database).await?;
+  let db_body = DatabaseBody::from_collab(
+    &db_collab,
+    Arc::new(NoPersistenceDatabaseCollabService),
+    None,
+  )
+  .ok_or_else(|| anyhow!("Failed to open database {}", database_id))?;
+  let row_ids: Vec<String> = {
+    let txn = db_collab.transact();
+    let inline_view_id = db_body.get_inline_view_id(&txn);
+    db_body
+      .views
+      .get_row_orders(&txn, &inline_view_id)
+      .iter()
+      .map(|row_order| row_order.id.to_string())
+      .collect()
+  };
+
+  let mut database_row_collabs = HashMap::with_capacity(row_ids.len());
+  let mut database_row_document_collabs = HashMap::new();
+  for row_id in row_ids {
+    if let Ok(collab) = open_collab(context, workspace_id, &row_id, CollabType::DatabaseRow).await {
+      database_row_collabs.insert(row_id.clone(), encode_doc_state(&collab));
+    }
+    if let Some(document_id) = db_body
+      .block
+      .get_row_document_id(&RowId::from(row_id))
+      .map(|document_id| document_id.to_string())
+    {
+      if let Ok(collab) =
+        open_collab(context, workspace_id, &document_id, CollabType::Document).await
+      {
+        database_row_document_collabs.insert(document_id, encode_doc_state(&collab));
+      }
+    }
+  }
+
+  let data = PublishDatabaseData {
+    database_collab: encode_doc_state(&db_collab),
+    database_row_collabs,
+    database_row_document_collabs,
+    visible_database_view_ids: visible_database_view_ids.unwrap_or(vec![view_id.to_string()]),
+    database_relations: HashMap::from([(database_id, view_id.to_string())]),
+  };
+  serde_json::to_vec(&data).map_err(|err| WorkerError::Internal(anyhow!(err)))
+}
+
+/// The same content can be encoded differently, so the published data is compared by the content
+/// of its collabs rather than byte by byte.
+fn publish_data_changed(view_id: &str, old: &[u8], new: &[u8]) -> Result<bool, WorkerError> {
+  match (
+    serde_json::from_slice::<PublishDatabaseData>(old),
+    serde_json::from_slice::<PublishDatabaseData>(new),
+  ) {
+    (Ok(old), Ok(new)) => {
+      if old.visible_database_view_ids != new.visible_database_view_ids
+        || old.database_relations != new.database_relations
+        || collab_content(view_id, &old.database_collab)?
+          != collab_content(view_id, &new.database_collab)?
+      {
+        return Ok(true);
+      }
+      Ok(
+        collabs_changed(&old.database_row_collabs, &new.database_row_collabs)?
+          || collabs_changed(
+            &old.database_row_document_collabs,
+            &new.database_row_document_collabs,
+          )?,
+      )
+    },
+    _ => Ok(collab_content(view_id, old)? != collab_content(view_id, new)?),
+  }
+}
+
+fn collabs_changed(
+  old: &HashMap<String, Vec<u8>>,
+  new: &HashMap<String, Vec<u8>>,
+) -> Result<bool, WorkerError> {
+  if old.len() != new.len() {
+    return Ok(true);
+  }
+  for (object_id, new_doc_state) in new {
+    match old.get(object_id) {
+      Some(old_doc_state) => {
+        if collab_content(object_id, old_doc_state)? != collab_content(object_id, new_doc_state)? {
+          return Ok(true);
+        }
+      },
+      None => return Ok(true),
+    }
+  }
+  Ok(false)
+}
+
+fn collab_content(object_id: &str, doc_state: &[u8]) -> Result<Value, WorkerError> {
+  let collab = Collab::new_with_source(
+    CollabOrigin::Server,
+    object_id,
+    DataSource::DocStateV1(doc_state.to_vec()),
+    vec![],
+    false,
+  )
+  .map_err(|err| anyhow!("Failed to open collab {}: {}", object_id, err))?;
+  Ok(collab.to_json_value())
+}
+
+fn encode_doc_state(collab: &Collab) -> Vec<u8> {
+  collab
+    .transact()
+    .encode_state_as_update_v1(&StateVector::default())
+}
+
+async fn open_folder(
+  context: &mut PublishContext,
+  workspace_id: &str,
+  uid: i64,
+) -> Result<Folder, WorkerError> {
+  let encoded_collab = get_encode_collab_from_bytes(
+    workspace_id,
+    workspace_id,
+    &CollabType::Folder,
+    &context.pg_pool,
+    &context.s3_client,
+    context.collab_encryption.as_ref(),
+  )
+  .await?;
+  let mut folder = Folder::from_collab_doc_state(
+    uid,
+    CollabOrigin::Server,
+    encoded_collab.into(),
+    workspace_id,
+    vec![],
+  )
+  .map_err(|err| WorkerError::Internal(anyhow!("Failed to open folder: {}", err)))?;
+  apply_pending_updates(
+    &mut context.redis_client,
+    workspace_id,
+    workspace_id,
+    &mut folder.collab,
+  )
+  .await?;
+  Ok(folder)
+}
+
+async fn open_collab(
+  context: &mut PublishContext,
+  workspace_id: &str,
+  object_id: &str,
+  collab_type: CollabType,
+) -> Result<Collab, WorkerError> {
+  let encoded_collab = get_encode_collab_from_bytes(
+    workspace_id,
+    object_id,
+    &collab_type,
+    &context.pg_pool,
+    &context.s3_client,
+    context.collab_encryption.as_ref(),
+  )
+  .await?;
+  let mut collab = Collab::new_with_source(
+    CollabOrigin::Server,
+    object_id,
+    encoded_collab.into(),
+    vec![],
+    false,
+  )
+  .map_err(|err| anyhow!("Failed to open collab {}: {}", object_id, err))?;
+  apply_pending_updates(
+    &mut context.redis_client,
+    workspace_id,
+    object_id,
+    &mut collab,
+  )
+  .await?;
+  Ok(collab)
+}
+
+async fn open_workspace_database(
+  context: &mut PublishContext,
+  workspace_id: &str,
+) -> Result<WorkspaceDatabase, WorkerError> {
+  let w_database_id = select_workspace_database_storage_id(&context.pg_pool, workspace_id)
+    .await
+    .map_err(|err| anyhow!("Failed to select workspace database storage id: {:?}", err))?
+    .to_string();
+  let collab = open_collab(
+    context,
+    workspace_id,
+    &w_database_id,
+    CollabType::WorkspaceDatabase,
+  )
+  .await?;
+  WorkspaceDatabase::open(collab)
+    .map_err(|err| WorkerError::Internal(anyhow!("Failed to open workspace database: {}", err)))
+}
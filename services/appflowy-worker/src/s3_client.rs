@@ -1,19 +1,26 @@
 use crate::error::WorkerError;
 use anyhow::{anyhow, Context};
+use app_error::AppError;
 use aws_sdk_s3::error::SdkError;
 use std::fs::Permissions;
+use std::io::ErrorKind;
 
 use anyhow::Result;
+use aws_sdk_s3::operation::abort_multipart_upload::AbortMultipartUploadError;
 use aws_sdk_s3::operation::get_object::GetObjectError;
 use aws_sdk_s3::operation::head_object::{HeadObjectError, HeadObjectOutput};
+use aws_sdk_s3::presigning::PresigningConfig;
 use aws_sdk_s3::primitives::ByteStream;
 use axum::async_trait;
 use base64::engine::general_purpose::STANDARD;
 use base64::Engine;
+use database::file::fs_client_impl::LocalFsBucketClientImpl;
+use database::file::BucketClient;
 use futures::AsyncReadExt;
 use std::ops::Deref;
 use std::os::unix::fs::PermissionsExt;
 use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
 use tokio::fs;
 use tokio::fs::OpenOptions;
 use tokio::io::AsyncWriteExt;
@@ -34,6 +41,14 @@ pub trait S3Client: Send + Sync {
 
   async fn is_blob_exist(&self, object_key: &str) -> Result<bool, WorkerError>;
   async fn get_blob_meta(&self, object_key: &str) -> Result<BlobMeta, WorkerError>;
+  /// Aborts a multipart upload created by the server, deleting the parts it received.
+  async fn abort_upload(&self, object_key: &str, upload_id: &str) -> Result<(), WorkerError>;
+  /// Generates a url that lets anyone holding it download the blob until it expires.
+  async fn gen_presigned_download_url(
+    &self,
+    object_key: &str,
+    expires_in_secs: u64,
+  ) -> Result<String, WorkerError>;
 }
 
 pub struct BlobMeta {
@@ -45,6 +60,8 @@ pub struct BlobMeta {
 pub struct S3ClientImpl {
   pub inner: aws_sdk_s3::Client,
   pub bucket: String,
+  pub endpoint: String,
+  pub presigned_url_endpoint: Option<String>,
 }
 
 impl S3ClientImpl {
@@ -198,6 +215,167 @@ impl S3Client for S3ClientImpl {
       content_type,
     })
   }
+
+  async fn abort_upload(&self, object_key: &str, upload_id: &str) -> Result<(), WorkerError> {
+    trace!("Aborting upload to S3: {} - {}", object_key, upload_id);
+    match self
+      .inner
+      .abort_multipart_upload()
+      .bucket(&self.bucket)
+      .key(object_key)
+      .upload_id(upload_id)
+      .send()
+      .await
+    {
+      Ok(_) => Ok(()),
+      Err(SdkError::ServiceError(service_err)) => match service_err.err() {
+        // The upload was already completed or aborted
+        AbortMultipartUploadError::NoSuchUpload(_) => Ok(()),
+        _ => Err(WorkerError::from(anyhow!(
+          "Failed to abort upload to S3: {:?}",
+          service_err
+        ))),
+      },
+      Err(err) => Err(WorkerError::from(anyhow!(
+        "Failed to abort upload to S3: {}",
+        err
+      ))),
+    }
+  }
+
+  async fn gen_presigned_download_url(
+    &self,
+    object_key: &str,
+    expires_in_secs: u64,
+  ) -> Result<String, WorkerError> {
+    let config = PresigningConfig::builder()
+      .start_time(SystemTime::now())
+      .expires_in(Duration::from_secs(expires_in_secs))
+      .build()
+      .map_err(|err| anyhow!("Invalid presigning config: {}", err))?;
+    let request = self
+      .inner
+      .get_object()
+      .bucket(&self.bucket)
+      .key(object_key)
+      .presigned(config)
+      .await
+      .map_err(|err| anyhow!("Failed to generate presigned url: {:?}", err))?;
+    let url = request.uri().to_string();
+    Ok(
+      self
+        .presigned_url_endpoint
+        .as_ref()
+        .map_or(url.clone(), |presigned| {
+          url.replace(&self.endpoint, presigned)
+        }),
+    )
+  }
+}
+
+/// Lets the worker read the blobs written by the server when both are configured to store blobs in
+/// the same local directory.
+#[async_trait]
+impl S3Client for LocalFsBucketClientImpl {
+  async fn get_blob_stream(&self, object_key: &str) -> Result<S3StreamResponse, WorkerError> {
+    let path = self
+      .object_path(object_key)
+      .map_err(app_error_to_worker_error)?;
+    let file = match fs::File::open(&path).await {
+      Ok(file) => file,
+      Err(err) if err.kind() == ErrorKind::NotFound => {
+        return Err(WorkerError::RecordNotFound(format!(
+          "blob not found for key:{object_key}"
+        )));
+      },
+      Err(err) => return Err(WorkerError::from(err)),
+    };
+    let content_length = file.metadata().await?.len() as i64;
+    let content_type = self
+      .get_content_type(object_key)
+      .await
+      .map_err(app_error_to_worker_error)?;
+    trace!(
+      "get object from local file system: {} ({} bytes)",
+      object_key,
+      content_length
+    );
+
+    Ok(S3StreamResponse {
+      stream: Box::new(futures::io::BufReader::new(file.compat())),
+      content_type,
+      content_length: Some(content_length),
+    })
+  }
+
+  async fn put_blob(
+    &self,
+    object_key: &str,
+    content: ByteStream,
+    content_type: Option<&str>,
+  ) -> Result<(), WorkerError> {
+    BucketClient::put_blob(self, object_key, content, content_type)
+      .await
+      .map_err(app_error_to_worker_error)
+  }
+
+  async fn delete_blob(&self, object_key: &str) -> Result<(), WorkerError> {
+    BucketClient::delete_blob(self, object_key)
+      .await
+      .map_err(app_error_to_worker_error)?;
+    Ok(())
+  }
+
+  async fn is_blob_exist(&self, object_key: &str) -> Result<bool, WorkerError> {
+    let path = self
+      .object_path(object_key)
+      .map_err(app_error_to_worker_error)?;
+    Ok(fs::try_exists(path).await?)
+  }
+
+  async fn get_blob_meta(&self, object_key: &str) -> Result<BlobMeta, WorkerError> {
+    let path = self
+      .object_path(object_key)
+      .map_err(app_error_to_worker_error)?;
+    let metadata = match fs::metadata(&path).await {
+      Ok(metadata) => metadata,
+      Err(err) if err.kind() == ErrorKind::NotFound => {
+        return Err(WorkerError::RecordNotFound("blob not found".to_string()));
+      },
+      Err(err) => return Err(WorkerError::from(err)),
+    };
+    let content_type = self
+      .get_content_type(object_key)
+      .await
+      .map_err(app_error_to_worker_error)?;
+    Ok(BlobMeta {
+      content_length: metadata.len() as i64,
+      content_type,
+    })
+  }
+
+  async fn abort_upload(&self, object_key: &str, upload_id: &str) -> Result<(), WorkerError> {
+    BucketClient::abort_upload(self, object_key, upload_id)
+      .await
+      .map_err(app_error_to_worker_error)
+  }
+
+  async fn gen_presigned_download_url(
+    &self,
+    _object_key: &str,
+    _expires_in_secs: u64,
+  ) -> Result<String, WorkerError> {
+    Err(WorkerError::Internal(anyhow!(
+      "Presigned urls are only available when blobs are stored in S3"
+    )))
+  }
+}
+
+pub(crate) fn app_error_to_worker_error(err: AppError) -> WorkerError {
+  match err {
+    AppError::RecordNotFound(msg) => WorkerError::RecordNotFound(msg),
+    err => WorkerError::Internal(anyhow!("Local file system blob storage error: {}", err)),
+  }
 }
 
 pub struct S3StreamResponse {
@@ -0,0 +1,91 @@
+use crate::error::WorkerError;
+use crate::s3_client::{app_error_to_worker_error, S3Client};
+use database::pg_row::AFUploadSessionRow;
+use database::resource_usage::{delete_upload_session, select_expired_upload_sessions};
+use sqlx::types::chrono::Utc;
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::{interval, MissedTickBehavior};
+use tracing::{error, info};
+
+/// Maximum number of upload sessions aborted per tick.
+const UPLOAD_SESSION_BATCH_SIZE: i64 = 100;
+
+pub struct UploadSessionCleanupConfig {
+  pub enable: bool,
+  pub tick_interval_secs: u64,
+}
+
+/// Periodically aborts the multipart uploads that expired, i.e. that didn't receive any part for
+/// a while, so that the parts of the uploads abandoned by the clients don't stay in the bucket.
+pub async fn run_upload_session_cleanup_worker(
+  pg_pool: PgPool,
+  s3_client: Arc<dyn S3Client>,
+  config: UploadSessionCleanupConfig,
+) {
+  if !config.enable {
+    info!("Upload session cleanup is disabled");
+    return;
+  }
+
+  info!("Starting upload session cleanup");
+  let mut interval = interval(Duration::from_secs(config.tick_interval_secs));
+  interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+  loop {
+    interval.tick().await;
+    loop {
+      let sessions =
+        match select_expired_upload_sessions(&pg_pool, Utc::now(), UPLOAD_SESSION_BATCH_SIZE).await
+        {
+          Ok(sessions) => sessions,
+          Err(err) => {
+            error!(
+              "[Upload Session] failed to select expired sessions: {:?}",
+              err
+            );
+            break;
+          },
+        };
+      let is_last_batch = (sessions.len() as i64) < UPLOAD_SESSION_BATCH_SIZE;
+      let mut aborted = 0;
+      for session in sessions {
+        match abort_upload_session(&pg_pool, &s3_client, &session).await {
+          Ok(_) => aborted += 1,
+          Err(err) => error!(
+            "[Upload Session] failed to abort upload {} of workspace {}: {:?}",
+            session.upload_id, session.workspace_id, err
+          ),
+        }
+      }
+      // Stop when nothing can be aborted, rather than retrying the failed sessions until the
+      // next tick
+      if is_last_batch || aborted == 0 {
+        break;
+      }
+    }
+  }
+}
+
+async fn abort_upload_session(
+  pg_pool: &PgPool,
+  s3_client: &Arc<dyn S3Client>,
+  session: &AFUploadSessionRow,
+) -> Result<(), WorkerError> {
+  // Same key as the one the server uploads the parts to
+  let object_key = format!(
+    "{}/{}/{}",
+    session.workspace_id, session.parent_dir, session.file_id
+  );
+  info!(
+    "[Upload Session] abort upload {} of {}, expired at {}",
+    session.upload_id, object_key, session.expires_at
+  );
+  s3_client
+    .abort_upload(&object_key, &session.upload_id)
+    .await?;
+  delete_upload_session(pg_pool, &session.upload_id)
+    .await
+    .map_err(app_error_to_worker_error)?;
+  Ok(())
+}
@@ -146,6 +146,7 @@ fn run_importer_worker(
       redis_client,
       None,
       Arc::new(MockS3Client),
+      None,
       notifier,
       &stream_name,
       tick_interval_secs,
@@ -228,6 +229,18 @@ impl S3Client for MockS3Client {
   async fn get_blob_meta(&self, _object_key: &str) -> Result<BlobMeta, WorkerError> {
     todo!()
   }
+
+  async fn abort_upload(&self, _object_key: &str, _upload_id: &str) -> Result<(), WorkerError> {
+    Ok(())
+  }
+
+  async fn gen_presigned_download_url(
+    &self,
+    object_key: &str,
+    _expires_in_secs: u64,
+  ) -> Result<String, WorkerError> {
+    Ok(format!("https://mock-s3.appflowy.test/{}", object_key))
+  }
 }
 
 pub fn setup_log() {
@@ -0,0 +1,27 @@
+use actix_web::{
+  web::{self, Data, Json},
+  Result, Scope,
+};
+use app_error::AppError;
+use authentication::jwt::Authorization;
+use database_entity::dto::{ExplainPolicyDecisionQueryParams, PolicyDecisionExplanation};
+use shared_entity::response::{AppResponse, JsonAppResponse};
+
+use crate::{biz::access_control::ops::explain_policy_decision, state::AppState};
+
+pub fn access_control_scope() -> Scope {
+  web::scope("/api/access-control")
+    .service(web::resource("/explain").route(web::get().to(explain_policy_decision_handler)))
+}
+
+async fn explain_policy_decision_handler(
+  auth: Authorization,
+  state: Data<AppState>,
+  query: web::Query<ExplainPolicyDecisionQueryParams>,
+) -> Result<JsonAppResponse<PolicyDecisionExplanation>> {
+  if !auth.is_admin() {
+    return Err(AppError::NotEnoughPermissions.into());
+  }
+  let explanation = explain_policy_decision(&state.access_control, query.into_inner()).await?;
+  Ok(Json(AppResponse::Ok().with_data(explanation)))
+}
@@ -6,7 +6,8 @@ use anyhow::anyhow;
 use app_error::AppError;
 use authentication::jwt::UserUuid;
 use database_entity::dto::{
-  AccessRequestMinimal, ApproveAccessRequestParams, CreateAccessRequestParams,
+  AccessRequestAutoApprovalRule, AccessRequestMinimal, ApproveAccessRequestParams,
+  CreateAccessRequestParams,
 };
 use shared_entity::{
   dto::access_request_dto::AccessRequest,
@@ -17,6 +18,8 @@ use uuid::Uuid;
 use crate::{
   biz::access_request::ops::{
     approve_or_reject_access_request, create_access_request, get_access_request,
+    get_access_request_auto_approval_rules, remove_access_request_auto_approval_rule,
+    upsert_access_request_auto_approval_rule,
   },
   state::AppState,
 };
@@ -29,6 +32,15 @@ pub fn access_request_scope() -> Scope {
       web::resource("/{request_id}/approve")
         .route(web::post().to(post_approve_access_request_handler)),
     )
+    .service(
+      web::resource("/workspace/{workspace_id}/auto-approval-rule")
+        .route(web::get().to(list_auto_approval_rule_handler))
+        .route(web::put().to(put_auto_approval_rule_handler)),
+    )
+    .service(
+      web::resource("/workspace/{workspace_id}/auto-approval-rule/{email_domain}")
+        .route(web::delete().to(delete_auto_approval_rule_handler)),
+    )
 }
 
 async fn get_access_request_handler(
@@ -41,6 +53,7 @@ async fn get_access_request_handler(
   let access_request = get_access_request(
     &state.pg_pool,
     &state.collab_access_control_storage,
+    state.config.access_request.expire_days,
     access_request_id,
     uid,
   )
@@ -65,8 +78,10 @@ async fn post_access_request_handler(
     )))?;
   let request_id = create_access_request(
     &state.pg_pool,
+    state.workspace_access_control.clone(),
     state.mailer.clone(),
     &appflowy_web_url,
+    state.config.access_request.expire_days,
     workspace_id,
     view_id,
     uid,
@@ -102,6 +117,7 @@ async fn post_approve_access_request_handler(
     state.workspace_access_control.clone(),
     state.mailer.clone(),
     &appflowy_web_url,
+    state.config.access_request.expire_days,
     access_request_id,
     uid,
     is_approved,
@@ -109,3 +125,55 @@ async fn post_approve_access_request_handler(
   .await?;
   Ok(Json(AppResponse::Ok()))
 }
+
+async fn list_auto_approval_rule_handler(
+  uuid: UserUuid,
+  workspace_id: web::Path<Uuid>,
+  state: Data<AppState>,
+) -> Result<JsonAppResponse<Vec<AccessRequestAutoApprovalRule>>> {
+  let uid = state.user_cache.get_user_uid(&uuid).await?;
+  let rules = get_access_request_auto_approval_rules(
+    &state.pg_pool,
+    state.workspace_access_control.clone(),
+    workspace_id.into_inner(),
+    uid,
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok().with_data(rules)))
+}
+
+async fn put_auto_approval_rule_handler(
+  uuid: UserUuid,
+  workspace_id: web::Path<Uuid>,
+  rule: Json<AccessRequestAutoApprovalRule>,
+  state: Data<AppState>,
+) -> Result<JsonAppResponse<()>> {
+  let uid = state.user_cache.get_user_uid(&uuid).await?;
+  upsert_access_request_auto_approval_rule(
+    &state.pg_pool,
+    state.workspace_access_control.clone(),
+    workspace_id.into_inner(),
+    uid,
+    rule.into_inner(),
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok()))
+}
+
+async fn delete_auto_approval_rule_handler(
+  uuid: UserUuid,
+  path: web::Path<(Uuid, String)>,
+  state: Data<AppState>,
+) -> Result<JsonAppResponse<()>> {
+  let (workspace_id, email_domain) = path.into_inner();
+  let uid = state.user_cache.get_user_uid(&uuid).await?;
+  remove_access_request_auto_approval_rule(
+    &state.pg_pool,
+    state.workspace_access_control.clone(),
+    workspace_id,
+    uid,
+    &email_domain,
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok()))
+}
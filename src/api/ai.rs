@@ -1,4 +1,5 @@
 use crate::api::util::ai_model_from_header;
+use crate::biz::workspace::ops::ensure_not_end_to_end_encrypted;
 use crate::state::AppState;
 
 use actix_web::web::{Data, Json};
@@ -18,6 +19,7 @@ use shared_entity::dto::ai_dto::{
 use shared_entity::response::AppResponse;
 
 use tracing::{error, instrument, trace};
+use uuid::Uuid;
 
 pub fn ai_completion_scope() -> Scope {
   web::scope("/api/ai/{workspace_id}")
@@ -32,10 +34,12 @@ pub fn ai_completion_scope() -> Scope {
 }
 
 async fn stream_complete_text_handler(
+  path: web::Path<Uuid>,
   state: Data<AppState>,
   payload: Json<CompleteTextParams>,
   req: HttpRequest,
 ) -> actix_web::Result<HttpResponse> {
+  ensure_not_end_to_end_encrypted(&state.pg_pool, &path.into_inner()).await?;
   let ai_model = ai_model_from_header(&req);
   let params = payload.into_inner();
   state.metrics.ai_metrics.record_total_completion_count(1);
@@ -62,10 +66,12 @@ async fn stream_complete_text_handler(
 
 #[instrument(level = "debug", skip(state, payload), err)]
 async fn summarize_row_handler(
+  path: web::Path<Uuid>,
   state: Data<AppState>,
   payload: Json<SummarizeRowParams>,
   req: HttpRequest,
 ) -> actix_web::Result<Json<AppResponse<SummarizeRowResponse>>> {
+  ensure_not_end_to_end_encrypted(&state.pg_pool, &path.into_inner()).await?;
   let params = payload.into_inner();
   match params.data {
     SummarizeRowData::Identity { .. } => {
@@ -102,10 +108,12 @@ async fn summarize_row_handler(
 
 #[instrument(level = "debug", skip(state, payload), err)]
 async fn translate_row_handler(
+  path: web::Path<Uuid>,
   state: web::Data<AppState>,
   payload: web::Json<TranslateRowParams>,
   req: HttpRequest,
 ) -> actix_web::Result<Json<AppResponse<TranslateRowResponse>>> {
+  ensure_not_end_to_end_encrypted(&state.pg_pool, &path.into_inner()).await?;
   let params = payload.into_inner();
   let ai_model = ai_model_from_header(&req);
   state.metrics.ai_metrics.record_total_translate_row_count(1);
@@ -2,6 +2,7 @@ use crate::biz::chat::ops::{
   create_chat, create_chat_message, delete_chat, generate_chat_message_answer, get_chat_messages,
   get_question_message, update_chat_message,
 };
+use crate::biz::workspace::ops::ensure_not_end_to_end_encrypted;
 use crate::state::AppState;
 use actix_web::web::{Data, Json};
 use actix_web::{web, HttpRequest, HttpResponse, Scope};
@@ -113,6 +114,8 @@ async fn create_chat_handler(
 ) -> actix_web::Result<JsonAppResponse<()>> {
   let workspace_id = path.into_inner();
   let params = payload.into_inner();
+  let workspace_uuid = Uuid::parse_str(&workspace_id).map_err(AppError::from)?;
+  ensure_not_end_to_end_encrypted(&state.pg_pool, &workspace_uuid).await?;
   create_chat(&state.pg_pool, params, &workspace_id).await?;
   Ok(AppResponse::Ok().into())
 }
@@ -0,0 +1,160 @@
+use crate::biz::workspace::ops::ensure_not_end_to_end_encrypted;
+use crate::state::AppState;
+use access_control::act::Action;
+use actix_web::web::Data;
+use actix_web::{web, Scope};
+use app_error::AppError;
+use authentication::jwt::UserUuid;
+use database::pg_row::AFExportTask;
+use database::user::select_name_and_email_from_uuid;
+use database::workspace::{
+  insert_export_task, select_export_task, select_export_tasks_for_user,
+  select_unfinished_export_task_count, select_workspace_name_from_workspace_id, ExportTaskState,
+};
+use infra::env_util::get_env_var;
+use serde_json::json;
+use shared_entity::dto::export_dto::{CreateExportTaskResponse, ExportTaskDetail, UserExportTask};
+use shared_entity::response::{AppResponse, JsonAppResponse};
+use tracing::{info, instrument, warn};
+use uuid::Uuid;
+
+/// How long the download links returned by the task detail stay valid.
+const DOWNLOAD_URL_EXPIRES_IN_SECS: u64 = 3600;
+
+pub fn data_export_scope() -> Scope {
+  web::scope("/api/export")
+    .service(web::resource("").route(web::get().to(list_export_tasks_handler)))
+    .service(web::resource("/task/{task_id}").route(web::get().to(get_export_task_handler)))
+    .service(web::resource("/{workspace_id}").route(web::post().to(create_export_handler)))
+}
+
+/// Queues the export of the workspace. The worker emails a link to the archive once it's ready.
+#[instrument(level = "debug", skip_all)]
+async fn create_export_handler(
+  user_uuid: UserUuid,
+  state: Data<AppState>,
+  workspace_id: web::Path<Uuid>,
+) -> actix_web::Result<JsonAppResponse<CreateExportTaskResponse>> {
+  let workspace_id = workspace_id.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id.to_string(), Action::Read)
+    .await?;
+  // The worker can't read the collabs of a workspace that are encrypted by the clients
+  ensure_not_end_to_end_encrypted(&state.pg_pool, &workspace_id).await?;
+  check_maximum_export_task(&state, uid).await?;
+
+  let (user_name, user_email) = select_name_and_email_from_uuid(&state.pg_pool, &user_uuid).await?;
+  let workspace_name = select_workspace_name_from_workspace_id(&state.pg_pool, &workspace_id)
+    .await?
+    .unwrap_or_default();
+  let task_id = Uuid::new_v4();
+  info!(
+    "User:{} export workspace:{}, task:{}",
+    uid, workspace_id, task_id
+  );
+  insert_export_task(
+    &state.pg_pool,
+    &task_id,
+    &workspace_id,
+    uid,
+    json!({
+      "user_name": user_name,
+      "user_email": user_email,
+      "workspace_name": workspace_name,
+    }),
+  )
+  .await?;
+
+  Ok(
+    AppResponse::Ok()
+      .with_data(CreateExportTaskResponse {
+        task_id: task_id.to_string(),
+      })
+      .into(),
+  )
+}
+
+async fn list_export_tasks_handler(
+  user_uuid: UserUuid,
+  state: Data<AppState>,
+) -> actix_web::Result<JsonAppResponse<UserExportTask>> {
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  let tasks = select_export_tasks_for_user(&state.pg_pool, uid)
+    .await?
+    .into_iter()
+    .map(|task| export_task_detail(task, None))
+    .collect();
+  Ok(AppResponse::Ok().with_data(UserExportTask { tasks }).into())
+}
+
+/// Returns the export task, with a fresh download link if the archive is ready.
+async fn get_export_task_handler(
+  user_uuid: UserUuid,
+  state: Data<AppState>,
+  task_id: web::Path<Uuid>,
+) -> actix_web::Result<JsonAppResponse<ExportTaskDetail>> {
+  let task_id = task_id.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  let task = select_export_task(&state.pg_pool, &task_id).await?;
+  if task.created_by != uid {
+    return Err(AppError::RecordNotFound(format!("export task {} not found", task_id)).into());
+  }
+
+  let download_url = match (ExportTaskState::from(task.status), task.s3_key.as_ref()) {
+    (ExportTaskState::Completed, Some(s3_key)) => {
+      match state
+        .bucket_client
+        .gen_presigned_download_url(s3_key, DOWNLOAD_URL_EXPIRES_IN_SECS)
+        .await
+      {
+        Ok(url) => Some(url),
+        Err(err) => {
+          warn!(
+            "Failed to generate download url of export task {}: {}",
+            task_id, err
+          );
+          None
+        },
+      }
+    },
+    _ => None,
+  };
+  Ok(
+    AppResponse::Ok()
+      .with_data(export_task_detail(task, download_url))
+      .into(),
+  )
+}
+
+fn export_task_detail(task: AFExportTask, download_url: Option<String>) -> ExportTaskDetail {
+  ExportTaskDetail {
+    task_id: task.task_id.to_string(),
+    workspace_id: task.workspace_id.to_string(),
+    status: task.status,
+    created_at: task.created_at.timestamp(),
+    file_size: task.file_size.map(|size| size as u64),
+    download_url,
+    error: task
+      .metadata
+      .get("error")
+      .and_then(|error| error.as_str())
+      .map(|error| error.to_string()),
+  }
+}
+
+async fn check_maximum_export_task(state: &Data<AppState>, uid: i64) -> Result<(), AppError> {
+  let count = select_unfinished_export_task_count(&state.pg_pool, uid).await?;
+  let maximum_pending_task = get_env_var("MAXIMUM_EXPORT_PENDING_TASK", "1")
+    .parse::<i64>()
+    .unwrap_or(1);
+
+  if count >= maximum_pending_task {
+    return Err(AppError::TooManyExportTask(format!(
+      "{} exports are in progress. Please wait until they are completed",
+      count
+    )));
+  }
+  Ok(())
+}
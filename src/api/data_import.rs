@@ -1,4 +1,5 @@
 use crate::state::AppState;
+use access_control::act::Action;
 use actix_multipart::Multipart;
 use actix_web::web::{Data, Json};
 use actix_web::{web, HttpRequest, Scope};
@@ -8,16 +9,24 @@ use authentication::jwt::UserUuid;
 use aws_sdk_s3::primitives::ByteStream;
 use database::file::BucketClient;
 
-use crate::biz::workspace::ops::{create_empty_workspace, create_upload_task, num_pending_task};
+use crate::biz::workspace::ops::{
+  create_empty_workspace, create_upload_task, ensure_not_end_to_end_encrypted, num_pending_task,
+};
 use base64::engine::general_purpose::STANDARD;
 use base64::Engine;
 use database::user::select_name_and_email_from_uuid;
-use database::workspace::select_import_task_by_state;
-use database_entity::dto::{CreateImportTask, CreateImportTaskResponse};
+use database::workspace::{
+  cancel_import_task, select_import_task, select_import_task_by_state,
+  select_workspace_name_from_workspace_id,
+};
+use database_entity::dto::{
+  CreateDatabaseImportTask, CreateImportTask, CreateImportTaskResponse, CreateWorkspaceImportTask,
+  ImportPhase,
+};
 use futures_util::StreamExt;
 use infra::env_util::get_env_var;
 use serde_json::json;
-use shared_entity::dto::import_dto::{ImportTaskDetail, UserImportTask};
+use shared_entity::dto::import_dto::{ImportTaskDetail, ImportTaskProgress, UserImportTask};
 use shared_entity::response::{AppResponse, JsonAppResponse};
 use std::env::temp_dir;
 use std::path::PathBuf;
@@ -35,6 +44,14 @@ pub fn data_import_scope() -> Scope {
         .route(web::get().to(get_import_detail_handler)),
     )
     .service(web::resource("/create").route(web::post().to(create_import_handler)))
+    .service(web::resource("/{task_id}/cancel").route(web::post().to(cancel_import_handler)))
+    .service(
+      web::resource("/{workspace_id}/database")
+        .route(web::post().to(create_database_import_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/pages").route(web::post().to(create_workspace_import_handler)),
+    )
 }
 
 #[instrument(level = "debug", skip_all)]
@@ -77,7 +94,7 @@ async fn create_import_handler(
   let timestamp = chrono::Utc::now().timestamp();
   let task_id = Uuid::new_v4();
   let task = json!({
-      "notion": {
+      params.source.task_name(): {
          "uid": uid,
          "user_name": user_name,
          "user_email": user_email,
@@ -112,6 +129,137 @@ async fn create_import_handler(
   Ok(AppResponse::Ok().with_data(data).into())
 }
 
+#[instrument(level = "debug", skip_all)]
+async fn create_database_import_handler(
+  user_uuid: UserUuid,
+  state: Data<AppState>,
+  workspace_id: web::Path<Uuid>,
+  payload: Json<CreateDatabaseImportTask>,
+  req: HttpRequest,
+) -> actix_web::Result<JsonAppResponse<CreateImportTaskResponse>> {
+  let params = payload.into_inner();
+  params.validate().map_err(AppError::from)?;
+  let data = create_import_into_workspace_task(
+    &user_uuid,
+    &state,
+    workspace_id.into_inner(),
+    &req,
+    params.format.task_name(),
+    &params.parent_view_id,
+    &params.name,
+    params.content_length,
+  )
+  .await?;
+  Ok(AppResponse::Ok().with_data(data).into())
+}
+
+/// Imports a zip file into an existing workspace, under the given parent view, instead of creating
+/// a new workspace for it.
+#[instrument(level = "debug", skip_all)]
+async fn create_workspace_import_handler(
+  user_uuid: UserUuid,
+  state: Data<AppState>,
+  workspace_id: web::Path<Uuid>,
+  payload: Json<CreateWorkspaceImportTask>,
+  req: HttpRequest,
+) -> actix_web::Result<JsonAppResponse<CreateImportTaskResponse>> {
+  let params = payload.into_inner();
+  params.validate().map_err(AppError::from)?;
+  let data = create_import_into_workspace_task(
+    &user_uuid,
+    &state,
+    workspace_id.into_inner(),
+    &req,
+    params.source.task_name(),
+    &params.parent_view_id,
+    &params.name,
+    params.content_length,
+  )
+  .await?;
+  Ok(AppResponse::Ok().with_data(data).into())
+}
+
+/// Creates the upload task of a file that the worker imports into an existing workspace, as a new
+/// view named `view_name` under `parent_view_id`. `task_name` selects the worker's import task.
+#[allow(clippy::too_many_arguments)]
+async fn create_import_into_workspace_task(
+  user_uuid: &UserUuid,
+  state: &Data<AppState>,
+  workspace_id: Uuid,
+  req: &HttpRequest,
+  task_name: &str,
+  parent_view_id: &str,
+  view_name: &str,
+  content_length: u64,
+) -> Result<CreateImportTaskResponse, AppError> {
+  let uid = state.user_cache.get_user_uid(user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id.to_string(), Action::Write)
+    .await?;
+  // The worker can't write the imported views into a workspace whose collabs are encrypted by the
+  // clients
+  ensure_not_end_to_end_encrypted(&state.pg_pool, &workspace_id).await?;
+  check_maximum_task(state, uid).await?;
+  let s3_key = format!("import_presigned_url_{}", Uuid::new_v4());
+
+  // Generate presigned url with 10 minutes expiration
+  let presigned_url = state
+    .bucket_client
+    .gen_presigned_url(&s3_key, content_length, 600)
+    .await?;
+  trace!("[Import] Presigned url: {}", presigned_url);
+
+  let (user_name, user_email) = select_name_and_email_from_uuid(&state.pg_pool, user_uuid).await?;
+  let workspace_name = select_workspace_name_from_workspace_id(&state.pg_pool, &workspace_id)
+    .await?
+    .unwrap_or_default();
+  let host = get_host_from_request(req);
+  let workspace_id = workspace_id.to_string();
+  info!(
+    "User:{} import {} file into workspace:{}, name:{}",
+    uid, task_name, workspace_id, view_name,
+  );
+  let timestamp = chrono::Utc::now().timestamp();
+  let task_id = Uuid::new_v4();
+  let task = json!({
+      task_name: {
+         "uid": uid,
+         "user_name": user_name,
+         "user_email": user_email,
+         "task_id": task_id.to_string(),
+         "workspace_id": workspace_id,
+         "file_size": content_length,
+         "created_at": timestamp,
+         "s3_key": s3_key,
+         "host": host,
+         "workspace_name": workspace_name,
+         "parent_view_id": parent_view_id,
+         "view_name": view_name,
+      }
+  });
+
+  let data = CreateImportTaskResponse {
+    task_id: task_id.to_string(),
+    presigned_url: presigned_url.clone(),
+  };
+
+  create_upload_task(
+    uid,
+    task_id,
+    task,
+    &host,
+    &workspace_id,
+    0,
+    Some(presigned_url),
+    &state.redis_connection_manager,
+    &state.pg_pool,
+  )
+  .await?;
+
+  Ok(data)
+}
+
 async fn get_import_detail_handler(
   user_uuid: UserUuid,
   state: Data<AppState>,
@@ -127,6 +275,19 @@ async fn get_import_detail_handler(
           file_size: task.file_size as u64,
           created_at: task.created_at.timestamp(),
           status: task.status,
+          row_errors: task
+            .metadata
+            .get("row_errors")
+            .and_then(|row_errors| serde_json::from_value(row_errors.clone()).ok())
+            .unwrap_or_default(),
+          progress: task
+            .phase
+            .and_then(ImportPhase::from_i16)
+            .map(|phase| ImportTaskProgress {
+              phase,
+              processed_count: task.processed_count.max(0) as u64,
+              total_count: task.total_count.max(0) as u64,
+            }),
         })
         .collect::<Vec<_>>()
     })?;
@@ -141,6 +302,33 @@ async fn get_import_detail_handler(
   )
 }
 
+/// Cancels a pending import. The worker stops the import at its next step, then deletes the
+/// workspace being imported and the uploaded file.
+async fn cancel_import_handler(
+  user_uuid: UserUuid,
+  state: Data<AppState>,
+  task_id: web::Path<Uuid>,
+) -> actix_web::Result<JsonAppResponse<()>> {
+  let task_id = task_id.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  if !cancel_import_task(&state.pg_pool, &task_id, uid).await? {
+    let task = select_import_task(&state.pg_pool, &task_id)
+      .await
+      .ok()
+      .filter(|task| task.created_by == uid)
+      .ok_or_else(|| AppError::RecordNotFound(format!("import task {} not found", task_id)))?;
+    return Err(
+      AppError::InvalidRequest(format!(
+        "import task {} is no longer pending, status: {}",
+        task_id, task.status
+      ))
+      .into(),
+    );
+  }
+  info!("User:{} cancel import task:{}", uid, task_id);
+  Ok(AppResponse::Ok().into())
+}
+
 async fn import_data_handler(
   user_uuid: UserUuid,
   state: Data<AppState>,
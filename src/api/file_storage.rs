@@ -1,8 +1,8 @@
 use access_control::act::Action;
-use actix_http::body::BoxBody;
+use actix_http::body::{BoxBody, SizedStream};
 use actix_web::http::header::{
-  ContentLength, ContentType, CACHE_CONTROL, CONTENT_LENGTH, CONTENT_TYPE, ETAG, IF_MODIFIED_SINCE,
-  LAST_MODIFIED,
+  ContentLength, ContentType, Range, ACCEPT_RANGES, CACHE_CONTROL, CONTENT_LENGTH, CONTENT_RANGE,
+  CONTENT_TYPE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, IF_RANGE, LAST_MODIFIED, RANGE,
 };
 use actix_web::web::{Json, Payload};
 use actix_web::{
@@ -11,30 +11,50 @@ use actix_web::{
 };
 use actix_web::{HttpResponse, Result};
 use app_error::AppError;
-use authentication::jwt::UserUuid;
+use authentication::jwt::{Authorization, OptionalUserUuid, UserUuid};
 use chrono::DateTime;
-use database::file::BlobKey;
-use database::resource_usage::{get_all_workspace_blob_metadata, get_workspace_usage_size};
+use database::file::{BlobKey, BlobRange, BLOB_CONTENT_DIR};
+use database::resource_usage::{
+  delete_upload_session, get_all_workspace_blob_metadata, get_workspace_usage_size,
+  select_orphaned_blob_metadata,
+};
 use database_entity::file_dto::{
-  CompleteUploadRequest, CreateUploadRequest, CreateUploadResponse, UploadPartData,
-  UploadPartResponse,
+  CompleteUploadRequest, CreateUploadRequest, CreateUploadResponse, ListUploadPartsResponse,
+  UploadPartData, UploadPartResponse,
 };
 
 use crate::biz::data_import::LimitedPayload;
+use crate::biz::file_storage::image_variant::{
+  get_or_create_image_variant, image_variant_width, is_resizable_image,
+  MAX_IMAGE_VARIANT_SOURCE_SIZE,
+};
+use crate::biz::file_storage::malware_scan::schedule_blob_scan;
+use crate::biz::file_storage::quota::{
+  get_workspace_storage_quota, update_workspace_storage_quota,
+};
+use crate::biz::file_storage::upload_session::{
+  create_upload_session, get_upload_session, touch_upload_session,
+};
+use crate::biz::workspace::audit_log::record_audit_log;
 use crate::state::AppState;
 use anyhow::anyhow;
 use aws_sdk_s3::primitives::ByteStream;
 use collab_importer::util::FileId;
-use database::pg_row::AFBlobStatus;
+use database::pg_row::{AFBlobMetadataRow, AFBlobStatus};
+use database_entity::dto::{AFRole, AuditAction};
 use serde::Deserialize;
+use serde_json::json;
 use shared_entity::dto::file_dto::PutFileResponse;
-use shared_entity::dto::workspace_dto::{BlobMetadata, RepeatedBlobMetaData, WorkspaceSpaceUsage};
+use shared_entity::dto::workspace_dto::{
+  BlobMetadata, BlobStatus, OrphanedBlob, OrphanedBlobsReport, RepeatedBlobMetaData,
+  UpdateWorkspaceStorageQuotaParams, WorkspaceSpaceUsage, WorkspaceStorageQuota,
+};
 use shared_entity::response::{AppResponse, AppResponseError, JsonAppResponse};
 use sqlx::types::Uuid;
 use std::pin::Pin;
 use tokio::io::{AsyncRead, AsyncReadExt};
 use tokio_stream::StreamExt;
-use tokio_util::io::StreamReader;
+use tokio_util::io::{ReaderStream, StreamReader};
 use tracing::{error, event, instrument, trace};
 
 pub fn file_storage_scope() -> Scope {
@@ -53,10 +73,19 @@ pub fn file_storage_scope() -> Scope {
     .service(
       web::resource("/{workspace_id}/usage").route(web::get().to(get_workspace_usage_handler)),
     )
+    .service(
+      web::resource("/{workspace_id}/quota")
+        .route(web::get().to(get_workspace_storage_quota_handler))
+        .route(web::put().to(update_workspace_storage_quota_handler)),
+    )
     .service(
       web::resource("/{workspace_id}/blobs")
         .route(web::get().to(get_all_workspace_blob_metadata_handler)),
     )
+    .service(
+      web::resource("/{workspace_id}/orphaned_blobs")
+        .route(web::get().to(get_orphaned_blobs_handler)),
+    )
     .service(web::resource("/{workspace_id}/create_upload").route(web::post().to(create_upload)))
     .service(
       web::resource("/{workspace_id}/upload_part/{parent_dir}/{file_id}/{upload_id}/{part_num}")
@@ -66,6 +95,14 @@ pub fn file_storage_scope() -> Scope {
       web::resource("/{workspace_id}/complete_upload")
         .route(web::put().to(complete_upload_handler)),
     )
+    .service(
+      web::resource("/{workspace_id}/upload_parts/{parent_dir}/{file_id}/{upload_id}")
+        .route(web::get().to(list_upload_parts_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/upload/{parent_dir}/{file_id}/{upload_id}")
+        .route(web::delete().to(abort_upload_handler)),
+    )
     .service(
       web::resource("/{workspace_id}/v1/blob/{parent_dir}/{file_id}")
         .route(web::get().to(get_blob_v1_handler))
@@ -97,6 +134,12 @@ async fn create_upload(
     return Err(AppError::InvalidRequest("parent_dir is empty".to_string()).into());
   }
 
+  if req.parent_dir == BLOB_CONTENT_DIR {
+    return Err(
+      AppError::InvalidRequest(format!("parent_dir {} is reserved", BLOB_CONTENT_DIR)).into(),
+    );
+  }
+
   if req.file_id.is_empty() {
     return Err(AppError::InvalidRequest("file_id is empty".to_string()).into());
   }
@@ -107,6 +150,12 @@ async fn create_upload(
     .enforce_action(&uid, &workspace_id.to_string(), Action::Write)
     .await?;
 
+  // The size of the file is checked again once the upload completes, as it is optional here. An
+  // upload of unknown size only requires the workspace not to be full.
+  get_workspace_storage_quota(&state.pg_pool, &state.config.storage_quota, &workspace_id)
+    .await?
+    .check_upload(req.file_size.unwrap_or(1))?;
+
   let key = BlobPathV1 {
     workspace_id,
     parent_dir: req.parent_dir.clone(),
@@ -114,9 +163,19 @@ async fn create_upload(
   };
   let resp = state
     .bucket_storage
-    .create_upload(key, req)
+    .create_upload(key.clone(), req)
     .await
     .map_err(AppResponseError::from)?;
+  create_upload_session(
+    &state.pg_pool,
+    &state.config.blob_storage,
+    &workspace_id,
+    &key.parent_dir,
+    &key.file_id,
+    &resp.upload_id,
+    uid,
+  )
+  .await?;
 
   Ok(AppResponse::Ok().with_data(resp).into())
 }
@@ -153,6 +212,14 @@ async fn upload_part_handler(
     .workspace_access_control
     .enforce_action(&uid, &workspace_id.to_string(), Action::Write)
     .await?;
+  get_upload_session(
+    &state.pg_pool,
+    &workspace_id,
+    &path_params.parent_dir,
+    &path_params.file_id,
+    &path_params.upload_id,
+  )
+  .await?;
 
   let content_length = content_length.into_inner().into_inner();
   let mut content = Vec::with_capacity(content_length);
@@ -172,7 +239,7 @@ async fn upload_part_handler(
   }
   let data = UploadPartData {
     file_id: path_params.file_id.clone(),
-    upload_id: path_params.upload_id,
+    upload_id: path_params.upload_id.clone(),
     part_number: path_params.part_num,
     body: content,
   };
@@ -188,6 +255,13 @@ async fn upload_part_handler(
     .upload_part(key, data)
     .await
     .map_err(AppResponseError::from)?;
+  touch_upload_session(
+    &state.pg_pool,
+    &state.config.blob_storage,
+    &workspace_id,
+    &path_params.upload_id,
+  )
+  .await?;
   Ok(AppResponse::Ok().with_data(resp).into())
 }
 
@@ -198,6 +272,11 @@ async fn complete_upload_handler(
   req: web::Json<CompleteUploadRequest>,
 ) -> Result<JsonAppResponse<()>> {
   let req = req.into_inner();
+  if req.parent_dir == BLOB_CONTENT_DIR {
+    return Err(
+      AppError::InvalidRequest(format!("parent_dir {} is reserved", BLOB_CONTENT_DIR)).into(),
+    );
+  }
   let uid = state.user_cache.get_user_uid(&user_uuid).await?;
   let workspace_id = workspace_id.into_inner();
   state
@@ -205,17 +284,118 @@ async fn complete_upload_handler(
     .enforce_action(&uid, &workspace_id.to_string(), Action::Write)
     .await?;
 
+  get_upload_session(
+    &state.pg_pool,
+    &workspace_id,
+    &req.parent_dir,
+    &req.file_id,
+    &req.upload_id,
+  )
+  .await?;
+
+  let quota =
+    get_workspace_storage_quota(&state.pg_pool, &state.config.storage_quota, &workspace_id).await?;
   let key = BlobPathV1 {
     workspace_id,
     parent_dir: req.parent_dir.clone(),
     file_id: req.file_id.clone(),
   };
+  let upload_id = req.upload_id.clone();
   state
     .bucket_storage
-    .complete_upload(key, req)
+    .complete_upload(key.clone(), req, |file_size| quota.check_upload(file_size))
     .await
     .map_err(AppResponseError::from)?;
+  delete_upload_session(&state.pg_pool, &upload_id).await?;
+  scan_uploaded_blob(&state, key).await?;
+
+  Ok(AppResponse::Ok().into())
+}
+
+#[derive(Deserialize)]
+struct UploadPath {
+  workspace_id: Uuid,
+  parent_dir: String,
+  file_id: String,
+  upload_id: String,
+}
+
+/// Returns the parts received so far by a multipart upload, which lets the client resume an
+/// interrupted upload by only sending the missing parts.
+#[instrument(level = "debug", skip_all, err)]
+async fn list_upload_parts_handler(
+  user_uuid: UserUuid,
+  path: web::Path<UploadPath>,
+  state: web::Data<AppState>,
+) -> Result<JsonAppResponse<ListUploadPartsResponse>> {
+  let path = path.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &path.workspace_id.to_string(), Action::Write)
+    .await?;
+
+  let session = get_upload_session(
+    &state.pg_pool,
+    &path.workspace_id,
+    &path.parent_dir,
+    &path.file_id,
+    &path.upload_id,
+  )
+  .await?;
+  let key = BlobPathV1 {
+    workspace_id: path.workspace_id,
+    parent_dir: path.parent_dir,
+    file_id: path.file_id,
+  };
+  let parts = state
+    .bucket_storage
+    .list_upload_parts(key, &path.upload_id)
+    .await?;
+  Ok(
+    AppResponse::Ok()
+      .with_data(ListUploadPartsResponse {
+        file_id: session.file_id,
+        upload_id: session.upload_id,
+        parts,
+        expires_at: session.expires_at.timestamp(),
+      })
+      .into(),
+  )
+}
+
+/// Aborts a multipart upload, deleting the parts received so far.
+#[instrument(level = "debug", skip_all, err)]
+async fn abort_upload_handler(
+  user_uuid: UserUuid,
+  path: web::Path<UploadPath>,
+  state: web::Data<AppState>,
+) -> Result<JsonAppResponse<()>> {
+  let path = path.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &path.workspace_id.to_string(), Action::Write)
+    .await?;
 
+  get_upload_session(
+    &state.pg_pool,
+    &path.workspace_id,
+    &path.parent_dir,
+    &path.file_id,
+    &path.upload_id,
+  )
+  .await?;
+  let key = BlobPathV1 {
+    workspace_id: path.workspace_id,
+    parent_dir: path.parent_dir,
+    file_id: path.file_id,
+  };
+  state
+    .bucket_storage
+    .abort_upload(key, &path.upload_id)
+    .await?;
+  delete_upload_session(&state.pg_pool, &path.upload_id).await?;
   Ok(AppResponse::Ok().into())
 }
 
@@ -237,6 +417,9 @@ async fn put_blob_handler(
     .await?;
 
   let content_length = content_length.into_inner().into_inner();
+  get_workspace_storage_quota(&state.pg_pool, &state.config.storage_quota, &workspace_id)
+    .await?
+    .check_upload(content_length as u64)?;
   let content_type = content_type.into_inner().to_string();
   let content = {
     let mut payload_reader = payload_to_async_read(payload);
@@ -289,9 +472,10 @@ async fn put_blob_handler(
   let file_stream = ByteStream::from(content);
   state
     .bucket_storage
-    .put_blob_with_content_type(path, file_stream, content_type, file_size)
+    .put_blob_with_content_type(path.clone(), file_stream, content_type, file_size)
     .await
     .map_err(AppResponseError::from)?;
+  scan_uploaded_blob(&state, path).await?;
 
   Ok(AppResponse::Ok().into())
 }
@@ -318,14 +502,16 @@ async fn delete_blob_handler(
   Ok(AppResponse::Ok().into())
 }
 
-#[instrument(level = "debug", skip(state), err)]
+#[instrument(level = "debug", skip(state, user_uuid), err)]
 async fn get_blob_v1_handler(
+  user_uuid: OptionalUserUuid,
   state: Data<AppState>,
   path: web::Path<BlobPathV1>,
+  query: web::Query<BlobVariantQuery>,
   req: HttpRequest,
 ) -> Result<HttpResponse<BoxBody>> {
   let path = path.into_inner();
-  get_blob_by_object_key(state, &path, req).await
+  get_blob_by_object_key(state, &path, query.w, req, user_uuid).await
 }
 
 #[instrument(level = "debug", skip(state), err)]
@@ -350,10 +536,13 @@ async fn delete_blob_v1_handler(
   Ok(AppResponse::Ok().into())
 }
 
+/// Returns the blob, or its resized variant when a width is requested for an image blob.
 async fn get_blob_by_object_key(
   state: Data<AppState>,
   key: &impl BlobKey,
+  width: Option<u32>,
   req: HttpRequest,
+  user_uuid: OptionalUserUuid,
 ) -> Result<HttpResponse<BoxBody>> {
   // Get the metadata
   let result = state
@@ -370,11 +559,17 @@ async fn get_blob_by_object_key(
   }
 
   let metadata = result.unwrap();
-  match AFBlobStatus::from(metadata.status) {
+  // A blob that hasn't been found clean by the malware scanner yet may still be blocked, so it
+  // must not be cached by the browsers or the CDN.
+  let cache_control = match AFBlobStatus::from(metadata.status) {
     AFBlobStatus::DallEContentPolicyViolation => {
       return Ok(HttpResponse::UnprocessableEntity().finish());
     },
-    AFBlobStatus::Ok => {},
+    AFBlobStatus::Infected => {
+      return Ok(HttpResponse::Forbidden().body("The file is infected and has been blocked"));
+    },
+    AFBlobStatus::Ok => "public, immutable, max-age=31536000", // 31536000 seconds = 1 year
+    AFBlobStatus::ScanPending | AFBlobStatus::ScanFailed => "no-store",
   };
 
   // Check if the file is modified since the last time
@@ -389,17 +584,121 @@ async fn get_blob_by_object_key(
     }
   }
 
-  trace!("Get blob data from bucket storage: {:?}", key.object_key());
-  let blob_result = state.bucket_storage.get_blob(key).await;
+  // Blobs and their variants are immutable, so a matching ETag is always up to date
+  let width = width.filter(|_| {
+    is_resizable_image(&metadata.file_type) && metadata.file_size <= MAX_IMAGE_VARIANT_SOURCE_SIZE
+  });
+  let variant_e_tag = width.map(|width| format!("{}-w{}", key.e_tag(), image_variant_width(width)));
+  if let Some(if_none_match) = req
+    .headers()
+    .get(IF_NONE_MATCH)
+    .and_then(|h| h.to_str().ok())
+  {
+    if if_none_match
+      .split(',')
+      .map(str::trim)
+      .any(|e_tag| e_tag == key.e_tag() || Some(e_tag) == variant_e_tag.as_deref())
+    {
+      return Ok(HttpResponse::NotModified().finish());
+    }
+  }
+
+  // Variants are previews, e.g. board covers, so only downloads of the original are audited
+  if let (Some(width), Some(variant_e_tag)) = (width, variant_e_tag) {
+    match get_or_create_image_variant(&state.bucket_storage, key, &metadata.file_type, width).await
+    {
+      Ok(Some(variant)) => {
+        trace!(
+          "Get {}px variant of blob: {:?}",
+          variant.width,
+          key.object_key()
+        );
+        return Ok(
+          HttpResponse::Ok()
+            .append_header((ETAG, variant_e_tag))
+            .append_header((CONTENT_TYPE, variant.content_type))
+            .append_header((LAST_MODIFIED, metadata.modified_at.to_rfc2822()))
+            .append_header((CONTENT_LENGTH, variant.content.len()))
+            .append_header((CACHE_CONTROL, cache_control))
+            .body(variant.content),
+        );
+      },
+      // The image isn't wider than the requested width, serve the original
+      Ok(None) => {},
+      Err(err) => {
+        return if err.is_record_not_found() {
+          Ok(HttpResponse::NotFound().finish())
+        } else {
+          Ok(AppResponseError::from(err).error_response())
+        };
+      },
+    }
+  }
+
+  let blob_size = metadata.file_size.max(0) as u64;
+  let range = match requested_range(&req, key, blob_size) {
+    RequestedRange::Full => None,
+    RequestedRange::Partial(range) => Some(range),
+    RequestedRange::Unsatisfiable => {
+      return Ok(
+        HttpResponse::RangeNotSatisfiable()
+          .append_header((CONTENT_RANGE, format!("bytes */{}", blob_size)))
+          .finish(),
+      );
+    },
+  };
+
+  trace!(
+    "Get blob data from bucket storage: {:?}, range: {:?}",
+    key.object_key(),
+    range
+  );
+  let blob_result = state.bucket_storage.get_blob_stream(key, range).await;
   match blob_result {
     Ok(blob) => {
-      let response = HttpResponse::Ok()
-          .append_header((ETAG, key.e_tag()))
-          .append_header((CONTENT_TYPE, metadata.file_type))
-          .append_header((LAST_MODIFIED, metadata.modified_at.to_rfc2822()))
-          .append_header((CONTENT_LENGTH, blob.len()))
-          .append_header((CACHE_CONTROL, "public, immutable, max-age=31536000"))// 31536000 seconds = 1 year
-          .body(blob);
+      // Blobs can be downloaded anonymously, so only downloads by a signed-in user are audited.
+      // Seeking in a video or a PDF sends many range requests, so only the first one is audited.
+      let is_first_range = range.map(|range| range.start == 0).unwrap_or(true);
+      if let Some(user_uuid) = user_uuid.as_uuid().filter(|_| is_first_range) {
+        let state = state.clone();
+        let workspace_id = *key.workspace_id();
+        let object_key = key.object_key();
+        tokio::spawn(async move {
+          if let Ok(uid) = state.user_cache.get_user_uid(&user_uuid).await {
+            record_audit_log(
+              &state.pg_pool,
+              &workspace_id,
+              Some(uid),
+              AuditAction::DownloadBlob,
+              Some(&object_key),
+              json!({}),
+            )
+            .await;
+          }
+        });
+      }
+
+      let mut response = match range {
+        Some(range) => {
+          let mut response = HttpResponse::PartialContent();
+          response.append_header((
+            CONTENT_RANGE,
+            format!("bytes {}-{}/{}", range.start, range.end, blob_size),
+          ));
+          response
+        },
+        None => HttpResponse::Ok(),
+      };
+      let response = response
+        .append_header((ETAG, key.e_tag()))
+        .append_header((CONTENT_TYPE, metadata.file_type))
+        .append_header((LAST_MODIFIED, metadata.modified_at.to_rfc2822()))
+        .append_header((ACCEPT_RANGES, "bytes"))
+        .append_header((CACHE_CONTROL, cache_control))
+        .body(SizedStream::new(
+          blob.content_length,
+          ReaderStream::new(blob.reader),
+        ));
 
       Ok(response)
     },
@@ -413,14 +712,47 @@ async fn get_blob_by_object_key(
   }
 }
 
-#[instrument(level = "debug", skip(state), err)]
+enum RequestedRange {
+  Full,
+  Partial(BlobRange),
+  Unsatisfiable,
+}
+
+/// Returns the range of the blob requested with the `Range` header. Only single byte ranges are
+/// supported, the whole blob is sent for the other ranges as allowed by RFC 9110.
+fn requested_range(req: &HttpRequest, key: &impl BlobKey, blob_size: u64) -> RequestedRange {
+  // Blobs are immutable, so `If-Range` only fails when it refers to another blob
+  if let Some(if_range) = req.headers().get(IF_RANGE).and_then(|h| h.to_str().ok()) {
+    if if_range.trim() != key.e_tag() && DateTime::parse_from_rfc2822(if_range).is_err() {
+      return RequestedRange::Full;
+    }
+  }
+
+  let spec = match req
+    .headers()
+    .get(RANGE)
+    .and_then(|h| h.to_str().ok())
+    .and_then(|h| h.parse::<Range>().ok())
+  {
+    Some(Range::Bytes(mut specs)) if specs.len() == 1 => specs.remove(0),
+    _ => return RequestedRange::Full,
+  };
+  match spec.to_satisfiable_range(blob_size) {
+    Some((start, end)) => RequestedRange::Partial(BlobRange { start, end }),
+    None => RequestedRange::Unsatisfiable,
+  }
+}
+
+#[instrument(level = "debug", skip(state, user_uuid), err)]
 async fn get_blob_handler(
+  user_uuid: OptionalUserUuid,
   state: Data<AppState>,
   path: web::Path<BlobPathV0>,
+  query: web::Query<BlobVariantQuery>,
   req: HttpRequest,
 ) -> Result<HttpResponse<BoxBody>> {
   let blob_path = path.into_inner();
-  get_blob_by_object_key(state, &blob_path, req).await
+  get_blob_by_object_key(state, &blob_path, query.w, req, user_uuid).await
 }
 
 #[instrument(level = "debug", skip(state), err)]
@@ -435,13 +767,7 @@ async fn get_blob_metadata_handler(
     .bucket_storage
     .get_blob_metadata(&path.workspace_id, &path.blob_metadata_key())
     .await
-    .map(|meta| BlobMetadata {
-      workspace_id: meta.workspace_id,
-      file_id: meta.file_id,
-      file_type: meta.file_type,
-      file_size: meta.file_size,
-      modified_at: meta.modified_at,
-    })
+    .map(to_blob_metadata)
     .map_err(AppResponseError::from)?;
 
   Ok(Json(AppResponse::Ok().with_data(metadata)))
@@ -459,13 +785,7 @@ async fn get_blob_metadata_v1_handler(
     .bucket_storage
     .get_blob_metadata(&path.workspace_id, &path.blob_metadata_key())
     .await
-    .map(|meta| BlobMetadata {
-      workspace_id: meta.workspace_id,
-      file_id: meta.file_id,
-      file_type: meta.file_type,
-      file_size: meta.file_size,
-      modified_at: meta.modified_at,
-    })
+    .map(to_blob_metadata)
     .map_err(AppResponseError::from)?;
 
   Ok(Json(AppResponse::Ok().with_data(metadata)))
@@ -485,6 +805,39 @@ async fn get_workspace_usage_handler(
   Ok(AppResponse::Ok().with_data(usage).into())
 }
 
+#[instrument(level = "debug", skip(state, user_uuid), err)]
+async fn get_workspace_storage_quota_handler(
+  user_uuid: UserUuid,
+  state: Data<AppState>,
+  workspace_id: web::Path<Uuid>,
+) -> Result<JsonAppResponse<WorkspaceStorageQuota>> {
+  let workspace_id = workspace_id.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id.to_string(), Action::Read)
+    .await?;
+  let quota =
+    get_workspace_storage_quota(&state.pg_pool, &state.config.storage_quota, &workspace_id).await?;
+  Ok(AppResponse::Ok().with_data(quota.into()).into())
+}
+
+/// Overrides the default storage quotas of the workspace. Only the admin of the server can change
+/// them, as the members of a workspace could otherwise lift its limits.
+#[instrument(level = "debug", skip(state, auth), err)]
+async fn update_workspace_storage_quota_handler(
+  auth: Authorization,
+  state: Data<AppState>,
+  workspace_id: web::Path<Uuid>,
+  params: Json<UpdateWorkspaceStorageQuotaParams>,
+) -> Result<JsonAppResponse<()>> {
+  if !auth.is_admin() {
+    return Err(AppError::NotEnoughPermissions.into());
+  }
+  update_workspace_storage_quota(&state.pg_pool, &workspace_id, params.into_inner()).await?;
+  Ok(AppResponse::Ok().into())
+}
+
 // TODO(nathan): implement pagination
 #[instrument(level = "debug", skip(state), err)]
 async fn get_all_workspace_blob_metadata_handler(
@@ -495,13 +848,7 @@ async fn get_all_workspace_blob_metadata_handler(
     .await
     .map_err(AppResponseError::from)?
     .into_iter()
-    .map(|meta| BlobMetadata {
-      workspace_id: meta.workspace_id,
-      file_id: meta.file_id,
-      file_type: meta.file_type,
-      file_size: meta.file_size,
-      modified_at: meta.modified_at,
-    })
+    .map(to_blob_metadata)
     .collect::<Vec<_>>();
   Ok(
     AppResponse::Ok()
@@ -509,6 +856,59 @@ async fn get_all_workspace_blob_metadata_handler(
       .into(),
   )
 }
+/// Dry-run report of the blob garbage collector: lists the blobs it will delete, without deleting
+/// anything.
+#[instrument(level = "debug", skip(state), err)]
+async fn get_orphaned_blobs_handler(
+  user_uuid: UserUuid,
+  state: Data<AppState>,
+  workspace_id: web::Path<Uuid>,
+) -> Result<JsonAppResponse<OrphanedBlobsReport>> {
+  let workspace_id = workspace_id.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_role(&uid, &workspace_id.to_string(), AFRole::Owner)
+    .await?;
+  let blobs = select_orphaned_blob_metadata(&state.pg_pool, &workspace_id)
+    .await
+    .map_err(AppResponseError::from)?
+    .into_iter()
+    .filter_map(|meta| {
+      Some(OrphanedBlob {
+        orphaned_at: meta.orphaned_at?,
+        file_id: meta.file_id,
+        file_type: meta.file_type,
+        file_size: meta.file_size,
+        modified_at: meta.modified_at,
+      })
+    })
+    .collect();
+  Ok(
+    AppResponse::Ok()
+      .with_data(OrphanedBlobsReport { blobs })
+      .into(),
+  )
+}
+
+fn to_blob_metadata(meta: AFBlobMetadataRow) -> BlobMetadata {
+  let status = match AFBlobStatus::from(meta.status) {
+    AFBlobStatus::Ok => BlobStatus::Ok,
+    AFBlobStatus::DallEContentPolicyViolation => BlobStatus::ContentPolicyViolation,
+    AFBlobStatus::ScanPending => BlobStatus::ScanPending,
+    AFBlobStatus::Infected => BlobStatus::Infected,
+    AFBlobStatus::ScanFailed => BlobStatus::ScanFailed,
+  };
+  BlobMetadata {
+    workspace_id: meta.workspace_id,
+    file_id: meta.file_id,
+    file_type: meta.file_type,
+    file_size: meta.file_size,
+    modified_at: meta.modified_at,
+    status,
+  }
+}
+
 fn payload_to_async_read(payload: Payload) -> Pin<Box<dyn AsyncRead>> {
   let mapped =
     payload.map(|chunk| chunk.map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e)));
@@ -526,6 +926,11 @@ async fn put_blob_handler_v1(
   payload: Payload,
 ) -> Result<JsonAppResponse<PutFileResponse>> {
   let path = path.into_inner();
+  if path.parent_dir == BLOB_CONTENT_DIR {
+    return Err(
+      AppError::InvalidRequest(format!("parent_dir {} is reserved", BLOB_CONTENT_DIR)).into(),
+    );
+  }
   let uid = state.user_cache.get_user_uid(&user_uuid).await?;
   state
     .workspace_access_control
@@ -533,6 +938,13 @@ async fn put_blob_handler_v1(
     .await?;
 
   let content_length = content_length.into_inner().into_inner();
+  get_workspace_storage_quota(
+    &state.pg_pool,
+    &state.config.storage_quota,
+    &path.workspace_id,
+  )
+  .await?
+  .check_upload(content_length as u64)?;
   let content_type = content_type.into_inner().to_string();
 
   let mut content = Vec::with_capacity(content_length);
@@ -569,21 +981,33 @@ async fn put_blob_handler_v1(
   );
 
   let file_stream = ByteStream::from(content);
+  let key = BlobPathV1::from((path, file_id));
   state
     .bucket_storage
-    .put_blob_with_content_type(
-      BlobPathV1::from((path, file_id)),
-      file_stream,
-      content_type,
-      content_length,
-    )
+    .put_blob_with_content_type(key.clone(), file_stream, content_type, content_length)
     .await
     .map_err(AppResponseError::from)?;
+  scan_uploaded_blob(&state, key).await?;
   Ok(AppResponse::Ok().with_data(resp_data).into())
 }
 
-/// Use [BlobPathV0] when get/put object by single part
+/// Scans the uploaded blob for malware in the background, if a malware scanner is configured.
+async fn scan_uploaded_blob(state: &AppState, key: impl BlobKey + 'static) -> Result<(), AppError> {
+  if let Some(scanner) = &state.malware_scanner {
+    schedule_blob_scan(&state.pg_pool, &state.bucket_storage, scanner, key).await?;
+  }
+  Ok(())
+}
+
 #[derive(Deserialize, Debug)]
+struct BlobVariantQuery {
+  /// Width, in pixels, of the resized variant to return for an image blob. It is rounded up to
+  /// the width of one of the generated variants.
+  w: Option<u32>,
+}
+
+/// Use [BlobPathV0] when get/put object by single part
+#[derive(Deserialize, Debug, Clone)]
 struct BlobPathV0 {
   workspace_id: Uuid,
   file_id: String,
@@ -608,7 +1032,7 @@ impl BlobKey for BlobPathV0 {
 }
 
 /// Use [BlobPathV1] when put/get object by multiple upload parts
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Clone)]
 pub struct BlobPathV1 {
   pub workspace_id: Uuid,
   pub parent_dir: String,
@@ -1,6 +1,8 @@
+pub mod access_control;
 pub mod access_request;
 pub mod ai;
 pub mod chat;
+pub mod data_export;
 pub mod data_import;
 pub mod file_storage;
 pub mod metrics;
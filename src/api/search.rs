@@ -8,6 +8,7 @@ use shared_entity::dto::search_dto::{SearchDocumentRequest, SearchDocumentRespon
 use shared_entity::response::{AppResponse, JsonAppResponse};
 
 use crate::biz::search::search_document;
+use crate::biz::workspace::ops::ensure_not_end_to_end_encrypted;
 use crate::state::AppState;
 
 pub fn search_scope() -> Scope {
@@ -29,6 +30,7 @@ async fn document_search(
     .workspace_access_control
     .enforce_action(&uid, &workspace_id.to_string(), Action::Read)
     .await?;
+  ensure_not_end_to_end_encrypted(&state.pg_pool, &workspace_id).await?;
   let metrics = &*state.metrics.request_metrics;
   let resp = search_document(
     &state.pg_pool,
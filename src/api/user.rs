@@ -1,3 +1,6 @@
+use crate::biz::user::personal_access_token::{
+  create_personal_access_token, list_personal_access_tokens, revoke_personal_access_token_for_user,
+};
 use crate::biz::user::user_delete::delete_user;
 use crate::biz::user::user_info::{get_profile, get_user_workspace_info, update_user};
 use crate::biz::user::user_verify::verify_token;
@@ -5,11 +8,16 @@ use crate::state::AppState;
 use actix_web::web::{Data, Json};
 use actix_web::Result;
 use actix_web::{web, Scope};
+use app_error::AppError;
 use authentication::jwt::{Authorization, UserUuid};
-use database_entity::dto::{AFUserProfile, AFUserWorkspaceInfo};
+use database_entity::dto::{
+  AFUserProfile, AFUserWorkspaceInfo, CreatePersonalAccessTokenParams, CreatedPersonalAccessToken,
+  PersonalAccessToken,
+};
 use shared_entity::dto::auth_dto::{DeleteUserQuery, SignInTokenResponse, UpdateUserParams};
 use shared_entity::response::AppResponseError;
 use shared_entity::response::{AppResponse, JsonAppResponse};
+use uuid::Uuid;
 
 pub fn user_scope() -> Scope {
   web::scope("/api/user")
@@ -18,6 +26,15 @@ pub fn user_scope() -> Scope {
     .service(web::resource("/profile").route(web::get().to(get_user_profile_handler)))
     .service(web::resource("/workspace").route(web::get().to(get_user_workspace_info_handler)))
     .service(web::resource("").route(web::delete().to(delete_user_handler)))
+    .service(
+      web::resource("/personal-access-token")
+        .route(web::get().to(list_personal_access_tokens_handler))
+        .route(web::post().to(create_personal_access_token_handler)),
+    )
+    .service(
+      web::resource("/personal-access-token/{token_id}")
+        .route(web::delete().to(revoke_personal_access_token_handler)),
+    )
 }
 
 #[tracing::instrument(skip(state, path), err)]
@@ -70,6 +87,9 @@ async fn delete_user_handler(
   state: Data<AppState>,
   query: web::Query<DeleteUserQuery>,
 ) -> Result<JsonAppResponse<()>, actix_web::Error> {
+  if auth.personal_access_token().is_some() {
+    return Err(AppError::NotEnoughPermissions.into());
+  }
   let user_uuid = auth.uuid()?;
   let DeleteUserQuery {
     provider_access_token,
@@ -89,3 +109,32 @@ async fn delete_user_handler(
   .await?;
   Ok(AppResponse::Ok().into())
 }
+
+#[tracing::instrument(skip(state, auth), err)]
+async fn list_personal_access_tokens_handler(
+  auth: Authorization,
+  state: Data<AppState>,
+) -> Result<JsonAppResponse<Vec<PersonalAccessToken>>> {
+  let tokens = list_personal_access_tokens(&state, &auth).await?;
+  Ok(AppResponse::Ok().with_data(tokens).into())
+}
+
+#[tracing::instrument(skip(state, auth, payload), err)]
+async fn create_personal_access_token_handler(
+  auth: Authorization,
+  payload: Json<CreatePersonalAccessTokenParams>,
+  state: Data<AppState>,
+) -> Result<JsonAppResponse<CreatedPersonalAccessToken>> {
+  let token = create_personal_access_token(&state, &auth, payload.into_inner()).await?;
+  Ok(AppResponse::Ok().with_data(token).into())
+}
+
+#[tracing::instrument(skip(state, auth), err)]
+async fn revoke_personal_access_token_handler(
+  auth: Authorization,
+  path: web::Path<Uuid>,
+  state: Data<AppState>,
+) -> Result<JsonAppResponse<()>> {
+  revoke_personal_access_token_for_user(&state, &auth, &path.into_inner()).await?;
+  Ok(AppResponse::Ok().into())
+}
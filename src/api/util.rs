@@ -88,6 +88,16 @@ pub fn device_id_from_headers(headers: &HeaderMap) -> Result<&str, AppError> {
   )
 }
 
+/// Retrieve the password of a password protected published view from headers
+pub fn publish_password_from_headers(headers: &HeaderMap) -> Option<&str> {
+  value_from_headers(
+    headers,
+    &["X-Publish-Password", "x-publish-password"],
+    "Missing X-Publish-Password header",
+  )
+  .ok()
+}
+
 /// Create new realtime user for requests from appflowy web
 pub fn realtime_user_for_web_request(
   headers: &HeaderMap,
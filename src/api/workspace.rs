@@ -1,5 +1,7 @@
 use crate::api::util::{client_version_from_headers, realtime_user_for_web_request, PayloadReader};
-use crate::api::util::{compress_type_from_header_value, device_id_from_headers};
+use crate::api::util::{
+  compress_type_from_header_value, device_id_from_headers, publish_password_from_headers,
+};
 use crate::api::ws::RealtimeServerAddr;
 use crate::biz;
 use crate::biz::collab::ops::{
@@ -7,16 +9,33 @@ use crate::biz::collab::ops::{
 };
 use crate::biz::user::user_verify::verify_token;
 use crate::biz::workspace;
+use crate::biz::workspace::audit_log::{export_audit_logs_csv, list_audit_logs, record_audit_log};
 use crate::biz::workspace::ops::{
   create_comment_on_published_view, create_reaction_on_comment, get_comments_on_published_view,
   get_reactions_on_published_view, remove_comment_on_published_view, remove_reaction_on_comment,
 };
+use crate::biz::workspace::page_export::export_page;
 use crate::biz::workspace::page_view::{
   create_page, create_space, delete_all_pages_from_trash, delete_trash, get_page_view_collab,
   move_page, move_page_to_trash, publish_page, restore_all_pages_from_trash,
   restore_page_from_trash, unpublish_page, update_page, update_page_collab_data, update_space,
 };
 use crate::biz::workspace::publish::get_workspace_default_publish_view_info_meta;
+use crate::biz::workspace::publish_access::check_published_collab_access;
+use crate::biz::workspace::publish_analytics::{
+  get_publish_analytics, record_published_view_visit, PublishedViewVisit,
+};
+use crate::biz::workspace::publish_domain::{
+  get_workspace_custom_domain, remove_workspace_custom_domain, resolve_publish_namespace,
+  set_workspace_custom_domain, verify_workspace_custom_domain,
+};
+use crate::biz::workspace::publish_history::{
+  get_publish_history_blob, get_publish_history_detail, list_publish_history,
+  rollback_to_publish_history,
+};
+use crate::biz::workspace::publish_schedule::{
+  cancel_pending_publish_schedule, create_publish_schedule, list_publish_schedules,
+};
 use crate::biz::workspace::quick_note::{
   create_quick_note, delete_quick_note, list_quick_notes, update_quick_note,
 };
@@ -49,6 +68,10 @@ use collab_rt_entity::user::RealtimeUser;
 use collab_rt_entity::RealtimeMessage;
 use collab_rt_protocol::collab_from_encode_collab;
 use database::collab::{CollabStorage, GetCollabOrigin};
+use database::pg_row::AFPublishedCollabAccess;
+use database::publish::{
+  select_published_collab_access, select_published_collab_access_for_view_id,
+};
 use database::user::select_uid_from_email;
 use database_entity::dto::PublishCollabItem;
 use database_entity::dto::PublishInfo;
@@ -56,6 +79,7 @@ use database_entity::dto::*;
 use indexer::scheduler::{UnindexedCollabTask, UnindexedData};
 use prost::Message as ProstMessage;
 use rayon::prelude::*;
+use serde_json::json;
 use sha2::{Digest, Sha256};
 use shared_entity::dto::publish_dto::DuplicatePublishedPageResponse;
 use shared_entity::dto::workspace_dto::*;
@@ -109,6 +133,10 @@ pub fn workspace_scope() -> Scope {
         .route(web::get().to(get_workspace_settings_handler))
         .route(web::post().to(post_workspace_settings_handler)),
     )
+    .service(
+      web::resource("/{workspace_id}/data-key/rotate")
+        .route(web::post().to(rotate_workspace_data_key_handler)),
+    )
     .service(web::resource("/{workspace_id}/open").route(web::put().to(open_workspace_handler)))
     .service(web::resource("/{workspace_id}/leave").route(web::post().to(leave_workspace_handler)))
     .service(
@@ -170,6 +198,10 @@ pub fn workspace_scope() -> Scope {
         .route(web::get().to(get_page_view_handler))
         .route(web::patch().to(update_page_view_handler)),
     )
+    .service(
+      web::resource("/{workspace_id}/page-view/{view_id}/export")
+        .route(web::get().to(export_page_view_handler)),
+    )
     .service(
       web::resource("/{workspace_id}/page-view/{view_id}/move")
         .route(web::post().to(move_page_handler)),
@@ -198,6 +230,31 @@ pub fn workspace_scope() -> Scope {
       web::resource("/{workspace_id}/page-view/{view_id}/unpublish")
         .route(web::post().to(unpublish_page_handler)),
     )
+    .service(
+      web::resource("/{workspace_id}/page-view/{view_id}/publish-history")
+        .route(web::get().to(list_publish_history_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/page-view/{view_id}/publish-history/{history_id}")
+        .route(web::get().to(get_publish_history_detail_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/page-view/{view_id}/publish-history/{history_id}/blob")
+        .route(web::get().to(get_publish_history_blob_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/page-view/{view_id}/publish-history/{history_id}/rollback")
+        .route(web::post().to(rollback_publish_history_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/page-view/{view_id}/publish-schedule")
+        .route(web::get().to(list_publish_schedules_handler))
+        .route(web::post().to(create_publish_schedule_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/publish-schedule/{schedule_id}")
+        .route(web::delete().to(cancel_publish_schedule_handler)),
+    )
     .service(
       web::resource("/{workspace_id}/batch/collab")
         .route(web::post().to(batch_create_collab_handler)),
@@ -260,6 +317,16 @@ pub fn workspace_scope() -> Scope {
         .route(web::put().to(put_publish_namespace_handler))
         .route(web::get().to(get_publish_namespace_handler)),
     )
+    .service(
+      web::resource("/{workspace_id}/publish-custom-domain")
+        .route(web::put().to(put_publish_custom_domain_handler))
+        .route(web::get().to(get_publish_custom_domain_handler))
+        .route(web::delete().to(delete_publish_custom_domain_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/publish-custom-domain/verify")
+        .route(web::post().to(verify_publish_custom_domain_handler)),
+    )
     .service(
       web::resource("/{workspace_id}/publish-default")
         .route(web::put().to(put_workspace_default_published_view_handler))
@@ -328,6 +395,17 @@ pub fn workspace_scope() -> Scope {
         .route(web::put().to(update_quick_note_handler))
         .route(web::delete().to(delete_quick_note_handler)),
     )
+    .service(
+      web::resource("/{workspace_id}/publish-analytics")
+        .route(web::get().to(get_publish_analytics_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/audit-log").route(web::get().to(list_audit_logs_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/audit-log/csv")
+        .route(web::get().to(export_audit_logs_csv_handler)),
+    )
 }
 
 pub fn collab_scope() -> Scope {
@@ -347,21 +425,35 @@ async fn create_workspace_handler(
   state: Data<AppState>,
   create_workspace_param: Json<CreateWorkspaceParam>,
 ) -> Result<Json<AppResponse<AFWorkspace>>> {
-  let workspace_name = create_workspace_param
-    .into_inner()
-    .workspace_name
-    .unwrap_or_else(|| format!("workspace_{}", chrono::Utc::now().timestamp()));
+  let CreateWorkspaceParam {
+    workspace_name,
+    end_to_end_encryption,
+  } = create_workspace_param.into_inner();
+  let workspace_name =
+    workspace_name.unwrap_or_else(|| format!("workspace_{}", chrono::Utc::now().timestamp()));
 
   let uid = state.user_cache.get_user_uid(&uuid).await?;
-  let new_workspace = workspace::ops::create_workspace_for_user(
-    &state.pg_pool,
-    state.workspace_access_control.clone(),
-    &state.collab_access_control_storage,
-    &uuid,
-    uid,
-    &workspace_name,
-  )
-  .await?;
+  let new_workspace = if end_to_end_encryption {
+    workspace::ops::create_end_to_end_encrypted_workspace(
+      &state.pg_pool,
+      state.workspace_access_control.clone(),
+      &state.collab_access_control_storage,
+      &uuid,
+      uid,
+      &workspace_name,
+    )
+    .await?
+  } else {
+    workspace::ops::create_workspace_for_user(
+      &state.pg_pool,
+      state.workspace_access_control.clone(),
+      &state.collab_access_control_storage,
+      &uuid,
+      uid,
+      &workspace_name,
+    )
+    .await?
+  };
 
   Ok(AppResponse::Ok().with_data(new_workspace).into())
 }
@@ -518,6 +610,28 @@ async fn get_workspace_settings_handler(
   Ok(AppResponse::Ok().with_data(settings).into())
 }
 
+/// Adds a new data key to the workspace, used to encrypt its collabs from now on. Only available
+/// to the admin, as the data encrypted with the previous keys can still be read.
+#[instrument(level = "info", skip(auth, state), err)]
+async fn rotate_workspace_data_key_handler(
+  auth: Authorization,
+  state: Data<AppState>,
+  workspace_id: web::Path<Uuid>,
+) -> Result<JsonAppResponse<WorkspaceDataKeyRotated>> {
+  if !auth.is_admin() {
+    return Err(AppError::NotEnoughPermissions.into());
+  }
+  let collab_encryption = state.collab_encryption.as_ref().ok_or_else(|| {
+    AppError::InvalidRequest("Encryption at rest of the collabs is not enabled".to_string())
+  })?;
+  let key_version = collab_encryption.rotate_data_key(&workspace_id).await?;
+  Ok(
+    AppResponse::Ok()
+      .with_data(WorkspaceDataKeyRotated { key_version })
+      .into(),
+  )
+}
+
 #[instrument(level = "info", skip_all, err, fields(user_uuid))]
 async fn post_workspace_settings_handler(
   user_uuid: UserUuid,
@@ -525,13 +639,28 @@ async fn post_workspace_settings_handler(
   workspace_id: web::Path<Uuid>,
   data: Json<AFWorkspaceSettingsChange>,
 ) -> Result<JsonAppResponse<AFWorkspaceSettings>> {
-  let data = data.into_inner();
+  let mut data = data.into_inner();
   trace!("workspace settings: {:?}", data);
   let uid = state.user_cache.get_user_uid(&user_uuid).await?;
   state
     .workspace_access_control
     .enforce_action(&uid, &workspace_id.to_string(), Action::Write)
     .await?;
+  if data.changes_sso() {
+    state
+      .workspace_access_control
+      .enforce_role(&uid, &workspace_id.to_string(), AFRole::Owner)
+      .await?;
+  }
+  if let Some(sso) = data.sso.as_mut() {
+    workspace::sso::validate_workspace_sso_settings(
+      &state.pg_pool,
+      &state.gotrue_admin,
+      &workspace_id,
+      sso,
+    )
+    .await?;
+  }
   let settings =
     workspace::ops::update_workspace_settings(&state.pg_pool, &workspace_id, data).await?;
   Ok(AppResponse::Ok().with_data(settings).into())
@@ -583,6 +712,7 @@ async fn remove_workspace_member_handler(
     .collect::<Vec<String>>();
   workspace::ops::remove_workspace_members(
     &state.pg_pool,
+    uid,
     &workspace_id,
     &member_emails,
     state.workspace_access_control.clone(),
@@ -674,6 +804,7 @@ async fn update_workspace_member_handler(
     workspace::ops::update_workspace_member(
       &changeset_uid,
       &state.pg_pool,
+      uid,
       &workspace_id,
       &changeset,
       state.workspace_access_control.clone(),
@@ -713,6 +844,8 @@ async fn create_collab_handler(
   };
 
   let (params, workspace_id) = params.split();
+  let workspace_uuid = Uuid::parse_str(&workspace_id).map_err(AppError::from)?;
+  workspace::ops::ensure_not_end_to_end_encrypted(&state.pg_pool, &workspace_uuid).await?;
 
   if params.object_id == workspace_id {
     // Only the object with [CollabType::Folder] can have the same object_id as workspace_id. But
@@ -796,6 +929,7 @@ async fn batch_create_collab_handler(
   let uid = state.user_cache.get_user_uid(&user_uuid).await?;
   let workspace_id_uuid = workspace_id.into_inner();
   let workspace_id = workspace_id_uuid.to_string();
+  workspace::ops::ensure_not_end_to_end_encrypted(&state.pg_pool, &workspace_id_uuid).await?;
   let compress_type = compress_type_from_header_value(req.headers())?;
   event!(tracing::Level::DEBUG, "start decompressing collab list");
 
@@ -1025,6 +1159,7 @@ async fn post_web_update_handler(
     .await
     .map_err(AppResponseError::from)?;
   let (workspace_id, object_id) = path.into_inner();
+  workspace::ops::ensure_not_end_to_end_encrypted(&state.pg_pool, &workspace_id).await?;
   state
     .collab_access_control
     .enforce_action(
@@ -1063,6 +1198,7 @@ async fn post_space_handler(
 ) -> Result<Json<AppResponse<Space>>> {
   let uid = state.user_cache.get_user_uid(&user_uuid).await?;
   let workspace_uuid = path.into_inner();
+  workspace::ops::ensure_not_end_to_end_encrypted(&state.pg_pool, &workspace_uuid).await?;
   let user = realtime_user_for_web_request(req.headers(), uid)?;
   let space = create_space(
     &state.metrics.appflowy_web_metrics,
@@ -1090,6 +1226,7 @@ async fn update_space_handler(
 ) -> Result<Json<AppResponse<Space>>> {
   let uid = state.user_cache.get_user_uid(&user_uuid).await?;
   let (workspace_uuid, view_id) = path.into_inner();
+  workspace::ops::ensure_not_end_to_end_encrypted(&state.pg_pool, &workspace_uuid).await?;
   let user = realtime_user_for_web_request(req.headers(), uid)?;
   update_space(
     &state.metrics.appflowy_web_metrics,
@@ -1117,6 +1254,7 @@ async fn post_page_view_handler(
 ) -> Result<Json<AppResponse<Page>>> {
   let uid = state.user_cache.get_user_uid(&user_uuid).await?;
   let workspace_uuid = path.into_inner();
+  workspace::ops::ensure_not_end_to_end_encrypted(&state.pg_pool, &workspace_uuid).await?;
   let user = realtime_user_for_web_request(req.headers(), uid)?;
   let page = create_page(
     &state.metrics.appflowy_web_metrics,
@@ -1143,6 +1281,7 @@ async fn move_page_handler(
 ) -> Result<Json<AppResponse<()>>> {
   let uid = state.user_cache.get_user_uid(&user_uuid).await?;
   let (workspace_uuid, view_id) = path.into_inner();
+  workspace::ops::ensure_not_end_to_end_encrypted(&state.pg_pool, &workspace_uuid).await?;
   let user = realtime_user_for_web_request(req.headers(), uid)?;
   move_page(
     &state.metrics.appflowy_web_metrics,
@@ -1167,6 +1306,7 @@ async fn move_page_to_trash_handler(
 ) -> Result<Json<AppResponse<()>>> {
   let uid = state.user_cache.get_user_uid(&user_uuid).await?;
   let (workspace_uuid, view_id) = path.into_inner();
+  workspace::ops::ensure_not_end_to_end_encrypted(&state.pg_pool, &workspace_uuid).await?;
   let user = realtime_user_for_web_request(req.headers(), uid)?;
   move_page_to_trash(
     &state.metrics.appflowy_web_metrics,
@@ -1189,6 +1329,7 @@ async fn restore_page_from_trash_handler(
 ) -> Result<Json<AppResponse<()>>> {
   let uid = state.user_cache.get_user_uid(&user_uuid).await?;
   let (workspace_uuid, view_id) = path.into_inner();
+  workspace::ops::ensure_not_end_to_end_encrypted(&state.pg_pool, &workspace_uuid).await?;
   let user = realtime_user_for_web_request(req.headers(), uid)?;
   restore_page_from_trash(
     &state.metrics.appflowy_web_metrics,
@@ -1211,6 +1352,7 @@ async fn restore_all_pages_from_trash_handler(
 ) -> Result<Json<AppResponse<()>>> {
   let uid = state.user_cache.get_user_uid(&user_uuid).await?;
   let workspace_uuid = path.into_inner();
+  workspace::ops::ensure_not_end_to_end_encrypted(&state.pg_pool, &workspace_uuid).await?;
   let user = realtime_user_for_web_request(req.headers(), uid)?;
   restore_all_pages_from_trash(
     &state.metrics.appflowy_web_metrics,
@@ -1236,6 +1378,7 @@ async fn delete_page_from_trash_handler(
     .await
     .map_err(AppResponseError::from)?;
   let (workspace_id, view_id) = path.into_inner();
+  workspace::ops::ensure_not_end_to_end_encrypted(&state.pg_pool, &workspace_id).await?;
   state
     .workspace_access_control
     .enforce_action(&uid, &workspace_id.to_string(), Action::Write)
@@ -1250,6 +1393,15 @@ async fn delete_page_from_trash_handler(
     &view_id,
   )
   .await?;
+  record_audit_log(
+    &state.pg_pool,
+    &workspace_id,
+    Some(uid),
+    AuditAction::DeleteViewFromTrash,
+    Some(&view_id),
+    json!({}),
+  )
+  .await;
   Ok(Json(AppResponse::Ok()))
 }
 
@@ -1266,6 +1418,7 @@ async fn delete_all_pages_from_trash_handler(
     .await
     .map_err(AppResponseError::from)?;
   let workspace_id = path.into_inner();
+  workspace::ops::ensure_not_end_to_end_encrypted(&state.pg_pool, &workspace_id).await?;
   state
     .workspace_access_control
     .enforce_action(&uid, &workspace_id.to_string(), Action::Write)
@@ -1279,6 +1432,15 @@ async fn delete_all_pages_from_trash_handler(
     workspace_id,
   )
   .await?;
+  record_audit_log(
+    &state.pg_pool,
+    &workspace_id,
+    Some(uid),
+    AuditAction::DeleteAllViewsFromTrash,
+    None,
+    json!({}),
+  )
+  .await;
   Ok(Json(AppResponse::Ok()))
 }
 
@@ -1298,11 +1460,15 @@ async fn publish_page_handler(
     .workspace_access_control
     .enforce_role(&uid, &workspace_id.to_string(), AFRole::Member)
     .await?;
+  workspace::ops::ensure_not_end_to_end_encrypted(&state.pg_pool, &workspace_id).await?;
   let PublishPageParams {
     publish_name,
     visible_database_view_ids,
     comments_enabled,
     duplicate_enabled,
+    auto_republish,
+    password,
+    members_only,
   } = payload.into_inner();
   publish_page(
     &state.pg_pool,
@@ -1316,6 +1482,9 @@ async fn publish_page_handler(
     publish_name,
     comments_enabled.unwrap_or(true),
     duplicate_enabled.unwrap_or(true),
+    auto_republish,
+    password,
+    members_only,
   )
   .await?;
   Ok(Json(AppResponse::Ok()))
@@ -1346,6 +1515,140 @@ async fn unpublish_page_handler(
   Ok(Json(AppResponse::Ok()))
 }
 
+async fn list_publish_history_handler(
+  user_uuid: UserUuid,
+  path: web::Path<(Uuid, Uuid)>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<Vec<PublishHistoryInfo>>>> {
+  let (workspace_id, view_id) = path.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_role(&uid, &workspace_id.to_string(), AFRole::Member)
+    .await?;
+  let history = list_publish_history(&state.pg_pool, workspace_id, view_id).await?;
+  Ok(Json(AppResponse::Ok().with_data(history)))
+}
+
+async fn get_publish_history_detail_handler(
+  user_uuid: UserUuid,
+  path: web::Path<(Uuid, Uuid, Uuid)>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<PublishHistoryDetail>>> {
+  let (workspace_id, view_id, history_id) = path.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_role(&uid, &workspace_id.to_string(), AFRole::Member)
+    .await?;
+  let detail =
+    get_publish_history_detail(&state.pg_pool, workspace_id, view_id, history_id).await?;
+  Ok(Json(AppResponse::Ok().with_data(detail)))
+}
+
+async fn get_publish_history_blob_handler(
+  user_uuid: UserUuid,
+  path: web::Path<(Uuid, Uuid, Uuid)>,
+  state: Data<AppState>,
+) -> Result<Vec<u8>> {
+  let (workspace_id, view_id, history_id) = path.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_role(&uid, &workspace_id.to_string(), AFRole::Member)
+    .await?;
+  let blob = get_publish_history_blob(&state.pg_pool, workspace_id, view_id, history_id).await?;
+  Ok(blob)
+}
+
+async fn rollback_publish_history_handler(
+  user_uuid: UserUuid,
+  path: web::Path<(Uuid, Uuid, Uuid)>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<()>>> {
+  let (workspace_id, view_id, history_id) = path.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_role(&uid, &workspace_id.to_string(), AFRole::Member)
+    .await?;
+  workspace::ops::ensure_not_end_to_end_encrypted(&state.pg_pool, &workspace_id).await?;
+  rollback_to_publish_history(
+    &state.pg_pool,
+    state.published_collab_store.as_ref(),
+    *user_uuid,
+    workspace_id,
+    view_id,
+    history_id,
+  )
+  .await?;
+  record_audit_log(
+    &state.pg_pool,
+    &workspace_id,
+    Some(uid),
+    AuditAction::PublishView,
+    Some(&view_id.to_string()),
+    json!({ "rollback_to": history_id }),
+  )
+  .await;
+  Ok(Json(AppResponse::Ok()))
+}
+
+async fn create_publish_schedule_handler(
+  user_uuid: UserUuid,
+  path: web::Path<(Uuid, Uuid)>,
+  payload: Json<CreatePublishSchedule>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<PublishSchedule>>> {
+  let (workspace_id, view_id) = path.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_role(&uid, &workspace_id.to_string(), AFRole::Member)
+    .await?;
+  workspace::ops::ensure_not_end_to_end_encrypted(&state.pg_pool, &workspace_id).await?;
+  let schedule = create_publish_schedule(
+    &state.pg_pool,
+    &state.collab_access_control_storage,
+    uid,
+    workspace_id,
+    view_id,
+    payload.into_inner(),
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok().with_data(schedule)))
+}
+
+async fn list_publish_schedules_handler(
+  user_uuid: UserUuid,
+  path: web::Path<(Uuid, Uuid)>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<Vec<PublishSchedule>>>> {
+  let (workspace_id, view_id) = path.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_role(&uid, &workspace_id.to_string(), AFRole::Member)
+    .await?;
+  let schedules = list_publish_schedules(&state.pg_pool, workspace_id, view_id).await?;
+  Ok(Json(AppResponse::Ok().with_data(schedules)))
+}
+
+async fn cancel_publish_schedule_handler(
+  user_uuid: UserUuid,
+  path: web::Path<(Uuid, Uuid)>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<()>>> {
+  let (workspace_id, schedule_id) = path.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_role(&uid, &workspace_id.to_string(), AFRole::Member)
+    .await?;
+  cancel_pending_publish_schedule(&state.pg_pool, workspace_id, schedule_id).await?;
+  Ok(Json(AppResponse::Ok()))
+}
+
 async fn update_page_view_handler(
   user_uuid: UserUuid,
   path: web::Path<(Uuid, String)>,
@@ -1356,6 +1659,7 @@ async fn update_page_view_handler(
 ) -> Result<Json<AppResponse<()>>> {
   let uid = state.user_cache.get_user_uid(&user_uuid).await?;
   let (workspace_uuid, view_id) = path.into_inner();
+  workspace::ops::ensure_not_end_to_end_encrypted(&state.pg_pool, &workspace_uuid).await?;
   let icon = payload.icon.as_ref();
   let extra = payload
     .extra
@@ -1400,6 +1704,45 @@ async fn get_page_view_handler(
   Ok(Json(AppResponse::Ok().with_data(page_collab)))
 }
 
+async fn export_page_view_handler(
+  user_uuid: UserUuid,
+  path: web::Path<(Uuid, String)>,
+  query: web::Query<ExportPageQuery>,
+  state: Data<AppState>,
+) -> Result<HttpResponse> {
+  let (workspace_uuid, view_id) = path.into_inner();
+  workspace::ops::ensure_not_end_to_end_encrypted(&state.pg_pool, &workspace_uuid).await?;
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  let page = export_page(
+    &state.pg_pool,
+    &state.collab_access_control_storage,
+    state.config.appflowy_web_url.as_deref(),
+    uid,
+    workspace_uuid,
+    &view_id,
+    query.into_inner().format,
+  )
+  .await?;
+  let content_type = match page.format {
+    PageExportFormat::Markdown => "text/markdown; charset=utf-8",
+    PageExportFormat::Html => "text/html; charset=utf-8",
+    PageExportFormat::Json => "application/json",
+    PageExportFormat::Csv => "text/csv; charset=utf-8",
+  };
+  let mut resp = HttpResponse::Ok();
+  resp
+    .content_type(content_type)
+    .insert_header((actix_web::http::header::X_CONTENT_TYPE_OPTIONS, "nosniff"));
+  // The page is downloaded instead of being rendered from the origin of the server
+  if page.format == PageExportFormat::Html {
+    resp.insert_header((
+      actix_web::http::header::CONTENT_DISPOSITION,
+      format!("attachment; filename=\"{}.html\"", view_id),
+    ));
+  }
+  Ok(resp.body(page.content))
+}
+
 #[instrument(level = "trace", skip_all, err)]
 async fn get_collab_snapshot_handler(
   payload: Json<QuerySnapshotParams>,
@@ -1501,6 +1844,8 @@ async fn update_collab_handler(
 
   let create_params = CreateCollabParams::from((workspace_id.to_string(), params));
   let (params, workspace_id) = create_params.split();
+  let workspace_uuid = Uuid::parse_str(&workspace_id).map_err(AppError::from)?;
+  workspace::ops::ensure_not_end_to_end_encrypted(&state.pg_pool, &workspace_uuid).await?;
   if state
     .indexer_scheduler
     .can_index_workspace(&workspace_id)
@@ -1746,13 +2091,131 @@ async fn get_publish_namespace_handler(
   Ok(Json(AppResponse::Ok().with_data(namespace)))
 }
 
+async fn put_publish_custom_domain_handler(
+  user_uuid: UserUuid,
+  workspace_id: web::Path<Uuid>,
+  payload: Json<SetPublishCustomDomain>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<PublishCustomDomain>>> {
+  let workspace_id = workspace_id.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_role(&uid, &workspace_id.to_string(), AFRole::Owner)
+    .await?;
+  let custom_domain =
+    set_workspace_custom_domain(&state.pg_pool, &workspace_id, &payload.domain).await?;
+  Ok(Json(AppResponse::Ok().with_data(custom_domain)))
+}
+
+async fn get_publish_custom_domain_handler(
+  user_uuid: UserUuid,
+  workspace_id: web::Path<Uuid>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<PublishCustomDomain>>> {
+  let workspace_id = workspace_id.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_role(&uid, &workspace_id.to_string(), AFRole::Owner)
+    .await?;
+  let custom_domain = get_workspace_custom_domain(&state.pg_pool, &workspace_id).await?;
+  Ok(Json(AppResponse::Ok().with_data(custom_domain)))
+}
+
+async fn delete_publish_custom_domain_handler(
+  user_uuid: UserUuid,
+  workspace_id: web::Path<Uuid>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<()>>> {
+  let workspace_id = workspace_id.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_role(&uid, &workspace_id.to_string(), AFRole::Owner)
+    .await?;
+  remove_workspace_custom_domain(&state.pg_pool, &workspace_id).await?;
+  Ok(Json(AppResponse::Ok()))
+}
+
+async fn verify_publish_custom_domain_handler(
+  user_uuid: UserUuid,
+  workspace_id: web::Path<Uuid>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<PublishCustomDomain>>> {
+  let workspace_id = workspace_id.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_role(&uid, &workspace_id.to_string(), AFRole::Owner)
+    .await?;
+  let custom_domain = verify_workspace_custom_domain(
+    &state.pg_pool,
+    state.txt_record_resolver.as_ref(),
+    &workspace_id,
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok().with_data(custom_domain)))
+}
+
+/// Check the access restrictions of a published view, with the password given in the
+/// `X-Publish-Password` header.
+async fn enforce_published_collab_access(
+  state: &AppState,
+  user_uuid: Option<Uuid>,
+  req: &HttpRequest,
+  access: Option<AFPublishedCollabAccess>,
+) -> Result<(), AppError> {
+  // Views that are not published are reported as not found when they are fetched
+  let Some(access) = access else {
+    return Ok(());
+  };
+  let uid = optional_uid(state, user_uuid).await?;
+  check_published_collab_access(
+    state.workspace_access_control.as_ref(),
+    &access,
+    uid,
+    publish_password_from_headers(req.headers()),
+    req.connection_info().realip_remote_addr(),
+  )
+  .await
+}
+
+/// Check the access restrictions of the published view that the comments and reactions belong
+/// to.
+async fn enforce_published_view_access(
+  state: &AppState,
+  user_uuid: Option<Uuid>,
+  req: &HttpRequest,
+  view_id: &Uuid,
+) -> Result<(), AppError> {
+  let access = select_published_collab_access_for_view_id(&state.pg_pool, view_id).await?;
+  enforce_published_collab_access(state, user_uuid, req, access).await
+}
+
+async fn optional_uid(state: &AppState, user_uuid: Option<Uuid>) -> Result<Option<i64>, AppError> {
+  match user_uuid {
+    Some(user_uuid) => Ok(Some(state.user_cache.get_user_uid(&user_uuid).await?)),
+    None => Ok(None),
+  }
+}
+
 async fn get_default_published_collab_info_meta_handler(
   publish_namespace: web::Path<String>,
+  optional_user_uuid: OptionalUserUuid,
   state: Data<AppState>,
+  req: HttpRequest,
 ) -> Result<Json<AppResponse<PublishInfoMeta<serde_json::Value>>>> {
-  let publish_namespace = publish_namespace.into_inner();
+  let publish_namespace = resolve_publish_namespace(
+    &state.pg_pool,
+    req.connection_info().host(),
+    publish_namespace.into_inner(),
+  )
+  .await?;
   let (info, meta) =
     get_workspace_default_publish_view_info_meta(&state.pg_pool, &publish_namespace).await?;
+  let access = select_published_collab_access_for_view_id(&state.pg_pool, &info.view_id).await?;
+  enforce_published_collab_access(&state, optional_user_uuid.as_uuid(), &req, access).await?;
   Ok(Json(
     AppResponse::Ok().with_data(PublishInfoMeta { info, meta }),
   ))
@@ -1760,9 +2223,20 @@ async fn get_default_published_collab_info_meta_handler(
 
 async fn get_v1_published_collab_handler(
   path_param: web::Path<(String, String)>,
+  optional_user_uuid: OptionalUserUuid,
   state: Data<AppState>,
+  req: HttpRequest,
 ) -> Result<Json<AppResponse<serde_json::Value>>> {
   let (workspace_namespace, publish_name) = path_param.into_inner();
+  let workspace_namespace = resolve_publish_namespace(
+    &state.pg_pool,
+    req.connection_info().host(),
+    workspace_namespace,
+  )
+  .await?;
+  let access =
+    select_published_collab_access(&state.pg_pool, &workspace_namespace, &publish_name).await?;
+  enforce_published_collab_access(&state, optional_user_uuid.as_uuid(), &req, access).await?;
   let metadata = state
     .published_collab_store
     .get_collab_metadata(&workspace_namespace, &publish_name)
@@ -1772,13 +2246,42 @@ async fn get_v1_published_collab_handler(
 
 async fn get_published_collab_blob_handler(
   path_param: web::Path<(String, String)>,
+  optional_user_uuid: OptionalUserUuid,
   state: Data<AppState>,
+  req: HttpRequest,
 ) -> Result<Vec<u8>> {
   let (publish_namespace, publish_name) = path_param.into_inner();
+  let publish_namespace = resolve_publish_namespace(
+    &state.pg_pool,
+    req.connection_info().host(),
+    publish_namespace,
+  )
+  .await?;
+  let access =
+    select_published_collab_access(&state.pg_pool, &publish_namespace, &publish_name).await?;
+  let published_view = access
+    .as_ref()
+    .map(|access| (access.workspace_id, access.view_id));
+  enforce_published_collab_access(&state, optional_user_uuid.as_uuid(), &req, access).await?;
   let collab_data = state
     .published_collab_store
     .get_collab_blob_by_publish_namespace(&publish_namespace, &publish_name)
     .await?;
+  if let Some((workspace_id, view_id)) = published_view {
+    let connection_info = req.connection_info().clone();
+    let headers = req.headers();
+    let visit = PublishedViewVisit {
+      client_ip: connection_info.realip_remote_addr(),
+      user_agent: headers
+        .get(actix_web::http::header::USER_AGENT)
+        .and_then(|value| value.to_str().ok()),
+      referrer: headers
+        .get(actix_web::http::header::REFERER)
+        .and_then(|value| value.to_str().ok()),
+      host: connection_info.host(),
+    };
+    record_published_view_visit(&state.pg_pool, &workspace_id, &view_id, visit).await;
+  }
   Ok(collab_data)
 }
 
@@ -1787,6 +2290,7 @@ async fn post_published_duplicate_handler(
   workspace_id: web::Path<String>,
   state: Data<AppState>,
   params: Json<PublishedDuplicate>,
+  req: HttpRequest,
 ) -> Result<Json<AppResponse<DuplicatePublishedPageResponse>>> {
   let uid = state.user_cache.get_user_uid(&user_uuid).await?;
   state
@@ -1794,6 +2298,19 @@ async fn post_published_duplicate_handler(
     .enforce_action(&uid, &workspace_id.to_string(), Action::Write)
     .await?;
   let params = params.into_inner();
+  let published_view_id = Uuid::parse_str(&params.published_view_id).map_err(AppError::from)?;
+  let access =
+    select_published_collab_access_for_view_id(&state.pg_pool, &published_view_id).await?;
+  if let Some(access) = access {
+    check_published_collab_access(
+      state.workspace_access_control.as_ref(),
+      &access,
+      Some(uid),
+      publish_password_from_headers(req.headers()),
+      req.connection_info().realip_remote_addr(),
+    )
+    .await?;
+  }
   let root_view_id_for_duplicate =
     biz::workspace::publish_dup::duplicate_published_collab_to_workspace(
       &state.pg_pool,
@@ -1859,8 +2376,10 @@ async fn get_published_collab_comment_handler(
   view_id: web::Path<Uuid>,
   optional_user_uuid: OptionalUserUuid,
   state: Data<AppState>,
+  req: HttpRequest,
 ) -> Result<JsonAppResponse<GlobalComments>> {
   let view_id = view_id.into_inner();
+  enforce_published_view_access(&state, optional_user_uuid.as_uuid(), &req, &view_id).await?;
   let comments =
     get_comments_on_published_view(&state.pg_pool, &view_id, &optional_user_uuid).await?;
   let resp = GlobalComments { comments };
@@ -1872,8 +2391,10 @@ async fn post_published_collab_comment_handler(
   view_id: web::Path<Uuid>,
   state: Data<AppState>,
   data: Json<CreateGlobalCommentParams>,
+  req: HttpRequest,
 ) -> Result<JsonAppResponse<()>> {
   let view_id = view_id.into_inner();
+  enforce_published_view_access(&state, Some(*user_uuid), &req, &view_id).await?;
   create_comment_on_published_view(
     &state.pg_pool,
     &view_id,
@@ -1890,18 +2411,23 @@ async fn delete_published_collab_comment_handler(
   view_id: web::Path<Uuid>,
   state: Data<AppState>,
   data: Json<DeleteGlobalCommentParams>,
+  req: HttpRequest,
 ) -> Result<JsonAppResponse<()>> {
   let view_id = view_id.into_inner();
+  enforce_published_view_access(&state, Some(*user_uuid), &req, &view_id).await?;
   remove_comment_on_published_view(&state.pg_pool, &view_id, &data.comment_id, &user_uuid).await?;
   Ok(Json(AppResponse::Ok()))
 }
 
 async fn get_published_collab_reaction_handler(
   view_id: web::Path<Uuid>,
+  optional_user_uuid: OptionalUserUuid,
   query: web::Query<GetReactionQueryParams>,
   state: Data<AppState>,
+  req: HttpRequest,
 ) -> Result<JsonAppResponse<Reactions>> {
   let view_id = view_id.into_inner();
+  enforce_published_view_access(&state, optional_user_uuid.as_uuid(), &req, &view_id).await?;
   let reactions =
     get_reactions_on_published_view(&state.pg_pool, &view_id, &query.comment_id).await?;
   let resp = Reactions { reactions };
@@ -1913,8 +2439,10 @@ async fn post_published_collab_reaction_handler(
   view_id: web::Path<Uuid>,
   data: Json<CreateReactionParams>,
   state: Data<AppState>,
+  req: HttpRequest,
 ) -> Result<JsonAppResponse<()>> {
   let view_id = view_id.into_inner();
+  enforce_published_view_access(&state, Some(*user_uuid), &req, &view_id).await?;
   create_reaction_on_comment(
     &state.pg_pool,
     &data.comment_id,
@@ -1928,9 +2456,12 @@ async fn post_published_collab_reaction_handler(
 
 async fn delete_published_collab_reaction_handler(
   user_uuid: UserUuid,
+  view_id: web::Path<Uuid>,
   data: Json<DeleteReactionParams>,
   state: Data<AppState>,
+  req: HttpRequest,
 ) -> Result<JsonAppResponse<()>> {
+  enforce_published_view_access(&state, Some(*user_uuid), &req, &view_id).await?;
   remove_reaction_on_comment(
     &state.pg_pool,
     &data.comment_id,
@@ -1951,6 +2482,7 @@ async fn post_publish_collabs_handler(
   state: Data<AppState>,
 ) -> Result<Json<AppResponse<()>>> {
   let workspace_id = workspace_id.into_inner();
+  workspace::ops::ensure_not_end_to_end_encrypted(&state.pg_pool, &workspace_id).await?;
 
   let mut accumulator = Vec::<PublishCollabItem<serde_json::Value, Vec<u8>>>::new();
   let mut payload_reader: PayloadReader = PayloadReader::new(payload);
@@ -2213,12 +2745,23 @@ async fn get_trash_views_handler(
 
 async fn get_workspace_publish_outline_handler(
   publish_namespace: web::Path<String>,
+  optional_user_uuid: OptionalUserUuid,
   state: Data<AppState>,
+  req: HttpRequest,
 ) -> Result<Json<AppResponse<PublishedView>>> {
+  let publish_namespace = resolve_publish_namespace(
+    &state.pg_pool,
+    req.connection_info().host(),
+    publish_namespace.into_inner(),
+  )
+  .await?;
+  let uid = optional_uid(&state, optional_user_uuid.as_uuid()).await?;
   let published_view = biz::collab::ops::get_published_view(
     &state.collab_access_control_storage,
-    publish_namespace.into_inner(),
+    state.workspace_access_control.as_ref(),
+    publish_namespace,
     &state.pg_pool,
+    uid,
   )
   .await?;
   Ok(Json(AppResponse::Ok().with_data(published_view)))
@@ -2231,6 +2774,8 @@ async fn list_database_handler(
 ) -> Result<Json<AppResponse<Vec<AFDatabase>>>> {
   let uid = state.user_cache.get_user_uid(&user_uuid).await?;
   let workspace_id = workspace_id.into_inner();
+  let workspace_uuid = Uuid::parse_str(&workspace_id).map_err(AppError::from)?;
+  workspace::ops::ensure_not_end_to_end_encrypted(&state.pg_pool, &workspace_uuid).await?;
   let dbs = biz::collab::ops::list_database(
     &state.pg_pool,
     &state.collab_access_control_storage,
@@ -2247,6 +2792,8 @@ async fn list_database_row_id_handler(
   state: Data<AppState>,
 ) -> Result<Json<AppResponse<Vec<AFDatabaseRow>>>> {
   let (workspace_id, db_id) = path_param.into_inner();
+  let workspace_uuid = Uuid::parse_str(&workspace_id).map_err(AppError::from)?;
+  workspace::ops::ensure_not_end_to_end_encrypted(&state.pg_pool, &workspace_uuid).await?;
   let uid = state.user_cache.get_user_uid(&user_uuid).await?;
 
   state
@@ -2270,6 +2817,8 @@ async fn post_database_row_handler(
   add_database_row: Json<AddDatatabaseRow>,
 ) -> Result<Json<AppResponse<String>>> {
   let (workspace_id, db_id) = path_param.into_inner();
+  let workspace_uuid = Uuid::parse_str(&workspace_id).map_err(AppError::from)?;
+  workspace::ops::ensure_not_end_to_end_encrypted(&state.pg_pool, &workspace_uuid).await?;
   let uid = state.user_cache.get_user_uid(&user_uuid).await?;
   state
     .workspace_access_control
@@ -2299,6 +2848,8 @@ async fn put_database_row_handler(
   upsert_db_row: Json<UpsertDatatabaseRow>,
 ) -> Result<Json<AppResponse<String>>> {
   let (workspace_id, db_id) = path_param.into_inner();
+  let workspace_uuid = Uuid::parse_str(&workspace_id).map_err(AppError::from)?;
+  workspace::ops::ensure_not_end_to_end_encrypted(&state.pg_pool, &workspace_uuid).await?;
   let uid = state.user_cache.get_user_uid(&user_uuid).await?;
   state
     .workspace_access_control
@@ -2345,6 +2896,8 @@ async fn get_database_fields_handler(
   state: Data<AppState>,
 ) -> Result<Json<AppResponse<Vec<AFDatabaseField>>>> {
   let (workspace_id, db_id) = path_param.into_inner();
+  let workspace_uuid = Uuid::parse_str(&workspace_id).map_err(AppError::from)?;
+  workspace::ops::ensure_not_end_to_end_encrypted(&state.pg_pool, &workspace_uuid).await?;
   let uid = state.user_cache.get_user_uid(&user_uuid).await?;
   state
     .workspace_access_control
@@ -2368,6 +2921,8 @@ async fn post_database_fields_handler(
   field: Json<AFInsertDatabaseField>,
 ) -> Result<Json<AppResponse<String>>> {
   let (workspace_id, db_id) = path_param.into_inner();
+  let workspace_uuid = Uuid::parse_str(&workspace_id).map_err(AppError::from)?;
+  workspace::ops::ensure_not_end_to_end_encrypted(&state.pg_pool, &workspace_uuid).await?;
   let uid = state.user_cache.get_user_uid(&user_uuid).await?;
   state
     .workspace_access_control
@@ -2394,6 +2949,8 @@ async fn list_database_row_id_updated_handler(
   param: web::Query<ListDatabaseRowUpdatedParam>,
 ) -> Result<Json<AppResponse<Vec<DatabaseRowUpdatedItem>>>> {
   let (workspace_id, db_id) = path_param.into_inner();
+  let workspace_uuid = Uuid::parse_str(&workspace_id).map_err(AppError::from)?;
+  workspace::ops::ensure_not_end_to_end_encrypted(&state.pg_pool, &workspace_uuid).await?;
   let uid = state.user_cache.get_user_uid(&user_uuid).await?;
 
   state
@@ -2429,11 +2986,10 @@ async fn list_database_row_details_handler(
   let with_doc = list_db_row_query.with_doc.unwrap_or_default();
   let row_ids = list_db_row_query.into_ids();
 
-  if let Err(e) = Uuid::parse_str(&workspace_id) {
-    return Err(
-      AppError::InvalidRequest(format!("invalid workspace id `{}`: {}", db_id, e)).into(),
-    );
-  }
+  let workspace_uuid = Uuid::parse_str(&workspace_id).map_err(|e| {
+    AppError::InvalidRequest(format!("invalid workspace id `{}`: {}", workspace_id, e))
+  })?;
+  workspace::ops::ensure_not_end_to_end_encrypted(&state.pg_pool, &workspace_uuid).await?;
   if let Err(e) = Uuid::parse_str(&db_id) {
     return Err(AppError::InvalidRequest(format!("invalid database id `{}`: {}", db_id, e)).into());
   }
@@ -2722,3 +3278,59 @@ async fn delete_quick_note_handler(
   delete_quick_note(&state.pg_pool, quick_note_id).await?;
   Ok(Json(AppResponse::Ok()))
 }
+
+async fn get_publish_analytics_handler(
+  user_uuid: UserUuid,
+  workspace_id: web::Path<Uuid>,
+  state: Data<AppState>,
+  query: web::Query<PublishAnalyticsQueryParams>,
+) -> Result<JsonAppResponse<PublishAnalytics>> {
+  let workspace_id = workspace_id.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_role(&uid, &workspace_id.to_string(), AFRole::Owner)
+    .await?;
+  let analytics = get_publish_analytics(&state.pg_pool, &workspace_id, query.into_inner()).await?;
+  Ok(Json(AppResponse::Ok().with_data(analytics)))
+}
+
+async fn list_audit_logs_handler(
+  user_uuid: UserUuid,
+  workspace_id: web::Path<Uuid>,
+  state: Data<AppState>,
+  query: web::Query<ListAuditLogsQueryParams>,
+) -> Result<JsonAppResponse<AuditLogs>> {
+  let workspace_id = workspace_id.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_role(&uid, &workspace_id.to_string(), AFRole::Owner)
+    .await?;
+  let audit_logs = list_audit_logs(&state.pg_pool, &workspace_id, query.into_inner()).await?;
+  Ok(Json(AppResponse::Ok().with_data(audit_logs)))
+}
+
+async fn export_audit_logs_csv_handler(
+  user_uuid: UserUuid,
+  workspace_id: web::Path<Uuid>,
+  state: Data<AppState>,
+  query: web::Query<ListAuditLogsQueryParams>,
+) -> Result<HttpResponse> {
+  let workspace_id = workspace_id.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_role(&uid, &workspace_id.to_string(), AFRole::Owner)
+    .await?;
+  let csv = export_audit_logs_csv(state.pg_pool.clone(), workspace_id, query.into_inner())?;
+  Ok(
+    HttpResponse::Ok()
+      .content_type("text/csv; charset=utf-8")
+      .insert_header((
+        actix_web::http::header::CONTENT_DISPOSITION,
+        format!("attachment; filename=\"audit-log-{}.csv\"", workspace_id),
+      ))
+      .streaming(csv),
+  )
+}
@@ -17,7 +17,8 @@ use appflowy_collaborate::actix_ws::client::rt_client::RealtimeClient;
 use appflowy_collaborate::actix_ws::server::RealtimeServerActor;
 use appflowy_collaborate::collab::storage::CollabAccessControlStorage;
 use authentication::jwt::{authorization_from_token, UserUuid};
-use collab_rt_entity::user::{AFUserChange, RealtimeUser, UserMessage};
+use authentication::sso::sso_restricted_workspaces;
+use collab_rt_entity::user::{AFAccessRequestChange, AFUserChange, RealtimeUser, UserMessage};
 use collab_rt_entity::RealtimeMessage;
 use shared_entity::response::AppResponseError;
 
@@ -116,6 +117,11 @@ async fn start_connect(
   connect_at: i64,
 ) -> Result<HttpResponse> {
   let auth = authorization_from_token(access_token.as_str(), jwt_secret)?;
+  let sso_restricted_workspaces = sso_restricted_workspaces(&state.pg_pool, &auth)
+    .await?
+    .iter()
+    .map(|workspace_id| workspace_id.to_string())
+    .collect();
   let user_uuid = UserUuid::from_auth(auth)?;
   let result = state.user_cache.get_user_uid(&user_uuid).await;
 
@@ -143,10 +149,13 @@ async fn start_connect(
         client_app_version,
         external_source,
         10,
-      );
+      )
+      .with_sso_restricted_workspaces(sso_restricted_workspaces);
 
       // Receive user change notifications and send them to the client.
-      listen_on_user_change(state, uid, tx);
+      listen_on_user_change(state, uid, tx.clone());
+      // Receive access requests of the workspaces owned by the user.
+      listen_on_access_request_change(state, uid, tx);
 
       match ws::WsResponseBuilder::new(client, request, payload)
         .frame_size(MAX_FRAME_SIZE * 2)
@@ -194,6 +203,31 @@ fn listen_on_user_change(state: &Data<AppState>, uid: i64, tx: Sender<RealtimeMe
   });
 }
 
+fn listen_on_access_request_change(
+  state: &Data<AppState>,
+  owner_uid: i64,
+  tx: Sender<RealtimeMessage>,
+) {
+  let mut access_request_recv = state
+    .pg_listeners
+    .subscribe_access_request_change(owner_uid);
+  actix::spawn(async move {
+    while let Some(notification) = access_request_recv.recv().await {
+      trace!("Receive access request change: {:?}", notification);
+      let msg = UserMessage::AccessRequestChange(AFAccessRequestChange {
+        request_id: notification.request_id.to_string(),
+        workspace_id: notification.workspace_id.to_string(),
+        view_id: notification.view_id.to_string(),
+        requester_uid: notification.requester_uid,
+        status: notification.status.into(),
+      });
+      if tx.send(RealtimeMessage::User(msg)).await.is_err() {
+        break;
+      }
+    }
+  });
+}
+
 struct ConnectInfo {
   access_token: String,
   client_version: Version,
@@ -41,16 +41,21 @@ use appflowy_collaborate::snapshot::SnapshotControl;
 use appflowy_collaborate::CollaborationServer;
 use collab_stream::metrics::CollabStreamMetrics;
 use collab_stream::stream_router::{StreamRouter, StreamRouterOptions};
-use database::file::s3_client_impl::{AwsS3BucketClientImpl, S3BucketStorage};
+use database::encryption::CollabEncryption;
+use database::file::bucket_client_impl::{BucketClientImpl, BucketStorageImpl};
+use database::file::fs_client_impl::LocalFsBucketClientImpl;
+use database::file::s3_client_impl::AwsS3BucketClientImpl;
 use indexer::collab_indexer::IndexerProvider;
 use indexer::scheduler::{IndexerConfiguration, IndexerScheduler};
 use infra::env_util::get_env_var;
 use mailer::sender::Mailer;
 use snowflake::Snowflake;
 
+use crate::api::access_control::access_control_scope;
 use crate::api::access_request::access_request_scope;
 use crate::api::ai::ai_completion_scope;
 use crate::api::chat::chat_scope;
+use crate::api::data_export::data_export_scope;
 use crate::api::data_import::data_import_scope;
 use crate::api::file_storage::file_storage_scope;
 use crate::api::metrics::metrics_scope;
@@ -60,12 +65,15 @@ use crate::api::template::template_scope;
 use crate::api::user::user_scope;
 use crate::api::workspace::{collab_scope, workspace_scope};
 use crate::api::ws::ws_scope;
+use crate::biz::file_storage::malware_scan::{ClamAvScanner, MalwareScanner};
 use crate::biz::pg_listener::PgListeners;
 use crate::biz::workspace::publish::{
   PublishedCollabPostgresStore, PublishedCollabS3StoreWithPostgresFallback, PublishedCollabStore,
 };
+use crate::biz::workspace::publish_domain::{DnsOverHttpsResolver, TxtRecordResolver};
 use crate::config::config::{
-  Config, DatabaseSetting, GoTrueSetting, PublishedCollabStorageBackend, S3Setting,
+  BlobStorageBackend, Config, DatabaseSetting, GoTrueSetting, MalwareScanBackend,
+  PublishedCollabStorageBackend, S3Setting,
 };
 use crate::mailer::AFCloudMailer;
 use crate::middleware::metrics_mw::MetricsMiddleware;
@@ -130,6 +138,7 @@ pub async fn run_actix_server(
     Duration::from_secs(config.collab.group_persistence_interval_secs),
     Duration::from_secs(config.collab.group_prune_grace_period_secs),
     state.indexer_scheduler.clone(),
+    state.pg_pool.clone(),
   )
   .await
   .unwrap();
@@ -158,7 +167,9 @@ pub async fn run_actix_server(
       .service(search_scope())
       .service(template_scope())
       .service(data_import_scope())
+      .service(data_export_scope())
       .service(access_request_scope())
+      .service(access_control_scope())
       .route("/health", web::get().to(health_check))
       .app_data(Data::new(state.metrics.registry.clone()))
       .app_data(Data::new(state.metrics.request_metrics.clone()))
@@ -166,6 +177,7 @@ pub async fn run_actix_server(
       .app_data(Data::new(state.metrics.access_control_metrics.clone()))
       .app_data(Data::new(realtime_server_actor.clone()))
       .app_data(Data::new(state.config.gotrue.jwt_secret.clone()))
+      .app_data(Data::new(state.pg_pool.clone()))
       .app_data(Data::new(state.clone()))
       .app_data(Data::new(storage.clone()))
       .app_data(Data::new(state.published_collab_store.clone()))
@@ -187,15 +199,9 @@ pub async fn init_state(config: &Config, rt_cmd_tx: CLCommandSender) -> Result<A
   migrate(&pg_pool).await?;
 
   // Bucket storage
-  info!("Setting up S3 bucket...");
-  let s3_client = AwsS3BucketClientImpl::new(
-    get_aws_s3_client(&config.s3).await?,
-    config.s3.bucket.clone(),
-    config.s3.minio_url.clone(),
-    config.s3.presigned_url_endpoint.clone(),
-  );
-  let bucket_storage = Arc::new(S3BucketStorage::from_bucket_impl(
-    s3_client.clone(),
+  let bucket_client = get_bucket_client(config).await?;
+  let bucket_storage = Arc::new(BucketStorageImpl::new(
+    bucket_client.clone(),
     pg_pool.clone(),
   ));
 
@@ -215,11 +221,28 @@ pub async fn init_state(config: &Config, rt_cmd_tx: CLCommandSender) -> Result<A
         Arc::new(PublishedCollabS3StoreWithPostgresFallback::new(
           metrics.published_collab_metrics.clone(),
           pg_pool.clone(),
-          s3_client.clone(),
+          bucket_client.clone(),
         ))
       },
     };
 
+  let txt_record_resolver: Arc<dyn TxtRecordResolver> = Arc::new(DnsOverHttpsResolver::new(
+    config.published_collab.dns_over_https_url.clone(),
+  ));
+
+  let malware_scanner: Option<Arc<dyn MalwareScanner>> = match config.malware_scan.backend {
+    MalwareScanBackend::Disabled => None,
+    MalwareScanBackend::ClamAv => {
+      info!(
+        "Scanning uploaded files with ClamAV at {}",
+        config.malware_scan.clamav_address
+      );
+      Some(Arc::new(ClamAvScanner::new(
+        config.malware_scan.clamav_address.clone(),
+      )))
+    },
+  };
+
   // Gotrue
   info!("Connecting to GoTrue...");
   let gotrue_client = get_gotrue_client(&config.gotrue).await?;
@@ -263,16 +286,33 @@ pub async fn init_state(config: &Config, rt_cmd_tx: CLCommandSender) -> Result<A
     };
   let realtime_access_control: Arc<dyn RealtimeAccessControl> =
     if config.access_control.is_enabled && config.access_control.enable_realtime_access_control {
-      Arc::new(RealtimeCollabAccessControlImpl::new(access_control))
+      Arc::new(RealtimeCollabAccessControlImpl::new(access_control.clone()))
     } else {
       Arc::new(NoOpsRealtimeCollabAccessControlImpl::new())
     };
+  let collab_encryption = CollabEncryption::new(
+    pg_pool.clone(),
+    config.collab.encryption_master_keys.expose_secret(),
+  )?;
+  if let Some(collab_encryption) = collab_encryption.clone() {
+    info!("Collabs are encrypted at rest");
+    // Wrap the data keys with the current master key, in case the master key was rotated
+    tokio::spawn(async move {
+      if let Err(err) = collab_encryption.rewrap_data_keys().await {
+        error!(
+          "Failed to wrap the data keys with the current master key: {}",
+          err
+        );
+      }
+    });
+  }
   let collab_cache = CollabCache::new(
     redis_conn_manager.clone(),
     pg_pool.clone(),
-    s3_client.clone(),
+    bucket_client.clone(),
     metrics.collab_metrics.clone(),
     config.collab.s3_collab_threshold as usize,
+    collab_encryption.clone(),
   );
 
   let collab_storage_access_control = CollabStorageAccessControlImpl {
@@ -282,8 +322,9 @@ pub async fn init_state(config: &Config, rt_cmd_tx: CLCommandSender) -> Result<A
   };
   let snapshot_control = SnapshotControl::new(
     pg_pool.clone(),
-    s3_client.clone(),
+    bucket_client.clone(),
     metrics.collab_metrics.clone(),
+    collab_encryption.clone(),
   )
   .await;
   let collab_access_control_storage = Arc::new(CollabStorageImpl::new(
@@ -331,15 +372,19 @@ pub async fn init_state(config: &Config, rt_cmd_tx: CLCommandSender) -> Result<A
     collab_access_control,
     workspace_access_control,
     realtime_access_control,
+    access_control,
     bucket_storage,
     published_collab_store,
-    bucket_client: s3_client,
+    txt_record_resolver,
+    bucket_client,
     pg_listeners,
     metrics,
     gotrue_admin,
     mailer,
     ai_client: appflowy_ai_client,
     indexer_scheduler,
+    malware_scanner,
+    collab_encryption,
   })
 }
 
@@ -382,6 +427,29 @@ async fn get_redis_client(
   Ok((manager, router.into()))
 }
 
+pub async fn get_bucket_client(config: &Config) -> Result<BucketClientImpl, Error> {
+  match config.blob_storage.backend {
+    BlobStorageBackend::S3 => {
+      info!("Setting up S3 bucket...");
+      let s3_client = AwsS3BucketClientImpl::new(
+        get_aws_s3_client(&config.s3).await?,
+        config.s3.bucket.clone(),
+        config.s3.minio_url.clone(),
+        config.s3.presigned_url_endpoint.clone(),
+      );
+      Ok(s3_client.into())
+    },
+    BlobStorageBackend::LocalFs => {
+      info!(
+        "Using local file system as the blob storage backend: {}",
+        config.blob_storage.local_path
+      );
+      let fs_client = LocalFsBucketClientImpl::new(&config.blob_storage.local_path).await?;
+      Ok(fs_client.into())
+    },
+  }
+}
+
 pub async fn get_aws_s3_client(s3_setting: &S3Setting) -> Result<aws_sdk_s3::Client, Error> {
   let credentials = Credentials::new(
     s3_setting.access_key.clone(),
@@ -0,0 +1,48 @@
+use access_control::act::{Action, Acts};
+use access_control::casbin::access::AccessControl;
+use access_control::entity::ObjectType;
+use app_error::AppError;
+use database_entity::dto::{
+  AFAccessLevel, AFRole, ExplainPolicyDecisionQueryParams, PolicyDecisionExplanation,
+  PolicyObjectType,
+};
+
+pub async fn explain_policy_decision(
+  access_control: &AccessControl,
+  params: ExplainPolicyDecisionQueryParams,
+) -> Result<PolicyDecisionExplanation, AppError> {
+  let ExplainPolicyDecisionQueryParams {
+    uid,
+    object_type,
+    object_id,
+    action,
+  } = params;
+  let obj = match object_type {
+    PolicyObjectType::Workspace => ObjectType::Workspace(object_id),
+    PolicyObjectType::Collab => ObjectType::Collab(object_id),
+  };
+
+  // `from_enforce_act` falls back to the lowest permission for unknown values, which would
+  // explain a different request than the one asked for, so the action is matched explicitly.
+  match action.as_str() {
+    "read" | "write" | "delete" => {
+      access_control
+        .explain(&uid, obj, Action::from_enforce_act(&action))
+        .await
+    },
+    "r:1" | "r:2" | "r:3" => {
+      access_control
+        .explain(&uid, obj, AFRole::from_enforce_act(&action))
+        .await
+    },
+    "l:10" | "l:20" | "l:30" | "l:50" => {
+      access_control
+        .explain(&uid, obj, AFAccessLevel::from_enforce_act(&action))
+        .await
+    },
+    _ => Err(AppError::InvalidRequest(format!(
+      "Unknown action: {}, expected read, write, delete, r:<role> or l:<access level>",
+      action
+    ))),
+  }
+}
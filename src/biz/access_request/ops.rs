@@ -2,6 +2,7 @@ use std::ops::DerefMut;
 use std::sync::Arc;
 
 use crate::biz::collab::utils::get_latest_collab_folder;
+use crate::biz::workspace::audit_log::record_audit_log;
 use crate::mailer::AFCloudMailer;
 use crate::{
   biz::collab::folder_view::{to_dto_view_icon, to_dto_view_layout},
@@ -11,29 +12,67 @@ use access_control::workspace::WorkspaceAccessControl;
 use anyhow::Context;
 use app_error::AppError;
 use appflowy_collaborate::collab::storage::CollabAccessControlStorage;
+use chrono::{DateTime, Duration, Utc};
 use database::{
   access_request::{
-    insert_new_access_request, select_access_request_by_request_id, update_access_request_status,
+    delete_access_request_auto_approval_rule, delete_stale_access_request,
+    insert_new_access_request, select_access_request_auto_approval_rule_for_email,
+    select_access_request_auto_approval_rules, select_access_request_by_request_id,
+    update_access_request_status,
   },
   collab::GetCollabOrigin,
   pg_row::AFAccessRequestStatusColumn,
-  workspace::upsert_workspace_member_with_txn,
+  workspace::{select_workspace_member, upsert_workspace_member_with_txn},
 };
-use database_entity::dto::AFRole;
+use database_entity::dto::{
+  AFRole, AccessRequestAutoApprovalRule, AccessRequestStatus, AccessRequestWithViewId, AuditAction,
+};
+use serde_json::json;
 use shared_entity::dto::access_request_dto::{AccessRequest, AccessRequestView};
 use sqlx::PgPool;
 use uuid::Uuid;
 
+#[allow(clippy::too_many_arguments)]
 pub async fn create_access_request(
   pg_pool: &PgPool,
+  workspace_access_control: Arc<dyn WorkspaceAccessControl>,
   mailer: AFCloudMailer,
   appflowy_web_url: &str,
+  expire_days: i64,
   workspace_id: Uuid,
   view_id: Uuid,
   uid: i64,
 ) -> Result<Uuid, AppError> {
+  let expire_before = Utc::now() - Duration::days(expire_days);
+  delete_stale_access_request(pg_pool, workspace_id, view_id, uid, expire_before).await?;
   let request_id = insert_new_access_request(pg_pool, workspace_id, view_id, uid).await?;
   let access_request = select_access_request_by_request_id(pg_pool, request_id).await?;
+
+  let auto_approval_rule = select_access_request_auto_approval_rule_for_email(
+    pg_pool,
+    workspace_id,
+    &access_request.requester.email,
+  )
+  .await?;
+  if let Some(rule) = auto_approval_rule {
+    tracing::info!(
+      "access request {} auto approved by rule for domain {}",
+      request_id,
+      rule.email_domain
+    );
+    approve_access_request_with_role(
+      pg_pool,
+      workspace_access_control,
+      mailer,
+      appflowy_web_url,
+      access_request,
+      rule.role,
+      None,
+    )
+    .await?;
+    return Ok(request_id);
+  }
+
   let cloned_mailer = mailer.clone();
   let approve_url = format!(
     "{}/app/approve-request?request_id={}",
@@ -72,6 +111,7 @@ pub async fn create_access_request(
 pub async fn get_access_request(
   pg_pool: &PgPool,
   collab_storage: &CollabAccessControlStorage,
+  expire_days: i64,
   access_request_id: Uuid,
   user_uid: i64,
 ) -> Result<AccessRequest, AppError> {
@@ -107,7 +147,11 @@ pub async fn get_access_request(
     workspace: access_request_with_view_id.workspace,
     requester: access_request_with_view_id.requester,
     view: access_request_view,
-    status: access_request_with_view_id.status,
+    status: if is_access_request_expired(&access_request_with_view_id, expire_days) {
+      AccessRequestStatus::Expired
+    } else {
+      access_request_with_view_id.status
+    },
     created_at: access_request_with_view_id.created_at,
   };
   Ok(access_request)
@@ -119,6 +163,7 @@ pub async fn approve_or_reject_access_request(
   workspace_access_control: Arc<dyn WorkspaceAccessControl>,
   mailer: AFCloudMailer,
   appflowy_web_url: &str,
+  expire_days: i64,
   request_id: Uuid,
   uid: i64,
   is_approved: bool,
@@ -132,9 +177,67 @@ pub async fn approve_or_reject_access_request(
     )
     .await?;
 
-  let mut txn = pg_pool.begin().await.context("approving request")?;
-  let role = AFRole::Member;
+  if is_access_request_expired(&access_request, expire_days) {
+    update_access_request_status(pg_pool, request_id, AFAccessRequestStatusColumn::Expired).await?;
+    return Err(AppError::AccessRequestExpired { request_id });
+  }
+
   if is_approved {
+    approve_access_request_with_role(
+      pg_pool,
+      workspace_access_control,
+      mailer,
+      appflowy_web_url,
+      access_request,
+      AFRole::Member,
+      Some(uid),
+    )
+    .await
+  } else {
+    update_access_request_status(pg_pool, request_id, AFAccessRequestStatusColumn::Rejected)
+      .await?;
+    record_audit_log(
+      pg_pool,
+      &access_request.workspace.workspace_id,
+      Some(uid),
+      AuditAction::RejectAccessRequest,
+      Some(&request_id.to_string()),
+      json!({
+        "view_id": access_request.view_id,
+        "requester_uid": access_request.requester.uid,
+        "requester_email": access_request.requester.email,
+      }),
+    )
+    .await;
+    Ok(())
+  }
+}
+
+async fn approve_access_request_with_role(
+  pg_pool: &PgPool,
+  workspace_access_control: Arc<dyn WorkspaceAccessControl>,
+  mailer: AFCloudMailer,
+  appflowy_web_url: &str,
+  access_request: AccessRequestWithViewId,
+  role: AFRole,
+  approver_uid: Option<i64>,
+) -> Result<(), AppError> {
+  let request_id = access_request.request_id;
+  let mut txn = pg_pool.begin().await.context("approving request")?;
+  // The role of a requester who is already a member is kept as is, both in the database and in
+  // the access control, so approving the request never downgrades or upgrades an existing member.
+  let is_member = match select_workspace_member(
+    txn.deref_mut(),
+    &access_request.requester.uid,
+    &access_request.workspace.workspace_id,
+  )
+  .await
+  {
+    Ok(_) => true,
+    Err(err) if err.is_record_not_found() => false,
+    Err(err) => return Err(err),
+  };
+  if !is_member {
     upsert_workspace_member_with_txn(
       &mut txn,
       &access_request.workspace.workspace_id,
@@ -149,42 +252,177 @@ pub async fn approve_or_reject_access_request(
         role.clone(),
       )
       .await?;
-    let cloned_mailer = mailer.clone();
-    let launch_workspace_url = format!(
-      "{}/app/{}",
-      appflowy_web_url, &access_request.workspace.workspace_id
-    );
-
-    // use default icon until we have workspace icon
-    let workspace_icon_url =
-      "https://miro.medium.com/v2/resize:fit:2400/1*mTPfm7CwU31-tLhtLNkyJw.png".to_string();
-    tokio::spawn(async move {
-      if let Err(err) = cloned_mailer
-        .send_workspace_access_request_approval_notification(
-          &access_request.requester.name,
-          &access_request.requester.email,
-          WorkspaceAccessRequestApprovedMailerParam {
-            workspace_name: access_request.workspace.workspace_name,
-            workspace_icon_url,
-            workspace_member_count: access_request.workspace.member_count.unwrap_or(0),
-            launch_workspace_url,
-          },
-        )
-        .await
-      {
-        tracing::error!(
-          "Failed to send access request approved notification email: {:?}",
-          err
-        );
-      };
-    });
   }
-  let status = if is_approved {
-    AFAccessRequestStatusColumn::Approved
-  } else {
-    AFAccessRequestStatusColumn::Rejected
-  };
-  update_access_request_status(txn.deref_mut(), request_id, status).await?;
+  update_access_request_status(
+    txn.deref_mut(),
+    request_id,
+    AFAccessRequestStatusColumn::Approved,
+  )
+  .await?;
   txn.commit().await.context("committing transaction")?;
+  record_audit_log(
+    pg_pool,
+    &access_request.workspace.workspace_id,
+    approver_uid,
+    AuditAction::ApproveAccessRequest,
+    Some(&request_id.to_string()),
+    json!({
+      "view_id": access_request.view_id,
+      "requester_uid": access_request.requester.uid,
+      "requester_email": access_request.requester.email,
+      "role": if is_member { None } else { Some(role) },
+      "already_member": is_member,
+    }),
+  )
+  .await;
+
+  let launch_workspace_url = format!(
+    "{}/app/{}",
+    appflowy_web_url, &access_request.workspace.workspace_id
+  );
+  // use default icon until we have workspace icon
+  let workspace_icon_url =
+    "https://miro.medium.com/v2/resize:fit:2400/1*mTPfm7CwU31-tLhtLNkyJw.png".to_string();
+  tokio::spawn(async move {
+    if let Err(err) = mailer
+      .send_workspace_access_request_approval_notification(
+        &access_request.requester.name,
+        &access_request.requester.email,
+        WorkspaceAccessRequestApprovedMailerParam {
+          workspace_name: access_request.workspace.workspace_name,
+          workspace_icon_url,
+          workspace_member_count: access_request.workspace.member_count.unwrap_or(0),
+          launch_workspace_url,
+        },
+      )
+      .await
+    {
+      tracing::error!(
+        "Failed to send access request approved notification email: {:?}",
+        err
+      );
+    };
+  });
   Ok(())
 }
+
+/// A pending access request is expired once it is older than `expire_days`.
+fn is_access_request_expired(access_request: &AccessRequestWithViewId, expire_days: i64) -> bool {
+  is_expired(
+    access_request.status,
+    access_request.created_at,
+    expire_days,
+    Utc::now(),
+  )
+}
+
+fn is_expired(
+  status: AccessRequestStatus,
+  created_at: DateTime<Utc>,
+  expire_days: i64,
+  now: DateTime<Utc>,
+) -> bool {
+  status == AccessRequestStatus::Expired
+    || (status == AccessRequestStatus::Pending && created_at < now - Duration::days(expire_days))
+}
+
+pub async fn get_access_request_auto_approval_rules(
+  pg_pool: &PgPool,
+  workspace_access_control: Arc<dyn WorkspaceAccessControl>,
+  workspace_id: Uuid,
+  uid: i64,
+) -> Result<Vec<AccessRequestAutoApprovalRule>, AppError> {
+  workspace_access_control
+    .enforce_role(&uid, &workspace_id.to_string(), AFRole::Owner)
+    .await?;
+  select_access_request_auto_approval_rules(pg_pool, workspace_id).await
+}
+
+pub async fn upsert_access_request_auto_approval_rule(
+  pg_pool: &PgPool,
+  workspace_access_control: Arc<dyn WorkspaceAccessControl>,
+  workspace_id: Uuid,
+  uid: i64,
+  rule: AccessRequestAutoApprovalRule,
+) -> Result<(), AppError> {
+  workspace_access_control
+    .enforce_role(&uid, &workspace_id.to_string(), AFRole::Owner)
+    .await?;
+  let email_domain = rule.email_domain.trim();
+  if email_domain.is_empty() || email_domain.contains('@') {
+    return Err(AppError::InvalidRequest(format!(
+      "Invalid email domain: {}",
+      rule.email_domain
+    )));
+  }
+  if rule.role == AFRole::Owner {
+    return Err(AppError::InvalidRequest(
+      "Access requests can not be auto approved with the owner role".to_string(),
+    ));
+  }
+  database::access_request::upsert_access_request_auto_approval_rule(
+    pg_pool,
+    workspace_id,
+    email_domain,
+    &rule.role,
+  )
+  .await
+}
+
+pub async fn remove_access_request_auto_approval_rule(
+  pg_pool: &PgPool,
+  workspace_access_control: Arc<dyn WorkspaceAccessControl>,
+  workspace_id: Uuid,
+  uid: i64,
+  email_domain: &str,
+) -> Result<(), AppError> {
+  workspace_access_control
+    .enforce_role(&uid, &workspace_id.to_string(), AFRole::Owner)
+    .await?;
+  delete_access_request_auto_approval_rule(pg_pool, workspace_id, email_domain.trim()).await
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn access_request_expiry_test() {
+    let now = Utc::now();
+    let expire_days = 7;
+    let recent = now - Duration::days(1);
+    let stale = now - Duration::days(8);
+
+    assert!(!is_expired(
+      AccessRequestStatus::Pending,
+      recent,
+      expire_days,
+      now
+    ));
+    assert!(is_expired(
+      AccessRequestStatus::Pending,
+      stale,
+      expire_days,
+      now
+    ));
+    assert!(is_expired(
+      AccessRequestStatus::Expired,
+      recent,
+      expire_days,
+      now
+    ));
+    // Resolved requests never expire
+    assert!(!is_expired(
+      AccessRequestStatus::Approved,
+      stale,
+      expire_days,
+      now
+    ));
+    assert!(!is_expired(
+      AccessRequestStatus::Rejected,
+      stale,
+      expire_days,
+      now
+    ));
+  }
+}
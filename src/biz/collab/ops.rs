@@ -60,7 +60,9 @@ use crate::biz::collab::folder_view::check_if_view_is_space;
 use crate::biz::collab::utils::get_database_row_doc_changes;
 use crate::biz::workspace::ops::broadcast_update_with_timeout;
 use crate::biz::workspace::page_view::update_workspace_folder_data;
+use crate::biz::workspace::publish_access::is_workspace_member;
 use access_control::collab::CollabAccessControl;
+use access_control::workspace::WorkspaceAccessControl;
 use anyhow::Context;
 use database_entity::dto::{
   AFCollabMember, InsertCollabMemberParams, QueryCollabMembers, UpdateCollabMemberParams,
@@ -447,12 +449,18 @@ pub async fn get_latest_workspace_database(
   Ok((workspace_database_oid, workspace_database))
 }
 
+/// Returns the outline of the published views of the namespace. The views restricted to the
+/// members of the workspace or protected by a password are only listed for the members.
 pub async fn get_published_view(
   collab_storage: &CollabAccessControlStorage,
+  workspace_access_control: &dyn WorkspaceAccessControl,
   publish_namespace: String,
   pg_pool: &PgPool,
+  uid: Option<i64>,
 ) -> Result<PublishedView, AppError> {
   let workspace_id = select_workspace_id_for_publish_namespace(pg_pool, &publish_namespace).await?;
+  let include_restricted_views =
+    is_workspace_member(workspace_access_control, &workspace_id, uid).await?;
   let folder = get_latest_collab_folder(
     collab_storage,
     GetCollabOrigin::Server,
@@ -464,6 +472,7 @@ pub async fn get_published_view(
   let publish_view_id_to_info_map: HashMap<String, PublishedViewInfo> =
     publish_view_ids_with_publish_info
       .into_iter()
+      .filter(|pv| include_restricted_views || !(pv.members_only || pv.password_protected))
       .map(|pv| {
         (
           pv.view_id.to_string(),
@@ -0,0 +1,181 @@
+use std::io::Cursor;
+
+use anyhow::anyhow;
+use app_error::AppError;
+use database::file::bucket_client_impl::BucketStorageImpl;
+use database::file::{BlobKey, BLOB_IMAGE_VARIANT_WIDTHS};
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageOutputFormat};
+use tracing::{trace, warn};
+
+/// Images larger than this are always served as is, as decoding them would be too expensive.
+pub const MAX_IMAGE_VARIANT_SOURCE_SIZE: i64 = 20 * 1024 * 1024;
+
+/// Images with more pixels than this are served as is. A small compressed image can declare huge
+/// dimensions, and decoding it would allocate memory for every pixel.
+const MAX_IMAGE_VARIANT_SOURCE_PIXELS: u64 = 40_000_000;
+
+const JPEG_QUALITY: u8 = 85;
+
+pub struct ImageVariant {
+  pub width: u32,
+  pub content: Vec<u8>,
+  pub content_type: &'static str,
+}
+
+/// Rounds the requested width up to the width of a variant, capped to the largest one.
+pub fn image_variant_width(requested_width: u32) -> u32 {
+  BLOB_IMAGE_VARIANT_WIDTHS
+    .iter()
+    .copied()
+    .find(|width| *width >= requested_width)
+    .unwrap_or(BLOB_IMAGE_VARIANT_WIDTHS[BLOB_IMAGE_VARIANT_WIDTHS.len() - 1])
+}
+
+/// Returns whether variants can be generated for a blob of the given content type. Animated and
+/// vector images are always served as is.
+pub fn is_resizable_image(content_type: &str) -> bool {
+  let mime = content_type
+    .split(';')
+    .next()
+    .unwrap_or_default()
+    .trim()
+    .to_ascii_lowercase();
+  matches!(
+    mime.as_str(),
+    "image/png" | "image/jpeg" | "image/jpg" | "image/webp" | "image/bmp"
+  )
+}
+
+/// JPEG images keep their format, the other images are resized to PNG to keep their transparency.
+fn variant_content_type(content_type: &str) -> &'static str {
+  if content_type.contains("jpeg") || content_type.contains("jpg") {
+    "image/jpeg"
+  } else {
+    "image/png"
+  }
+}
+
+/// Returns the variant of the image blob for the requested width, generating and storing it next
+/// to the original on first access. Returns `None` if the original should be served instead, i.e.
+/// when the image isn't wider than the variant or can't be decoded. In that case an empty variant
+/// is stored, so that the original isn't downloaded and decoded again on the next access.
+pub async fn get_or_create_image_variant(
+  bucket_storage: &BucketStorageImpl,
+  key: &impl BlobKey,
+  content_type: &str,
+  requested_width: u32,
+) -> Result<Option<ImageVariant>, AppError> {
+  let width = image_variant_width(requested_width);
+  let variant_content_type = variant_content_type(content_type);
+  match bucket_storage.get_blob_image_variant(key, width).await? {
+    Some(content) if content.is_empty() => return Ok(None),
+    Some(content) => {
+      return Ok(Some(ImageVariant {
+        width,
+        content,
+        content_type: variant_content_type,
+      }))
+    },
+    None => {},
+  }
+
+  let original = bucket_storage.get_blob(key).await?;
+  let content = tokio::task::spawn_blocking(move || {
+    resize_image(&original, width, variant_content_type == "image/jpeg")
+  })
+  .await
+  .map_err(|err| AppError::Internal(anyhow!("Failed to resize image: {}", err)))?;
+  let content = match content {
+    Ok(Some(content)) => content,
+    Ok(None) => {
+      store_original_marker(bucket_storage, key, width, variant_content_type).await;
+      return Ok(None);
+    },
+    Err(err) => {
+      warn!(
+        "Failed to generate variant of {}, serving the original: {}",
+        key.object_key(),
+        err
+      );
+      store_original_marker(bucket_storage, key, width, variant_content_type).await;
+      return Ok(None);
+    },
+  };
+
+  trace!(
+    "Generated {}px variant of {} ({} bytes)",
+    width,
+    key.object_key(),
+    content.len()
+  );
+  bucket_storage
+    .put_blob_image_variant(key, width, content.clone(), variant_content_type)
+    .await?;
+  Ok(Some(ImageVariant {
+    width,
+    content,
+    content_type: variant_content_type,
+  }))
+}
+
+/// Stores an empty variant, which tells that the original is served for this width.
+async fn store_original_marker(
+  bucket_storage: &BucketStorageImpl,
+  key: &impl BlobKey,
+  width: u32,
+  content_type: &str,
+) {
+  if let Err(err) = bucket_storage
+    .put_blob_image_variant(key, width, vec![], content_type)
+    .await
+  {
+    warn!(
+      "Failed to store the {}px variant marker of {}: {}",
+      width,
+      key.object_key(),
+      err
+    );
+  }
+}
+
+/// Resizes the image to the given width, keeping its aspect ratio. Returns `None` if the image
+/// isn't wider than the given width, as images are never upscaled, or if it has more pixels than
+/// [MAX_IMAGE_VARIANT_SOURCE_PIXELS]. The dimensions are read from the header of the image, so
+/// that these images are never decoded.
+fn resize_image(content: &[u8], width: u32, to_jpeg: bool) -> Result<Option<Vec<u8>>, AppError> {
+  let (image_width, image_height) = image::io::Reader::new(Cursor::new(content))
+    .with_guessed_format()
+    .map_err(|err| AppError::InvalidRequest(format!("Failed to read image: {}", err)))?
+    .into_dimensions()
+    .map_err(|err| AppError::InvalidRequest(format!("Failed to decode image: {}", err)))?;
+  if image_width <= width {
+    return Ok(None);
+  }
+  if image_width as u64 * image_height as u64 > MAX_IMAGE_VARIANT_SOURCE_PIXELS {
+    trace!(
+      "Image of {}x{} pixels is too large to be resized",
+      image_width,
+      image_height
+    );
+    return Ok(None);
+  }
+
+  let image = image::load_from_memory(content)
+    .map_err(|err| AppError::InvalidRequest(format!("Failed to decode image: {}", err)))?;
+
+  let resized = image.resize(width, u32::MAX, FilterType::Triangle);
+  let (resized, format) = if to_jpeg {
+    (
+      DynamicImage::ImageRgb8(resized.to_rgb8()),
+      ImageOutputFormat::Jpeg(JPEG_QUALITY),
+    )
+  } else {
+    (resized, ImageOutputFormat::Png)
+  };
+  let mut buf = Cursor::new(Vec::new());
+  resized
+    .write_to(&mut buf, format)
+    .map_err(|err| AppError::Internal(anyhow!("Failed to encode image: {}", err)))?;
+  Ok(Some(buf.into_inner()))
+}
@@ -0,0 +1,170 @@
+use std::pin::Pin;
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use app_error::AppError;
+use async_trait::async_trait;
+use database::file::bucket_client_impl::BucketStorageImpl;
+use database::file::BlobKey;
+use database::pg_row::AFBlobStatus;
+use database::resource_usage::update_blob_status;
+use sqlx::PgPool;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpStream, UnixStream};
+use tracing::{error, trace, warn};
+
+pub enum ScanVerdict {
+  Clean,
+  /// The file is infected, with the name of the detected signature.
+  Infected(String),
+}
+
+/// Scans the uploaded files for viruses and malware.
+#[async_trait]
+pub trait MalwareScanner: Send + Sync {
+  async fn scan(&self, content: Pin<Box<dyn AsyncRead + Send>>) -> Result<ScanVerdict, AppError>;
+}
+
+/// Size of the chunks sent to clamd. It must be smaller than the `StreamMaxLength` of clamd.
+const CLAMAV_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Scans files with the clamd daemon of ClamAV, using the `INSTREAM` command over a TCP
+/// (`tcp://host:port`) or unix (`unix:///path/to/clamd.sock`) socket.
+pub struct ClamAvScanner {
+  address: String,
+}
+
+impl ClamAvScanner {
+  pub fn new(address: String) -> Self {
+    Self { address }
+  }
+
+  async fn instream<S>(
+    mut socket: S,
+    mut content: Pin<Box<dyn AsyncRead + Send>>,
+  ) -> Result<String, std::io::Error>
+  where
+    S: AsyncRead + AsyncWrite + Unpin,
+  {
+    socket.write_all(b"zINSTREAM\0").await?;
+    let mut buf = vec![0; CLAMAV_CHUNK_SIZE];
+    loop {
+      let n = content.read(&mut buf).await?;
+      if n == 0 {
+        break;
+      }
+      socket.write_all(&(n as u32).to_be_bytes()).await?;
+      socket.write_all(&buf[..n]).await?;
+    }
+    socket.write_all(&0u32.to_be_bytes()).await?;
+    socket.flush().await?;
+
+    let mut reply = Vec::new();
+    socket.read_to_end(&mut reply).await?;
+    Ok(
+      String::from_utf8_lossy(&reply)
+        .trim_end_matches('\0')
+        .trim()
+        .to_string(),
+    )
+  }
+}
+
+#[async_trait]
+impl MalwareScanner for ClamAvScanner {
+  async fn scan(&self, content: Pin<Box<dyn AsyncRead + Send>>) -> Result<ScanVerdict, AppError> {
+    let reply = if let Some(path) = self.address.strip_prefix("unix://") {
+      Self::instream(UnixStream::connect(path).await?, content).await?
+    } else {
+      let address = self.address.strip_prefix("tcp://").unwrap_or(&self.address);
+      Self::instream(TcpStream::connect(address).await?, content).await?
+    };
+
+    // The reply is `stream: OK`, `stream: <signature> FOUND` or `<reason> ERROR`
+    let result = reply.strip_prefix("stream:").unwrap_or(&reply).trim();
+    if result == "OK" {
+      Ok(ScanVerdict::Clean)
+    } else if let Some(signature) = result.strip_suffix("FOUND") {
+      Ok(ScanVerdict::Infected(signature.trim().to_string()))
+    } else {
+      Err(AppError::Internal(anyhow!(
+        "ClamAV failed to scan: {}",
+        reply
+      )))
+    }
+  }
+}
+
+/// Marks the blob as pending and scans it in the background. Only downloads of infected blobs are
+/// blocked, so the blob stays available while it is being scanned.
+pub async fn schedule_blob_scan<K>(
+  pg_pool: &PgPool,
+  bucket_storage: &Arc<BucketStorageImpl>,
+  scanner: &Arc<dyn MalwareScanner>,
+  key: K,
+) -> Result<(), AppError>
+where
+  K: BlobKey + 'static,
+{
+  update_blob_status(
+    pg_pool,
+    key.workspace_id(),
+    &key.blob_metadata_key(),
+    AFBlobStatus::ScanPending,
+  )
+  .await?;
+
+  let pg_pool = pg_pool.clone();
+  let bucket_storage = bucket_storage.clone();
+  let scanner = scanner.clone();
+  tokio::spawn(async move {
+    let status = match scan_blob(&bucket_storage, scanner.as_ref(), &key).await {
+      Ok(ScanVerdict::Clean) => {
+        trace!("blob {} is clean", key.object_key());
+        AFBlobStatus::Ok
+      },
+      Ok(ScanVerdict::Infected(signature)) => {
+        warn!(
+          "blob {} of workspace {} is infected: {}",
+          key.blob_metadata_key(),
+          key.workspace_id(),
+          signature
+        );
+        AFBlobStatus::Infected
+      },
+      Err(err) => {
+        error!("failed to scan blob {}: {}", key.object_key(), err);
+        AFBlobStatus::ScanFailed
+      },
+    };
+    if let Err(err) = update_blob_status(
+      &pg_pool,
+      key.workspace_id(),
+      &key.blob_metadata_key(),
+      status,
+    )
+    .await
+    {
+      error!(
+        "failed to update the scan status of blob {}: {}",
+        key.object_key(),
+        err
+      );
+    }
+  });
+  Ok(())
+}
+
+async fn scan_blob(
+  bucket_storage: &BucketStorageImpl,
+  scanner: &dyn MalwareScanner,
+  key: &impl BlobKey,
+) -> Result<ScanVerdict, AppError> {
+  let blob = bucket_storage.get_blob_stream(key, None).await?;
+  trace!(
+    "scanning blob {} ({} bytes)",
+    key.object_key(),
+    blob.content_length
+  );
+  scanner.scan(blob.reader).await
+}
@@ -0,0 +1,4 @@
+pub mod image_variant;
+pub mod malware_scan;
+pub mod quota;
+pub mod upload_session;
@@ -0,0 +1,150 @@
+use app_error::AppError;
+use database::resource_usage::{
+  get_workspace_usage_size, select_workspace_storage_quota, upsert_workspace_storage_quota,
+};
+use shared_entity::dto::workspace_dto::{UpdateWorkspaceStorageQuotaParams, WorkspaceStorageQuota};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::config::config::StorageQuotaSetting;
+
+/// Storage quotas of a workspace, with the defaults of the server applied. A `None` limit means
+/// unlimited.
+#[derive(Debug, Clone)]
+pub struct StorageQuota {
+  pub consumed_capacity: u64,
+  pub storage_limit: Option<u64>,
+  pub single_upload_limit: Option<u64>,
+}
+
+impl StorageQuota {
+  pub fn remaining_capacity(&self) -> Option<u64> {
+    self
+      .storage_limit
+      .map(|limit| limit.saturating_sub(self.consumed_capacity))
+  }
+
+  /// Checks that a file of the given size can be added to the workspace.
+  pub fn check_upload(&self, file_size: u64) -> Result<(), AppError> {
+    if let Some(limit) = self.single_upload_limit {
+      if file_size > limit {
+        return Err(AppError::SingleUploadLimitExceeded { file_size, limit });
+      }
+    }
+    if let Some(remaining) = self.remaining_capacity() {
+      if file_size > remaining {
+        return Err(AppError::FileStorageLimitExceeded {
+          file_size,
+          remaining,
+        });
+      }
+    }
+    Ok(())
+  }
+}
+
+impl From<StorageQuota> for WorkspaceStorageQuota {
+  fn from(quota: StorageQuota) -> Self {
+    Self {
+      consumed_capacity: quota.consumed_capacity,
+      storage_limit: quota.storage_limit,
+      remaining_capacity: quota.remaining_capacity(),
+      single_upload_limit: quota.single_upload_limit,
+    }
+  }
+}
+
+/// A limit of 0 means unlimited.
+fn limit_from_setting(limit: u64) -> Option<u64> {
+  if limit == 0 {
+    None
+  } else {
+    Some(limit)
+  }
+}
+
+pub async fn get_workspace_storage_quota(
+  pg_pool: &PgPool,
+  setting: &StorageQuotaSetting,
+  workspace_id: &Uuid,
+) -> Result<StorageQuota, AppError> {
+  let quota = select_workspace_storage_quota(pg_pool, workspace_id).await?;
+  let (storage_limit, single_upload_limit) = match quota {
+    Some(quota) => (
+      quota
+        .storage_limit
+        .map(|limit| limit as u64)
+        .unwrap_or(setting.workspace_storage_limit),
+      quota
+        .single_upload_limit
+        .map(|limit| limit as u64)
+        .unwrap_or(setting.single_upload_limit),
+    ),
+    None => (setting.workspace_storage_limit, setting.single_upload_limit),
+  };
+  let consumed_capacity = get_workspace_usage_size(pg_pool, workspace_id).await?;
+  Ok(StorageQuota {
+    consumed_capacity,
+    storage_limit: limit_from_setting(storage_limit),
+    single_upload_limit: limit_from_setting(single_upload_limit),
+  })
+}
+
+pub async fn update_workspace_storage_quota(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  params: UpdateWorkspaceStorageQuotaParams,
+) -> Result<(), AppError> {
+  let to_i64 = |limit: Option<u64>| -> Result<Option<i64>, AppError> {
+    limit
+      .map(|limit| {
+        i64::try_from(limit)
+          .map_err(|_| AppError::InvalidRequest(format!("Storage limit {} is too large", limit)))
+      })
+      .transpose()
+  };
+  upsert_workspace_storage_quota(
+    pg_pool,
+    workspace_id,
+    to_i64(params.storage_limit)?,
+    to_i64(params.single_upload_limit)?,
+  )
+  .await
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn check_upload_test() {
+    let quota = StorageQuota {
+      consumed_capacity: 80,
+      storage_limit: Some(100),
+      single_upload_limit: Some(50),
+    };
+    assert!(quota.check_upload(20).is_ok());
+    assert!(matches!(
+      quota.check_upload(21),
+      Err(AppError::FileStorageLimitExceeded {
+        file_size: 21,
+        remaining: 20
+      })
+    ));
+    assert!(matches!(
+      quota.check_upload(51),
+      Err(AppError::SingleUploadLimitExceeded {
+        file_size: 51,
+        limit: 50
+      })
+    ));
+
+    let unlimited = StorageQuota {
+      consumed_capacity: 80,
+      storage_limit: None,
+      single_upload_limit: None,
+    };
+    assert!(unlimited.check_upload(u64::MAX).is_ok());
+    assert_eq!(unlimited.remaining_capacity(), None);
+  }
+}
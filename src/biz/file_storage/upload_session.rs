@@ -0,0 +1,61 @@
+use app_error::AppError;
+use chrono::{Duration, Utc};
+use database::pg_row::AFUploadSessionRow;
+use database::resource_usage::{
+  insert_upload_session, select_upload_session, update_upload_session_expires_at,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::config::config::BlobStorageSetting;
+
+fn next_expires_at(setting: &BlobStorageSetting) -> chrono::DateTime<Utc> {
+  Utc::now() + Duration::seconds(setting.upload_session_expiry_secs as i64)
+}
+
+/// Records the multipart upload, so that it can be listed, and aborted by the worker if it is
+/// abandoned.
+pub async fn create_upload_session(
+  pg_pool: &PgPool,
+  setting: &BlobStorageSetting,
+  workspace_id: &Uuid,
+  parent_dir: &str,
+  file_id: &str,
+  upload_id: &str,
+  uid: i64,
+) -> Result<(), AppError> {
+  insert_upload_session(
+    pg_pool,
+    upload_id,
+    workspace_id,
+    parent_dir,
+    file_id,
+    uid,
+    next_expires_at(setting),
+  )
+  .await
+}
+
+/// Postpones the expiration of the upload, as it is still in progress.
+pub async fn touch_upload_session(
+  pg_pool: &PgPool,
+  setting: &BlobStorageSetting,
+  workspace_id: &Uuid,
+  upload_id: &str,
+) -> Result<(), AppError> {
+  update_upload_session_expires_at(pg_pool, workspace_id, upload_id, next_expires_at(setting)).await
+}
+
+/// Returns the session of the upload of the given file.
+pub async fn get_upload_session(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  parent_dir: &str,
+  file_id: &str,
+  upload_id: &str,
+) -> Result<AFUploadSessionRow, AppError> {
+  select_upload_session(pg_pool, workspace_id, upload_id)
+    .await?
+    .filter(|session| session.parent_dir == parent_dir && session.file_id == file_id)
+    .ok_or_else(|| AppError::RecordNotFound(format!("upload not found for id:{}", upload_id)))
+}
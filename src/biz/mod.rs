@@ -1,7 +1,9 @@
+pub mod access_control;
 pub mod access_request;
 pub mod chat;
 pub mod collab;
 pub mod data_import;
+pub mod file_storage;
 pub mod pg_listener;
 pub mod search;
 pub mod template;
@@ -1,16 +1,22 @@
 use anyhow::Error;
 use database::listener::PostgresDBListener;
-use database::pg_row::AFUserNotification;
+use database::pg_row::{AFAccessRequestNotification, AFUserNotification};
 use sqlx::PgPool;
 
 pub struct PgListeners {
   user_listener: UserListener,
+  access_request_listener: AccessRequestListener,
 }
 
 impl PgListeners {
   pub async fn new(pg_pool: &PgPool) -> Result<Self, Error> {
     let user_listener = UserListener::new(pg_pool, "af_user_channel").await?;
-    Ok(Self { user_listener })
+    let access_request_listener =
+      AccessRequestListener::new(pg_pool, "af_access_request_channel").await?;
+    Ok(Self {
+      user_listener,
+      access_request_listener,
+    })
   }
 
   pub fn subscribe_user_change(&self, uid: i64) -> tokio::sync::mpsc::Receiver<AFUserNotification> {
@@ -27,6 +33,24 @@ impl PgListeners {
     });
     rx
   }
+
+  /// Subscribe to the access requests of the workspaces owned by the given user.
+  pub fn subscribe_access_request_change(
+    &self,
+    owner_uid: i64,
+  ) -> tokio::sync::mpsc::Receiver<AFAccessRequestNotification> {
+    let (tx, rx) = tokio::sync::mpsc::channel(100);
+    let mut access_request_notify = self.access_request_listener.notify.subscribe();
+    tokio::spawn(async move {
+      while let Ok(notification) = access_request_notify.recv().await {
+        if notification.owner_uid == Some(owner_uid) && tx.send(notification).await.is_err() {
+          break;
+        }
+      }
+    });
+    rx
+  }
 }
 
 pub type UserListener = PostgresDBListener<AFUserNotification>;
+pub type AccessRequestListener = PostgresDBListener<AFAccessRequestNotification>;
@@ -1,3 +1,4 @@
+pub mod personal_access_token;
 pub mod user_delete;
 pub mod user_info;
 pub mod user_init;
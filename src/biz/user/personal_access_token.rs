@@ -0,0 +1,109 @@
+use access_control::act::Action;
+use app_error::AppError;
+use authentication::jwt::Authorization;
+use authentication::personal_access_token::generate_personal_access_token;
+use chrono::{Duration, Utc};
+use database::personal_access_token::{
+  insert_personal_access_token, revoke_personal_access_token, select_personal_access_tokens,
+};
+use database::user::select_uid_from_uuid;
+use database_entity::dto::{
+  CreatePersonalAccessTokenParams, CreatedPersonalAccessToken, PersonalAccessToken,
+};
+use uuid::Uuid;
+
+use crate::state::AppState;
+
+const DEFAULT_EXPIRES_IN_DAYS: i64 = 90;
+const MAX_EXPIRES_IN_DAYS: i64 = 365;
+
+/// Personal access tokens can only be managed with a session token, so that a leaked personal
+/// access token can't be used to issue new tokens or to revoke the others.
+fn ensure_not_personal_access_token(auth: &Authorization) -> Result<(), AppError> {
+  if auth.personal_access_token().is_some() {
+    return Err(AppError::NotEnoughPermissions);
+  }
+  Ok(())
+}
+
+pub async fn create_personal_access_token(
+  state: &AppState,
+  auth: &Authorization,
+  params: CreatePersonalAccessTokenParams,
+) -> Result<CreatedPersonalAccessToken, AppError> {
+  ensure_not_personal_access_token(auth)?;
+  let name = params.name.trim();
+  if name.is_empty() {
+    return Err(AppError::InvalidRequest(
+      "The name of the personal access token can't be empty".to_string(),
+    ));
+  }
+  let expires_in_days = params.expires_in_days.unwrap_or(DEFAULT_EXPIRES_IN_DAYS);
+  if !(1..=MAX_EXPIRES_IN_DAYS).contains(&expires_in_days) {
+    return Err(AppError::InvalidRequest(format!(
+      "A personal access token must expire within 1 to {} days",
+      MAX_EXPIRES_IN_DAYS
+    )));
+  }
+
+  let user_uuid = auth
+    .uuid()
+    .map_err(|err| AppError::UserUnAuthorized(err.to_string()))?;
+  let uid = select_uid_from_uuid(&state.pg_pool, &user_uuid).await?;
+  if let Some(workspace_id) = &params.workspace_id {
+    state
+      .workspace_access_control
+      .enforce_action(&uid, &workspace_id.to_string(), Action::Read)
+      .await?;
+  }
+
+  let token_id = Uuid::new_v4();
+  let (token, token_hash) = generate_personal_access_token();
+  let expires_at = Utc::now() + Duration::days(expires_in_days);
+  let personal_access_token = insert_personal_access_token(
+    &state.pg_pool,
+    &token_id,
+    &token_hash,
+    uid,
+    name,
+    params.read_only,
+    params.workspace_id.as_ref(),
+    expires_at,
+  )
+  .await?;
+  Ok(CreatedPersonalAccessToken {
+    personal_access_token,
+    token,
+  })
+}
+
+pub async fn list_personal_access_tokens(
+  state: &AppState,
+  auth: &Authorization,
+) -> Result<Vec<PersonalAccessToken>, AppError> {
+  ensure_not_personal_access_token(auth)?;
+  let user_uuid = auth
+    .uuid()
+    .map_err(|err| AppError::UserUnAuthorized(err.to_string()))?;
+  let uid = select_uid_from_uuid(&state.pg_pool, &user_uuid).await?;
+  select_personal_access_tokens(&state.pg_pool, uid).await
+}
+
+pub async fn revoke_personal_access_token_for_user(
+  state: &AppState,
+  auth: &Authorization,
+  token_id: &Uuid,
+) -> Result<(), AppError> {
+  ensure_not_personal_access_token(auth)?;
+  let user_uuid = auth
+    .uuid()
+    .map_err(|err| AppError::UserUnAuthorized(err.to_string()))?;
+  let uid = select_uid_from_uuid(&state.pg_pool, &user_uuid).await?;
+  if !revoke_personal_access_token(&state.pg_pool, uid, token_id).await? {
+    return Err(AppError::RecordNotFound(format!(
+      "Personal access token {} doesn't exist or has already been revoked",
+      token_id
+    )));
+  }
+  Ok(())
+}
@@ -4,7 +4,7 @@ use crate::state::GoTrueAdmin;
 use crate::{biz::workspace::ops::delete_workspace_for_user, config::config::AppleOAuthSetting};
 use app_error::ErrorCode;
 use authentication::jwt::Authorization;
-use database::file::s3_client_impl::S3BucketStorage;
+use database::file::bucket_client_impl::BucketStorageImpl;
 use database::workspace::select_user_owned_workspaces_id;
 use gotrue::params::AdminDeleteUserParams;
 use secrecy::{ExposeSecret, Secret};
@@ -14,7 +14,7 @@ use uuid::Uuid;
 #[allow(clippy::too_many_arguments)]
 pub async fn delete_user(
   pg_pool: &sqlx::PgPool,
-  bucket_storage: &Arc<S3BucketStorage>,
+  bucket_storage: &Arc<BucketStorageImpl>,
   gotrue_client: &gotrue::api::Client,
   gotrue_admin: &GoTrueAdmin,
   apple_oauth: &AppleOAuthSetting,
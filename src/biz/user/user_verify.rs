@@ -5,22 +5,35 @@ use std::time::Instant;
 use tracing::{event, instrument, trace};
 
 use app_error::AppError;
-use database::user::{create_user, is_user_exist};
+use authentication::sso::sso_provider_id_from_claims;
+use database::user::{create_user, is_user_exist, select_uid_from_uuid};
 use database::workspace::select_workspace;
 use database_entity::dto::AFRole;
+use gotrue_entity::gotrue_jwt::GoTrueJWTClaims;
+use secrecy::ExposeSecret;
 use workspace_template::document::getting_started::GettingStartedTemplate;
 
 use crate::biz::user::user_init::initialize_workspace_for_user;
+use crate::biz::workspace::sso::auto_join_workspaces_by_email_domain;
 use crate::state::AppState;
 
 /// Verify the token from the gotrue server and create the user if it is a new user
 /// Return true if the user is a new user
 ///
+/// Users that are members of a workspace enforcing SSO must have signed in with the SSO provider
+/// of that workspace. Users signing in with SSO join the workspaces capturing their email domain.
+///
 #[instrument(skip_all, err)]
 pub async fn verify_token(access_token: &str, state: &AppState) -> Result<bool, AppError> {
   let user = state.gotrue_client.user_info(access_token).await?;
   let user_uuid = uuid::Uuid::parse_str(&user.id)?;
   let name = name_from_user_metadata(&user.user_metadata);
+  let claims = GoTrueJWTClaims::decode(
+    access_token,
+    state.config.gotrue.jwt_secret.expose_secret().as_bytes(),
+  )
+  .map_err(|err| AppError::UserUnAuthorized(format!("fail to decode token, error:{}", err)))?;
+  let sso_provider_id = sso_provider_id_from_claims(&claims);
 
   // Create new user if it doesn't exist
   let mut txn = state
@@ -30,7 +43,7 @@ pub async fn verify_token(access_token: &str, state: &AppState) -> Result<bool,
     .context("acquire transaction to verify token")?;
 
   let is_new = !is_user_exist(txn.deref_mut(), &user_uuid).await?;
-  if is_new {
+  let uid = if is_new {
     let new_uid = state.id_gen.write().await.next_id();
     event!(tracing::Level::INFO, "create new user:{}", new_uid);
     let workspace_id =
@@ -66,8 +79,26 @@ pub async fn verify_token(access_token: &str, state: &AppState) -> Result<bool,
       .await
       .context("fail to commit transaction to initialize workspace")?;
     state.metrics.collab_metrics.observe_pg_tx(start.elapsed());
+    new_uid
   } else {
     trace!("user already exists:{},{}", user.id, user.email);
+    let uid = select_uid_from_uuid(txn.deref_mut(), &user_uuid).await?;
+    txn
+      .commit()
+      .await
+      .context("fail to commit transaction to verify token")?;
+    uid
+  };
+
+  if let Some(sso_provider_id) = sso_provider_id {
+    auto_join_workspaces_by_email_domain(
+      &state.pg_pool,
+      &state.workspace_access_control,
+      uid,
+      &user.email,
+      &sso_provider_id,
+    )
+    .await?;
   }
 
   Ok(is_new)
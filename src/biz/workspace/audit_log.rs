@@ -0,0 +1,238 @@
+use app_error::AppError;
+use bytes::Bytes;
+use chrono::Utc;
+use database::audit_log::{insert_audit_log, select_audit_logs_with_one_more_than_limit};
+use database_entity::dto::{AuditAction, AuditLog, AuditLogs, ListAuditLogsQueryParams};
+use futures_util::{stream, Stream, StreamExt};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+const AUDIT_LOG_CSV_HEADER: &str =
+  "id,created_at,workspace_id,actor_uid,actor_email,action,object_id,metadata";
+const MAX_AUDIT_LOG_LIMIT: i32 = 1000;
+/// Characters that make spreadsheet applications interpret a cell as a formula.
+const CSV_FORMULA_PREFIXES: [char; 6] = ['=', '+', '-', '@', '\t', '\r'];
+
+/// Writes an audit log entry, once the audited change has been committed. Failing to record an
+/// entry should never fail the action that is being audited, so errors are only logged.
+pub async fn record_audit_log(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  actor_uid: Option<i64>,
+  action: AuditAction,
+  object_id: Option<&str>,
+  metadata: serde_json::Value,
+) {
+  if let Err(err) = insert_audit_log(
+    pg_pool,
+    workspace_id,
+    actor_uid,
+    &action,
+    object_id,
+    &metadata,
+  )
+  .await
+  {
+    tracing::error!(
+      "Failed to record audit log {} for workspace {}: {}",
+      action,
+      workspace_id,
+      err
+    );
+  }
+}
+
+pub async fn list_audit_logs(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  params: ListAuditLogsQueryParams,
+) -> Result<AuditLogs, AppError> {
+  let ListAuditLogsQueryParams {
+    action,
+    actor_uid,
+    object_id,
+    since,
+    until,
+    offset,
+    limit,
+  } = params;
+  let (offset, limit) = validate_pagination(offset, limit)?;
+  let mut audit_logs_with_one_more_than_limit = select_audit_logs_with_one_more_than_limit(
+    pg_pool,
+    workspace_id,
+    action.as_ref(),
+    actor_uid,
+    object_id.as_deref(),
+    since,
+    until,
+    Some(offset),
+    Some(limit),
+  )
+  .await?;
+  let has_more = audit_logs_with_one_more_than_limit.len() as i32 > limit;
+  audit_logs_with_one_more_than_limit.truncate(limit as usize);
+  let audit_logs = audit_logs_with_one_more_than_limit;
+
+  Ok(AuditLogs {
+    audit_logs,
+    has_more,
+  })
+}
+
+/// Streams the audit log entries matching the filters as CSV. Unlike the listing, the export is
+/// not capped: the entries are loaded in pages of [MAX_AUDIT_LOG_LIMIT] until `limit` entries, or
+/// all of them, have been written. The entries recorded after the export started are left out, so
+/// that the pages don't shift while they are read.
+pub fn export_audit_logs_csv(
+  pg_pool: PgPool,
+  workspace_id: Uuid,
+  params: ListAuditLogsQueryParams,
+) -> Result<impl Stream<Item = Result<Bytes, AppError>>, AppError> {
+  let ListAuditLogsQueryParams {
+    action,
+    actor_uid,
+    object_id,
+    since,
+    until,
+    offset,
+    limit,
+  } = params;
+  validate_pagination(offset, limit)?;
+  let until = until.unwrap_or_else(Utc::now);
+
+  let header = stream::once(async { Ok(Bytes::from(format!("{}\n", AUDIT_LOG_CSV_HEADER))) });
+  // The state is the offset and the number of entries left to export of the next page, if any
+  let rows = stream::try_unfold(Some((offset.unwrap_or(0), limit)), move |page| {
+    let pg_pool = pg_pool.clone();
+    let action = action.clone();
+    let object_id = object_id.clone();
+    async move {
+      let Some((offset, remaining)) = page else {
+        return Ok(None);
+      };
+      let page_limit = remaining.map_or(MAX_AUDIT_LOG_LIMIT, |remaining| {
+        remaining.min(MAX_AUDIT_LOG_LIMIT)
+      });
+      if page_limit == 0 {
+        return Ok(None);
+      }
+      let mut audit_logs = select_audit_logs_with_one_more_than_limit(
+        &pg_pool,
+        &workspace_id,
+        action.as_ref(),
+        actor_uid,
+        object_id.as_deref(),
+        since,
+        Some(until),
+        Some(offset),
+        Some(page_limit),
+      )
+      .await?;
+      let has_more = audit_logs.len() as i32 > page_limit;
+      audit_logs.truncate(page_limit as usize);
+      let csv = audit_logs
+        .iter()
+        .map(|audit_log| format!("{}\n", audit_log_to_csv_row(audit_log)))
+        .collect::<String>();
+      let next_page = has_more.then(|| {
+        (
+          offset + page_limit,
+          remaining.map(|remaining| remaining - page_limit),
+        )
+      });
+      Ok::<_, AppError>(Some((Bytes::from(csv), next_page)))
+    }
+  });
+  Ok(header.chain(rows))
+}
+
+fn audit_log_to_csv_row(audit_log: &AuditLog) -> String {
+  let fields = [
+    audit_log.id.to_string(),
+    audit_log.created_at.to_rfc3339(),
+    audit_log.workspace_id.to_string(),
+    audit_log
+      .actor_uid
+      .map(|uid| uid.to_string())
+      .unwrap_or_default(),
+    audit_log.actor_email.clone().unwrap_or_default(),
+    audit_log.action.to_string(),
+    audit_log.object_id.clone().unwrap_or_default(),
+    audit_log.metadata.to_string(),
+  ];
+  fields
+    .iter()
+    .map(|field| escape_csv_field(field))
+    .collect::<Vec<_>>()
+    .join(",")
+}
+
+/// Negative values are rejected and the limit is capped, so a single request can't load the
+/// whole audit log of a workspace.
+fn validate_pagination(offset: Option<i32>, limit: Option<i32>) -> Result<(i32, i32), AppError> {
+  let offset = offset.unwrap_or(0);
+  if offset < 0 {
+    return Err(AppError::InvalidRequest(
+      "offset must not be negative".to_string(),
+    ));
+  }
+  let limit = limit.unwrap_or(MAX_AUDIT_LOG_LIMIT);
+  if limit < 0 {
+    return Err(AppError::InvalidRequest(
+      "limit must not be negative".to_string(),
+    ));
+  }
+  Ok((offset, limit.min(MAX_AUDIT_LOG_LIMIT)))
+}
+
+fn escape_csv_field(field: &str) -> String {
+  // Prefixing with a quote keeps spreadsheet applications from evaluating the cell as a formula
+  let field = if field.starts_with(CSV_FORMULA_PREFIXES) {
+    format!("'{}", field)
+  } else {
+    field.to_string()
+  };
+  if field.contains([',', '"', '\n', '\r']) {
+    format!("\"{}\"", field.replace('"', "\"\""))
+  } else {
+    field
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{escape_csv_field, validate_pagination, MAX_AUDIT_LOG_LIMIT};
+
+  #[test]
+  fn escape_csv_field_test() {
+    assert_eq!(escape_csv_field("plain"), "plain");
+    assert_eq!(escape_csv_field("a,b"), "\"a,b\"");
+    assert_eq!(
+      escape_csv_field(r#"{"email":"a@appflowy.io"}"#),
+      r#""{""email"":""a@appflowy.io""}""#
+    );
+    assert_eq!(escape_csv_field("line\nbreak"), "\"line\nbreak\"");
+    assert_eq!(
+      escape_csv_field("=HYPERLINK(\"x\")"),
+      "\"'=HYPERLINK(\"\"x\"\")\""
+    );
+    assert_eq!(escape_csv_field("+1"), "'+1");
+    assert_eq!(escape_csv_field("-1"), "'-1");
+    assert_eq!(escape_csv_field("@SUM(A1)"), "'@SUM(A1)");
+  }
+
+  #[test]
+  fn validate_pagination_test() {
+    assert_eq!(
+      validate_pagination(None, None).unwrap(),
+      (0, MAX_AUDIT_LOG_LIMIT)
+    );
+    assert_eq!(validate_pagination(Some(10), Some(5)).unwrap(), (10, 5));
+    assert_eq!(
+      validate_pagination(None, Some(MAX_AUDIT_LOG_LIMIT + 1)).unwrap(),
+      (0, MAX_AUDIT_LOG_LIMIT)
+    );
+    assert!(validate_pagination(Some(-1), None).is_err());
+    assert!(validate_pagination(None, Some(-1)).is_err());
+  }
+}
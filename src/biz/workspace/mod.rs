@@ -1,5 +1,13 @@
+pub mod audit_log;
 pub mod ops;
+pub mod page_export;
 pub mod page_view;
 pub mod publish;
+pub mod publish_access;
+pub mod publish_analytics;
+pub mod publish_domain;
 pub mod publish_dup;
+pub mod publish_history;
+pub mod publish_schedule;
 pub mod quick_note;
+pub mod sso;
@@ -20,14 +20,14 @@ use access_control::workspace::WorkspaceAccessControl;
 use app_error::AppError;
 use appflowy_collaborate::collab::storage::CollabAccessControlStorage;
 use database::collab::upsert_collab_member_with_txn;
-use database::file::s3_client_impl::S3BucketStorage;
+use database::file::bucket_client_impl::BucketStorageImpl;
 use database::pg_row::AFWorkspaceMemberRow;
 
 use database::user::select_uid_from_email;
 use database::workspace::*;
 use database_entity::dto::{
   AFAccessLevel, AFRole, AFWorkspace, AFWorkspaceInvitation, AFWorkspaceInvitationStatus,
-  AFWorkspaceSettings, GlobalComment, Reaction, WorkspaceUsage,
+  AFWorkspaceSettings, AuditAction, GlobalComment, Reaction, WorkspaceUsage,
 };
 use gotrue::params::{GenerateLinkParams, GenerateLinkType};
 
@@ -41,6 +41,7 @@ use crate::biz::user::user_init::{
   create_user_awareness, create_workspace_collab, create_workspace_database_collab,
   initialize_workspace_for_user,
 };
+use crate::biz::workspace::audit_log::record_audit_log;
 use crate::mailer::{AFCloudMailer, WorkspaceInviteMailerParam};
 use crate::state::{GoTrueAdmin, RedisConnectionManager};
 
@@ -49,7 +50,7 @@ const MAX_COMMENT_LENGTH: usize = 5000;
 pub async fn delete_workspace_for_user(
   pg_pool: PgPool,
   workspace_id: Uuid,
-  bucket_storage: Arc<S3BucketStorage>,
+  bucket_storage: Arc<BucketStorageImpl>,
 ) -> Result<(), AppResponseError> {
   // remove files from s3
   bucket_storage
@@ -75,15 +76,43 @@ pub async fn create_empty_workspace(
   user_uid: i64,
   workspace_name: &str,
 ) -> Result<AFWorkspace, AppResponseError> {
-  let new_workspace_row = insert_user_workspace(pg_pool, user_uuid, workspace_name, false).await?;
+  create_empty_workspace_with_settings(
+    pg_pool,
+    workspace_access_control,
+    collab_storage,
+    user_uuid,
+    user_uid,
+    workspace_name,
+    None,
+  )
+  .await
+}
+
+/// Creates the workspace row, its settings if any, and its initial collabs in a single
+/// transaction, so that the workspace never exists without its settings.
+async fn create_empty_workspace_with_settings(
+  pg_pool: &PgPool,
+  workspace_access_control: Arc<dyn WorkspaceAccessControl>,
+  collab_storage: &Arc<CollabAccessControlStorage>,
+  user_uuid: &Uuid,
+  user_uid: i64,
+  workspace_name: &str,
+  settings: Option<&AFWorkspaceSettings>,
+) -> Result<AFWorkspace, AppResponseError> {
+  let mut txn = pg_pool.begin().await?;
+  let start = Instant::now();
+  let new_workspace_row =
+    insert_user_workspace(txn.deref_mut(), user_uuid, workspace_name, false).await?;
+  if let Some(settings) = settings {
+    upsert_workspace_settings(&mut txn, &new_workspace_row.workspace_id, settings).await?;
+  }
+  // The collabs are created with the permission of the owner
   workspace_access_control
     .insert_role(&user_uid, &new_workspace_row.workspace_id, AFRole::Owner)
     .await?;
   let workspace_id = new_workspace_row.workspace_id.to_string();
 
   // create CollabType::Folder
-  let mut txn = pg_pool.begin().await?;
-  let start = Instant::now();
   create_workspace_collab(
     user_uid,
     &workspace_id,
@@ -155,6 +184,48 @@ pub async fn create_workspace_for_user(
   Ok(new_workspace)
 }
 
+/// Creates an end-to-end encrypted workspace. It doesn't contain the getting started template, as
+/// its content would be stored in plaintext, and the search indexing is disabled since the server
+/// can't read the content written by the clients.
+pub async fn create_end_to_end_encrypted_workspace(
+  pg_pool: &PgPool,
+  workspace_access_control: Arc<dyn WorkspaceAccessControl>,
+  collab_storage: &Arc<CollabAccessControlStorage>,
+  user_uuid: &Uuid,
+  user_uid: i64,
+  workspace_name: &str,
+) -> Result<AFWorkspace, AppResponseError> {
+  let settings = AFWorkspaceSettings {
+    disable_search_indexing: true,
+    end_to_end_encryption: true,
+    ..Default::default()
+  };
+  create_empty_workspace_with_settings(
+    pg_pool,
+    workspace_access_control,
+    collab_storage,
+    user_uuid,
+    user_uid,
+    workspace_name,
+    Some(&settings),
+  )
+  .await
+}
+
+/// Returns [AppError::EndToEndEncrypted] if the workspace is end-to-end encrypted. Used by the
+/// features that need the content of the collabs, which the server can't read in such workspace.
+pub async fn ensure_not_end_to_end_encrypted(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+) -> Result<(), AppError> {
+  if select_workspace_end_to_end_encryption(pg_pool, workspace_id).await? {
+    return Err(AppError::EndToEndEncrypted {
+      workspace_id: *workspace_id,
+    });
+  }
+  Ok(())
+}
+
 pub async fn patch_workspace(
   pg_pool: &PgPool,
   workspace_id: &Uuid,
@@ -370,6 +441,7 @@ pub async fn invite_workspace_members(
   let admin_token = gotrue_admin.token().await?;
 
   let inviter_name = database::user::select_name_from_uuid(pg_pool, inviter).await?;
+  let inviter_uid = database::user::select_uid_from_uuid(pg_pool, inviter).await?;
   let workspace_name =
     database::workspace::select_workspace_name_from_workspace_id(pg_pool, workspace_id)
       .await?
@@ -397,6 +469,7 @@ pub async fn invite_workspace_members(
     }
   }
 
+  let mut invited = Vec::with_capacity(invitations.len());
   for invitation in invitations {
     let inviter_name = inviter_name.clone();
     let workspace_name = workspace_name.clone();
@@ -429,6 +502,10 @@ pub async fn invite_workspace_members(
         *invite_id
       },
     };
+    invited.push((
+      invitation.email.clone(),
+      json!({ "invite_id": invite_id, "role": invitation.role }),
+    ));
 
     // Generate a link such that when clicked, the user is added to the workspace.
     let accept_url = {
@@ -495,6 +572,17 @@ pub async fn invite_workspace_members(
     .commit()
     .await
     .context("Commit transaction to invite workspace members")?;
+  for (email, metadata) in invited {
+    record_audit_log(
+      pg_pool,
+      workspace_id,
+      Some(inviter_uid),
+      AuditAction::InviteMember,
+      Some(email.as_str()),
+      metadata,
+    )
+    .await;
+  }
   Ok(())
 }
 
@@ -565,11 +653,20 @@ pub async fn leave_workspace(
   workspace_access_control: Arc<dyn WorkspaceAccessControl>,
 ) -> Result<(), AppResponseError> {
   let email = database::user::select_email_from_user_uuid(pg_pool, user_uuid).await?;
-  remove_workspace_members(pg_pool, workspace_id, &[email], workspace_access_control).await
+  let uid = database::user::select_uid_from_uuid(pg_pool, user_uuid).await?;
+  remove_workspace_members(
+    pg_pool,
+    uid,
+    workspace_id,
+    &[email],
+    workspace_access_control,
+  )
+  .await
 }
 
 pub async fn remove_workspace_members(
   pg_pool: &PgPool,
+  actor_uid: i64,
   workspace_id: &Uuid,
   member_emails: &[String],
   workspace_access_control: Arc<dyn WorkspaceAccessControl>,
@@ -595,6 +692,17 @@ pub async fn remove_workspace_members(
     .commit()
     .await
     .context("Commit transaction to delete workspace members")?;
+  for email in member_emails {
+    record_audit_log(
+      pg_pool,
+      workspace_id,
+      Some(actor_uid),
+      AuditAction::RemoveMember,
+      Some(email.as_str()),
+      json!({}),
+    )
+    .await;
+  }
   Ok(())
 }
 
@@ -616,6 +724,7 @@ pub async fn get_workspace_member(
 pub async fn update_workspace_member(
   uid: &i64,
   pg_pool: &PgPool,
+  actor_uid: i64,
   workspace_id: &Uuid,
   changeset: &WorkspaceMemberChangeset,
   workspace_access_control: Arc<dyn WorkspaceAccessControl>,
@@ -625,6 +734,15 @@ pub async fn update_workspace_member(
     workspace_access_control
       .insert_role(uid, workspace_id, role.clone())
       .await?;
+    record_audit_log(
+      pg_pool,
+      workspace_id,
+      Some(actor_uid),
+      AuditAction::UpdateMemberRole,
+      Some(changeset.email.as_str()),
+      json!({ "uid": uid, "role": role }),
+    )
+    .await;
   }
 
   Ok(())
@@ -658,6 +776,14 @@ pub async fn update_workspace_settings(
     .await?
     .unwrap_or_default();
   if let Some(disable_search_indexing) = change.disable_search_indexing {
+    if setting.end_to_end_encryption && !disable_search_indexing {
+      return Err(
+        AppError::EndToEndEncrypted {
+          workspace_id: *workspace_id,
+        }
+        .into(),
+      );
+    }
     setting.disable_search_indexing = disable_search_indexing;
   }
 
@@ -665,6 +791,14 @@ pub async fn update_workspace_settings(
     setting.ai_model = ai_model;
   }
 
+  if change.remove_sso.unwrap_or(false) {
+    setting.sso = None;
+  }
+
+  if let Some(sso) = change.sso {
+    setting.sso = Some(sso);
+  }
+
   // Update the workspace settings in the database
   upsert_workspace_settings(&mut tx, workspace_id, &setting).await?;
   tx.commit().await?;
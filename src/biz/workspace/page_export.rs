@@ -0,0 +1,273 @@
+use crate::biz::collab::folder_view::{
+  check_if_view_ancestors_fulfil_condition, private_space_and_trash_view_ids,
+  PrivateSpaceAndTrashViews,
+};
+use crate::biz::collab::ops::get_latest_workspace_database;
+use crate::biz::collab::utils::{
+  field_by_id_name_uniq, get_latest_collab_database_body, get_latest_collab_document,
+  get_latest_collab_folder, get_row_details_serde, type_option_reader_by_id,
+};
+use anyhow::anyhow;
+use app_error::AppError;
+use appflowy_collaborate::collab::storage::CollabAccessControlStorage;
+use collab::core::collab::Collab;
+use collab_database::rows::{Row, RowDetail};
+use collab_entity::{CollabType, EncodedCollab};
+use collab_export::database::{database_to_csv, fields_in_view_order};
+use collab_export::document::{
+  document_to_html, document_to_json, document_to_markdown, html_page, ExportLinks, PageLink,
+};
+use collab_folder::{CollabOrigin, Folder, ViewLayout};
+use database::collab::{CollabStorage, GetCollabOrigin};
+use database_entity::dto::{QueryCollab, QueryCollabResult};
+use serde_json::json;
+use shared_entity::dto::workspace_dto::PageExportFormat;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+pub struct ExportedPage {
+  pub name: String,
+  pub format: PageExportFormat,
+  pub content: String,
+}
+
+/// Renders the latest version of a page: documents as Markdown, HTML or a JSON tree of blocks,
+/// and database views as CSV or JSON rows.
+pub async fn export_page(
+  pg_pool: &PgPool,
+  collab_storage: &CollabAccessControlStorage,
+  appflowy_web_url: Option<&str>,
+  uid: i64,
+  workspace_id: Uuid,
+  view_id: &str,
+  format: Option<PageExportFormat>,
+) -> Result<ExportedPage, AppError> {
+  let folder = get_latest_collab_folder(
+    collab_storage,
+    GetCollabOrigin::User { uid },
+    &workspace_id.to_string(),
+  )
+  .await?;
+  let hidden_views = private_space_and_trash_view_ids(&folder);
+  let links = WorkspaceLinks {
+    folder: &folder,
+    hidden_views: &hidden_views,
+    workspace_id,
+    appflowy_web_url,
+  };
+  let view = folder
+    .get_view(view_id)
+    .filter(|_| !links.is_in_other_private_space(view_id))
+    .ok_or(AppError::InvalidFolderView(format!(
+      "View {} not found",
+      view_id
+    )))?;
+
+  let (format, content) = match view.layout {
+    ViewLayout::Document => {
+      let format = format.unwrap_or(PageExportFormat::Markdown);
+      let document = get_latest_collab_document(
+        collab_storage,
+        GetCollabOrigin::User { uid },
+        &workspace_id.to_string(),
+        view_id,
+      )
+      .await?;
+      let data = document
+        .get_document_data()
+        .map_err(|err| AppError::Internal(anyhow!("Failed to read document: {}", err)))?;
+      let content = match format {
+        PageExportFormat::Markdown => document_to_markdown(&data, &links),
+        PageExportFormat::Html => html_page(&view.name, &document_to_html(&data, &links)),
+        PageExportFormat::Json => document_to_json(&data, &links).to_string(),
+        PageExportFormat::Csv => {
+          return Err(AppError::InvalidRequest(
+            "Documents can only be exported as markdown, html or json".to_string(),
+          ))
+        },
+      };
+      (format, content)
+    },
+    ViewLayout::Grid | ViewLayout::Board | ViewLayout::Calendar => {
+      let format = format.unwrap_or(PageExportFormat::Csv);
+      if !matches!(format, PageExportFormat::Csv | PageExportFormat::Json) {
+        return Err(AppError::InvalidRequest(
+          "Databases can only be exported as csv or json".to_string(),
+        ));
+      }
+      let content =
+        export_database_view(pg_pool, collab_storage, uid, workspace_id, view_id, format).await?;
+      (format, content)
+    },
+    ViewLayout::Chat => {
+      return Err(AppError::InvalidRequest(
+        "Export of AI chat is not supported".to_string(),
+      ))
+    },
+  };
+
+  Ok(ExportedPage {
+    name: view.name.clone(),
+    format,
+    content,
+  })
+}
+
+/// Converts the rows of the database view, in the order of the view.
+async fn export_database_view(
+  pg_pool: &PgPool,
+  collab_storage: &CollabAccessControlStorage,
+  uid: i64,
+  workspace_id: Uuid,
+  view_id: &str,
+  format: PageExportFormat,
+) -> Result<String, AppError> {
+  let (_, workspace_database) = get_latest_workspace_database(
+    collab_storage,
+    pg_pool,
+    GetCollabOrigin::User { uid },
+    workspace_id,
+  )
+  .await?;
+  let database_id = workspace_database
+    .get_database_meta_with_view_id(view_id)
+    .ok_or(AppError::NoRequiredData(format!(
+      "Database view {} not found",
+      view_id
+    )))?
+    .database_id;
+  let (database_collab, database_body) =
+    get_latest_collab_database_body(collab_storage, &workspace_id.to_string(), &database_id)
+      .await?;
+  let (fields, row_ids) = {
+    let txn = database_collab.transact();
+    let view = database_body
+      .views
+      .get_view(&txn, view_id)
+      .ok_or(AppError::NoRequiredData(format!(
+        "Database view {} not found",
+        view_id
+      )))?;
+    let field_order_ids = view
+      .field_orders
+      .iter()
+      .map(|field_order| field_order.id.clone())
+      .collect::<Vec<_>>();
+    let fields = fields_in_view_order(database_body.fields.get_all_fields(&txn), &field_order_ids);
+    let row_ids = view
+      .row_orders
+      .iter()
+      .map(|row_order| row_order.id.to_string())
+      .collect::<Vec<_>>();
+    (fields, row_ids)
+  };
+
+  let query_collabs = row_ids
+    .iter()
+    .map(|row_id| QueryCollab {
+      object_id: row_id.clone(),
+      collab_type: CollabType::DatabaseRow,
+    })
+    .collect();
+  let mut row_collabs = collab_storage
+    .batch_get_collab(&uid, &workspace_id.to_string(), query_collabs, true)
+    .await;
+  let rows = row_ids
+    .iter()
+    .filter_map(|row_id| match row_collabs.remove(row_id)? {
+      QueryCollabResult::Success { encode_collab_v1 } => {
+        let encoded_collab = EncodedCollab::decode_from_bytes(&encode_collab_v1).ok()?;
+        let collab = Collab::new_with_source(
+          CollabOrigin::Server,
+          row_id,
+          encoded_collab.into(),
+          vec![],
+          false,
+        )
+        .ok()?;
+        RowDetail::from_collab(&collab)
+      },
+      QueryCollabResult::Failed { error } => {
+        tracing::warn!(
+          "Failed to get row {} of database {}: {:?}",
+          row_id,
+          database_id,
+          error
+        );
+        None
+      },
+    })
+    .collect::<Vec<RowDetail>>();
+
+  match format {
+    PageExportFormat::Json => {
+      let type_option_reader_by_id = type_option_reader_by_id(&fields);
+      // Cells are keyed by name, which is made unique if several fields share it
+      let field_by_id = field_by_id_name_uniq(fields.clone());
+      let field_names = fields
+        .iter()
+        .filter_map(|field| field_by_id.get(&field.id))
+        .map(|field| field.name.clone())
+        .collect::<Vec<_>>();
+      let rows = rows
+        .into_iter()
+        .map(|row_detail| {
+          let id = row_detail.row.id.to_string();
+          let cells = get_row_details_serde(row_detail, &field_by_id, &type_option_reader_by_id);
+          json!({ "id": id, "cells": cells })
+        })
+        .collect::<Vec<_>>();
+      Ok(json!({ "fields": field_names, "rows": rows }).to_string())
+    },
+    _ => {
+      let rows = rows
+        .into_iter()
+        .map(|row_detail| row_detail.row)
+        .collect::<Vec<Row>>();
+      database_to_csv(&fields, &rows).map_err(AppError::Internal)
+    },
+  }
+}
+
+/// Links the pages of the workspace that the user can open to the web app, or to their export if
+/// the url of the web app isn't configured.
+struct WorkspaceLinks<'a> {
+  folder: &'a Folder,
+  hidden_views: &'a PrivateSpaceAndTrashViews,
+  workspace_id: Uuid,
+  appflowy_web_url: Option<&'a str>,
+}
+
+impl WorkspaceLinks<'_> {
+  fn is_in_other_private_space(&self, view_id: &str) -> bool {
+    check_if_view_ancestors_fulfil_condition(view_id, self.folder, |view| {
+      self.hidden_views.other_private_space_ids.contains(&view.id)
+    })
+  }
+
+  fn is_in_trash(&self, view_id: &str) -> bool {
+    check_if_view_ancestors_fulfil_condition(view_id, self.folder, |view| {
+      self.hidden_views.view_ids_in_trash.contains(&view.id)
+    })
+  }
+}
+
+impl ExportLinks for WorkspaceLinks<'_> {
+  fn page_link(&self, view_id: &str) -> Option<PageLink> {
+    let view = self.folder.get_view(view_id)?;
+    if self.is_in_other_private_space(view_id) || self.is_in_trash(view_id) {
+      return None;
+    }
+    let href = match self.appflowy_web_url {
+      Some(web_url) => format!("{}/app/{}/{}", web_url, self.workspace_id, view_id),
+      None => format!(
+        "/api/workspace/{}/page-view/{}/export",
+        self.workspace_id, view_id
+      ),
+    };
+    Some(PageLink {
+      name: view.name.clone(),
+      href,
+    })
+  }
+}
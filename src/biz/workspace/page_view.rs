@@ -41,7 +41,10 @@ use collab_folder::hierarchy_builder::NestedChildViewBuilder;
 use collab_folder::{timestamp, CollabOrigin, Folder, SpaceInfo};
 use collab_rt_entity::user::RealtimeUser;
 use database::collab::{select_workspace_database_oid, CollabStorage, GetCollabOrigin};
-use database::publish::select_published_view_ids_for_workspace;
+use database::publish::{
+  select_published_view_ids_for_workspace, update_published_collab_access,
+  update_published_collab_auto_republish,
+};
 use database::user::select_web_user_from_uid;
 use database_entity::dto::{
   CollabParams, PublishCollabItem, PublishCollabMetadata, QueryCollab, QueryCollabResult,
@@ -64,6 +67,7 @@ use tracing::instrument;
 use uuid::Uuid;
 
 use super::publish::PublishedCollabStore;
+use super::publish_access::hash_publish_password;
 
 #[allow(clippy::too_many_arguments)]
 pub async fn update_space(
@@ -1131,7 +1135,7 @@ fn replace_invalid_url_chars(input: &str) -> String {
   INVALID_URL_CHARS.replace_all(input, "-").to_string()
 }
 
-fn generate_publish_name(view_id: &str, name: &str) -> String {
+pub(crate) fn generate_publish_name(view_id: &str, name: &str) -> String {
   let id_len = view_id.len();
   let name = replace_invalid_url_chars(name);
   let name_len = name.len();
@@ -1160,7 +1164,14 @@ pub async fn publish_page(
   publish_name: Option<impl ToString>,
   comments_enabled: bool,
   duplicate_enabled: bool,
+  auto_republish: Option<bool>,
+  password: Option<String>,
+  members_only: Option<bool>,
 ) -> Result<(), AppError> {
+  let password_hash = match password.as_deref() {
+    Some(password) => Some(hash_publish_password(password).await?),
+    None => None,
+  };
   let folder = get_latest_collab_folder(
     collab_access_control_storage,
     GetCollabOrigin::User { uid },
@@ -1236,6 +1247,25 @@ pub async fn publish_page(
       &user_uuid,
     )
     .await?;
+  if let Some(auto_republish) = auto_republish {
+    update_published_collab_auto_republish(
+      pg_pool,
+      &workspace_id,
+      &Uuid::parse_str(view_id)?,
+      auto_republish,
+    )
+    .await?;
+  }
+  if password_hash.is_some() || members_only.is_some() {
+    update_published_collab_access(
+      pg_pool,
+      &workspace_id,
+      &Uuid::parse_str(view_id)?,
+      password_hash.as_ref().map(|hash| hash.as_deref()),
+      members_only,
+    )
+    .await?;
+  }
   Ok(())
 }
 
@@ -5,12 +5,13 @@ use database::{
     insert_non_orginal_workspace_publish_namespace, select_all_published_collab_info,
     select_default_published_view_id, select_default_published_view_id_for_namespace,
     select_workspace_publish_namespace, select_workspace_publish_namespaces,
-    update_published_collabs, update_workspace_default_publish_view,
-    update_workspace_default_publish_view_set_null,
+    update_published_collab_access, update_published_collabs,
+    update_workspace_default_publish_view, update_workspace_default_publish_view_set_null,
   },
   workspace::{select_publish_name_exists, select_view_id_from_publish_name},
 };
-use database_entity::dto::PatchPublishedCollab;
+use database_entity::dto::{AuditAction, PatchPublishedCollab};
+use serde_json::json;
 use std::sync::Arc;
 
 use app_error::AppError;
@@ -26,7 +27,7 @@ use tracing::debug;
 use uuid::Uuid;
 
 use database::{
-  file::{s3_client_impl::AwsS3BucketClientImpl, BucketClient, ResponseBlob},
+  file::{bucket_client_impl::BucketClientImpl, BucketClient, ResponseBlob},
   publish::{
     insert_or_replace_publish_collabs, select_publish_collab_meta, select_published_collab_blob,
     select_published_collab_info, select_published_collab_workspace_view_id,
@@ -34,6 +35,7 @@ use database::{
     select_user_is_collab_publisher_for_all_views, select_workspace_publish_namespace_exists,
     set_published_collabs_as_unpublished, update_non_orginal_workspace_publish_namespace,
   },
+  user::select_uid_from_uuid,
   workspace::select_user_is_workspace_owner,
 };
 
@@ -42,6 +44,9 @@ use crate::{
   biz::collab::{folder_view::to_dto_folder_view_miminal, utils::get_latest_collab_folder},
 };
 
+use super::audit_log::record_audit_log;
+use super::publish_access::hash_publish_password;
+
 async fn check_workspace_owner_or_publisher(
   pg_pool: &PgPool,
   user_uuid: &Uuid,
@@ -334,6 +339,7 @@ impl PublishedCollabStore for PublishedCollabPostgresStore {
       .await?;
     }
     let publish_items_batch_size = publish_items.len() as i64;
+    let published = published_view_names(&publish_items);
     let result =
       insert_or_replace_publish_collabs(&self.pg_pool, workspace_id, user_uuid, publish_items)
         .await;
@@ -345,6 +351,7 @@ impl PublishedCollabStore for PublishedCollabPostgresStore {
       self
         .metrics
         .incr_success_write_count(publish_items_batch_size);
+      record_publish_audit_logs(&self.pg_pool, workspace_id, user_uuid, published).await;
     }
     result
   }
@@ -411,6 +418,7 @@ impl PublishedCollabStore for PublishedCollabPostgresStore {
   ) -> Result<(), AppError> {
     check_workspace_owner_or_publisher(&self.pg_pool, user_uuid, workspace_id, view_ids).await?;
     set_published_collabs_as_unpublished(&self.pg_pool, workspace_id, view_ids).await?;
+    record_unpublish_audit_logs(&self.pg_pool, workspace_id, user_uuid, view_ids).await;
     Ok(())
   }
 
@@ -427,14 +435,14 @@ impl PublishedCollabStore for PublishedCollabPostgresStore {
 pub struct PublishedCollabS3StoreWithPostgresFallback {
   metrics: Arc<PublishedCollabMetrics>,
   pg_pool: PgPool,
-  bucket_client: AwsS3BucketClientImpl,
+  bucket_client: BucketClientImpl,
 }
 
 impl PublishedCollabS3StoreWithPostgresFallback {
   pub fn new(
     metrics: Arc<PublishedCollabMetrics>,
     pg_pool: PgPool,
-    bucket_client: AwsS3BucketClientImpl,
+    bucket_client: BucketClientImpl,
   ) -> Self {
     Self {
       metrics,
@@ -483,6 +491,7 @@ impl PublishedCollabStore for PublishedCollabS3StoreWithPostgresFallback {
       handle.await?;
     }
 
+    let published = published_view_names(&publish_items);
     let result =
       insert_or_replace_publish_collabs(&self.pg_pool, workspace_id, user_uuid, publish_items)
         .await;
@@ -494,6 +503,7 @@ impl PublishedCollabStore for PublishedCollabS3StoreWithPostgresFallback {
       self
         .metrics
         .incr_fallback_write_count(publish_items_batch_size);
+      record_publish_audit_logs(&self.pg_pool, workspace_id, user_uuid, published).await;
     }
     result
   }
@@ -602,6 +612,7 @@ impl PublishedCollabStore for PublishedCollabS3StoreWithPostgresFallback {
       .collect::<Vec<String>>();
     self.bucket_client.delete_blobs(object_keys).await?;
     set_published_collabs_as_unpublished(&self.pg_pool, workspace_id, view_ids).await?;
+    record_unpublish_audit_logs(&self.pg_pool, workspace_id, user_uuid, view_ids).await;
     Ok(())
   }
 
@@ -633,13 +644,83 @@ async fn patch_collabs(
   }
   check_workspace_owner_or_publisher(pg_pool, user_uuid, workspace_id, &view_ids).await?;
 
+  let mut password_hashes = Vec::with_capacity(patches.len());
+  for patch in patches {
+    let password_hash = match patch.password.as_deref() {
+      Some(password) => Some(hash_publish_password(password).await?),
+      None => None,
+    };
+    password_hashes.push(password_hash);
+  }
+
   let mut txn = pg_pool.begin().await?;
   update_published_collabs(&mut txn, workspace_id, patches).await?;
+  for (patch, password_hash) in patches.iter().zip(password_hashes) {
+    if password_hash.is_some() || patch.members_only.is_some() {
+      update_published_collab_access(
+        txn.as_mut(),
+        workspace_id,
+        &patch.view_id,
+        password_hash.as_ref().map(|hash| hash.as_deref()),
+        patch.members_only,
+      )
+      .await?;
+    }
+  }
   txn.commit().await?;
   Ok(())
 }
 
 /// Checks if the `publish_name` already exists for the workspace
+fn published_view_names(
+  publish_items: &[PublishCollabItem<serde_json::Value, Vec<u8>>],
+) -> Vec<(Uuid, String)> {
+  publish_items
+    .iter()
+    .map(|item| (item.meta.view_id, item.meta.publish_name.clone()))
+    .collect()
+}
+
+async fn record_publish_audit_logs(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  user_uuid: &Uuid,
+  published: Vec<(Uuid, String)>,
+) {
+  let actor_uid = select_uid_from_uuid(pg_pool, user_uuid).await.ok();
+  for (view_id, publish_name) in published {
+    record_audit_log(
+      pg_pool,
+      workspace_id,
+      actor_uid,
+      AuditAction::PublishView,
+      Some(&view_id.to_string()),
+      json!({ "publish_name": publish_name }),
+    )
+    .await;
+  }
+}
+
+async fn record_unpublish_audit_logs(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  user_uuid: &Uuid,
+  view_ids: &[Uuid],
+) {
+  let actor_uid = select_uid_from_uuid(pg_pool, user_uuid).await.ok();
+  for view_id in view_ids {
+    record_audit_log(
+      pg_pool,
+      workspace_id,
+      actor_uid,
+      AuditAction::UnpublishView,
+      Some(&view_id.to_string()),
+      json!({}),
+    )
+    .await;
+  }
+}
+
 async fn check_publish_name_already_exists(
   pg_pool: &PgPool,
   workspace_id: &Uuid,
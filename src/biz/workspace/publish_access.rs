@@ -0,0 +1,178 @@
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+
+use access_control::workspace::WorkspaceAccessControl;
+use anyhow::anyhow;
+use app_error::AppError;
+use authentication::password::{
+  compute_hash_password, spawn_blocking_with_tracing, verify_password_hash,
+};
+use dashmap::DashMap;
+use database::pg_row::AFPublishedCollabAccess;
+use database_entity::dto::AFRole;
+use secrecy::{ExposeSecret, Secret};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+/// How many wrong passwords a client can try for a published view within
+/// [PUBLISH_PASSWORD_ATTEMPT_WINDOW].
+const MAX_PUBLISH_PASSWORD_FAILED_ATTEMPTS: u32 = 5;
+const PUBLISH_PASSWORD_ATTEMPT_WINDOW: Duration = Duration::from_secs(60);
+/// How long a verified password is accepted without hashing it again. Loading a published view
+/// sends the password with every request, so that it would otherwise be hashed many times.
+const VERIFIED_PUBLISH_PASSWORD_TTL: Duration = Duration::from_secs(10 * 60);
+/// The expired entries are removed once a cache holds this many entries.
+const PUBLISH_PASSWORD_CACHE_CLEANUP_SIZE: usize = 10_000;
+
+/// The failed attempts of each client for each view, with the start of their window.
+static PUBLISH_PASSWORD_FAILED_ATTEMPTS: LazyLock<DashMap<(Uuid, String), (Instant, u32)>> =
+  LazyLock::new(DashMap::new);
+/// The verified passwords of each view, keyed by a digest of the password and of its hash, so
+/// that changing the password invalidates the entries.
+static VERIFIED_PUBLISH_PASSWORDS: LazyLock<DashMap<(Uuid, [u8; 32]), Instant>> =
+  LazyLock::new(DashMap::new);
+
+/// Hash the password of a published view. An empty password removes the password protection,
+/// so `None` is returned for it.
+pub async fn hash_publish_password(password: &str) -> Result<Option<String>, AppError> {
+  if password.is_empty() {
+    return Ok(None);
+  }
+  let password = password.to_string();
+  let password_hash =
+    spawn_blocking_with_tracing(move || compute_hash_password(password.as_bytes()))
+      .await
+      .map_err(|err| AppError::Internal(anyhow!("Failed to hash publish password: {}", err)))?
+      .map_err(AppError::Internal)?;
+  Ok(Some(password_hash.expose_secret().clone()))
+}
+
+/// Members of the workspace can always view its published views. Other users can't view the
+/// published views restricted to members, and need the password of password protected views.
+/// The wrong passwords are counted per client, identified by `client_ip`, which is refused once
+/// it made too many attempts.
+pub async fn check_published_collab_access(
+  workspace_access_control: &dyn WorkspaceAccessControl,
+  access: &AFPublishedCollabAccess,
+  uid: Option<i64>,
+  password: Option<&str>,
+  client_ip: Option<&str>,
+) -> Result<(), AppError> {
+  if !access.members_only && access.password_hash.is_none() {
+    return Ok(());
+  }
+  if is_workspace_member(workspace_access_control, &access.workspace_id, uid).await? {
+    return Ok(());
+  }
+
+  if access.members_only {
+    return match uid {
+      Some(_) => Err(AppError::NotEnoughPermissions),
+      None => Err(AppError::UserUnAuthorized(
+        "The published view is only visible to the members of the workspace".to_string(),
+      )),
+    };
+  }
+  if let Some(password_hash) = &access.password_hash {
+    let password = password.ok_or_else(|| {
+      AppError::InvalidPassword("The published view is password protected".to_string())
+    })?;
+    verify_publish_password_of_view(
+      &access.view_id,
+      password_hash,
+      password,
+      client_ip.unwrap_or_default(),
+    )
+    .await?;
+  }
+  Ok(())
+}
+
+async fn verify_publish_password_of_view(
+  view_id: &Uuid,
+  password_hash: &str,
+  password: &str,
+  client_ip: &str,
+) -> Result<(), AppError> {
+  let verified_key = (*view_id, verified_password_digest(password_hash, password));
+  if let Some(verified_at) = VERIFIED_PUBLISH_PASSWORDS.get(&verified_key) {
+    if verified_at.elapsed() < VERIFIED_PUBLISH_PASSWORD_TTL {
+      return Ok(());
+    }
+  }
+
+  let attempts_key = (*view_id, client_ip.to_string());
+  if let Some(attempts) = PUBLISH_PASSWORD_FAILED_ATTEMPTS.get(&attempts_key) {
+    let (window_start, failed_attempts) = *attempts.value();
+    if window_start.elapsed() < PUBLISH_PASSWORD_ATTEMPT_WINDOW
+      && failed_attempts >= MAX_PUBLISH_PASSWORD_FAILED_ATTEMPTS
+    {
+      return Err(AppError::TooManyPasswordAttempts(
+        "Too many incorrect passwords, try again later".to_string(),
+      ));
+    }
+  }
+
+  match verify_publish_password(password_hash, password).await {
+    Ok(()) => {
+      PUBLISH_PASSWORD_FAILED_ATTEMPTS.remove(&attempts_key);
+      if VERIFIED_PUBLISH_PASSWORDS.len() >= PUBLISH_PASSWORD_CACHE_CLEANUP_SIZE {
+        VERIFIED_PUBLISH_PASSWORDS
+          .retain(|_, verified_at| verified_at.elapsed() < VERIFIED_PUBLISH_PASSWORD_TTL);
+      }
+      VERIFIED_PUBLISH_PASSWORDS.insert(verified_key, Instant::now());
+      Ok(())
+    },
+    Err(err @ AppError::InvalidPassword(_)) => {
+      if PUBLISH_PASSWORD_FAILED_ATTEMPTS.len() >= PUBLISH_PASSWORD_CACHE_CLEANUP_SIZE {
+        PUBLISH_PASSWORD_FAILED_ATTEMPTS
+          .retain(|_, (window_start, _)| window_start.elapsed() < PUBLISH_PASSWORD_ATTEMPT_WINDOW);
+      }
+      let mut attempts = PUBLISH_PASSWORD_FAILED_ATTEMPTS
+        .entry(attempts_key)
+        .or_insert((Instant::now(), 0));
+      if attempts.0.elapsed() >= PUBLISH_PASSWORD_ATTEMPT_WINDOW {
+        *attempts = (Instant::now(), 0);
+      }
+      attempts.1 += 1;
+      Err(err)
+    },
+    Err(err) => Err(err),
+  }
+}
+
+fn verified_password_digest(password_hash: &str, password: &str) -> [u8; 32] {
+  let mut hasher = Sha256::new();
+  hasher.update(password_hash.as_bytes());
+  hasher.update([0]);
+  hasher.update(password.as_bytes());
+  hasher.finalize().into()
+}
+
+/// Returns whether the user, if any, is a member of the workspace.
+pub async fn is_workspace_member(
+  workspace_access_control: &dyn WorkspaceAccessControl,
+  workspace_id: &Uuid,
+  uid: Option<i64>,
+) -> Result<bool, AppError> {
+  let Some(uid) = uid else {
+    return Ok(false);
+  };
+  match workspace_access_control
+    .enforce_role(&uid, &workspace_id.to_string(), AFRole::Member)
+    .await
+  {
+    Ok(_) => Ok(true),
+    Err(err) if err.is_not_enough_permissions() => Ok(false),
+    Err(err) => Err(err),
+  }
+}
+
+async fn verify_publish_password(password_hash: &str, password: &str) -> Result<(), AppError> {
+  let password_hash = Secret::new(password_hash.to_string());
+  let password = Secret::new(password.to_string());
+  spawn_blocking_with_tracing(move || verify_password_hash(password_hash, password))
+    .await
+    .map_err(|err| AppError::Internal(anyhow!("Failed to verify publish password: {}", err)))?
+    .map_err(|_| AppError::InvalidPassword("Incorrect password".to_string()))
+}
@@ -0,0 +1,238 @@
+use app_error::AppError;
+use chrono::{Duration, NaiveDate, Utc};
+use database::publish::{
+  increment_published_view_duplicate_count, insert_published_view_visit,
+  select_or_insert_publish_analytics_salt, select_published_view_daily_stats,
+  select_published_view_top_referrers,
+};
+use database_entity::dto::{PublishAnalytics, PublishAnalyticsQueryParams};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use url::Url;
+use uuid::Uuid;
+
+use super::publish_domain::host_without_port;
+
+/// Number of days included in the analytics when `since` is not set.
+const DEFAULT_ANALYTICS_DAYS: i64 = 30;
+const MAX_ANALYTICS_DAYS: i64 = 366;
+const MAX_TOP_REFERRERS: i64 = 10;
+
+/// Request of a published view, as seen by the server.
+pub struct PublishedViewVisit<'a> {
+  pub client_ip: Option<&'a str>,
+  pub user_agent: Option<&'a str>,
+  pub referrer: Option<&'a str>,
+  /// Host serving the published view, used to ignore the navigation between published views.
+  pub host: &'a str,
+}
+
+/// Counts a view of the published view. The visitors are identified by a hash of their IP address
+/// and user agent, salted with a random salt that changes every day, so they can't be tracked
+/// across days. Failing to record a view should never fail the request, so errors are only
+/// logged.
+pub async fn record_published_view_visit(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  view_id: &Uuid,
+  visit: PublishedViewVisit<'_>,
+) {
+  let user_agent = visit.user_agent.unwrap_or_default();
+  if is_bot_user_agent(user_agent) {
+    return;
+  }
+  let today = Utc::now().date_naive();
+  let result = async {
+    let salt = select_or_insert_publish_analytics_salt(pg_pool, today, &random_salt()).await?;
+    let visitor_hash = visitor_hash(
+      &salt,
+      view_id,
+      // The address of the peer includes its port, which changes between connections
+      visit.client_ip.map(host_without_port).unwrap_or_default(),
+      user_agent,
+    );
+    let referrer = visit
+      .referrer
+      .and_then(|referrer| referrer_host(referrer, visit.host));
+    insert_published_view_visit(
+      pg_pool,
+      workspace_id,
+      view_id,
+      today,
+      &visitor_hash,
+      referrer.as_deref(),
+    )
+    .await
+  }
+  .await;
+  if let Err(err) = result {
+    tracing::error!(
+      "Failed to record a view of published view {}: {}",
+      view_id,
+      err
+    );
+  }
+}
+
+/// Counts a duplicate of the published view. Errors are only logged, like the views.
+pub async fn record_published_view_duplicate(pg_pool: &PgPool, view_id: &Uuid) {
+  let today = Utc::now().date_naive();
+  if let Err(err) = increment_published_view_duplicate_count(pg_pool, view_id, today).await {
+    tracing::error!(
+      "Failed to record a duplicate of published view {}: {}",
+      view_id,
+      err
+    );
+  }
+}
+
+pub async fn get_publish_analytics(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  params: PublishAnalyticsQueryParams,
+) -> Result<PublishAnalytics, AppError> {
+  let (since, until) = analytics_date_range(params.since, params.until, Utc::now().date_naive())?;
+  let daily_stats =
+    select_published_view_daily_stats(pg_pool, workspace_id, params.view_id.as_ref(), since, until)
+      .await?
+      .into_iter()
+      .map(Into::into)
+      .collect();
+  let top_referrers = select_published_view_top_referrers(
+    pg_pool,
+    workspace_id,
+    params.view_id.as_ref(),
+    since,
+    until,
+    MAX_TOP_REFERRERS,
+  )
+  .await?
+  .into_iter()
+  .map(Into::into)
+  .collect();
+  Ok(PublishAnalytics {
+    daily_stats,
+    top_referrers,
+  })
+}
+
+fn analytics_date_range(
+  since: Option<NaiveDate>,
+  until: Option<NaiveDate>,
+  today: NaiveDate,
+) -> Result<(NaiveDate, NaiveDate), AppError> {
+  let until = until.unwrap_or(today);
+  let since = since.unwrap_or(until - Duration::days(DEFAULT_ANALYTICS_DAYS - 1));
+  if since > until {
+    return Err(AppError::InvalidRequest(
+      "since must not be after until".to_string(),
+    ));
+  }
+  if (until - since).num_days() >= MAX_ANALYTICS_DAYS {
+    return Err(AppError::InvalidRequest(format!(
+      "The analytics cannot span more than {} days",
+      MAX_ANALYTICS_DAYS
+    )));
+  }
+  Ok((since, until))
+}
+
+fn random_salt() -> [u8; 32] {
+  let mut salt = [0u8; 32];
+  rand::thread_rng().fill_bytes(&mut salt);
+  salt
+}
+
+fn visitor_hash(salt: &[u8], view_id: &Uuid, client_ip: &str, user_agent: &str) -> Vec<u8> {
+  let mut hasher = Sha256::new();
+  hasher.update(salt);
+  hasher.update(view_id.as_bytes());
+  hasher.update(client_ip.as_bytes());
+  hasher.update([0]);
+  hasher.update(user_agent.as_bytes());
+  hasher.finalize().to_vec()
+}
+
+/// Only the host of the referrer is kept. Referrers from the host serving the published views
+/// are ignored, as they are navigations between the published views.
+fn referrer_host(referrer: &str, host: &str) -> Option<String> {
+  let referrer_host = Url::parse(referrer).ok()?.host_str()?.to_lowercase();
+  let referrer_host = referrer_host
+    .strip_prefix("www.")
+    .unwrap_or(&referrer_host)
+    .to_string();
+  let host = host_without_port(host).to_lowercase();
+  if referrer_host == host.strip_prefix("www.").unwrap_or(&host) {
+    return None;
+  }
+  Some(referrer_host)
+}
+
+fn is_bot_user_agent(user_agent: &str) -> bool {
+  let user_agent = user_agent.to_lowercase();
+  ["bot", "crawler", "spider", "slurp", "facebookexternalhit"]
+    .iter()
+    .any(|keyword| user_agent.contains(keyword))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn referrer_host_test() {
+    assert_eq!(
+      referrer_host("https://www.Google.com/search?q=appflowy", "appflowy.com"),
+      Some("google.com".to_string())
+    );
+    assert_eq!(
+      referrer_host("https://appflowy.com/ns/page", "appflowy.com:443"),
+      None
+    );
+    assert_eq!(
+      referrer_host("https://www.appflowy.com/ns/page", "appflowy.com"),
+      None
+    );
+    assert_eq!(referrer_host("not a url", "appflowy.com"), None);
+  }
+
+  #[test]
+  fn visitor_hash_test() {
+    let view_id = Uuid::new_v4();
+    let hash = visitor_hash(b"salt", &view_id, "127.0.0.1", "Mozilla/5.0");
+    assert_eq!(
+      hash,
+      visitor_hash(b"salt", &view_id, "127.0.0.1", "Mozilla/5.0")
+    );
+    assert_ne!(
+      hash,
+      visitor_hash(b"other salt", &view_id, "127.0.0.1", "Mozilla/5.0")
+    );
+    assert_ne!(
+      hash,
+      visitor_hash(b"salt", &Uuid::new_v4(), "127.0.0.1", "Mozilla/5.0")
+    );
+  }
+
+  #[test]
+  fn is_bot_user_agent_test() {
+    assert!(is_bot_user_agent(
+      "Mozilla/5.0 (compatible; Googlebot/2.1; +http://www.google.com/bot.html)"
+    ));
+    assert!(!is_bot_user_agent(
+      "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/605.1.15"
+    ));
+  }
+
+  #[test]
+  fn analytics_date_range_test() {
+    let today = NaiveDate::from_ymd_opt(2025, 2, 17).unwrap();
+    assert_eq!(
+      analytics_date_range(None, None, today).unwrap(),
+      (NaiveDate::from_ymd_opt(2025, 1, 19).unwrap(), today)
+    );
+    assert!(analytics_date_range(Some(today), Some(today - Duration::days(1)), today).is_err());
+    assert!(analytics_date_range(Some(today - Duration::days(366)), None, today).is_err());
+  }
+}
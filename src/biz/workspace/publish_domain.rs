@@ -0,0 +1,342 @@
+use anyhow::anyhow;
+use app_error::AppError;
+use async_trait::async_trait;
+use database::pg_row::AFWorkspaceCustomDomain;
+use database::publish::{
+  delete_workspace_custom_domain, select_publish_namespace_for_custom_domain,
+  select_workspace_custom_domain, update_workspace_custom_domain_verified,
+  upsert_workspace_custom_domain,
+};
+use database_entity::dto::PublishCustomDomain;
+use serde::Deserialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// The verification token is expected in the TXT record of `_appflowy-verification.{domain}`.
+const VERIFICATION_RECORD_PREFIX: &str = "_appflowy-verification";
+const VERIFICATION_VALUE_PREFIX: &str = "appflowy-verification=";
+
+/// Looks up the TXT records of a domain name, to verify the custom domains.
+#[async_trait]
+pub trait TxtRecordResolver: Send + Sync {
+  async fn resolve_txt(&self, name: &str) -> Result<Vec<String>, AppError>;
+}
+
+/// Resolves TXT records with the JSON API of a DNS over HTTPS server, e.g.
+/// `https://cloudflare-dns.com/dns-query` or `https://dns.google/resolve`.
+pub struct DnsOverHttpsResolver {
+  client: reqwest::Client,
+  url: String,
+}
+
+impl DnsOverHttpsResolver {
+  pub fn new(url: String) -> Self {
+    Self {
+      client: reqwest::Client::new(),
+      url,
+    }
+  }
+}
+
+#[derive(Deserialize)]
+struct DnsJsonResponse {
+  #[serde(rename = "Status")]
+  status: u32,
+  #[serde(rename = "Answer", default)]
+  answer: Vec<DnsJsonAnswer>,
+}
+
+#[derive(Deserialize)]
+struct DnsJsonAnswer {
+  #[serde(rename = "type")]
+  record_type: u16,
+  data: String,
+}
+
+/// Type of the TXT records in DNS answers.
+const DNS_TXT_RECORD_TYPE: u16 = 16;
+
+/// Status of the DNS responses for names that don't exist.
+const DNS_NXDOMAIN: u32 = 3;
+
+#[async_trait]
+impl TxtRecordResolver for DnsOverHttpsResolver {
+  async fn resolve_txt(&self, name: &str) -> Result<Vec<String>, AppError> {
+    let resp = self
+      .client
+      .get(&self.url)
+      .query(&[("name", name), ("type", "TXT")])
+      .header("accept", "application/dns-json")
+      .send()
+      .await
+      .map_err(|err| AppError::Internal(anyhow!("Failed to resolve {}: {}", name, err)))?
+      .error_for_status()
+      .map_err(|err| AppError::Internal(anyhow!("Failed to resolve {}: {}", name, err)))?
+      .json::<DnsJsonResponse>()
+      .await
+      .map_err(|err| AppError::Internal(anyhow!("Invalid DNS response for {}: {}", name, err)))?;
+    match resp.status {
+      0 => Ok(
+        resp
+          .answer
+          .into_iter()
+          .filter(|answer| answer.record_type == DNS_TXT_RECORD_TYPE)
+          .map(|answer| parse_txt_data(&answer.data))
+          .collect(),
+      ),
+      DNS_NXDOMAIN => Ok(vec![]),
+      status => Err(AppError::Internal(anyhow!(
+        "Failed to resolve {}, DNS status: {}",
+        name,
+        status
+      ))),
+    }
+  }
+}
+
+/// The data of a TXT record is made of quoted strings, which are concatenated.
+fn parse_txt_data(data: &str) -> String {
+  let data = data.trim();
+  if !data.starts_with('"') {
+    return data.to_string();
+  }
+  data
+    .split('"')
+    .skip(1)
+    .step_by(2)
+    .collect::<Vec<_>>()
+    .concat()
+}
+
+/// Lowercases the domain and checks that it is a valid host name with at least two labels.
+fn normalize_custom_domain(domain: &str) -> Result<String, AppError> {
+  let domain = domain.trim().trim_end_matches('.').to_lowercase();
+  let labels = domain.split('.').collect::<Vec<_>>();
+  let is_valid_label = |label: &&str| {
+    !label.is_empty()
+      && label.len() <= 63
+      && !label.starts_with('-')
+      && !label.ends_with('-')
+      && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+  };
+  let is_valid = domain.len() <= 253
+    && labels.len() >= 2
+    && labels.iter().all(is_valid_label)
+    && !labels
+      .last()
+      .is_some_and(|tld| tld.chars().all(|c| c.is_ascii_digit()));
+  if !is_valid {
+    return Err(AppError::InvalidRequest(format!(
+      "Invalid custom domain: {}",
+      domain
+    )));
+  }
+  Ok(domain)
+}
+
+/// The host of a request, without its port.
+pub(crate) fn host_without_port(host: &str) -> &str {
+  match host.rsplit_once(':') {
+    Some((name, port)) if port.chars().all(|c| c.is_ascii_digit()) => name,
+    _ => host,
+  }
+}
+
+fn to_publish_custom_domain(custom_domain: AFWorkspaceCustomDomain) -> PublishCustomDomain {
+  PublishCustomDomain {
+    verification_record_name: format!("{}.{}", VERIFICATION_RECORD_PREFIX, custom_domain.domain),
+    verification_record_value: format!(
+      "{}{}",
+      VERIFICATION_VALUE_PREFIX, custom_domain.verification_token
+    ),
+    domain: custom_domain.domain,
+    verified_at: custom_domain.verified_at,
+    created_at: custom_domain.created_at,
+  }
+}
+
+pub async fn set_workspace_custom_domain(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  domain: &str,
+) -> Result<PublishCustomDomain, AppError> {
+  let domain = normalize_custom_domain(domain)?;
+  let verification_token = Uuid::new_v4().simple().to_string();
+  let custom_domain =
+    upsert_workspace_custom_domain(pg_pool, workspace_id, &domain, &verification_token).await?;
+  Ok(to_publish_custom_domain(custom_domain))
+}
+
+pub async fn get_workspace_custom_domain(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+) -> Result<PublishCustomDomain, AppError> {
+  let custom_domain = select_workspace_custom_domain(pg_pool, workspace_id)
+    .await?
+    .ok_or_else(|| {
+      AppError::RecordNotFound(format!(
+        "No custom domain found for workspace_id: {}",
+        workspace_id
+      ))
+    })?;
+  Ok(to_publish_custom_domain(custom_domain))
+}
+
+/// Marks the custom domain of the workspace as verified if its verification TXT record contains
+/// the verification token.
+pub async fn verify_workspace_custom_domain(
+  pg_pool: &PgPool,
+  resolver: &dyn TxtRecordResolver,
+  workspace_id: &Uuid,
+) -> Result<PublishCustomDomain, AppError> {
+  let custom_domain = get_workspace_custom_domain(pg_pool, workspace_id).await?;
+  if custom_domain.verified_at.is_some() {
+    return Ok(custom_domain);
+  }
+  if !has_verification_record(resolver, &custom_domain).await? {
+    return Err(AppError::InvalidRequest(format!(
+      "TXT record {} does not contain {}",
+      custom_domain.verification_record_name, custom_domain.verification_record_value
+    )));
+  }
+  let custom_domain =
+    update_workspace_custom_domain_verified(pg_pool, workspace_id, &custom_domain.domain).await?;
+  Ok(to_publish_custom_domain(custom_domain))
+}
+
+async fn has_verification_record(
+  resolver: &dyn TxtRecordResolver,
+  custom_domain: &PublishCustomDomain,
+) -> Result<bool, AppError> {
+  let records = resolver
+    .resolve_txt(&custom_domain.verification_record_name)
+    .await?;
+  Ok(
+    records
+      .iter()
+      .any(|record| record.trim() == custom_domain.verification_record_value),
+  )
+}
+
+pub async fn remove_workspace_custom_domain(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+) -> Result<(), AppError> {
+  if !delete_workspace_custom_domain(pg_pool, workspace_id).await? {
+    return Err(AppError::RecordNotFound(format!(
+      "No custom domain found for workspace_id: {}",
+      workspace_id
+    )));
+  }
+  Ok(())
+}
+
+/// Requests sent to a verified custom domain are served from the namespace of the workspace that
+/// owns the domain, whatever the namespace in the path. Other requests use the namespace in the
+/// path.
+pub async fn resolve_publish_namespace(
+  pg_pool: &PgPool,
+  host: &str,
+  publish_namespace: String,
+) -> Result<String, AppError> {
+  let host = host_without_port(host).trim_end_matches('.').to_lowercase();
+  let namespace = select_publish_namespace_for_custom_domain(pg_pool, &host).await?;
+  Ok(namespace.unwrap_or(publish_namespace))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use chrono::Utc;
+
+  struct StubTxtRecordResolver {
+    records: Vec<(String, String)>,
+  }
+
+  #[async_trait]
+  impl TxtRecordResolver for StubTxtRecordResolver {
+    async fn resolve_txt(&self, name: &str) -> Result<Vec<String>, AppError> {
+      Ok(
+        self
+          .records
+          .iter()
+          .filter(|(record_name, _)| record_name == name)
+          .map(|(_, value)| value.clone())
+          .collect(),
+      )
+    }
+  }
+
+  fn custom_domain(domain: &str, token: &str) -> PublishCustomDomain {
+    to_publish_custom_domain(AFWorkspaceCustomDomain {
+      domain: domain.to_string(),
+      workspace_id: Uuid::new_v4(),
+      verification_token: token.to_string(),
+      verified_at: None,
+      created_at: Utc::now(),
+    })
+  }
+
+  #[tokio::test]
+  async fn verification_record_test() {
+    let resolver = StubTxtRecordResolver {
+      records: vec![
+        (
+          "_appflowy-verification.docs.example.com".to_string(),
+          "v=spf1 -all".to_string(),
+        ),
+        (
+          "_appflowy-verification.docs.example.com".to_string(),
+          "appflowy-verification=abc".to_string(),
+        ),
+      ],
+    };
+    assert!(
+      has_verification_record(&resolver, &custom_domain("docs.example.com", "abc"))
+        .await
+        .unwrap()
+    );
+    assert!(
+      !has_verification_record(&resolver, &custom_domain("docs.example.com", "xyz"))
+        .await
+        .unwrap()
+    );
+    assert!(
+      !has_verification_record(&resolver, &custom_domain("example.com", "abc"))
+        .await
+        .unwrap()
+    );
+  }
+
+  #[test]
+  fn normalize_custom_domain_test() {
+    assert_eq!(
+      normalize_custom_domain(" Docs.Example.COM. ").unwrap(),
+      "docs.example.com"
+    );
+    assert!(normalize_custom_domain("localhost").is_err());
+    assert!(normalize_custom_domain("-docs.example.com").is_err());
+    assert!(normalize_custom_domain("docs..example.com").is_err());
+    assert!(normalize_custom_domain("docs.example.com/path").is_err());
+    assert!(normalize_custom_domain("127.0.0.1").is_err());
+  }
+
+  #[test]
+  fn parse_txt_data_test() {
+    assert_eq!(parse_txt_data("\"abc\""), "abc");
+    assert_eq!(
+      parse_txt_data("\"appflowy-\" \"verification=abc\""),
+      "appflowy-verification=abc"
+    );
+    assert_eq!(parse_txt_data("abc"), "abc");
+  }
+
+  #[test]
+  fn host_without_port_test() {
+    assert_eq!(
+      host_without_port("docs.example.com:8000"),
+      "docs.example.com"
+    );
+    assert_eq!(host_without_port("docs.example.com"), "docs.example.com");
+  }
+}
@@ -19,7 +19,7 @@ use collab_entity::CollabType;
 use collab_folder::{CollabOrigin, Folder, RepeatedViewIdentifier, View};
 use database::collab::GetCollabOrigin;
 use database::collab::{select_workspace_database_oid, CollabStorage};
-use database::file::s3_client_impl::AwsS3BucketClientImpl;
+use database::file::bucket_client_impl::BucketClientImpl;
 use database::file::BucketClient;
 use database::file::ResponseBlob;
 use database::publish::select_published_data_for_view_id;
@@ -48,17 +48,22 @@ use crate::biz::collab::utils::get_latest_collab_encoded;
 
 use super::ops::broadcast_update;
 use super::ops::broadcast_update_with_timeout;
+use super::ops::ensure_not_end_to_end_encrypted;
+use super::publish_analytics::record_published_view_duplicate;
 
 #[allow(clippy::too_many_arguments)]
 pub async fn duplicate_published_collab_to_workspace(
   pg_pool: &PgPool,
-  bucket_client: AwsS3BucketClientImpl,
+  bucket_client: BucketClientImpl,
   collab_storage: Arc<CollabAccessControlStorage>,
   dest_uid: i64,
   publish_view_id: String,
   dest_workspace_id: String,
   dest_view_id: String,
 ) -> Result<String, AppError> {
+  let dest_workspace_uuid = uuid::Uuid::parse_str(&dest_workspace_id)?;
+  ensure_not_end_to_end_encrypted(pg_pool, &dest_workspace_uuid).await?;
+
   let copier = PublishCollabDuplicator::new(
     pg_pool.clone(),
     bucket_client,
@@ -75,6 +80,9 @@ pub async fn duplicate_published_collab_to_workspace(
     "duplicate_published_collab_to_workspace: elapsed time: {}ms",
     elapsed
   );
+  if let Ok(publish_view_id) = uuid::Uuid::parse_str(&publish_view_id) {
+    record_published_view_duplicate(pg_pool, &publish_view_id).await;
+  }
   Ok(root_view_id_for_duplicate)
 }
 
@@ -104,7 +112,7 @@ pub struct PublishCollabDuplicator {
   /// and writing them to dest workspace
   pg_pool: PgPool,
   /// for fetching published data from s3
-  bucket_client: AwsS3BucketClientImpl,
+  bucket_client: BucketClientImpl,
   /// user initiating the duplication
   duplicator_uid: i64,
   /// workspace to duplicate into
@@ -116,7 +124,7 @@ pub struct PublishCollabDuplicator {
 impl PublishCollabDuplicator {
   pub fn new(
     pg_pool: PgPool,
-    bucket_client: AwsS3BucketClientImpl,
+    bucket_client: BucketClientImpl,
     collab_storage: Arc<CollabAccessControlStorage>,
     dest_uid: i64,
     dest_workspace_id: String,
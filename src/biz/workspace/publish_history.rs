@@ -0,0 +1,99 @@
+use app_error::AppError;
+use database::pg_row::{AFPublishHistory, AFPublishHistoryVersion};
+use database::publish::{select_publish_history, select_publish_history_version};
+use database_entity::dto::{PublishCollabItem, PublishCollabMetadata};
+use shared_entity::dto::workspace_dto::{PublishHistoryDetail, PublishHistoryInfo};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::publish::PublishedCollabStore;
+
+pub async fn list_publish_history(
+  pg_pool: &PgPool,
+  workspace_id: Uuid,
+  view_id: Uuid,
+) -> Result<Vec<PublishHistoryInfo>, AppError> {
+  let history = select_publish_history(pg_pool, &workspace_id, &view_id).await?;
+  Ok(history.into_iter().map(to_publish_history_info).collect())
+}
+
+pub async fn get_publish_history_detail(
+  pg_pool: &PgPool,
+  workspace_id: Uuid,
+  view_id: Uuid,
+  history_id: Uuid,
+) -> Result<PublishHistoryDetail, AppError> {
+  let version =
+    select_publish_history_version(pg_pool, &workspace_id, &view_id, &history_id).await?;
+  let (info, metadata, _) = split_publish_history_version(version);
+  Ok(PublishHistoryDetail { info, metadata })
+}
+
+pub async fn get_publish_history_blob(
+  pg_pool: &PgPool,
+  workspace_id: Uuid,
+  view_id: Uuid,
+  history_id: Uuid,
+) -> Result<Vec<u8>, AppError> {
+  let version =
+    select_publish_history_version(pg_pool, &workspace_id, &view_id, &history_id).await?;
+  Ok(version.blob)
+}
+
+/// Publish a previous version of the view again. The republished content is recorded as the
+/// latest version in the publish history.
+pub async fn rollback_to_publish_history(
+  pg_pool: &PgPool,
+  publish_collab_store: &dyn PublishedCollabStore,
+  user_uuid: Uuid,
+  workspace_id: Uuid,
+  view_id: Uuid,
+  history_id: Uuid,
+) -> Result<(), AppError> {
+  let version =
+    select_publish_history_version(pg_pool, &workspace_id, &view_id, &history_id).await?;
+  let (info, metadata, blob) = split_publish_history_version(version);
+  publish_collab_store
+    .publish_collabs(
+      vec![PublishCollabItem {
+        meta: PublishCollabMetadata {
+          view_id: info.view_id,
+          publish_name: info.publish_name,
+          metadata,
+        },
+        data: blob,
+        comments_enabled: info.comments_enabled,
+        duplicate_enabled: info.duplicate_enabled,
+      }],
+      &workspace_id,
+      &user_uuid,
+    )
+    .await
+}
+
+fn to_publish_history_info(history: AFPublishHistory) -> PublishHistoryInfo {
+  PublishHistoryInfo {
+    history_id: history.history_id,
+    view_id: history.view_id,
+    publish_name: history.publish_name,
+    publisher_email: history.publisher_email,
+    published_at: history.published_at,
+    comments_enabled: history.comments_enabled,
+    duplicate_enabled: history.duplicate_enabled,
+  }
+}
+
+fn split_publish_history_version(
+  version: AFPublishHistoryVersion,
+) -> (PublishHistoryInfo, serde_json::Value, Vec<u8>) {
+  let info = PublishHistoryInfo {
+    history_id: version.history_id,
+    view_id: version.view_id,
+    publish_name: version.publish_name,
+    publisher_email: version.publisher_email,
+    published_at: version.published_at,
+    comments_enabled: version.comments_enabled,
+    duplicate_enabled: version.duplicate_enabled,
+  };
+  (info, version.metadata, version.blob)
+}
@@ -0,0 +1,129 @@
+use app_error::AppError;
+use appflowy_collaborate::collab::storage::CollabAccessControlStorage;
+use chrono::Utc;
+use database::collab::GetCollabOrigin;
+use database::pg_row::AFPublishSchedule;
+use database::publish::{
+  cancel_publish_schedule, insert_publish_schedule, select_publish_schedules_for_view,
+};
+use shared_entity::dto::workspace_dto::{
+  CreatePublishSchedule, PublishSchedule, PublishScheduleAction, PublishScheduleStatus,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::page_view::generate_publish_name;
+use crate::biz::collab::utils::get_latest_collab_folder;
+
+/// Schedule a publish or unpublish of the view, executed by the worker at `scheduled_at`.
+/// The publish name is resolved when the schedule is created, so that the page is published
+/// under the name shown to the user even if the view is renamed in the meantime.
+pub async fn create_publish_schedule(
+  pg_pool: &PgPool,
+  collab_access_control_storage: &CollabAccessControlStorage,
+  uid: i64,
+  workspace_id: Uuid,
+  view_id: Uuid,
+  params: CreatePublishSchedule,
+) -> Result<PublishSchedule, AppError> {
+  if params.scheduled_at <= Utc::now() {
+    return Err(AppError::InvalidRequest(
+      "The scheduled time must be in the future".to_string(),
+    ));
+  }
+
+  let folder = get_latest_collab_folder(
+    collab_access_control_storage,
+    GetCollabOrigin::User { uid },
+    &workspace_id.to_string(),
+  )
+  .await?;
+  let view_id_str = view_id.to_string();
+  let view = folder
+    .get_view(&view_id_str)
+    .ok_or(AppError::InvalidFolderView(format!(
+      "View {} not found",
+      view_id
+    )))?;
+  if matches!(view.layout, collab_folder::ViewLayout::Chat) {
+    return Err(AppError::InvalidRequest(
+      "AI Chat cannot be published".to_string(),
+    ));
+  }
+
+  if params.params.password.is_some() {
+    // The params of the schedules are stored as they are, so passwords can't be scheduled.
+    return Err(AppError::InvalidRequest(
+      "The password of a published page cannot be scheduled".to_string(),
+    ));
+  }
+
+  let mut publish_params = params.params;
+  if params.action == PublishScheduleAction::Publish && publish_params.publish_name.is_none() {
+    publish_params.publish_name = Some(generate_publish_name(&view_id_str, &view.name));
+  }
+  let schedule = insert_publish_schedule(
+    pg_pool,
+    &Uuid::new_v4(),
+    &workspace_id,
+    &view_id,
+    params.action as i16,
+    params.scheduled_at,
+    uid,
+    serde_json::to_value(publish_params)?,
+  )
+  .await?;
+  to_publish_schedule(schedule)
+}
+
+pub async fn list_publish_schedules(
+  pg_pool: &PgPool,
+  workspace_id: Uuid,
+  view_id: Uuid,
+) -> Result<Vec<PublishSchedule>, AppError> {
+  select_publish_schedules_for_view(pg_pool, &workspace_id, &view_id)
+    .await?
+    .into_iter()
+    .map(to_publish_schedule)
+    .collect()
+}
+
+pub async fn cancel_pending_publish_schedule(
+  pg_pool: &PgPool,
+  workspace_id: Uuid,
+  schedule_id: Uuid,
+) -> Result<(), AppError> {
+  let cancelled = cancel_publish_schedule(pg_pool, &workspace_id, &schedule_id).await?;
+  if !cancelled {
+    return Err(AppError::RecordNotFound(format!(
+      "No pending publish schedule {}",
+      schedule_id
+    )));
+  }
+  Ok(())
+}
+
+fn to_publish_schedule(schedule: AFPublishSchedule) -> Result<PublishSchedule, AppError> {
+  let action = PublishScheduleAction::from_i16(schedule.action).ok_or_else(|| {
+    AppError::Internal(anyhow::anyhow!(
+      "Invalid publish schedule action: {}",
+      schedule.action
+    ))
+  })?;
+  let status = PublishScheduleStatus::from_i16(schedule.status).ok_or_else(|| {
+    AppError::Internal(anyhow::anyhow!(
+      "Invalid publish schedule status: {}",
+      schedule.status
+    ))
+  })?;
+  Ok(PublishSchedule {
+    schedule_id: schedule.schedule_id,
+    view_id: schedule.view_id,
+    action,
+    scheduled_at: schedule.scheduled_at,
+    status,
+    error: schedule.error,
+    created_at: schedule.created_at,
+    executed_at: schedule.executed_at,
+  })
+}
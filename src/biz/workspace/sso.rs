@@ -0,0 +1,111 @@
+use std::sync::Arc;
+
+use access_control::workspace::WorkspaceAccessControl;
+use anyhow::Context;
+use app_error::AppError;
+use database::workspace::{
+  select_auto_join_workspaces_for_user, select_workspace_ids_with_auto_join_domain,
+  upsert_workspace_member_with_txn,
+};
+use database_entity::dto::{AFRole, AFWorkspaceSsoSettings};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::state::GoTrueAdmin;
+
+/// Checks that the SSO settings refer to an existing GoTrue SSO provider, and that the workspace
+/// only captures domains that were registered for that provider and are not captured by another
+/// workspace. The domains are normalized to lowercase.
+pub async fn validate_workspace_sso_settings(
+  pg_pool: &PgPool,
+  gotrue_admin: &GoTrueAdmin,
+  workspace_id: &Uuid,
+  sso: &mut AFWorkspaceSsoSettings,
+) -> Result<(), AppError> {
+  if sso.default_role == AFRole::Owner {
+    return Err(AppError::InvalidRequest(
+      "Users joining by email domain can't be given the owner role".to_string(),
+    ));
+  }
+
+  let admin_token = gotrue_admin.token().await?;
+  let provider = gotrue_admin
+    .gotrue_client
+    .admin_get_sso_provider(&admin_token, &sso.provider_id)
+    .await
+    .map_err(|err| {
+      AppError::InvalidRequest(format!(
+        "SSO provider {} is not available: {}",
+        sso.provider_id, err
+      ))
+    })?;
+
+  for domain in sso.auto_join_domains.iter_mut() {
+    *domain = domain.trim().to_lowercase();
+    if !provider
+      .domains
+      .iter()
+      .any(|provider_domain| provider_domain.eq_ignore_ascii_case(domain))
+    {
+      return Err(AppError::InvalidRequest(format!(
+        "Domain {} is not registered for SSO provider {}",
+        domain, sso.provider_id
+      )));
+    }
+
+    let workspace_ids = select_workspace_ids_with_auto_join_domain(pg_pool, domain).await?;
+    if workspace_ids.iter().any(|id| id != workspace_id) {
+      return Err(AppError::InvalidRequest(format!(
+        "Domain {} is already captured by another workspace",
+        domain
+      )));
+    }
+  }
+  sso.auto_join_domains.sort();
+  sso.auto_join_domains.dedup();
+  Ok(())
+}
+
+/// Adds the user to every workspace that captures the domain of their email, given that the
+/// user signed in with the SSO provider of that workspace.
+pub async fn auto_join_workspaces_by_email_domain(
+  pg_pool: &PgPool,
+  workspace_access_control: &Arc<dyn WorkspaceAccessControl>,
+  uid: i64,
+  email: &str,
+  sso_provider_id: &str,
+) -> Result<(), AppError> {
+  let email_domain = match email.rsplit_once('@') {
+    Some((_, domain)) => domain.to_lowercase(),
+    None => return Ok(()),
+  };
+
+  let workspaces = select_auto_join_workspaces_for_user(pg_pool, &email_domain, uid).await?;
+  for (workspace_id, settings) in workspaces {
+    let sso = match settings.sso {
+      Some(sso) if sso.provider_id == sso_provider_id => sso,
+      _ => continue,
+    };
+
+    tracing::info!(
+      "user {} joins workspace {} by email domain {}",
+      uid,
+      workspace_id,
+      email_domain
+    );
+    let mut txn = pg_pool
+      .begin()
+      .await
+      .context("Begin transaction to join workspace by email domain")?;
+    upsert_workspace_member_with_txn(&mut txn, &workspace_id, email, sso.default_role.clone())
+      .await?;
+    workspace_access_control
+      .insert_role(&uid, &workspace_id, sso.default_role)
+      .await?;
+    txn
+      .commit()
+      .await
+      .context("Commit transaction to join workspace by email domain")?;
+  }
+  Ok(())
+}
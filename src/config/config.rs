@@ -21,9 +21,13 @@ pub struct Config {
   pub redis_uri: Secret<String>,
   pub redis_worker_count: usize,
   pub s3: S3Setting,
+  pub blob_storage: BlobStorageSetting,
+  pub storage_quota: StorageQuotaSetting,
+  pub malware_scan: MalwareScanSetting,
   pub appflowy_ai: AppFlowyAISetting,
   pub collab: CollabSetting,
   pub published_collab: PublishedCollabSetting,
+  pub access_request: AccessRequestSetting,
   pub mailer: MailerSetting,
   pub apple_oauth: AppleOAuthSetting,
   pub appflowy_web_url: Option<String>,
@@ -62,6 +66,70 @@ pub struct S3Setting {
   pub presigned_url_endpoint: Option<String>,
 }
 
+#[derive(Clone, Debug)]
+pub enum BlobStorageBackend {
+  S3,
+  LocalFs,
+}
+
+impl TryFrom<&str> for BlobStorageBackend {
+  type Error = anyhow::Error;
+
+  fn try_from(value: &str) -> Result<Self, Self::Error> {
+    match value {
+      "s3" => Ok(BlobStorageBackend::S3),
+      "local_fs" => Ok(BlobStorageBackend::LocalFs),
+      _ => Err(anyhow::anyhow!("Invalid BlobStorageBackend")),
+    }
+  }
+}
+
+#[derive(Clone, Debug)]
+pub struct BlobStorageSetting {
+  pub backend: BlobStorageBackend,
+  /// Directory where blobs are stored when using [BlobStorageBackend::LocalFs]. It must be shared
+  /// with the collaboration server and the worker.
+  pub local_path: String,
+  /// Multipart uploads that don't receive any part for this long, in seconds, are considered
+  /// abandoned and aborted by the worker.
+  pub upload_session_expiry_secs: u64,
+}
+
+/// Default storage quotas of the workspaces, which can be overridden per workspace. A limit of 0
+/// means unlimited.
+#[derive(Clone, Debug)]
+pub struct StorageQuotaSetting {
+  /// Maximum total size, in bytes, of the files of a workspace.
+  pub workspace_storage_limit: u64,
+  /// Maximum size, in bytes, of a single file.
+  pub single_upload_limit: u64,
+}
+
+#[derive(Clone, Debug)]
+pub enum MalwareScanBackend {
+  Disabled,
+  ClamAv,
+}
+
+impl TryFrom<&str> for MalwareScanBackend {
+  type Error = anyhow::Error;
+
+  fn try_from(value: &str) -> Result<Self, Self::Error> {
+    match value {
+      "disabled" => Ok(MalwareScanBackend::Disabled),
+      "clamav" => Ok(MalwareScanBackend::ClamAv),
+      _ => Err(anyhow::anyhow!("Invalid MalwareScanBackend")),
+    }
+  }
+}
+
+#[derive(Clone, Debug)]
+pub struct MalwareScanSetting {
+  pub backend: MalwareScanBackend,
+  /// Address of clamd, either `tcp://host:port` or `unix:///path/to/clamd.sock`.
+  pub clamav_address: String,
+}
+
 #[derive(serde::Deserialize, Clone, Debug)]
 pub struct GoTrueSetting {
   pub base_url: String,
@@ -142,6 +210,16 @@ pub struct CollabSetting {
   pub edit_state_max_count: u32,
   pub edit_state_max_secs: i64,
   pub s3_collab_threshold: u64,
+  /// Master keys used to encrypt the collabs at rest, formatted as `<key id>:<hex encoded key>`
+  /// and separated by commas, the first key being the current one. Empty disables the encryption.
+  pub encryption_master_keys: Secret<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct AccessRequestSetting {
+  /// Pending access requests older than this are considered expired and can no longer be
+  /// approved. The requester is allowed to create a new request for the same view afterwards.
+  pub expire_days: i64,
 }
 
 #[derive(Clone, Debug)]
@@ -153,6 +231,8 @@ pub enum PublishedCollabStorageBackend {
 #[derive(Clone, Debug)]
 pub struct PublishedCollabSetting {
   pub storage_backend: PublishedCollabStorageBackend,
+  /// JSON API of the DNS over HTTPS server used to verify the custom domains.
+  pub dns_over_https_url: String,
 }
 
 impl TryFrom<&str> for PublishedCollabStorageBackend {
@@ -231,6 +311,27 @@ pub fn get_configuration() -> Result<Config, anyhow::Error> {
       region: get_env_var("APPFLOWY_S3_REGION", ""),
       presigned_url_endpoint: get_env_var_opt("APPFLOWY_S3_PRESIGNED_URL_ENDPOINT"),
     },
+    blob_storage: BlobStorageSetting {
+      backend: get_env_var("APPFLOWY_BLOB_STORAGE_BACKEND", "s3")
+        .as_str()
+        .try_into()?,
+      local_path: get_env_var("APPFLOWY_BLOB_STORAGE_LOCAL_PATH", "./data/blob"),
+      upload_session_expiry_secs: get_env_var("APPFLOWY_UPLOAD_SESSION_EXPIRY_SECS", "86400")
+        .parse()?,
+    },
+    storage_quota: StorageQuotaSetting {
+      workspace_storage_limit: get_env_var("APPFLOWY_WORKSPACE_STORAGE_LIMIT", "0").parse()?,
+      single_upload_limit: get_env_var("APPFLOWY_SINGLE_UPLOAD_LIMIT", "0").parse()?,
+    },
+    malware_scan: MalwareScanSetting {
+      backend: get_env_var("APPFLOWY_MALWARE_SCAN_BACKEND", "disabled")
+        .as_str()
+        .try_into()?,
+      clamav_address: get_env_var(
+        "APPFLOWY_MALWARE_SCAN_CLAMAV_ADDRESS",
+        "tcp://localhost:3310",
+      ),
+    },
     appflowy_ai: AppFlowyAISetting {
       port: get_env_var("AI_SERVER_PORT", "5001").into(),
       host: get_env_var("AI_SERVER_HOST", "localhost").into(),
@@ -246,11 +347,19 @@ pub fn get_configuration() -> Result<Config, anyhow::Error> {
       edit_state_max_count: get_env_var("APPFLOWY_COLLAB_EDIT_STATE_MAX_COUNT", "100").parse()?,
       edit_state_max_secs: get_env_var("APPFLOWY_COLLAB_EDIT_STATE_MAX_SECS", "60").parse()?,
       s3_collab_threshold: get_env_var("APPFLOWY_COLLAB_S3_THRESHOLD", "8000").parse()?,
+      encryption_master_keys: get_env_var("APPFLOWY_COLLAB_ENCRYPTION_MASTER_KEYS", "").into(),
     },
     published_collab: PublishedCollabSetting {
       storage_backend: get_env_var("APPFLOWY_PUBLISHED_COLLAB_STORAGE_BACKEND", "postgres")
         .as_str()
         .try_into()?,
+      dns_over_https_url: get_env_var(
+        "APPFLOWY_PUBLISHED_COLLAB_DNS_OVER_HTTPS_URL",
+        "https://cloudflare-dns.com/dns-query",
+      ),
+    },
+    access_request: AccessRequestSetting {
+      expire_days: get_env_var("APPFLOWY_ACCESS_REQUEST_EXPIRE_DAYS", "30").parse()?,
     },
     mailer: MailerSetting {
       smtp_host: get_env_var("APPFLOWY_MAILER_SMTP_HOST", "smtp.gmail.com"),
@@ -1,5 +1,6 @@
 use std::sync::Arc;
 
+use access_control::casbin::access::AccessControl;
 use access_control::collab::{CollabAccessControl, RealtimeAccessControl};
 use access_control::workspace::WorkspaceAccessControl;
 use dashmap::DashMap;
@@ -18,7 +19,8 @@ use appflowy_collaborate::metrics::CollabMetrics;
 use appflowy_collaborate::CollabRealtimeMetrics;
 use collab_stream::metrics::CollabStreamMetrics;
 use collab_stream::stream_router::StreamRouter;
-use database::file::s3_client_impl::{AwsS3BucketClientImpl, S3BucketStorage};
+use database::encryption::CollabEncryption;
+use database::file::bucket_client_impl::{BucketClientImpl, BucketStorageImpl};
 use database::user::{select_all_uid_uuid, select_uid_from_uuid};
 use gotrue::grant::{Grant, PasswordGrant};
 use indexer::metrics::EmbeddingMetrics;
@@ -27,8 +29,10 @@ use snowflake::Snowflake;
 
 use crate::api::metrics::{AppFlowyWebMetrics, PublishedCollabMetrics, RequestMetrics};
 use crate::biz::chat::metrics::AIMetrics;
+use crate::biz::file_storage::malware_scan::MalwareScanner;
 use crate::biz::pg_listener::PgListeners;
 use crate::biz::workspace::publish::PublishedCollabStore;
+use crate::biz::workspace::publish_domain::TxtRecordResolver;
 use crate::config::config::Config;
 use crate::mailer::AFCloudMailer;
 
@@ -47,15 +51,22 @@ pub struct AppState {
   pub collab_access_control: Arc<dyn CollabAccessControl>,
   pub workspace_access_control: Arc<dyn WorkspaceAccessControl>,
   pub realtime_access_control: Arc<dyn RealtimeAccessControl>,
-  pub bucket_storage: Arc<S3BucketStorage>,
+  /// The casbin access control that backs the workspace and collab access controls above.
+  /// Only used to explain policy decisions; use the access controls above to guard requests.
+  pub access_control: AccessControl,
+  pub bucket_storage: Arc<BucketStorageImpl>,
   pub published_collab_store: Arc<dyn PublishedCollabStore>,
-  pub bucket_client: AwsS3BucketClientImpl,
+  /// Resolves the TXT records that verify the custom domains of the publish namespaces.
+  pub txt_record_resolver: Arc<dyn TxtRecordResolver>,
+  pub bucket_client: BucketClientImpl,
   pub pg_listeners: Arc<PgListeners>,
   pub metrics: AppMetrics,
   pub gotrue_admin: GoTrueAdmin,
   pub mailer: AFCloudMailer,
   pub ai_client: AppFlowyAIClient,
   pub indexer_scheduler: Arc<IndexerScheduler>,
+  pub malware_scanner: Option<Arc<dyn MalwareScanner>>,
+  pub collab_encryption: Option<CollabEncryption>,
 }
 
 impl AppState {
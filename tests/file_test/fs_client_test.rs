@@ -0,0 +1,173 @@
+use app_error::ErrorCode;
+use database::file::fs_client_impl::LocalFsBucketClientImpl;
+use database::file::{BucketClient, ResponseBlob};
+use database_entity::file_dto::{
+  CompleteUploadRequest, CompletedPartRequest, CreateUploadRequest, UploadPartData,
+};
+
+use crate::collab::util::generate_random_bytes;
+
+async fn create_upload(client: &LocalFsBucketClientImpl, object_key: &str) -> String {
+  client
+    .create_upload(
+      object_key,
+      CreateUploadRequest {
+        file_id: "file".to_string(),
+        parent_dir: "dir".to_string(),
+        content_type: mime::TEXT_PLAIN_UTF_8.to_string(),
+        file_size: None,
+      },
+    )
+    .await
+    .unwrap()
+    .upload_id
+}
+
+async fn upload_part(
+  client: &LocalFsBucketClientImpl,
+  object_key: &str,
+  upload_id: &str,
+  part_number: i32,
+  body: Vec<u8>,
+) -> Result<CompletedPartRequest, app_error::AppError> {
+  let resp = client
+    .upload_part(
+      object_key,
+      UploadPartData {
+        file_id: "file".to_string(),
+        upload_id: upload_id.to_string(),
+        part_number,
+        body,
+      },
+    )
+    .await?;
+  Ok(CompletedPartRequest {
+    e_tag: resp.e_tag,
+    part_number: resp.part_num,
+  })
+}
+
+fn complete_request(upload_id: &str, parts: Vec<CompletedPartRequest>) -> CompleteUploadRequest {
+  CompleteUploadRequest {
+    file_id: "file".to_string(),
+    parent_dir: "dir".to_string(),
+    upload_id: upload_id.to_string(),
+    parts,
+  }
+}
+
+#[tokio::test]
+async fn fs_multiple_part_upload_test() {
+  let root = tempfile::tempdir().unwrap();
+  let client = LocalFsBucketClientImpl::new(root.path()).await.unwrap();
+  let object_key = "workspace/dir/file";
+  let upload_id = create_upload(&client, object_key).await;
+
+  let first = generate_random_bytes(1024);
+  let second = generate_random_bytes(1024);
+  // Parts can be uploaded in any order
+  let second_part = upload_part(&client, object_key, &upload_id, 2, second.clone())
+    .await
+    .unwrap();
+  let first_part = upload_part(&client, object_key, &upload_id, 1, first.clone())
+    .await
+    .unwrap();
+  assert_eq!(
+    client
+      .list_upload_parts(object_key, &upload_id)
+      .await
+      .unwrap()
+      .len(),
+    2
+  );
+
+  let (content_length, content_type) = client
+    .complete_upload(
+      object_key,
+      complete_request(&upload_id, vec![second_part, first_part]),
+    )
+    .await
+    .unwrap();
+  assert_eq!(content_length, first.len() + second.len());
+  assert_eq!(content_type, mime::TEXT_PLAIN_UTF_8.to_string());
+  let blob = client.get_blob(object_key).await.unwrap().to_blob();
+  assert_eq!(blob, [first, second].concat());
+}
+
+#[tokio::test]
+async fn fs_upload_is_bound_to_its_object_key_test() {
+  let root = tempfile::tempdir().unwrap();
+  let client = LocalFsBucketClientImpl::new(root.path()).await.unwrap();
+  let object_key = "workspace/dir/file";
+  let other_object_key = "workspace/dir/other_file";
+  let upload_id = create_upload(&client, object_key).await;
+  let part = upload_part(
+    &client,
+    object_key,
+    &upload_id,
+    1,
+    generate_random_bytes(1024),
+  )
+  .await
+  .unwrap();
+
+  let err = upload_part(
+    &client,
+    other_object_key,
+    &upload_id,
+    2,
+    generate_random_bytes(1024),
+  )
+  .await
+  .unwrap_err();
+  assert_eq!(err.code(), ErrorCode::RecordNotFound);
+
+  let err = client
+    .list_upload_parts(other_object_key, &upload_id)
+    .await
+    .unwrap_err();
+  assert_eq!(err.code(), ErrorCode::RecordNotFound);
+
+  let err = client
+    .complete_upload(
+      other_object_key,
+      complete_request(
+        &upload_id,
+        vec![CompletedPartRequest {
+          e_tag: part.e_tag.clone(),
+          part_number: part.part_number,
+        }],
+      ),
+    )
+    .await
+    .unwrap_err();
+  assert_eq!(err.code(), ErrorCode::RecordNotFound);
+  client.get_blob(other_object_key).await.unwrap_err();
+
+  let err = client
+    .abort_upload(other_object_key, &upload_id)
+    .await
+    .unwrap_err();
+  assert_eq!(err.code(), ErrorCode::RecordNotFound);
+
+  // The upload is left intact
+  client
+    .complete_upload(object_key, complete_request(&upload_id, vec![part]))
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn fs_complete_upload_without_parts_test() {
+  let root = tempfile::tempdir().unwrap();
+  let client = LocalFsBucketClientImpl::new(root.path()).await.unwrap();
+  let object_key = "workspace/dir/file";
+  let upload_id = create_upload(&client, object_key).await;
+
+  let err = client
+    .complete_upload(object_key, complete_request(&upload_id, vec![]))
+    .await
+    .unwrap_err();
+  assert_eq!(err.code(), ErrorCode::InvalidRequest);
+  client.get_blob(object_key).await.unwrap_err();
+}
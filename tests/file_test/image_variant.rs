@@ -0,0 +1,105 @@
+use std::io::Cursor;
+
+use client_api_test::{generate_unique_registered_user_client, workspace_id_from_client};
+use image::{GenericImageView, ImageOutputFormat, RgbaImage};
+
+fn png_image(width: u32, height: u32) -> Vec<u8> {
+  let image = image::DynamicImage::ImageRgba8(RgbaImage::new(width, height));
+  let mut buf = Cursor::new(Vec::new());
+  image.write_to(&mut buf, ImageOutputFormat::Png).unwrap();
+  buf.into_inner()
+}
+
+#[tokio::test]
+async fn get_resized_image_variant_test() {
+  let (c1, _user1) = generate_unique_registered_user_client().await;
+  let workspace_id = workspace_id_from_client(&c1).await;
+  let parent_dir = uuid::Uuid::new_v4().to_string();
+  let data = png_image(800, 400);
+  let resp = c1
+    .put_blob_v1(&workspace_id, &parent_dir, data.clone(), &mime::IMAGE_PNG)
+    .await
+    .unwrap();
+
+  // The requested width is rounded up to the width of a variant
+  for _ in 0..2 {
+    let (mime, variant) = c1
+      .get_blob_v1_resized(&workspace_id, &parent_dir, &resp.file_id, 200)
+      .await
+      .unwrap();
+    assert_eq!(mime, mime::IMAGE_PNG);
+    let variant = image::load_from_memory(&variant).unwrap();
+    assert_eq!(variant.dimensions(), (256, 128));
+  }
+
+  // Images are never upscaled, the second request is served from the stored empty variant
+  for _ in 0..2 {
+    let (mime, original) = c1
+      .get_blob_v1_resized(&workspace_id, &parent_dir, &resp.file_id, 2048)
+      .await
+      .unwrap();
+    assert_eq!(mime, mime::IMAGE_PNG);
+    assert_eq!(original, data);
+  }
+
+  c1.delete_blob_v1(&workspace_id, &parent_dir, &resp.file_id)
+    .await
+    .unwrap();
+}
+
+/// A tiny PNG whose header declares the given dimensions, without the pixel data to match.
+fn png_with_declared_dimensions(width: u32, height: u32) -> Vec<u8> {
+  let mut data = png_image(1, 1);
+  // The IHDR chunk follows the 8 bytes signature: 4 bytes length, 4 bytes type, then the width
+  // and height, and its CRC covers the type and the 13 bytes of data.
+  data[16..20].copy_from_slice(&width.to_be_bytes());
+  data[20..24].copy_from_slice(&height.to_be_bytes());
+  let mut crc = flate2::Crc::new();
+  crc.update(&data[12..29]);
+  data[29..33].copy_from_slice(&crc.sum().to_be_bytes());
+  data
+}
+
+#[tokio::test]
+async fn get_image_variant_of_huge_image_test() {
+  let (c1, _user1) = generate_unique_registered_user_client().await;
+  let workspace_id = workspace_id_from_client(&c1).await;
+  let parent_dir = uuid::Uuid::new_v4().to_string();
+  let data = png_with_declared_dimensions(2000, 200_000);
+  let resp = c1
+    .put_blob_v1(&workspace_id, &parent_dir, data.clone(), &mime::IMAGE_PNG)
+    .await
+    .unwrap();
+
+  // The image is never decoded, the original is served instead
+  let (mime, original) = c1
+    .get_blob_v1_resized(&workspace_id, &parent_dir, &resp.file_id, 256)
+    .await
+    .unwrap();
+  assert_eq!(mime, mime::IMAGE_PNG);
+  assert_eq!(original, data);
+}
+
+#[tokio::test]
+async fn get_non_image_blob_with_width_test() {
+  let (c1, _user1) = generate_unique_registered_user_client().await;
+  let workspace_id = workspace_id_from_client(&c1).await;
+  let parent_dir = uuid::Uuid::new_v4().to_string();
+  let data = "hello world".to_string();
+  let resp = c1
+    .put_blob_v1(
+      &workspace_id,
+      &parent_dir,
+      data.clone(),
+      &mime::TEXT_PLAIN_UTF_8,
+    )
+    .await
+    .unwrap();
+
+  let (mime, got_data) = c1
+    .get_blob_v1_resized(&workspace_id, &parent_dir, &resp.file_id, 256)
+    .await
+    .unwrap();
+  assert_eq!(mime, mime::TEXT_PLAIN_UTF_8);
+  assert_eq!(String::from_utf8(got_data).unwrap(), data);
+}
@@ -2,8 +2,13 @@ use std::borrow::Cow;
 use std::ops::Deref;
 
 mod delete_dir_test;
+mod fs_client_test;
+mod image_variant;
 mod multiple_part_test;
+mod orphaned_blobs;
 mod put_and_get;
+mod quota;
+mod range_test;
 mod usage;
 
 use appflowy_cloud::application::get_aws_s3_client;
@@ -5,7 +5,7 @@ use appflowy_cloud::api::file_storage::BlobPathV1;
 use aws_sdk_s3::types::CompletedPart;
 use bytes::Bytes;
 use client_api_test::{generate_unique_registered_user_client, workspace_id_from_client};
-use database::file::{BlobKey, BucketClient, ResponseBlob};
+use database::file::{BlobKey, BucketClient, ResponseBlob, BLOB_CONTENT_DIR};
 use database_entity::file_dto::{
   CompleteUploadRequest, CompletedPartRequest, CreateUploadRequest, UploadPartData,
 };
@@ -145,6 +145,254 @@ async fn single_part_put_and_get_test() {
   assert_eq!(blob_text, text);
 }
 
+#[tokio::test]
+async fn resume_multiple_part_upload_test() {
+  let (c1, _user1) = generate_unique_registered_user_client().await;
+  let workspace_id = workspace_id_from_client(&c1).await;
+  let parent_dir = workspace_id.clone();
+  let mime = mime::TEXT_PLAIN_UTF_8;
+  let text = generate_random_string(8 * 1024 * 1024);
+  let file_id = Uuid::new_v4().to_string();
+
+  let upload = c1
+    .create_upload(
+      &workspace_id,
+      CreateUploadRequest {
+        file_id: file_id.clone(),
+        parent_dir: parent_dir.clone(),
+        content_type: mime.to_string(),
+        file_size: Some(text.len() as u64),
+      },
+    )
+    .await
+    .unwrap();
+  let mut chunked_bytes = ChunkedBytes::from_bytes(Bytes::from(text.clone())).unwrap();
+  chunked_bytes.set_chunk_size(5 * 1024 * 1024).unwrap();
+  let chunks = chunked_bytes
+    .iter()
+    .map(|chunk| chunk.to_vec())
+    .collect::<Vec<_>>();
+  assert_eq!(chunks.len(), 2);
+
+  // The client is interrupted after uploading the first part
+  c1.upload_part(
+    &workspace_id,
+    &parent_dir,
+    &file_id,
+    &upload.upload_id,
+    1,
+    chunks[0].clone(),
+  )
+  .await
+  .unwrap();
+
+  let listed = c1
+    .list_upload_parts(&workspace_id, &parent_dir, &file_id, &upload.upload_id)
+    .await
+    .unwrap();
+  assert_eq!(listed.upload_id, upload.upload_id);
+  assert_eq!(listed.parts.len(), 1);
+  assert_eq!(listed.parts[0].part_number, 1);
+  assert_eq!(listed.parts[0].size, chunks[0].len() as u64);
+
+  // Resume the upload by only sending the missing part
+  let resp = c1
+    .upload_part(
+      &workspace_id,
+      &parent_dir,
+      &file_id,
+      &upload.upload_id,
+      2,
+      chunks[1].clone(),
+    )
+    .await
+    .unwrap();
+  let mut parts = listed
+    .parts
+    .into_iter()
+    .map(|part| CompletedPartRequest {
+      e_tag: part.e_tag,
+      part_number: part.part_number,
+    })
+    .collect::<Vec<_>>();
+  parts.push(CompletedPartRequest {
+    e_tag: resp.e_tag,
+    part_number: resp.part_num,
+  });
+  let req = CompleteUploadRequest {
+    file_id: file_id.clone(),
+    parent_dir: parent_dir.clone(),
+    upload_id: upload.upload_id.clone(),
+    parts,
+  };
+  c1.complete_upload(&workspace_id, req).await.unwrap();
+
+  let blob = c1
+    .get_blob_v1(&workspace_id, &parent_dir, &file_id)
+    .await
+    .unwrap()
+    .1;
+  assert_eq!(String::from_utf8(blob.to_vec()).unwrap(), text);
+
+  // The session of a completed upload is removed
+  let err = c1
+    .list_upload_parts(&workspace_id, &parent_dir, &file_id, &upload.upload_id)
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::RecordNotFound);
+}
+
+#[tokio::test]
+async fn abort_multiple_part_upload_test() {
+  let (c1, _user1) = generate_unique_registered_user_client().await;
+  let workspace_id = workspace_id_from_client(&c1).await;
+  let parent_dir = workspace_id.clone();
+  let mime = mime::TEXT_PLAIN_UTF_8;
+  let file_id = Uuid::new_v4().to_string();
+
+  let upload = c1
+    .create_upload(
+      &workspace_id,
+      CreateUploadRequest {
+        file_id: file_id.clone(),
+        parent_dir: parent_dir.clone(),
+        content_type: mime.to_string(),
+        file_size: None,
+      },
+    )
+    .await
+    .unwrap();
+  let resp = c1
+    .upload_part(
+      &workspace_id,
+      &parent_dir,
+      &file_id,
+      &upload.upload_id,
+      1,
+      generate_random_bytes(1024),
+    )
+    .await
+    .unwrap();
+
+  c1.abort_upload(&workspace_id, &parent_dir, &file_id, &upload.upload_id)
+    .await
+    .unwrap();
+
+  let err = c1
+    .list_upload_parts(&workspace_id, &parent_dir, &file_id, &upload.upload_id)
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::RecordNotFound);
+
+  // An aborted upload can't be completed
+  let req = CompleteUploadRequest {
+    file_id: file_id.clone(),
+    parent_dir: parent_dir.clone(),
+    upload_id: upload.upload_id.clone(),
+    parts: vec![CompletedPartRequest {
+      e_tag: resp.e_tag,
+      part_number: resp.part_num,
+    }],
+  };
+  c1.complete_upload(&workspace_id, req).await.unwrap_err();
+}
+
+#[tokio::test]
+async fn upload_part_of_other_workspace_upload_test() {
+  let (c1, _user1) = generate_unique_registered_user_client().await;
+  let (c2, _user2) = generate_unique_registered_user_client().await;
+  let workspace_id = workspace_id_from_client(&c1).await;
+  let other_workspace_id = workspace_id_from_client(&c2).await;
+  let parent_dir = workspace_id.clone();
+  let file_id = Uuid::new_v4().to_string();
+
+  let upload = c1
+    .create_upload(
+      &workspace_id,
+      CreateUploadRequest {
+        file_id: file_id.clone(),
+        parent_dir: parent_dir.clone(),
+        content_type: mime::TEXT_PLAIN_UTF_8.to_string(),
+        file_size: None,
+      },
+    )
+    .await
+    .unwrap();
+
+  // The upload belongs to the workspace of the first user
+  let err = c2
+    .upload_part(
+      &other_workspace_id,
+      &parent_dir,
+      &file_id,
+      &upload.upload_id,
+      1,
+      generate_random_bytes(1024),
+    )
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::RecordNotFound);
+
+  // The upload is bound to the file it was created for
+  let err = c1
+    .upload_part(
+      &workspace_id,
+      &parent_dir,
+      &Uuid::new_v4().to_string(),
+      &upload.upload_id,
+      1,
+      generate_random_bytes(1024),
+    )
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::RecordNotFound);
+}
+
+#[tokio::test]
+async fn complete_upload_into_content_dir_test() {
+  let (c1, _user1) = generate_unique_registered_user_client().await;
+  let workspace_id = workspace_id_from_client(&c1).await;
+  let parent_dir = workspace_id.clone();
+  let file_id = Uuid::new_v4().to_string();
+
+  let upload = c1
+    .create_upload(
+      &workspace_id,
+      CreateUploadRequest {
+        file_id: file_id.clone(),
+        parent_dir: parent_dir.clone(),
+        content_type: mime::TEXT_PLAIN_UTF_8.to_string(),
+        file_size: None,
+      },
+    )
+    .await
+    .unwrap();
+  let resp = c1
+    .upload_part(
+      &workspace_id,
+      &parent_dir,
+      &file_id,
+      &upload.upload_id,
+      1,
+      generate_random_bytes(1024),
+    )
+    .await
+    .unwrap();
+
+  // The content shared by the deduplicated blobs can't be overwritten
+  let req = CompleteUploadRequest {
+    file_id: file_id.clone(),
+    parent_dir: BLOB_CONTENT_DIR.to_string(),
+    upload_id: upload.upload_id.clone(),
+    parts: vec![CompletedPartRequest {
+      e_tag: resp.e_tag,
+      part_number: resp.part_num,
+    }],
+  };
+  let err = c1.complete_upload(&workspace_id, req).await.unwrap_err();
+  assert_eq!(err.code, ErrorCode::InvalidRequest);
+}
+
 #[tokio::test]
 async fn empty_part_upload_test() {
   // Test with empty part
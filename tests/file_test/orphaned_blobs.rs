@@ -0,0 +1,38 @@
+use app_error::ErrorCode;
+use client_api_test::TestClient;
+use database_entity::dto::AFRole;
+
+#[tokio::test]
+async fn owner_get_orphaned_blobs_report() {
+  let client = TestClient::new_user_without_ws_conn().await;
+  let workspace_id = client.workspace_id().await;
+  let mime = mime::TEXT_PLAIN_UTF_8;
+  let file_id = uuid::Uuid::new_v4().to_string();
+  client.upload_blob(&file_id, "123", &mime).await;
+
+  // Recently uploaded blobs are never orphaned
+  let report = client
+    .api_client
+    .get_orphaned_blobs(&workspace_id)
+    .await
+    .unwrap();
+  assert!(report.blobs.is_empty());
+}
+
+#[tokio::test]
+async fn member_cannot_get_orphaned_blobs_report() {
+  let owner = TestClient::new_user_without_ws_conn().await;
+  let member = TestClient::new_user_without_ws_conn().await;
+  let workspace_id = owner.workspace_id().await;
+  owner
+    .invite_and_accepted_workspace_member(&workspace_id, &member, AFRole::Member)
+    .await
+    .unwrap();
+
+  let err = member
+    .api_client
+    .get_orphaned_blobs(&workspace_id)
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::NotEnoughPermissions);
+}
@@ -4,7 +4,10 @@ use app_error::ErrorCode;
 
 use crate::collab::util::generate_random_string;
 use client_api_test::{generate_unique_registered_user_client, workspace_id_from_client};
-use database::file::{BucketClient, ResponseBlob};
+use database::file::{blob_content_object_key, BucketClient, ResponseBlob};
+use sha2::{Digest, Sha256};
+use shared_entity::dto::workspace_dto::BlobStatus;
+use uuid::Uuid;
 
 #[tokio::test]
 async fn get_but_not_exists() {
@@ -44,6 +47,30 @@ async fn put_and_get() {
   c1.delete_blob(&url).await.unwrap();
 }
 
+#[tokio::test]
+async fn put_and_get_metadata_without_malware_scanner() {
+  let (c1, _user1) = generate_unique_registered_user_client().await;
+  let workspace_id = workspace_id_from_client(&c1).await;
+  let parent_dir = uuid::Uuid::new_v4().to_string();
+  let resp = c1
+    .put_blob_v1(
+      &workspace_id,
+      &parent_dir,
+      "hello world",
+      &mime::TEXT_PLAIN_UTF_8,
+    )
+    .await
+    .unwrap();
+
+  // Uploads are not scanned unless a malware scanner is configured
+  let metadata = c1
+    .get_blob_v1_metadata(&workspace_id, &parent_dir, &resp.file_id)
+    .await
+    .unwrap();
+  assert_eq!(metadata.status, BlobStatus::Ok);
+  assert_eq!(metadata.file_size, 11);
+}
+
 // TODO: fix inconsistent behavior due to different error handling with nginx
 #[tokio::test]
 async fn put_giant_file() {
@@ -116,9 +143,13 @@ async fn put_and_delete_workspace() {
     c1.put_blob(&url, blob_to_put, &mime).await.unwrap();
   }
 
+  // blobs are stored in the content-addressed object of the workspace
+  let obj_key = blob_content_object_key(
+    &Uuid::parse_str(&workspace_id).unwrap(),
+    &format!("{:x}", Sha256::digest(blob_to_put)),
+  );
   {
     // blob exists in the bucket
-    let obj_key = format!("{}/{}", workspace_id, file_id);
     let raw_data = test_bucket.get_blob(&obj_key).await.unwrap().to_blob();
     assert_eq!(blob_to_put, String::from_utf8_lossy(&raw_data));
   }
@@ -128,12 +159,39 @@ async fn put_and_delete_workspace() {
 
   {
     // blob does not exist in the bucket
-    let obj_key = format!("{}/{}", workspace_id, file_id);
     let err = test_bucket.get_blob(&obj_key).await.unwrap_err();
     assert!(err.is_record_not_found());
   }
 }
 
+#[tokio::test]
+async fn put_same_content_is_stored_once() {
+  let test_bucket = TestBucket::new().await;
+  let (c1, _user1) = generate_unique_registered_user_client().await;
+  let workspace_id = workspace_id_from_client(&c1).await;
+  let mime = mime::TEXT_PLAIN_UTF_8;
+  let data = generate_random_string(1024);
+  let url_1 = c1.get_blob_url(&workspace_id, &Uuid::new_v4().to_string());
+  let url_2 = c1.get_blob_url(&workspace_id, &Uuid::new_v4().to_string());
+  c1.put_blob(&url_1, data.clone(), &mime).await.unwrap();
+  c1.put_blob(&url_2, data.clone(), &mime).await.unwrap();
+  let obj_key = blob_content_object_key(
+    &Uuid::parse_str(&workspace_id).unwrap(),
+    &format!("{:x}", Sha256::digest(&data)),
+  );
+
+  // deleting one reference doesn't delete the shared content
+  c1.delete_blob(&url_1).await.unwrap();
+  let (_, got_data) = c1.get_blob(&url_2).await.unwrap();
+  assert_eq!(String::from_utf8(got_data).unwrap(), data);
+  assert!(test_bucket.get_blob(&obj_key).await.is_ok());
+
+  // the content is deleted with its last reference
+  c1.delete_blob(&url_2).await.unwrap();
+  let err = test_bucket.get_blob(&obj_key).await.unwrap_err();
+  assert!(err.is_record_not_found());
+}
+
 #[tokio::test]
 async fn simulate_30_put_blob_request_test() {
   let (c1, _user1) = generate_unique_registered_user_client().await;
@@ -0,0 +1,69 @@
+use app_error::ErrorCode;
+use client_api::entity::workspace_dto::UpdateWorkspaceStorageQuotaParams;
+use client_api_test::{admin_user_client, TestClient};
+
+#[tokio::test]
+async fn workspace_storage_quota_test() {
+  let client = TestClient::new_user_without_ws_conn().await;
+  let workspace_id = client.workspace_id().await;
+  let parent_dir = uuid::Uuid::new_v4().to_string();
+  let mime = mime::TEXT_PLAIN_UTF_8;
+
+  // Only the admin can override the quotas of a workspace
+  let params = UpdateWorkspaceStorageQuotaParams {
+    storage_limit: Some(10),
+    single_upload_limit: Some(6),
+  };
+  let err = client
+    .api_client
+    .update_workspace_storage_quota(&workspace_id, &params)
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::NotEnoughPermissions);
+  admin_user_client()
+    .await
+    .update_workspace_storage_quota(&workspace_id, &params)
+    .await
+    .unwrap();
+
+  let err = client
+    .api_client
+    .put_blob_v1(&workspace_id, &parent_dir, "1234567", &mime)
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::SingleUploadLimitExceeded);
+
+  client
+    .api_client
+    .put_blob_v1(&workspace_id, &parent_dir, "123456", &mime)
+    .await
+    .unwrap();
+  let quota = client
+    .api_client
+    .get_workspace_storage_quota(&workspace_id)
+    .await
+    .unwrap();
+  assert_eq!(quota.consumed_capacity, 6);
+  assert_eq!(quota.storage_limit, Some(10));
+  assert_eq!(quota.remaining_capacity, Some(4));
+
+  let err = client
+    .api_client
+    .put_blob_v1(&workspace_id, &parent_dir, "abcde", &mime)
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::FileStorageLimitExceeded);
+  assert!(err.message.contains("4 bytes"));
+
+  // Restoring the defaults of the server lifts the limits
+  admin_user_client()
+    .await
+    .update_workspace_storage_quota(&workspace_id, &UpdateWorkspaceStorageQuotaParams::default())
+    .await
+    .unwrap();
+  client
+    .api_client
+    .put_blob_v1(&workspace_id, &parent_dir, "abcde", &mime)
+    .await
+    .unwrap();
+}
@@ -0,0 +1,65 @@
+use client_api_test::{generate_unique_registered_user_client, workspace_id_from_client};
+use reqwest::header::{ACCEPT_RANGES, CONTENT_RANGE, RANGE};
+use reqwest::StatusCode;
+
+#[tokio::test]
+async fn get_blob_range_test() {
+  let (c1, _user1) = generate_unique_registered_user_client().await;
+  let workspace_id = workspace_id_from_client(&c1).await;
+  let parent_dir = uuid::Uuid::new_v4().to_string();
+  let data = (0..1024).map(|i| (i % 256) as u8).collect::<Vec<u8>>();
+  let resp = c1
+    .put_blob_v1(
+      &workspace_id,
+      &parent_dir,
+      data.clone(),
+      &mime::APPLICATION_OCTET_STREAM,
+    )
+    .await
+    .unwrap();
+  let url = c1.get_blob_url_v1(&workspace_id, &parent_dir, &resp.file_id);
+  let http_client = reqwest::Client::new();
+
+  let resp = http_client.get(&url).send().await.unwrap();
+  assert_eq!(resp.status(), StatusCode::OK);
+  assert_eq!(resp.headers().get(ACCEPT_RANGES).unwrap(), "bytes");
+  assert_eq!(resp.bytes().await.unwrap().to_vec(), data);
+
+  let resp = http_client
+    .get(&url)
+    .header(RANGE, "bytes=100-199")
+    .send()
+    .await
+    .unwrap();
+  assert_eq!(resp.status(), StatusCode::PARTIAL_CONTENT);
+  assert_eq!(
+    resp.headers().get(CONTENT_RANGE).unwrap(),
+    "bytes 100-199/1024"
+  );
+  assert_eq!(
+    resp.bytes().await.unwrap().to_vec(),
+    data[100..200].to_vec()
+  );
+
+  let resp = http_client
+    .get(&url)
+    .header(RANGE, "bytes=-24")
+    .send()
+    .await
+    .unwrap();
+  assert_eq!(resp.status(), StatusCode::PARTIAL_CONTENT);
+  assert_eq!(
+    resp.headers().get(CONTENT_RANGE).unwrap(),
+    "bytes 1000-1023/1024"
+  );
+  assert_eq!(resp.bytes().await.unwrap().to_vec(), data[1000..].to_vec());
+
+  let resp = http_client
+    .get(&url)
+    .header(RANGE, "bytes=2048-")
+    .send()
+    .await
+    .unwrap();
+  assert_eq!(resp.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+  assert_eq!(resp.headers().get(CONTENT_RANGE).unwrap(), "bytes */1024");
+}
@@ -0,0 +1,74 @@
+use crate::sql_test::util::{setup_db, test_create_user};
+
+use appflowy_cloud::biz::workspace::audit_log::export_audit_logs_csv;
+use database::audit_log::insert_audit_log;
+use database_entity::dto::{AuditAction, ListAuditLogsQueryParams};
+use futures_util::TryStreamExt;
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+async fn export_csv_lines(
+  pool: &PgPool,
+  workspace_id: Uuid,
+  params: ListAuditLogsQueryParams,
+) -> Vec<String> {
+  let chunks: Vec<_> = export_audit_logs_csv(pool.clone(), workspace_id, params)
+    .unwrap()
+    .try_collect()
+    .await
+    .unwrap();
+  let csv = String::from_utf8(chunks.concat()).unwrap();
+  csv.lines().map(str::to_string).collect()
+}
+
+#[sqlx::test(migrations = false)]
+async fn export_audit_logs_csv_beyond_listing_limit_sql_test(pool: PgPool) {
+  setup_db(&pool).await.unwrap();
+
+  let user_uuid = Uuid::new_v4();
+  let user = test_create_user(&pool, user_uuid, &format!("{}@appflowy.io", user_uuid), "1")
+    .await
+    .unwrap();
+  let workspace_id = Uuid::parse_str(&user.workspace_id).unwrap();
+  // More entries than a single page of the listing
+  let entry_count = 2_005;
+  for i in 0..entry_count {
+    insert_audit_log(
+      &pool,
+      &workspace_id,
+      Some(user.uid),
+      &AuditAction::PublishView,
+      Some(&i.to_string()),
+      &json!({}),
+    )
+    .await
+    .unwrap();
+  }
+
+  let lines = export_csv_lines(&pool, workspace_id, ListAuditLogsQueryParams::default()).await;
+  assert_eq!(lines.len(), entry_count + 1);
+  assert!(lines[0].starts_with("id,created_at,workspace_id"));
+
+  let lines = export_csv_lines(
+    &pool,
+    workspace_id,
+    ListAuditLogsQueryParams {
+      offset: Some(1_000),
+      limit: Some(1_002),
+      ..Default::default()
+    },
+  )
+  .await;
+  assert_eq!(lines.len(), 1_002 + 1);
+
+  assert!(export_audit_logs_csv(
+    pool.clone(),
+    workspace_id,
+    ListAuditLogsQueryParams {
+      limit: Some(-1),
+      ..Default::default()
+    },
+  )
+  .is_err());
+}
@@ -0,0 +1,69 @@
+use crate::sql_test::util::{setup_db, test_create_user};
+
+use database::collab::{
+  compact_encrypted_collab_updates, insert_encrypted_collab_update, select_encrypted_collab_updates,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+async fn stored_update_count(pool: &PgPool, oid: &str) -> i64 {
+  sqlx::query_scalar("SELECT COUNT(*) FROM af_collab_encrypted_update WHERE oid = $1")
+    .bind(oid)
+    .fetch_one(pool)
+    .await
+    .unwrap()
+}
+
+#[sqlx::test(migrations = false)]
+async fn compact_encrypted_collab_updates_sql_test(pool: PgPool) {
+  setup_db(&pool).await.unwrap();
+
+  let user_uuid = Uuid::new_v4();
+  let user = test_create_user(&pool, user_uuid, &format!("{}@appflowy.io", user_uuid), "1")
+    .await
+    .unwrap();
+  let workspace_id = Uuid::parse_str(&user.workspace_id).unwrap();
+  let oid = Uuid::new_v4().to_string();
+
+  insert_encrypted_collab_update(&pool, &workspace_id, &oid, b"1")
+    .await
+    .unwrap();
+  let up_to_seq = insert_encrypted_collab_update(&pool, &workspace_id, &oid, b"2")
+    .await
+    .unwrap();
+  insert_encrypted_collab_update(&pool, &workspace_id, &oid, b"3")
+    .await
+    .unwrap();
+
+  let mut txn = pool.begin().await.unwrap();
+  compact_encrypted_collab_updates(&mut txn, &workspace_id, &oid, up_to_seq, b"1+2")
+    .await
+    .unwrap();
+  txn.commit().await.unwrap();
+
+  // The replaced updates are no longer sent to the clients, but they are kept
+  let updates = select_encrypted_collab_updates(&pool, &workspace_id, &oid)
+    .await
+    .unwrap();
+  let data: Vec<&[u8]> = updates
+    .iter()
+    .map(|update| update.data.as_slice())
+    .collect();
+  assert_eq!(data, vec![b"3".as_slice(), b"1+2".as_slice()]);
+  assert_eq!(stored_update_count(&pool, &oid).await, 4);
+
+  // They are deleted by the next compaction
+  let up_to_seq = updates.last().unwrap().seq;
+  let mut txn = pool.begin().await.unwrap();
+  compact_encrypted_collab_updates(&mut txn, &workspace_id, &oid, up_to_seq, b"1+2+3")
+    .await
+    .unwrap();
+  txn.commit().await.unwrap();
+
+  let updates = select_encrypted_collab_updates(&pool, &workspace_id, &oid)
+    .await
+    .unwrap();
+  assert_eq!(updates.len(), 1);
+  assert_eq!(updates[0].data, b"1+2+3");
+  assert_eq!(stored_update_count(&pool, &oid).await, 3);
+}
@@ -1,4 +1,8 @@
+mod audit_log_test;
 mod chat_test;
+mod encrypted_update_test;
 mod history_test;
+mod publish_domain_test;
+mod sso_test;
 pub(crate) mod util;
 mod workspace_test;
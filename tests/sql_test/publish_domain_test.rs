@@ -0,0 +1,79 @@
+use crate::sql_test::util::{setup_db, test_create_user};
+
+use app_error::AppError;
+use database::publish::{
+  select_workspace_custom_domain, upsert_workspace_custom_domain,
+  UNVERIFIED_CUSTOM_DOMAIN_EXPIRATION_DAYS,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[sqlx::test(migrations = false)]
+async fn unverified_custom_domain_expiration_sql_test(pool: PgPool) {
+  setup_db(&pool).await.unwrap();
+
+  let user_1 = Uuid::new_v4();
+  let workspace_id_1 = test_create_user(&pool, user_1, &format!("{}@appflowy.io", user_1), "1")
+    .await
+    .unwrap()
+    .workspace_id;
+  let workspace_id_1 = Uuid::parse_str(&workspace_id_1).unwrap();
+  let user_2 = Uuid::new_v4();
+  let workspace_id_2 = test_create_user(&pool, user_2, &format!("{}@appflowy.io", user_2), "2")
+    .await
+    .unwrap()
+    .workspace_id;
+  let workspace_id_2 = Uuid::parse_str(&workspace_id_2).unwrap();
+  let domain = format!("docs-{}.example.com", Uuid::new_v4().simple());
+
+  upsert_workspace_custom_domain(&pool, &workspace_id_1, &domain, "token-1")
+    .await
+    .unwrap();
+
+  // A recent unverified claim can't be taken over
+  let err = upsert_workspace_custom_domain(&pool, &workspace_id_2, &domain, "token-2")
+    .await
+    .unwrap_err();
+  assert!(matches!(err, AppError::RecordAlreadyExists(_)), "{:?}", err);
+
+  // Once expired, the unverified claim is replaced by the claim of the other workspace
+  sqlx::query(
+    r#"
+      UPDATE af_workspace_custom_domain
+      SET created_at = NOW() - make_interval(days => $2::int + 1)
+      WHERE domain = $1
+    "#,
+  )
+  .bind(&domain)
+  .bind(UNVERIFIED_CUSTOM_DOMAIN_EXPIRATION_DAYS as i32)
+  .execute(&pool)
+  .await
+  .unwrap();
+  let custom_domain = upsert_workspace_custom_domain(&pool, &workspace_id_2, &domain, "token-2")
+    .await
+    .unwrap();
+  assert_eq!(custom_domain.workspace_id, workspace_id_2);
+  assert_eq!(custom_domain.verification_token, "token-2");
+  assert!(select_workspace_custom_domain(&pool, &workspace_id_1)
+    .await
+    .unwrap()
+    .is_none());
+
+  // A verified domain is never taken over
+  sqlx::query(
+    r#"
+      UPDATE af_workspace_custom_domain
+      SET verified_at = NOW(), created_at = NOW() - make_interval(days => $2::int + 1)
+      WHERE domain = $1
+    "#,
+  )
+  .bind(&domain)
+  .bind(UNVERIFIED_CUSTOM_DOMAIN_EXPIRATION_DAYS as i32)
+  .execute(&pool)
+  .await
+  .unwrap();
+  let err = upsert_workspace_custom_domain(&pool, &workspace_id_1, &domain, "token-1")
+    .await
+    .unwrap_err();
+  assert!(matches!(err, AppError::RecordAlreadyExists(_)), "{:?}", err);
+}
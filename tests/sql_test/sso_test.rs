@@ -0,0 +1,112 @@
+use crate::sql_test::util::{setup_db, test_create_user};
+
+use authentication::jwt::Authorization;
+use authentication::sso::{check_workspace_sso, sso_restricted_workspaces};
+use database::workspace::{upsert_workspace_member_with_txn, upsert_workspace_settings};
+use database_entity::dto::{AFRole, AFWorkspaceSettings};
+use gotrue_entity::gotrue_jwt::{Amr, GoTrueJWTClaims};
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[sqlx::test(migrations = false)]
+async fn workspace_sso_enforcement_sql_test(pool: PgPool) {
+  setup_db(&pool).await.unwrap();
+
+  let owner_uuid = Uuid::new_v4();
+  let owner_email = format!("{}@appflowy.io", owner_uuid);
+  let owner = test_create_user(&pool, owner_uuid, &owner_email, "owner")
+    .await
+    .unwrap();
+  let member_uuid = Uuid::new_v4();
+  let member_email = format!("{}@appflowy.io", member_uuid);
+  let member = test_create_user(&pool, member_uuid, &member_email, "member")
+    .await
+    .unwrap();
+  let workspace_id = Uuid::parse_str(&owner.workspace_id).unwrap();
+  let member_workspace_id = Uuid::parse_str(&member.workspace_id).unwrap();
+
+  let provider_id = Uuid::new_v4().to_string();
+  let settings: AFWorkspaceSettings = serde_json::from_value(json!({
+    "sso": {
+      "provider_id": provider_id,
+      "enforce_sso": true,
+      "default_role": AFRole::Member,
+    }
+  }))
+  .unwrap();
+  let mut txn = pool.begin().await.unwrap();
+  upsert_workspace_member_with_txn(&mut txn, &workspace_id, &member_email, AFRole::Member)
+    .await
+    .unwrap();
+  upsert_workspace_settings(&mut txn, &workspace_id, &settings)
+    .await
+    .unwrap();
+  txn.commit().await.unwrap();
+
+  // The owner is exempt from the SSO enforcement of their workspace
+  let owner_auth = authorization(owner_uuid, &owner_email, None);
+  check_workspace_sso(&pool, &owner_auth, &workspace_id)
+    .await
+    .unwrap();
+
+  // Members must sign in with the SSO provider of the workspace
+  let member_auth = authorization(member_uuid, &member_email, None);
+  assert_eq!(
+    sso_restricted_workspaces(&pool, &member_auth)
+      .await
+      .unwrap(),
+    vec![workspace_id]
+  );
+  assert!(check_workspace_sso(&pool, &member_auth, &workspace_id)
+    .await
+    .is_err());
+  let other_provider_auth = authorization(member_uuid, &member_email, Some("other-provider"));
+  assert!(
+    check_workspace_sso(&pool, &other_provider_auth, &workspace_id)
+      .await
+      .is_err()
+  );
+  let sso_auth = authorization(member_uuid, &member_email, Some(&provider_id));
+  check_workspace_sso(&pool, &sso_auth, &workspace_id)
+    .await
+    .unwrap();
+
+  // The member keeps access to their other workspaces whatever they signed in with
+  for auth in [&member_auth, &other_provider_auth, &sso_auth] {
+    check_workspace_sso(&pool, auth, &member_workspace_id)
+      .await
+      .unwrap();
+  }
+}
+
+fn authorization(user_uuid: Uuid, email: &str, sso_provider_id: Option<&str>) -> Authorization {
+  Authorization {
+    token: String::new(),
+    claims: GoTrueJWTClaims {
+      aud: Some("authenticated".to_string()),
+      exp: None,
+      jti: None,
+      iat: None,
+      iss: None,
+      nbf: None,
+      sub: Some(user_uuid.to_string()),
+      email: email.to_string(),
+      phone: String::new(),
+      app_metadata: json!({}),
+      user_metadata: json!({}),
+      role: "authenticated".to_string(),
+      aal: None,
+      amr: Some(vec![Amr {
+        method: match sso_provider_id {
+          Some(_) => "sso/saml".to_string(),
+          None => "password".to_string(),
+        },
+        timestamp: 0,
+        provider: sso_provider_id.map(str::to_string),
+      }]),
+      session_id: None,
+    },
+    personal_access_token: None,
+  }
+}
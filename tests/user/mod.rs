@@ -1,4 +1,5 @@
 mod delete;
+mod personal_access_token;
 mod refresh;
 mod sign_in;
 mod sign_out;
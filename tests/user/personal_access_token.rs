@@ -0,0 +1,129 @@
+use app_error::ErrorCode;
+use client_api::entity::{AFWorkspaceSettingsChange, CreatePersonalAccessTokenParams};
+use client_api::{Client, ClientConfiguration};
+use client_api_test::*;
+use uuid::Uuid;
+
+fn personal_access_token_client(token: String) -> Client {
+  Client::new(
+    &LOCALHOST_URL,
+    &LOCALHOST_WS,
+    &LOCALHOST_GOTRUE,
+    &Uuid::new_v4().to_string(),
+    ClientConfiguration::default().with_personal_access_token(token),
+    "0.7.0",
+  )
+}
+
+#[tokio::test]
+async fn personal_access_token_crud() {
+  let (c, user) = generate_unique_registered_user_client().await;
+  let created = c
+    .create_personal_access_token(&CreatePersonalAccessTokenParams {
+      name: "ci".to_string(),
+      read_only: false,
+      workspace_id: None,
+      expires_in_days: Some(7),
+    })
+    .await
+    .unwrap();
+  // Personal access tokens are opaque and can't be decoded like a session token
+  assert!(created.token.starts_with("afpat_"));
+
+  let pat_client = personal_access_token_client(created.token.clone());
+  let profile = pat_client.get_profile().await.unwrap();
+  assert_eq!(profile.email.unwrap(), user.email);
+
+  // A personal access token can't be used to manage personal access tokens
+  let err = pat_client.list_personal_access_tokens().await.unwrap_err();
+  assert_eq!(err.code, ErrorCode::NotEnoughPermissions);
+
+  let tokens = c.list_personal_access_tokens().await.unwrap();
+  assert_eq!(tokens.len(), 1);
+  assert_eq!(tokens[0].token_id, created.personal_access_token.token_id);
+  assert!(tokens[0].revoked_at.is_none());
+
+  c.revoke_personal_access_token(&created.personal_access_token.token_id)
+    .await
+    .unwrap();
+  pat_client.get_profile().await.unwrap_err();
+
+  let tokens = c.list_personal_access_tokens().await.unwrap();
+  assert!(tokens[0].revoked_at.is_some());
+}
+
+#[tokio::test]
+async fn read_only_personal_access_token() {
+  let (c, _user) = generate_unique_registered_user_client().await;
+  let workspace_id = workspace_id_from_client(&c).await;
+  let created = c
+    .create_personal_access_token(&CreatePersonalAccessTokenParams {
+      name: "read only".to_string(),
+      read_only: true,
+      workspace_id: None,
+      expires_in_days: None,
+    })
+    .await
+    .unwrap();
+
+  let pat_client = personal_access_token_client(created.token);
+  pat_client
+    .get_workspace_settings(&workspace_id)
+    .await
+    .unwrap();
+  pat_client
+    .update_workspace_settings(
+      &workspace_id,
+      &AFWorkspaceSettingsChange::new().disable_search_indexing(true),
+    )
+    .await
+    .unwrap_err();
+}
+
+#[tokio::test]
+async fn workspace_restricted_personal_access_token() {
+  let (c, _user) = generate_unique_registered_user_client().await;
+  let workspace_id = workspace_id_from_client(&c).await;
+  let created = c
+    .create_personal_access_token(&CreatePersonalAccessTokenParams {
+      name: "workspace".to_string(),
+      read_only: false,
+      workspace_id: Some(Uuid::parse_str(&workspace_id).unwrap()),
+      expires_in_days: None,
+    })
+    .await
+    .unwrap();
+
+  let pat_client = personal_access_token_client(created.token);
+  pat_client
+    .get_workspace_settings(&workspace_id)
+    .await
+    .unwrap();
+
+  let (other_client, _other) = generate_unique_registered_user_client().await;
+  let other_workspace_id = workspace_id_from_client(&other_client).await;
+  pat_client
+    .get_workspace_settings(&other_workspace_id)
+    .await
+    .unwrap_err();
+
+  // Routes that don't target the workspace of the token are rejected, unless they are explicitly
+  // allowed
+  pat_client.get_profile().await.unwrap();
+  pat_client.get_workspaces().await.unwrap_err();
+}
+
+#[tokio::test]
+async fn create_personal_access_token_with_invalid_expiry() {
+  let (c, _user) = generate_unique_registered_user_client().await;
+  let err = c
+    .create_personal_access_token(&CreatePersonalAccessTokenParams {
+      name: "forever".to_string(),
+      read_only: false,
+      workspace_id: None,
+      expires_in_days: Some(10_000),
+    })
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::InvalidRequest);
+}
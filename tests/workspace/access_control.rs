@@ -0,0 +1,56 @@
+use app_error::ErrorCode;
+use client_api_test::{admin_user_client, TestClient};
+use database_entity::dto::{
+  AFAccessLevel, AFRole, ExplainPolicyDecisionQueryParams, PolicyObjectType,
+};
+
+#[tokio::test]
+async fn explain_policy_decision_test() {
+  let owner = TestClient::new_user_without_ws_conn().await;
+  let guest = TestClient::new_user_without_ws_conn().await;
+  let workspace_id = owner.workspace_id().await;
+  owner
+    .invite_and_accepted_workspace_member(&workspace_id, &guest, AFRole::Guest)
+    .await
+    .unwrap();
+  let guest_uid = guest.uid().await;
+
+  let params = |action: &str| ExplainPolicyDecisionQueryParams {
+    uid: guest_uid,
+    object_type: PolicyObjectType::Workspace,
+    object_id: workspace_id.clone(),
+    action: action.to_string(),
+  };
+
+  // Only the admin is allowed to explain policy decisions
+  let err = owner
+    .api_client
+    .explain_policy_decision(&params("read"))
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::NotEnoughPermissions);
+
+  let admin_client = admin_user_client().await;
+  let explanation = admin_client
+    .explain_policy_decision(&params("read"))
+    .await
+    .unwrap();
+  assert!(explanation.allowed);
+  assert_eq!(explanation.role, Some(AFRole::Guest));
+  assert_eq!(explanation.access_level, Some(AFAccessLevel::ReadOnly));
+  assert!(!explanation.matching_policies.is_empty());
+
+  let explanation = admin_client
+    .explain_policy_decision(&params("write"))
+    .await
+    .unwrap();
+  assert!(!explanation.allowed);
+  assert!(explanation.matching_policies.is_empty());
+  assert!(explanation.denial_reason.is_some());
+
+  let err = admin_client
+    .explain_policy_decision(&params("publish"))
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::InvalidRequest);
+}
@@ -1,6 +1,8 @@
 use app_error::ErrorCode;
-use client_api::entity::CreateAccessRequestParams;
-use client_api_test::generate_unique_registered_user_client;
+use client_api::entity::{
+  AFRole, AccessRequestAutoApprovalRule, AccessRequestStatus, CreateAccessRequestParams,
+};
+use client_api_test::{generate_unique_registered_user_client, TestClient};
 use shared_entity::dto::workspace_dto::ViewLayout;
 use uuid::Uuid;
 
@@ -79,3 +81,130 @@ async fn access_request_test() {
     .unwrap();
   assert!(workspace_members.iter().any(|m| m.email == requester.email));
 }
+
+#[tokio::test]
+async fn access_request_auto_approval_test() {
+  let (owner_client, _) = generate_unique_registered_user_client().await;
+  let workspace_id = owner_client.get_workspaces().await.unwrap()[0].workspace_id;
+  let folder_view = owner_client
+    .get_workspace_folder(&workspace_id.to_string(), Some(2), None)
+    .await
+    .unwrap();
+  let view_id = folder_view
+    .children
+    .into_iter()
+    .find(|v| v.name == "General")
+    .unwrap()
+    .view_id;
+  let view_id = Uuid::parse_str(&view_id).unwrap();
+
+  let rule = AccessRequestAutoApprovalRule {
+    email_domain: "appflowy.io".to_string(),
+    role: AFRole::Guest,
+  };
+  let (requester_client, requester) = generate_unique_registered_user_client().await;
+  // Only the workspace owner is allowed to manage auto approval rules
+  let resp = requester_client
+    .upsert_access_request_auto_approval_rule(workspace_id, &rule)
+    .await;
+  assert_eq!(resp.unwrap_err().code, ErrorCode::NotEnoughPermissions);
+  owner_client
+    .upsert_access_request_auto_approval_rule(workspace_id, &rule)
+    .await
+    .unwrap();
+  let rules = owner_client
+    .list_access_request_auto_approval_rules(workspace_id)
+    .await
+    .unwrap();
+  assert_eq!(rules, vec![rule]);
+
+  let access_request = requester_client
+    .create_access_request(CreateAccessRequestParams {
+      workspace_id,
+      view_id,
+    })
+    .await
+    .unwrap();
+  let access_request = owner_client
+    .get_access_request(access_request.request_id)
+    .await
+    .unwrap();
+  assert_eq!(access_request.status, AccessRequestStatus::Approved);
+  let member = owner_client
+    .get_workspace_members(workspace_id.to_string())
+    .await
+    .unwrap()
+    .into_iter()
+    .find(|m| m.email == requester.email)
+    .unwrap();
+  assert_eq!(member.role, AFRole::Guest);
+
+  owner_client
+    .delete_access_request_auto_approval_rule(workspace_id, "appflowy.io")
+    .await
+    .unwrap();
+  let rules = owner_client
+    .list_access_request_auto_approval_rules(workspace_id)
+    .await
+    .unwrap();
+  assert!(rules.is_empty());
+}
+
+#[tokio::test]
+async fn approve_access_request_of_existing_member_test() {
+  let owner = TestClient::new_user_without_ws_conn().await;
+  let member = TestClient::new_user_without_ws_conn().await;
+  let workspace_id = owner.workspace_id().await;
+  let workspace_uuid = Uuid::parse_str(&workspace_id).unwrap();
+  owner
+    .invite_and_accepted_workspace_member(&workspace_id, &member, AFRole::Member)
+    .await
+    .unwrap();
+  let view_id = owner
+    .api_client
+    .get_workspace_folder(&workspace_id, Some(1), None)
+    .await
+    .unwrap()
+    .children[0]
+    .view_id
+    .clone();
+
+  // The auto approval rule would grant the guest role, but the existing member keeps its role
+  owner
+    .api_client
+    .upsert_access_request_auto_approval_rule(
+      workspace_uuid,
+      &AccessRequestAutoApprovalRule {
+        email_domain: "appflowy.io".to_string(),
+        role: AFRole::Guest,
+      },
+    )
+    .await
+    .unwrap();
+  let access_request = member
+    .api_client
+    .create_access_request(CreateAccessRequestParams {
+      workspace_id: workspace_uuid,
+      view_id: Uuid::parse_str(&view_id).unwrap(),
+    })
+    .await
+    .unwrap();
+  let access_request = owner
+    .api_client
+    .get_access_request(access_request.request_id)
+    .await
+    .unwrap();
+  assert_eq!(access_request.status, AccessRequestStatus::Approved);
+
+  let member_email = member.email().await;
+  let role = owner
+    .api_client
+    .get_workspace_members(&workspace_id)
+    .await
+    .unwrap()
+    .into_iter()
+    .find(|m| m.email == member_email)
+    .unwrap()
+    .role;
+  assert_eq!(role, AFRole::Member);
+}
@@ -0,0 +1,101 @@
+use app_error::ErrorCode;
+use client_api_test::TestClient;
+use database_entity::dto::{AFRole, AuditAction, ListAuditLogsQueryParams};
+use uuid::Uuid;
+
+#[tokio::test]
+async fn audit_log_records_member_changes_test() {
+  let owner = TestClient::new_user_without_ws_conn().await;
+  let member = TestClient::new_user_without_ws_conn().await;
+  let workspace_id = owner.workspace_id().await;
+  let workspace_uuid = Uuid::parse_str(&workspace_id).unwrap();
+  let owner_email = owner.email().await;
+  let member_email = member.email().await;
+
+  owner
+    .invite_and_accepted_workspace_member(&workspace_id, &member, AFRole::Member)
+    .await
+    .unwrap();
+  owner
+    .try_update_workspace_member(&workspace_id, &member, AFRole::Guest)
+    .await
+    .unwrap();
+
+  // Only the owner is allowed to read the audit log
+  let err = member
+    .api_client
+    .list_audit_logs(workspace_uuid, &ListAuditLogsQueryParams::default())
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::NotEnoughPermissions);
+
+  owner
+    .try_remove_workspace_member(&workspace_id, &member)
+    .await
+    .unwrap();
+
+  let audit_logs = owner
+    .api_client
+    .list_audit_logs(workspace_uuid, &ListAuditLogsQueryParams::default())
+    .await
+    .unwrap();
+  let actions: Vec<AuditAction> = audit_logs
+    .audit_logs
+    .iter()
+    .map(|log| log.action.clone())
+    .collect();
+  assert_eq!(
+    actions,
+    vec![
+      AuditAction::RemoveMember,
+      AuditAction::UpdateMemberRole,
+      AuditAction::InviteMember
+    ]
+  );
+  assert!(audit_logs
+    .audit_logs
+    .iter()
+    .all(
+      |log| log.object_id.as_deref() == Some(member_email.as_str())
+        && log.actor_email.as_deref() == Some(owner_email.as_str())
+    ));
+
+  // Filtering and pagination
+  let audit_logs = owner
+    .api_client
+    .list_audit_logs(
+      workspace_uuid,
+      &ListAuditLogsQueryParams {
+        action: Some(AuditAction::InviteMember),
+        ..Default::default()
+      },
+    )
+    .await
+    .unwrap();
+  assert_eq!(audit_logs.audit_logs.len(), 1);
+  assert!(!audit_logs.has_more);
+
+  let audit_logs = owner
+    .api_client
+    .list_audit_logs(
+      workspace_uuid,
+      &ListAuditLogsQueryParams {
+        limit: Some(2),
+        ..Default::default()
+      },
+    )
+    .await
+    .unwrap();
+  assert_eq!(audit_logs.audit_logs.len(), 2);
+  assert!(audit_logs.has_more);
+
+  let csv = owner
+    .api_client
+    .export_audit_logs_csv(workspace_uuid, &ListAuditLogsQueryParams::default())
+    .await
+    .unwrap();
+  let lines: Vec<&str> = csv.lines().collect();
+  assert_eq!(lines.len(), 4);
+  assert!(lines[0].starts_with("id,created_at,workspace_id"));
+  assert!(lines[1].contains("remove_member"));
+}
@@ -0,0 +1,57 @@
+use app_error::ErrorCode;
+use client_api_test::TestClient;
+
+#[tokio::test]
+async fn create_export_task_test() {
+  let client = TestClient::new_user_without_ws_conn().await;
+  let workspace_id = client.workspace_id().await;
+  let task_id = client
+    .api_client
+    .create_export(&workspace_id)
+    .await
+    .unwrap()
+    .task_id;
+
+  let tasks = client.api_client.get_export_list().await.unwrap().tasks;
+  assert_eq!(tasks.len(), 1);
+  assert_eq!(tasks[0].task_id, task_id);
+  assert_eq!(tasks[0].workspace_id, workspace_id);
+
+  let detail = client.api_client.get_export_detail(&task_id).await.unwrap();
+  assert_eq!(detail.task_id, task_id);
+
+  // Only one export can be in progress by default
+  let err = client
+    .api_client
+    .create_export(&workspace_id)
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::TooManyExportTask);
+}
+
+#[tokio::test]
+async fn export_task_of_other_user_test() {
+  let owner = TestClient::new_user_without_ws_conn().await;
+  let workspace_id = owner.workspace_id().await;
+  let task_id = owner
+    .api_client
+    .create_export(&workspace_id)
+    .await
+    .unwrap()
+    .task_id;
+
+  let other = TestClient::new_user_without_ws_conn().await;
+  let err = other
+    .api_client
+    .get_export_detail(&task_id)
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::RecordNotFound);
+
+  let err = other
+    .api_client
+    .create_export(&workspace_id)
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::NotEnoughPermissions);
+}
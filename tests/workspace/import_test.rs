@@ -2,6 +2,7 @@ use anyhow::Error;
 use client_api_test::TestClient;
 use collab_document::importer::define::{BlockType, URL_FIELD};
 use collab_folder::ViewLayout;
+use database_entity::dto::{ImportPhase, ImportSource};
 
 use std::path::PathBuf;
 use std::time::Duration;
@@ -264,3 +265,118 @@ async fn wait_until_num_import_task_complete(client: &TestClient, num: usize) {
     "The import task was not completed within the expected time."
   );
 }
+
+#[tokio::test]
+async fn import_csv_into_grid_test() {
+  let client = TestClient::new_user().await;
+  let workspace_id = client.workspace_id().await;
+  let folder = client.get_folder(&workspace_id).await;
+  let space_view = folder.get_views_belong_to(&workspace_id).pop().unwrap();
+
+  let file_path = PathBuf::from("tests/workspace/asset/products.csv");
+  let url = client
+    .api_client
+    .create_database_import(&workspace_id, &space_view.id, &file_path)
+    .await
+    .unwrap()
+    .presigned_url;
+  client
+    .api_client
+    .upload_import_file(&file_path, &url)
+    .await
+    .unwrap();
+  wait_until_num_import_task_complete(&client, 1).await;
+
+  // the grid is created in the existing workspace, under the chosen space
+  let workspaces = client.api_client.get_workspaces().await.unwrap();
+  assert_eq!(workspaces.len(), 1);
+  let folder = client.get_folder(&workspace_id).await;
+  let grid_view = folder
+    .get_views_belong_to(&space_view.id)
+    .into_iter()
+    .find(|view| view.name == "products")
+    .expect("Failed to find imported grid");
+  assert_eq!(grid_view.layout, ViewLayout::Grid);
+
+  // the extra cell of the Banana row is reported
+  let tasks = client.api_client.get_import_list().await.unwrap().tasks;
+  assert_eq!(tasks[0].row_errors.len(), 1);
+  assert_eq!(tasks[0].row_errors[0].row, 4);
+}
+
+#[tokio::test]
+async fn import_notion_zip_into_existing_workspace_test() {
+  let client = TestClient::new_user().await;
+  let workspace_id = client.workspace_id().await;
+  let folder = client.get_folder(&workspace_id).await;
+  let space_view = folder.get_views_belong_to(&workspace_id).pop().unwrap();
+  let num_of_spaces = folder.get_views_belong_to(&workspace_id).len();
+
+  let file_path = PathBuf::from("tests/workspace/asset/project&task.zip");
+  let url = client
+    .api_client
+    .create_workspace_import(
+      &workspace_id,
+      &space_view.id,
+      &file_path,
+      ImportSource::Notion,
+    )
+    .await
+    .unwrap()
+    .presigned_url;
+  client
+    .api_client
+    .upload_import_file(&file_path, &url)
+    .await
+    .unwrap();
+  wait_until_num_import_task_complete(&client, 1).await;
+
+  // the pages are imported into the existing workspace, under a page of the chosen space
+  let workspaces = client.api_client.get_workspaces().await.unwrap();
+  assert_eq!(workspaces.len(), 1);
+  let folder = client.get_folder(&workspace_id).await;
+  assert_eq!(
+    folder.get_views_belong_to(&workspace_id).len(),
+    num_of_spaces
+  );
+  let imported_view = folder
+    .get_views_belong_to(&space_view.id)
+    .into_iter()
+    .find(|view| view.name == "project&task")
+    .expect("Failed to find imported page");
+  // the space created by the import becomes a regular page
+  assert!(!imported_view
+    .extra
+    .as_deref()
+    .unwrap_or_default()
+    .contains("is_space"));
+  assert!(!folder.get_views_belong_to(&imported_view.id).is_empty());
+}
+
+#[tokio::test]
+async fn cancel_import_before_upload_test() {
+  let client = TestClient::new_user().await;
+  let file_path = PathBuf::from("tests/workspace/asset/blog_post.zip");
+  let task_id = client
+    .api_client
+    .create_import(&file_path)
+    .await
+    .unwrap()
+    .task_id;
+  client.api_client.cancel_import(&task_id).await.unwrap();
+
+  let tasks = client.api_client.get_import_list().await.unwrap().tasks;
+  assert_eq!(tasks.len(), 1);
+  assert_eq!(tasks[0].status, 4);
+  // only pending tasks can be cancelled
+  client.api_client.cancel_import(&task_id).await.unwrap_err();
+}
+
+#[tokio::test]
+async fn import_progress_test() {
+  let (client, _) = import_notion_zip_until_complete("blog_post.zip").await;
+  let tasks = client.api_client.get_import_list().await.unwrap().tasks;
+  let progress = tasks[0].progress.as_ref().unwrap();
+  assert_eq!(progress.phase, ImportPhase::Upload);
+  assert_eq!(progress.processed_count, progress.total_count);
+}
@@ -1,6 +1,9 @@
+mod access_control;
 mod access_request;
+mod audit_log;
 mod default_user_workspace;
 mod edit_workspace;
+mod export_test;
 mod import_test;
 mod invitation_crud;
 mod member_crud;
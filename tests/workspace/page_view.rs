@@ -1,5 +1,7 @@
+use app_error::ErrorCode;
 use std::{collections::HashSet, time::Duration};
 
+use chrono::{Duration as ChronoDuration, Utc};
 use client_api::entity::{QueryCollab, QueryCollabParams};
 use client_api_test::{
   generate_unique_registered_user, generate_unique_registered_user_client, TestClient,
@@ -9,7 +11,8 @@ use collab_entity::CollabType;
 use collab_folder::{CollabOrigin, Folder};
 use serde_json::{json, Value};
 use shared_entity::dto::workspace_dto::{
-  CreatePageParams, CreateSpaceParams, IconType, MovePageParams, PublishPageParams,
+  CreatePageParams, CreatePublishSchedule, CreateSpaceParams, IconType, MovePageParams,
+  PageExportFormat, PublishPageParams, PublishScheduleAction, PublishScheduleStatus,
   SpacePermission, UpdatePageParams, UpdateSpaceParams, ViewIcon, ViewLayout,
 };
 use tokio::time::sleep;
@@ -79,6 +82,73 @@ async fn get_page_view() {
   assert_eq!(resp.data.row_data.len(), 0);
 }
 
+#[tokio::test]
+async fn export_page_view() {
+  let (c, _user) = generate_unique_registered_user_client().await;
+  let workspaces = c.get_workspaces().await.unwrap();
+  let workspace_id = workspaces[0].workspace_id;
+  let folder_view = c
+    .get_workspace_folder(&workspace_id.to_string(), Some(2), None)
+    .await
+    .unwrap();
+  let general_space = &folder_view
+    .children
+    .into_iter()
+    .find(|v| v.name == "General")
+    .unwrap();
+  let getting_started = general_space
+    .children
+    .iter()
+    .find(|v| v.name == "Getting started")
+    .unwrap();
+  let markdown = c
+    .export_workspace_page_view(workspace_id, &getting_started.view_id, None)
+    .await
+    .unwrap();
+  assert!(!markdown.is_empty());
+  let html = c
+    .export_workspace_page_view(
+      workspace_id,
+      &getting_started.view_id,
+      Some(PageExportFormat::Html),
+    )
+    .await
+    .unwrap();
+  assert!(html.contains("<title>Getting started</title>"));
+  let json = c
+    .export_workspace_page_view(
+      workspace_id,
+      &getting_started.view_id,
+      Some(PageExportFormat::Json),
+    )
+    .await
+    .unwrap();
+  let json: Value = serde_json::from_str(&json).unwrap();
+  assert_eq!(json["type"], "page");
+
+  let todo = general_space
+    .children
+    .iter()
+    .find(|v| v.name == "To-dos")
+    .unwrap();
+  let csv = c
+    .export_workspace_page_view(workspace_id, &todo.view_id, None)
+    .await
+    .unwrap();
+  // Header and the 5 rows of the template
+  assert_eq!(csv.lines().count(), 6);
+  let json = c
+    .export_workspace_page_view(workspace_id, &todo.view_id, Some(PageExportFormat::Json))
+    .await
+    .unwrap();
+  let json: Value = serde_json::from_str(&json).unwrap();
+  assert_eq!(json["rows"].as_array().unwrap().len(), 5);
+
+  c.export_workspace_page_view(workspace_id, &todo.view_id, Some(PageExportFormat::Html))
+    .await
+    .unwrap_err();
+}
+
 #[tokio::test]
 async fn create_new_page_with_database() {
   let (c, _user) = generate_unique_registered_user_client().await;
@@ -820,6 +890,9 @@ async fn publish_page() {
           visible_database_view_ids: None,
           comments_enabled: None,
           duplicate_enabled: None,
+          auto_republish: None,
+          password: None,
+          members_only: None,
         },
       )
       .await
@@ -861,3 +934,173 @@ async fn publish_page() {
     .unwrap();
   assert_eq!(published_view.children.len(), 0);
 }
+
+#[tokio::test]
+async fn publish_page_history_and_rollback() {
+  let registered_user = generate_unique_registered_user().await;
+  let web_client = TestClient::user_with_new_device(registered_user.clone()).await;
+  let workspace_id = web_client.workspace_id().await;
+  let workspace_uuid = Uuid::parse_str(&workspace_id).unwrap();
+  let folder_view = web_client
+    .api_client
+    .get_workspace_folder(&workspace_id, Some(2), None)
+    .await
+    .unwrap();
+  let view_id = folder_view
+    .children
+    .iter()
+    .find(|v| v.name == "General")
+    .unwrap()
+    .children
+    .iter()
+    .find(|v| v.name == "Getting started")
+    .unwrap()
+    .view_id
+    .clone();
+
+  for comments_enabled in [true, false] {
+    web_client
+      .api_client
+      .publish_page(
+        workspace_uuid,
+        &view_id,
+        &PublishPageParams {
+          publish_name: Some("getting-started".to_string()),
+          comments_enabled: Some(comments_enabled),
+          ..Default::default()
+        },
+      )
+      .await
+      .unwrap();
+  }
+  let history = web_client
+    .api_client
+    .list_publish_history(&workspace_id, &view_id)
+    .await
+    .unwrap();
+  assert_eq!(history.len(), 2);
+  assert!(!history[0].comments_enabled);
+  assert!(history[1].comments_enabled);
+  assert_eq!(history[1].publisher_email, registered_user.email);
+
+  let first_version = &history[1];
+  let detail = web_client
+    .api_client
+    .get_publish_history_detail(&workspace_id, &view_id, &first_version.history_id)
+    .await
+    .unwrap();
+  assert_eq!(detail.metadata["view"]["view_id"], json!(view_id));
+  let blob = web_client
+    .api_client
+    .get_publish_history_blob(&workspace_id, &view_id, &first_version.history_id)
+    .await
+    .unwrap();
+  assert!(!blob.is_empty());
+
+  web_client
+    .api_client
+    .unpublish_page(workspace_uuid, &view_id)
+    .await
+    .unwrap();
+  web_client
+    .api_client
+    .rollback_to_publish_history(&workspace_id, &view_id, &first_version.history_id)
+    .await
+    .unwrap();
+  let publish_namespace = web_client
+    .api_client
+    .get_workspace_publish_namespace(&workspace_id)
+    .await
+    .unwrap();
+  let published_blob = web_client
+    .api_client
+    .get_published_collab_blob(&publish_namespace, "getting-started")
+    .await
+    .unwrap();
+  assert_eq!(published_blob, blob);
+  let history = web_client
+    .api_client
+    .list_publish_history(&workspace_id, &view_id)
+    .await
+    .unwrap();
+  assert_eq!(history.len(), 3);
+  assert!(history[0].comments_enabled);
+}
+
+#[tokio::test]
+async fn create_and_cancel_publish_schedule() {
+  let registered_user = generate_unique_registered_user().await;
+  let web_client = TestClient::user_with_new_device(registered_user.clone()).await;
+  let workspace_id = web_client.workspace_id().await;
+  let folder_view = web_client
+    .api_client
+    .get_workspace_folder(&workspace_id, Some(2), None)
+    .await
+    .unwrap();
+  let view_id = folder_view
+    .children
+    .iter()
+    .find(|v| v.name == "General")
+    .unwrap()
+    .children
+    .iter()
+    .find(|v| v.name == "Getting started")
+    .unwrap()
+    .view_id
+    .clone();
+
+  let err = web_client
+    .api_client
+    .create_publish_schedule(
+      &workspace_id,
+      &view_id,
+      &CreatePublishSchedule {
+        action: PublishScheduleAction::Publish,
+        scheduled_at: Utc::now() - ChronoDuration::hours(1),
+        params: PublishPageParams::default(),
+      },
+    )
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::InvalidRequest);
+
+  let schedule = web_client
+    .api_client
+    .create_publish_schedule(
+      &workspace_id,
+      &view_id,
+      &CreatePublishSchedule {
+        action: PublishScheduleAction::Publish,
+        scheduled_at: Utc::now() + ChronoDuration::days(1),
+        params: PublishPageParams::default(),
+      },
+    )
+    .await
+    .unwrap();
+  assert_eq!(schedule.status, PublishScheduleStatus::Pending);
+  let schedules = web_client
+    .api_client
+    .list_publish_schedules(&workspace_id, &view_id)
+    .await
+    .unwrap();
+  assert_eq!(schedules.len(), 1);
+  assert_eq!(schedules[0].schedule_id, schedule.schedule_id);
+
+  web_client
+    .api_client
+    .cancel_publish_schedule(&workspace_id, &schedule.schedule_id)
+    .await
+    .unwrap();
+  let schedules = web_client
+    .api_client
+    .list_publish_schedules(&workspace_id, &view_id)
+    .await
+    .unwrap();
+  assert_eq!(schedules[0].status, PublishScheduleStatus::Cancelled);
+  let err = web_client
+    .api_client
+    .cancel_publish_schedule(&workspace_id, &schedule.schedule_id)
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::RecordNotFound);
+}
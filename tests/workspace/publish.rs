@@ -2,8 +2,8 @@ use app_error::ErrorCode;
 use appflowy_cloud::biz::collab::folder_view::collab_folder_to_folder_view;
 use appflowy_cloud::biz::collab::utils::collab_from_doc_state;
 use client_api::entity::{
-  AFRole, GlobalComment, PatchPublishedCollab, PublishCollabItem, PublishCollabMetadata,
-  PublishInfoMeta,
+  AFRole, GlobalComment, PatchPublishedCollab, PublishAnalyticsQueryParams, PublishCollabItem,
+  PublishCollabMetadata, PublishInfoMeta,
 };
 use client_api_test::TestClient;
 use client_api_test::{generate_unique_registered_user_client, localhost_client};
@@ -104,6 +104,347 @@ async fn test_set_publish_namespace_set() {
   }
 }
 
+#[tokio::test]
+async fn test_publish_custom_domain() {
+  let (c, _user) = generate_unique_registered_user_client().await;
+  let workspace_id = get_first_workspace_string(&c).await;
+  let domain = format!("docs-{}.example.com", Uuid::new_v4().simple());
+
+  let custom_domain = c
+    .set_workspace_publish_custom_domain(&workspace_id, &domain.to_uppercase())
+    .await
+    .unwrap();
+  assert_eq!(custom_domain.domain, domain);
+  assert_eq!(
+    custom_domain.verification_record_name,
+    format!("_appflowy-verification.{}", domain)
+  );
+  assert!(custom_domain.verified_at.is_none());
+
+  {
+    // setting the same domain again keeps the verification token
+    let same_domain = c
+      .set_workspace_publish_custom_domain(&workspace_id, &domain)
+      .await
+      .unwrap();
+    assert_eq!(
+      same_domain.verification_record_value,
+      custom_domain.verification_record_value
+    );
+  }
+
+  {
+    // another workspace cannot use the same domain
+    let (c2, _user) = generate_unique_registered_user_client().await;
+    let workspace_id_2 = get_first_workspace_string(&c2).await;
+    let err = c2
+      .set_workspace_publish_custom_domain(&workspace_id_2, &domain)
+      .await
+      .unwrap_err();
+    assert_eq!(err.code, ErrorCode::RecordAlreadyExists, "{:?}", err);
+  }
+
+  {
+    // invalid domains are rejected
+    let err = c
+      .set_workspace_publish_custom_domain(&workspace_id, "not a domain")
+      .await
+      .unwrap_err();
+    assert_eq!(err.code, ErrorCode::InvalidRequest, "{:?}", err);
+  }
+
+  // the verification record does not exist
+  c.verify_workspace_publish_custom_domain(&workspace_id)
+    .await
+    .unwrap_err();
+  let custom_domain = c
+    .get_workspace_publish_custom_domain(&workspace_id)
+    .await
+    .unwrap();
+  assert!(custom_domain.verified_at.is_none());
+
+  c.delete_workspace_publish_custom_domain(&workspace_id)
+    .await
+    .unwrap();
+  let err = c
+    .get_workspace_publish_custom_domain(&workspace_id)
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::RecordNotFound, "{:?}", err);
+}
+
+#[tokio::test]
+async fn test_publish_access_restrictions() {
+  let client_1 = TestClient::new_user_without_ws_conn().await;
+  let workspace_id = client_1.workspace_id().await;
+  let client_2 = TestClient::new_user_without_ws_conn().await;
+  client_1
+    .invite_and_accepted_workspace_member(&workspace_id, &client_2, AFRole::Member)
+    .await
+    .unwrap();
+  let namespace = Uuid::new_v4().to_string();
+  client_1
+    .api_client
+    .set_workspace_publish_namespace(&workspace_id, namespace.clone())
+    .await
+    .unwrap();
+
+  let view_id = Uuid::new_v4();
+  let publish_name = "protected-page";
+  client_1
+    .api_client
+    .publish_collabs::<MyCustomMetadata, &[u8]>(
+      &workspace_id,
+      vec![PublishCollabItem {
+        meta: PublishCollabMetadata {
+          view_id,
+          publish_name: publish_name.to_string(),
+          metadata: MyCustomMetadata {
+            title: "my_title_1".to_string(),
+          },
+        },
+        data: "yrs_encoded_data_1".as_bytes(),
+        comments_enabled: true,
+        duplicate_enabled: true,
+      }],
+    )
+    .await
+    .unwrap();
+  client_1
+    .api_client
+    .patch_published_collabs(
+      &workspace_id,
+      &[PatchPublishedCollab {
+        view_id,
+        publish_name: None,
+        comments_enabled: None,
+        duplicate_enabled: None,
+        password: Some("secret".to_string()),
+        members_only: None,
+      }],
+    )
+    .await
+    .unwrap();
+  let info = client_1
+    .api_client
+    .get_published_collab_info(&view_id)
+    .await
+    .unwrap();
+  assert!(info.password_protected);
+  assert!(!info.members_only);
+
+  {
+    // guests need the password
+    let guest_client = localhost_client();
+    let err = guest_client
+      .get_published_collab::<MyCustomMetadata>(&namespace, publish_name)
+      .await
+      .unwrap_err();
+    assert_eq!(err.code, ErrorCode::InvalidPassword, "{:?}", err);
+    let err = guest_client
+      .get_protected_published_collab_blob(&namespace, publish_name, Some("wrong"))
+      .await
+      .unwrap_err();
+    assert_eq!(err.code, ErrorCode::InvalidPassword, "{:?}", err);
+    let metadata = guest_client
+      .get_protected_published_collab::<MyCustomMetadata>(&namespace, publish_name, Some("secret"))
+      .await
+      .unwrap();
+    assert_eq!(metadata.title, "my_title_1");
+    let blob = guest_client
+      .get_protected_published_collab_blob(&namespace, publish_name, Some("secret"))
+      .await
+      .unwrap();
+    assert_eq!(blob.as_ref(), "yrs_encoded_data_1".as_bytes());
+
+    // guests are refused after too many wrong passwords
+    for _ in 0..4 {
+      let err = guest_client
+        .get_protected_published_collab_blob(&namespace, publish_name, Some("wrong"))
+        .await
+        .unwrap_err();
+      assert_eq!(err.code, ErrorCode::InvalidPassword, "{:?}", err);
+    }
+    let err = guest_client
+      .get_protected_published_collab_blob(&namespace, publish_name, Some("wrong"))
+      .await
+      .unwrap_err();
+    assert_eq!(err.code, ErrorCode::TooManyPasswordAttempts, "{:?}", err);
+  }
+
+  {
+    // members of the workspace don't need the password
+    let blob = client_2
+      .api_client
+      .get_published_collab_blob(&namespace, publish_name)
+      .await
+      .unwrap();
+    assert_eq!(blob.as_ref(), "yrs_encoded_data_1".as_bytes());
+  }
+
+  // republishing the view keeps its access restrictions
+  client_1
+    .api_client
+    .publish_collabs::<MyCustomMetadata, &[u8]>(
+      &workspace_id,
+      vec![PublishCollabItem {
+        meta: PublishCollabMetadata {
+          view_id,
+          publish_name: publish_name.to_string(),
+          metadata: MyCustomMetadata {
+            title: "my_title_2".to_string(),
+          },
+        },
+        data: "yrs_encoded_data_2".as_bytes(),
+        comments_enabled: true,
+        duplicate_enabled: true,
+      }],
+    )
+    .await
+    .unwrap();
+  client_1
+    .api_client
+    .patch_published_collabs(
+      &workspace_id,
+      &[PatchPublishedCollab {
+        view_id,
+        publish_name: None,
+        comments_enabled: None,
+        duplicate_enabled: None,
+        password: Some("".to_string()),
+        members_only: Some(true),
+      }],
+    )
+    .await
+    .unwrap();
+  let info = client_1
+    .api_client
+    .get_published_collab_info(&view_id)
+    .await
+    .unwrap();
+  assert!(!info.password_protected);
+  assert!(info.members_only);
+
+  {
+    // only members can view the page
+    let guest_client = localhost_client();
+    let err = guest_client
+      .get_published_collab_blob(&namespace, publish_name)
+      .await
+      .unwrap_err();
+    assert_eq!(err.code, ErrorCode::UserUnAuthorized, "{:?}", err);
+    let (other_client, _user) = generate_unique_registered_user_client().await;
+    let err = other_client
+      .get_published_collab::<MyCustomMetadata>(&namespace, publish_name)
+      .await
+      .unwrap_err();
+    assert_eq!(err.code, ErrorCode::NotEnoughPermissions, "{:?}", err);
+    let metadata = client_2
+      .api_client
+      .get_published_collab::<MyCustomMetadata>(&namespace, publish_name)
+      .await
+      .unwrap();
+    assert_eq!(metadata.title, "my_title_2");
+
+    // the comments and reactions of the page are restricted as well
+    let err = guest_client
+      .get_published_view_comments(&view_id)
+      .await
+      .unwrap_err();
+    assert_eq!(err.code, ErrorCode::UserUnAuthorized, "{:?}", err);
+    let err = other_client
+      .create_comment_on_published_view(&view_id, "hello", &None)
+      .await
+      .unwrap_err();
+    assert_eq!(err.code, ErrorCode::NotEnoughPermissions, "{:?}", err);
+    let err = guest_client
+      .get_published_view_reactions(&view_id, &None)
+      .await
+      .unwrap_err();
+    assert_eq!(err.code, ErrorCode::UserUnAuthorized, "{:?}", err);
+    client_2
+      .api_client
+      .get_published_view_comments(&view_id)
+      .await
+      .unwrap();
+  }
+}
+
+#[tokio::test]
+async fn test_publish_analytics() {
+  let client_1 = TestClient::new_user_without_ws_conn().await;
+  let workspace_id = client_1.workspace_id().await;
+  let client_2 = TestClient::new_user_without_ws_conn().await;
+  client_1
+    .invite_and_accepted_workspace_member(&workspace_id, &client_2, AFRole::Member)
+    .await
+    .unwrap();
+  let namespace = Uuid::new_v4().to_string();
+  client_1
+    .api_client
+    .set_workspace_publish_namespace(&workspace_id, namespace.clone())
+    .await
+    .unwrap();
+
+  let view_id = Uuid::new_v4();
+  let publish_name = "analytics-page";
+  client_1
+    .api_client
+    .publish_collabs::<MyCustomMetadata, &[u8]>(
+      &workspace_id,
+      vec![PublishCollabItem {
+        meta: PublishCollabMetadata {
+          view_id,
+          publish_name: publish_name.to_string(),
+          metadata: MyCustomMetadata {
+            title: "my_title_1".to_string(),
+          },
+        },
+        data: "yrs_encoded_data_1".as_bytes(),
+        comments_enabled: true,
+        duplicate_enabled: true,
+      }],
+    )
+    .await
+    .unwrap();
+
+  // the same guest views the page twice
+  let guest_client = localhost_client();
+  for _ in 0..2 {
+    guest_client
+      .get_published_collab_blob(&namespace, publish_name)
+      .await
+      .unwrap();
+  }
+
+  let analytics = client_1
+    .api_client
+    .get_workspace_publish_analytics(
+      &workspace_id,
+      &PublishAnalyticsQueryParams {
+        view_id: Some(view_id),
+        ..Default::default()
+      },
+    )
+    .await
+    .unwrap();
+  assert_eq!(analytics.daily_stats.len(), 1);
+  let stats = &analytics.daily_stats[0];
+  assert_eq!(stats.publish_name.as_deref(), Some(publish_name));
+  assert_eq!(stats.view_count, 2);
+  assert_eq!(stats.unique_visitor_count, 1);
+  assert_eq!(stats.duplicate_count, 0);
+  assert!(analytics.top_referrers.is_empty());
+
+  // only the owner can get the analytics
+  let err = client_2
+    .api_client
+    .get_workspace_publish_analytics(&workspace_id, &PublishAnalyticsQueryParams::default())
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::NotEnoughPermissions, "{:?}", err);
+}
+
 #[tokio::test]
 async fn test_publish_doc() {
   let (c, _user) = generate_unique_registered_user_client().await;
@@ -374,6 +715,8 @@ async fn test_publish_doc() {
           publish_name: Some(publish_name_2.to_string()),
           comments_enabled: None,
           duplicate_enabled: None,
+          password: None,
+          members_only: None,
         }],
       )
       .await
@@ -390,6 +733,8 @@ async fn test_publish_doc() {
         publish_name: Some(new_publish_name_1.to_string()),
         comments_enabled: None,
         duplicate_enabled: None,
+        password: None,
+        members_only: None,
       }],
     )
     .await
@@ -419,6 +764,8 @@ async fn test_publish_doc() {
         publish_name: Some(publish_name_1.to_string()),
         comments_enabled: None,
         duplicate_enabled: None,
+        password: None,
+        members_only: None,
       }],
     )
     .await
@@ -1853,6 +2200,8 @@ async fn test_republish_patch() {
       publish_name: Some(publish_name.to_string()),
       comments_enabled: None,
       duplicate_enabled: None,
+      password: None,
+      members_only: None,
     }],
   )
   .await
@@ -1,7 +1,12 @@
+use app_error::ErrorCode;
 use client_api::Client;
 use client_api_test::generate_unique_registered_user_client;
-use database_entity::dto::{AFRole, AFWorkspaceInvitationStatus, AFWorkspaceSettingsChange};
-use shared_entity::dto::workspace_dto::WorkspaceMemberInvitation;
+use database_entity::dto::{
+  AFRole, AFWorkspaceInvitationStatus, AFWorkspaceSettingsChange, AFWorkspaceSsoSettings,
+};
+use shared_entity::dto::workspace_dto::{
+  CreatePageParams, CreateWorkspaceParam, ViewLayout, WorkspaceMemberInvitation,
+};
 use uuid::Uuid;
 
 #[tokio::test]
@@ -55,6 +60,97 @@ async fn get_and_set_workspace_by_non_owner() {
     .unwrap();
 }
 
+#[tokio::test]
+async fn non_owner_cannot_change_workspace_sso() {
+  let (alice_client, _alice) = generate_unique_registered_user_client().await;
+  let workspaces = alice_client.get_workspaces().await.unwrap();
+  let alice_workspace_id = workspaces.first().unwrap().workspace_id;
+
+  let (bob_client, bob) = generate_unique_registered_user_client().await;
+  invite_user_to_workspace(&alice_workspace_id, &alice_client, &bob_client, &bob.email).await;
+
+  let err = bob_client
+    .update_workspace_settings(
+      &alice_workspace_id.to_string(),
+      &AFWorkspaceSettingsChange::new().remove_sso(),
+    )
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::NotEnoughPermissions);
+}
+
+#[tokio::test]
+async fn set_workspace_sso_with_unknown_provider() {
+  let (c, _user) = generate_unique_registered_user_client().await;
+  let workspaces = c.get_workspaces().await.unwrap();
+  let workspace_id = workspaces.first().unwrap().workspace_id.to_string();
+
+  let err = c
+    .update_workspace_settings(
+      &workspace_id,
+      &AFWorkspaceSettingsChange::new().sso(AFWorkspaceSsoSettings {
+        provider_id: Uuid::new_v4().to_string(),
+        enforce_sso: true,
+        auto_join_domains: vec!["appflowy.io".to_string()],
+        default_role: AFRole::Member,
+      }),
+    )
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::InvalidRequest);
+
+  let settings = c.get_workspace_settings(&workspace_id).await.unwrap();
+  assert!(settings.sso.is_none());
+}
+
+#[tokio::test]
+async fn end_to_end_encrypted_workspace_disables_plaintext_features() {
+  let (c, _user) = generate_unique_registered_user_client().await;
+  let workspace = c
+    .create_workspace(CreateWorkspaceParam {
+      workspace_name: Some("encrypted".to_string()),
+      end_to_end_encryption: true,
+    })
+    .await
+    .unwrap();
+  let workspace_id = workspace.workspace_id.to_string();
+
+  let settings = c.get_workspace_settings(&workspace_id).await.unwrap();
+  assert!(settings.end_to_end_encryption);
+  assert!(settings.disable_search_indexing);
+
+  let err = c
+    .update_workspace_settings(
+      &workspace_id,
+      &AFWorkspaceSettingsChange::new().disable_search_indexing(false),
+    )
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::EndToEndEncrypted);
+
+  let err = c
+    .search_documents(&workspace_id, "hello", 10, 100)
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::EndToEndEncrypted);
+
+  let err = c
+    .create_workspace_page_view(
+      workspace.workspace_id,
+      &CreatePageParams {
+        parent_view_id: workspace_id.clone(),
+        layout: ViewLayout::Document,
+        name: Some("plaintext page".to_string()),
+      },
+    )
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::EndToEndEncrypted);
+
+  let err = c.list_databases(&workspace_id).await.unwrap_err();
+  assert_eq!(err.code, ErrorCode::EndToEndEncrypted);
+}
+
 async fn invite_user_to_workspace(
   workspace_id: &Uuid,
   owner: &Client,